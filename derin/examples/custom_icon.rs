@@ -9,7 +9,7 @@ use derin::{LoopFlow, Window, WindowConfig};
 use derin::layout::{Align, Align2, Margins, SizeBounds, LayoutHorizontal};
 use derin::container::SingleContainer;
 use derin::widgets::{Contents, Group, Label};
-use derin::theme::{ThemeWidget, Image, RescaleRules};
+use derin::theme::{ThemeWidget, Brush, Image, RescaleRules};
 use derin::theme::color::Rgba;
 use derin::geometry::rect::DimsBox;
 
@@ -25,7 +25,7 @@ fn main() {
         "AddIcon".to_string(),
         ThemeWidget {
             text: None,
-            image: Some(Rc::new(Image {
+            background: Some(Brush::Image(Rc::new(Image {
                 pixels: {
                     let image_png = png::Decoder::new(::std::io::Cursor::new(&include_bytes!("plus_icon.png")[..]));
                     let (info, mut reader) = image_png.read_info().unwrap();
@@ -37,7 +37,7 @@ fn main() {
                 dims: DimsBox::new2(32, 32),
                 rescale: RescaleRules::Align(Align2::new(Align::Center, Align::Center)),
                 size_bounds: SizeBounds::default()
-            }))
+            })))
         }
     );
 
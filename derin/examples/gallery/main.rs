@@ -0,0 +1,117 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Displays every widget Derin provides, for use as a reference and as a manual regression test.
+//!
+//! ```text
+//! cargo run --example derin-gallery -- [--scale 1,1.5,2] [--high-contrast] [--rtl]
+//! ```
+//!
+//! The gallery opens one window for each scale factor passed to `--scale`, opening the next window
+//! once the previous one has been closed. Right-to-left mode mirrors the gallery's layouts; it
+//! doesn't change how text is shaped.
+
+extern crate derin;
+#[macro_use]
+extern crate derin_macros;
+extern crate png;
+
+mod pages;
+
+use derin::{Window, WindowConfig};
+use derin::accessibility::AccessibilityPrefs;
+use derin::layout::{Align, Align2, Margins, SizeBounds};
+use derin::event::{Key, KeyChord, ModifierKeys, ShortcutScope};
+use derin::widgets::{Group, Menu, PopupLayer, ToggleStatsOverlay};
+use derin::theme::{Theme, ThemeWidget, Brush, Image, RescaleRules};
+use derin::theme::color::Rgba;
+use derin::geometry::rect::DimsBox;
+
+use std::rc::Rc;
+
+use crate::pages::{GalleryLayout, GalleryPages};
+
+/// The settings the gallery is displayed with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GalleryOptions {
+    pub scale: f32,
+    pub high_contrast: bool,
+    pub rtl: bool,
+}
+
+impl GalleryOptions {
+    /// The margins placed around each widget, scaled by the scale factor.
+    pub fn margins(&self) -> Margins<i32> {
+        let margin = (4.0 * self.scale).round() as i32;
+        Margins::new(margin, margin, margin, margin)
+    }
+
+    fn theme(&self) -> Theme {
+        let mut theme = Theme::default();
+        theme.insert_widget(
+            "AddIcon".to_string(),
+            ThemeWidget {
+                text: None,
+                background: Some(Brush::Image(Rc::new(Image {
+                    pixels: {
+                        let image_png = png::Decoder::new(::std::io::Cursor::new(&include_bytes!("../plus_icon.png")[..]));
+                        let (info, mut reader) = image_png.read_info().unwrap();
+                        let mut image = vec![0; info.buffer_size()];
+                        reader.next_frame(&mut image).unwrap();
+                        Rgba::slice_from_raw(&image).to_vec()
+                    },
+                    dims: DimsBox::new2(32, 32),
+                    rescale: RescaleRules::Align(Align2::new(Align::Center, Align::Center)),
+                    size_bounds: SizeBounds::default()
+                }))),
+                content_margins: Margins::default(),
+            }
+        );
+        theme.with_accessibility(&AccessibilityPrefs {
+            high_contrast: self.high_contrast,
+            text_scale: self.scale,
+            ..AccessibilityPrefs::default()
+        })
+    }
+}
+
+fn main() {
+    let mut scales = vec![1.0];
+    let mut high_contrast = false;
+    let mut rtl = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--scale" => {
+                let scale_list = args.next().expect("--scale requires a comma-separated list of scale factors");
+                scales = scale_list.split(',')
+                    .map(|s| s.trim().parse().expect("invalid scale factor"))
+                    .collect();
+            },
+            "--high-contrast" => high_contrast = true,
+            "--rtl" => rtl = true,
+            _ => {
+                eprintln!("usage: derin-gallery [--scale 1,1.5,2] [--high-contrast] [--rtl]");
+                return;
+            }
+        }
+    }
+
+    for scale in scales {
+        let options = GalleryOptions{ scale, high_contrast, rtl };
+        let gallery = Group::new(GalleryPages::new(&options), GalleryLayout::vertical(&options));
+        let gallery: PopupLayer<_, Menu> = PopupLayer::new(gallery);
+
+        let window_config = WindowConfig {
+            dimensions: Some(DimsBox::new2((640.0 * scale) as u32, (480.0 * scale) as u32)),
+            title: format!("Derin Gallery ({}%)", (scale * 100.0).round()),
+            ..WindowConfig::default()
+        };
+
+        let mut window = unsafe{ Window::new(window_config, gallery, options.theme()).unwrap() };
+        window.register_shortcut(KeyChord::new(ModifierKeys::empty(), Key::F12), ShortcutScope::Global, ToggleStatsOverlay).unwrap();
+        window.run_forever();
+    }
+}
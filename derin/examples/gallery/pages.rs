@@ -0,0 +1,491 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The gallery's pages. Every widget in `derin::widgets` gets a page here.
+//!
+//! To register a page for a new widget, add a container struct displaying the widget's variants,
+//! add a field for it to `GalleryPages`, and construct it in `GalleryPages::new`.
+
+use derin::widgets::custom::WidgetIdent;
+use derin::layout::{Align, Align2, AutoFlow, Axis, DynLayout, GridLayout, GridSize, Margins, WidgetPos, WidgetSpan};
+use derin::widgets::*;
+use derin::container::DynContainer;
+use derin::event::{Key, KeyChord, ModifierKeys};
+use derin::geometry::Vector2;
+use derin::geometry::rect::BoundBox;
+use derin::gl_render::GLRenderer;
+use derin::menu::{MenuActivated, MenuItem, MenuModel};
+use std::{
+    collections::HashMap,
+    time::Duration,
+};
+
+use crate::GalleryOptions;
+
+/// Stacks widgets in a single row or column, mirroring the order of rows and the alignment of
+/// columns in right-to-left mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GalleryLayout {
+    horizontal: bool,
+    rtl: bool,
+    widget_margins: Margins<i32>,
+}
+
+impl GalleryLayout {
+    pub fn horizontal(options: &GalleryOptions) -> GalleryLayout {
+        GalleryLayout {
+            horizontal: true,
+            rtl: options.rtl,
+            widget_margins: options.margins(),
+        }
+    }
+
+    pub fn vertical(options: &GalleryOptions) -> GalleryLayout {
+        GalleryLayout {
+            horizontal: false,
+            rtl: options.rtl,
+            widget_margins: options.margins(),
+        }
+    }
+}
+
+impl GridLayout for GalleryLayout {
+    fn positions(&self, _: WidgetIdent, widget_index: usize, num_widgets: usize) -> Option<WidgetPos> {
+        if widget_index >= num_widgets {
+            return None;
+        }
+
+        let (widget_span, place_x) = match (self.horizontal, self.rtl) {
+            (true, false) => (WidgetSpan::new(widget_index as u32, 0), Align::Stretch),
+            (true, true) => (WidgetSpan::new((num_widgets - widget_index - 1) as u32, 0), Align::Stretch),
+            (false, false) => (WidgetSpan::new(0, widget_index as u32), Align::Start),
+            (false, true) => (WidgetSpan::new(0, widget_index as u32), Align::End),
+        };
+        Some(WidgetPos {
+            widget_span,
+            margins: self.widget_margins,
+            place_in_cell: Align2::new(place_x, Align::Center),
+            ..WidgetPos::default()
+        })
+    }
+
+    fn grid_size(&self, num_widgets: usize) -> GridSize {
+        match self.horizontal {
+            true => GridSize::new(num_widgets as u32, 1),
+            false => GridSize::new(1, num_widgets as u32),
+        }
+    }
+}
+
+/// Prints the name of whichever widget triggered it, so the gallery can be used to check that
+/// widgets fire their handlers.
+#[derive(Debug, Clone, Copy)]
+pub struct Log(pub &'static str);
+
+impl ButtonHandler for Log {
+    fn on_click(&mut self) {
+        println!("{}: clicked", self.0);
+    }
+}
+
+impl CheckToggleHandler for Log {
+    fn change_state(&mut self, enabled: bool) {
+        println!("{}: {}", self.0, if enabled {"checked"} else {"unchecked"});
+    }
+}
+
+impl SliderHandler for Log {
+    type Action = ();
+    fn on_move(&mut self, _: f32, new_value: f32) -> Option<()> {
+        println!("{}: {}", self.0, new_value);
+        None
+    }
+}
+
+impl ScrollBarHandler for Log {
+    type Action = ();
+    fn on_scroll(&mut self, _: f32, new_value: f32) -> Option<()> {
+        println!("{}: {}", self.0, new_value);
+        None
+    }
+}
+
+impl ShortcutRecorderHandler for Log {
+    type Action = ();
+    fn on_record(&mut self, _: Option<KeyChord>, new: Option<KeyChord>) -> Option<()> {
+        println!("{}: {}", self.0, new.map(|c| c.platform_notation()).unwrap_or("None".to_string()));
+        None
+    }
+}
+
+pub type Page<C> = Group<C, GalleryLayout>;
+
+fn text(s: &str) -> Contents {
+    Contents::Text(s.to_string())
+}
+
+#[derive(WidgetContainer)]
+pub struct GalleryPages {
+    buttons: Page<ButtonPage>,
+    check_boxes: Page<CheckBoxPage>,
+    radio_buttons: Page<RadioButtonPage>,
+    text: Page<TextPage>,
+    ranges: Page<RangePage>,
+    scroll_bars: Page<ScrollBarPage>,
+    clip: Page<ClipPage>,
+    cross_fade: Page<CrossFadePage>,
+    pan_zoom: Page<PanZoomPage>,
+    popup: Page<PopupPage>,
+    dyn_group: Page<DynGroupPage>,
+    dialog: Page<DialogPage>,
+    context_menu: Page<ContextMenuPage>,
+    overflow: Page<OverflowPage>,
+    shortcuts: Page<ShortcutPage>,
+    splitter: Page<SplitterPage>,
+    stats: Page<StatsPage>,
+    template: Page<TemplatePage>,
+    help: Page<HelpPage>,
+}
+
+#[derive(WidgetContainer)]
+pub struct ButtonPage {
+    title: Label,
+    text: Button<Log>,
+    icon: Button<Log>,
+    long_text: Button<Log>,
+}
+
+#[derive(WidgetContainer)]
+pub struct CheckBoxPage {
+    title: Label,
+    checked: CheckBox<Log>,
+    unchecked: CheckBox<Log>,
+}
+
+#[derive(WidgetContainer)]
+pub struct RadioButtonPage {
+    title: Label,
+    vertical: RadioButtonList<Vec<RadioButton>, GalleryLayout>,
+    horizontal: RadioButtonList<Vec<RadioButton>, GalleryLayout>,
+}
+
+#[derive(WidgetContainer)]
+pub struct TextPage {
+    title: Label,
+    label: Label,
+    line_box: LineBox,
+    edit_box: EditBox,
+}
+
+#[derive(WidgetContainer)]
+pub struct RangePage {
+    title: Label,
+    slider: Slider<Log>,
+    stepped_slider: Slider<Log>,
+    empty_progress: ProgressBar,
+    half_progress: ProgressBar,
+    full_progress: ProgressBar,
+}
+
+#[derive(WidgetContainer)]
+pub struct ScrollBarPage {
+    title: Label,
+    horizontal: ScrollBar<Log>,
+    vertical: ScrollBar<Log>,
+}
+
+#[derive(WidgetContainer)]
+pub struct ClipPage {
+    title: Label,
+    clipped: Clip<Label>,
+}
+
+#[derive(WidgetContainer)]
+pub struct CrossFadePage {
+    title: Label,
+    fade: CrossFade<Label>,
+}
+
+#[derive(WidgetContainer)]
+pub struct PanZoomPage {
+    title: Label,
+    view: PanZoomView<Label>,
+}
+
+#[derive(WidgetContainer)]
+pub struct PopupPage {
+    title: Label,
+    layer: PopupLayer<Label, Label>,
+}
+
+#[derive(WidgetContainer)]
+pub struct DynGroupPage {
+    title: Label,
+    group: ContextMenu<DynGroup<GLRenderer>>,
+}
+
+#[derive(WidgetContainer)]
+pub struct DialogPage {
+    title: Label,
+    dialog: Dialog<Label, ()>,
+}
+
+#[derive(WidgetContainer)]
+pub struct ContextMenuPage {
+    title: Label,
+    target: ContextMenu<Label>,
+}
+
+#[derive(WidgetContainer)]
+pub struct Toolbar {
+    new: Button<Log>,
+    open: Button<Log>,
+    save: Button<Log>,
+    print: Button<Log>,
+}
+
+#[derive(WidgetContainer)]
+pub struct OverflowPage {
+    title: Label,
+    toolbar: Overflow<Toolbar>,
+}
+
+#[derive(WidgetContainer)]
+pub struct ShortcutPage {
+    title: Label,
+    empty: ShortcutRecorder<Log>,
+    bound: ShortcutRecorder<Log>,
+}
+
+#[derive(WidgetContainer)]
+pub struct SplitterPage {
+    title: Label,
+    split: Splitter<Label, Label>,
+}
+
+#[derive(WidgetContainer)]
+pub struct StatsPage {
+    title: Label,
+    overlay: StatsOverlay,
+}
+
+#[derive(WidgetContainer)]
+pub struct Card<H, C, F> {
+    #[derin(slot)]
+    header: H,
+    #[derin(slot = "stretch")]
+    content: C,
+    #[derin(slot)]
+    footer: F,
+}
+
+#[derive(WidgetContainer)]
+pub struct TemplatePage {
+    title: Label,
+    card: Template<Card<Label, EditBox, Button<Log>>>,
+}
+
+#[derive(WidgetContainer)]
+pub struct HelpPage {
+    title: Label,
+    viewer: HelpViewer,
+}
+
+/// Creates a group that adds and removes labels when its context menu's items are clicked.
+fn dyn_group(options: &GalleryOptions) -> DynGroup<GLRenderer> {
+    let mut layout = DynLayout::new(GridSize::new(4, 1));
+    layout.auto_flow = Some(AutoFlow::Row);
+    layout.gap = Vector2::new(options.margins().left, options.margins().top);
+
+    let mut group = Group::new(DynContainer::new(), layout);
+    group.container_mut().insert(WidgetIdent::Num(0), Label::new(text("Right-click to add or remove children")));
+    group.register_message(|group: &mut DynGroup<GLRenderer>, activated: &MenuActivated| {
+        let num_children = group.container().idents().count() as u32;
+        match &*activated.id {
+            "dyn_group_add" => {
+                let label = Label::new(text(&format!("Child {}", num_children)));
+                group.container_mut().insert(WidgetIdent::Num(num_children), label);
+            },
+            // The first label explains how to use the page, so it never gets removed.
+            "dyn_group_remove" if num_children > 1 => {
+                group.remove_child(&WidgetIdent::Num(num_children - 1));
+            },
+            _ => ()
+        }
+    });
+    group
+}
+
+impl GalleryPages {
+    pub fn new(options: &GalleryOptions) -> GalleryPages {
+        let page = |container| Group::new(container, GalleryLayout::horizontal(options));
+        let radio_buttons = || vec![
+            RadioButton::new(true, text("First")),
+            RadioButton::new(false, text("Second")),
+            RadioButton::new(false, text("Third")),
+        ];
+
+        let mut help_topics = HashMap::new();
+        help_topics.insert("gallery".to_string(), "Press F1 over a widget to show its help text here.".to_string());
+
+        GalleryPages {
+            buttons: page(ButtonPage {
+                title: Label::new(text("Button")),
+                text: Button::new(text("Text"), Log("Text button")),
+                icon: Button::new(Contents::Icon("AddIcon".to_string()), Log("Icon button")),
+                long_text: Button::new(text("A button with enough text that it should wrap or clip"), Log("Long button")),
+            }),
+            check_boxes: page(CheckBoxPage {
+                title: Label::new(text("CheckBox")),
+                checked: CheckBox::new(true, text("Checked"), Log("Checked box")),
+                unchecked: CheckBox::new(false, text("Unchecked"), Log("Unchecked box")),
+            }),
+            radio_buttons: page(RadioButtonPage {
+                title: Label::new(text("RadioButtonList")),
+                vertical: RadioButtonList::new(radio_buttons(), GalleryLayout::vertical(options)),
+                horizontal: RadioButtonList::new(radio_buttons(), GalleryLayout::horizontal(options)),
+            }),
+            text: page(TextPage {
+                title: Label::new(text("Text")),
+                label: Label::new(text("Label")),
+                line_box: LineBox::new("Single line".to_string()),
+                edit_box: EditBox::new("Multiple\nlines".to_string()),
+            }),
+            ranges: page(RangePage {
+                title: Label::new(text("Slider & ProgressBar")),
+                slider: Slider::new(0.5, 0.0, 0.0..=1.0, Log("Slider")),
+                stepped_slider: Slider::new(50.0, 10.0, 0.0..=100.0, Log("Stepped slider")),
+                empty_progress: ProgressBar::new(0.0, 0.0, 1.0),
+                half_progress: ProgressBar::new(0.5, 0.0, 1.0),
+                full_progress: ProgressBar::new(1.0, 0.0, 1.0),
+            }),
+            scroll_bars: page(ScrollBarPage {
+                title: Label::new(text("ScrollBar (100 rows, 20 visible)")),
+                horizontal: {
+                    let mut horizontal = ScrollBar::new(Axis::X, Log("Horizontal scroll bar"));
+                    horizontal.set_lengths(100.0, 20.0);
+                    horizontal
+                },
+                vertical: {
+                    let mut vertical = ScrollBar::new(Axis::Y, Log("Vertical scroll bar"));
+                    vertical.set_lengths(100.0, 20.0);
+                    vertical
+                },
+            }),
+            clip: page(ClipPage {
+                title: Label::new(text("Clip")),
+                clipped: Clip::new(Label::new(text("Text that's clipped to its parent's rectangle when it doesn't fit"))),
+            }),
+            cross_fade: page(CrossFadePage {
+                title: Label::new(text("CrossFade")),
+                fade: {
+                    let mut fade = CrossFade::new(Label::new(text("Old contents")), Duration::from_millis(500));
+                    fade.replace(Label::new(text("New contents")));
+                    fade
+                },
+            }),
+            pan_zoom: page(PanZoomPage {
+                title: Label::new(text("PanZoomView")),
+                view: PanZoomView::new(Label::new(text("Drag to pan, and hold Ctrl and scroll to zoom"))),
+            }),
+            popup: page(PopupPage {
+                title: Label::new(text("PopupLayer")),
+                layer: {
+                    let mut layer = PopupLayer::new(Label::new(text("Click outside of the popup to close it")));
+                    layer.open_popup(BoundBox::new2(0, 0, 0, 0), Label::new(text("Popup")));
+                    layer
+                },
+            }),
+            dyn_group: page(DynGroupPage {
+                title: Label::new(text("DynGroup")),
+                group: ContextMenu::new(
+                    dyn_group(options),
+                    MenuModel::new(vec![
+                        MenuItem::action("dyn_group_add", "Add child"),
+                        MenuItem::action("dyn_group_remove", "Remove child"),
+                    ]),
+                ),
+            }),
+            // The dialog is left closed, since an open dialog would block input to the rest of the
+            // gallery.
+            dialog: page(DialogPage {
+                title: Label::new(text("Dialog")),
+                dialog: Dialog::new(Label::new(text("Dialog content"))),
+            }),
+            context_menu: page(ContextMenuPage {
+                title: Label::new(text("ContextMenu & Menu")),
+                target: ContextMenu::new(
+                    Label::new(text("Right-click for a context menu")),
+                    MenuModel::new(vec![
+                        MenuItem::Action {
+                            id: "copy".to_string(),
+                            label: "Copy".to_string(),
+                            enabled: true,
+                            shortcut: Some(KeyChord::new(ModifierKeys::CTRL, Key::C)),
+                        },
+                        MenuItem::Action {
+                            id: "paste".to_string(),
+                            label: "Paste".to_string(),
+                            enabled: false,
+                            shortcut: Some(KeyChord::new(ModifierKeys::CTRL, Key::V)),
+                        },
+                        MenuItem::Separator,
+                        MenuItem::submenu("More", MenuModel::new(vec![
+                            MenuItem::action("first", "First"),
+                            MenuItem::action("second", "Second"),
+                        ])),
+                    ]),
+                ),
+            }),
+            overflow: page(OverflowPage {
+                title: Label::new(text("Overflow")),
+                toolbar: {
+                    let mut toolbar = Overflow::new(
+                        Toolbar {
+                            new: Button::new(text("New"), Log("New")),
+                            open: Button::new(text("Open"), Log("Open")),
+                            save: Button::new(text("Save"), Log("Save")),
+                            print: Button::new(text("Print"), Log("Print")),
+                        },
+                        Axis::X,
+                    );
+                    // Shrink the window to push the buttons into the "More…" menu.
+                    for &(ident, label) in [("new", "New"), ("open", "Open"), ("save", "Save"), ("print", "Print")].iter() {
+                        toolbar.set_overflow_item(WidgetIdent::new_str(ident), Some(MenuItem::action(ident, label)));
+                    }
+                    toolbar
+                },
+            }),
+            shortcuts: page(ShortcutPage {
+                title: Label::new(text("ShortcutRecorder")),
+                empty: ShortcutRecorder::new(None, Log("Empty shortcut")),
+                bound: ShortcutRecorder::new(Some(KeyChord::new(ModifierKeys::CTRL, Key::S)), Log("Bound shortcut")),
+            }),
+            splitter: page(SplitterPage {
+                title: Label::new(text("Splitter")),
+                split: Splitter::new(Label::new(text("Drag the bar")), Label::new(text("to resize")), Axis::X),
+            }),
+            stats: page(StatsPage {
+                title: Label::new(text("StatsOverlay (F12 to toggle)")),
+                overlay: StatsOverlay::new(true),
+            }),
+            template: page(TemplatePage {
+                title: Label::new(text("Template")),
+                card: Group::template(Card {
+                    header: Label::new(text("Card header")),
+                    content: EditBox::new("Card content".to_string()),
+                    footer: Button::new(text("Card footer"), Log("Card button")),
+                }),
+            }),
+            help: page(HelpPage {
+                title: Label::new(text("HelpViewer")),
+                viewer: {
+                    let mut viewer = HelpViewer::new(help_topics, "No help for this widget.".to_string());
+                    viewer.show_topic(Some("gallery"));
+                    viewer
+                },
+            }),
+        }
+    }
+}
@@ -5,13 +5,15 @@
 //! Types used to specify children of container widgets.
 //!
 //! This module's primary functionality is in the `WidgetContainer` trait, and an implementation
-//! which contains a single widget is provided with the `SingleContainer` struct.
+//! which contains a single widget is provided with the `SingleContainer` struct. Containers whose
+//! children are only known at runtime can use `DynContainer`.
 
 use crate::{
     core::LoopFlow,
     core::render::Renderer,
-    core::widget::{WidgetIdent, WidgetInfo, WidgetInfoMut, WidgetSubtype, Widget},
+    core::widget::{BoxedWidget, WidgetIdent, WidgetInfo, WidgetInfoMut, WidgetSubtype, Widget},
 };
+use std::collections::BTreeMap;
 
 /// Designates a struct that contains other widgets.
 ///
@@ -22,6 +24,12 @@ use crate::{
 ///   `Action` type.
 /// * `#[derin(collection = "$type_in_collection")]` is placed on fields within the struct which aren't
 ///   themselves widgets, but are instead collections of widgets, such as `Vec`.
+/// * `#[derin(keyed_collection = "$type_in_collection")]` is placed on fields that implement
+///   [`KeyedCollection`]. Their children are identified by their keys instead of their positions,
+///   so focus, hover, and scroll state stay with the right item when items are inserted or removed
+///   in the middle of the collection.
+/// * `#[derin(slot)]` or `#[derin(slot = "stretch")]` is placed on every field of a template's
+///   slots, and additionally derives [`TemplateSlots`].
 ///
 /// # Example
 /// ```ignore
@@ -33,9 +41,14 @@ use crate::{
 ///     label: Label,
 ///     edit_box: EditBox,
 ///     #[derin(collection = "Button<Option<GalleryEvent>>")]
-///     buttons: Vec<Button<Option<GalleryEvent>>>
+///     buttons: Vec<Button<Option<GalleryEvent>>>,
+///     #[derin(keyed_collection = "Label")]
+///     rows: BTreeMap<u64, Label>
 /// }
 /// ```
+///
+/// [`KeyedCollection`]: ./trait.KeyedCollection.html
+/// [`TemplateSlots`]: ./trait.TemplateSlots.html
 pub trait WidgetContainer<S: ?Sized>: 'static {
     /// Get the number of children stored within the container.
     fn num_children(&self) -> usize;
@@ -147,6 +160,117 @@ pub trait WidgetContainer<S: ?Sized>: 'static {
     }
 }
 
+/// The named slots of a composite widget, such as a card or a dialog, which consumers fill with
+/// arbitrary widgets.
+///
+/// Slots get stacked vertically in the order they're declared. This should be derived alongside
+/// `WidgetContainer`, and the slots put in a [`Template`]:
+///
+/// ```ignore
+/// #[derive(WidgetContainer)]
+/// struct Card<H, C, F> {
+///     #[derin(slot)]
+///     header: H,
+///     #[derin(slot = "stretch")]
+///     content: C,
+///     #[derin(slot)]
+///     footer: F,
+/// }
+///
+/// let card = Group::template(Card {
+///     header: Label::new(Contents::Text("Title".to_string())),
+///     content: EditBox::new(String::new()),
+///     footer: Button::new(Contents::Text("OK".to_string()), handler),
+/// });
+/// ```
+///
+/// [`Template`]: ../widgets/type.Template.html
+pub trait TemplateSlots: WidgetContainer<dyn Widget> {
+    /// The container's slots, in the order they're declared.
+    fn slots() -> &'static [Slot];
+}
+
+/// A slot in a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    /// The name of the slot's field, which the slot's widget is identified by.
+    pub name: &'static str,
+    /// Whether the slot takes up the space left over by the other slots. Other slots are kept at
+    /// their widget's minimum height.
+    pub stretch: bool,
+}
+
+/// A collection of widgets, each with a key that stays the same as other widgets are inserted into
+/// or removed from the collection.
+///
+/// Keys must be unique within the collection.
+pub trait KeyedCollection<W>: 'static {
+    /// Get the number of widgets in the collection.
+    fn len(&self) -> usize;
+
+    /// Call `for_each` on each widget in the collection alongside its key, in order.
+    fn for_each_keyed<'a, G>(&'a self, for_each: G)
+        where G: FnMut(u64, &'a W) -> LoopFlow;
+
+    /// Call `for_each` on each widget in the collection alongside its key, in order.
+    fn for_each_keyed_mut<'a, G>(&'a mut self, for_each: G)
+        where G: FnMut(u64, &'a mut W) -> LoopFlow;
+}
+
+impl<W: 'static> KeyedCollection<W> for Vec<(u64, W)> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn for_each_keyed<'a, G>(&'a self, mut for_each: G)
+        where G: FnMut(u64, &'a W) -> LoopFlow
+    {
+        for &(key, ref widget) in self {
+            if let LoopFlow::Break = for_each(key, widget) {
+                return;
+            }
+        }
+    }
+
+    fn for_each_keyed_mut<'a, G>(&'a mut self, mut for_each: G)
+        where G: FnMut(u64, &'a mut W) -> LoopFlow
+    {
+        for &mut (key, ref mut widget) in self {
+            if let LoopFlow::Break = for_each(key, widget) {
+                return;
+            }
+        }
+    }
+}
+
+impl<W: 'static> KeyedCollection<W> for BTreeMap<u64, W> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn for_each_keyed<'a, G>(&'a self, mut for_each: G)
+        where G: FnMut(u64, &'a W) -> LoopFlow
+    {
+        for (&key, widget) in self {
+            if let LoopFlow::Break = for_each(key, widget) {
+                return;
+            }
+        }
+    }
+
+    fn for_each_keyed_mut<'a, G>(&'a mut self, mut for_each: G)
+        where G: FnMut(u64, &'a mut W) -> LoopFlow
+    {
+        for (&key, widget) in self {
+            if let LoopFlow::Break = for_each(key, widget) {
+                return;
+            }
+        }
+    }
+}
+
 /// A container that contains a single widget.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct SingleContainer<W: Widget> {
@@ -217,3 +341,98 @@ impl<S, W> WidgetContainer<S> for Vec<W>
         }
     }
 }
+
+/// A container whose children can be added, removed, and replaced at runtime.
+///
+/// Children are rendered with the renderer `R`, and are identified by the `WidgetIdent` they were
+/// inserted with.
+pub struct DynContainer<R: Renderer> {
+    children: Vec<(WidgetIdent, BoxedWidget<R>)>,
+}
+
+impl<R: Renderer> DynContainer<R> {
+    /// Creates a new, empty container.
+    pub fn new() -> DynContainer<R> {
+        DynContainer {
+            children: Vec::new()
+        }
+    }
+
+    /// Inserts a widget into the container. If a widget with the same ident is already in the
+    /// container, the old widget is replaced in place and returned.
+    pub fn insert(&mut self, ident: WidgetIdent, widget: impl Widget) -> Option<BoxedWidget<R>> {
+        self.insert_boxed(ident, BoxedWidget::new(widget))
+    }
+
+    /// Inserts an already-boxed widget into the container. See `insert` for details.
+    pub fn insert_boxed(&mut self, ident: WidgetIdent, widget: BoxedWidget<R>) -> Option<BoxedWidget<R>> {
+        match self.children.iter_mut().find(|(i, _)| *i == ident) {
+            Some((_, old_widget)) => Some(std::mem::replace(old_widget, widget)),
+            None => {
+                self.children.push((ident, widget));
+                None
+            }
+        }
+    }
+
+    /// Removes the widget with the given ident from the container, returning it if it existed.
+    pub fn remove(&mut self, ident: &WidgetIdent) -> Option<BoxedWidget<R>> {
+        let index = self.children.iter().position(|(i, _)| i == ident)?;
+        Some(self.children.remove(index).1)
+    }
+
+    pub fn get(&self, ident: &WidgetIdent) -> Option<&Widget> {
+        self.children.iter()
+            .find(|(i, _)| i == ident)
+            .map(|(_, widget)| widget.widget())
+    }
+
+    pub fn get_mut(&mut self, ident: &WidgetIdent) -> Option<&mut Widget> {
+        self.children.iter_mut()
+            .find(|(i, _)| i == ident)
+            .map(|(_, widget)| widget.widget_mut())
+    }
+
+    /// Retrieves the idents of the container's children, in order.
+    pub fn idents(&self) -> impl '_ + Iterator<Item=&WidgetIdent> {
+        self.children.iter().map(|(ident, _)| ident)
+    }
+
+    /// Removes all widgets from the container.
+    pub fn clear(&mut self) {
+        self.children.clear();
+    }
+}
+
+impl<R: Renderer> WidgetContainer<dyn Widget> for DynContainer<R> {
+    #[inline(always)]
+    fn num_children(&self) -> usize {
+        self.children.len()
+    }
+
+    fn framed_children<'a, F, G>(&'a self, mut for_each_child: G)
+            where G: FnMut(WidgetInfo<'a, F, dyn Widget>) -> LoopFlow,
+                  F: Renderer
+    {
+        for (index, (ident, widget)) in self.children.iter().enumerate() {
+            // The subtype is erased because the widget info refers to the boxed widget, not to the
+            // box itself.
+            match for_each_child(WidgetInfo::new(ident.clone(), index, widget).erase_subtype()) {
+                LoopFlow::Continue => (),
+                LoopFlow::Break => return
+            }
+        }
+    }
+
+    fn framed_children_mut<'a, F, G>(&'a mut self, mut for_each_child: G)
+            where G: FnMut(WidgetInfoMut<'a, F, dyn Widget>) -> LoopFlow,
+                  F: Renderer
+    {
+        for (index, (ident, widget)) in self.children.iter_mut().enumerate() {
+            match for_each_child(WidgetInfoMut::new(ident.clone(), index, widget).erase_subtype()) {
+                LoopFlow::Continue => (),
+                LoopFlow::Break => return
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::core::{
+    error::CoreError,
+    recovery::FrameError,
+};
+use derin_layout_engine::TrackOutOfRange;
+use glutin::CreationError;
+
+use std::{
+    error::Error,
+    fmt,
+};
+
+/// Any error that can be produced by Derin.
+///
+/// Derin's fallible APIs return the specific error type for the failure, which can be converted
+/// into a `DerinError` with `?` or `From`, so applications can handle every failure through one
+/// type.
+#[derive(Debug)]
+pub enum DerinError {
+    /// The window or its OpenGL context couldn't be created.
+    Window(CreationError),
+    /// A widget panicked while processing a frame.
+    Frame(FrameError),
+    /// An error occurred while laying out or rendering the widget tree.
+    Core(CoreError),
+    /// A grid layout was given a column or row outside of the grid.
+    TrackOutOfRange(TrackOutOfRange),
+}
+
+impl fmt::Display for DerinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DerinError::Window(ref error) => write!(f, "couldn't create window: {}", error),
+            DerinError::Frame(ref error) => error.fmt(f),
+            DerinError::Core(ref error) => error.fmt(f),
+            DerinError::TrackOutOfRange(ref error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for DerinError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            DerinError::Window(ref error) => Some(error),
+            DerinError::Frame(ref error) => Some(error),
+            DerinError::Core(ref error) => Some(error),
+            DerinError::TrackOutOfRange(ref error) => Some(error),
+        }
+    }
+}
+
+impl From<CreationError> for DerinError {
+    fn from(error: CreationError) -> DerinError {
+        DerinError::Window(error)
+    }
+}
+
+impl From<FrameError> for DerinError {
+    fn from(error: FrameError) -> DerinError {
+        DerinError::Frame(error)
+    }
+}
+
+impl From<CoreError> for DerinError {
+    fn from(error: CoreError) -> DerinError {
+        DerinError::Core(error)
+    }
+}
+
+impl From<TrackOutOfRange> for DerinError {
+    fn from(error: TrackOutOfRange) -> DerinError {
+        DerinError::TrackOutOfRange(error)
+    }
+}
@@ -8,12 +8,18 @@
 
 mod atlas;
 mod font_cache;
+mod gpu_timer;
 // mod translate;
 
+use std::hash::Hash;
 use std::rc::Rc;
 use derin_common_types::cursor::CursorIcon;
 use derin_common_types::layout::SizeBounds;
 use core::widget::WidgetId;
+use core::display_list::{self, DisplayListCache, DisplayListTarget};
+use core::gpu_memory::{GpuMemoryStats, GpuMemoryTracker, GpuResourceKind};
+use core::designer::DesignOverlay;
+use core::stats::RenderStats;
 
 use crate::cgmath::{Array, Bounded, Point2, Vector2, EuclideanSpace};
 
@@ -32,29 +38,42 @@ use cgmath_geometry::{D2, rect::{BoundBox, OffsetBox, DimsBox, GeoBox}};
 
 use glutin::*;
 
-use crate::theme::Theme;
+use crate::theme::{Theme, color::ColorSpace};
 pub use crate::core::render::Renderer;
 
 use self::atlas::Atlas;
 use self::font_cache::FontCache;
+use self::gpu_timer::GpuTimer;
 // use self::translate::Translator;
 // pub use self::translate::{Prim, ThemedPrim, RelPoint};
 
 pub struct GLRenderer {
     window: GlWindow,
     client_size_bounds: SizeBounds,
+    gpu_timer: GpuTimer,
     frame: GLFrame,
 }
 
 pub struct GLFrame {
     // poly_translator: Translator,
+    display_lists: DisplayListCache<GLVertex>,
     draw: FrameDraw
 }
 
 struct FrameDraw {
     vertices: Vec<GLVertex>,
+    /// The vertices currently stored in the vertex buffer, so identical vertices don't get
+    /// uploaded again.
+    uploaded_vertices: Vec<GLVertex>,
+    /// The number of times `vertices` has been drawn and cleared.
+    flushes: u64,
+    /// The draw calls and triangles submitted this frame.
+    draw_calls: u32,
+    triangles: u32,
     atlas: Atlas,
     font_cache: FontCache,
+    gpu_memory: GpuMemoryTracker<GLResource>,
+    evicted_owners: Vec<WidgetId>,
 
     // OpenGL structs
     context_state: Rc<ContextState>,
@@ -64,11 +83,22 @@ struct FrameDraw {
     program: Program<GLVertex, GLUniforms<'static>>,
     vao: VertexArrayObject<GLVertex, !>,
     window_dims: DimsBox<D2, u32>,
-    scale_factor: f32
+    scale_factor: f32,
+    color_space: ColorSpace,
+    /// The opacity of the widget being drawn.
+    opacity: f32,
+    /// Whether the widget being drawn is enabled.
+    enabled: bool,
+    /// The theme's opacity for disabled widgets, as of the start of the frame.
+    disabled_opacity: f32
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum GLResource {
+    Atlas,
+}
 
-#[derive(Vertex, Debug, Clone, Copy)]
+#[derive(Vertex, Debug, Clone, Copy, PartialEq)]
 struct GLVertex {
     loc: Point2<f32>,
     color: Rgba<u8>,
@@ -79,12 +109,27 @@ struct GLVertex {
 struct GLUniforms<'a> {
     atlas_size: Vector2<u32>,
     window_size: Point2<f32>,
+    /// Non-zero if colors should be decoded into linear space before being blended.
+    linear_color: u32,
     tex_atlas: &'a Texture<D2, Rgba<u8>>
 }
 
+/// `GL_FRAMEBUFFER_SRGB`, which gullery doesn't expose.
+const GL_FRAMEBUFFER_SRGB: u32 = 0x8DB9;
+
 
 impl GLRenderer {
-    pub unsafe fn new(events_loop: &EventsLoop, window_builder: WindowBuilder, gen_context_builder: impl Fn() -> ContextBuilder<'static>) -> Result<GLRenderer, CreationError> {
+    /// Create a renderer drawing to a new window.
+    ///
+    /// If `color_space` is `ColorSpace::Linear`, the context builders should request an sRGB
+    /// framebuffer. The renderer falls back to blending in sRGB if the context can't load
+    /// `glEnable`.
+    pub unsafe fn new(
+        events_loop: &EventsLoop,
+        window_builder: WindowBuilder,
+        gen_context_builder: impl Fn() -> ContextBuilder<'static>,
+        color_space: ColorSpace
+    ) -> Result<GLRenderer, CreationError> {
         let show_window = window_builder.window.visible;
         let window = {
             let window_builder_no_show = window_builder.with_visibility(false);
@@ -127,11 +172,23 @@ impl GLRenderer {
 
         window.context().make_current().unwrap();
         let context_state = ContextState::new(|f| window.context().get_proc_address(f));
+        let gl_enable = window.context().get_proc_address("glEnable");
+        let color_space = match color_space == ColorSpace::Linear && !gl_enable.is_null() {
+            true => {
+                // Have OpenGL encode the shader's linear output into the sRGB framebuffer, which
+                // also makes blending happen in linear space.
+                let gl_enable: extern "system" fn(u32) = std::mem::transmute(gl_enable);
+                gl_enable(GL_FRAMEBUFFER_SRGB);
+                ColorSpace::Linear
+            },
+            false => ColorSpace::Srgb
+        };
 
         let vert_shader = Shader::new(VERT_SHADER, context_state.clone()).unwrap();
         let frag_shader = Shader::new(FRAG_SHADER, context_state.clone()).unwrap();
 
         let program = Program::new(&vert_shader, None, &frag_shader).unwrap().0;
+        let gpu_timer = GpuTimer::new(|f| window.context().get_proc_address(f));
 
         let gl_tex_atlas = Texture::new(DimsBox::new2(1024, 1024), 1, context_state.clone()).unwrap();
 
@@ -149,10 +206,17 @@ impl GLRenderer {
         Ok(GLRenderer {
             frame: GLFrame {
                 // poly_translator: Translator::new(),
+                display_lists: DisplayListCache::new(),
                 draw: FrameDraw {
                     vertices,
+                    uploaded_vertices: Vec::new(),
+                    flushes: 0,
+                    draw_calls: 0,
+                    triangles: 0,
                     atlas: Atlas::new(),
                     font_cache: FontCache::new(),
+                    gpu_memory: GpuMemoryTracker::new(None),
+                    evicted_owners: Vec::new(),
                     fb: FramebufferDefault::new(context_state.clone()).expect("Could not access default framebuffer"),
                     vao,
                     render_state: RenderState {
@@ -168,14 +232,34 @@ impl GLRenderer {
                     gl_tex_atlas,
                     context_state,
                     window_dims: DimsBox::new2(0, 0),
-                    scale_factor: 1.0
+                    scale_factor: 1.0,
+                    color_space,
+                    opacity: 1.0,
+                    enabled: true,
+                    disabled_opacity: 1.0
                 }
             },
             client_size_bounds: SizeBounds::default(),
+            gpu_timer,
             window,
         })
     }
 
+    /// Set the amount of GPU memory the renderer tries to stay under, in bytes. If `None`, cached
+    /// GPU data is never evicted.
+    pub fn set_gpu_memory_budget(&mut self, budget_bytes: Option<u64>) {
+        self.frame.draw.gpu_memory.set_budget_bytes(budget_bytes);
+    }
+
+    pub fn gpu_memory_stats(&self) -> GpuMemoryStats {
+        self.frame.draw.gpu_memory.stats()
+    }
+
+    /// The color space the renderer blends colors in.
+    pub fn color_space(&self) -> ColorSpace {
+        self.frame.draw.color_space
+    }
+
     #[inline]
     pub fn window(&self) -> &GlWindow {
         &self.window
@@ -223,6 +307,7 @@ impl GLRenderer {
             CursorIcon::SizeNeSw => MouseCursor::NeswResize,
             CursorIcon::SizeNwSe => MouseCursor::NwseResize,
             CursorIcon::SizeAll => MouseCursor::AllScroll,
+            CursorIcon::Help => MouseCursor::Help,
             CursorIcon::Hide => {
                 self.window.set_cursor_state(CursorState::Hide).ok();
                 return;
@@ -244,10 +329,14 @@ impl Renderer for GLRenderer {
     }
 
     fn widget_removed(&mut self, widget_id: WidgetId) {
-        unimplemented!()
+        self.frame.display_lists.remove(widget_id);
     }
 
-    fn start_frame(&mut self, _: &Self::Theme) {
+    fn start_frame(&mut self, theme: &Self::Theme) {
+        self.gpu_timer.begin_frame();
+        self.frame.draw.disabled_opacity = theme.disabled_opacity();
+        self.frame.draw.draw_calls = 0;
+        self.frame.draw.triangles = 0;
         let (width, height) = self.window.get_inner_size().unwrap();
         let scale_factor = self.window.hidpi_factor();
         self.frame.draw.window_dims = DimsBox::new2(width, height);
@@ -260,6 +349,14 @@ impl Renderer for GLRenderer {
         self.frame.draw.fb.clear_stencil(0);
     }
 
+    fn set_opacity(&mut self, opacity: f32) {
+        self.frame.draw.opacity = opacity;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.frame.draw.enabled = enabled;
+    }
+
     fn render_subframe(
         &mut self,
         _: &Self::Theme,
@@ -270,10 +367,90 @@ impl Renderer for GLRenderer {
         draw_to_frame(&mut self.frame);
     }
 
+    fn render_design_overlay(&mut self, overlay: &DesignOverlay) {
+        let (width, height) = self.window.get_inner_size().unwrap();
+        let window_rect = BoundBox::new2(0, 0, width as i32, height as i32);
+        let draw = &mut self.frame.draw;
+        let (outline_color, handle_color) = (Rgba::new(0, 120, 215, 255), Rgba::new(255, 255, 255, 255));
+
+        let rect = overlay.selection_rect;
+        let edges = [
+            BoundBox::new2(rect.min.x, rect.min.y, rect.max.x, rect.min.y + 1),
+            BoundBox::new2(rect.min.x, rect.max.y - 1, rect.max.x, rect.max.y),
+            BoundBox::new2(rect.min.x, rect.min.y, rect.min.x + 1, rect.max.y),
+            BoundBox::new2(rect.max.x - 1, rect.min.y, rect.max.x, rect.max.y),
+        ];
+        for edge in edges.iter() {
+            translate::draw_solid_rect(draw, *edge, window_rect, outline_color);
+        }
+        for &(_, handle_rect) in overlay.handles.iter() {
+            translate::draw_solid_rect(draw, handle_rect, window_rect, outline_color);
+            let inner = BoundBox::new2(handle_rect.min.x + 1, handle_rect.min.y + 1, handle_rect.max.x - 1, handle_rect.max.y - 1);
+            translate::draw_solid_rect(draw, inner, window_rect, handle_color);
+        }
+    }
+
     fn finish_frame(&mut self, _: &Self::Theme) {
         self.frame.draw.draw_contents();
+        self.gpu_timer.end_frame();
         self.window.swap_buffers().unwrap();
         self.frame.draw.atlas.bump_frame_count();
+
+        let FrameDraw{ ref mut gpu_memory, ref mut evicted_owners, .. } = self.frame.draw;
+        gpu_memory.bump_frame();
+        evicted_owners.extend(gpu_memory.evict().into_iter().filter_map(|e| e.owner));
+    }
+
+    fn take_evicted_owners(&mut self) -> Vec<WidgetId> {
+        std::mem::replace(&mut self.frame.draw.evicted_owners, Vec::new())
+    }
+
+    fn render_stats(&mut self) -> Option<RenderStats> {
+        let draw = &self.frame.draw;
+        Some(RenderStats {
+            draw_calls: draw.draw_calls,
+            triangles: draw.triangles,
+            atlas_occupancy: Some(draw.atlas.used_fraction()),
+            gpu_time: self.gpu_timer.last_elapsed(),
+        })
+    }
+}
+
+impl GLFrame {
+    /// Draw a widget, re-using the vertices it generated last frame if `commands` hashes to the
+    /// same value. `commands` should contain everything `generate` depends on.
+    pub(in crate::gl_render) fn draw_widget<H: Hash>(&mut self, widget_id: WidgetId, commands: &H, generate: impl FnOnce(&mut FrameDraw)) {
+        let key = display_list::hash_commands(commands);
+        let start = self.draw.vertices.len();
+        self.display_lists.draw_widget(widget_id, key, &mut self.draw, generate);
+
+        // The opacity gets applied after caching, so fading or disabling a widget doesn't
+        // re-tessellate it.
+        let opacity = match self.draw.enabled {
+            true => self.draw.opacity,
+            false => self.draw.opacity * self.draw.disabled_opacity
+        };
+        if opacity < 1.0 {
+            for vertex in self.draw.vertices.get_mut(start..).into_iter().flatten() {
+                vertex.color.a = (vertex.color.a as f32 * opacity.max(0.0)) as u8;
+            }
+        }
+    }
+}
+
+impl DisplayListTarget for FrameDraw {
+    type Vertex = GLVertex;
+
+    fn vertices(&mut self) -> &mut Vec<GLVertex> {
+        &mut self.vertices
+    }
+
+    fn flushes(&self) -> u64 {
+        self.flushes
+    }
+
+    fn generation(&self) -> u64 {
+        self.atlas.generation()
     }
 }
 
@@ -282,20 +459,36 @@ impl FrameDraw {
         let atlas_dims = self.atlas.dims();
         if atlas_dims != self.gl_tex_atlas.dims() {
             self.gl_tex_atlas = Texture::new(atlas_dims, 1, self.context_state.clone()).unwrap();
+            let atlas_bytes = atlas_dims.width() as u64 * atlas_dims.height() as u64 * 4;
+            self.gpu_memory.insert(GLResource::Atlas, GpuResourceKind::AtlasPage, atlas_bytes, None);
+        } else {
+            self.gpu_memory.touch(&GLResource::Atlas);
+        }
+        if self.atlas.take_modified() {
+            self.gl_tex_atlas.sub_image(0, Vector2::new(0, 0), atlas_dims, self.atlas.pixels());
         }
-        self.gl_tex_atlas.sub_image(0, Vector2::new(0, 0), atlas_dims, self.atlas.pixels());
 
         let uniform = GLUniforms {
             atlas_size: self.gl_tex_atlas.dims().dims,
             window_size: Point2::from_vec(self.window_dims.dims.cast::<f32>().unwrap_or(Vector2::from_value(f32::max_value()))),
+            linear_color: (self.color_space == ColorSpace::Linear) as u32,
             tex_atlas: &self.gl_tex_atlas
         };
 
         for verts in self.vertices.chunks(self.vao.vertex_buffer().len()) {
-            self.vao.vertex_buffer_mut().sub_data(0, verts);
+            // Most frames only redraw a few widgets, so the buffer often already holds exactly
+            // these vertices.
+            if verts != &self.uploaded_vertices[..] {
+                self.vao.vertex_buffer_mut().sub_data(0, verts);
+                self.uploaded_vertices.clear();
+                self.uploaded_vertices.extend_from_slice(verts);
+            }
             self.fb.draw(DrawMode::Triangles, 0..verts.len(), &self.vao, &self.program, uniform, self.render_state);
+            self.draw_calls += 1;
+            self.triangles += verts.len() as u32 / 3;
         }
         self.vertices.clear();
+        self.flushes += 1;
     }
 }
 
@@ -307,13 +500,21 @@ const VERT_SHADER: &str = r#"
 
     uniform uvec2 atlas_size;
     uniform vec2 window_size;
+    uniform uint linear_color;
 
     out vec2 tex_coord_out;
     out vec4 frag_color;
 
+    vec3 srgb_to_linear(vec3 c) {
+        return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), step(0.04045, c));
+    }
+
     void main() {
         gl_Position = vec4(vec2(1.0, -1.0) * (vec2(loc) / window_size - 0.5) * 2.0, 1.0, 1.0);
         frag_color = color;
+        if (linear_color != 0u) {
+            frag_color.rgb = srgb_to_linear(color.rgb);
+        }
         tex_coord_out = tex_coord / vec2(atlas_size);
     }
 "#;
@@ -324,11 +525,20 @@ const FRAG_SHADER: &str = r#"
     in vec2 tex_coord_out;
 
     uniform sampler2D tex_atlas;
+    uniform uint linear_color;
 
     out vec4 out_color;
 
+    vec3 srgb_to_linear(vec3 c) {
+        return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), step(0.04045, c));
+    }
+
     void main() {
-        out_color = frag_color * texture(tex_atlas, tex_coord_out);
+        vec4 tex_color = texture(tex_atlas, tex_coord_out);
+        if (linear_color != 0u) {
+            tex_color.rgb = srgb_to_linear(tex_color.rgb);
+        }
+        out_color = frag_color * tex_color;
     }
 "#;
 
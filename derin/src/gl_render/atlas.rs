@@ -12,22 +12,30 @@ use gullery::image_format::Rgba;
 
 use derin_atlas::SkylineAtlas;
 
-use crate::theme::ThemeFace;
+use crate::theme::{TextAntialiasing, ThemeFace};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct GlyphKey {
     face_fingerprint: u64,
     size: u32,
-    glyph_index: u32
+    glyph_index: u32,
+    antialiasing: TextAntialiasing
 }
 
+/// Images and glyphs stay in the atlas from frame to frame, so that the texture coordinates of
+/// vertices cached from earlier frames stay valid. The atlas only gets cleared when it's had to
+/// grow, which bumps its generation.
 pub struct Atlas {
     atlas: SkylineAtlas<Rgba<u8>>,
     white_rect: Option<OffsetBox<D2, u32>>,
-    // image_rects: HashMap<(), OffsetBox<D2, u32>>,
+    image_rects: HashMap<(String, DimsBox<D2, u32>), OffsetBox<D2, u32>>,
     glyph_rects: HashMap<GlyphKey, (OffsetBox<D2, u32>, Vector2<i32>)>,
-    // image_rects: hashmap,
-    // glyph_rects: hashmap
+    /// Incremented whenever images get moved or removed from the atlas.
+    generation: u64,
+    /// Whether the atlas has grown since the last clear.
+    grown: bool,
+    /// Whether any pixels have changed since the atlas was last uploaded.
+    modified: bool,
 }
 
 impl Atlas {
@@ -35,11 +43,25 @@ impl Atlas {
         Atlas {
             atlas: SkylineAtlas::new(Rgba::new(0, 0, 0, 0), DimsBox::new2(1024, 1024)),
             white_rect: None,
-            // image_rects: HashMap::new(),
-            glyph_rects: HashMap::new()
+            image_rects: HashMap::new(),
+            glyph_rects: HashMap::new(),
+            generation: 0,
+            grown: false,
+            modified: true,
         }
     }
 
+    /// The number of times the atlas has been cleared. Texture coordinates retrieved in an earlier
+    /// generation are no longer valid.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether the pixels have changed since the last time this was called.
+    pub fn take_modified(&mut self) -> bool {
+        std::mem::replace(&mut self.modified, false)
+    }
+
     pub fn dims(&self) -> DimsBox<D2, u32> {
         self.atlas.dims()
     }
@@ -48,13 +70,22 @@ impl Atlas {
         self.atlas.pixels()
     }
 
-    /// Tell the atlas that a new frame has begun. This can be used to tell how old an image is, and
-    /// to throw away pixel data that's been unused for a while.
+    /// The fraction of the atlas's area that's taken up by images.
+    pub fn used_fraction(&self) -> f32 {
+        self.atlas.stats().used_fraction()
+    }
+
+    /// Tell the atlas that a new frame has begun. If the atlas had to grow to fit the last frame's
+    /// images, it gets cleared to throw away the images that are no longer used.
     pub fn bump_frame_count(&mut self) {
-        self.atlas.clear(None);
-        self.white_rect = None;
-        // self.image_rects.clear();
-        self.glyph_rects.clear();
+        if self.grown {
+            self.atlas.clear(None);
+            self.white_rect = None;
+            self.image_rects.clear();
+            self.glyph_rects.clear();
+            self.generation += 1;
+            self.grown = false;
+        }
     }
 
     pub fn white(&mut self) -> OffsetBox<D2, u32> {
@@ -62,18 +93,26 @@ impl Atlas {
             &[Rgba::new(255, 255, 255, 255)][..],
             DimsBox::new2(1, 1)
         );
-        self.white_rect.unwrap_or_else(|| self.image_rect("TODO: REPLACE WHEN STRINGS MATTER", || white_pic))
+        let white_rect = self.white_rect.unwrap_or_else(|| self.image_rect("TODO: REPLACE WHEN STRINGS MATTER", || white_pic));
+        self.white_rect = Some(white_rect);
+        white_rect
     }
 
     /// Retrieve an image from the atlas. `image_path` refers to the theme's name for the image,
     /// while `get_image` is used to add the image to the atlas in case it's not already stored.
-    pub fn image_rect<'a, F>(&mut self, _image_path: &str, get_image: F) -> OffsetBox<D2, u32>
+    pub fn image_rect<'a, F>(&mut self, image_path: &str, get_image: F) -> OffsetBox<D2, u32>
         where F: FnOnce() -> (&'a [Rgba<u8>], DimsBox<D2, u32>)
     {
         let (pixels, dims) = get_image();
-        match self.atlas.add_image(dims, dims.into(), pixels) {
+        if let Some(&rect) = self.image_rects.get(&(image_path.to_string(), dims)) {
+            return rect;
+        }
+
+        self.modified = true;
+        let rect = match self.atlas.add_image(dims, dims.into(), pixels) {
             Some(rect) => rect,
             None => {
+                self.grown = true;
                 let new_width = cmp::max(dims.width(), self.atlas.dims().width());
                 let new_height = self.atlas.dims().height() + cmp::max(self.atlas.dims().height(), dims.height());
                 self.atlas.set_dims(
@@ -83,7 +122,9 @@ impl Atlas {
 
                 self.atlas.add_image(dims, dims.into(), pixels).unwrap()
             }
-        }
+        };
+        self.image_rects.insert((image_path.to_string(), dims), rect);
+        rect
     }
 
     /// Retrieve a glyph and it's bearing from the atlas. `style` and `glyph_index` are used as keys for
@@ -91,7 +132,7 @@ impl Atlas {
     /// within the atlas.
     ///
     /// `get_glyph` returns `(pixel_buf, image_dims, glyph_bearing)`
-    pub fn glyph_rect<'a, F, I, J>(&mut self, face: ThemeFace, face_size: u32, glyph_index: u32, get_glyph: F) -> (OffsetBox<D2, u32>, Vector2<i32>)
+    pub fn glyph_rect<'a, F, I, J>(&mut self, face: ThemeFace, face_size: u32, glyph_index: u32, antialiasing: TextAntialiasing, get_glyph: F) -> (OffsetBox<D2, u32>, Vector2<i32>)
         where F: FnOnce() -> (I, DimsBox<D2, u32>, Vector2<i32>),
               I: 'a + IntoIterator<Item=J>,
               J: 'a + IntoIterator<Item=Rgba<u8>>
@@ -99,19 +140,24 @@ impl Atlas {
         let key = GlyphKey {
             face_fingerprint: face.fingerprint(),
             size: face_size,
-            glyph_index
+            glyph_index,
+            antialiasing
         };
 
         let Atlas {
             ref mut glyph_rects,
             ref mut atlas,
+            ref mut grown,
+            ref mut modified,
             ..
         } = *self;
         *glyph_rects.entry(key).or_insert_with(|| {
             let (pixels, dims, bearing) = get_glyph();
+            *modified = true;
             match atlas.add_image_pixels(dims, pixels) {
                 Ok(rect) => (rect, bearing),
                 Err(pixels) => {
+                    *grown = true;
                     let new_width = cmp::max(dims.width(), atlas.dims().width());
                     let new_height = atlas.dims().height() + cmp::max(atlas.dims().height(), dims.height());
                     atlas.set_dims(
@@ -0,0 +1,122 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::time::Duration;
+
+const GL_TIME_ELAPSED: u32 = 0x88BF;
+const GL_QUERY_RESULT: u32 = 0x8866;
+const GL_QUERY_RESULT_AVAILABLE: u32 = 0x8867;
+
+/// The number of frames that can be waiting on their timings at once. Frames started while every
+/// query is in flight don't get timed.
+const QUERY_COUNT: usize = 4;
+
+/// Times frames on the GPU with `GL_TIME_ELAPSED` queries, which gullery doesn't expose.
+///
+/// Query results get read back once they're available instead of right after the frame, so that
+/// timing a frame doesn't stall the pipeline waiting on the GPU.
+pub struct GpuTimer {
+    fns: Option<QueryFns>,
+    free: Vec<u32>,
+    in_flight: VecDeque<u32>,
+    /// The query timing the current frame.
+    active: Option<u32>,
+    last_elapsed: Option<Duration>,
+}
+
+struct QueryFns {
+    begin_query: extern "system" fn(u32, u32),
+    end_query: extern "system" fn(u32),
+    get_query_object_iv: extern "system" fn(u32, u32, *mut i32),
+    get_query_object_ui64v: extern "system" fn(u32, u32, *mut u64),
+}
+
+impl GpuTimer {
+    /// Load the query functions with `get_proc_address`. If the context doesn't support timer
+    /// queries, the timer never reports any timings.
+    ///
+    /// Unsafe because the context the functions are loaded from must be current whenever the
+    /// timer is used.
+    pub unsafe fn new(get_proc_address: impl Fn(&str) -> *const ()) -> GpuTimer {
+        let load = |name| {
+            let f = get_proc_address(name);
+            match f.is_null() {
+                true => None,
+                false => Some(f)
+            }
+        };
+        let fns = (|| {
+            let gen_queries: extern "system" fn(i32, *mut u32) = mem::transmute(load("glGenQueries")?);
+            let fns = QueryFns {
+                begin_query: mem::transmute(load("glBeginQuery")?),
+                end_query: mem::transmute(load("glEndQuery")?),
+                get_query_object_iv: mem::transmute(load("glGetQueryObjectiv")?),
+                get_query_object_ui64v: mem::transmute(load("glGetQueryObjectui64v")?),
+            };
+            Some((gen_queries, fns))
+        })();
+
+        let mut free = vec![0; QUERY_COUNT];
+        let fns = fns.map(|(gen_queries, fns)| {
+            gen_queries(QUERY_COUNT as i32, free.as_mut_ptr());
+            fns
+        });
+        if fns.is_none() {
+            free.clear();
+        }
+
+        GpuTimer {
+            fns,
+            free,
+            in_flight: VecDeque::new(),
+            active: None,
+            last_elapsed: None,
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.poll();
+        if let (Some(ref fns), None) = (&self.fns, self.active) {
+            if let Some(query) = self.free.pop() {
+                (fns.begin_query)(GL_TIME_ELAPSED, query);
+                self.active = Some(query);
+            }
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        if let (Some(ref fns), Some(query)) = (&self.fns, self.active.take()) {
+            (fns.end_query)(GL_TIME_ELAPSED);
+            self.in_flight.push_back(query);
+        }
+    }
+
+    /// The GPU time of the most recent frame whose timing has become available.
+    pub fn last_elapsed(&mut self) -> Option<Duration> {
+        self.poll();
+        self.last_elapsed
+    }
+
+    fn poll(&mut self) {
+        let fns = match self.fns {
+            Some(ref fns) => fns,
+            None => return
+        };
+        while let Some(&query) = self.in_flight.front() {
+            let mut available = 0;
+            (fns.get_query_object_iv)(query, GL_QUERY_RESULT_AVAILABLE, &mut available);
+            if available == 0 {
+                break;
+            }
+
+            let mut nanos = 0;
+            (fns.get_query_object_ui64v)(query, GL_QUERY_RESULT, &mut nanos);
+            self.last_elapsed = Some(Duration::from_nanos(nanos));
+            self.in_flight.pop_front();
+            self.free.push(query);
+        }
+    }
+}
@@ -6,14 +6,14 @@ mod image;
 // mod text;
 
 use crate::cgmath::{Point2, EuclideanSpace};
-use cgmath_geometry::{D2, rect::{GeoBox, OffsetBox, BoundBox}};
+use cgmath_geometry::{D2, rect::{GeoBox, OffsetBox, BoundBox, DimsBox}};
 use glyphydog::{ShapedBuffer, Shaper, FaceSize, DPI};
 
 use gullery::image_format::Rgba;
 
 use crate::gl_render::FrameDraw;
 
-use crate::theme::Theme;
+use crate::theme::{Brush, RescaleRules, Theme};
 use crate::core::render::Theme as CoreTheme;
 
 use self::image::ImageToVertices;
@@ -23,6 +23,9 @@ use self::image::ImageToVertices;
 
 use std::mem;
 
+/// The width and height of the texture gradient brushes get rasterized into.
+const GRADIENT_RES: u32 = 64;
+
 
 #[derive(Debug, PartialEq)]
 pub struct ThemedPrim {
@@ -47,6 +50,18 @@ pub enum Prim {
     DirectRender(*mut FnMut(&mut D))
 }
 
+/// Draw a rectangle filled with a single color.
+pub(in crate::gl_render) fn draw_solid_rect(draw: &mut FrameDraw, rect: BoundBox<D2, i32>, clip: BoundBox<D2, i32>, color: Rgba<u8>) {
+    let atlas_rect = draw.atlas.white().cast::<u16>().unwrap();
+    draw.vertices.extend(ImageToVertices::new(
+        rect,
+        clip,
+        atlas_rect,
+        color,
+        RescaleRules::StretchOnPixelCenter
+    ));
+}
+
 impl RelPoint {
     #[inline]
     pub fn new(frac_origin: f32, pixel_pos: i32) -> RelPoint {
@@ -96,8 +111,8 @@ impl Translator {
             let widget_theme = theme.widget_theme(theme_path);
 
             if let Some(parent_clipped) = clip_rect.intersect_rect(parent_rect) {
-                match (prim.prim, widget_theme.image, widget_theme.text) {
-                    (Prim::Image, Some(image), _) => {
+                match (prim.prim, widget_theme.background, widget_theme.text) {
+                    (Prim::Image, Some(Brush::Image(image)), _) => {
                         let atlas_rect = draw.atlas.image_rect(theme_path, || (&image.pixels, image.dims)).cast::<u16>().unwrap();
 
                         let abs_rect_dims = abs_rect.dims();
@@ -119,6 +134,72 @@ impl Translator {
 
                         draw.vertices.extend(image_translate);
                     },
+                    (Prim::Image, Some(Brush::TiledImage(image)), _) => {
+                        let atlas_rect = draw.atlas.image_rect(theme_path, || (&image.pixels, image.dims)).cast::<u16>().unwrap();
+                        let tile_dims = image.dims.cast::<i32>().unwrap();
+
+                        if let Some(tile_clip) = parent_clipped.intersect_rect(abs_rect) {
+                            if tile_dims.width() > 0 && tile_dims.height() > 0 {
+                                // Only emit the tiles that are actually visible.
+                                let first_col = (tile_clip.min().x - abs_rect.min().x) / tile_dims.width();
+                                let first_row = (tile_clip.min().y - abs_rect.min().y) / tile_dims.height();
+                                let mut y = abs_rect.min().y + first_row * tile_dims.height();
+                                while y < tile_clip.max().y {
+                                    let mut x = abs_rect.min().x + first_col * tile_dims.width();
+                                    while x < tile_clip.max().x {
+                                        let tile_rect = BoundBox::new2(x, y, x + tile_dims.width(), y + tile_dims.height());
+                                        draw.vertices.extend(ImageToVertices::new(
+                                            tile_rect,
+                                            tile_clip,
+                                            atlas_rect,
+                                            Rgba::new(255, 255, 255, 255),
+                                            RescaleRules::Stretch
+                                        ));
+                                        x += tile_dims.width();
+                                    }
+                                    y += tile_dims.height();
+                                }
+                            }
+                        }
+
+                        if let Some(rect_px_out) = prim.rect_px_out {
+                            unsafe{ *rect_px_out = abs_rect - parent_rect.min().to_vec() };
+                        }
+                    },
+                    (Prim::Image, Some(Brush::Solid(color)), _) => {
+                        draw_solid_rect(draw, abs_rect, parent_clipped, color);
+
+                        if let Some(rect_px_out) = prim.rect_px_out {
+                            unsafe{ *rect_px_out = abs_rect - parent_rect.min().to_vec() };
+                        }
+                    },
+                    (Prim::Image, Some(brush @ Brush::LinearGradient{..}), _) |
+                    (Prim::Image, Some(brush @ Brush::RadialGradient{..}), _) => {
+                        // Gradients get rasterized into a small texture, which gets stretched over
+                        // the widget. Texture filtering smooths out the steps between texels.
+                        let pixels = (0..GRADIENT_RES * GRADIENT_RES).map(|i| {
+                            let point = Point2::new(
+                                ((i % GRADIENT_RES) as f32 + 0.5) / GRADIENT_RES as f32,
+                                ((i / GRADIENT_RES) as f32 + 0.5) / GRADIENT_RES as f32
+                            );
+                            brush.color_at_in(point, draw.color_space).unwrap()
+                        }).collect::<Vec<_>>();
+                        let atlas_rect = draw.atlas.image_rect(
+                            theme_path,
+                            || (&pixels, DimsBox::new2(GRADIENT_RES, GRADIENT_RES))
+                        ).cast::<u16>().unwrap();
+                        draw.vertices.extend(ImageToVertices::new(
+                            abs_rect,
+                            parent_clipped,
+                            atlas_rect,
+                            Rgba::new(255, 255, 255, 255),
+                            RescaleRules::StretchOnPixelCenter
+                        ));
+
+                        if let Some(rect_px_out) = prim.rect_px_out {
+                            unsafe{ *rect_px_out = abs_rect - parent_rect.min().to_vec() };
+                        }
+                    },
                     // (Prim::String(render_string), _, Some(theme_text)) => {
                     //     match draw.font_cache.face(theme_text.face.clone()) {
                     //         Ok(face) => {
@@ -251,11 +251,17 @@ impl<'a> GlyphDraw<'a> {
 
         let face_size = FaceSize::new(text_style.face_size, text_style.face_size);
 
-        let render_mode = RenderMode::Normal;
+        // The atlas stores single-channel coverage, so subpixel text gets drawn as grayscale.
+        let antialiasing = text_style.antialiasing;
+        let render_mode = match antialiasing.is_monochrome() {
+            true => RenderMode::Mono,
+            false => RenderMode::Normal
+        };
         let (atlas_rect, glyph_bearing) = atlas.glyph_rect(
             text_style.face.clone(),
             text_style.face_size,
             glyph_index,
+            antialiasing,
             || {
                 let glyph_res = face.load_glyph(
                     glyph_index,
@@ -278,10 +284,10 @@ impl<'a> GlyphDraw<'a> {
                         (
                             bytes.chunks(pitch)
                                 .map(move |b|
-                                    b[..dims.width() as usize]
+                                    (0..dims.width() as usize)
                                         // We upload white glyphs to the atlas, which are colored by
                                         // vertex colors.
-                                        .into_iter().map(|t| Rgba::new(255, 255, 255, *t))
+                                        .map(move |x| Rgba::new(255, 255, 255, antialiasing.coverage(b, x)))
                                 ),
                             bitmap.dims,
                             glyph_metrics.hori_bearing / 64
@@ -3,54 +3,84 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use glutin::*;
-use glutin::{MouseButton as GMouseButton, WindowEvent as GWindowEvent, MouseScrollDelta};
+use glutin::{MouseButton as GMouseButton, WindowEvent as GWindowEvent, MouseScrollDelta, TouchPhase};
 use crate::gl_render::{GLRenderer, GLFrame};
-use derin_common_types::buttons::{MouseButton, Key, ModifierKeys};
+use derin_common_types::buttons::{MouseButton, Key, KeyChord, ModifierKeys};
 use crate::core::{
     Root, EventLoopResult, WindowEvent,
+    event::{FocusPolicy, ScrollPhase},
+    error::CoreError,
+    recovery::FrameError,
+    shortcut::{ShortcutError, ShortcutId, ShortcutScope},
     widget::Widget,
     render::Renderer,
 };
-use crate::theme::Theme;
+use crate::theme::{Theme, color::ColorSpace};
+use crate::window_geometry::{GeometryTracker, Monitor, WindowGeometry};
 use gullery::ContextState;
 
 use std::thread::{self, JoinHandle};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::rc::Rc;
 use crate::cgmath::{Point2, Vector2};
-use cgmath_geometry::{D2, rect::{DimsBox, GeoBox}};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
 
 use parking_lot::Mutex;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WindowConfig {
     pub dimensions: Option<DimsBox<D2, u32>>,
+    /// The position of the window's top-left corner. Chosen by the OS if `None`.
+    pub position: Option<Point2<i32>>,
+    pub maximized: bool,
     pub title: String,
 
     pub multisampling: u16,
     pub depth_bits: Option<u8>,
     pub stencil_bits: Option<u8>,
+    /// The color space colors get blended in. `ColorSpace::Linear` renders to an sRGB framebuffer,
+    /// which avoids the dark fringes sRGB blending leaves around translucent edges.
+    pub color_space: ColorSpace,
 }
 
 impl Default for WindowConfig {
     fn default() -> WindowConfig {
         WindowConfig {
             dimensions: None,
+            position: None,
+            maximized: false,
             title: "Derin Window".to_string(),
             multisampling: 0,
             depth_bits: None,
-            stencil_bits: None
+            stencil_bits: None,
+            color_space: ColorSpace::Srgb,
         }
     }
 }
 
+impl WindowConfig {
+    /// Restore saved window geometry, moving the window back onto one of `monitors` if it would
+    /// otherwise open off-screen. `monitors` can be retrieved with `GlutinWindow::monitors`.
+    pub fn restore_geometry(&mut self, geometry: &WindowGeometry, monitors: &[Monitor]) {
+        let geometry = geometry.fit_to_monitors(monitors);
+        self.dimensions = Some(geometry.dimensions);
+        self.position = Some(geometry.position);
+        self.maximized = geometry.maximized;
+    }
+}
+
 /// A window displayed on the desktop, which contains a set of drawable widgets.
+///
+/// Screen reader announcements get dropped; see `Announcement`.
 pub struct GlutinWindow<W: Widget> {
     primary_renderer: GLRenderer,
     events_loop: EventsLoop,
     timer_sync: Arc<Mutex<TimerPark>>,
     timer_thread_handle: JoinHandle<()>,
+    /// Whether the window was last set to be maximized, and where it was before that. Glutin
+    /// can't query either from the OS.
+    geometry_tracker: GeometryTracker,
     root: Root<W, GLFrame>
 }
 
@@ -71,10 +101,12 @@ impl<W: Widget> GlutinWindow<W> {
         let mut window_builder = WindowBuilder::new();
         window_builder.window.dimensions = config.dimensions.map(|d| (d.width(), d.height()));
         window_builder.window.title = config.title.clone();
+        window_builder.window.maximized = config.maximized;
         let gen_context_builder = || {
             let mut context_builder = ContextBuilder::new();
 
             context_builder = context_builder.with_multisampling(config.multisampling);
+            context_builder = context_builder.with_srgb(config.color_space == ColorSpace::Linear);
             if let Some(depth_bits) = config.depth_bits {
                 context_builder = context_builder.with_depth_buffer(depth_bits);
             }
@@ -86,7 +118,10 @@ impl<W: Widget> GlutinWindow<W> {
         };
 
         let events_loop = EventsLoop::new();
-        let renderer = GLRenderer::new(&events_loop, window_builder, gen_context_builder)?;
+        let renderer = GLRenderer::new(&events_loop, window_builder, gen_context_builder, config.color_space)?;
+        if let Some(position) = config.position {
+            renderer.window().set_position(position.x, position.y);
+        }
 
         let timer_sync = Arc::new(Mutex::new(TimerPark::Indefinite));
         let timer_sync_timer_thread = timer_sync.clone();
@@ -115,12 +150,25 @@ impl<W: Widget> GlutinWindow<W> {
             }
         });
 
+        let scroll_line_height = theme.scroll_line_height();
+        let mut root = Root::new(root, theme, renderer.dims());
+        root.set_scroll_line_height(scroll_line_height);
+        let task_proxy = events_loop.create_proxy();
+        root.set_task_wakeup(move || {task_proxy.wakeup().ok();});
+
+        let mut geometry_tracker = GeometryTracker::new();
+        if let (Some(position), Some(dimensions)) = (config.position, config.dimensions) {
+            geometry_tracker.observe(position, dimensions);
+        }
+        geometry_tracker.set_maximized(config.maximized);
+
         Ok(GlutinWindow {
-            root: Root::new(root, theme, renderer.dims()),
+            root,
             primary_renderer: renderer,
             events_loop,
             timer_sync,
             timer_thread_handle,
+            geometry_tracker,
         })
     }
 
@@ -134,6 +182,88 @@ impl<W: Widget> GlutinWindow<W> {
         &mut self.root.root_widget
     }
 
+    /// Retrieves the panic that aborted the last bad frame, if it hasn't been cleared.
+    pub fn frame_error(&self) -> Option<&FrameError> {
+        self.root.frame_error()
+    }
+
+    /// Clears the stored frame error, returning it.
+    pub fn clear_frame_error(&mut self) -> Option<FrameError> {
+        self.root.clear_frame_error()
+    }
+
+    /// Takes the errors that have occurred while laying out and rendering widgets since this was
+    /// last called.
+    pub fn take_errors(&mut self) -> Vec<CoreError> {
+        self.root.take_errors()
+    }
+
+    /// Retrieves how keyboard focus follows the mouse.
+    pub fn focus_policy(&self) -> FocusPolicy {
+        self.root.focus_policy()
+    }
+
+    /// Sets how keyboard focus follows the mouse. Defaults to `FocusPolicy::ClickToFocus`.
+    pub fn set_focus_policy(&mut self, policy: FocusPolicy) {
+        self.root.set_focus_policy(policy);
+    }
+
+    /// Sets the longest time between two presses of a double click.
+    pub fn set_double_click_interval(&mut self, interval: Duration) {
+        self.root.set_double_click_interval(interval);
+    }
+
+    /// Binds `chord` to broadcast a clone of `message` whenever it's pressed within `scope`.
+    pub fn register_shortcut<M>(&mut self, chord: KeyChord, scope: ShortcutScope, message: M) -> Result<ShortcutId, ShortcutError>
+        where M: 'static + Clone
+    {
+        self.root.register_shortcut(chord, scope, message)
+    }
+
+    /// Removes a shortcut registered with `register_shortcut`, returning whether it was found.
+    pub fn remove_shortcut(&mut self, shortcut_id: ShortcutId) -> bool {
+        self.root.remove_shortcut(shortcut_id)
+    }
+
+    /// Retrieves the monitors connected to the desktop.
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.events_loop.get_available_monitors().map(|monitor| {
+            let (x, y) = monitor.get_position();
+            let (width, height) = monitor.get_dimensions();
+            Monitor {
+                name: monitor.get_name(),
+                rect: BoundBox::new2(x, y, x + width as i32, y + height as i32)
+            }
+        }).collect()
+    }
+
+    /// Retrieves the window's current geometry, for saving with the rest of the application's
+    /// settings. Returns `None` if the window has been closed.
+    ///
+    /// If the window's maximized, the saved position and size are the ones it had before it was
+    /// maximized, so that it has somewhere to be restored to when it's reopened.
+    pub fn geometry(&self) -> Option<WindowGeometry> {
+        let window = self.primary_renderer.window();
+        let (position, dimensions) = self.position_and_size()?;
+        Some(self.geometry_tracker.geometry(position, dimensions, window.get_current_monitor().get_name()))
+    }
+
+    /// Maximizes or restores the window.
+    pub fn set_maximized(&mut self, maximized: bool) {
+        if let Some((position, dimensions)) = self.position_and_size() {
+            self.geometry_tracker.observe(position, dimensions);
+        }
+        self.primary_renderer.window().set_maximized(maximized);
+        self.geometry_tracker.set_maximized(maximized);
+    }
+
+    fn position_and_size(&self) -> Option<(Point2<i32>, DimsBox<D2, u32>)> {
+        let window = self.primary_renderer.window();
+        let (x, y) = window.get_position()?;
+        let (width, height) = window.get_inner_size()?;
+        Some((Point2::new(x, y), DimsBox::new2(width, height)))
+    }
+
     /// Starts the `derin` event loop, calling `on_action` whenever an action is triggered by a
     /// child widget. Aborts when `LoopFlow::Break` is returned by `on_action`.
     ///
@@ -148,6 +278,7 @@ impl<W: Widget> GlutinWindow<W> {
             ref mut timer_sync,
             ref mut timer_thread_handle,
             ref mut root,
+            geometry_tracker: _,
         } = *self;
 
         let map_modifiers = |g_modifiers: ModifiersState| {
@@ -192,11 +323,21 @@ impl<W: Widget> GlutinWindow<W> {
                                     ElementState::Released => WindowEvent::MouseUp(button)
                                 }
                             }
-                            GWindowEvent::MouseWheel{delta, modifiers, ..} => {
+                            GWindowEvent::MouseWheel{delta, phase, modifiers, ..} => {
                                 frame.set_modifiers(map_modifiers(modifiers));
                                 match delta {
                                     MouseScrollDelta::LineDelta(x, y) => WindowEvent::MouseScrollLines(Vector2::new(x as i32, y as i32)),
-                                    MouseScrollDelta::PixelDelta(x, y) => WindowEvent::MouseScrollPx(Vector2::new(x as i32, y as i32)),
+                                    // Glutin doesn't report momentum scrolling, so those deltas
+                                    // show up as regular moves.
+                                    MouseScrollDelta::PixelDelta(x, y) => WindowEvent::MouseScrollPxPrecise {
+                                        delta: Vector2::new(x, y),
+                                        phase: match phase {
+                                            TouchPhase::Started => ScrollPhase::Started,
+                                            TouchPhase::Moved => ScrollPhase::Moved,
+                                            TouchPhase::Ended |
+                                            TouchPhase::Cancelled => ScrollPhase::Ended,
+                                        }
+                                    },
                                 }
                             }
                             GWindowEvent::Resized(width, height) => WindowEvent::WindowResize(DimsBox::new2(scale!(width), scale!(height))),
@@ -235,6 +376,14 @@ impl<W: Widget> GlutinWindow<W> {
                 next_timer,
                 set_cursor_pos,
                 set_cursor_icon,
+                // TODO: POSITION THE IME WINDOW ONCE GLUTIN REPORTS COMPOSITION EVENTS
+                set_ime_caret_rect: _,
+                // Glutin doesn't expose the platform's accessibility API.
+                announcements: _,
+                // TODO: PLAY THE THEME'S SOUNDS THROUGH A PLATFORM AUDIO BACKEND
+                sounds: _,
+                // Any panic is kept in `root.frame_error` for the application to inspect.
+                frame_aborted: _,
             } = frame.finish();
 
             match next_timer {
@@ -289,6 +438,7 @@ fn map_key(k: VirtualKeyCode) -> Option<Key> {
         // VirtualKeyCode::Print => Some(Key::Print),
         // VirtualKeyCode::Execute => Some(Key::Execute),
         VirtualKeyCode::Snapshot => Some(Key::PrntScr),
+        VirtualKeyCode::Apps => Some(Key::Apps),
         VirtualKeyCode::Insert => Some(Key::Insert),
         VirtualKeyCode::Delete => Some(Key::Delete),
         // VirtualKeyCode::Help => Some(Key::Help),
@@ -419,7 +569,6 @@ fn map_key(k: VirtualKeyCode) -> Option<Key> {
         VirtualKeyCode::Compose |
         VirtualKeyCode::AbntC1 |
         VirtualKeyCode::AbntC2 |
-        VirtualKeyCode::Apps |
         VirtualKeyCode::At |
         VirtualKeyCode::Ax |
         VirtualKeyCode::Calculator |
@@ -0,0 +1,322 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Syntax highlighting for editable text.
+//!
+//! A [`Highlighter`] splits lines of text into spans, each with a style class such as `"keyword"`
+//! or `"string"`. `EditBox::set_highlighter` highlights the box's contents with a highlighter, and
+//! the theme maps each style class to a color with `Theme::insert_highlight_color`.
+//!
+//! Highlighters work one line at a time, carrying a state from the end of each line to the start of
+//! the next, so constructs that span several lines (like block comments) get highlighted properly.
+//! [`HighlightCache`] uses that to re-highlight incrementally: after an edit, only the changed lines
+//! get re-highlighted, along with any following lines whose starting state changed.
+//!
+//! [`Highlighter`]: ./trait.Highlighter.html
+//! [`HighlightCache`]: ./struct.HighlightCache.html
+
+pub use crate::core::render::HighlightSpan;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// Splits text into highlighted spans.
+pub trait Highlighter: 'static {
+    /// The state carried from one line to the next, such as whether a block comment is open.
+    type State: 'static + Clone + PartialEq;
+
+    /// The state at the start of the text.
+    fn start_state(&self) -> Self::State;
+
+    /// Push the spans in `line` onto `spans`, with ranges relative to the start of the line, and
+    /// update `state` to the state at the end of the line. `line` doesn't include the line break.
+    ///
+    /// Spans may touch or overlap, such as an escape sequence inside a string. They get passed on
+    /// in the order they're pushed, without being merged or split.
+    fn highlight_line(&mut self, line: &str, state: &mut Self::State, spans: &mut Vec<HighlightSpan>);
+}
+
+/// Caches the highlighting of each line in a string, so that only changed lines get re-highlighted.
+#[derive(Clone)]
+pub struct HighlightCache<H: Highlighter> {
+    highlighter: H,
+    lines: Vec<LineHighlight<H::State>>,
+    spans: Vec<HighlightSpan>,
+}
+
+#[derive(Clone)]
+struct LineHighlight<S> {
+    hash: u64,
+    start_state: S,
+    end_state: S,
+    spans: Vec<HighlightSpan>,
+}
+
+/// `HighlightCache` with the highlighter type erased, so widgets can store a cache for any
+/// highlighter.
+pub(crate) trait DynHighlightCache {
+    fn update(&mut self, string: &str) -> bool;
+    fn spans(&self) -> &[HighlightSpan];
+    fn box_clone(&self) -> Box<DynHighlightCache>;
+}
+
+impl<H: Highlighter> HighlightCache<H> {
+    pub fn new(highlighter: H) -> HighlightCache<H> {
+        HighlightCache {
+            highlighter,
+            lines: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn highlighter(&self) -> &H {
+        &self.highlighter
+    }
+
+    /// Retrieves the highlighter, for mutation. Call `invalidate` afterwards if the change affects
+    /// how text gets highlighted.
+    pub fn highlighter_mut(&mut self) -> &mut H {
+        &mut self.highlighter
+    }
+
+    /// Throw away the cached highlighting, so the next `update` re-highlights every line.
+    pub fn invalidate(&mut self) {
+        self.lines.clear();
+        self.spans.clear();
+    }
+
+    /// The spans in the string passed to the last `update`, with ranges relative to the start of
+    /// the string.
+    pub fn spans(&self) -> &[HighlightSpan] {
+        &self.spans
+    }
+
+    /// Re-highlight the lines of `string` that changed since the last update. Returns whether any
+    /// lines changed.
+    pub fn update(&mut self, string: &str) -> bool {
+        let new_lines: Vec<(&str, u64)> = string.split('\n').map(|line| (line, hash_line(line))).collect();
+
+        // Edits usually touch a few lines in the middle of the text, so find the lines before and
+        // after the edit that stayed the same.
+        let prefix = self.lines.iter().zip(&new_lines)
+            .take_while(|(old, new)| old.hash == new.1)
+            .count();
+        if prefix == self.lines.len() && prefix == new_lines.len() {
+            return false;
+        }
+        let suffix = self.lines[prefix..].iter().rev().zip(new_lines[prefix..].iter().rev())
+            .take_while(|(old, new)| old.hash == new.1)
+            .count();
+
+        let old_suffix = self.lines.split_off(self.lines.len() - suffix);
+        self.lines.truncate(prefix);
+
+        let mut state = match self.lines.last() {
+            Some(line) => line.end_state.clone(),
+            None => self.highlighter.start_state()
+        };
+        for &(line, hash) in &new_lines[prefix..new_lines.len() - suffix] {
+            let line = highlight_line(&mut self.highlighter, line, hash, state);
+            state = line.end_state.clone();
+            self.lines.push(line);
+        }
+
+        // Unchanged lines still need re-highlighting if the state they start in changed.
+        for (old, &(line, hash)) in old_suffix.into_iter().zip(&new_lines[new_lines.len() - suffix..]) {
+            let line = match old.start_state == state {
+                true => old,
+                false => highlight_line(&mut self.highlighter, line, hash, state)
+            };
+            state = line.end_state.clone();
+            self.lines.push(line);
+        }
+
+        self.spans.clear();
+        let mut line_start = 0;
+        for (line, &(line_str, _)) in self.lines.iter().zip(&new_lines) {
+            self.spans.extend(line.spans.iter().map(|span| HighlightSpan {
+                range: span.range.start + line_start..span.range.end + line_start,
+                class: span.class,
+            }));
+            line_start += line_str.len() + 1;
+        }
+        true
+    }
+}
+
+fn hash_line(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn highlight_line<H: Highlighter>(highlighter: &mut H, line: &str, hash: u64, start_state: H::State) -> LineHighlight<H::State> {
+    let mut end_state = start_state.clone();
+    let mut spans = Vec::new();
+    highlighter.highlight_line(line, &mut end_state, &mut spans);
+    LineHighlight{ hash, start_state, end_state, spans }
+}
+
+impl<H: Highlighter> fmt::Debug for HighlightCache<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HighlightCache")
+            .field("lines", &self.lines.len())
+            .field("spans", &self.spans)
+            .finish()
+    }
+}
+
+impl<H: Highlighter + Clone> DynHighlightCache for HighlightCache<H> {
+    fn update(&mut self, string: &str) -> bool {
+        HighlightCache::update(self, string)
+    }
+
+    fn spans(&self) -> &[HighlightSpan] {
+        HighlightCache::spans(self)
+    }
+
+    fn box_clone(&self) -> Box<DynHighlightCache> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<DynHighlightCache> {
+    fn clone(&self) -> Box<DynHighlightCache> {
+        self.box_clone()
+    }
+}
+
+impl fmt::Debug for DynHighlightCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HighlightCache")
+            .field("spans", &self.spans())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    /// Highlights identifiers, punctuation, strings with `\n` escapes, and `/* */` block comments.
+    #[derive(Default, Clone)]
+    struct TestHighlighter {
+        lines_highlighted: usize,
+    }
+
+    fn span(range: Range<usize>, class: &'static str) -> HighlightSpan {
+        HighlightSpan{ range, class }
+    }
+
+    impl Highlighter for TestHighlighter {
+        /// Whether a block comment is open.
+        type State = bool;
+
+        fn start_state(&self) -> bool {
+            false
+        }
+
+        fn highlight_line(&mut self, line: &str, in_comment: &mut bool, spans: &mut Vec<HighlightSpan>) {
+            self.lines_highlighted += 1;
+            let bytes = line.as_bytes();
+            let mut i = 0;
+            while i < bytes.len() {
+                let start = i;
+                if *in_comment || line[i..].starts_with("/*") {
+                    let search_from = if *in_comment {i} else {i + 2};
+                    i = match line[search_from..].find("*/") {
+                        Some(close) => {*in_comment = false; search_from + close + 2},
+                        None => {*in_comment = true; bytes.len()}
+                    };
+                    spans.push(span(start..i, "comment"));
+                } else if bytes[i] == b'"' {
+                    i = line[i + 1..].find('"').map(|close| i + close + 2).unwrap_or(bytes.len());
+                    spans.push(span(start..i, "string"));
+                    for (escape, _) in line[start..i].match_indices("\\n") {
+                        spans.push(span(start + escape..start + escape + 2, "escape"));
+                    }
+                } else if bytes[i].is_ascii_alphanumeric() {
+                    while i < bytes.len() && bytes[i].is_ascii_alphanumeric() {
+                        i += 1;
+                    }
+                    spans.push(span(start..i, "ident"));
+                } else {
+                    i += 1;
+                    if bytes[start] != b' ' {
+                        spans.push(span(start..i, "punct"));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn adjacent_spans() {
+        let mut cache = HighlightCache::new(TestHighlighter::default());
+        assert!(cache.update("a+b\n\ncd"));
+
+        // Touching spans stay separate, and blank lines still shift the following spans.
+        assert_eq!(
+            &[
+                span(0..1, "ident"),
+                span(1..2, "punct"),
+                span(2..3, "ident"),
+                span(5..7, "ident"),
+            ],
+            cache.spans()
+        );
+    }
+
+    #[test]
+    fn overlapping_spans() {
+        let mut cache = HighlightCache::new(TestHighlighter::default());
+        cache.update("a+b\nx = \"a\\nb\"");
+
+        // The escape span overlaps the string span, and both get offset to the second line.
+        assert_eq!(
+            &[
+                span(0..1, "ident"),
+                span(1..2, "punct"),
+                span(2..3, "ident"),
+                span(4..5, "ident"),
+                span(6..7, "punct"),
+                span(8..14, "string"),
+                span(10..12, "escape"),
+            ],
+            cache.spans()
+        );
+    }
+
+    #[test]
+    fn incremental_update() {
+        let mut cache = HighlightCache::new(TestHighlighter::default());
+        cache.update("a\nb\nc");
+        assert_eq!(3, cache.highlighter().lines_highlighted);
+
+        assert!(!cache.update("a\nb\nc"));
+        assert_eq!(3, cache.highlighter().lines_highlighted);
+
+        // Only the edited line gets re-highlighted.
+        assert!(cache.update("a\nB\nc"));
+        assert_eq!(4, cache.highlighter().lines_highlighted);
+        assert_eq!(&[span(0..1, "ident"), span(2..3, "ident"), span(4..5, "ident")], cache.spans());
+
+        // Opening a comment changes the state every following line starts in.
+        cache.update("/*a\nB\nc");
+        assert_eq!(7, cache.highlighter().lines_highlighted);
+        assert_eq!(&[span(0..3, "comment"), span(4..5, "comment"), span(6..7, "comment")], cache.spans());
+
+        // Closing it re-highlights the edited line and the line after it, but not the line before.
+        cache.update("/*a\nB*/\nc");
+        assert_eq!(9, cache.highlighter().lines_highlighted);
+        assert_eq!(&[span(0..3, "comment"), span(4..7, "comment"), span(8..9, "ident")], cache.spans());
+
+        cache.invalidate();
+        cache.update("/*a\nB*/\nc");
+        assert_eq!(12, cache.highlighter().lines_highlighted);
+    }
+}
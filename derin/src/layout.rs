@@ -3,13 +3,370 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 //! Utilities for specifying the layout of widgets.
-pub use derin_common_types::layout::{Align, Align2, GridSize, Margins, SizeBounds, TrRange, TrackHints, WidgetPos, WidgetSpan};
+pub use derin_common_types::layout::{AbsolutePos, Align, Align2, AutoFlow, Axis, GridSize, LayoutDirection, Margins, SizeBounds, TrRange, TrackHints, WidgetPos, WidgetSpan};
+use derin_common_types::{Px, layout::{ContentSize, Fr}};
+use derin_layout_engine::GridEngine;
+use crate::container::Slot;
 use crate::core::widget::WidgetIdent;
+use crate::cgmath::Vector2;
+use std::collections::HashMap;
 
 /// Places widgets in a resizable grid-based layout.
 pub trait GridLayout: 'static {
     fn positions(&self, widget_ident: WidgetIdent, widget_index: usize, num_widgets: usize) -> Option<WidgetPos>;
     fn grid_size(&self, num_widgets: usize) -> GridSize;
+    /// Position the widget relative to the parent's rectangle, instead of placing it in the grid.
+    ///
+    /// Widgets this returns `Some` for are left out of the grid entirely, and don't affect the size
+    /// of the grid's tracks or the parent's size bounds. Their rects get clamped to the parent's
+    /// rect, so they can't extend outside of it. Returns `None` by default.
+    fn absolute_position(&self, _widget_ident: WidgetIdent, _widget_index: usize, _num_widgets: usize) -> Option<AbsolutePos> {
+        None
+    }
+    /// The space between adjacent columns (`x`) and adjacent rows (`y`). Returns no gap by default.
+    fn gap(&self, _num_widgets: usize) -> Vector2<i32> {
+        Vector2::new(0, 0)
+    }
+    /// The order the grid's columns and rows are laid out in. Returns left-to-right, top-to-bottom
+    /// by default.
+    fn direction(&self, _num_widgets: usize) -> LayoutDirection {
+        LayoutDirection::default()
+    }
+    /// Place widgets without a position into the next free cell of the grid, adding tracks as
+    /// needed. Widgets that `positions` returns `None` for, or a position with an empty span, are
+    /// auto-placed. Returns `None` by default, which leaves such widgets unsolvable.
+    fn auto_flow(&self, _num_widgets: usize) -> Option<AutoFlow> {
+        None
+    }
+    /// The sizes of the grid's columns. If `Some`, the grid has as many columns as the template
+    /// has tracks, regardless of `grid_size`. Returns `None` by default.
+    fn col_template(&self, _num_widgets: usize) -> Option<TrackTemplate> {
+        None
+    }
+    /// The sizes of the grid's rows. If `Some`, the grid has as many rows as the template has
+    /// tracks, regardless of `grid_size`. Returns `None` by default.
+    fn row_template(&self, _num_widgets: usize) -> Option<TrackTemplate> {
+        None
+    }
+}
+
+/// A sequence of track sizes, for setting the hints of every column or row of a grid at once.
+///
+/// ```ignore
+/// // A fixed-size sidebar, followed by three equally-sized columns.
+/// let cols = TrackTemplate::new()
+///     .track(rigid(200))
+///     .repeat(3, &[fr(1.0)]);
+/// cols.apply(&mut engine, Axis::X);
+/// ```
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TrackTemplate {
+    tracks: Vec<TrackHints>,
+}
+
+/// A track that takes up `fr_size` of the free space.
+#[inline]
+pub fn fr(fr_size: Fr) -> TrackHints {
+    TrackHints {
+        fr_size,
+        ..TrackHints::default()
+    }
+}
+
+/// A track that's exactly `size` pixels large.
+#[inline]
+pub fn rigid(size: Px) -> TrackHints {
+    TrackHints {
+        min_size: size,
+        max_size: size,
+        fr_size: 0.0,
+        ..TrackHints::default()
+    }
+}
+
+/// A track sized to fit the widgets inside of it.
+#[inline]
+pub fn content(content_size: ContentSize) -> TrackHints {
+    TrackHints {
+        content_size: Some(content_size),
+        ..TrackHints::default()
+    }
+}
+
+impl TrackTemplate {
+    #[inline]
+    pub fn new() -> TrackTemplate {
+        TrackTemplate::default()
+    }
+
+    /// Builds a template from a whitespace-separated list of tracks, written like a CSS grid
+    /// template: `24px` is a `rigid(24)` track, `1fr` is an `fr(1.0)` track, and
+    /// `repeat(3, 24px 1fr)` repeats the tracks inside of it three times.
+    ///
+    /// ```ignore
+    /// assert_eq!(
+    ///     TrackTemplate::new().track(rigid(200)).repeat(3, &[fr(1.0)]),
+    ///     TrackTemplate::parse("200px repeat(3, 1fr)")
+    /// );
+    /// ```
+    ///
+    /// Panics if the template is malformed.
+    pub fn parse(template: &str) -> TrackTemplate {
+        let mut tracks = TrackTemplate::new();
+        let mut rest = template.trim();
+        while !rest.is_empty() {
+            let end = match rest.starts_with("repeat(") {
+                true => {
+                    let close = rest.find(')')
+                        .unwrap_or_else(|| panic!("unclosed `repeat(` in track template `{}`", template));
+                    let mut args = rest["repeat(".len()..close].splitn(2, ',');
+                    let count = args.next().unwrap().trim();
+                    let count = count.parse::<u32>()
+                        .unwrap_or_else(|_| panic!("bad repeat count `{}` in track template `{}`", count, template));
+                    let pattern = args.next().unwrap_or("").split_whitespace()
+                        .map(|track| parse_track(track, template))
+                        .collect::<Vec<_>>();
+                    if pattern.is_empty() {
+                        panic!("`repeat` without any tracks in track template `{}`", template);
+                    }
+
+                    tracks = tracks.repeat(count, &pattern);
+                    close + 1
+                },
+                false => {
+                    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    tracks = tracks.track(parse_track(&rest[..end], template));
+                    end
+                }
+            };
+            rest = rest[end..].trim_start();
+        }
+        tracks
+    }
+
+    /// Appends a single track.
+    pub fn track(mut self, hints: TrackHints) -> TrackTemplate {
+        self.tracks.push(hints);
+        self
+    }
+
+    /// Appends `pattern` `count` times, so `repeat(2, &[rigid(24), fr(1.0)])` appends four
+    /// alternating tracks.
+    pub fn repeat(mut self, count: u32, pattern: &[TrackHints]) -> TrackTemplate {
+        for _ in 0..count {
+            self.tracks.extend_from_slice(pattern);
+        }
+        self
+    }
+
+    /// The number of tracks in the template.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.tracks.len() as u32
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    #[inline]
+    pub fn tracks(&self) -> &[TrackHints] {
+        &self.tracks
+    }
+
+    /// Resizes the engine's grid to have as many tracks along `axis` as the template, and sets the
+    /// hints of each of those tracks.
+    pub fn apply(&self, engine: &mut GridEngine, axis: Axis) {
+        let mut grid_size = engine.grid_size();
+        let num_tracks = match axis {
+            Axis::X => &mut grid_size.x,
+            Axis::Y => &mut grid_size.y
+        };
+        if *num_tracks != self.len() {
+            *num_tracks = self.len();
+            engine.set_grid_size(grid_size);
+        }
+
+        for (index, &hints) in self.tracks.iter().enumerate() {
+            let index = index as u32;
+            // Only touch tracks that changed, so the engine's solve doesn't get thrown out.
+            match axis {
+                Axis::X if engine.col_hints(index) != hints => engine.set_col_hints(index, hints),
+                Axis::Y if engine.row_hints(index) != hints => engine.set_row_hints(index, hints),
+                _ => ()
+            }
+        }
+    }
+}
+
+/// Parse a single `px` or `fr` track in a `TrackTemplate`.
+fn parse_track(track: &str, template: &str) -> TrackHints {
+    let hints = match (track.ends_with("px"), track.ends_with("fr")) {
+        (true, _) => track[..track.len() - 2].parse::<Px>().ok()
+            .filter(|&size| size >= 0)
+            .map(rigid),
+        (_, true) => track[..track.len() - 2].parse::<Fr>().ok()
+            .filter(|&fr_size| fr_size >= 0.0 && fr_size.is_finite())
+            .map(fr),
+        _ => None
+    };
+    hints.unwrap_or_else(|| panic!(
+        "bad track `{}` in track template `{}`; tracks look like `24px` or `1fr`",
+        track, template
+    ))
+}
+
+/// A grid divided into named areas, for placing widgets by name instead of by track.
+///
+/// Each row of the template is a whitespace-separated list of area names, with one name per
+/// column. An area covers every cell with its name, so every area has to be rectangular. `.`
+/// marks a cell that doesn't belong to any area.
+///
+/// ```ignore
+/// let areas = GridAreas::parse("header header / nav content / footer footer");
+/// assert_eq!(Some(WidgetSpan::new(1, 1)), areas.span("content"));
+/// ```
+///
+/// `GridAreas` implements `GridLayout` by placing each widget in the area with the same name as
+/// the widget's field, so a container with `header`, `nav`, `content` and `footer` fields can be
+/// laid out without writing any positions by hand.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct GridAreas {
+    grid_size: GridSize,
+    rows: Vec<Vec<Option<String>>>,
+    areas: HashMap<String, WidgetSpan>,
+    pub widget_margins: Margins<i32>,
+    pub widget_place: Align2,
+}
+
+impl GridAreas {
+    #[inline]
+    pub fn new() -> GridAreas {
+        GridAreas::default()
+    }
+
+    /// Builds the areas from a template with rows separated by `/`.
+    ///
+    /// Panics if the template is invalid. See `row` for details.
+    pub fn parse(template: &str) -> GridAreas {
+        template.split('/').fold(GridAreas::new(), |areas, row| areas.row(row))
+    }
+
+    /// Appends a row of whitespace-separated area names.
+    ///
+    /// Panics if the row has a different number of columns than the previous rows, or if adding
+    /// the row makes any area non-rectangular.
+    pub fn row(mut self, row: &str) -> GridAreas {
+        let cells: Vec<Option<String>> = row.split_whitespace()
+            .map(|name| match name {
+                "." => None,
+                name => Some(name.to_string())
+            })
+            .collect();
+
+        if cells.is_empty() {
+            panic!("grid areas row {} is empty", self.rows.len());
+        }
+        if self.rows.len() > 0 && cells.len() as u32 != self.grid_size.x {
+            panic!(
+                "grid areas row {} (`{}`) has {} columns, but the rows before it have {}",
+                self.rows.len(), row.trim(), cells.len(), self.grid_size.x
+            );
+        }
+
+        let row_index = self.rows.len() as u32;
+        for (col_index, name) in cells.iter().enumerate() {
+            let (col_index, name) = match name {
+                Some(name) => (col_index as u32, name),
+                None => continue
+            };
+            let span = self.areas.entry(name.clone())
+                .or_insert(WidgetSpan::new(col_index..col_index + 1, row_index..row_index + 1));
+            span.x.start = span.x.start.map(|s| s.min(col_index));
+            span.x.end = span.x.end.map(|e| e.max(col_index + 1));
+            span.y.end = span.y.end.map(|e| e.max(row_index + 1));
+        }
+
+        self.grid_size = GridSize::new(cells.len() as u32, row_index + 1);
+        self.rows.push(cells);
+        self.validate();
+        self
+    }
+
+    /// Panic if any area doesn't fill its bounding rectangle.
+    fn validate(&self) {
+        for (name, span) in &self.areas {
+            let (x, y) = (span.x.start.unwrap()..span.x.end.unwrap(), span.y.start.unwrap()..span.y.end.unwrap());
+            for row in y.clone() {
+                for col in x.clone() {
+                    let cell = self.rows[row as usize][col as usize].as_ref();
+                    if cell != Some(name) {
+                        panic!(
+                            "grid area `{}` isn't rectangular: it spans columns {}..{} and rows {}..{}, \
+                             but cell ({}, {}) is `{}`\n{}",
+                            name, x.start, x.end, y.start, y.end,
+                            col, row, cell.map(|s| &**s).unwrap_or("."),
+                            self.template()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The template, with one row per line.
+    fn template(&self) -> String {
+        self.rows.iter()
+            .map(|row| row.iter().map(|cell| cell.as_ref().map(|s| &**s).unwrap_or(".")).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[inline]
+    pub fn grid_size(&self) -> GridSize {
+        self.grid_size
+    }
+
+    /// The span of the area with the given name.
+    #[inline]
+    pub fn span(&self, name: &str) -> Option<WidgetSpan> {
+        self.areas.get(name).cloned()
+    }
+
+    /// The names of every area, in no particular order.
+    pub fn area_names(&self) -> impl '_ + Iterator<Item=&str> {
+        self.areas.keys().map(|name| &**name)
+    }
+
+    /// A position that places a widget in the area with the given name.
+    ///
+    /// Panics if there's no such area.
+    pub fn place(&self, name: &str) -> WidgetPos {
+        match self.span(name) {
+            Some(widget_span) => WidgetPos {
+                widget_span,
+                margins: self.widget_margins,
+                place_in_cell: self.widget_place,
+                ..WidgetPos::default()
+            },
+            None => panic!("no grid area named `{}`\n{}", name, self.template())
+        }
+    }
+}
+
+impl GridLayout for GridAreas {
+    fn positions(&self, widget_ident: WidgetIdent, _: usize, _: usize) -> Option<WidgetPos> {
+        match widget_ident {
+            WidgetIdent::Str(ref name) if self.areas.contains_key(&**name) => Some(self.place(name)),
+            _ => None
+        }
+    }
+
+    #[inline]
+    fn grid_size(&self, _: usize) -> GridSize {
+        self.grid_size
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -75,3 +432,248 @@ impl GridLayout for LayoutVertical {
         GridSize::new(1, num_widgets as u32)
     }
 }
+
+/// Stacks a template's slots vertically, in the order they're declared. Used by [`Template`].
+///
+/// Stretching slots share the space left over by the other slots, which are kept at their
+/// widgets' minimum heights.
+///
+/// [`Template`]: ../widgets/type.Template.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotLayout {
+    pub slots: &'static [Slot],
+    pub widget_margins: Margins<i32>,
+}
+
+impl SlotLayout {
+    #[inline(always)]
+    pub fn new(slots: &'static [Slot], widget_margins: Margins<i32>) -> SlotLayout {
+        SlotLayout{ slots, widget_margins }
+    }
+}
+
+impl GridLayout for SlotLayout {
+    fn positions(&self, _: WidgetIdent, widget_index: usize, num_widgets: usize) -> Option<WidgetPos> {
+        match widget_index >= num_widgets.min(self.slots.len()) {
+            true => None,
+            false => Some(WidgetPos {
+                widget_span: WidgetSpan::new(0, widget_index as u32),
+                margins: self.widget_margins,
+                place_in_cell: Align2::new(Align::Stretch, Align::Stretch),
+                ..WidgetPos::default()
+            })
+        }
+    }
+
+    #[inline]
+    fn grid_size(&self, _: usize) -> GridSize {
+        GridSize::new(1, self.slots.len() as u32)
+    }
+
+    fn row_template(&self, _: usize) -> Option<TrackTemplate> {
+        let mut template = TrackTemplate::new();
+        for slot in self.slots {
+            template = template.track(fr(match slot.stretch {
+                true => 1.0,
+                false => 0.0
+            }));
+        }
+        Some(template)
+    }
+}
+
+/// A layout whose widget positions are set at runtime, for use with `DynContainer`.
+///
+/// Widgets without a position are placed in the next free cell of the grid if `auto_flow` is set.
+#[derive(Debug, Clone)]
+pub struct DynLayout {
+    pub grid_size: GridSize,
+    pub gap: Vector2<i32>,
+    pub direction: LayoutDirection,
+    pub auto_flow: Option<AutoFlow>,
+    pub col_template: Option<TrackTemplate>,
+    pub row_template: Option<TrackTemplate>,
+    positions: HashMap<WidgetIdent, WidgetPos>,
+    absolute_positions: HashMap<WidgetIdent, AbsolutePos>
+}
+
+impl DynLayout {
+    #[inline]
+    pub fn new(grid_size: GridSize) -> DynLayout {
+        DynLayout {
+            grid_size,
+            gap: Vector2::new(0, 0),
+            direction: LayoutDirection::default(),
+            auto_flow: None,
+            col_template: None,
+            row_template: None,
+            positions: HashMap::new(),
+            absolute_positions: HashMap::new()
+        }
+    }
+
+    pub fn position(&self, widget_ident: &WidgetIdent) -> Option<WidgetPos> {
+        self.positions.get(widget_ident).cloned()
+    }
+
+    /// Sets the grid position of the widget with the given ident. Clears any absolute position
+    /// the widget had.
+    pub fn set_position(&mut self, widget_ident: WidgetIdent, position: Option<WidgetPos>) {
+        self.absolute_positions.remove(&widget_ident);
+        match position {
+            Some(position) => self.positions.insert(widget_ident, position),
+            None => self.positions.remove(&widget_ident)
+        };
+    }
+
+    /// Sets the absolute position of the widget with the given ident. Clears any grid position the
+    /// widget had.
+    pub fn set_absolute_position(&mut self, widget_ident: WidgetIdent, position: Option<AbsolutePos>) {
+        self.positions.remove(&widget_ident);
+        match position {
+            Some(position) => self.absolute_positions.insert(widget_ident, position),
+            None => self.absolute_positions.remove(&widget_ident)
+        };
+    }
+
+    /// Removes the positions stored for the given widget.
+    pub fn remove(&mut self, widget_ident: &WidgetIdent) {
+        self.positions.remove(widget_ident);
+        self.absolute_positions.remove(widget_ident);
+    }
+}
+
+impl GridLayout for DynLayout {
+    fn positions(&self, widget_ident: WidgetIdent, _: usize, _: usize) -> Option<WidgetPos> {
+        self.positions.get(&widget_ident).cloned()
+    }
+
+    #[inline]
+    fn grid_size(&self, _: usize) -> GridSize {
+        self.grid_size
+    }
+
+    fn absolute_position(&self, widget_ident: WidgetIdent, _: usize, _: usize) -> Option<AbsolutePos> {
+        self.absolute_positions.get(&widget_ident).cloned()
+    }
+
+    #[inline]
+    fn gap(&self, _: usize) -> Vector2<i32> {
+        self.gap
+    }
+
+    #[inline]
+    fn direction(&self, _: usize) -> LayoutDirection {
+        self.direction
+    }
+
+    #[inline]
+    fn auto_flow(&self, _: usize) -> Option<AutoFlow> {
+        self.auto_flow
+    }
+
+    fn col_template(&self, _: usize) -> Option<TrackTemplate> {
+        self.col_template.clone()
+    }
+
+    fn row_template(&self, _: usize) -> Option<TrackTemplate> {
+        self.row_template.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_template() {
+        assert_eq!(
+            TrackTemplate::new().track(rigid(200)).track(fr(1.0)).track(fr(2.5)),
+            TrackTemplate::parse("200px 1fr 2.5fr")
+        );
+        assert_eq!(
+            TrackTemplate::new().track(rigid(200)).repeat(2, &[rigid(24), fr(1.0)]).track(rigid(0)),
+            TrackTemplate::parse("  200px repeat(2,24px   1fr) 0px ")
+        );
+        assert_eq!(5, TrackTemplate::parse("repeat(5, 1fr)").len());
+        assert!(TrackTemplate::parse("repeat(0, 1fr)").is_empty());
+        assert!(TrackTemplate::parse("").is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "bad track `24`")]
+    fn track_template_no_unit() {
+        TrackTemplate::parse("24 1fr");
+    }
+
+    #[test]
+    #[should_panic(expected = "bad track `-24px`")]
+    fn track_template_negative() {
+        TrackTemplate::parse("-24px");
+    }
+
+    #[test]
+    #[should_panic(expected = "bad track `1.5px`")]
+    fn track_template_fractional_px() {
+        TrackTemplate::parse("1.5px");
+    }
+
+    #[test]
+    #[should_panic(expected = "unclosed `repeat(`")]
+    fn track_template_unclosed_repeat() {
+        TrackTemplate::parse("repeat(2, 1fr");
+    }
+
+    #[test]
+    #[should_panic(expected = "bad repeat count `two`")]
+    fn track_template_bad_repeat_count() {
+        TrackTemplate::parse("repeat(two, 1fr)");
+    }
+
+    #[test]
+    #[should_panic(expected = "`repeat` without any tracks")]
+    fn track_template_empty_repeat() {
+        TrackTemplate::parse("repeat(2)");
+    }
+
+    #[test]
+    fn grid_areas() {
+        let areas = GridAreas::parse("header header / nav content / footer footer");
+        assert_eq!(GridSize::new(2, 3), areas.grid_size());
+        assert_eq!(Some(WidgetSpan::new(0..2, 0)), areas.span("header"));
+        assert_eq!(Some(WidgetSpan::new(0, 1)), areas.span("nav"));
+        assert_eq!(Some(WidgetSpan::new(1, 1)), areas.span("content"));
+        assert_eq!(Some(WidgetSpan::new(0..2, 2)), areas.span("footer"));
+        assert_eq!(None, areas.span("sidebar"));
+
+        // `.` cells don't belong to any area, and areas can span several rows.
+        let areas = GridAreas::parse("nav . / nav content");
+        assert_eq!(Some(WidgetSpan::new(0, 0..2)), areas.span("nav"));
+        assert_eq!(Some(WidgetSpan::new(1, 1)), areas.span("content"));
+        assert_eq!(2, areas.area_names().count());
+    }
+
+    #[test]
+    #[should_panic(expected = "has 1 columns, but the rows before it have 2")]
+    fn grid_areas_ragged_rows() {
+        GridAreas::parse("header header / content");
+    }
+
+    #[test]
+    #[should_panic(expected = "is empty")]
+    fn grid_areas_empty_row() {
+        GridAreas::parse("header / ");
+    }
+
+    #[test]
+    #[should_panic(expected = "grid area `nav` isn't rectangular")]
+    fn grid_areas_l_shaped() {
+        GridAreas::parse("nav nav / nav content");
+    }
+
+    #[test]
+    #[should_panic(expected = "grid area `nav` isn't rectangular")]
+    fn grid_areas_disjoint() {
+        GridAreas::parse("nav content nav");
+    }
+}
@@ -47,22 +47,58 @@ extern crate png;
 extern crate parking_lot;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 pub mod container;
+pub mod error;
+pub mod highlight;
 // pub mod gl_render;
 // mod glutin_window;
 pub mod layout;
+pub mod menu;
+pub mod platform;
+pub mod selection;
 pub mod theme;
 pub mod widgets;
+pub mod window_geometry;
 
 // pub use crate::glutin_window::{GlutinWindow as Window, WindowConfig};
 pub use glutin::WindowAttributes;
 pub use crate::core::LoopFlow;
+pub use crate::core::recovery::FrameError;
+pub use crate::error::DerinError;
 
 /// `WidgetEvent` type and associated helpers.
 pub mod event {
-    pub use crate::core::event::{EventOps, InputState, MouseDown, FocusChange, WidgetEvent, WidgetEventSourced, MouseHoverChange};
-    pub use derin_common_types::buttons::{ModifierKeys, Key, MouseButton};
+    pub use crate::core::event::{EventOps, InputState, MouseDown, FocusChange, FocusPolicy, WidgetEvent, WidgetEventSourced, MouseHoverChange, PenInput, PenSample, PenTool};
+    pub use crate::core::activity::AppEvent;
+    pub use crate::core::drag::{DragPayload, DRAG_THRESHOLD};
+    pub use crate::core::gesture::{Gesture, GestureSet, SwipeDirection};
+    pub use crate::core::shortcut::{ShortcutError, ShortcutId, ShortcutScope};
+    pub use crate::core::widget::ScopeButton;
+    pub use derin_common_types::buttons::{ModifierKeys, Key, KeyChord, MouseButton};
+}
+
+/// Values that flow down the widget tree, such as the color scheme and locale.
+pub mod ambient {
+    pub use crate::core::ambient::{AmbientValue, ColorScheme, Enabled, Locale, Opacity, ScaleFactor};
+}
+
+/// User accessibility preferences.
+pub mod accessibility {
+    pub use crate::core::accessibility::{AccessibilityPrefs, Announcement, Politeness};
+}
+
+/// Audio feedback for interface events.
+pub mod sound {
+    pub use crate::core::sound::UiSound;
+}
+
+/// Per-frame performance statistics, as displayed by `StatsOverlay`.
+pub mod stats {
+    pub use crate::core::stats::{FrameStats, RenderStats};
 }
 
 /// Types used to assemble widget geometry.
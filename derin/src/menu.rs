@@ -0,0 +1,96 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Descriptions of menu contents, displayed by the `Menu` and `ContextMenu` widgets.
+//!
+//! Widgets request context menus when they receive a `WidgetEvent::ContextMenuRequested` event,
+//! which gets sent when a widget is right-clicked, or when the menu key or `Shift+F10` is pressed
+//! while it's focused. Menus are displayed as popups in the enclosing `PopupLayer`; Derin doesn't
+//! have a backend for native platform menus yet.
+
+use derin_common_types::buttons::KeyChord;
+
+/// The contents of a menu.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MenuModel {
+    pub items: Vec<MenuItem>,
+}
+
+/// A single entry in a menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuItem {
+    /// An item that broadcasts a [`MenuActivated`] message with its `id` when clicked.
+    ///
+    /// [`MenuActivated`]: ./struct.MenuActivated.html
+    Action {
+        id: String,
+        label: String,
+        enabled: bool,
+        /// The shortcut that performs the same action, which gets displayed next to the label.
+        /// Menus don't handle the shortcut themselves.
+        shortcut: Option<KeyChord>,
+    },
+    /// A line separating groups of items.
+    Separator,
+    /// An item that opens another menu next to itself when clicked.
+    Submenu {
+        label: String,
+        enabled: bool,
+        menu: MenuModel,
+    },
+}
+
+/// Message broadcast when an enabled `MenuItem::Action` is clicked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuActivated {
+    /// The `id` of the clicked action.
+    pub id: String,
+}
+
+impl MenuModel {
+    /// Creates a menu with the given items.
+    pub fn new(items: Vec<MenuItem>) -> MenuModel {
+        MenuModel{ items }
+    }
+}
+
+impl MenuItem {
+    /// Creates an enabled action item without a shortcut.
+    pub fn action(id: &str, label: &str) -> MenuItem {
+        MenuItem::Action {
+            id: id.to_string(),
+            label: label.to_string(),
+            enabled: true,
+            shortcut: None,
+        }
+    }
+
+    /// Creates an enabled submenu item.
+    pub fn submenu(label: &str, menu: MenuModel) -> MenuItem {
+        MenuItem::Submenu {
+            label: label.to_string(),
+            enabled: true,
+            menu,
+        }
+    }
+
+    /// Whether the item can be clicked. Separators are never enabled.
+    pub fn enabled(&self) -> bool {
+        match *self {
+            MenuItem::Action{ enabled, .. } |
+            MenuItem::Submenu{ enabled, .. } => enabled,
+            MenuItem::Separator => false
+        }
+    }
+
+    /// The text displayed for the item, including the shortcut of action items.
+    pub fn display_text(&self) -> String {
+        match *self {
+            MenuItem::Action{ ref label, shortcut: Some(shortcut), .. } => format!("{}\t{}", label, shortcut.platform_notation()),
+            MenuItem::Action{ ref label, shortcut: None, .. } => label.clone(),
+            MenuItem::Submenu{ ref label, .. } => format!("{}\t>", label),
+            MenuItem::Separator => String::new()
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Text editing conventions that differ between platforms.
+//!
+//! `EditBox` and `LineBox` follow the [`PlatformConventions`] of the platform Derin was built for:
+//! which modifier key triggers clipboard shortcuts, what double- and triple-clicking select, where
+//! `Home` and `End` move the caret, and which items go in the context menu. The conventions are an
+//! ambient value, so setting a different set on the root widget with `WidgetTag::set_ambient`
+//! changes them for the whole window, and setting it on a container changes them for the text
+//! widgets inside of it.
+//!
+//! [`PlatformConventions`]: ./struct.PlatformConventions.html
+
+pub use crate::core::render::TextUnit;
+use crate::{
+    event::{Key, KeyChord, ModifierKeys},
+    menu::{MenuItem, MenuModel},
+};
+
+/// How text widgets respond to the mouse and keyboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformConventions {
+    /// What double-clicking selects.
+    pub double_click: TextUnit,
+    /// What triple-clicking selects.
+    pub triple_click: TextUnit,
+    /// Whether dragging after a double- or triple-click extends the selection by whole units,
+    /// rather than by characters.
+    pub drag_by_click_unit: bool,
+    /// The modifier held for clipboard shortcuts, select all, and adding carets.
+    pub command_modifier: ModifierKeys,
+    /// The modifier that makes the arrow keys, `Backspace` and `Delete` move by words.
+    pub word_modifier: ModifierKeys,
+    /// The modifier that makes the left and right arrow keys move to the start and end of the
+    /// paragraph, and the up and down arrow keys move to the start and end of the text.
+    pub paragraph_modifier: Option<ModifierKeys>,
+    /// Where `Home` and `End` move the caret. With the command modifier held, they always move to
+    /// the start and end of the text.
+    pub home_end: TextUnit,
+    /// The items in a text widget's context menu, with `None` standing for a separator. Text
+    /// widgets don't open a context menu if this is empty, letting the request bubble up instead.
+    pub edit_menu: Vec<Option<EditAction>>,
+}
+
+/// An editing action that can be placed in a text widget's context menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditAction {
+    Cut,
+    Copy,
+    Paste,
+    /// Delete the selected text.
+    Delete,
+    SelectAll,
+}
+
+impl PlatformConventions {
+    /// The conventions of the platform Derin was built for.
+    pub fn current() -> PlatformConventions {
+        if cfg!(target_os = "macos") {
+            PlatformConventions::macos()
+        } else if cfg!(target_os = "windows") {
+            PlatformConventions::windows()
+        } else {
+            PlatformConventions::linux()
+        }
+    }
+
+    pub fn windows() -> PlatformConventions {
+        PlatformConventions {
+            double_click: TextUnit::Word,
+            triple_click: TextUnit::Paragraph,
+            drag_by_click_unit: true,
+            command_modifier: ModifierKeys::CTRL,
+            word_modifier: ModifierKeys::CTRL,
+            paragraph_modifier: None,
+            home_end: TextUnit::Paragraph,
+            edit_menu: vec![
+                Some(EditAction::Cut),
+                Some(EditAction::Copy),
+                Some(EditAction::Paste),
+                Some(EditAction::Delete),
+                None,
+                Some(EditAction::SelectAll),
+            ],
+        }
+    }
+
+    pub fn macos() -> PlatformConventions {
+        PlatformConventions {
+            // `ModifierKeys::ALT` is the Command key, and `ModifierKeys::LOGO` the Option key.
+            command_modifier: ModifierKeys::ALT,
+            word_modifier: ModifierKeys::LOGO,
+            paragraph_modifier: Some(ModifierKeys::ALT),
+            home_end: TextUnit::Document,
+            edit_menu: vec![
+                Some(EditAction::Cut),
+                Some(EditAction::Copy),
+                Some(EditAction::Paste),
+            ],
+            ..PlatformConventions::windows()
+        }
+    }
+
+    /// The conventions of GTK-based desktops.
+    pub fn linux() -> PlatformConventions {
+        PlatformConventions::windows()
+    }
+
+    /// Builds the context menu for a text widget. Items that need a selection are disabled if
+    /// `has_selection` is false.
+    pub fn edit_menu_model(&self, has_selection: bool) -> MenuModel {
+        let items = self.edit_menu.iter().map(|action| match *action {
+            Some(action) => MenuItem::Action {
+                id: action.id().to_string(),
+                label: action.label().to_string(),
+                enabled: has_selection || !action.needs_selection(),
+                shortcut: Some(self.shortcut(action)),
+            },
+            None => MenuItem::Separator
+        }).collect();
+        MenuModel::new(items)
+    }
+
+    /// The keyboard shortcut that performs `action`.
+    pub fn shortcut(&self, action: EditAction) -> KeyChord {
+        match action {
+            EditAction::Cut => KeyChord::new(self.command_modifier, Key::X),
+            EditAction::Copy => KeyChord::new(self.command_modifier, Key::C),
+            EditAction::Paste => KeyChord::new(self.command_modifier, Key::V),
+            EditAction::Delete => KeyChord::new(ModifierKeys::empty(), Key::Delete),
+            EditAction::SelectAll => KeyChord::new(self.command_modifier, Key::A),
+        }
+    }
+}
+
+impl Default for PlatformConventions {
+    fn default() -> PlatformConventions {
+        PlatformConventions::current()
+    }
+}
+
+impl EditAction {
+    /// The ID of the action's menu item, which gets sent in the `MenuActivated` message.
+    pub fn id(self) -> &'static str {
+        match self {
+            EditAction::Cut => "derin.edit.cut",
+            EditAction::Copy => "derin.edit.copy",
+            EditAction::Paste => "derin.edit.paste",
+            EditAction::Delete => "derin.edit.delete",
+            EditAction::SelectAll => "derin.edit.select_all",
+        }
+    }
+
+    /// Looks up the action with the given menu item ID.
+    pub fn from_id(id: &str) -> Option<EditAction> {
+        [EditAction::Cut, EditAction::Copy, EditAction::Paste, EditAction::Delete, EditAction::SelectAll]
+            .iter().cloned()
+            .find(|action| action.id() == id)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EditAction::Cut => "Cut",
+            EditAction::Copy => "Copy",
+            EditAction::Paste => "Paste",
+            EditAction::Delete => "Delete",
+            EditAction::SelectAll => "Select All",
+        }
+    }
+
+    /// Whether the action does anything without a selection.
+    fn needs_selection(self) -> bool {
+        match self {
+            EditAction::Cut |
+            EditAction::Copy |
+            EditAction::Delete => true,
+            EditAction::Paste |
+            EditAction::SelectAll => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcut_modifiers() {
+        let windows = PlatformConventions::windows();
+        let macos = PlatformConventions::macos();
+
+        assert_eq!(KeyChord::new(ModifierKeys::CTRL, Key::C), windows.shortcut(EditAction::Copy));
+        assert_eq!(KeyChord::new(ModifierKeys::CTRL, Key::A), windows.shortcut(EditAction::SelectAll));
+        assert_eq!(KeyChord::new(ModifierKeys::ALT, Key::C), macos.shortcut(EditAction::Copy));
+        assert_eq!(KeyChord::new(ModifierKeys::ALT, Key::A), macos.shortcut(EditAction::SelectAll));
+        // `Delete` doesn't take a modifier on any platform.
+        assert_eq!(windows.shortcut(EditAction::Delete), macos.shortcut(EditAction::Delete));
+
+        // Words and paragraphs get their own modifiers on macOS, rather than sharing `Ctrl`.
+        assert_eq!(ModifierKeys::CTRL, windows.word_modifier);
+        assert_eq!(None, windows.paragraph_modifier);
+        assert_eq!(ModifierKeys::LOGO, macos.word_modifier);
+        assert_eq!(Some(ModifierKeys::ALT), macos.paragraph_modifier);
+        assert_eq!(windows, PlatformConventions::linux());
+    }
+
+    #[test]
+    fn edit_menu_model() {
+        let menu = PlatformConventions::macos().edit_menu_model(false);
+        let enabled = menu.items.iter().map(|item| item.enabled()).collect::<Vec<_>>();
+        // Only pasting works without a selection.
+        assert_eq!(vec![false, false, true], enabled);
+        assert_eq!(
+            MenuItem::Action {
+                id: "derin.edit.cut".to_string(),
+                label: "Cut".to_string(),
+                enabled: false,
+                shortcut: Some(KeyChord::new(ModifierKeys::ALT, Key::X)),
+            },
+            menu.items[0]
+        );
+
+        let menu = PlatformConventions::windows().edit_menu_model(true);
+        assert_eq!(6, menu.items.len());
+        assert_eq!(MenuItem::Separator, menu.items[4]);
+        assert!(menu.items.iter().filter(|item| **item != MenuItem::Separator).all(|item| item.enabled()));
+    }
+
+    #[test]
+    fn edit_action_ids() {
+        for &action in &[EditAction::Cut, EditAction::Copy, EditAction::Paste, EditAction::Delete, EditAction::SelectAll] {
+            assert_eq!(Some(action), EditAction::from_id(action.id()));
+        }
+        assert_eq!(None, EditAction::from_id("derin.edit.undo"));
+    }
+}
@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Selections shared between widgets.
+
+use crate::core::widget::{WidgetId, WidgetIdent};
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+
+/// A set of selected widgets, identified by their idents.
+///
+/// Cloning the model creates another handle to the same selection, so the widget that changes the
+/// selection and the widgets that display it can each hold onto a copy. Widgets that change the
+/// selection broadcast a [`SelectionChanged`] message afterwards.
+///
+/// [`SelectionChanged`]: ./struct.SelectionChanged.html
+#[derive(Debug, Clone, Default)]
+pub struct SelectionModel {
+    selected: Rc<RefCell<Vec<WidgetIdent>>>,
+}
+
+/// Message broadcast when a widget changes the contents of a `SelectionModel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionChanged {
+    /// The widget that changed the selection.
+    pub source: WidgetId,
+}
+
+impl SelectionModel {
+    /// Creates a new, empty selection.
+    pub fn new() -> SelectionModel {
+        SelectionModel::default()
+    }
+
+    /// Retrieves whether the widget with the given ident is selected.
+    pub fn is_selected(&self, ident: &WidgetIdent) -> bool {
+        self.selected.borrow().contains(ident)
+    }
+
+    /// Retrieves the selected widgets, in the order they were selected.
+    pub fn selected(&self) -> Vec<WidgetIdent> {
+        self.selected.borrow().clone()
+    }
+
+    /// Adds the widget to the selection, if it isn't already selected.
+    pub fn select(&self, ident: WidgetIdent) {
+        let mut selected = self.selected.borrow_mut();
+        if !selected.contains(&ident) {
+            selected.push(ident);
+        }
+    }
+
+    /// Removes the widget from the selection.
+    pub fn deselect(&self, ident: &WidgetIdent) {
+        self.selected.borrow_mut().retain(|i| i != ident);
+    }
+
+    /// Replaces the selection. Returns `true` if the selection changed.
+    pub fn set_selection(&self, idents: impl IntoIterator<Item=WidgetIdent>) -> bool {
+        let mut new_selection = Vec::new();
+        for ident in idents {
+            if !new_selection.contains(&ident) {
+                new_selection.push(ident);
+            }
+        }
+
+        let mut selected = self.selected.borrow_mut();
+        let changed = *selected != new_selection;
+        *selected = new_selection;
+        changed
+    }
+
+    /// Deselects every widget.
+    pub fn clear(&self) {
+        self.selected.borrow_mut().clear();
+    }
+}
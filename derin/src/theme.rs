@@ -7,6 +7,7 @@
 use png;
 use gullery::image_format::Rgba;
 
+use crate::cgmath::{Point2, Vector2, InnerSpace};
 use cgmath_geometry::{D2, rect::DimsBox};
 use derin_common_types::layout::{Align, Align2, Margins, SizeBounds};
 
@@ -19,11 +20,51 @@ use std::collections::hash_map::RandomState;
 
 
 pub use derin_common_types::cursor::CursorIcon;
+pub use crate::core::accessibility::AccessibilityPrefs;
+pub use crate::core::sound::UiSound;
 
 pub mod color {
     pub use gullery::image_format::Rgba;
+
+    /// The color space colors get blended and interpolated in.
+    ///
+    /// Theme colors are always given in sRGB. Blending the sRGB values directly is what most
+    /// applications do, but it darkens the midpoints of gradients and the anti-aliased edges of
+    /// translucent shapes. Blending in linear space avoids that.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ColorSpace {
+        Srgb,
+        Linear,
+    }
+
+    impl Default for ColorSpace {
+        fn default() -> ColorSpace {
+            ColorSpace::Srgb
+        }
+    }
+
+    /// Decode an sRGB-encoded channel into linear light, in the range `0.0..=1.0`.
+    pub fn srgb_to_linear(channel: u8) -> f32 {
+        let c = channel as f32 / 255.0;
+        match c <= 0.04045 {
+            true => c / 12.92,
+            false => ((c + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    /// Encode a linear channel, in the range `0.0..=1.0`, into sRGB.
+    pub fn linear_to_srgb(channel: f32) -> u8 {
+        let c = channel.max(0.0).min(1.0);
+        let encoded = match c <= 0.0031308 {
+            true => c * 12.92,
+            false => 1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (encoded * 255.0).round() as u8
+    }
 }
 
+use self::color::ColorSpace;
+
 /// An RGBA representation of an image.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Image {
@@ -47,6 +88,71 @@ pub enum RescaleRules {
     Align(Align2)
 }
 
+/// How to fill in a widget's background.
+///
+/// Points and radii used by the gradients are given as fractions of the widget's rectangle, with
+/// `(0.0, 0.0)` at the top-left corner and `(1.0, 1.0)` at the bottom-right corner.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Brush {
+    /// Fill the background with a single color.
+    Solid(Rgba<u8>),
+    /// Blend between the stops along the line from `start` to `end`.
+    LinearGradient {
+        start: Point2<f32>,
+        end: Point2<f32>,
+        stops: Rc<[GradientStop]>
+    },
+    /// Blend between the stops outwards from `center`, reaching the last stop at `radius`.
+    RadialGradient {
+        center: Point2<f32>,
+        radius: Vector2<f32>,
+        stops: Rc<[GradientStop]>
+    },
+    /// Draw an image, rescaled with the image's `RescaleRules`.
+    Image(Rc<Image>),
+    /// Repeat an image at its native size, starting from the top-left corner of the widget.
+    TiledImage(Rc<Image>),
+    /// Draw the background with a shader the renderer knows by the given name. Renderers that
+    /// don't recognize the name don't draw any background.
+    Shader(Rc<str>)
+}
+
+impl Brush {
+    /// The average relative luminance of the brush, from `0.0` for black to `1.0` for white.
+    /// Returns `None` for images and shaders.
+    fn luminance(&self) -> Option<f32> {
+        match *self {
+            Brush::Solid(color) => Some(luminance(color)),
+            Brush::LinearGradient{ref stops, ..} |
+            Brush::RadialGradient{ref stops, ..} if stops.len() > 0 =>
+                Some(stops.iter().map(|s| luminance(s.color)).sum::<f32>() / stops.len() as f32),
+            _ => None
+        }
+    }
+}
+
+/// The relative luminance of an sRGB color, from `0.0` for black to `1.0` for white.
+fn luminance(color: Rgba<u8>) -> f32 {
+    0.2126 * color::srgb_to_linear(color.r) +
+    0.7152 * color::srgb_to_linear(color.g) +
+    0.0722 * color::srgb_to_linear(color.b)
+}
+
+/// Whether white text contrasts more with a background of the given relative luminance than
+/// black text does, going by the WCAG contrast ratio.
+fn prefers_white_ink(background_luminance: f32) -> bool {
+    let contrast_ratio = |a: f32, b: f32| (a.max(b) + 0.05) / (a.min(b) + 0.05);
+    contrast_ratio(background_luminance, 1.0) > contrast_ratio(background_luminance, 0.0)
+}
+
+/// A color at a given position along a gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// The position of the stop, where `0.0` is the start of the gradient and `1.0` is the end.
+    pub offset: f32,
+    pub color: Rgba<u8>
+}
+
 /// The algorithm used to determine where line breaks occur in text.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LineWrap {
@@ -56,6 +162,45 @@ pub enum LineWrap {
     Normal
 }
 
+/// How glyph edges get smoothed when text is rasterized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextAntialiasing {
+    /// Smooth glyph edges with partially transparent pixels.
+    Grayscale,
+    /// Draw every pixel as either fully covered or fully transparent. Use this for bitmap and pixel
+    /// fonts, which would otherwise get blurred.
+    Aliased,
+    /// Smooth glyph edges using the display's individual subpixels. Renderers that can't draw
+    /// subpixel text fall back to `Grayscale`.
+    Subpixel
+}
+
+impl Default for TextAntialiasing {
+    fn default() -> TextAntialiasing {
+        TextAntialiasing::Grayscale
+    }
+}
+
+impl TextAntialiasing {
+    /// Whether glyphs get rasterized into 1-bit monochrome bitmaps. Renderers that only store
+    /// single-channel coverage draw `Subpixel` text as `Grayscale`.
+    pub fn is_monochrome(self) -> bool {
+        self == TextAntialiasing::Aliased
+    }
+
+    /// Read the coverage of pixel `x` from a row of a glyph bitmap rasterized with this mode,
+    /// from `0` (transparent) to `255` (fully covered).
+    ///
+    /// Monochrome bitmaps pack eight pixels into each byte, most significant bit first. The
+    /// others store a byte per pixel.
+    pub fn coverage(self, row: &[u8], x: usize) -> u8 {
+        match self.is_monochrome() {
+            true => ((row[x / 8] >> (7 - x % 8)) & 1) * 255,
+            false => row[x]
+        }
+    }
+}
+
 /// Collection of information used to determine how to render text in a widget.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ThemeText {
@@ -78,14 +223,16 @@ pub struct ThemeText {
     /// The number of pixels on the sides of a draw box in which text shouldn't be drawn.
     pub margins: Margins<u16>,
     /// The line wrapping algorithm.
-    pub line_wrap: LineWrap
+    pub line_wrap: LineWrap,
+    /// How the edges of glyphs are smoothed.
+    pub antialiasing: TextAntialiasing
 }
 
-/// The text style and image used to draw a widget with a given style.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The text style and background used to draw a widget with a given style.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ThemeWidget {
     pub text: Option<ThemeText>,
-    pub image: Option<Rc<Image>>,
+    pub background: Option<Brush>,
     pub content_margins: Margins<u16>,
 }
 
@@ -110,8 +257,13 @@ pub struct ThemeFaceBuffer {
     fingerprint: u64,
 }
 
+#[derive(Clone)]
 pub struct Theme {
-    map: HashMap<String, ThemeWidget>
+    map: HashMap<String, ThemeWidget>,
+    sounds: HashMap<UiSound, Rc<Path>>,
+    scroll_line_height: i32,
+    highlight_colors: HashMap<String, Rgba<u8>>,
+    disabled_opacity: f32,
 }
 
 
@@ -137,6 +289,77 @@ lazy_static!{
     static ref FINGERPRINT_STATE: RandomState = RandomState::new();
 }
 
+impl Brush {
+    /// Get the color of a gradient or solid brush at the given point, in the coordinates described
+    /// in the type-level docs. Returns `None` for image and shader brushes.
+    pub fn color_at(&self, point: Point2<f32>) -> Option<Rgba<u8>> {
+        self.color_at_in(point, ColorSpace::Srgb)
+    }
+
+    /// Like `color_at`, but interpolating between gradient stops in the given color space.
+    pub fn color_at_in(&self, point: Point2<f32>, color_space: ColorSpace) -> Option<Rgba<u8>> {
+        match *self {
+            Brush::Solid(color) => Some(color),
+            Brush::LinearGradient{start, end, ref stops} => {
+                let line = end - start;
+                let t = match line.magnitude2() {
+                    len2 if len2 > 0.0 => (point - start).dot(line) / len2,
+                    _ => 0.0
+                };
+                Some(sample_stops(stops, t, color_space))
+            },
+            Brush::RadialGradient{center, radius, ref stops} => {
+                let offset = point - center;
+                let scaled = Vector2::new(
+                    if radius.x > 0.0 {offset.x / radius.x} else {0.0},
+                    if radius.y > 0.0 {offset.y / radius.y} else {0.0}
+                );
+                Some(sample_stops(stops, scaled.magnitude(), color_space))
+            },
+            Brush::Image(_) |
+            Brush::TiledImage(_) |
+            Brush::Shader(_) => None
+        }
+    }
+}
+
+/// Linearly interpolate the color at `t` between the stops, which are assumed to be sorted by
+/// offset.
+fn sample_stops(stops: &[GradientStop], t: f32, color_space: ColorSpace) -> Rgba<u8> {
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Rgba::new(0, 0, 0, 0)
+    };
+    if t <= first.offset {
+        return first.color;
+    }
+
+    for pair in stops.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if t <= b.offset {
+            let frac = match b.offset - a.offset {
+                span if span > 0.0 => (t - a.offset) / span,
+                _ => 1.0
+            };
+            let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * frac).round() as u8;
+            let lerp_color = |a: u8, b: u8| match color_space {
+                ColorSpace::Srgb => lerp(a, b),
+                ColorSpace::Linear => {
+                    let (a, b) = (color::srgb_to_linear(a), color::srgb_to_linear(b));
+                    color::linear_to_srgb(a + (b - a) * frac)
+                }
+            };
+            return Rgba::new(
+                lerp_color(a.color.r, b.color.r),
+                lerp_color(a.color.g, b.color.g),
+                lerp_color(a.color.b, b.color.b),
+                lerp(a.color.a, b.color.a)
+            );
+        }
+    }
+    last.color
+}
+
 impl ThemeFacePath {
     /// Create a new face, referencing the font file at the provided path.
     #[inline]
@@ -209,7 +432,11 @@ impl ThemeFaceBuffer {
 impl Theme {
     pub fn empty() -> Theme {
         Theme {
-            map: HashMap::new()
+            map: HashMap::new(),
+            sounds: HashMap::new(),
+            scroll_line_height: crate::core::event::SCROLL_LINE_HEIGHT,
+            highlight_colors: HashMap::new(),
+            disabled_opacity: 0.5,
         }
     }
 
@@ -217,11 +444,91 @@ impl Theme {
         self.map.insert(key, theme)
     }
 
+    /// Declare the sound file played for the given interface event.
+    pub fn insert_sound<P: AsRef<Path>>(&mut self, sound: UiSound, path: P) -> Option<Rc<Path>> {
+        self.sounds.insert(sound, Rc::from(path.as_ref()))
+    }
+
+    /// Retrieves the sound file played for the given interface event, if the theme declares one.
+    pub fn sound(&self, sound: UiSound) -> Option<&Path> {
+        self.sounds.get(&sound).map(|path| &**path)
+    }
+
+    /// Declare the color of text highlighted with the given style class, such as `"keyword"`.
+    pub fn insert_highlight_color(&mut self, class: String, color: Rgba<u8>) -> Option<Rgba<u8>> {
+        self.highlight_colors.insert(class, color)
+    }
+
+    /// Retrieves the color of text highlighted with the given style class. Text in classes without a
+    /// color gets drawn with the widget's normal text color.
+    pub fn highlight_color(&self, class: &str) -> Option<Rgba<u8>> {
+        self.highlight_colors.get(class).cloned()
+    }
+
+    /// The number of pixels scrolled by one line of mouse wheel movement.
+    pub fn scroll_line_height(&self) -> i32 {
+        self.scroll_line_height
+    }
+
+    /// Set the number of pixels scrolled by one line of mouse wheel movement. This should usually
+    /// be close to the height of a line of body text.
+    pub fn set_scroll_line_height(&mut self, line_height: i32) {
+        self.scroll_line_height = line_height;
+    }
+
+    /// The opacity disabled widgets get drawn with, on top of their `Opacity` ambient value.
+    pub fn disabled_opacity(&self) -> f32 {
+        self.disabled_opacity
+    }
+
+    /// Set the opacity disabled widgets get drawn with. `1.0` draws them the same as enabled
+    /// widgets.
+    pub fn set_disabled_opacity(&mut self, opacity: f32) {
+        self.disabled_opacity = opacity;
+    }
+
+    /// Create a copy of the theme that follows the user's accessibility preferences.
+    ///
+    /// All text gets scaled by `text_scale`. In high contrast mode, text is drawn in either pure
+    /// black or pure white, whichever contrasts more with the widget's background, and highlighted
+    /// text is drawn with the two colors swapped.
+    ///
+    /// `derin` doesn't read these preferences from the operating system, so applications have to
+    /// fill in the `AccessibilityPrefs` themselves.
+    ///
+    /// The theme this is called on is left untouched. When the preferences change, derive a new
+    /// theme from the original rather than from a previously derived one, since the text scale
+    /// would otherwise get applied twice.
+    pub fn with_accessibility(&self, prefs: &AccessibilityPrefs) -> Theme {
+        let mut theme = self.clone();
+        for widget in theme.map.values_mut() {
+            let background = widget.background.as_ref();
+            if let Some(ref mut text) = widget.text {
+                text.face_size = (text.face_size as f32 * prefs.text_scale).round() as u32;
+                if prefs.high_contrast {
+                    // Widgets without a plain background get drawn over their parent, so the
+                    // theme's text color is the best guess of what's behind them.
+                    let dark_background = background.and_then(Brush::luminance)
+                        .map(prefers_white_ink)
+                        .unwrap_or(!prefers_white_ink(luminance(text.color)));
+                    let (ink, paper) = match dark_background {
+                        true => (Rgba::new(255, 255, 255, 255), Rgba::new(0, 0, 0, 255)),
+                        false => (Rgba::new(0, 0, 0, 255), Rgba::new(255, 255, 255, 255))
+                    };
+                    text.color = ink;
+                    text.highlight_bg_color = ink;
+                    text.highlight_text_color = paper;
+                }
+            }
+        }
+        theme
+    }
+
     pub fn widget_theme(&self, path: &str) -> ThemeWidget {
         self.map.get(path).cloned().unwrap_or(
             ThemeWidget {
                 text: None,
-                image: None,
+                background: None,
                 content_margins: Margins::default(),
             }
         )
@@ -272,9 +579,10 @@ impl Default for Theme {
                             tab_size: 8,
                             justify: $text_align,
                             margins: Margins::new($border, $border, $border, $border),
-                            line_wrap: LineWrap::None
+                            line_wrap: LineWrap::None,
+                            antialiasing: TextAntialiasing::Grayscale
                         }),
-                        image: Some(Rc::new(Image {
+                        background: Some(Brush::Image(Rc::new(Image {
                             pixels: image_buf!($path),
                             dims: DimsBox::new2($dims.0, $dims.1),
                             rescale: RescaleRules::Slice(Margins::new($border, $border, $border, $border)),
@@ -282,7 +590,7 @@ impl Default for Theme {
                                 min: DimsBox::new2($border * 2, $border * 2),
                                 ..SizeBounds::default()
                             }
-                        })),
+                        }))),
                         content_margins: Margins::default(),
                     }
                 );
@@ -295,11 +603,27 @@ impl Default for Theme {
         upload_image!("Button::Pressed", "./default_theme_resources/button/pressed.png", (16, 16), 4, Align2::new(Align::Center, Align::Center));
         upload_image!("ScrollBar", "./default_theme_resources/scroll_bar.png", (3, 3), 1, Align2::new(Align::Center, Align::Center));
         upload_image!("ScrollBackground", "./default_theme_resources/scroll_bg.png", (3, 3), 1, Align2::new(Align::Center, Align::Center));
+        theme.insert_widget(
+            "Group::Marquee".to_string(),
+            ThemeWidget {
+                text: None,
+                background: Some(Brush::Solid(Rgba::new(51, 153, 255, 64))),
+                content_margins: Margins::default(),
+            }
+        );
+        theme.insert_widget(
+            "Splitter".to_string(),
+            ThemeWidget {
+                text: None,
+                background: Some(Brush::Solid(Rgba::new(200, 200, 200, 255))),
+                content_margins: Margins::default(),
+            }
+        );
         theme.insert_widget(
             "Slider::Bar".to_string(),
             ThemeWidget {
                 text: None,
-                image: Some(Rc::new(Image {
+                background: Some(Brush::Image(Rc::new(Image {
                     pixels: image_buf!("./default_theme_resources/slider_bar.png"),
                     dims: DimsBox::new2(32, 8),
                     rescale: RescaleRules::Slice(Margins::new(4, 4, 4, 4)),
@@ -307,7 +631,7 @@ impl Default for Theme {
                         min: DimsBox::new2(32, 8),
                         max: DimsBox::new2(i32::max_value(), 8)
                     }
-                })),
+                }))),
                 content_margins: Margins::default(),
             }
         );
@@ -315,7 +639,7 @@ impl Default for Theme {
             "Slider::Head".to_string(),
             ThemeWidget {
                 text: None,
-                image: Some(Rc::new(Image {
+                background: Some(Brush::Image(Rc::new(Image {
                     pixels: image_buf!("./default_theme_resources/slider_head.png"),
                     dims: DimsBox::new2(8, 16),
                     rescale: RescaleRules::Align(Align2::new(Align::Center, Align::Center)),
@@ -323,7 +647,7 @@ impl Default for Theme {
                         min: DimsBox::new2(8, 16),
                         ..SizeBounds::default()
                     }
-                })),
+                }))),
                 content_margins: Margins::default(),
             }
         );
@@ -339,9 +663,10 @@ impl Default for Theme {
                     tab_size: 8,
                     justify: Align2::new(Align::Center, Align::Start),
                     margins: Margins::default(),
-                    line_wrap: LineWrap::Normal
+                    line_wrap: LineWrap::Normal,
+                    antialiasing: TextAntialiasing::Grayscale
                 }),
-                image: None,
+                background: None,
                 content_margins: Margins::default(),
             }
         );
@@ -357,9 +682,10 @@ impl Default for Theme {
                     tab_size: 8,
                     justify: Align2::new(Align::Start, Align::Center),
                     margins: Margins::new(18, 0, 0, 0),
-                    line_wrap: LineWrap::None
+                    line_wrap: LineWrap::None,
+                    antialiasing: TextAntialiasing::Grayscale
                 }),
-                image: None,
+                background: None,
                 content_margins: Margins::default(),
             }
         );
@@ -369,7 +695,7 @@ impl Default for Theme {
                     concat!("CheckBox::", $name).to_string(),
                     ThemeWidget {
                         text: None,
-                        image: Some(Rc::new(Image {
+                        background: Some(Brush::Image(Rc::new(Image {
                             pixels: image_buf!($path),
                             dims: DimsBox::new2(16, 16),
                             rescale: RescaleRules::Align(Align2::new(Align::Start, Align::Center)),
@@ -377,7 +703,7 @@ impl Default for Theme {
                                 min: DimsBox::new2(16, 16),
                                 ..SizeBounds::default()
                             }
-                        })),
+                        }))),
                         content_margins: Margins::default(),
                     }
                 );
@@ -401,9 +727,10 @@ impl Default for Theme {
                     tab_size: 8,
                     justify: Align2::new(Align::Start, Align::Center),
                     margins: Margins::new(18, 0, 0, 0),
-                    line_wrap: LineWrap::None
+                    line_wrap: LineWrap::None,
+                    antialiasing: TextAntialiasing::Grayscale
                 }),
-                image: None,
+                background: None,
                 content_margins: Margins::default(),
             }
         );
@@ -413,7 +740,7 @@ impl Default for Theme {
                     concat!("RadioButton::", $name).to_string(),
                     ThemeWidget {
                         text: None,
-                        image: Some(Rc::new(Image {
+                        background: Some(Brush::Image(Rc::new(Image {
                             pixels: image_buf!($path),
                             dims: DimsBox::new2(16, 16),
                             rescale: RescaleRules::Align(Align2::new(Align::Start, Align::Center)),
@@ -421,7 +748,7 @@ impl Default for Theme {
                                 min: DimsBox::new2(16, 16),
                                 ..SizeBounds::default()
                             }
-                        })),
+                        }))),
                         content_margins: Margins::default(),
                     }
                 );
@@ -440,7 +767,7 @@ impl Default for Theme {
                     concat!("ProgressBar::", $name).to_string(),
                     ThemeWidget {
                         text: None,
-                        image: Some(Rc::new(Image {
+                        background: Some(Brush::Image(Rc::new(Image {
                             pixels: image_buf!($path),
                             dims: DimsBox::new2(16, 16),
                             rescale: RescaleRules::Slice(Margins::new(3, 2, 3, 2)),
@@ -448,7 +775,7 @@ impl Default for Theme {
                                 min: $min,
                                 ..SizeBounds::default()
                             }
-                        })),
+                        }))),
                         content_margins: Margins::default(),
                     }
                 );
@@ -471,9 +798,10 @@ impl Default for Theme {
                             tab_size: 8,
                             justify: Align2::new(Align::Center, Align::Center),
                             margins: Margins::new(4, 4, 4, 4),
-                            line_wrap: LineWrap::None
+                            line_wrap: LineWrap::None,
+                            antialiasing: TextAntialiasing::Grayscale
                         }),
-                        image: Some(Rc::new(Image {
+                        background: Some(Brush::Image(Rc::new(Image {
                             pixels: image_buf!($path),
                             dims: DimsBox::new2(16, 8),
                             rescale: RescaleRules::Slice(Margins::new(4, 4, 4, 0)),
@@ -481,7 +809,7 @@ impl Default for Theme {
                                 min: DimsBox::new2(8, 4),
                                 ..SizeBounds::default()
                             }
-                        })),
+                        }))),
                         content_margins: Margins::default(),
                     }
                 );
@@ -502,9 +830,10 @@ impl Default for Theme {
                     tab_size: 8,
                     justify: Align2::new(Align::Start, Align::Start),
                     margins: Margins::new(3, 3, 3, 3),
-                    line_wrap: LineWrap::Normal
+                    line_wrap: LineWrap::Normal,
+                    antialiasing: TextAntialiasing::Grayscale
                 }),
-                image: Some(Rc::new(Image {
+                background: Some(Brush::Image(Rc::new(Image {
                     pixels: image_buf!("./default_theme_resources/editbox.png"),
                     dims: DimsBox::new2(8, 8),
                     rescale: RescaleRules::Slice(Margins::new(3, 3, 3, 3)),
@@ -512,7 +841,7 @@ impl Default for Theme {
                         min: DimsBox::new2(3 * 2, 3 * 2),
                         ..SizeBounds::default()
                     }
-                })),
+                }))),
                 content_margins: Margins::default(),
             }
         );
@@ -528,9 +857,10 @@ impl Default for Theme {
                     tab_size: 8,
                     justify: Align2::new(Align::Start, Align::Start),
                     margins: Margins::new(3, 3, 3, 3),
-                    line_wrap: LineWrap::None
+                    line_wrap: LineWrap::None,
+                    antialiasing: TextAntialiasing::Grayscale
                 }),
-                image: Some(Rc::new(Image {
+                background: Some(Brush::Image(Rc::new(Image {
                     pixels: image_buf!("./default_theme_resources/editbox.png"),
                     dims: DimsBox::new2(8, 8),
                     rescale: RescaleRules::Slice(Margins::new(3, 3, 3, 3)),
@@ -538,7 +868,7 @@ impl Default for Theme {
                         min: DimsBox::new2(3 * 2, 3 * 2),
                         ..SizeBounds::default()
                     }
-                })),
+                }))),
                 content_margins: Margins::default(),
             }
         );
@@ -558,3 +888,74 @@ impl Image {
         // }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_contrast_ink() {
+        let label = Theme::default().widget_theme("Label");
+        let text_widget = |background: Rgba<u8>| ThemeWidget {
+            text: Some(ThemeText {
+                color: Rgba::new(0, 0, 0, 255),
+                ..label.text.clone().unwrap()
+            }),
+            background: Some(Brush::Solid(background)),
+            ..label.clone()
+        };
+        let mut theme = Theme::empty();
+        // Relative luminances of about 0.22 and 0.13, on either side of the 0.18 where black and
+        // white contrast equally.
+        theme.insert_widget("mid_grey".to_string(), text_widget(Rgba::new(128, 128, 128, 255)));
+        theme.insert_widget("dark_grey".to_string(), text_widget(Rgba::new(100, 100, 100, 255)));
+
+        let prefs = AccessibilityPrefs {
+            high_contrast: true,
+            ..AccessibilityPrefs::default()
+        };
+        let theme = theme.with_accessibility(&prefs);
+        let text_color = |path| theme.widget_theme(path).text.unwrap().color;
+        assert_eq!(Rgba::new(0, 0, 0, 255), text_color("mid_grey"));
+        assert_eq!(Rgba::new(255, 255, 255, 255), text_color("dark_grey"));
+    }
+
+    #[test]
+    fn glyph_coverage() {
+        let mono_row = [0b1010_0000, 0b0000_0001];
+        let coverage = |antialiasing: TextAntialiasing, row: &[u8], width| (0..width)
+            .map(|x| antialiasing.coverage(row, x))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![255, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255], coverage(TextAntialiasing::Aliased, &mono_row, 16));
+        assert_eq!(vec![160, 1], coverage(TextAntialiasing::Grayscale, &mono_row, 2));
+        assert_eq!(vec![160, 1], coverage(TextAntialiasing::Subpixel, &mono_row, 2));
+    }
+
+    #[test]
+    fn srgb_round_trip() {
+        for channel in 0..=255 {
+            assert_eq!(channel, color::linear_to_srgb(color::srgb_to_linear(channel)));
+        }
+        assert_eq!(0.0, color::srgb_to_linear(0));
+        assert_eq!(1.0, color::srgb_to_linear(255));
+    }
+
+    #[test]
+    fn gradient_color_space() {
+        let brush = Brush::LinearGradient {
+            start: Point2::new(0.0, 0.0),
+            end: Point2::new(1.0, 0.0),
+            stops: Rc::from(&[
+                GradientStop{ offset: 0.0, color: Rgba::new(0, 0, 0, 0) },
+                GradientStop{ offset: 1.0, color: Rgba::new(255, 255, 255, 255) },
+            ][..])
+        };
+        let midpoint = Point2::new(0.5, 0.0);
+
+        assert_eq!(Some(Rgba::new(128, 128, 128, 128)), brush.color_at(midpoint));
+        // Halfway to white in linear light is a lot brighter once it's encoded back into sRGB.
+        // Alpha is always interpolated linearly.
+        assert_eq!(Some(Rgba::new(188, 188, 188, 128)), brush.color_at_in(midpoint, ColorSpace::Linear));
+    }
+}
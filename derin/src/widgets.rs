@@ -3,38 +3,65 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 //! The core set of widgets provided by Derin to create GUIs.
+//!
+//! Every widget here has a page in the `derin-gallery` example (`examples/gallery`), which should be
+//! updated alongside the widget. New widgets must register a page in `examples/gallery/pages.rs`.
 
 #[macro_use]
 pub mod assistants;
 mod button;
 mod check_box;
 mod clip;
+mod cross_fade;
+mod dialog;
 // mod direct_render;
 mod edit_box;
 mod group;
+mod help_viewer;
 mod label;
+mod menu;
+mod overflow;
+mod pan_zoom_view;
+mod popup_layer;
 mod progress_bar;
 mod radio_buttons;
+mod scroll_bar;
+mod shortcut_recorder;
 // mod scroll_box;
 mod slider;
+mod splitter;
+mod stats_overlay;
 // mod tabs;
 
 pub use self::button::*;
 pub use self::check_box::*;
 pub use self::clip::*;
+pub use self::cross_fade::*;
+pub use self::dialog::*;
 // pub use self::direct_render::*;
 pub use self::edit_box::*;
 pub use self::group::*;
+pub use self::help_viewer::*;
 pub use self::label::*;
+pub use self::menu::*;
+pub use self::overflow::*;
+pub use self::pan_zoom_view::*;
+pub use self::popup_layer::*;
 pub use self::progress_bar::*;
 pub use self::radio_buttons::*;
+pub use self::scroll_bar::*;
+pub use self::shortcut_recorder::*;
 // pub use self::scroll_box::*;
 pub use self::slider::*;
+pub use self::splitter::*;
+pub use self::stats_overlay::*;
 // pub use self::tabs::*;
 
 /// The `Widget` trait, as well as associated types used to create custom widgets.
 pub mod custom {
     pub use crate::core::widget::{WidgetTag, Widget, Parent, WidgetSubtype, WidgetInfo, WidgetInfoMut, WidgetIdent};
+    pub use crate::core::handle::WidgetHandle;
+    pub use crate::core::rate_limit::{Debounce, Throttle, DistinctUntilChanged};
 }
 
 /// What should be drawn inside of a label, or other widgets that contains a label.
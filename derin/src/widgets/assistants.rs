@@ -2,6 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+pub mod mnemonic;
 mod slider;
 pub mod text_edit;
 pub mod toggle_button;
@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Parsing and layout for mnemonic strings, such as `"&File"`.
+//!
+//! The character after the first unescaped `&` becomes the string's mnemonic, and a literal `&`
+//! can be written as `&&`.
+
+use derin_core::{
+    render::RendererLayout,
+    widget::WidgetTag,
+};
+use derin_common_types::buttons::Key;
+use std::ops::Range;
+
+/// A string, with its mnemonic markers removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MnemonicString {
+    pub text: String,
+    pub mnemonic: Option<Mnemonic>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    /// The key that activates the mnemonic.
+    pub key: Key,
+    /// The byte range of the mnemonic character in the marker-free text.
+    pub range: Range<usize>,
+}
+
+impl MnemonicString {
+    pub fn parse(s: &str) -> MnemonicString {
+        let mut text = String::with_capacity(s.len());
+        let mut mnemonic = None;
+
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '&' {
+                text.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('&') => text.push('&'),
+                Some(c) => {
+                    if mnemonic.is_none() {
+                        mnemonic = Key::from_char(c).map(|key| Mnemonic {
+                            key,
+                            range: text.len()..text.len() + c.len_utf8(),
+                        });
+                    }
+                    text.push(c);
+                },
+                None => text.push('&'),
+            }
+        }
+
+        MnemonicString{ text, mnemonic }
+    }
+}
+
+/// Lay out a string that may contain a mnemonic, registering the mnemonic on `widget_tag` and
+/// underlining it if mnemonics are visible.
+///
+/// The underline only shows up with renderers that implement
+/// `RendererLayout::prepare_string_underlined`. The GL renderer doesn't yet, so it draws the text
+/// without one.
+pub fn prepare_mnemonic_string<L>(layout: &mut L, widget_tag: &mut WidgetTag, string: &str)
+    where L: RendererLayout
+{
+    let MnemonicString{ text, mnemonic } = MnemonicString::parse(string);
+    widget_tag.set_mnemonic(mnemonic.as_ref().map(|m| m.key));
+
+    match mnemonic {
+        Some(Mnemonic{ range, .. }) if widget_tag.mnemonics_visible() => layout.prepare_string_underlined(&text, range),
+        _ => layout.prepare_string(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            MnemonicString {
+                text: "File".to_string(),
+                mnemonic: Some(Mnemonic{ key: Key::F, range: 0..1 }),
+            },
+            MnemonicString::parse("&File")
+        );
+        assert_eq!(
+            MnemonicString {
+                text: "Save as".to_string(),
+                mnemonic: Some(Mnemonic{ key: Key::A, range: 5..6 }),
+            },
+            MnemonicString::parse("Save &as")
+        );
+        assert_eq!(
+            MnemonicString {
+                text: "Name".to_string(),
+                mnemonic: None,
+            },
+            MnemonicString::parse("Name")
+        );
+    }
+
+    #[test]
+    fn parse_escapes() {
+        // `&&` is a literal `&`, and only the first marker counts.
+        assert_eq!(
+            MnemonicString {
+                text: "Cut & Paste".to_string(),
+                mnemonic: Some(Mnemonic{ key: Key::P, range: 6..7 }),
+            },
+            MnemonicString::parse("Cut && &Paste")
+        );
+        assert_eq!(
+            MnemonicString {
+                text: "Copy".to_string(),
+                mnemonic: Some(Mnemonic{ key: Key::C, range: 0..1 }),
+            },
+            MnemonicString::parse("&Co&py")
+        );
+        // A trailing `&` has nothing to mark, so it's kept as-is.
+        assert_eq!(
+            MnemonicString {
+                text: "Rock &".to_string(),
+                mnemonic: None,
+            },
+            MnemonicString::parse("Rock &")
+        );
+    }
+
+    #[test]
+    fn parse_non_key_mnemonic() {
+        // Characters without a key don't become mnemonics, so a later marker gets used instead. The
+        // range is in bytes, so it accounts for the two-byte `É` in front of it.
+        assert_eq!(
+            MnemonicString {
+                text: "Éditer".to_string(),
+                mnemonic: Some(Mnemonic{ key: Key::I, range: 3..4 }),
+            },
+            MnemonicString::parse("&Éd&iter")
+        );
+    }
+}
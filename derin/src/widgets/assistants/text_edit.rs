@@ -4,7 +4,10 @@
 
 use crate::{
     event::{Key, ModifierKeys, WidgetEvent, FocusChange, InputState, MouseHoverChange},
+    highlight::DynHighlightCache,
+    platform::{EditAction, PlatformConventions, TextUnit},
     theme::CursorIcon,
+    widgets::Gutter,
 };
 use clipboard::{ClipboardContext, ClipboardProvider};
 use cgmath_geometry::line::Segment;
@@ -55,6 +58,24 @@ pub struct TextEditOps {
     pub focus: Option<FocusChange>,
     pub cursor_flash: Option<CursorFlashOp>,
     pub cursor_icon: Option<CursorIcon>,
+    /// Whether the widget should open its context menu.
+    pub context_menu: bool,
+}
+
+/// Tracks the context menu opened by the text widget, so that clicking the menu doesn't take focus
+/// away from the widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContextMenuState {
+    Closed,
+    Open,
+    /// The menu has been clicked, either to pick an item or to dismiss it.
+    Clicked,
+}
+
+impl Default for ContextMenuState {
+    fn default() -> ContextMenuState {
+        ContextMenuState::Closed
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -65,12 +86,22 @@ pub struct TextEditAssist<C = DefaultCharFilter>
     pub cursor_data: CursorData,
     pub cursor_ops: Vec<CursorOp>,
     pub filter: C,
+    /// Allow adding carets by clicking and pressing `D` with the command modifier held.
+    pub multi_caret: bool,
+    pub(crate) highlight: Option<Box<DynHighlightCache>>,
+    pub(crate) gutter: Option<Gutter>,
+    /// Whether the gutter was clicked. The clicked line is found by moving the caret to it, which
+    /// only the renderer can do, so the click gets handled once the box has been laid out.
+    pub(crate) gutter_clicked: bool,
+    /// The unit selected by the click that started the current drag.
+    pub(crate) drag_unit: Option<TextUnit>,
+    pub(crate) context_menu: ContextMenuState,
 }
 
 impl<C> TextEditAssist<C>
     where C: CharFilter
 {
-    pub fn adapt_event(&mut self, event: &WidgetEvent, input_state: InputState) -> TextEditOps {
+    pub fn adapt_event(&mut self, event: &WidgetEvent, input_state: InputState, conventions: &PlatformConventions) -> TextEditOps {
         use self::WidgetEvent::*;
         use derin_common_types::buttons::MouseButton;
 
@@ -79,60 +110,82 @@ impl<C> TextEditAssist<C>
         let mut allow_bubble = true;
         let mut redraw = false;
         let mut cursor_flash = None;
+        let mut context_menu = false;
+
+        match *event {
+            KeyDown(..) |
+            Char(..) |
+            MouseDown{in_widget: true, ..} |
+            LoseFocus => self.context_menu = ContextMenuState::Closed,
+            _ => ()
+        }
 
         match *event {
             KeyDown(key, modifiers) => loop {
                 allow_bubble = false;
-                let jump_to_word_boundaries = modifiers.contains(ModifierKeys::CTRL);
-                match (key, modifiers) {
-                    (Key::LArrow, _) => self.cursor_ops.push(CursorOp::MoveHorizontal {
+                let expand_selection = modifiers.contains(ModifierKeys::SHIFT);
+                let command = modifiers == conventions.command_modifier;
+                let jump_to_word_boundaries = modifiers.contains(conventions.word_modifier);
+                let jump_to_paragraph_boundaries = conventions.paragraph_modifier.map(|m| modifiers.contains(m)).unwrap_or(false);
+                let move_to_boundary = |unit, forward| CursorOp::MoveToBoundary{ unit, forward, expand_selection };
+                match key {
+                    Key::LArrow if jump_to_paragraph_boundaries => self.cursor_ops.push(move_to_boundary(TextUnit::Paragraph, false)),
+                    Key::RArrow if jump_to_paragraph_boundaries => self.cursor_ops.push(move_to_boundary(TextUnit::Paragraph, true)),
+                    Key::UArrow if jump_to_paragraph_boundaries => self.cursor_ops.push(move_to_boundary(TextUnit::Document, false)),
+                    Key::DArrow if jump_to_paragraph_boundaries => self.cursor_ops.push(move_to_boundary(TextUnit::Document, true)),
+                    Key::LArrow => self.cursor_ops.push(CursorOp::MoveHorizontal {
                         delta: -1,
-                        expand_selection: modifiers.contains(ModifierKeys::SHIFT),
+                        expand_selection,
                         jump_to_word_boundaries,
                     }),
-                    (Key::RArrow, _) => self.cursor_ops.push(CursorOp::MoveHorizontal {
+                    Key::RArrow => self.cursor_ops.push(CursorOp::MoveHorizontal {
                         delta: 1,
-                        expand_selection: modifiers.contains(ModifierKeys::SHIFT),
+                        expand_selection,
                         jump_to_word_boundaries,
                     }),
-                    (Key::UArrow, _) => self.cursor_ops.push(CursorOp::MoveVertical {
+                    Key::UArrow => self.cursor_ops.push(CursorOp::MoveVertical {
                         delta: -1,
-                        expand_selection: modifiers.contains(ModifierKeys::SHIFT),
+                        expand_selection,
                     }),
-                    (Key::DArrow, _) => self.cursor_ops.push(CursorOp::MoveVertical {
+                    Key::DArrow => self.cursor_ops.push(CursorOp::MoveVertical {
                         delta: 1,
-                        expand_selection: modifiers.contains(ModifierKeys::SHIFT),
+                        expand_selection,
                     }),
-                    (Key::A, ModifierKeys::CTRL) => self.cursor_ops.push(CursorOp::SelectAll),
+                    Key::Home |
+                    Key::End => {
+                        let unit = match modifiers.contains(conventions.command_modifier) {
+                            true => TextUnit::Document,
+                            false => conventions.home_end
+                        };
+                        self.cursor_ops.push(move_to_boundary(unit, key == Key::End));
+                    },
+                    Key::A if command => self.cursor_ops.push(CursorOp::SelectAll),
+                    Key::D if command && self.multi_caret => self.cursor_ops.push(CursorOp::SelectNextOccurrence),
+                    Key::Escape if !self.cursor_data.extra_carets.is_empty() => self.cursor_ops.push(CursorOp::RemoveExtraCarets),
+                    // Let `Enter` and `Escape` bubble up to the focus scope, so they can activate
+                    // its default and cancel buttons. `Enter` is kept if it inserts a newline.
+                    Key::Escape => {
+                        allow_bubble = true;
+                        break;
+                    },
+                    Key::Enter if !self.filter.char_allowed('\n') => {
+                        allow_bubble = true;
+                        break;
+                    },
 
                     // This implementation has a bug - if any `CursorOp`s has been submitted earlier in
                     // the same frame that produced these cut/copy/paste events, the ops will be ignored
                     // when performing the clipboard operation. However, as far as I can tell the only
                     // way to fix that is to add `Cut`/`Copy`/`Paste` events to `CursorOp`, which I'm
                     // presently against.
-                    (Key::C, ModifierKeys::CTRL) => {
-                        if let Ok(mut clipboard) = ClipboardContext::new() {
-                            let new_contents = self.string[self.cursor_data.highlight_range.clone()].to_string();
-                            clipboard.set_contents(new_contents).ok();
-                        }
-                    },
-                    (Key::V, ModifierKeys::CTRL) => {
-                        if let Ok(clipboard_contents) = ClipboardContext::new().and_then(|mut c| c.get_contents()) {
-                            self.cursor_ops.push(CursorOp::InsertString(clipboard_contents));
-                        }
-                    },
-                    (Key::X, ModifierKeys::CTRL) => {
-                        if let Ok(mut clipboard) = ClipboardContext::new() {
-                            let new_contents = self.string[self.cursor_data.highlight_range.clone()].to_string();
-                            clipboard.set_contents(new_contents).ok();
-                            self.cursor_ops.push(CursorOp::DeleteSelection);
-                        }
-                    },
-                    (Key::Back, _) => self.cursor_ops.push(CursorOp::DeleteChars {
+                    Key::C if command => self.perform(EditAction::Copy),
+                    Key::V if command => self.perform(EditAction::Paste),
+                    Key::X if command => self.perform(EditAction::Cut),
+                    Key::Back => self.cursor_ops.push(CursorOp::DeleteChars {
                         dist: -1,
                         jump_to_word_boundaries,
                     }),
-                    (Key::Delete, _) => self.cursor_ops.push(CursorOp::DeleteChars {
+                    Key::Delete => self.cursor_ops.push(CursorOp::DeleteChars {
                         dist: 1,
                         jump_to_word_boundaries,
                     }),
@@ -149,15 +202,49 @@ impl<C> TextEditAssist<C>
                 redraw = true;
                 cursor_flash = Some(CursorFlashOp::Start);
             }
-            MouseDown{in_widget: true, button, pos} => {
+            ImeCommit(ref text) => {
+                let filter = &mut self.filter;
+                let text: String = text.chars().filter(|c| filter.char_allowed(*c)).collect();
+                if !text.is_empty() {
+                    allow_bubble = false;
+                    self.cursor_ops.push(CursorOp::InsertString(text));
+                    redraw = true;
+                    cursor_flash = Some(CursorFlashOp::Start);
+                }
+            },
+            MouseDown{in_widget: true, button, pos, click_count} => {
                 focus = Some(FocusChange::Take);
-                if button == MouseButton::Left {
+                self.drag_unit = None;
+                let add_caret = self.multi_caret && input_state.modifiers.contains(conventions.command_modifier);
+                if button == MouseButton::Left && add_caret {
+                    self.cursor_ops.push(CursorOp::AddCaretOnSegment(Segment::new(pos, pos)));
+                    redraw = true;
+                    cursor_flash = Some(CursorFlashOp::Start);
+                } else if button == MouseButton::Left && self.gutter.as_ref().map(|g| pos.x < g.width).unwrap_or(false) {
+                    self.cursor_ops.push(CursorOp::SelectOnSegment(Segment::new(pos, pos)));
+                    self.gutter_clicked = true;
+                    redraw = true;
+                } else if button == MouseButton::Left {
                     self.cursor_ops.push(CursorOp::SelectOnSegment(Segment::new(pos, pos)));
+                    self.drag_unit = match click_count {
+                        1 => None,
+                        2 => Some(conventions.double_click),
+                        _ => Some(conventions.triple_click)
+                    };
+                    if let Some(unit) = self.drag_unit {
+                        self.cursor_ops.push(CursorOp::ExpandSelection(unit));
+                    }
                     redraw = true;
                     cursor_flash = Some(CursorFlashOp::Start);
                 }
             },
+            // The first click after opening the context menu lands on the menu, and shouldn't
+            // take focus away from the text.
+            MouseDown{in_widget: false, ..} if self.context_menu == ContextMenuState::Open => {
+                self.context_menu = ContextMenuState::Clicked;
+            },
             MouseDown{in_widget: false, ..} => {
+                self.context_menu = ContextMenuState::Closed;
                 focus = Some(FocusChange::Remove);
                 self.cursor_data.draw_cursor = false;
                 redraw = true;
@@ -169,11 +256,23 @@ impl<C> TextEditAssist<C>
                     Some(MouseHoverChange::Exit) => cursor_icon = Some(CursorIcon::default()),
                     _ => ()
                 }
-                if let Some(down) = input_state.mouse_buttons_down_in_widget.iter().find(|d| d.button == MouseButton::Left) {
+                // Dragging after `Ctrl`+clicking would replace every caret with the dragged
+                // selection, so the added caret stays where it was placed instead.
+                let adding_caret = self.multi_caret && input_state.modifiers.contains(conventions.command_modifier);
+                let left_down = input_state.mouse_buttons_down_in_widget.iter().find(|d| d.button == MouseButton::Left);
+                if let (Some(down), false) = (left_down, adding_caret) {
                     self.cursor_ops.push(CursorOp::SelectOnSegment(Segment::new(down.down_pos, new_pos)));
+                    if let (Some(unit), true) = (self.drag_unit, conventions.drag_by_click_unit) {
+                        self.cursor_ops.push(CursorOp::ExpandSelection(unit));
+                    }
                     redraw = true;
                 }
             },
+            ContextMenuRequested{..} if !conventions.edit_menu.is_empty() => {
+                allow_bubble = false;
+                context_menu = true;
+                self.context_menu = ContextMenuState::Open;
+            },
             GainFocus(_, _) => {
                 redraw = true;
                 cursor_flash = Some(CursorFlashOp::Start);
@@ -190,7 +289,168 @@ impl<C> TextEditAssist<C>
             redraw,
             cursor_flash,
             cursor_icon,
-            focus
+            focus,
+            context_menu,
         }
     }
+
+    /// Whether any caret has text selected.
+    pub fn has_selection(&self) -> bool {
+        self.cursor_data.carets().any(|c| c.highlight_range.len() != 0)
+    }
+
+    /// Perform an action picked from the widget's context menu. Returns `false` if the widget's
+    /// context menu isn't open, in which case the action came from some other widget's menu and
+    /// gets ignored.
+    pub fn menu_activated(&mut self, action: EditAction) -> bool {
+        match self.context_menu {
+            ContextMenuState::Closed => false,
+            ContextMenuState::Open |
+            ContextMenuState::Clicked => {
+                self.context_menu = ContextMenuState::Closed;
+                self.perform(action);
+                true
+            }
+        }
+    }
+
+    fn perform(&mut self, action: EditAction) {
+        match action {
+            EditAction::Cut => if let Ok(mut clipboard) = ClipboardContext::new() {
+                clipboard.set_contents(self.selected_text()).ok();
+                self.cursor_ops.push(CursorOp::DeleteSelection);
+            },
+            EditAction::Copy => if let Ok(mut clipboard) = ClipboardContext::new() {
+                clipboard.set_contents(self.selected_text()).ok();
+            },
+            EditAction::Paste => if let Ok(clipboard_contents) = ClipboardContext::new().and_then(|mut c| c.get_contents()) {
+                self.cursor_ops.push(CursorOp::InsertString(clipboard_contents));
+            },
+            EditAction::Delete => self.cursor_ops.push(CursorOp::DeleteSelection),
+            EditAction::SelectAll => self.cursor_ops.push(CursorOp::SelectAll),
+        }
+    }
+
+    /// The text selected by every caret, in the order it appears in the string and separated by
+    /// newlines.
+    fn selected_text(&self) -> String {
+        let mut ranges: Vec<_> = self.cursor_data.carets()
+            .map(|c| c.highlight_range)
+            .filter(|r| r.len() != 0)
+            .collect();
+        ranges.sort_by_key(|r| r.start);
+
+        let selections: Vec<&str> = ranges.into_iter().map(|r| &self.string[r]).collect();
+        selections.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cgmath::Point2,
+        core::event::{MouseDown, WidgetCoords},
+    };
+    use cgmath_geometry::rect::BoundBox;
+    use derin_common_types::buttons::MouseButton;
+
+    fn input_state(buttons_down: &[MouseDown]) -> InputState<'_> {
+        InputState {
+            mouse_buttons_down: buttons_down,
+            mouse_buttons_down_in_widget: buttons_down,
+            mouse_pos: None,
+            modifiers: ModifierKeys::empty(),
+            keys_down: &[],
+            coords: WidgetCoords {
+                window_rect: BoundBox::new2(0, 0, 100, 20),
+                clip: None,
+            },
+        }
+    }
+
+    #[test]
+    fn click_and_drag_by_unit() {
+        let mut assist = TextEditAssist::<DefaultCharFilter>::default();
+        let (down_pos, drag_pos) = (Point2::new(10, 5), Point2::new(40, 5));
+        let left_down = [MouseDown{ button: MouseButton::Left, down_pos }];
+        let down = |click_count| WidgetEvent::MouseDown {
+            pos: down_pos,
+            in_widget: true,
+            button: MouseButton::Left,
+            click_count,
+        };
+        let drag = WidgetEvent::MouseMove {
+            old_pos: down_pos,
+            new_pos: drag_pos,
+            in_widget: true,
+            hover_change: None,
+        };
+        let click_ops = |unit| vec![CursorOp::SelectOnSegment(Segment::new(down_pos, down_pos)), CursorOp::ExpandSelection(unit)];
+        let drag_ops = |unit| vec![CursorOp::SelectOnSegment(Segment::new(down_pos, drag_pos)), CursorOp::ExpandSelection(unit)];
+
+        // Double-clicking selects a word and triple-clicking a paragraph, and dragging afterwards
+        // extends the selection by whole words or paragraphs.
+        let conventions = PlatformConventions::windows();
+        for &(click_count, unit) in &[(2, TextUnit::Word), (3, TextUnit::Paragraph)] {
+            assist.adapt_event(&down(click_count), input_state(&[]), &conventions);
+            assert_eq!(click_ops(unit), assist.cursor_ops.drain(..).collect::<Vec<_>>());
+            assist.adapt_event(&drag, input_state(&left_down), &conventions);
+            assert_eq!(drag_ops(unit), assist.cursor_ops.drain(..).collect::<Vec<_>>());
+        }
+
+        // Single clicks drag by characters.
+        assist.adapt_event(&down(1), input_state(&[]), &conventions);
+        assist.cursor_ops.clear();
+        assist.adapt_event(&drag, input_state(&left_down), &conventions);
+        assert_eq!(vec![CursorOp::SelectOnSegment(Segment::new(down_pos, drag_pos))], assist.cursor_ops);
+        assist.cursor_ops.clear();
+
+        // So do double-clicks, on platforms that don't drag by unit.
+        let conventions = PlatformConventions {
+            drag_by_click_unit: false,
+            ..PlatformConventions::windows()
+        };
+        assist.adapt_event(&down(2), input_state(&[]), &conventions);
+        assert_eq!(click_ops(TextUnit::Word), assist.cursor_ops.drain(..).collect::<Vec<_>>());
+        assist.adapt_event(&drag, input_state(&left_down), &conventions);
+        assert_eq!(vec![CursorOp::SelectOnSegment(Segment::new(down_pos, drag_pos))], assist.cursor_ops);
+    }
+
+    #[test]
+    fn platform_modifiers() {
+        let mut assist = TextEditAssist::<DefaultCharFilter>::default();
+        let mut key_ops = |conventions: &PlatformConventions, modifiers, key| {
+            assist.adapt_event(&WidgetEvent::KeyDown(key, modifiers), input_state(&[]), conventions);
+            assist.cursor_ops.drain(..).collect::<Vec<_>>()
+        };
+        let move_horizontal = |delta, jump_to_word_boundaries| vec![CursorOp::MoveHorizontal {
+            delta,
+            expand_selection: false,
+            jump_to_word_boundaries,
+        }];
+        let move_to_boundary = |unit, forward, expand_selection| vec![CursorOp::MoveToBoundary {
+            unit,
+            forward,
+            expand_selection,
+        }];
+        let (ctrl, alt, logo, shift) = (ModifierKeys::CTRL, ModifierKeys::ALT, ModifierKeys::LOGO, ModifierKeys::SHIFT);
+
+        let windows = PlatformConventions::windows();
+        assert_eq!(move_horizontal(-1, true), key_ops(&windows, ctrl, Key::LArrow));
+        assert_eq!(move_horizontal(-1, false), key_ops(&windows, alt, Key::LArrow));
+        assert_eq!(move_to_boundary(TextUnit::Paragraph, false, false), key_ops(&windows, ModifierKeys::empty(), Key::Home));
+        assert_eq!(move_to_boundary(TextUnit::Document, true, false), key_ops(&windows, ctrl, Key::End));
+        assert_eq!(vec![CursorOp::SelectAll], key_ops(&windows, ctrl, Key::A));
+        assert_eq!(Vec::<CursorOp>::new(), key_ops(&windows, alt, Key::A));
+
+        let macos = PlatformConventions::macos();
+        assert_eq!(move_horizontal(-1, true), key_ops(&macos, logo, Key::LArrow));
+        assert_eq!(move_horizontal(-1, false), key_ops(&macos, ctrl, Key::LArrow));
+        assert_eq!(move_to_boundary(TextUnit::Paragraph, true, true), key_ops(&macos, alt | shift, Key::RArrow));
+        assert_eq!(move_to_boundary(TextUnit::Document, false, false), key_ops(&macos, alt, Key::UArrow));
+        assert_eq!(move_to_boundary(TextUnit::Document, false, false), key_ops(&macos, ModifierKeys::empty(), Key::Home));
+        assert_eq!(vec![CursorOp::SelectAll], key_ops(&macos, alt, Key::A));
+        assert_eq!(Vec::<CursorOp>::new(), key_ops(&macos, ctrl, Key::A));
+    }
 }
@@ -3,6 +3,7 @@ use derin_core::{
     event::{EventOps, InputState, WidgetEvent, WidgetEventSourced, MouseHoverChange},
     widget::{Parent, Widget, WidgetInfo, WidgetInfoMut, WidgetIdent, WidgetTag, WidgetRenderable},
     render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+    sound::UiSound,
 };
 use crate::widgets::{
     Contents, Label,
@@ -153,6 +154,7 @@ impl<H, T> Widget for Toggle<H, T>
             },
             MouseDown{..} => new_state = ButtonState::Pressed,
             MouseUp{in_widget: true, pressed_in_widget: true, ..} => {
+                self.widget_tag.play_sound(UiSound::Click).ok();
                 self.handler.on_click(&mut new_selected);
                 new_state = ButtonState::Hover;
             },
@@ -4,12 +4,13 @@
 
 use derin_core::{
     event::{EventOps, WidgetEvent, WidgetEventSourced, InputState, MouseHoverChange},
-    widget::{WidgetTag, WidgetRenderable, Widget},
+    widget::{ScopeButton, WidgetTag, WidgetRenderable, Widget},
     render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+    sound::UiSound,
 };
 use crate::widgets::{
     Contents,
-    assistants::ButtonState,
+    assistants::{ButtonState, mnemonic::prepare_mnemonic_string},
 };
 
 use cgmath_geometry::{D2, rect::BoundBox};
@@ -64,6 +65,19 @@ impl<H> Button<H> {
             .request_relayout();
         &mut self.contents
     }
+
+    /// Retrieves whether this button is activated by `Enter` or `Escape` in its focus scope.
+    pub fn scope_button(&self) -> Option<ScopeButton> {
+        self.widget_tag.scope_button()
+    }
+
+    /// Makes this button the default or cancel button of the nearest enclosing focus scope.
+    ///
+    /// The default button gets clicked when `Enter` is pressed within the scope, and the cancel
+    /// button gets clicked when `Escape` is pressed.
+    pub fn set_scope_button(&mut self, scope_button: Option<ScopeButton>) {
+        self.widget_tag.set_scope_button(scope_button);
+    }
 }
 
 impl<H> Widget for Button<H>
@@ -100,10 +114,17 @@ impl<H> Widget for Button<H>
             },
             MouseDown{..} => ButtonState::Pressed,
             MouseUp{in_widget: true, pressed_in_widget: true, ..} => {
+                self.widget_tag.play_sound(UiSound::Click).ok();
                 self.handler.on_click();
                 ButtonState::Hover
             },
             MouseUp{in_widget: false, ..} => ButtonState::Normal,
+            MnemonicActivated |
+            ScopeButtonActivated(_) => {
+                self.widget_tag.play_sound(UiSound::Click).ok();
+                self.handler.on_click();
+                self.state
+            },
             GainFocus(_, _) => ButtonState::Hover,
             LoseFocus => ButtonState::Normal,
             _ => self.state
@@ -140,7 +161,7 @@ impl<R, H> WidgetRenderable<R> for Button<H>
 
     fn update_layout(&mut self, layout: &mut R::Layout) {
         match self.contents {
-            Contents::Text(ref s) => layout.prepare_string(s),
+            Contents::Text(ref s) => prepare_mnemonic_string(layout, &mut self.widget_tag, s),
             Contents::Icon(ref i) => layout.prepare_icon(i),
         }
 
@@ -0,0 +1,408 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    ambient::Opacity,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState},
+    timer::{Timer, TimerId},
+    widget::{WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, SubFrame, WidgetTheme},
+};
+use derin_common_types::layout::SizeBounds;
+
+use crate::cgmath::EuclideanSpace;
+use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
+
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+/// How often the fade gets updated.
+const FADE_FRAME: Duration = Duration::from_millis(16);
+
+/// A container that cross-fades between its old and new child when the child gets replaced.
+///
+/// While a fade is in progress, the outgoing child stays in the tree so it can be drawn fading out
+/// underneath the incoming child. Only the incoming child is accessible through `widget` and
+/// `widget_mut`, and the outgoing child is dropped once the fade finishes.
+///
+/// The children are faded with the `Opacity` ambient value, so renderers that don't support
+/// translucent widgets switch between the children without fading. Fades are shortened or skipped
+/// according to the user's motion preferences.
+#[derive(Debug, Clone)]
+pub struct CrossFade<W> {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    duration: Duration,
+    current: FadeLayer<W>,
+    outgoing: Option<Fade<W>>,
+    timer_id: TimerId,
+}
+
+#[derive(Debug, Clone)]
+struct Fade<W> {
+    layer: FadeLayer<W>,
+    start: Instant,
+    duration: Duration,
+}
+
+/// Wraps a child of the `CrossFade`, so that its opacity can be set without access to the child's
+/// `WidgetTag`.
+#[derive(Debug, Clone)]
+struct FadeLayer<W> {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    widget: W,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CrossFadeTheme(());
+
+impl<W> CrossFade<W> {
+    /// Creates a new cross-fade container, which fades over `duration` when its child gets
+    /// replaced.
+    pub fn new(widget: W, duration: Duration) -> CrossFade<W> {
+        CrossFade {
+            widget_tag: WidgetTag::new(),
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            duration,
+            current: FadeLayer::new(widget),
+            outgoing: None,
+            timer_id: TimerId::new(),
+        }
+    }
+
+    /// Retrieves the current child.
+    pub fn widget(&self) -> &W {
+        &self.current.widget
+    }
+
+    /// Retrieves the current child, for mutation.
+    pub fn widget_mut(&mut self) -> &mut W {
+        self.widget_tag.request_relayout().request_rescan();
+        &mut self.current.widget
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Sets how long future fades take. Doesn't affect a fade that's already in progress.
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    /// Whether the container is fading between two children.
+    pub fn is_fading(&self) -> bool {
+        self.outgoing.is_some()
+    }
+
+    /// Replaces the child, fading from the old child to the new one.
+    ///
+    /// If a fade is already in progress, the child being faded out gets removed immediately and
+    /// the fade restarts from the current child.
+    pub fn replace(&mut self, widget: W) {
+        let old = mem::replace(&mut self.current, FadeLayer::new(widget));
+        let duration = self.widget_tag.accessibility_prefs().animation_duration(self.duration);
+
+        self.outgoing = match duration {
+            Some(duration) if duration > Duration::from_secs(0) => Some(Fade {
+                layer: old,
+                start: Instant::now(),
+                duration,
+            }),
+            _ => None
+        };
+        match self.outgoing {
+            Some(_) => {self.widget_tag.timers_mut().insert(self.timer_id, Timer::new(FADE_FRAME));},
+            None => {self.widget_tag.timers_mut().remove(&self.timer_id);}
+        }
+
+        self.update_fade(Instant::now());
+        self.widget_tag.request_relayout();
+    }
+
+    /// Set the opacity of the children according to how far the fade has progressed, ending the
+    /// fade if it's finished.
+    fn update_fade(&mut self, now: Instant) {
+        let progress = match self.outgoing {
+            Some(ref fade) => {
+                let elapsed = now.duration_since(fade.start);
+                let as_secs = |d: Duration| d.as_secs() as f32 + d.subsec_nanos() as f32 / 1_000_000_000.0;
+                as_secs(elapsed) / as_secs(fade.duration)
+            },
+            None => 1.0
+        };
+
+        if progress < 1.0 {
+            if let Some(ref mut fade) = self.outgoing {
+                fade.layer.widget_tag.set_ambient(Opacity(1.0 - progress));
+                self.current.widget_tag.set_ambient(Opacity(progress));
+                return;
+            }
+        }
+
+        self.current.widget_tag.clear_ambient::<Opacity>();
+        if self.outgoing.take().is_some() {
+            self.widget_tag.timers_mut().remove(&self.timer_id);
+            self.widget_tag.request_relayout();
+        }
+    }
+}
+
+impl<W> FadeLayer<W> {
+    fn new(widget: W) -> FadeLayer<W> {
+        FadeLayer {
+            widget_tag: WidgetTag::new(),
+            rect: BoundBox::new2(0, 0, 0, 0),
+            widget,
+        }
+    }
+}
+
+impl<W> Widget for CrossFade<W>
+    where W: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.current.widget.size_bounds()
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        if let WidgetEventSourced::This(WidgetEvent::Timer{timer_id, ..}) = event {
+            if timer_id == self.timer_id {
+                self.update_fade(Instant::now());
+            }
+        }
+
+        EventOps {
+            focus: None,
+            bubble: event.default_bubble(),
+        }
+    }
+}
+
+impl<W> Widget for FadeLayer<W>
+    where W: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.rect
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.widget.size_bounds()
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: true,
+        }
+    }
+}
+
+impl<W> Parent for CrossFade<W>
+    where W: Widget
+{
+    fn num_children(&self) -> usize {
+        1 + self.outgoing.is_some() as usize
+    }
+
+    // The outgoing child comes first, so that the current child gets drawn on top of it and
+    // receives input.
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        let index = self.outgoing.is_some() as usize;
+        match (widget_ident, &self.outgoing) {
+            (WidgetIdent::Num(0), _) => Some(WidgetInfo::new(WidgetIdent::Num(0), index, &self.current)),
+            (WidgetIdent::Num(1), Some(fade)) => Some(WidgetInfo::new(WidgetIdent::Num(1), 0, &fade.layer)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        let index = self.outgoing.is_some() as usize;
+        match (widget_ident, &mut self.outgoing) {
+            (WidgetIdent::Num(0), _) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), index, &mut self.current)),
+            (WidgetIdent::Num(1), Some(fade)) => Some(WidgetInfoMut::new(WidgetIdent::Num(1), 0, &mut fade.layer)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        if let Some(ref fade) = self.outgoing {
+            if let LoopFlow::Break = for_each(WidgetInfo::new(WidgetIdent::Num(1), 0, &fade.layer)) {
+                return;
+            }
+        }
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(0), self.outgoing.is_some() as usize, &self.current));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let index = self.outgoing.is_some() as usize;
+        if let Some(ref mut fade) = self.outgoing {
+            if let LoopFlow::Break = for_each(WidgetInfoMut::new(WidgetIdent::Num(1), 0, &mut fade.layer)) {
+                return;
+            }
+        }
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), index, &mut self.current));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match (index, self.outgoing.is_some()) {
+            (0, true) => self.framed_child(WidgetIdent::Num(1)),
+            (0, false) | (1, true) => self.framed_child(WidgetIdent::Num(0)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match (index, self.outgoing.is_some()) {
+            (0, true) => self.framed_child_mut(WidgetIdent::Num(1)),
+            (0, false) | (1, true) => self.framed_child_mut(WidgetIdent::Num(0)),
+            _ => None
+        }
+    }
+}
+
+impl<W> Parent for FadeLayer<W>
+    where W: Widget
+{
+    fn num_children(&self) -> usize {
+        1
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+}
+
+impl<W, R> WidgetRenderable<R> for CrossFade<W>
+    where W: Widget,
+          R: Renderer
+{
+    type Theme = CrossFadeTheme;
+
+    fn theme(&self) -> CrossFadeTheme {
+        CrossFadeTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        // Both children cover the whole container.
+        let rect = BoundBox::new2(0, 0, self.bounds.width(), self.bounds.height());
+        if self.current.rect != rect {
+            *self.current.rect_mut() = rect;
+        }
+        if let Some(ref mut fade) = self.outgoing {
+            if fade.layer.rect != rect {
+                *fade.layer.rect_mut() = rect;
+            }
+        }
+    }
+}
+
+impl<W, R> WidgetRenderable<R> for FadeLayer<W>
+    where W: Widget,
+          R: Renderer
+{
+    type Theme = CrossFadeTheme;
+
+    fn theme(&self) -> CrossFadeTheme {
+        CrossFadeTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let rect = BoundBox::new2(0, 0, self.rect.width(), self.rect.height());
+        let size_bounds = self.widget.size_bounds();
+        let dims_bounded = size_bounds.bound_rect(rect.dims());
+        let new_rect = BoundBox::from(dims_bounded) + rect.min().to_vec();
+        if self.widget.rect() != new_rect {
+            *self.widget.rect_mut() = new_rect;
+        }
+    }
+}
+
+impl WidgetTheme for CrossFadeTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
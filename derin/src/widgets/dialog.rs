@@ -0,0 +1,271 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEventSourced, InputState},
+    widget::{WidgetId, WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, SubFrame, WidgetTheme},
+};
+use derin_common_types::layout::SizeBounds;
+
+use crate::cgmath::{EuclideanSpace, Point2};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+};
+
+/// A modal dialog box.
+///
+/// The dialog covers its whole rectangle with a backdrop that dims whatever's underneath, and
+/// centers its content inside of it at the content's minimum size. It should generally be given
+/// the entire window. While open, the dialog is modal: input only reaches the dialog's content,
+/// and keyboard focus moves into the dialog until it's closed. Closed dialogs still draw their
+/// content, but don't receive any input.
+///
+/// Dialogs are also focus scopes, so `Enter` and `Escape` activate the default and cancel buttons
+/// inside of them.
+///
+/// Dialogs are closed by calling `close`, or by a widget inside of the dialog broadcasting a
+/// `CloseDialog` message. Either way, the dialog broadcasts a `DialogClosed` message carrying the
+/// result. Closed dialogs stop blocking input, but don't remove themselves from the widget tree;
+/// whatever owns the dialog should remove it once it receives `DialogClosed`.
+#[derive(Debug, Clone)]
+pub struct Dialog<W, T> {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    content: W,
+    open: bool,
+    _result: PhantomData<fn(T)>,
+}
+
+/// Message that closes the topmost open `Dialog` with the given result.
+///
+/// The result type `T` has to match the dialog's result type, or the message gets ignored.
+#[derive(Debug)]
+pub struct CloseDialog<T> {
+    result: RefCell<Option<T>>,
+}
+
+/// Message broadcast by a `Dialog` when it closes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogClosed<T> {
+    /// The widget ID of the dialog that was closed.
+    pub dialog: WidgetId,
+    pub result: T,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DialogTheme {
+    /// Whether the backdrop should be drawn.
+    pub open: bool,
+}
+
+impl<T> CloseDialog<T> {
+    pub fn new(result: T) -> CloseDialog<T> {
+        CloseDialog {
+            result: RefCell::new(Some(result)),
+        }
+    }
+}
+
+impl<W, T> Dialog<W, T>
+    where W: Widget,
+          T: 'static
+{
+    /// Creates a new dialog displaying `content`. The dialog starts out closed.
+    pub fn new(content: W) -> Dialog<W, T> {
+        let mut widget_tag = WidgetTag::new();
+        widget_tag.register_message(Self::on_close_dialog);
+        widget_tag
+            .set_focus_scope(true)
+            .set_input_transparent(true);
+        Dialog {
+            widget_tag,
+            rect: BoundBox::new2(0, 0, 0, 0),
+            content,
+            open: false,
+            _result: PhantomData,
+        }
+    }
+
+    /// Retrieves the dialog's content.
+    pub fn content(&self) -> &W {
+        &self.content
+    }
+
+    /// Retrieves the dialog's content, for mutation.
+    pub fn content_mut(&mut self) -> &mut W {
+        self.widget_tag.request_relayout();
+        &mut self.content
+    }
+
+    /// Whether the dialog is open, and blocking input to the rest of the window.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens the dialog, making it modal. Dialogs opened while another dialog is open get stacked
+    /// on top of it.
+    pub fn open(&mut self) {
+        if self.open {
+            return;
+        }
+        self.open = true;
+        self.widget_tag
+            .push_modal()
+            .set_input_transparent(false)
+            .request_redraw();
+    }
+
+    /// Closes the dialog, broadcasting a `DialogClosed` message with the given result. Does nothing
+    /// if the dialog isn't open.
+    pub fn close(&mut self, result: T) {
+        if !self.open {
+            return;
+        }
+        self.open = false;
+        let dialog = self.widget_tag.widget_id();
+        self.widget_tag.remove_modal();
+        self.widget_tag
+            .set_input_transparent(true)
+            .request_redraw()
+            .broadcast_message(DialogClosed{ dialog, result });
+    }
+
+    fn on_close_dialog(&mut self, close: &CloseDialog<T>) {
+        // Only the topmost dialog gets closed, which is the one the message came from.
+        if !self.open || !self.widget_tag.is_top_modal() {
+            return;
+        }
+        if let Some(result) = close.result.borrow_mut().take() {
+            self.close(result);
+        }
+    }
+}
+
+impl<W, T> Widget for Dialog<W, T>
+    where W: Widget,
+          T: 'static
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.rect
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        SizeBounds {
+            min: self.content.size_bounds().min,
+            ..SizeBounds::default()
+        }
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        // Clicks on the backdrop get swallowed, rather than bubbling to whatever's underneath.
+        EventOps {
+            focus: None,
+            bubble: event.default_bubble() && !self.open,
+        }
+    }
+}
+
+impl<W, T> Parent for Dialog<W, T>
+    where W: Widget,
+          T: 'static
+{
+    fn num_children(&self) -> usize {
+        1
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.content)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.content)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.content));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.content));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.content)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.content)),
+            _ => None
+        }
+    }
+}
+
+impl<W, T, R> WidgetRenderable<R> for Dialog<W, T>
+    where W: Widget,
+          T: 'static,
+          R: Renderer
+{
+    type Theme = DialogTheme;
+
+    fn theme(&self) -> DialogTheme {
+        DialogTheme {
+            open: self.open,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let dims = self.rect.dims();
+        let content_dims = self.content.size_bounds().bound_rect(DimsBox::new2(0, 0));
+        let (width, height) = (content_dims.width().min(dims.width()), content_dims.height().min(dims.height()));
+        let origin = Point2::new((dims.width() - width) / 2, (dims.height() - height) / 2);
+
+        let content_rect = BoundBox::from(DimsBox::new2(width, height)) + origin.to_vec();
+        if self.content.rect() != content_rect {
+            *self.content.rect_mut() = content_rect;
+        }
+    }
+}
+
+impl WidgetTheme for DialogTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
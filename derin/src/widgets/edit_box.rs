@@ -4,23 +4,39 @@
 
 use derin_core::{
     event::{EventOps, WidgetEvent, WidgetEventSourced, InputState},
-    timer::{Timer, TimerId},
     widget::{WidgetTag, WidgetRenderable, Widget},
-    render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+    render::{Renderer, RendererLayout, SubFrame, WidgetTheme, EditDecorations, FoldRegion, GutterLayout},
 };
-use crate::widgets::assistants::text_edit::{TextEditAssist, TextEditOps, CursorFlashOp, LineCharFilter};
-use cgmath_geometry::{D2, rect::BoundBox};
+use crate::{
+    highlight::{Highlighter, HighlightCache},
+    menu::MenuActivated,
+    platform::{EditAction, PlatformConventions},
+    widgets::{
+        Menu, OpenPopup,
+        assistants::text_edit::{TextEditAssist, TextEditOps, CursorFlashOp, LineCharFilter},
+    },
+};
+use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
 use derin_common_types::layout::SizeBounds;
-use std::time::Duration;
+use std::ops::Range;
 
 /// Multi-line editable text widget.
+///
+/// Supports multiple carets: `Ctrl`+click adds a caret, `Ctrl+D` selects the next occurrence of the
+/// selected text with a new caret, and `Escape` goes back to a single caret. On macOS, `Command`
+/// takes the place of `Ctrl`.
+///
+/// Text boxes follow the [`PlatformConventions`] that apply to them, and open a context menu with
+/// the standard editing actions in the enclosing `PopupLayer` when right-clicked.
+///
+/// [`PlatformConventions`]: ../platform/struct.PlatformConventions.html
 #[derive(Debug, Clone)]
 pub struct EditBox {
     widget_tag: WidgetTag,
     bounds: BoundBox<D2, i32>,
     edit: TextEditAssist,
     size_bounds: SizeBounds,
-    flash_timer: Option<TimerId>,
+    caret_active: bool,
 }
 
 /// Single-line editable text widget.
@@ -30,7 +46,32 @@ pub struct LineBox {
     bounds: BoundBox<D2, i32>,
     edit: TextEditAssist<LineCharFilter>,
     size_bounds: SizeBounds,
-    flash_timer: Option<TimerId>,
+    caret_active: bool,
+}
+
+/// The column to the left of an `EditBox`'s text, which shows line numbers, markers such as
+/// breakpoints, and controls for folding regions of lines.
+///
+/// Lines are referred to by their zero-based index. Markers and fold regions don't move when lines
+/// get inserted or removed, so they should be updated along with the text.
+///
+/// Clicking the first line of a fold region folds or unfolds it. Clicking any other line
+/// broadcasts a [`GutterClicked`] message.
+///
+/// [`GutterClicked`]: ./struct.GutterClicked.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gutter {
+    /// The width of the gutter, in pixels.
+    pub width: i32,
+    pub line_numbers: bool,
+    markers: Vec<(usize, &'static str)>,
+    folds: Vec<FoldRegion>,
+}
+
+/// Message broadcast when a line in an `EditBox`'s gutter is clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GutterClicked {
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -41,15 +82,19 @@ pub struct LineBoxTheme(());
 impl EditBox {
     /// Create a new `EditBox`, containing the included `String` by default.
     pub fn new(string: String) -> EditBox {
+        let mut widget_tag = WidgetTag::new();
+        widget_tag.register_message(EditBox::on_menu_activated);
+        widget_tag.set_focusable(true);
         EditBox {
-            widget_tag: WidgetTag::new(),
+            widget_tag,
             bounds: BoundBox::new2(0, 0, 0, 0),
             edit: TextEditAssist {
                 string,
+                multi_caret: true,
                 ..TextEditAssist::default()
             },
             size_bounds: SizeBounds::default(),
-            flash_timer: None,
+            caret_active: false,
         }
     }
 
@@ -66,20 +111,121 @@ impl EditBox {
         self.widget_tag.request_redraw().request_relayout();
         &mut self.edit.string
     }
+
+    /// Highlight the contents with the given highlighter. The colors of each style class are taken
+    /// from the theme.
+    pub fn set_highlighter<H: Highlighter + Clone>(&mut self, highlighter: H) {
+        self.edit.highlight = Some(Box::new(HighlightCache::new(highlighter)));
+        self.widget_tag.request_relayout();
+    }
+
+    /// Stop highlighting the contents.
+    pub fn clear_highlighter(&mut self) {
+        self.edit.highlight = None;
+        self.widget_tag.request_relayout();
+    }
+
+    pub fn gutter(&self) -> Option<&Gutter> {
+        self.edit.gutter.as_ref()
+    }
+
+    /// Retrieves the gutter, for mutation.
+    ///
+    /// Calling this function forces the box to be re-laid out, so you're discouraged from calling
+    /// it unless you're actually changing the gutter.
+    pub fn gutter_mut(&mut self) -> Option<&mut Gutter> {
+        self.widget_tag.request_redraw().request_relayout();
+        self.edit.gutter.as_mut()
+    }
+
+    /// Show or hide the gutter.
+    pub fn set_gutter(&mut self, gutter: Option<Gutter>) {
+        self.widget_tag.request_redraw().request_relayout();
+        self.edit.gutter = gutter;
+    }
+}
+
+impl Gutter {
+    /// Creates a gutter with the given width, showing line numbers.
+    pub fn new(width: i32) -> Gutter {
+        Gutter {
+            width,
+            line_numbers: true,
+            markers: Vec::new(),
+            folds: Vec::new(),
+        }
+    }
+
+    /// The lines that have markers, along with the style class of each marker.
+    pub fn markers(&self) -> &[(usize, &'static str)] {
+        &self.markers
+    }
+
+    /// Set or remove the marker on the given line. The theme's highlight color for the marker's
+    /// style class determines how it gets drawn.
+    pub fn set_marker(&mut self, line: usize, class: Option<&'static str>) {
+        let index = self.markers.binary_search_by_key(&line, |&(l, _)| l);
+        match (index, class) {
+            (Ok(i), Some(class)) => self.markers[i].1 = class,
+            (Ok(i), None) => {self.markers.remove(i);},
+            (Err(i), Some(class)) => self.markers.insert(i, (line, class)),
+            (Err(_), None) => ()
+        }
+    }
+
+    pub fn folds(&self) -> &[FoldRegion] {
+        &self.folds
+    }
+
+    /// Allow the given range of lines to be folded. Regions starting on the same line as an
+    /// existing region replace it.
+    pub fn add_fold(&mut self, lines: Range<usize>) {
+        self.folds.retain(|f| f.lines.start != lines.start);
+        self.folds.push(FoldRegion{ lines, folded: false });
+        self.folds.sort_by_key(|f| f.lines.start);
+    }
+
+    pub fn clear_folds(&mut self) {
+        self.folds.clear();
+    }
+
+    /// Fold or unfold the region starting on the given line, returning `false` if no region starts
+    /// there.
+    pub fn toggle_fold(&mut self, line: usize) -> bool {
+        match self.folds.iter_mut().find(|f| f.lines.start == line) {
+            Some(fold) => {
+                fold.folded = !fold.folded;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn layout(&self) -> GutterLayout {
+        GutterLayout {
+            width: self.width,
+            line_numbers: self.line_numbers,
+            markers: &self.markers,
+            folds: &self.folds,
+        }
+    }
 }
 
 impl LineBox {
     /// Create a new `LineBox`, containing the included `String` by default.
     pub fn new(string: String) -> LineBox {
+        let mut widget_tag = WidgetTag::new();
+        widget_tag.register_message(LineBox::on_menu_activated);
+        widget_tag.set_focusable(true);
         LineBox {
-            widget_tag: WidgetTag::new(),
+            widget_tag,
             bounds: BoundBox::new2(0, 0, 0, 0),
             edit: TextEditAssist {
                 string,
                 ..TextEditAssist::default()
             },
             size_bounds: SizeBounds::default(),
-            flash_timer: None,
+            caret_active: false,
         }
     }
 
@@ -112,12 +258,38 @@ macro_rules! render {
             }
 
             fn update_layout(&mut self, layout: &mut R::Layout) {
-                layout.prepare_edit_string(
+                self.edit.cursor_data.draw_cursor = self.caret_active && self.widget_tag.caret_visible();
+                let decorations = EditDecorations {
+                    spans: self.edit.highlight.as_ref().map(|h| h.spans()).unwrap_or(&[]),
+                    gutter: self.edit.gutter.as_ref().map(|g| g.layout()),
+                };
+                layout.prepare_edit_string_decorated(
                     &mut self.edit.string,
                     &mut self.edit.cursor_data,
                     self.edit.cursor_ops.drain(..),
+                    &decorations,
                 );
 
+                if self.edit.gutter_clicked {
+                    self.edit.gutter_clicked = false;
+                    let line = self.edit.string[..self.edit.cursor_data.cursor_pos].matches('\n').count();
+                    if let Some(ref mut gutter) = self.edit.gutter {
+                        match gutter.toggle_fold(line) {
+                            true => {self.widget_tag.request_relayout();},
+                            false => self.widget_tag.broadcast_message(GutterClicked{ line })
+                        }
+                    }
+                }
+
+                // The cursor ops can only be applied by the renderer, so the spans it just drew
+                // with describe the text from before this frame's edits. Re-highlight the edited
+                // lines, and lay out again if that changed anything.
+                if let Some(ref mut highlight) = self.edit.highlight {
+                    if highlight.update(&self.edit.string) {
+                        self.widget_tag.request_relayout();
+                    }
+                }
+
                 let result = layout.finish();
                 self.size_bounds = result.size_bounds;
             }
@@ -125,10 +297,27 @@ macro_rules! render {
     }
 }
 
+macro_rules! menu {
+    ($ty:ty) => {
+        impl $ty {
+            fn on_menu_activated(&mut self, activated: &MenuActivated) {
+                let action = match EditAction::from_id(&activated.id) {
+                    Some(action) => action,
+                    None => return
+                };
+                if self.edit.menu_activated(action) {
+                    self.widget_tag.request_redraw();
+                }
+            }
+        }
+    }
+}
+
 macro_rules! event {
     () => {
         fn on_widget_event(&mut self, event: WidgetEventSourced, input_state: InputState) -> EventOps {
             let event = event.unwrap();
+            let conventions = self.widget_tag.ambient::<PlatformConventions>().unwrap_or_default();
 
             let TextEditOps {
                 allow_bubble,
@@ -136,17 +325,34 @@ macro_rules! event {
                 cursor_flash,
                 cursor_icon,
                 focus,
-            } = self.edit.adapt_event(&event, input_state);
-
-            match (cursor_flash, self.flash_timer) {
-                (Some(CursorFlashOp::Start), None) => {
-                    let timer_id = TimerId::new();
-                    self.widget_tag.timers_mut().insert(timer_id, Timer::new(Duration::new(1, 0)/2));
-                    self.flash_timer = Some(timer_id);
+                context_menu,
+            } = self.edit.adapt_event(&event, input_state, &conventions);
+
+            // The caret's blink timer is shared by every widget in the tree, so we just need to tell
+            // core when to start and stop blinking.
+            match cursor_flash {
+                Some(CursorFlashOp::Start) => {
+                    self.caret_active = true;
+                    self.widget_tag
+                        .set_caret_blinking(true)
+                        .reset_caret_blink();
+                },
+                Some(CursorFlashOp::End) => {
+                    self.caret_active = false;
+                    self.widget_tag
+                        .set_caret_blinking(false)
+                        .request_relayout();
                 },
-                (Some(CursorFlashOp::End), Some(timer_id)) => {
-                    self.widget_tag.timers_mut().remove(&timer_id);
-                    self.flash_timer = None;
+                None => ()
+            }
+
+            // The renderer doesn't report where the caret gets drawn, so the input method's
+            // composition window gets placed by the whole box instead.
+            match event {
+                WidgetEvent::GainFocus(..) |
+                WidgetEvent::ImePreedit{..} => {
+                    let rect = BoundBox::new2(0, 0, self.bounds.width(), self.bounds.height());
+                    self.widget_tag.set_ime_caret_rect(rect).ok();
                 },
                 _ => ()
             }
@@ -155,18 +361,22 @@ macro_rules! event {
                 self.widget_tag.request_redraw();
             }
 
-            match event {
-                WidgetEvent::Timer{timer_id, times_triggered, ..} if Some(timer_id) == self.flash_timer => {
-                    self.edit.cursor_data.draw_cursor = times_triggered % 2 == 0;
-                    self.widget_tag.request_redraw();
-                },
-                _ => ()
-            };
-
             if let Some(cursor_icon) = cursor_icon {
                 self.widget_tag.set_cursor_icon(cursor_icon).ok();
             }
 
+            if let (true, WidgetEvent::ContextMenuRequested{ pos }) = (context_menu, &event) {
+                let anchor = match *pos {
+                    Some(pos) => {
+                        let pos = input_state.coords.local_to_window_point(pos);
+                        BoundBox::new(pos, pos)
+                    },
+                    None => input_state.coords.window_rect
+                };
+                let menu = Menu::new(conventions.edit_menu_model(self.edit.has_selection()));
+                self.widget_tag.broadcast_message(OpenPopup::new(anchor, menu));
+            }
+
             EventOps {
                 focus,
                 bubble: allow_bubble && event.default_bubble(),
@@ -225,6 +435,8 @@ impl Widget for LineBox {
 
 render!(EditBox, EditBoxTheme);
 render!(LineBox, LineBoxTheme);
+menu!(EditBox);
+menu!(LineBox);
 
 impl WidgetTheme for EditBoxTheme {
     type Fallback = !;
@@ -4,17 +4,22 @@
 
 use derin_core::{
     LoopFlow,
-    event::{EventOps, WidgetEventSourced, InputState},
-    widget::{WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState},
+    widget::{BoxedWidget, WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
     render::{Renderer, SubFrame, WidgetTheme},
 };
 use crate::{
-    container::WidgetContainer,
-    layout::GridLayout,
+    container::{DynContainer, TemplateSlots, WidgetContainer},
+    layout::{DynLayout, GridLayout, SlotLayout},
+    selection::{SelectionChanged, SelectionModel},
 };
 
+use crate::cgmath::Point2;
 use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
-use derin_common_types::layout::{SizeBounds, WidgetPos};
+use derin_common_types::{
+    buttons::{ModifierKeys, MouseButton},
+    layout::{AbsolutePos, Axis, Margins, SizeBounds, WidgetPos},
+};
 
 use std::cell::RefCell;
 
@@ -32,11 +37,34 @@ pub struct Group<C, L>
     bounds: BoundBox<D2, i32>,
     layout_engine: GridEngine,
     container: C,
-    layout: L
+    layout: L,
+    rubber_band: Option<RubberBand>
+}
+
+#[derive(Debug, Clone)]
+struct RubberBand {
+    model: SelectionModel,
+    /// The point the drag started at and the cursor's current position, relative to the group.
+    /// `None` when the marquee isn't being dragged.
+    drag: Option<(Point2<i32>, Point2<i32>)>,
+    /// The selection from before the drag started, which gets kept if `Shift` was held.
+    base_selection: Vec<WidgetIdent>
 }
 
+/// A group whose children are added and removed at runtime.
+pub type DynGroup<R> = Group<DynContainer<R>, DynLayout>;
+
+/// A composite widget whose named slots are filled with arbitrary widgets. See [`TemplateSlots`]
+/// for how to declare the slots.
+///
+/// [`TemplateSlots`]: ../container/trait.TemplateSlots.html
+pub type Template<S> = Group<S, SlotLayout>;
+
 #[derive(Debug, Clone, Default)]
-pub struct GroupTheme(());
+pub struct GroupTheme {
+    /// The rubber band selection's marquee, relative to the group, while it's being dragged.
+    pub marquee: Option<BoundBox<D2, i32>>
+}
 
 impl<C, L> Group<C, L>
     where L: GridLayout
@@ -48,7 +76,8 @@ impl<C, L> Group<C, L>
             widget_tag: WidgetTag::new(),
             bounds: BoundBox::new2(0, 0, 0, 0),
             layout_engine: GridEngine::new(),
-            container, layout
+            container, layout,
+            rubber_band: None
         }
     }
 
@@ -59,8 +88,120 @@ impl<C, L> Group<C, L>
 
     /// Retrieve the widgets contained within the group, for mutation.
     pub fn container_mut(&mut self) -> &mut C {
+        self.widget_tag.request_relayout();
         &mut self.container
     }
+
+    /// Retrieve the group's layout.
+    pub fn layout(&self) -> &L {
+        &self.layout
+    }
+
+    /// Retrieve the group's layout, for mutation.
+    pub fn layout_mut(&mut self) -> &mut L {
+        self.widget_tag.request_relayout();
+        &mut self.layout
+    }
+
+    /// Retrieves whether or not the group is a focus scope.
+    pub fn focus_scope(&self) -> bool {
+        self.widget_tag.focus_scope()
+    }
+
+    /// Sets whether or not the group is a focus scope, such as a dialog or a pane.
+    ///
+    /// Pressing `Enter` or `Escape` within a focus scope clicks the scope's default or cancel
+    /// button, as set by `Button::set_scope_button`.
+    pub fn set_focus_scope(&mut self, focus_scope: bool) {
+        self.widget_tag.set_focus_scope(focus_scope);
+    }
+
+    /// Retrieves the selection updated by rubber band selection, if it's enabled.
+    pub fn rubber_band_selection(&self) -> Option<&SelectionModel> {
+        self.rubber_band.as_ref().map(|r| &r.model)
+    }
+
+    /// Enables or disables rubber band selection.
+    ///
+    /// While enabled, clicking on the group's background and dragging draws a marquee, and every
+    /// child that intersects the marquee gets selected in `model`. Holding `Shift` when the drag
+    /// starts adds to the existing selection instead of replacing it.
+    pub fn set_rubber_band_selection(&mut self, model: Option<SelectionModel>) {
+        self.rubber_band = model.map(|model| RubberBand {
+            model,
+            drag: None,
+            base_selection: Vec::new()
+        });
+        self.widget_tag.request_redraw();
+    }
+
+    /// Registers a function that gets called whenever a message of type `A` reaches the group.
+    ///
+    /// This lets the group's owner react to messages sent from inside of the tree, such as adding
+    /// or removing a `DynGroup`'s children when a menu item gets activated.
+    pub fn register_message<A>(&mut self, f: impl 'static + FnMut(&mut Group<C, L>, &A))
+        where C: 'static,
+              L: 'static,
+              A: 'static
+    {
+        self.widget_tag.register_message(f);
+    }
+}
+
+impl RubberBand {
+    fn marquee(&self) -> Option<BoundBox<D2, i32>> {
+        self.drag.map(|(start, end)| BoundBox::new2(
+            start.x.min(end.x),
+            start.y.min(end.y),
+            start.x.max(end.x),
+            start.y.max(end.y)
+        ))
+    }
+}
+
+impl<S> Group<S, SlotLayout>
+    where S: TemplateSlots
+{
+    /// Create a new template, with its slots filled with the widgets in `slots`.
+    pub fn template(slots: S) -> Template<S> {
+        Group::new(slots, SlotLayout::new(S::slots(), Margins::default()))
+    }
+
+    /// Retrieve the template's slots.
+    pub fn slots(&self) -> &S {
+        self.container()
+    }
+
+    /// Retrieve the template's slots, for mutation.
+    pub fn slots_mut(&mut self) -> &mut S {
+        self.container_mut()
+    }
+}
+
+impl<R: Renderer> Group<DynContainer<R>, DynLayout> {
+    /// Inserts a widget into the group at the given grid position. If a widget with the same ident
+    /// is already in the group, the old widget is replaced and returned.
+    pub fn insert_child(&mut self, ident: WidgetIdent, widget: impl Widget, position: WidgetPos) -> Option<BoxedWidget<R>> {
+        self.layout_mut().set_position(ident.clone(), Some(position));
+        self.container_mut().insert(ident, widget)
+    }
+
+    /// Inserts a widget into the group, positioned relative to the group's rectangle.
+    pub fn insert_child_absolute(&mut self, ident: WidgetIdent, widget: impl Widget, position: AbsolutePos) -> Option<BoxedWidget<R>> {
+        self.layout_mut().set_absolute_position(ident.clone(), Some(position));
+        self.container_mut().insert(ident, widget)
+    }
+
+    /// Removes the widget with the given ident from the group, returning it if it existed.
+    pub fn remove_child(&mut self, ident: &WidgetIdent) -> Option<BoxedWidget<R>> {
+        self.layout_mut().remove(ident);
+        self.container_mut().remove(ident)
+    }
+
+    /// Moves the widget with the given ident to a new grid position.
+    pub fn set_child_position(&mut self, ident: WidgetIdent, position: WidgetPos) {
+        self.layout_mut().set_position(ident, Some(position));
+    }
 }
 
 impl<C, L> Widget for Group<C, L>
@@ -86,8 +227,47 @@ impl<C, L> Widget for Group<C, L>
         self.layout_engine.actual_size_bounds()
     }
 
-    #[inline]
-    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+    fn on_widget_event(&mut self, event: WidgetEventSourced, input_state: InputState) -> EventOps {
+        if let (Some(rubber_band), WidgetEventSourced::This(event)) = (self.rubber_band.as_mut(), event) {
+            let old_marquee = rubber_band.marquee();
+            match event {
+                WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left, ..} => {
+                    rubber_band.base_selection = match input_state.modifiers.contains(ModifierKeys::SHIFT) {
+                        true => rubber_band.model.selected(),
+                        false => Vec::new()
+                    };
+                    rubber_band.drag = Some((pos, pos));
+                },
+                WidgetEvent::MouseMove{new_pos, ..} => {
+                    if let Some((start, _)) = rubber_band.drag {
+                        rubber_band.drag = Some((start, new_pos));
+                    }
+                },
+                WidgetEvent::MouseUp{button: MouseButton::Left, ..} => {
+                    rubber_band.drag = None;
+                },
+                _ => ()
+            }
+
+            if rubber_band.marquee() != old_marquee {
+                if let Some(marquee) = rubber_band.marquee() {
+                    let mut selection = rubber_band.base_selection.clone();
+                    self.container.children::<_>(|summary| {
+                        if summary.widget().rect().intersect_rect(marquee).is_some() {
+                            selection.push(summary.ident.clone());
+                        }
+                        LoopFlow::Continue
+                    });
+
+                    if rubber_band.model.set_selection(selection) {
+                        let source = self.widget_tag.widget_id();
+                        self.widget_tag.broadcast_message(SelectionChanged{ source });
+                    }
+                }
+                self.widget_tag.request_redraw();
+            }
+        }
+
         // TODO: PASS FOCUS THROUGH SELF
         EventOps {
             focus: None,
@@ -141,7 +321,9 @@ impl<R, C, L> WidgetRenderable<R> for Group<C, L>
     type Theme = GroupTheme;
 
     fn theme(&self) -> GroupTheme {
-        GroupTheme(())
+        GroupTheme {
+            marquee: self.rubber_band.as_ref().and_then(|r| r.marquee())
+        }
     }
 
     fn render(&mut self, frame: &mut R::SubFrame) {
@@ -169,29 +351,61 @@ impl<R, C, L> WidgetRenderable<R> for Group<C, L>
             } = *hc;
 
             let num_children = self.num_children();
+            let self_dims = DimsBox::new2(self.bounds.width(), self.bounds.height());
             self.container.children::<_>(|summary| {
                 let widget_size_bounds = summary.widget().size_bounds();
-                let mut layout_hints = self.layout.positions(summary.ident, summary.index, num_children).unwrap_or(WidgetPos::default());
+                let bound_size_bounds = |size_bounds: SizeBounds| SizeBounds {
+                    min: size_bounds.bound_rect(widget_size_bounds.min),
+                    max: size_bounds.bound_rect(widget_size_bounds.max),
+                };
 
-                layout_hints.size_bounds = SizeBounds {
-                    min: layout_hints.size_bounds.bound_rect(widget_size_bounds.min),
-                    max: layout_hints.size_bounds.bound_rect(widget_size_bounds.max),
+                let layout_hints = match self.layout.absolute_position(summary.ident.clone(), summary.index, num_children) {
+                    Some(absolute_pos) => WidgetPos {
+                        absolute: Some(AbsolutePos {
+                            size_bounds: bound_size_bounds(absolute_pos.size_bounds),
+                            ..absolute_pos
+                        }),
+                        ..WidgetPos::default()
+                    },
+                    None => {
+                        let mut layout_hints = self.layout.positions(summary.ident, summary.index, num_children).unwrap_or(WidgetPos::default());
+                        layout_hints.size_bounds = bound_size_bounds(layout_hints.size_bounds);
+                        layout_hints
+                    }
                 };
                 hints_vec.push(layout_hints);
                 rects_vec.push(Ok(BoundBox::new2(0, 0, 0, 0)));
                 LoopFlow::Continue
             });
 
-            self.layout_engine.desired_size = DimsBox::new2(self.bounds.width(), self.bounds.height());
-            self.layout_engine.set_grid_size(self.layout.grid_size(num_children));
+            self.layout_engine.desired_size = self_dims;
+            self.layout_engine.auto_flow = self.layout.auto_flow(num_children);
+            let (col_template, row_template) = (self.layout.col_template(num_children), self.layout.row_template(num_children));
+            let mut grid_size = self.layout.grid_size(num_children);
+            if let Some(ref col_template) = col_template {
+                grid_size.x = col_template.len();
+            }
+            if let Some(ref row_template) = row_template {
+                grid_size.y = row_template.len();
+            }
+            self.layout_engine.set_grid_size(grid_size);
+            if let Some(col_template) = col_template {
+                col_template.apply(&mut self.layout_engine, Axis::X);
+            }
+            if let Some(row_template) = row_template {
+                row_template.apply(&mut self.layout_engine, Axis::Y);
+            }
+            self.layout_engine.gap = self.layout.gap(num_children);
+            self.layout_engine.direction = self.layout.direction(num_children);
             self.layout_engine.update_engine(hints_vec, rects_vec, update_heap_cache);
 
             let mut rects_iter = rects_vec.drain(..);
             self.container.children_mut::<_>(|mut summary| {
-                match rects_iter.next() {
-                    Some(rect) => *summary.widget_mut().rect_mut() = rect.unwrap_or(BoundBox::new2(0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF)),
+                let rect = match rects_iter.next() {
+                    Some(rect) => rect,
                     None => return LoopFlow::Break
-                }
+                };
+                *summary.widget_mut().rect_mut() = rect.unwrap_or(BoundBox::new2(0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF));
                 LoopFlow::Continue
             });
 
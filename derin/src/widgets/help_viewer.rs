@@ -0,0 +1,148 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    event::{EventOps, WidgetEventSourced, InputState},
+    help::HelpRequest,
+    widget::{WidgetTag, WidgetRenderable, Widget},
+    render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+};
+
+use cgmath_geometry::{D2, rect::BoundBox};
+use derin_common_types::layout::SizeBounds;
+use std::collections::HashMap;
+
+
+/// A widget that displays the help text for whichever widget the user last requested help on.
+///
+/// Help is requested by pressing `F1` over a widget, or by pressing `Shift+F1` and clicking on a
+/// widget. The viewer looks up the requested widget's help topic in its set of topics and displays
+/// the associated text. If the topic isn't in the set, the viewer displays its `missing_text`.
+#[derive(Debug, Clone)]
+pub struct HelpViewer {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    size_bounds: SizeBounds,
+
+    topics: HashMap<String, String>,
+    missing_text: String,
+    topic: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HelpViewerTheme(());
+
+impl WidgetTheme for HelpViewerTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {None}
+}
+
+impl HelpViewer {
+    /// Create a new help viewer, with the given map of help topics to help text.
+    pub fn new(topics: HashMap<String, String>, missing_text: String) -> HelpViewer {
+        let mut widget_tag = WidgetTag::new();
+        widget_tag.register_message(Self::on_help_request);
+        HelpViewer {
+            widget_tag,
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            size_bounds: SizeBounds::default(),
+
+            topics,
+            missing_text,
+            topic: None,
+        }
+    }
+
+    /// Retrieves the topic currently being displayed.
+    pub fn topic(&self) -> Option<&str> {
+        self.topic.as_ref().map(|t| &t[..])
+    }
+
+    /// Display the help text for the given topic.
+    pub fn show_topic(&mut self, topic: Option<&str>) {
+        self.widget_tag
+            .request_redraw()
+            .request_relayout();
+
+        self.topic = topic.map(String::from);
+    }
+
+    /// Retrieves the map of help topics to help text.
+    pub fn topics(&self) -> &HashMap<String, String> {
+        &self.topics
+    }
+
+    /// Retrieves the map of help topics to help text, for mutation.
+    ///
+    /// Calling this function forces the viewer to be re-drawn, so you're discouraged from calling
+    /// it unless you're actually changing the topics.
+    pub fn topics_mut(&mut self) -> &mut HashMap<String, String> {
+        self.widget_tag
+            .request_redraw()
+            .request_relayout();
+
+        &mut self.topics
+    }
+
+    /// Retrieves the text currently being displayed.
+    pub fn text(&self) -> &str {
+        match self.topic {
+            Some(ref topic) => self.topics.get(topic).unwrap_or(&self.missing_text),
+            None => "",
+        }
+    }
+
+    fn on_help_request(&mut self, request: &HelpRequest) {
+        self.show_topic(Some(&request.topic));
+    }
+}
+
+impl Widget for HelpViewer {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: true,
+        }
+    }
+}
+
+impl<R> WidgetRenderable<R> for HelpViewer
+    where R: Renderer
+{
+    type Theme = HelpViewerTheme;
+    fn theme(&self) -> HelpViewerTheme {
+        HelpViewerTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, layout: &mut R::Layout) {
+        layout.prepare_string(self.text());
+
+        let result = layout.finish();
+        self.size_bounds = result.size_bounds;
+    }
+}
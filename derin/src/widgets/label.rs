@@ -3,11 +3,14 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use derin_core::{
-    event::{EventOps, WidgetEventSourced, InputState},
+    event::{EventOps, FocusChange, WidgetEvent, WidgetEventSourced, InputState},
     widget::{WidgetTag, WidgetRenderable, Widget},
     render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
 };
-use crate::widgets::Contents;
+use crate::widgets::{
+    Contents,
+    assistants::mnemonic::prepare_mnemonic_string,
+};
 
 use cgmath_geometry::{D2, rect::BoundBox};
 use derin_common_types::layout::SizeBounds;
@@ -15,7 +18,9 @@ use derin_common_types::layout::SizeBounds;
 
 /// A simple, non-interactive label.
 ///
-/// Can display text or an image, depending on what's in `contents`.
+/// Can display text or an image, depending on what's in `contents`. If the text contains a
+/// mnemonic (such as `"&Name"`), activating the mnemonic gives keyboard focus to the widget after
+/// the label.
 #[derive(Debug, Clone)]
 pub struct Label {
     widget_tag: WidgetTag,
@@ -82,9 +87,14 @@ impl Widget for Label {
     }
 
     #[inline]
-    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        let focus = match event {
+            WidgetEventSourced::This(WidgetEvent::MnemonicActivated) => Some(FocusChange::Next),
+            _ => None
+        };
+
         EventOps {
-            focus: None,
+            focus,
             bubble: true,
         }
     }
@@ -104,7 +114,7 @@ impl<R> WidgetRenderable<R> for Label
 
     fn update_layout(&mut self, layout: &mut R::Layout) {
         match self.contents {
-            Contents::Text(ref s) => layout.prepare_string(s),
+            Contents::Text(ref s) => prepare_mnemonic_string(layout, &mut self.widget_tag, s),
             Contents::Icon(ref i) => layout.prepare_icon(i),
         }
 
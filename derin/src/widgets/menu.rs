@@ -0,0 +1,464 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState, MouseHoverChange},
+    widget::{WidgetId, WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+};
+use derin_common_types::layout::SizeBounds;
+use crate::{
+    menu::{MenuActivated, MenuItem, MenuModel},
+    widgets::{
+        ClosePopup, OpenPopup,
+        assistants::ButtonState,
+    },
+};
+
+use crate::cgmath::{EuclideanSpace, Point2};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+/// A popup menu, displaying the items of a `MenuModel` in a column.
+///
+/// Menus are meant to be opened as popups in a `PopupLayer<_, Menu>`. Clicking an action closes
+/// every open popup and broadcasts a `MenuActivated` message, and clicking a submenu opens the
+/// submenu next to its item.
+#[derive(Debug, Clone)]
+pub struct Menu {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    entries: Vec<MenuEntry>,
+    size_bounds: SizeBounds,
+}
+
+#[derive(Debug, Clone)]
+struct MenuEntry {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    menu_id: WidgetId,
+    item: MenuItem,
+    state: ButtonState,
+    size_bounds: SizeBounds,
+}
+
+/// A container that opens a context menu when its contents request one.
+#[derive(Debug, Clone)]
+pub struct ContextMenu<W> {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    widget: W,
+    menu: MenuModel,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MenuTheme(());
+
+#[derive(Debug, Clone, Copy)]
+pub struct MenuEntryTheme {
+    pub state: ButtonState,
+    pub enabled: bool,
+    pub separator: bool,
+}
+
+impl Menu {
+    /// Creates a menu displaying the given items.
+    pub fn new(model: MenuModel) -> Menu {
+        let widget_tag = WidgetTag::new();
+        let menu_id = widget_tag.widget_id();
+        Menu {
+            entries: model.items.into_iter().map(|item| MenuEntry {
+                widget_tag: WidgetTag::new(),
+                rect: BoundBox::new2(0, 0, 0, 0),
+                menu_id,
+                item,
+                state: ButtonState::Normal,
+                size_bounds: SizeBounds::default(),
+            }).collect(),
+            widget_tag,
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            size_bounds: SizeBounds::default(),
+        }
+    }
+
+    /// Retrieves the items displayed in the menu.
+    pub fn items(&self) -> impl '_ + Iterator<Item=&MenuItem> {
+        self.entries.iter().map(|e| &e.item)
+    }
+}
+
+impl<W> ContextMenu<W> {
+    /// Creates a container that opens `menu` when `widget` requests a context menu.
+    pub fn new(widget: W, menu: MenuModel) -> ContextMenu<W> {
+        ContextMenu {
+            widget_tag: WidgetTag::new(),
+            rect: BoundBox::new2(0, 0, 0, 0),
+            widget,
+            menu,
+        }
+    }
+
+    /// Retrieves the contained widget.
+    pub fn widget(&self) -> &W {
+        &self.widget
+    }
+
+    /// Retrieves the contained widget, for mutation.
+    pub fn widget_mut(&mut self) -> &mut W {
+        self.widget_tag.request_relayout();
+        &mut self.widget
+    }
+
+    /// Retrieves the menu that gets opened.
+    pub fn menu(&self) -> &MenuModel {
+        &self.menu
+    }
+
+    /// Retrieves the menu that gets opened, for mutation. Menus that are already open don't get
+    /// changed.
+    pub fn menu_mut(&mut self) -> &mut MenuModel {
+        &mut self.menu
+    }
+}
+
+impl Widget for Menu {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: event.default_bubble(),
+        }
+    }
+}
+
+impl Widget for MenuEntry {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        &mut self.rect
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, input_state: InputState) -> EventOps {
+        use self::WidgetEvent::*;
+        let bubble = event.default_bubble();
+
+        let new_state = match (event.unwrap(), self.item.enabled()) {
+            (_, false) => ButtonState::Normal,
+            (MouseMove{hover_change: Some(ref change), ..}, true) => match change {
+                MouseHoverChange::Enter => ButtonState::Hover,
+                MouseHoverChange::Exit => ButtonState::Normal,
+                _ => self.state
+            },
+            (MouseDown{..}, true) => ButtonState::Pressed,
+            (MouseUp{in_widget: true, pressed_in_widget: true, ..}, true) => {
+                match self.item {
+                    MenuItem::Action{ ref id, .. } => {
+                        self.widget_tag.broadcast_message(ClosePopup::All);
+                        self.widget_tag.broadcast_message(MenuActivated{ id: id.clone() });
+                    },
+                    MenuItem::Submenu{ ref menu, .. } => {
+                        // Anchoring the submenu to the entry's top-right corner places it to the
+                        // right of the entry.
+                        let window_rect = input_state.coords.window_rect;
+                        let corner = Point2::new(window_rect.max().x, window_rect.min().y);
+                        self.widget_tag.broadcast_message(ClosePopup::After(self.menu_id));
+                        self.widget_tag.broadcast_message(OpenPopup::new(
+                            BoundBox::new(corner, corner),
+                            Menu::new(menu.clone()),
+                        ));
+                    },
+                    MenuItem::Separator => ()
+                }
+                ButtonState::Hover
+            },
+            (MouseUp{in_widget: false, ..}, true) => ButtonState::Normal,
+            _ => self.state
+        };
+
+        if new_state != self.state {
+            self.widget_tag.request_redraw();
+            self.state = new_state;
+        }
+
+        EventOps {
+            focus: None,
+            bubble,
+        }
+    }
+}
+
+impl<W> Widget for ContextMenu<W>
+    where W: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.rect
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.widget.size_bounds()
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, input_state: InputState) -> EventOps {
+        let mut bubble = event.default_bubble();
+
+        if let WidgetEvent::ContextMenuRequested{ pos } = event.unwrap() {
+            // Mouse requests open the menu at the mouse, and keyboard requests open it below the
+            // container.
+            let anchor = match pos {
+                Some(pos) => {
+                    let pos = input_state.coords.local_to_window_point(pos);
+                    BoundBox::new(pos, pos)
+                },
+                None => input_state.coords.window_rect
+            };
+            self.widget_tag.broadcast_message(OpenPopup::new(anchor, Menu::new(self.menu.clone())));
+            bubble = false;
+        }
+
+        EventOps {
+            focus: None,
+            bubble,
+        }
+    }
+}
+
+impl Parent for Menu {
+    fn num_children(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(index) => self.framed_child_by_index(index as usize),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(index) => self.framed_child_by_index_mut(index as usize),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        for (index, entry) in self.entries.iter().enumerate() {
+            if let LoopFlow::Break = for_each(WidgetInfo::new(WidgetIdent::Num(index as u32), index, entry)) {
+                return;
+            }
+        }
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            if let LoopFlow::Break = for_each(WidgetInfoMut::new(WidgetIdent::Num(index as u32), index, entry)) {
+                return;
+            }
+        }
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        self.entries.get(index).map(|entry| WidgetInfo::new(WidgetIdent::Num(index as u32), index, entry))
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        self.entries.get_mut(index).map(|entry| WidgetInfoMut::new(WidgetIdent::Num(index as u32), index, entry))
+    }
+}
+
+impl<W> Parent for ContextMenu<W>
+    where W: Widget
+{
+    fn num_children(&self) -> usize {
+        1
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+}
+
+impl<R> WidgetRenderable<R> for Menu
+    where R: Renderer
+{
+    type Theme = MenuTheme;
+
+    fn theme(&self) -> MenuTheme {
+        MenuTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        // Entries are stacked in a column, each as tall as its minimum height and as wide as the
+        // widest entry.
+        let width = self.entries.iter()
+            .map(|e| e.size_bounds.min.width())
+            .max().unwrap_or(0)
+            .max(self.bounds.width());
+        let mut y = 0;
+        for entry in &mut self.entries {
+            let height = entry.size_bounds.min.height();
+            let entry_rect = BoundBox::from(DimsBox::new2(width, height)) + Point2::new(0, y).to_vec();
+            if entry.rect != entry_rect {
+                entry.rect = entry_rect;
+            }
+            y += height;
+        }
+
+        let min = DimsBox::new2(width, y);
+        if self.size_bounds.min != min {
+            self.size_bounds.min = min;
+            self.widget_tag.request_relayout();
+        }
+    }
+}
+
+impl<R> WidgetRenderable<R> for MenuEntry
+    where R: Renderer
+{
+    type Theme = MenuEntryTheme;
+
+    fn theme(&self) -> MenuEntryTheme {
+        MenuEntryTheme {
+            state: self.state,
+            enabled: self.item.enabled(),
+            separator: self.item == MenuItem::Separator,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, layout: &mut R::Layout) {
+        layout.prepare_string(&self.item.display_text());
+
+        let result = layout.finish();
+        self.size_bounds = result.size_bounds;
+    }
+}
+
+impl<W, R> WidgetRenderable<R> for ContextMenu<W>
+    where W: Widget,
+          R: Renderer
+{
+    type Theme = MenuTheme;
+
+    fn theme(&self) -> MenuTheme {
+        MenuTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let rect = BoundBox::new2(0, 0, self.rect.width(), self.rect.height());
+        if self.widget.rect() != rect {
+            *self.widget.rect_mut() = rect;
+        }
+    }
+}
+
+impl WidgetTheme for MenuTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
+
+impl WidgetTheme for MenuEntryTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
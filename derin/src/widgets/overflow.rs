@@ -0,0 +1,455 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState, MouseHoverChange},
+    widget::{WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+    sound::UiSound,
+};
+use derin_common_types::layout::{Axis, SizeBounds};
+use crate::{
+    container::WidgetContainer,
+    menu::{MenuItem, MenuModel},
+    widgets::{
+        Menu, OpenPopup,
+        assistants::ButtonState,
+    },
+};
+
+use crate::cgmath::Point2;
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+use std::sync::Arc;
+
+lazy_static!{
+    static ref MORE_IDENT: WidgetIdent = WidgetIdent::Str(Arc::from("overflow_more"));
+}
+
+/// The rectangle given to hidden children. It lies outside of the container, so hidden children
+/// don't get drawn and can't be hovered.
+const HIDDEN_RECT: BoundBox<D2, i32> = BoundBox {
+    min: Point2{ x: -1, y: -1 },
+    max: Point2{ x: -1, y: -1 },
+};
+
+/// A row or column of widgets that moves the widgets that don't fit behind a "More…" button.
+///
+/// Children are laid out along `axis` at their minimum size, in the order the container lists them.
+/// When they don't all fit, the container shows as many as it can and places a "More…" button after
+/// them. Clicking the button opens a `Menu` in the enclosing `PopupLayer`, listing the menu items
+/// set for the hidden children with `set_overflow_item`; hidden children without an item get left
+/// out of the menu. Which children are hidden gets recalculated whenever the container's rectangle
+/// changes.
+///
+/// Hidden children stay in the widget tree, but aren't drawn and don't receive input.
+#[derive(Debug, Clone)]
+pub struct Overflow<C> {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    axis: Axis,
+    container: C,
+    items: Vec<(WidgetIdent, MenuItem)>,
+    hidden: Vec<WidgetIdent>,
+    more: MoreButton,
+    size_bounds: SizeBounds,
+}
+
+#[derive(Debug, Clone)]
+struct MoreButton {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    label: String,
+    state: ButtonState,
+    menu: MenuModel,
+    size_bounds: SizeBounds,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OverflowTheme(());
+
+#[derive(Debug, Clone, Copy)]
+pub struct OverflowButtonTheme {
+    pub state: ButtonState,
+}
+
+impl<C> Overflow<C> {
+    /// Creates a container laying out the widgets in `container` along `axis`.
+    pub fn new(container: C, axis: Axis) -> Overflow<C> {
+        Overflow {
+            widget_tag: WidgetTag::new(),
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            axis,
+            container,
+            items: Vec::new(),
+            hidden: Vec::new(),
+            more: MoreButton {
+                widget_tag: WidgetTag::new(),
+                rect: HIDDEN_RECT,
+                label: "More…".to_string(),
+                state: ButtonState::Normal,
+                menu: MenuModel::default(),
+                size_bounds: SizeBounds::default(),
+            },
+            size_bounds: SizeBounds::default(),
+        }
+    }
+
+    /// Retrieve the widgets contained within the container.
+    pub fn container(&self) -> &C {
+        &self.container
+    }
+
+    /// Retrieve the widgets contained within the container, for mutation.
+    pub fn container_mut(&mut self) -> &mut C {
+        self.widget_tag.request_relayout();
+        &mut self.container
+    }
+
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+
+    pub fn set_axis(&mut self, axis: Axis) {
+        self.axis = axis;
+        self.widget_tag.request_relayout();
+    }
+
+    /// The text displayed on the "More…" button.
+    pub fn more_label(&self) -> &str {
+        &self.more.label
+    }
+
+    pub fn set_more_label(&mut self, label: String) {
+        self.more.label = label;
+        self.more.widget_tag.request_redraw().request_relayout();
+    }
+
+    /// Set the item listed in the "More…" menu while the child with the given ident is hidden, or
+    /// leave the child out of the menu if `item` is `None`.
+    pub fn set_overflow_item(&mut self, ident: WidgetIdent, item: Option<MenuItem>) {
+        self.items.retain(|(i, _)| *i != ident);
+        if let Some(item) = item {
+            self.items.push((ident, item));
+        }
+        self.update_menu();
+    }
+
+    /// The idents of the children that don't fit in the container, in order.
+    pub fn hidden_children(&self) -> &[WidgetIdent] {
+        &self.hidden
+    }
+
+    fn update_menu(&mut self) {
+        let items = &self.items;
+        self.more.menu = MenuModel::new(
+            self.hidden.iter()
+                .filter_map(|ident| items.iter().find(|(i, _)| i == ident))
+                .map(|(_, item)| item.clone())
+                .collect()
+        );
+    }
+}
+
+impl<C> Widget for Overflow<C>
+    where C: WidgetContainer<dyn Widget>
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: event.default_bubble(),
+        }
+    }
+
+    fn intercept_child_event(&mut self, _: &WidgetEvent, child_path: &[WidgetIdent], _: InputState) -> bool {
+        // Keeps mnemonics and the like from reaching hidden children.
+        child_path.first().map(|ident| self.hidden.contains(ident)).unwrap_or(false)
+    }
+}
+
+impl Widget for MoreButton {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        &mut self.rect
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, input_state: InputState) -> EventOps {
+        use self::WidgetEvent::*;
+        let bubble = event.default_bubble();
+
+        let new_state = match event.unwrap() {
+            MouseMove{hover_change: Some(ref change), ..} => match change {
+                MouseHoverChange::Enter => ButtonState::Hover,
+                MouseHoverChange::Exit => ButtonState::Normal,
+                _ => self.state
+            },
+            MouseDown{..} => ButtonState::Pressed,
+            MouseUp{in_widget: true, pressed_in_widget: true, ..} => {
+                self.widget_tag.play_sound(UiSound::Click).ok();
+                let anchor = input_state.coords.window_rect;
+                self.widget_tag.broadcast_message(OpenPopup::new(anchor, Menu::new(self.menu.clone())));
+                ButtonState::Hover
+            },
+            MouseUp{in_widget: false, ..} => ButtonState::Normal,
+            _ => self.state
+        };
+
+        if new_state != self.state {
+            self.widget_tag.request_redraw();
+            self.state = new_state;
+        }
+
+        EventOps {
+            focus: None,
+            bubble,
+        }
+    }
+}
+
+// The "More…" button comes after the container's children.
+impl<C> Parent for Overflow<C>
+    where C: WidgetContainer<dyn Widget>
+{
+    fn num_children(&self) -> usize {
+        self.container.num_children() + 1
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            _ if widget_ident == *MORE_IDENT => Some(WidgetInfo::new(MORE_IDENT.clone(), self.container.num_children(), &self.more)),
+            _ => self.container.framed_child(widget_ident).map(WidgetInfo::erase_subtype)
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            _ if widget_ident == *MORE_IDENT => Some(WidgetInfoMut::new(MORE_IDENT.clone(), self.container.num_children(), &mut self.more)),
+            _ => self.container.framed_child_mut(widget_ident).map(WidgetInfoMut::erase_subtype)
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        let mut flow = LoopFlow::Continue;
+        self.container.framed_children(|summary| {
+            flow = for_each(WidgetInfo::erase_subtype(summary));
+            flow
+        });
+        if let LoopFlow::Continue = flow {
+            let _ = for_each(WidgetInfo::new(MORE_IDENT.clone(), self.container.num_children(), &self.more));
+        }
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let mut flow = LoopFlow::Continue;
+        let num_children = self.container.num_children();
+        self.container.framed_children_mut(|summary| {
+            flow = for_each(WidgetInfoMut::erase_subtype(summary));
+            flow
+        });
+        if let LoopFlow::Continue = flow {
+            let _ = for_each(WidgetInfoMut::new(MORE_IDENT.clone(), num_children, &mut self.more));
+        }
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            _ if index == self.container.num_children() => Some(WidgetInfo::new(MORE_IDENT.clone(), index, &self.more)),
+            _ => self.container.framed_child_by_index(index).map(WidgetInfo::erase_subtype)
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            _ if index == self.container.num_children() => Some(WidgetInfoMut::new(MORE_IDENT.clone(), index, &mut self.more)),
+            _ => self.container.framed_child_by_index_mut(index).map(WidgetInfoMut::erase_subtype)
+        }
+    }
+}
+
+impl<C, R> WidgetRenderable<R> for Overflow<C>
+    where C: WidgetContainer<dyn Widget>,
+          R: Renderer
+{
+    type Theme = OverflowTheme;
+
+    fn theme(&self) -> OverflowTheme {
+        OverflowTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let axis = self.axis;
+        let along = |dims: DimsBox<D2, i32>| match axis {
+            Axis::X => dims.width(),
+            Axis::Y => dims.height()
+        };
+        let across = |dims: DimsBox<D2, i32>| match axis {
+            Axis::X => dims.height(),
+            Axis::Y => dims.width()
+        };
+        let rect_at = |offset: i32, length: i32, cross: i32| match axis {
+            Axis::X => BoundBox::new2(offset, 0, offset + length, cross),
+            Axis::Y => BoundBox::new2(0, offset, cross, offset + length)
+        };
+
+        let mut children = Vec::with_capacity(self.container.num_children());
+        self.container.children::<_>(|summary| {
+            children.push((summary.ident.clone(), summary.widget().size_bounds()));
+            LoopFlow::Continue
+        });
+
+        let dims = self.bounds.dims();
+        let more_bounds = self.more.size_bounds;
+        let total_length: i32 = children.iter().map(|(_, bounds)| along(bounds.min)).sum();
+        let num_visible = match total_length <= along(dims) {
+            true => children.len(),
+            false => {
+                let available = along(dims) - along(more_bounds.min);
+                children.iter()
+                    .scan(0, |length, (_, bounds)| {
+                        *length += along(bounds.min);
+                        Some(*length)
+                    })
+                    .take_while(|length| *length <= available)
+                    .count()
+            }
+        };
+
+        let mut rects = Vec::with_capacity(children.len());
+        let mut offset = 0;
+        for (index, (_, bounds)) in children.iter().enumerate() {
+            match index < num_visible {
+                true => {
+                    let length = along(bounds.min);
+                    let cross = match axis {
+                        Axis::X => bounds.bound_rect(DimsBox::new2(length, across(dims))).height(),
+                        Axis::Y => bounds.bound_rect(DimsBox::new2(across(dims), length)).width()
+                    };
+                    rects.push(rect_at(offset, length, cross));
+                    offset += length;
+                },
+                false => rects.push(HIDDEN_RECT)
+            }
+        }
+        let mut rects = rects.into_iter();
+        self.container.children_mut::<_>(|mut summary| {
+            let rect = match rects.next() {
+                Some(rect) => rect,
+                None => return LoopFlow::Break
+            };
+            if summary.widget().rect() != rect {
+                *summary.widget_mut().rect_mut() = rect;
+            }
+            LoopFlow::Continue
+        });
+
+        let more_rect = match num_visible < children.len() {
+            true => rect_at(offset, along(more_bounds.min), across(dims)),
+            false => HIDDEN_RECT
+        };
+        if self.more.rect != more_rect {
+            *self.more.rect_mut() = more_rect;
+        }
+
+        let hidden: Vec<_> = children[num_visible..].iter().map(|(ident, _)| ident.clone()).collect();
+        if hidden != self.hidden {
+            self.hidden = hidden;
+            self.update_menu();
+        }
+
+        // The container can shrink down to the "More…" button, and is as thick as its thickest
+        // child.
+        let thickness = children.iter().map(|(_, bounds)| across(bounds.min))
+            .chain(Some(across(more_bounds.min)))
+            .max().unwrap_or(0);
+        let length = match children.is_empty() {
+            true => 0,
+            false => i32::min(total_length, along(more_bounds.min))
+        };
+        self.size_bounds.min = match axis {
+            Axis::X => DimsBox::new2(length, thickness),
+            Axis::Y => DimsBox::new2(thickness, length)
+        };
+    }
+}
+
+impl<R> WidgetRenderable<R> for MoreButton
+    where R: Renderer
+{
+    type Theme = OverflowButtonTheme;
+
+    fn theme(&self) -> OverflowButtonTheme {
+        OverflowButtonTheme {
+            state: self.state,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, layout: &mut R::Layout) {
+        layout.prepare_string(&self.label);
+
+        let result = layout.finish();
+        self.size_bounds = result.size_bounds;
+    }
+}
+
+impl WidgetTheme for OverflowTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
+
+impl WidgetTheme for OverflowButtonTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
@@ -0,0 +1,607 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState},
+    timer::{Timer, TimerId},
+    widget::{WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, SubFrame, WidgetTheme},
+};
+use derin_common_types::{
+    buttons::{ModifierKeys, MouseButton},
+    cursor::CursorIcon,
+    layout::SizeBounds,
+};
+
+use crate::cgmath::{EuclideanSpace, InnerSpace, Point2, Vector2};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+use std::{
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
+
+/// The number of pixels of trackpad scrolling that zoom as much as a single line of `Ctrl`+wheel scrolling.
+const LINE_PX: f32 = 24.0;
+/// How much a single line of `Ctrl`+wheel scrolling zooms in or out.
+const ZOOM_PER_LINE: f32 = 1.1;
+/// How often the momentum gets updated.
+const MOMENTUM_FRAME: Duration = Duration::from_millis(16);
+/// The fraction of the momentum's velocity that's left after a second of coasting.
+const MOMENTUM_DECAY: f32 = 0.02;
+/// The velocity, in pixels per second, below which momentum stops.
+const MIN_VELOCITY: f32 = 20.0;
+/// If the mouse was held still for longer than this before being released, the view doesn't coast.
+const MOMENTUM_RELEASE_WINDOW: Duration = Duration::from_millis(50);
+
+/// The pan and zoom applied to the contents of a `PanZoomView`.
+///
+/// Set as an ambient value on the view's child, so that the child and its descendants can map
+/// between content and view coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+    /// The position of the content's origin, in the view's coordinates.
+    pub offset: Vector2<f32>,
+    /// How much the content is scaled up. `1.0` draws the content at its natural size.
+    pub scale: f32,
+}
+
+/// A viewport onto a larger area, like a map, node graph, or image, which the user can pan by
+/// dragging and zoom with `Ctrl` and the mouse wheel.
+///
+/// If the child has a minimum size, that's treated as the size of the content: the child's rect is
+/// that size scaled and offset by the view's transform, and the view can't be panned past the
+/// content's edges. Children without a minimum size, such as infinite canvases, fill the view and
+/// should apply the transform themselves, by reading the `ViewTransform` ambient value. The
+/// transform is available to all of the child's descendants either way.
+///
+/// By default, the child doesn't recieve mouse input, so that dragging anywhere in the view pans
+/// it. Interactive children can be enabled with `set_interactive`, in which case the view only pans
+/// when dragging parts of it the child doesn't cover.
+#[derive(Debug, Clone)]
+pub struct PanZoomView<W> {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    content: ContentLayer<W>,
+    scale_range: RangeInclusive<f32>,
+    drag: Option<PanDrag>,
+    /// The velocity the view is coasting at after a drag, in pixels per second.
+    velocity: Vector2<f32>,
+    /// The time of the last momentum update. `None` if the view isn't coasting.
+    momentum_tick: Option<Instant>,
+    timer_id: TimerId,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PanDrag {
+    last_pos: Point2<i32>,
+    last_time: Instant,
+}
+
+/// Wraps the child of the `PanZoomView`, so that the transform can be set as an ambient value and
+/// the child can be made input-transparent without access to the child's `WidgetTag`.
+#[derive(Debug, Clone)]
+struct ContentLayer<W> {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    transform: ViewTransform,
+    widget: W,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PanZoomViewTheme(());
+
+impl ViewTransform {
+    /// The transform that draws the content at its natural size, with its origin at the view's
+    /// origin.
+    pub fn identity() -> ViewTransform {
+        ViewTransform {
+            offset: Vector2::new(0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+
+    /// Convert a point from content coordinates to view coordinates.
+    #[inline]
+    pub fn content_to_view(&self, point: Point2<f32>) -> Point2<f32> {
+        point * self.scale + self.offset
+    }
+
+    /// Convert a point from view coordinates to content coordinates.
+    #[inline]
+    pub fn view_to_content(&self, point: Point2<f32>) -> Point2<f32> {
+        (point - self.offset) / self.scale
+    }
+}
+
+impl Default for ViewTransform {
+    #[inline]
+    fn default() -> ViewTransform {
+        ViewTransform::identity()
+    }
+}
+
+impl<W> PanZoomView<W> {
+    /// Creates a new view, which can be zoomed between a tenth and ten times the content's natural
+    /// size.
+    pub fn new(widget: W) -> PanZoomView<W> {
+        let mut content = ContentLayer {
+            widget_tag: WidgetTag::new(),
+            rect: BoundBox::new2(0, 0, 0, 0),
+            transform: ViewTransform::identity(),
+            widget,
+        };
+        content.widget_tag
+            .set_input_transparent(true)
+            .set_ambient(ViewTransform::identity());
+
+        PanZoomView {
+            widget_tag: WidgetTag::new(),
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            content,
+            scale_range: 0.1..=10.0,
+            drag: None,
+            velocity: Vector2::new(0.0, 0.0),
+            momentum_tick: None,
+            timer_id: TimerId::new(),
+        }
+    }
+
+    /// Retrieves the child.
+    pub fn widget(&self) -> &W {
+        &self.content.widget
+    }
+
+    /// Retrieves the child, for mutation.
+    pub fn widget_mut(&mut self) -> &mut W {
+        self.widget_tag.request_relayout().request_rescan();
+        &mut self.content.widget
+    }
+
+    /// Retrieves the current pan and zoom.
+    pub fn transform(&self) -> ViewTransform {
+        self.content.transform
+    }
+
+    /// Sets the pan and zoom, stopping any momentum. The transform gets constrained to the scale
+    /// range and the content's edges the next time the view is laid out.
+    pub fn set_transform(&mut self, transform: ViewTransform) {
+        self.stop_momentum();
+        self.update_transform(transform);
+    }
+
+    /// Retrieves the range the content can be scaled within.
+    pub fn scale_range(&self) -> RangeInclusive<f32> {
+        self.scale_range.clone()
+    }
+
+    pub fn set_scale_range(&mut self, scale_range: RangeInclusive<f32>) {
+        self.scale_range = scale_range;
+        self.widget_tag.request_relayout();
+    }
+
+    /// Whether or not the child recieves mouse input.
+    pub fn interactive(&self) -> bool {
+        !self.content.widget_tag.input_transparent()
+    }
+
+    /// Sets whether or not the child recieves mouse input.
+    ///
+    /// Views with interactive children only pan when dragging the parts of the view the child
+    /// doesn't cover. Mouse wheel input the child doesn't handle still pans and zooms the view.
+    pub fn set_interactive(&mut self, interactive: bool) {
+        self.content.widget_tag.set_input_transparent(!interactive);
+    }
+
+    /// Scale the content by `factor`, keeping the content under `point` fixed in place.
+    pub fn zoom_at(&mut self, point: Point2<f32>, factor: f32) {
+        let transform = self.content.transform;
+        let scale = clamp(transform.scale * factor, &self.scale_range);
+        let anchor = point.to_vec();
+
+        self.update_transform(ViewTransform {
+            offset: anchor + (transform.offset - anchor) * (scale / transform.scale),
+            scale,
+        });
+    }
+
+    /// Move the content by the given number of pixels.
+    pub fn pan_by(&mut self, delta: Vector2<f32>) {
+        let transform = self.content.transform;
+        self.update_transform(ViewTransform {
+            offset: transform.offset + delta,
+            ..transform
+        });
+    }
+
+    fn update_transform(&mut self, transform: ViewTransform) {
+        if transform != self.content.transform {
+            self.content.transform = transform;
+            self.content.widget_tag.set_ambient(transform);
+            self.widget_tag.request_relayout();
+        }
+    }
+
+    /// Constrain the transform so that the content stays within the view.
+    fn constrain_transform(&mut self)
+        where W: Widget
+    {
+        let mut transform = self.content.transform;
+        transform.scale = clamp(transform.scale, &self.scale_range);
+
+        if let Some(content_dims) = self.content.content_dims() {
+            let view_dims = Vector2::new(self.bounds.width() as f32, self.bounds.height() as f32);
+            let content_dims = content_dims * transform.scale;
+
+            // Content that's larger than the view can't be panned past its edges, and content
+            // that's smaller than the view can't be panned out of it.
+            let constrain_axis = |offset: f32, view: f32, content: f32| match content > view {
+                true => offset.min(0.0).max(view - content),
+                false => offset.max(0.0).min(view - content)
+            };
+            let offset = Vector2::new(
+                constrain_axis(transform.offset.x, view_dims.x, content_dims.x),
+                constrain_axis(transform.offset.y, view_dims.y, content_dims.y),
+            );
+
+            // Stop coasting along any axis that's hit an edge.
+            if offset.x != transform.offset.x {
+                self.velocity.x = 0.0;
+            }
+            if offset.y != transform.offset.y {
+                self.velocity.y = 0.0;
+            }
+            transform.offset = offset;
+        }
+
+        self.update_transform(transform);
+    }
+
+    fn start_momentum(&mut self) {
+        let coast = self.widget_tag.accessibility_prefs().animation_duration(MOMENTUM_FRAME).is_some();
+        match coast && self.velocity.magnitude() >= MIN_VELOCITY {
+            true => {
+                self.momentum_tick = Some(Instant::now());
+                self.widget_tag.timers_mut().insert(self.timer_id, Timer::new(MOMENTUM_FRAME));
+            },
+            false => self.stop_momentum()
+        }
+    }
+
+    fn stop_momentum(&mut self) {
+        self.velocity = Vector2::new(0.0, 0.0);
+        if self.momentum_tick.take().is_some() {
+            self.widget_tag.timers_mut().remove(&self.timer_id);
+        }
+    }
+
+    fn update_momentum(&mut self, now: Instant) {
+        let last_tick = match self.momentum_tick {
+            Some(tick) => tick,
+            None => return
+        };
+        let dt = as_secs(now.duration_since(last_tick));
+        self.momentum_tick = Some(now);
+
+        let velocity = self.velocity;
+        self.pan_by(velocity * dt);
+        self.velocity = velocity * MOMENTUM_DECAY.powf(dt);
+        if self.velocity.magnitude() < MIN_VELOCITY {
+            self.stop_momentum();
+        }
+    }
+}
+
+impl<W> ContentLayer<W>
+    where W: Widget
+{
+    /// The size of the content at its natural scale, or `None` if the child has no minimum size.
+    fn content_dims(&self) -> Option<Vector2<f32>> {
+        let min = self.widget.size_bounds().min;
+        match min.width() == 0 && min.height() == 0 {
+            true => None,
+            false => Some(Vector2::new(min.width() as f32, min.height() as f32))
+        }
+    }
+}
+
+fn clamp(value: f32, range: &RangeInclusive<f32>) -> f32 {
+    value.max(*range.start()).min(*range.end())
+}
+
+fn as_secs(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1_000_000_000.0
+}
+
+impl<W> Widget for PanZoomView<W>
+    where W: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        // The view shows however much of the content fits, so it doesn't need to be any size in
+        // particular.
+        SizeBounds::default()
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, input_state: InputState) -> EventOps {
+        let mut allow_bubble = true;
+
+        match event {
+            WidgetEventSourced::This(WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left, ..}) => {
+                self.stop_momentum();
+                self.drag = Some(PanDrag {
+                    last_pos: pos,
+                    last_time: Instant::now(),
+                });
+                self.widget_tag.set_cursor_icon(CursorIcon::Move).ok();
+            },
+            WidgetEventSourced::This(WidgetEvent::MouseMove{new_pos, ..}) => {
+                if let Some(drag) = self.drag {
+                    let now = Instant::now();
+                    let delta = (new_pos - drag.last_pos).cast::<f32>().unwrap();
+                    let dt = as_secs(now.duration_since(drag.last_time));
+                    if dt > 0.0 {
+                        // Smooth the velocity, since mouse movements are reported irregularly.
+                        self.velocity = self.velocity * 0.5 + delta / dt * 0.5;
+                    }
+
+                    self.pan_by(delta);
+                    self.drag = Some(PanDrag {
+                        last_pos: new_pos,
+                        last_time: now,
+                    });
+                }
+            },
+            WidgetEventSourced::This(WidgetEvent::MouseUp{button: MouseButton::Left, ..}) => {
+                if let Some(drag) = self.drag.take() {
+                    self.widget_tag.set_cursor_icon(CursorIcon::default()).ok();
+                    match drag.last_time.elapsed() <= MOMENTUM_RELEASE_WINDOW {
+                        true => self.start_momentum(),
+                        false => self.stop_momentum()
+                    }
+                }
+            },
+            WidgetEventSourced::This(WidgetEvent::Timer{timer_id, ..}) if timer_id == self.timer_id => {
+                self.update_momentum(Instant::now());
+            },
+            WidgetEventSourced::This(WidgetEvent::MouseScrollLines{dir, dir_px, in_widget: true}) |
+            WidgetEventSourced::Bubble(WidgetEvent::MouseScrollLines{dir, dir_px, in_widget: true}, _) => {
+                allow_bubble = false;
+                self.stop_momentum();
+                let dir = dir.cast::<f32>().unwrap();
+                match (input_state.modifiers.contains(ModifierKeys::CTRL), input_state.mouse_pos) {
+                    (true, Some(mouse_pos)) => self.zoom_at(mouse_pos.cast().unwrap(), ZOOM_PER_LINE.powf(dir.y)),
+                    (true, None) => (),
+                    (false, _) => self.pan_by(dir_px.cast::<f32>().unwrap())
+                }
+            },
+            WidgetEventSourced::This(WidgetEvent::MouseScrollPx{dir, in_widget: true, ..}) |
+            WidgetEventSourced::Bubble(WidgetEvent::MouseScrollPx{dir, in_widget: true, ..}, _) => {
+                allow_bubble = false;
+                self.stop_momentum();
+                let dir = dir.cast::<f32>().unwrap();
+                match (input_state.modifiers.contains(ModifierKeys::CTRL), input_state.mouse_pos) {
+                    (true, Some(mouse_pos)) => self.zoom_at(mouse_pos.cast().unwrap(), ZOOM_PER_LINE.powf(dir.y / LINE_PX)),
+                    (true, None) => (),
+                    (false, _) => self.pan_by(dir)
+                }
+            },
+            _ => ()
+        }
+
+        EventOps {
+            focus: None,
+            bubble: allow_bubble && event.default_bubble(),
+        }
+    }
+}
+
+impl<W> Widget for ContentLayer<W>
+    where W: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.rect
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        SizeBounds::default()
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: true,
+        }
+    }
+}
+
+impl<W> Parent for PanZoomView<W>
+    where W: Widget
+{
+    fn num_children(&self) -> usize {
+        1
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.content)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.content)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.content));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.content));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.content)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.content)),
+            _ => None
+        }
+    }
+}
+
+impl<W> Parent for ContentLayer<W>
+    where W: Widget
+{
+    fn num_children(&self) -> usize {
+        1
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+}
+
+impl<W, R> WidgetRenderable<R> for PanZoomView<W>
+    where W: Widget,
+          R: Renderer
+{
+    type Theme = PanZoomViewTheme;
+
+    fn theme(&self) -> PanZoomViewTheme {
+        PanZoomViewTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        self.constrain_transform();
+
+        let rect = BoundBox::new2(0, 0, self.bounds.width(), self.bounds.height());
+        if self.content.rect != rect {
+            *self.content.rect_mut() = rect;
+        }
+    }
+}
+
+impl<W, R> WidgetRenderable<R> for ContentLayer<W>
+    where W: Widget,
+          R: Renderer
+{
+    type Theme = PanZoomViewTheme;
+
+    fn theme(&self) -> PanZoomViewTheme {
+        PanZoomViewTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let new_rect = match self.content_dims() {
+            Some(content_dims) => {
+                let transform = self.transform;
+                let min = transform.content_to_view(Point2::new(0.0, 0.0));
+                let dims = DimsBox::new2(
+                    (content_dims.x * transform.scale).round() as i32,
+                    (content_dims.y * transform.scale).round() as i32,
+                );
+                let dims = self.widget.size_bounds().bound_rect(dims);
+                BoundBox::from(dims) + Vector2::new(min.x.round() as i32, min.y.round() as i32)
+            },
+            None => BoundBox::new2(0, 0, self.rect.width(), self.rect.height())
+        };
+        if self.widget.rect() != new_rect {
+            *self.widget.rect_mut() = new_rect;
+        }
+    }
+}
+
+impl WidgetTheme for PanZoomViewTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
@@ -0,0 +1,475 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState},
+    widget::{WidgetId, WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, SubFrame, WidgetTheme},
+};
+use derin_common_types::{
+    buttons::Key,
+    layout::SizeBounds,
+};
+
+use crate::cgmath::{EuclideanSpace, Point2};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+use std::{
+    cell::RefCell,
+    sync::Arc,
+};
+
+/// A container that displays popups - dropdown lists, menus, and the like - on top of its content.
+///
+/// Any widget inside the layer can open a popup by broadcasting an `OpenPopup` message, which
+/// carries the popup widget and the rectangle to anchor it to. Popups are placed just below their
+/// anchor, or just above it if there isn't room below, and are given their minimum size.
+///
+/// Popups receive input before the content: while any popups are open the content is made
+/// input-transparent, and clicking anywhere outside of the popups closes all of them. Pressing
+/// `Escape` inside of a popup closes it. The layer broadcasts a `PopupClosed` message for every
+/// popup it closes.
+///
+/// Anchors are given in window coordinates, so the layer should be used as the window's root
+/// widget. That also lets popups extend past the bounds of the widgets that open them.
+#[derive(Debug, Clone)]
+pub struct PopupLayer<W, P> {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    content: ContentLayer<W>,
+    popups: Vec<Popup<P>>,
+    next_ident: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Popup<P> {
+    ident: u32,
+    anchor: BoundBox<D2, i32>,
+    widget: P,
+}
+
+/// Wraps the layer's content, so that it can be made input-transparent without access to the
+/// content's `WidgetTag`.
+#[derive(Debug, Clone)]
+struct ContentLayer<W> {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    widget: W,
+}
+
+/// Message that opens a popup in the enclosing `PopupLayer`.
+///
+/// The popup type `P` has to match the layer's popup type, or the message gets ignored.
+#[derive(Debug)]
+pub struct OpenPopup<P> {
+    anchor: BoundBox<D2, i32>,
+    widget: RefCell<Option<P>>,
+}
+
+/// Message that closes popups in the enclosing `PopupLayer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClosePopup {
+    /// Close the popup with the given widget ID, along with every popup opened after it.
+    Popup(WidgetId),
+    /// Close every popup opened after the one with the given widget ID, leaving it open.
+    After(WidgetId),
+    /// Close every open popup.
+    All,
+}
+
+/// Message broadcast by a `PopupLayer` when it closes a popup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PopupClosed {
+    /// The widget ID of the popup that was closed.
+    pub popup: WidgetId,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PopupLayerTheme(());
+
+lazy_static!{
+    static ref CONTENT_IDENT: WidgetIdent = WidgetIdent::Str(Arc::from("content"));
+}
+
+impl<P> OpenPopup<P> {
+    /// Creates a message that opens `widget` as a popup, anchored to the given rectangle in window
+    /// coordinates. Widgets usually anchor popups to themselves, with `InputState::coords`.
+    pub fn new(anchor: BoundBox<D2, i32>, widget: P) -> OpenPopup<P> {
+        OpenPopup {
+            anchor,
+            widget: RefCell::new(Some(widget)),
+        }
+    }
+}
+
+impl<W, P> PopupLayer<W, P>
+    where W: Widget,
+          P: Widget
+{
+    /// Creates a new popup layer, displaying popups on top of `content`.
+    pub fn new(content: W) -> PopupLayer<W, P> {
+        let mut widget_tag = WidgetTag::new();
+        widget_tag.register_message(Self::on_open_popup);
+        widget_tag.register_message(Self::on_close_popup);
+        PopupLayer {
+            widget_tag,
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            content: ContentLayer {
+                widget_tag: WidgetTag::new(),
+                rect: BoundBox::new2(0, 0, 0, 0),
+                widget: content,
+            },
+            popups: Vec::new(),
+            next_ident: 0,
+        }
+    }
+
+    /// Retrieves the content the popups are displayed over.
+    pub fn content(&self) -> &W {
+        &self.content.widget
+    }
+
+    /// Retrieves the content the popups are displayed over, for mutation.
+    pub fn content_mut(&mut self) -> &mut W {
+        // The widget is a grandchild of this one, so any changes to its children won't get picked
+        // up unless our subtree gets rescanned.
+        self.widget_tag.request_relayout().request_rescan();
+        &mut self.content.widget
+    }
+
+    /// Retrieves the open popups, from the first opened to the last.
+    pub fn popups(&self) -> impl '_ + Iterator<Item=&P> {
+        self.popups.iter().map(|p| &p.widget)
+    }
+
+    /// Opens a popup, anchored to the given rectangle in window coordinates.
+    pub fn open_popup(&mut self, anchor: BoundBox<D2, i32>, widget: P) {
+        self.popups.push(Popup {
+            ident: self.next_ident,
+            anchor,
+            widget,
+        });
+        self.next_ident += 1;
+        self.update_content_transparency();
+    }
+
+    /// Closes every popup opened at or after the given index.
+    pub fn close_popups_from(&mut self, index: usize) {
+        if index >= self.popups.len() {
+            return;
+        }
+
+        for popup in self.popups.drain(index..) {
+            self.widget_tag.broadcast_message(PopupClosed{ popup: popup.widget.widget_id() });
+        }
+        self.update_content_transparency();
+    }
+
+    fn update_content_transparency(&mut self) {
+        self.content.widget_tag.set_input_transparent(!self.popups.is_empty());
+        self.widget_tag
+            .request_redraw()
+            .request_relayout();
+    }
+
+    fn on_open_popup(&mut self, open: &OpenPopup<P>) {
+        if let Some(widget) = open.widget.borrow_mut().take() {
+            self.open_popup(open.anchor, widget);
+        }
+    }
+
+    fn on_close_popup(&mut self, close: &ClosePopup) {
+        let index = match *close {
+            ClosePopup::Popup(popup_id) |
+            ClosePopup::After(popup_id) => match self.popups.iter().position(|p| p.widget.widget_id() == popup_id) {
+                Some(index) if *close == ClosePopup::After(popup_id) => index + 1,
+                Some(index) => index,
+                None => return
+            },
+            ClosePopup::All => 0
+        };
+        self.close_popups_from(index);
+    }
+}
+
+impl<W, P> Widget for PopupLayer<W, P>
+    where W: Widget,
+          P: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.content.widget.size_bounds()
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        let mut bubble = event.default_bubble();
+
+        match event {
+            // The content is input-transparent while popups are open, so any click that lands on
+            // the layer itself is outside of every popup.
+            WidgetEventSourced::This(WidgetEvent::MouseDown{in_widget: true, ..}) => self.close_popups_from(0),
+            WidgetEventSourced::Bubble(WidgetEvent::KeyDown(Key::Escape, _), path) => {
+                let popup_index = match path.first() {
+                    Some(WidgetIdent::Num(ident)) => self.popups.iter().position(|p| p.ident == *ident),
+                    _ => None
+                };
+                if let Some(index) = popup_index {
+                    self.close_popups_from(index);
+                    bubble = false;
+                }
+            },
+            _ => ()
+        }
+
+        EventOps {
+            focus: None,
+            bubble,
+        }
+    }
+}
+
+impl<W> Widget for ContentLayer<W>
+    where W: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.rect
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.widget.size_bounds()
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: true,
+        }
+    }
+}
+
+// The content comes first, so that the popups get drawn on top of it. Later popups get drawn on top
+// of earlier ones.
+impl<W, P> Parent for PopupLayer<W, P>
+    where W: Widget,
+          P: Widget
+{
+    fn num_children(&self) -> usize {
+        1 + self.popups.len()
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            _ if widget_ident == *CONTENT_IDENT => Some(WidgetInfo::new(CONTENT_IDENT.clone(), 0, &self.content)),
+            WidgetIdent::Num(ident) => {
+                let index = self.popups.iter().position(|p| p.ident == ident)?;
+                self.framed_child_by_index(index + 1)
+            },
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            _ if widget_ident == *CONTENT_IDENT => Some(WidgetInfoMut::new(CONTENT_IDENT.clone(), 0, &mut self.content)),
+            WidgetIdent::Num(ident) => {
+                let index = self.popups.iter().position(|p| p.ident == ident)?;
+                self.framed_child_by_index_mut(index + 1)
+            },
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        if let LoopFlow::Break = for_each(WidgetInfo::new(CONTENT_IDENT.clone(), 0, &self.content)) {
+            return;
+        }
+        for (index, popup) in self.popups.iter().enumerate() {
+            if let LoopFlow::Break = for_each(WidgetInfo::new(WidgetIdent::Num(popup.ident), index + 1, &popup.widget)) {
+                return;
+            }
+        }
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        if let LoopFlow::Break = for_each(WidgetInfoMut::new(CONTENT_IDENT.clone(), 0, &mut self.content)) {
+            return;
+        }
+        for (index, popup) in self.popups.iter_mut().enumerate() {
+            if let LoopFlow::Break = for_each(WidgetInfoMut::new(WidgetIdent::Num(popup.ident), index + 1, &mut popup.widget)) {
+                return;
+            }
+        }
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(CONTENT_IDENT.clone(), 0, &self.content)),
+            _ => self.popups.get(index - 1).map(|popup| WidgetInfo::new(WidgetIdent::Num(popup.ident), index, &popup.widget))
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(CONTENT_IDENT.clone(), 0, &mut self.content)),
+            _ => self.popups.get_mut(index - 1).map(|popup| WidgetInfoMut::new(WidgetIdent::Num(popup.ident), index, &mut popup.widget))
+        }
+    }
+}
+
+impl<W> Parent for ContentLayer<W>
+    where W: Widget
+{
+    fn num_children(&self) -> usize {
+        1
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.widget)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.widget)),
+            _ => None
+        }
+    }
+}
+
+impl<W, P, R> WidgetRenderable<R> for PopupLayer<W, P>
+    where W: Widget,
+          P: Widget,
+          R: Renderer
+{
+    type Theme = PopupLayerTheme;
+
+    fn theme(&self) -> PopupLayerTheme {
+        PopupLayerTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let dims = self.bounds.dims();
+        let content_rect = BoundBox::new2(0, 0, dims.width(), dims.height());
+        if self.content.rect != content_rect {
+            *self.content.rect_mut() = content_rect;
+        }
+
+        for popup in &mut self.popups {
+            let popup_dims = popup.widget.size_bounds().bound_rect(DimsBox::new2(0, 0));
+            let (width, height) = (popup_dims.width().min(dims.width()), popup_dims.height().min(dims.height()));
+            // Anchors are in window coordinates, and the layer's rect is relative to its parent.
+            // Those are the same for the root widget.
+            let anchor = popup.anchor - self.bounds.min().to_vec();
+
+            let x = anchor.min().x.min(dims.width() - width).max(0);
+            let y = match anchor.max().y + height <= dims.height() {
+                true => anchor.max().y,
+                false => anchor.min().y - height
+            };
+            let y = y.min(dims.height() - height).max(0);
+
+            let popup_rect = BoundBox::from(DimsBox::new2(width, height)) + Point2::new(x, y).to_vec();
+            if popup.widget.rect() != popup_rect {
+                *popup.widget.rect_mut() = popup_rect;
+            }
+        }
+    }
+}
+
+impl<W, R> WidgetRenderable<R> for ContentLayer<W>
+    where W: Widget,
+          R: Renderer
+{
+    type Theme = PopupLayerTheme;
+
+    fn theme(&self) -> PopupLayerTheme {
+        PopupLayerTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let rect = BoundBox::new2(0, 0, self.rect.width(), self.rect.height());
+        let size_bounds = self.widget.size_bounds();
+        let dims_bounded = size_bounds.bound_rect(rect.dims());
+        let new_rect = BoundBox::from(dims_bounded) + rect.min().to_vec();
+        if self.widget.rect() != new_rect {
+            *self.widget.rect_mut() = new_rect;
+        }
+    }
+}
+
+impl WidgetTheme for PopupLayerTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
@@ -17,7 +17,7 @@ use crate::{
     },
 };
 
-use derin_common_types::layout::{SizeBounds, WidgetPos};
+use derin_common_types::layout::{AbsolutePos, SizeBounds, WidgetPos};
 
 use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
 use std::cell::RefCell;
@@ -299,10 +299,24 @@ impl<R, C, L> WidgetRenderable<R> for RadioButtonList<C, L>
             let num_children = self.num_children();
             self.buttons.children::<_>(|summary| {
                 let widget_size_bounds = summary.widget().size_bounds();
-                let mut layout_hints = self.layout.positions(summary.ident, summary.index, num_children).unwrap_or(WidgetPos::default());
-                layout_hints.size_bounds = SizeBounds {
-                    min: layout_hints.size_bounds.bound_rect(widget_size_bounds.min),
-                    max: layout_hints.size_bounds.bound_rect(widget_size_bounds.max),
+                let bound_size_bounds = |size_bounds: SizeBounds| SizeBounds {
+                    min: size_bounds.bound_rect(widget_size_bounds.min),
+                    max: size_bounds.bound_rect(widget_size_bounds.max),
+                };
+
+                let layout_hints = match self.layout.absolute_position(summary.ident.clone(), summary.index, num_children) {
+                    Some(absolute_pos) => WidgetPos {
+                        absolute: Some(AbsolutePos {
+                            size_bounds: bound_size_bounds(absolute_pos.size_bounds),
+                            ..absolute_pos
+                        }),
+                        ..WidgetPos::default()
+                    },
+                    None => {
+                        let mut layout_hints = self.layout.positions(summary.ident, summary.index, num_children).unwrap_or(WidgetPos::default());
+                        layout_hints.size_bounds = bound_size_bounds(layout_hints.size_bounds);
+                        layout_hints
+                    }
                 };
                 hints_vec.push(layout_hints);
                 rects_vec.push(Ok(BoundBox::new2(0, 0, 0, 0)));
@@ -311,14 +325,17 @@ impl<R, C, L> WidgetRenderable<R> for RadioButtonList<C, L>
 
             self.layout_engine.desired_size = self.rect.dims();
             self.layout_engine.set_grid_size(self.layout.grid_size(num_children));
+            self.layout_engine.gap = self.layout.gap(num_children);
+            self.layout_engine.direction = self.layout.direction(num_children);
             self.layout_engine.update_engine(hints_vec, rects_vec, update_heap_cache);
 
             let mut rects_iter = rects_vec.drain(..);
             self.buttons.children_mut::<_>(|mut summary| {
-                match rects_iter.next() {
-                    Some(rect) => *summary.widget_mut().rect_mut() = rect.unwrap_or(BoundBox::new2(0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF)),
+                let rect = match rects_iter.next() {
+                    Some(rect) => rect,
                     None => return LoopFlow::Break
-                }
+                };
+                *summary.widget_mut().rect_mut() = rect.unwrap_or(BoundBox::new2(0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF));
 
                 LoopFlow::Continue
             });
@@ -337,3 +354,74 @@ impl WidgetTheme for RadioButtonListTheme {
     type Fallback = !;
     fn fallback(self) -> Option<!> {None}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use derin_core::render::{CursorData, CursorOp, LayoutResult, RendererLayout};
+    use crate::layout::{Align, Align2, DynLayout, GridSize, Margins, WidgetSpan};
+    use cgmath_geometry::rect::DimsBox;
+
+    /// Only exists so `update_layout` has a layout to be passed. `RadioButtonList` doesn't use it.
+    struct TestLayout;
+    struct TestRenderer;
+
+    impl RendererLayout for TestLayout {
+        fn prepare_string(&mut self, _: &str) {}
+        fn prepare_edit_string(&mut self, _: &mut String, _: &mut CursorData, _: impl Iterator<Item=CursorOp>) {}
+        fn prepare_icon(&mut self, _: &str) {}
+        fn finish(&mut self) -> LayoutResult {
+            unimplemented!()
+        }
+    }
+
+    impl Renderer for TestRenderer {
+        type SubFrame = !;
+        type Theme = ();
+        type Layout = TestLayout;
+
+        fn resized(&mut self, _: DimsBox<D2, u32>) {}
+        fn dims(&self) -> DimsBox<D2, u32> {
+            DimsBox::new2(0, 0)
+        }
+        fn widget_removed(&mut self, _: WidgetId) {}
+        fn layout(&mut self, _: WidgetId, _: impl FnOnce(&mut TestLayout)) {}
+        fn start_frame(&mut self, _: &()) {}
+        fn finish_frame(&mut self, _: &()) {}
+    }
+
+    #[test]
+    fn absolute_out_of_bounds() {
+        let button = || RadioButton::new(false, Contents::Text(String::new()));
+        let absolute = |anchor, margins| Some(AbsolutePos::new(
+            SizeBounds::new_min(DimsBox::new2(20, 20)),
+            anchor,
+            margins
+        ));
+
+        let mut layout = DynLayout::new(GridSize::new(1, 1));
+        layout.set_position(WidgetIdent::Num(0), Some(WidgetPos {
+            widget_span: WidgetSpan::new(0, 0),
+            ..WidgetPos::default()
+        }));
+        layout.set_absolute_position(
+            WidgetIdent::Num(1),
+            absolute(Align2::new(Align::End, Align::End), Margins::new(0, 0, -10, -10))
+        );
+        layout.set_absolute_position(
+            WidgetIdent::Num(2),
+            absolute(Align2::new(Align::Start, Align::Start), Margins::new(200, 0, 0, 0))
+        );
+
+        let mut list = RadioButtonList::new(vec![button(), button(), button()], layout);
+        *list.rect_mut() = BoundBox::new2(0, 0, 100, 50);
+        WidgetRenderable::<TestRenderer>::update_layout(&mut list, &mut TestLayout);
+
+        let rects: Vec<_> = list.buttons().iter().map(|button| button.rect()).collect();
+        assert_eq!(BoundBox::new2(0, 0, 100, 50), rects[0]);
+        // A button hanging past the list's corner gets cropped to the list's rect...
+        assert_eq!(BoundBox::new2(90, 40, 100, 50), rects[1]);
+        // ...and a button entirely outside of the list doesn't get placed anywhere inside it.
+        assert_eq!(None, list.rect().intersect_rect(rects[2]));
+    }
+}
@@ -0,0 +1,810 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState},
+    timer::{Timer, TimerId},
+    widget::{WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+};
+use derin_common_types::{
+    buttons::MouseButton,
+    layout::{Axis, SizeBounds},
+};
+
+use crate::cgmath::{Point2, Vector2};
+use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
+
+use std::time::{Duration, Instant};
+
+/// The minimum thickness of the bar, in pixels.
+const BAR_THICKNESS: i32 = 16;
+/// The minimum length of the thumb, so that it stays possible to grab with very long content.
+const MIN_THUMB_LEN: i32 = 16;
+/// How long an arrow or the track has to be held down before it starts repeating.
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(50);
+
+pub trait ScrollBarHandler: 'static {
+    type Action: 'static;
+
+    fn on_scroll(&mut self, old_value: f32, new_value: f32) -> Option<Self::Action>;
+}
+
+/// A scroll bar that isn't attached to any particular widget, for views that scroll their
+/// contents themselves, like virtualized lists.
+///
+/// The bar describes a viewport onto some content with three values:
+/// * `content_len`: The length of the content along the bar's axis.
+/// * `viewport_len`: How much of the content is visible at once.
+/// * `value`: The offset of the start of the viewport into the content, between `0` and
+///   `max_value()`.
+///
+/// These can be measured in any unit, such as pixels or rows, as long as `line_step` uses the same
+/// one. The user can scroll by dragging the thumb, by clicking the arrows to move `line_step`, or
+/// by clicking the track to move a page. Holding down an arrow or the track keeps scrolling.
+/// Scrolling the mouse wheel over the bar moves `line_step` for every line scrolled; trackpad
+/// scrolling moves the content as though the track were the same size as the viewport.
+///
+/// Whenever the user scrolls, the provided handler's [`on_scroll`] function is called. It isn't
+/// called when the value gets changed with `set_value`.
+///
+/// [`on_scroll`]: ./trait.ScrollBarHandler.html#tymethod.on_scroll
+#[derive(Debug, Clone)]
+pub struct ScrollBar<H: ScrollBarHandler> {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    size_bounds: SizeBounds,
+    axis: Axis,
+
+    value: f32,
+    content_len: f32,
+    viewport_len: f32,
+    line_step: f32,
+
+    decrement: ScrollBarArrow,
+    increment: ScrollBarArrow,
+    thumb: ScrollBarThumb,
+    /// The start and end of the track along `axis`.
+    track: (i32, i32),
+
+    /// The position of the cursor along `axis`.
+    cursor_pos: i32,
+    /// Where the thumb was grabbed, relative to its start, while it's being dragged.
+    grab_pos: Option<i32>,
+    held: Option<Held>,
+    timer_id: TimerId,
+
+    handler: H,
+}
+
+/// The part of the bar that's being held down, which gets repeated while the button is held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Held {
+    /// One of the arrows, which scrolls `line_step` towards the start of the content with `-1.0`
+    /// and towards the end with `1.0`.
+    Arrow(f32),
+    /// The track, which scrolls a page in the same directions as `Arrow`.
+    Track(f32),
+}
+
+#[derive(Debug, Clone)]
+struct ScrollBarArrow {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    axis: Axis,
+    increment: bool,
+    pressed: bool,
+}
+
+#[derive(Debug, Clone)]
+struct ScrollBarThumb {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    axis: Axis,
+    pressed: bool,
+}
+
+/// The theme of a scroll bar's track.
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollBarTheme {
+    pub axis: Axis,
+}
+
+/// The theme of one of a scroll bar's arrows.
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollBarArrowTheme {
+    pub axis: Axis,
+    /// Whether the arrow scrolls towards the end of the content, and so sits at the right or bottom
+    /// of the bar.
+    pub increment: bool,
+    pub pressed: bool,
+}
+
+/// The theme of a scroll bar's thumb.
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollBarThumbTheme {
+    pub axis: Axis,
+    pub pressed: bool,
+}
+
+impl<H: ScrollBarHandler> ScrollBar<H> {
+    /// Creates a new scroll bar along `axis`. The content and the viewport start out empty, and
+    /// `line_step` starts out as `1.0`.
+    pub fn new(axis: Axis, handler: H) -> ScrollBar<H> {
+        // The bar handles all the input itself, so that it doesn't have to forward input from its
+        // parts.
+        let part_tag = || {
+            let mut widget_tag = WidgetTag::new();
+            widget_tag.set_input_transparent(true);
+            widget_tag
+        };
+        let arrow = |increment| ScrollBarArrow {
+            widget_tag: part_tag(),
+            rect: BoundBox::new2(0, 0, 0, 0),
+            axis,
+            increment,
+            pressed: false,
+        };
+
+        ScrollBar {
+            widget_tag: WidgetTag::new(),
+            rect: BoundBox::new2(0, 0, 0, 0),
+            size_bounds: SizeBounds::default(),
+            axis,
+
+            value: 0.0,
+            content_len: 0.0,
+            viewport_len: 0.0,
+            line_step: 1.0,
+
+            decrement: arrow(false),
+            increment: arrow(true),
+            thumb: ScrollBarThumb {
+                widget_tag: part_tag(),
+                rect: BoundBox::new2(0, 0, 0, 0),
+                axis,
+                pressed: false,
+            },
+            track: (0, 0),
+
+            cursor_pos: 0,
+            grab_pos: None,
+            held: None,
+            timer_id: TimerId::new(),
+
+            handler,
+        }
+    }
+
+    /// Retrieves the axis the bar scrolls along.
+    #[inline]
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+
+    /// Retrieves the offset of the viewport into the content.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Retrieves the largest value the bar can be scrolled to, which puts the end of the viewport
+    /// at the end of the content.
+    #[inline]
+    pub fn max_value(&self) -> f32 {
+        (self.content_len - self.viewport_len).max(0.0)
+    }
+
+    /// Retrieves the length of the content.
+    #[inline]
+    pub fn content_len(&self) -> f32 {
+        self.content_len
+    }
+
+    /// Retrieves the length of the viewport, which is how far clicking the track scrolls.
+    #[inline]
+    pub fn viewport_len(&self) -> f32 {
+        self.viewport_len
+    }
+
+    /// Retrieves how far clicking an arrow scrolls.
+    #[inline]
+    pub fn line_step(&self) -> f32 {
+        self.line_step
+    }
+
+    /// Scroll the viewport to `value`, clamped to between `0` and `max_value()`.
+    pub fn set_value(&mut self, value: f32) {
+        self.value = self.clamp(value);
+        self.widget_tag.request_relayout().request_redraw();
+    }
+
+    /// Set the length of the content and of the viewport. The value gets clamped to the new
+    /// `max_value()`.
+    pub fn set_lengths(&mut self, content_len: f32, viewport_len: f32) {
+        self.content_len = content_len.max(0.0);
+        self.viewport_len = viewport_len.max(0.0);
+        self.value = self.clamp(self.value);
+        self.widget_tag.request_relayout().request_redraw();
+    }
+
+    /// Set how far clicking an arrow scrolls. Steps that aren't positive and finite get clamped to
+    /// the nearest step that is, with `NaN` becoming the smallest possible step.
+    pub fn set_line_step(&mut self, line_step: f32) {
+        self.line_step = line_step.max(f32::MIN_POSITIVE).min(f32::MAX);
+    }
+
+    fn clamp(&self, value: f32) -> f32 {
+        value.min(self.max_value()).max(0.0)
+    }
+
+    fn axis_pos(&self, pos: Point2<i32>) -> i32 {
+        match self.axis {
+            Axis::X => pos.x,
+            Axis::Y => pos.y
+        }
+    }
+
+    /// The start and end of the thumb along `axis`, computed from the current value so that it's
+    /// up to date even if the bar hasn't been laid out since the value changed.
+    fn thumb_span(&self) -> (i32, i32) {
+        let track_len = self.track.1 - self.track.0;
+        let max_value = self.max_value();
+        match max_value > 0.0 && track_len > 0 {
+            // There's nothing to scroll, so the thumb gets hidden.
+            false => (self.track.0, self.track.0),
+            true => {
+                let thumb_len = ((track_len as f32 * self.viewport_len / self.content_len) as i32)
+                    .max(MIN_THUMB_LEN)
+                    .min(track_len);
+                let offset = (track_len - thumb_len) as f32 * self.value / max_value;
+                let thumb_start = self.track.0 + offset.round() as i32;
+                (thumb_start, thumb_start + thumb_len)
+            }
+        }
+    }
+
+    /// A rect covering the bar's full thickness, between `start` and `end` along `axis`.
+    fn span_rect(&self, start: i32, end: i32) -> BoundBox<D2, i32> {
+        match self.axis {
+            Axis::X => BoundBox::new2(start, 0, end, self.rect.height()),
+            Axis::Y => BoundBox::new2(0, start, self.rect.width(), end)
+        }
+    }
+
+    /// Scroll to `value` on behalf of the user, returning whether the value changed.
+    fn scroll_to(&mut self, value: f32) -> bool {
+        let old_value = self.value;
+        self.value = self.clamp(value);
+        if self.value == old_value {
+            return false;
+        }
+
+        if let Some(action) = self.handler.on_scroll(old_value, self.value) {
+            self.widget_tag.broadcast_message(action);
+        }
+        self.widget_tag.request_relayout().request_redraw();
+        true
+    }
+
+    fn press(&mut self, pos: Point2<i32>) {
+        let axis_pos = self.axis_pos(pos);
+        let (thumb_start, thumb_end) = self.thumb_span();
+        self.cursor_pos = axis_pos;
+
+        if self.decrement.rect.contains(pos) {
+            self.decrement.set_pressed(true);
+            self.held = Some(Held::Arrow(-1.0));
+        } else if self.increment.rect.contains(pos) {
+            self.increment.set_pressed(true);
+            self.held = Some(Held::Arrow(1.0));
+        } else if self.span_rect(thumb_start, thumb_end).contains(pos) {
+            self.thumb.set_pressed(true);
+            self.grab_pos = Some(axis_pos - thumb_start);
+        } else if axis_pos < thumb_start {
+            self.held = Some(Held::Track(-1.0));
+        } else if axis_pos >= thumb_end {
+            self.held = Some(Held::Track(1.0));
+        }
+
+        if self.held.is_some() {
+            self.repeat_held();
+            let timer = Timer::new_delayed(REPEAT_INTERVAL, Instant::now() + REPEAT_DELAY);
+            self.widget_tag.timers_mut().insert(self.timer_id, timer);
+        }
+    }
+
+    fn release(&mut self) {
+        self.grab_pos = None;
+        if self.held.take().is_some() {
+            self.widget_tag.timers_mut().remove(&self.timer_id);
+        }
+        self.decrement.set_pressed(false);
+        self.increment.set_pressed(false);
+        self.thumb.set_pressed(false);
+    }
+
+    fn repeat_held(&mut self) {
+        match self.held {
+            Some(Held::Arrow(dir)) => {
+                let line_step = self.line_step;
+                self.scroll_to(self.value + dir * line_step);
+            },
+            Some(Held::Track(dir)) => {
+                // Stop paging once the thumb reaches the cursor, so that holding down the track
+                // scrolls to where it was clicked.
+                let (thumb_start, thumb_end) = self.thumb_span();
+                let reached_cursor = match dir > 0.0 {
+                    true => thumb_end > self.cursor_pos,
+                    false => thumb_start <= self.cursor_pos
+                };
+                if !reached_cursor {
+                    let viewport_len = self.viewport_len;
+                    self.scroll_to(self.value + dir * viewport_len);
+                }
+            },
+            None => ()
+        }
+    }
+
+    fn drag_thumb(&mut self, grab_pos: i32) {
+        let (thumb_start, thumb_end) = self.thumb_span();
+        let travel = (self.track.1 - self.track.0) - (thumb_end - thumb_start);
+        if travel <= 0 {
+            return;
+        }
+
+        let thumb_offset = self.cursor_pos - grab_pos - self.track.0;
+        let max_value = self.max_value();
+        self.scroll_to(thumb_offset as f32 / travel as f32 * max_value);
+    }
+
+    /// Convert a scroll event into a distance along the bar's axis, in the content's units.
+    fn scroll_delta(&self, event: &WidgetEvent) -> Option<f32> {
+        let along_axis = |dir: Vector2<i32>| match self.axis {
+            // Mice without horizontal wheels can still scroll horizontal bars.
+            Axis::X if dir.x == 0 => dir.y,
+            Axis::X => dir.x,
+            Axis::Y => dir.y
+        };
+        let track_len = self.track.1 - self.track.0;
+
+        match *event {
+            WidgetEvent::MouseScrollLines{dir, ..} => Some(-along_axis(dir) as f32 * self.line_step),
+            WidgetEvent::MouseScrollPx{dir, ..} if track_len > 0 =>
+                Some(-along_axis(dir) as f32 * self.viewport_len / track_len as f32),
+            _ => None
+        }
+    }
+}
+
+impl ScrollBarArrow {
+    fn set_pressed(&mut self, pressed: bool) {
+        if self.pressed != pressed {
+            self.pressed = pressed;
+            self.widget_tag.request_redraw();
+        }
+    }
+}
+
+impl ScrollBarThumb {
+    fn set_pressed(&mut self, pressed: bool) {
+        if self.pressed != pressed {
+            self.pressed = pressed;
+            self.widget_tag.request_redraw();
+        }
+    }
+}
+
+impl<H> Widget for ScrollBar<H>
+    where H: ScrollBarHandler
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.rect
+    }
+
+    #[inline]
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        let mut allow_bubble = true;
+
+        if let WidgetEventSourced::This(ref event) = event {
+            match event {
+                WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left, ..} => self.press(*pos),
+                WidgetEvent::MouseMove{new_pos, ..} => {
+                    self.cursor_pos = self.axis_pos(*new_pos);
+                    if let Some(grab_pos) = self.grab_pos {
+                        self.drag_thumb(grab_pos);
+                    }
+                },
+                WidgetEvent::MouseUp{button: MouseButton::Left, ..} => self.release(),
+                WidgetEvent::Timer{timer_id, ..} if *timer_id == self.timer_id => self.repeat_held(),
+                _ => ()
+            }
+
+            if let Some(delta) = self.scroll_delta(event) {
+                // Once the bar hits its end, let the scroll through so that whatever contains the
+                // bar can keep scrolling.
+                let value = self.value;
+                allow_bubble = !self.scroll_to(value + delta);
+            }
+        }
+
+        EventOps {
+            focus: None,
+            bubble: allow_bubble && event.default_bubble(),
+        }
+    }
+}
+
+impl Widget for ScrollBarArrow {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        &mut self.rect
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: true,
+        }
+    }
+}
+
+impl Widget for ScrollBarThumb {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        &mut self.rect
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: true,
+        }
+    }
+}
+
+impl<H> Parent for ScrollBar<H>
+    where H: ScrollBarHandler
+{
+    fn num_children(&self) -> usize {
+        3
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.decrement)),
+            WidgetIdent::Num(1) => Some(WidgetInfo::new(WidgetIdent::Num(1), 1, &self.increment)),
+            WidgetIdent::Num(2) => Some(WidgetInfo::new(WidgetIdent::Num(2), 2, &self.thumb)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.decrement)),
+            WidgetIdent::Num(1) => Some(WidgetInfoMut::new(WidgetIdent::Num(1), 1, &mut self.increment)),
+            WidgetIdent::Num(2) => Some(WidgetInfoMut::new(WidgetIdent::Num(2), 2, &mut self.thumb)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        if let LoopFlow::Break = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.decrement)) {
+            return;
+        }
+        if let LoopFlow::Break = for_each(WidgetInfo::new(WidgetIdent::Num(1), 1, &self.increment)) {
+            return;
+        }
+        let _ = for_each(WidgetInfo::new(WidgetIdent::Num(2), 2, &self.thumb));
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        if let LoopFlow::Break = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.decrement)) {
+            return;
+        }
+        if let LoopFlow::Break = for_each(WidgetInfoMut::new(WidgetIdent::Num(1), 1, &mut self.increment)) {
+            return;
+        }
+        let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(2), 2, &mut self.thumb));
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        self.framed_child(WidgetIdent::Num(index as u32))
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        self.framed_child_mut(WidgetIdent::Num(index as u32))
+    }
+}
+
+impl<R, H> WidgetRenderable<R> for ScrollBar<H>
+    where R: Renderer,
+          H: ScrollBarHandler
+{
+    type Theme = ScrollBarTheme;
+
+    fn theme(&self) -> ScrollBarTheme {
+        ScrollBarTheme {
+            axis: self.axis,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, layout: &mut R::Layout) {
+        let result = layout.finish();
+        let axis = self.axis;
+
+        // The bar needs to be thick enough to click, and long enough to fit both arrows and the
+        // thumb. It doesn't get any thicker than it needs to be.
+        let mut size_bounds = result.size_bounds;
+        let min_len = BAR_THICKNESS * 2 + MIN_THUMB_LEN;
+        match axis {
+            Axis::X => {
+                size_bounds.min.dims.x = size_bounds.min.width().max(min_len);
+                size_bounds.min.dims.y = size_bounds.min.height().max(BAR_THICKNESS);
+                size_bounds.max.dims.y = size_bounds.min.height();
+            },
+            Axis::Y => {
+                size_bounds.min.dims.x = size_bounds.min.width().max(BAR_THICKNESS);
+                size_bounds.min.dims.y = size_bounds.min.height().max(min_len);
+                size_bounds.max.dims.x = size_bounds.min.width();
+            }
+        }
+        self.size_bounds = size_bounds;
+
+        let (len, thickness) = match axis {
+            Axis::X => (self.rect.width(), self.rect.height()),
+            Axis::Y => (self.rect.height(), self.rect.width())
+        };
+        // The arrows are square, unless the bar is too short to fit them.
+        let arrow_len = thickness.min(len / 2);
+        self.track = (arrow_len, len - arrow_len);
+        let (thumb_start, thumb_end) = self.thumb_span();
+
+        self.decrement.rect = self.span_rect(0, arrow_len);
+        self.increment.rect = self.span_rect(len - arrow_len, len);
+        self.thumb.rect = self.span_rect(thumb_start, thumb_end);
+    }
+}
+
+impl<R> WidgetRenderable<R> for ScrollBarArrow
+    where R: Renderer
+{
+    type Theme = ScrollBarArrowTheme;
+
+    fn theme(&self) -> ScrollBarArrowTheme {
+        ScrollBarArrowTheme {
+            axis: self.axis,
+            increment: self.increment,
+            pressed: self.pressed,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) { }
+}
+
+impl<R> WidgetRenderable<R> for ScrollBarThumb
+    where R: Renderer
+{
+    type Theme = ScrollBarThumbTheme;
+
+    fn theme(&self) -> ScrollBarThumbTheme {
+        ScrollBarThumbTheme {
+            axis: self.axis,
+            pressed: self.pressed,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) { }
+}
+
+impl WidgetTheme for ScrollBarTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
+
+impl WidgetTheme for ScrollBarArrowTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
+
+impl WidgetTheme for ScrollBarThumbTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NoAction;
+
+    impl ScrollBarHandler for NoAction {
+        type Action = ();
+
+        fn on_scroll(&mut self, _: f32, _: f32) -> Option<()> {
+            None
+        }
+    }
+
+    /// A vertical bar with 16px arrows around a 100px track, laid out the way `update_layout`
+    /// would lay it out.
+    fn vertical_bar(content_len: f32, viewport_len: f32) -> ScrollBar<NoAction> {
+        let mut bar = ScrollBar::new(Axis::Y, NoAction);
+        bar.set_lengths(content_len, viewport_len);
+        bar.rect = BoundBox::new2(0, 0, 16, 132);
+        bar.track = (16, 116);
+        bar.decrement.rect = bar.span_rect(0, 16);
+        bar.increment.rect = bar.span_rect(116, 132);
+        bar
+    }
+
+    #[test]
+    fn thumb_span() {
+        // The viewport covers a quarter of the content, so the thumb covers a quarter of the track
+        // and has 75px to travel.
+        let mut bar = vertical_bar(200.0, 50.0);
+        assert_eq!((16, 41), bar.thumb_span());
+        bar.set_value(75.0);
+        assert_eq!((54, 79), bar.thumb_span());
+        bar.set_value(150.0);
+        assert_eq!((91, 116), bar.thumb_span());
+
+        // Long content doesn't shrink the thumb past its minimum length.
+        let bar = vertical_bar(10_000.0, 50.0);
+        assert_eq!((16, 16 + MIN_THUMB_LEN), bar.thumb_span());
+
+        // Neither does content that fits in the viewport, which hides the thumb instead.
+        let bar = vertical_bar(50.0, 100.0);
+        assert_eq!((16, 16), bar.thumb_span());
+    }
+
+    #[test]
+    fn drag_thumb() {
+        // The thumb is 20px long, so it has 80px to travel over 160 units of content.
+        let mut bar = vertical_bar(200.0, 40.0);
+        bar.press(Point2::new(8, 20));
+        assert_eq!(Some(4), bar.grab_pos);
+
+        // Dragging the thumb to a position maps back to the value that puts it there.
+        for &(thumb_start, value) in &[(96, 160.0), (56, 80.0), (36, 40.0), (16, 0.0)] {
+            bar.cursor_pos = thumb_start + 4;
+            bar.drag_thumb(4);
+            assert_eq!(value, bar.value());
+            assert_eq!(thumb_start, bar.thumb_span().0);
+        }
+
+        // Dragging past the ends of the track clamps the value.
+        bar.cursor_pos = 200;
+        bar.drag_thumb(4);
+        assert_eq!(160.0, bar.value());
+        bar.cursor_pos = -200;
+        bar.drag_thumb(4);
+        assert_eq!(0.0, bar.value());
+    }
+
+    #[test]
+    fn thumb_hit_test() {
+        // Presses level with the thumb but beside the bar don't grab the thumb.
+        let mut bar = vertical_bar(200.0, 50.0);
+        bar.press(Point2::new(20, 20));
+        assert_eq!(None, bar.grab_pos);
+        assert_eq!(None, bar.held);
+    }
+
+    #[test]
+    fn line_step() {
+        let mut bar = vertical_bar(200.0, 50.0);
+        bar.set_line_step(10.0);
+
+        bar.held = Some(Held::Arrow(-1.0));
+        bar.repeat_held();
+        assert_eq!(0.0, bar.value());
+
+        bar.set_value(145.0);
+        bar.held = Some(Held::Arrow(1.0));
+        bar.repeat_held();
+        assert_eq!(150.0, bar.value());
+        bar.repeat_held();
+        assert_eq!(150.0, bar.value());
+
+        bar.held = Some(Held::Arrow(-1.0));
+        bar.repeat_held();
+        assert_eq!(140.0, bar.value());
+
+        // Bad steps get clamped instead of breaking the bar.
+        bar.set_line_step(-1.0);
+        assert!(bar.line_step() > 0.0);
+        bar.set_line_step(std::f32::NAN);
+        assert!(bar.line_step() > 0.0);
+        bar.set_line_step(std::f32::INFINITY);
+        assert!(bar.line_step().is_finite());
+    }
+
+    #[test]
+    fn page_step() {
+        let mut bar = vertical_bar(200.0, 50.0);
+
+        // Paging towards the cursor stops at the end of the content.
+        bar.set_value(120.0);
+        bar.cursor_pos = 115;
+        bar.held = Some(Held::Track(1.0));
+        bar.repeat_held();
+        assert_eq!(150.0, bar.value());
+        bar.repeat_held();
+        assert_eq!(150.0, bar.value());
+
+        bar.cursor_pos = 17;
+        bar.held = Some(Held::Track(-1.0));
+        bar.repeat_held();
+        assert_eq!(100.0, bar.value());
+        bar.set_value(30.0);
+        bar.repeat_held();
+        assert_eq!(0.0, bar.value());
+        bar.repeat_held();
+        assert_eq!(0.0, bar.value());
+    }
+}
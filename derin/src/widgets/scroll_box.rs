@@ -17,7 +17,7 @@ use crate::{
 use crate::cgmath::{Point2, Vector2};
 use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
 use derin_common_types::layout::SizeBounds;
-use derin_common_types::buttons::MouseButton;
+use derin_common_types::buttons::{ModifierKeys, MouseButton};
 
 use arrayvec::ArrayVec;
 
@@ -26,6 +26,15 @@ use std::sync::Arc;
 
 const SCROLL_BAR_SIZE: i32 = 16;
 
+/// Scroll horizontally instead of vertically while shift is held, so mice without a horizontal
+/// wheel can scroll sideways.
+fn scroll_dir(dir: Vector2<i32>, modifiers: ModifierKeys) -> Vector2<i32> {
+    match modifiers.contains(ModifierKeys::SHIFT) && dir.x == 0 {
+        true => Vector2::new(dir.y, 0),
+        false => dir
+    }
+}
+
 /// A widget that is used to apply scroll bars to a contained widget.
 ///
 /// These bars are hidden by default, only appearing when the inner widget's minimum size is
@@ -99,7 +108,7 @@ impl<W> Widget for ScrollBox<W>
     }
 
     #[inline]
-    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+    fn on_widget_event(&mut self, event: WidgetEventSourced, input_state: InputState) -> EventOps {
         // TODO: PASS FOCUS TO CHILD
         let values = |slider_x: &Option<SliderAssist>, slider_y: &Option<SliderAssist>|
             (slider_x.as_ref().map(|s| s.value), slider_y.as_ref().map(|s| s.value));
@@ -108,7 +117,7 @@ impl<W> Widget for ScrollBox<W>
 
         match event {
             WidgetEventSourced::This(ref event) => match event {
-                WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left} => {
+                WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left, ..} => {
                     if let Some(ref mut slider_x) = self.slider_x {
                         slider_x.click_head(*pos);
                     }
@@ -136,20 +145,9 @@ impl<W> Widget for ScrollBox<W>
                 },
                 _ => ()
             },
-            WidgetEventSourced::Bubble(ref event, _) => match event {
-                WidgetEvent::MouseScrollLines{dir, in_widget: true} => {
-                    allow_bubble = false;
-                    if let Some(ref mut slider_x) = self.slider_x {
-                        slider_x.value -= (24 * dir.x) as f32;
-                        slider_x.round_to_step();
-                    }
-                    if let Some(ref mut slider_y) = self.slider_y {
-                        slider_y.value -= (24 * dir.y) as f32;
-                        slider_y.round_to_step();
-                    }
-                },
-                WidgetEvent::MouseScrollPx{dir, in_widget: true} => {
-                    allow_bubble = false;
+            WidgetEventSourced::Bubble(ref event, _) => match event.scroll_px() {
+                Some(dir) => {
+                    let dir = scroll_dir(dir, input_state.modifiers);
                     if let Some(ref mut slider_x) = self.slider_x {
                         slider_x.value -= dir.x as f32;
                         slider_x.round_to_step();
@@ -158,8 +156,12 @@ impl<W> Widget for ScrollBox<W>
                         slider_y.value -= dir.y as f32;
                         slider_y.round_to_step();
                     }
+
+                    // Once the scroll box hits its edge, let the scroll through so that the scroll
+                    // box containing this one can keep scrolling.
+                    allow_bubble = values(&self.slider_x, &self.slider_y) == start_values;
                 },
-                _ => ()
+                None => ()
             }
         }
 
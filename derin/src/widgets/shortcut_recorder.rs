@@ -0,0 +1,227 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    event::{EventOps, FocusChange, WidgetEvent, WidgetEventSourced, InputState},
+    widget::{WidgetTag, WidgetRenderable, Widget},
+    render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+};
+use derin_common_types::{
+    buttons::{Key, KeyChord, MouseButton},
+    layout::SizeBounds,
+};
+
+use cgmath_geometry::{D2, rect::BoundBox};
+
+pub trait ShortcutRecorderHandler: 'static {
+    type Action: 'static;
+
+    /// Check whether `chord` can be bound, returning a description of the conflict if it can't
+    /// (such as the name of the command it's already bound to).
+    ///
+    /// Defaults to accepting every chord.
+    fn validate(&mut self, _chord: KeyChord) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called when the user records a new binding. `None` means the binding was cleared.
+    fn on_record(&mut self, old: Option<KeyChord>, new: Option<KeyChord>) -> Option<Self::Action>;
+}
+
+/// A field that records the next key chord the user presses, for customizing keyboard shortcuts.
+///
+/// Clicking the field, or pressing `Enter` or `Space` while it has focus, starts recording. The
+/// next key pressed alongside any modifier keys becomes the new binding, as long as the handler's
+/// [`validate`] accepts it. If it doesn't, the conflict is displayed and recording continues.
+/// While recording, `Escape` cancels and `Backspace` clears the binding.
+///
+/// [`validate`]: ./trait.ShortcutRecorderHandler.html#method.validate
+#[derive(Debug, Clone)]
+pub struct ShortcutRecorder<H: ShortcutRecorderHandler> {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    size_bounds: SizeBounds,
+    chord: Option<KeyChord>,
+    recording: bool,
+    /// Why the last chord pressed while recording was rejected.
+    conflict: Option<String>,
+    handler: H,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutRecorderTheme {
+    pub recording: bool,
+}
+
+impl<H: ShortcutRecorderHandler> ShortcutRecorder<H> {
+    /// Creates a new shortcut recorder displaying the given binding.
+    pub fn new(chord: Option<KeyChord>, handler: H) -> ShortcutRecorder<H> {
+        let mut widget_tag = WidgetTag::new();
+        widget_tag.set_focusable(true);
+        ShortcutRecorder {
+            widget_tag,
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            size_bounds: SizeBounds::default(),
+            chord,
+            recording: false,
+            conflict: None,
+            handler,
+        }
+    }
+
+    /// Retrieves the recorded binding.
+    pub fn chord(&self) -> Option<KeyChord> {
+        self.chord
+    }
+
+    /// Retrieves the recorded binding, for mutation.
+    ///
+    /// Calling this function forces the recorder to be re-drawn, so you're discouraged from calling
+    /// it unless you're actually changing the binding.
+    pub fn chord_mut(&mut self) -> &mut Option<KeyChord> {
+        self.widget_tag
+            .request_redraw()
+            .request_relayout();
+
+        &mut self.chord
+    }
+
+    /// Retrieves whether the recorder is waiting for the user to press a chord.
+    pub fn recording(&self) -> bool {
+        self.recording
+    }
+
+    fn set_recording(&mut self, recording: bool) {
+        if self.recording != recording {
+            self.recording = recording;
+            self.conflict = None;
+            self.widget_tag
+                .request_redraw()
+                .request_relayout();
+        }
+    }
+
+    fn record(&mut self, chord: Option<KeyChord>) {
+        let old = self.chord;
+        self.chord = chord;
+        self.set_recording(false);
+        if old != chord {
+            if let Some(message) = self.handler.on_record(old, chord) {
+                self.widget_tag.broadcast_message(message);
+            }
+        }
+    }
+
+    fn display_text(&self) -> String {
+        match (self.recording, &self.conflict, self.chord) {
+            (true, Some(conflict), _) => format!("Conflicts with {}", conflict),
+            (true, None, _) => "Press a shortcut...".to_string(),
+            (false, _, Some(chord)) => chord.platform_notation(),
+            (false, _, None) => "None".to_string(),
+        }
+    }
+}
+
+impl<H> Widget for ShortcutRecorder<H>
+    where H: ShortcutRecorderHandler
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        use self::WidgetEvent::*;
+        let event = event.unwrap();
+
+        let mut focus = None;
+        let mut bubble = event.default_bubble();
+        match event {
+            MouseDown{in_widget: true, button: MouseButton::Left, ..} => {
+                focus = Some(FocusChange::Take);
+                self.set_recording(true);
+            },
+            KeyDown(key, modifiers) if !self.recording => {
+                if modifiers.is_empty() && (key == Key::Enter || key == Key::Space) {
+                    self.set_recording(true);
+                    bubble = false;
+                }
+            },
+            // While recording, every key gets consumed so it doesn't trigger another shortcut.
+            KeyDown(key, modifiers) => {
+                bubble = false;
+                match (key, modifiers.is_empty()) {
+                    // Wait for the main key when only modifiers are held.
+                    _ if key.is_modifier() => (),
+                    (Key::Escape, true) => self.set_recording(false),
+                    (Key::Back, true) => self.record(None),
+                    _ => {
+                        let chord = KeyChord::new(modifiers, key);
+                        match self.handler.validate(chord) {
+                            Ok(()) => self.record(Some(chord)),
+                            Err(conflict) => {
+                                self.conflict = Some(conflict);
+                                self.widget_tag
+                                    .request_redraw()
+                                    .request_relayout();
+                            }
+                        }
+                    }
+                }
+            },
+            LoseFocus => self.set_recording(false),
+            _ => ()
+        }
+
+        EventOps {
+            focus,
+            bubble,
+        }
+    }
+}
+
+impl<R, H> WidgetRenderable<R> for ShortcutRecorder<H>
+    where R: Renderer,
+          H: ShortcutRecorderHandler
+{
+    type Theme = ShortcutRecorderTheme;
+
+    fn theme(&self) -> ShortcutRecorderTheme {
+        ShortcutRecorderTheme {
+            recording: self.recording,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, layout: &mut R::Layout) {
+        layout.prepare_string(&self.display_text());
+
+        let result = layout.finish();
+        self.size_bounds = result.size_bounds;
+    }
+}
+
+impl WidgetTheme for ShortcutRecorderTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
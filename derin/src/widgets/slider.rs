@@ -190,7 +190,7 @@ impl<H> Widget for SliderHandle<H>
         if let WidgetEventSourced::This(ref event) = event {
             let start_value = self.value;
             match event {
-                WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left} => {
+                WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left, ..} => {
                     self.click_pos = Some(pos.x);
                     self.widget_tag.request_redraw();
                 },
@@ -0,0 +1,282 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState, MouseHoverChange},
+    widget::{WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, SubFrame, WidgetTheme},
+};
+use derin_common_types::{
+    buttons::MouseButton,
+    cursor::CursorIcon,
+    layout::{Axis, GridSize, SizeBounds, WidgetPos, WidgetSpan},
+};
+
+use crate::cgmath::{Point2, Vector2};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+use derin_layout_engine::{GridEngine, UpdateHeapCache, SolveError};
+
+/// The width of the draggable bar between the two panes, in pixels.
+const SPLITTER_WIDTH: i32 = 6;
+
+/// Two panes separated by a bar the user can drag to resize them.
+///
+/// The panes are laid out along `axis`: side by side for `Axis::X`, and on top of each other for
+/// `Axis::Y`. Both panes start out taking up an equal amount of space, and keep their proportions
+/// when the splitter gets resized.
+#[derive(Debug, Clone)]
+pub struct Splitter<A, B> {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    layout_engine: GridEngine,
+    axis: Axis,
+    first: A,
+    second: B,
+    /// The position of the cursor along `axis` while the bar's being dragged.
+    drag_pos: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SplitterTheme(());
+
+impl<A, B> Splitter<A, B> {
+    /// Create a new splitter, with `first` placed in the left or top pane and `second` placed in
+    /// the right or bottom pane.
+    pub fn new(first: A, second: B, axis: Axis) -> Splitter<A, B> {
+        let mut layout_engine = GridEngine::new();
+        layout_engine.set_grid_size(match axis {
+            Axis::X => GridSize::new(2, 1),
+            Axis::Y => GridSize::new(1, 2)
+        });
+        layout_engine.set_track_resizable(axis, 0, true);
+        layout_engine.gap = match axis {
+            Axis::X => Vector2::new(SPLITTER_WIDTH, 0),
+            Axis::Y => Vector2::new(0, SPLITTER_WIDTH)
+        };
+
+        Splitter {
+            widget_tag: WidgetTag::new(),
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            layout_engine,
+            axis,
+            first,
+            second,
+            drag_pos: None,
+        }
+    }
+
+    /// Retrieves the axis the panes are laid out along.
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+
+    /// Retrieves the widget in the left or top pane.
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Retrieves the widget in the left or top pane, for mutation.
+    pub fn first_mut(&mut self) -> &mut A {
+        self.widget_tag.request_relayout();
+        &mut self.first
+    }
+
+    /// Retrieves the widget in the right or bottom pane.
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+
+    /// Retrieves the widget in the right or bottom pane, for mutation.
+    pub fn second_mut(&mut self) -> &mut B {
+        self.widget_tag.request_relayout();
+        &mut self.second
+    }
+
+    fn axis_pos(&self, pos: Point2<i32>) -> i32 {
+        match self.axis {
+            Axis::X => pos.x,
+            Axis::Y => pos.y
+        }
+    }
+}
+
+impl<A, B> Widget for Splitter<A, B>
+    where A: Widget,
+          B: Widget
+{
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.layout_engine.actual_size_bounds()
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        // Events dispatched directly to the splitter land on the bar, since the panes cover the
+        // rest of the splitter.
+        if let WidgetEventSourced::This(ref event) = event {
+            let resize_icon = match self.axis {
+                Axis::X => CursorIcon::SizeWE,
+                Axis::Y => CursorIcon::SizeNS
+            };
+            match event {
+                WidgetEvent::MouseDown{pos, in_widget: true, button: MouseButton::Left, ..} => {
+                    self.drag_pos = Some(self.axis_pos(*pos));
+                },
+                WidgetEvent::MouseMove{new_pos, hover_change, ..} => {
+                    match hover_change {
+                        Some(MouseHoverChange::Enter) |
+                        Some(MouseHoverChange::ExitChild(_)) => {
+                            self.widget_tag.set_cursor_icon(resize_icon).ok();
+                        },
+                        Some(MouseHoverChange::Exit) |
+                        Some(MouseHoverChange::EnterChild(_)) if self.drag_pos.is_none() => {
+                            self.widget_tag.set_cursor_icon(CursorIcon::default()).ok();
+                        },
+                        _ => ()
+                    }
+
+                    if let Some(drag_pos) = self.drag_pos {
+                        let delta = self.axis_pos(*new_pos) - drag_pos;
+                        let moved = self.layout_engine.resize_track_by(self.axis, 0, delta);
+                        if moved != 0 {
+                            self.drag_pos = Some(drag_pos + moved);
+                            self.widget_tag.request_relayout();
+                        }
+                    }
+                },
+                WidgetEvent::MouseUp{button: MouseButton::Left, ..} => {
+                    self.drag_pos = None;
+                },
+                _ => ()
+            }
+        }
+
+        EventOps {
+            focus: None,
+            bubble: event.default_bubble() || event.is_bubble(),
+        }
+    }
+}
+
+impl<A, B> Parent for Splitter<A, B>
+    where A: Widget,
+          B: Widget
+{
+    fn num_children(&self) -> usize {
+        2
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.first)),
+            WidgetIdent::Num(1) => Some(WidgetInfo::new(WidgetIdent::Num(1), 1, &self.second)),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(0) => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.first)),
+            WidgetIdent::Num(1) => Some(WidgetInfoMut::new(WidgetIdent::Num(1), 1, &mut self.second)),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        if let LoopFlow::Continue = for_each(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.first)) {
+            let _ = for_each(WidgetInfo::new(WidgetIdent::Num(1), 1, &self.second));
+        }
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        if let LoopFlow::Continue = for_each(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.first)) {
+            let _ = for_each(WidgetInfoMut::new(WidgetIdent::Num(1), 1, &mut self.second));
+        }
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        match index {
+            0 => Some(WidgetInfo::new(WidgetIdent::Num(0), 0, &self.first)),
+            1 => Some(WidgetInfo::new(WidgetIdent::Num(1), 1, &self.second)),
+            _ => None
+        }
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        match index {
+            0 => Some(WidgetInfoMut::new(WidgetIdent::Num(0), 0, &mut self.first)),
+            1 => Some(WidgetInfoMut::new(WidgetIdent::Num(1), 1, &mut self.second)),
+            _ => None
+        }
+    }
+}
+
+impl<A, B, R> WidgetRenderable<R> for Splitter<A, B>
+    where A: Widget,
+          B: Widget,
+          R: Renderer
+{
+    type Theme = SplitterTheme;
+
+    fn theme(&self) -> SplitterTheme {
+        SplitterTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) {
+        let second_span = match self.axis {
+            Axis::X => WidgetSpan::new(1, 0),
+            Axis::Y => WidgetSpan::new(0, 1)
+        };
+        let hints = [
+            WidgetPos {
+                size_bounds: self.first.size_bounds(),
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                size_bounds: self.second.size_bounds(),
+                widget_span: second_span,
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+
+        self.layout_engine.desired_size = DimsBox::new2(self.bounds.width(), self.bounds.height());
+        self.layout_engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        *self.first.rect_mut() = rects[0].unwrap_or(BoundBox::new2(0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF));
+        *self.second.rect_mut() = rects[1].unwrap_or(BoundBox::new2(0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF, 0xDEDBEEF));
+    }
+}
+
+impl WidgetTheme for SplitterTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
@@ -0,0 +1,421 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_core::{
+    LoopFlow,
+    event::{EventOps, WidgetEvent, WidgetEventSourced, InputState},
+    stats::FrameStats,
+    timer::{Timer, TimerId},
+    widget::{WidgetIdent, WidgetRenderable, WidgetTag, WidgetInfo, WidgetInfoMut, Widget, Parent},
+    render::{Renderer, RendererLayout, SubFrame, WidgetTheme},
+};
+use derin_common_types::layout::SizeBounds;
+
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    time::Duration,
+};
+
+/// The number of frames shown in the graph.
+const HISTORY_LEN: usize = 120;
+const BAR_WIDTH: i32 = 2;
+const GRAPH_HEIGHT: i32 = 48;
+
+/// A diagnostic overlay showing how long recent frames took to produce.
+///
+/// The overlay draws a graph of the last 120 frame times, with frames that went over the frame
+/// budget highlighted, along with a summary of the latest frame: the time spent on layout and
+/// rendering, how many layout passes it took, and whatever the renderer reports, such as draw
+/// calls, triangles, atlas occupancy and GPU time. The statistics come from the `FrameStats`
+/// message `Root` sends after each frame.
+///
+/// The overlay is input-transparent, so it can be placed over the rest of the window without
+/// getting in the way. It only refreshes a few times a second, since redrawing it produces a frame
+/// of its own. Hidden overlays don't draw anything or wake the event loop; they can be shown and
+/// hidden with `set_shown`, or by broadcasting a `ToggleStatsOverlay` message, which lets them be
+/// toggled with a shortcut.
+#[derive(Debug, Clone)]
+pub struct StatsOverlay {
+    widget_tag: WidgetTag,
+    bounds: BoundBox<D2, i32>,
+    size_bounds: SizeBounds,
+    shown: bool,
+    frame_budget: Duration,
+    refresh_interval: Duration,
+    history: VecDeque<FrameStats>,
+    /// Whether frames have arrived since the summary was last updated.
+    stale: bool,
+    summary: String,
+    /// The graph's bars, oldest first. Empty while the overlay is hidden.
+    bars: Vec<StatsBar>,
+    timer_id: TimerId,
+}
+
+#[derive(Debug, Clone)]
+struct StatsBar {
+    widget_tag: WidgetTag,
+    rect: BoundBox<D2, i32>,
+    over_budget: bool,
+}
+
+/// Message that shows or hides every `StatsOverlay` in the tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ToggleStatsOverlay;
+
+#[derive(Debug, Clone, Default)]
+pub struct StatsOverlayTheme(());
+
+/// The theme of a single bar in a `StatsOverlay`'s frame time graph.
+#[derive(Debug, Clone, Copy)]
+pub struct StatsBarTheme {
+    /// Whether the frame took longer than the overlay's frame budget.
+    pub over_budget: bool,
+}
+
+impl StatsOverlay {
+    /// Creates a new stats overlay, which starts out shown or hidden according to `shown`.
+    pub fn new(shown: bool) -> StatsOverlay {
+        let mut widget_tag = WidgetTag::new();
+        widget_tag.register_message(Self::on_frame_stats);
+        widget_tag.register_message(Self::on_toggle);
+        widget_tag.set_input_transparent(true);
+
+        let mut overlay = StatsOverlay {
+            widget_tag,
+            bounds: BoundBox::new2(0, 0, 0, 0),
+            size_bounds: SizeBounds::default(),
+            shown: false,
+            frame_budget: Duration::from_millis(16),
+            refresh_interval: Duration::from_millis(250),
+            history: VecDeque::with_capacity(HISTORY_LEN),
+            stale: false,
+            summary: String::new(),
+            bars: Vec::new(),
+            timer_id: TimerId::new(),
+        };
+        overlay.set_shown(shown);
+        overlay
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Shows or hides the overlay. Hiding the overlay clears its history.
+    pub fn set_shown(&mut self, shown: bool) {
+        if self.shown == shown {
+            return;
+        }
+        self.shown = shown;
+
+        if shown {
+            self.bars = (0..HISTORY_LEN).map(|_| StatsBar::new()).collect();
+            self.summary = self.summary();
+            self.widget_tag.timers_mut().insert(self.timer_id, Timer::new(self.refresh_interval));
+        } else {
+            self.bars.clear();
+            self.history.clear();
+            self.summary.clear();
+            self.widget_tag.timers_mut().remove(&self.timer_id);
+        }
+        self.widget_tag
+            .request_relayout()
+            .request_redraw();
+    }
+
+    /// Retrieves how long a frame can take before it gets highlighted in the graph.
+    pub fn frame_budget(&self) -> Duration {
+        self.frame_budget
+    }
+
+    /// Sets how long a frame can take before it gets highlighted in the graph. The graph's scale
+    /// is twice the budget. Defaults to 16 milliseconds.
+    pub fn set_frame_budget(&mut self, frame_budget: Duration) {
+        self.frame_budget = frame_budget;
+        self.widget_tag.request_relayout();
+    }
+
+    /// Retrieves how often the overlay gets redrawn.
+    pub fn refresh_interval(&self) -> Duration {
+        self.refresh_interval
+    }
+
+    /// Sets how often the overlay gets redrawn. Defaults to 250 milliseconds.
+    pub fn set_refresh_interval(&mut self, refresh_interval: Duration) {
+        self.refresh_interval = refresh_interval;
+        if self.shown {
+            self.widget_tag.timers_mut().insert(self.timer_id, Timer::new(refresh_interval));
+        }
+    }
+
+    /// Retrieves the statistics of the frames drawn while the overlay was shown, oldest first.
+    pub fn history(&self) -> &VecDeque<FrameStats> {
+        &self.history
+    }
+
+    fn on_frame_stats(&mut self, stats: &FrameStats) {
+        if !self.shown {
+            return;
+        }
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(*stats);
+        self.stale = true;
+    }
+
+    fn on_toggle(&mut self, _: &ToggleStatsOverlay) {
+        let shown = !self.shown;
+        self.set_shown(shown);
+    }
+
+    fn summary(&self) -> String {
+        let latest = match self.history.back() {
+            Some(latest) => latest,
+            None => return "Waiting for frames".to_string()
+        };
+        let frame_times = self.history.iter().map(|s| s.frame_time());
+        let max = frame_times.clone().max().unwrap_or_default();
+        let average = frame_times.sum::<Duration>() / self.history.len() as u32;
+
+        let mut summary = String::new();
+        let _ = write!(
+            summary,
+            "frame {:.1} ms (avg {:.1}, max {:.1})\n\
+             layout {:.1} ms, {} passes, {} widgets\n\
+             render {:.1} ms, {} widgets",
+            millis(latest.frame_time()), millis(average), millis(max),
+            millis(latest.layout_time), latest.layout_iterations, latest.widgets_laid_out,
+            millis(latest.render_time), latest.widgets_rendered,
+        );
+        if let Some(render) = latest.render {
+            if let Some(gpu_time) = render.gpu_time {
+                let _ = write!(summary, "\ngpu {:.1} ms", millis(gpu_time));
+            }
+            let _ = write!(summary, "\n{} draw calls, {} triangles", render.draw_calls, render.triangles);
+            if let Some(occupancy) = render.atlas_occupancy {
+                let _ = write!(summary, "\natlas {:.0}% full", occupancy * 100.0);
+            }
+        }
+        summary
+    }
+}
+
+impl StatsBar {
+    fn new() -> StatsBar {
+        StatsBar {
+            widget_tag: WidgetTag::new(),
+            rect: BoundBox::new2(0, 0, 0, 0),
+            over_budget: false,
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f32 {
+    duration.as_secs() as f32 * 1000.0 + duration.subsec_nanos() as f32 / 1_000_000.0
+}
+
+impl Widget for StatsOverlay {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.bounds
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget_tag.request_relayout();
+        &mut self.bounds
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.size_bounds
+    }
+
+    fn on_widget_event(&mut self, event: WidgetEventSourced, _: InputState) -> EventOps {
+        if let WidgetEventSourced::This(WidgetEvent::Timer{timer_id, ..}) = event {
+            if timer_id == self.timer_id && self.stale {
+                self.stale = false;
+                self.summary = self.summary();
+                self.widget_tag
+                    .request_relayout()
+                    .request_redraw();
+            }
+        }
+
+        EventOps {
+            focus: None,
+            bubble: event.default_bubble(),
+        }
+    }
+}
+
+impl Widget for StatsBar {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        &self.widget_tag
+    }
+
+    #[inline]
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.rect
+    }
+
+    #[inline]
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        &mut self.rect
+    }
+
+    #[inline]
+    fn on_widget_event(&mut self, _: WidgetEventSourced, _: InputState) -> EventOps {
+        EventOps {
+            focus: None,
+            bubble: true,
+        }
+    }
+}
+
+impl Parent for StatsOverlay {
+    fn num_children(&self) -> usize {
+        self.bars.len()
+    }
+
+    fn framed_child<R: Renderer>(&self, widget_ident: WidgetIdent) -> Option<WidgetInfo<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(index) => self.framed_child_by_index(index as usize),
+            _ => None
+        }
+    }
+    fn framed_child_mut<R: Renderer>(&mut self, widget_ident: WidgetIdent) -> Option<WidgetInfoMut<'_, R>> {
+        match widget_ident {
+            WidgetIdent::Num(index) => self.framed_child_by_index_mut(index as usize),
+            _ => None
+        }
+    }
+
+    fn framed_children<'a, R, G>(&'a self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfo<'a, R>) -> LoopFlow
+    {
+        for (index, bar) in self.bars.iter().enumerate() {
+            if let LoopFlow::Break = for_each(WidgetInfo::new(WidgetIdent::Num(index as u32), index, bar)) {
+                return;
+            }
+        }
+    }
+
+    fn framed_children_mut<'a, R, G>(&'a mut self, mut for_each: G)
+        where R: Renderer,
+              G: FnMut(WidgetInfoMut<'a, R>) -> LoopFlow
+    {
+        for (index, bar) in self.bars.iter_mut().enumerate() {
+            if let LoopFlow::Break = for_each(WidgetInfoMut::new(WidgetIdent::Num(index as u32), index, bar)) {
+                return;
+            }
+        }
+    }
+
+    fn framed_child_by_index<R: Renderer>(&self, index: usize) -> Option<WidgetInfo<'_, R>> {
+        self.bars.get(index).map(|bar| WidgetInfo::new(WidgetIdent::Num(index as u32), index, bar))
+    }
+    fn framed_child_by_index_mut<R: Renderer>(&mut self, index: usize) -> Option<WidgetInfoMut<'_, R>> {
+        self.bars.get_mut(index).map(|bar| WidgetInfoMut::new(WidgetIdent::Num(index as u32), index, bar))
+    }
+}
+
+impl<R> WidgetRenderable<R> for StatsOverlay
+    where R: Renderer
+{
+    type Theme = StatsOverlayTheme;
+
+    fn theme(&self) -> StatsOverlayTheme {
+        StatsOverlayTheme(())
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        if self.shown {
+            frame.render_laid_out_content();
+        }
+    }
+
+    fn update_layout(&mut self, layout: &mut R::Layout) {
+        if !self.shown {
+            self.size_bounds = SizeBounds::default();
+            return;
+        }
+
+        layout.prepare_string(&self.summary);
+        let result = layout.finish();
+        let text_min = result.size_bounds.min;
+        self.size_bounds = SizeBounds {
+            min: DimsBox::new2(
+                text_min.width().max(HISTORY_LEN as i32 * BAR_WIDTH),
+                text_min.height() + GRAPH_HEIGHT
+            ),
+            ..result.size_bounds
+        };
+
+        // The newest frame goes on the right, with the graph filling in from there.
+        let graph_bottom = self.bounds.height();
+        let (history, frame_budget) = (&self.history, self.frame_budget);
+        let scale = millis(frame_budget) * 2.0;
+        let skip = HISTORY_LEN - history.len();
+        for (index, bar) in self.bars.iter_mut().enumerate() {
+            let (height, over_budget) = match index.checked_sub(skip).and_then(|i| history.get(i)) {
+                Some(stats) => {
+                    let frame_time = stats.frame_time();
+                    let height = (millis(frame_time) / scale * GRAPH_HEIGHT as f32) as i32;
+                    (height.max(1).min(GRAPH_HEIGHT), frame_time > frame_budget)
+                },
+                None => (0, false)
+            };
+
+            let x = index as i32 * BAR_WIDTH;
+            bar.rect = BoundBox::new2(x, graph_bottom - height, x + BAR_WIDTH, graph_bottom);
+            if bar.over_budget != over_budget {
+                bar.over_budget = over_budget;
+                bar.widget_tag.request_redraw();
+            }
+        }
+    }
+}
+
+impl<R> WidgetRenderable<R> for StatsBar
+    where R: Renderer
+{
+    type Theme = StatsBarTheme;
+
+    fn theme(&self) -> StatsBarTheme {
+        StatsBarTheme {
+            over_budget: self.over_budget,
+        }
+    }
+
+    fn render(&mut self, frame: &mut R::SubFrame) {
+        frame.render_laid_out_content();
+    }
+
+    fn update_layout(&mut self, _: &mut R::Layout) { }
+}
+
+impl WidgetTheme for StatsOverlayTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
+
+impl WidgetTheme for StatsBarTheme {
+    type Fallback = !;
+    fn fallback(self) -> Option<!> {
+        None
+    }
+}
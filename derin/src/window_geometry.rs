@@ -0,0 +1,235 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Saving and restoring where a window was on the desktop.
+//!
+//! Applications that want their windows to reopen where the user left them can store the
+//! [`WindowGeometry`] of their windows alongside the rest of their settings (it's serializable with
+//! the `derin-serde` feature), and restore it when creating the window the next time. The monitor
+//! setup may have changed in the meantime, so restored geometry should be validated with
+//! [`WindowGeometry::fit_to_monitors`] before being used, which moves the window back onto a
+//! monitor if it would otherwise open off-screen.
+//!
+//! Operating systems report a maximized window's maximized position and size, but the geometry
+//! worth saving is the one the window returns to when it's un-maximized. [`GeometryTracker`] keeps
+//! track of that.
+//!
+//! [`WindowGeometry`]: ./struct.WindowGeometry.html
+//! [`WindowGeometry::fit_to_monitors`]: ./struct.WindowGeometry.html#method.fit_to_monitors
+//! [`GeometryTracker`]: ./struct.GeometryTracker.html
+
+use crate::cgmath::Point2;
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+
+use std::cmp;
+
+/// The position, size, and state of a window.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowGeometry {
+    /// The position of the window's top-left corner, in desktop coordinates.
+    pub position: Point2<i32>,
+    /// The size of the window's contents, excluding the title bar and borders.
+    pub dimensions: DimsBox<D2, u32>,
+    pub maximized: bool,
+    /// The name of the monitor the window was on, if known.
+    pub monitor: Option<String>,
+}
+
+/// A monitor's name and the area it covers on the desktop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    pub name: Option<String>,
+    pub rect: BoundBox<D2, i32>,
+}
+
+/// Remembers a window's position and size from before it was maximized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeometryTracker {
+    restored: Option<(Point2<i32>, DimsBox<D2, u32>)>,
+    maximized: bool,
+}
+
+impl GeometryTracker {
+    pub fn new() -> GeometryTracker {
+        GeometryTracker {
+            restored: None,
+            maximized: false,
+        }
+    }
+
+    /// Whether the window was last set to be maximized.
+    pub fn maximized(&self) -> bool {
+        self.maximized
+    }
+
+    /// Tell the tracker the window's current position and size. They get remembered as the
+    /// restored geometry, unless the window is maximized.
+    pub fn observe(&mut self, position: Point2<i32>, dimensions: DimsBox<D2, u32>) {
+        if !self.maximized {
+            self.restored = Some((position, dimensions));
+        }
+    }
+
+    /// Tell the tracker whether the window is maximized. Call `observe` before maximizing the
+    /// window, so that the tracker knows where to restore it to.
+    pub fn set_maximized(&mut self, maximized: bool) {
+        self.maximized = maximized;
+    }
+
+    /// Retrieves the geometry to save, given the window's current position, size, and monitor.
+    /// While the window is maximized, the position and size it was at before getting maximized
+    /// get used instead, if they're known.
+    pub fn geometry(&self, position: Point2<i32>, dimensions: DimsBox<D2, u32>, monitor: Option<String>) -> WindowGeometry {
+        let (position, dimensions) = match (self.maximized, self.restored) {
+            (true, Some(restored)) => restored,
+            _ => (position, dimensions)
+        };
+        WindowGeometry {
+            position,
+            dimensions,
+            maximized: self.maximized,
+            monitor,
+        }
+    }
+}
+
+impl Default for GeometryTracker {
+    fn default() -> GeometryTracker {
+        GeometryTracker::new()
+    }
+}
+
+impl WindowGeometry {
+    /// Adjust the geometry so that the window lies entirely on one of `monitors`.
+    ///
+    /// The window stays on the monitor it was saved on if that monitor is still connected.
+    /// Otherwise, it's moved to the monitor its center is on, or to the first monitor if its center
+    /// isn't on any. Windows too big for the monitor are shrunk to fit. If `monitors` is empty, the
+    /// geometry is returned unchanged.
+    pub fn fit_to_monitors(&self, monitors: &[Monitor]) -> WindowGeometry {
+        let center = Point2::new(
+            self.position.x.saturating_add((self.dimensions.width() / 2) as i32),
+            self.position.y.saturating_add((self.dimensions.height() / 2) as i32)
+        );
+        let monitor = self.monitor.as_ref()
+            .and_then(|name| monitors.iter().find(|m| m.name.as_ref() == Some(name)))
+            .or_else(|| monitors.iter().find(|m| m.rect.contains(center)))
+            .or_else(|| monitors.first());
+        let monitor = match monitor {
+            Some(monitor) => monitor,
+            None => return self.clone()
+        };
+
+        let rect = monitor.rect;
+        let dimensions = DimsBox::new2(
+            cmp::min(self.dimensions.width(), rect.width() as u32),
+            cmp::min(self.dimensions.height(), rect.height() as u32)
+        );
+        let clamp_axis = |pos: i32, size: u32, min: i32, max: i32| cmp::max(min, cmp::min(pos, max - size as i32));
+
+        WindowGeometry {
+            position: Point2::new(
+                clamp_axis(self.position.x, dimensions.width(), rect.min.x, rect.max.x),
+                clamp_axis(self.position.y, dimensions.height(), rect.min.y, rect.max.y)
+            ),
+            dimensions,
+            maximized: self.maximized,
+            monitor: monitor.name.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitors() -> Vec<Monitor> {
+        vec![
+            Monitor {
+                name: Some("left".to_string()),
+                rect: BoundBox::new2(0, 0, 1920, 1080),
+            },
+            Monitor {
+                name: Some("right".to_string()),
+                rect: BoundBox::new2(1920, 0, 3200, 1024),
+            },
+        ]
+    }
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32, monitor: Option<&str>) -> WindowGeometry {
+        WindowGeometry {
+            position: Point2::new(x, y),
+            dimensions: DimsBox::new2(width, height),
+            maximized: false,
+            monitor: monitor.map(|m| m.to_string()),
+        }
+    }
+
+    #[test]
+    fn on_screen_unchanged() {
+        let on_screen = geometry(2000, 100, 800, 600, Some("right"));
+        assert_eq!(on_screen, on_screen.fit_to_monitors(&monitors()));
+    }
+
+    #[test]
+    fn off_screen() {
+        // The window hangs off the bottom right of the monitor it was saved on.
+        assert_eq!(
+            geometry(2400, 424, 800, 600, Some("right")),
+            geometry(3000, 900, 800, 600, Some("right")).fit_to_monitors(&monitors())
+        );
+        // The window is entirely off the desktop, so it goes to the first monitor.
+        assert_eq!(
+            geometry(0, 480, 800, 600, Some("left")),
+            geometry(-5000, 9000, 800, 600, None).fit_to_monitors(&monitors())
+        );
+    }
+
+    #[test]
+    fn removed_monitor() {
+        // The window's monitor is gone, so it moves to the monitor its center is on...
+        assert_eq!(
+            geometry(1120, 100, 800, 600, Some("left")),
+            geometry(1500, 100, 800, 600, Some("unplugged")).fit_to_monitors(&monitors())
+        );
+        // ...or to the first monitor if its center isn't on any of them.
+        assert_eq!(
+            geometry(1120, 480, 800, 600, Some("left")),
+            geometry(4000, 2000, 800, 600, Some("unplugged")).fit_to_monitors(&monitors())
+        );
+        // And it's left alone if there aren't any monitors at all.
+        let unplugged = geometry(4000, 2000, 800, 600, Some("unplugged"));
+        assert_eq!(unplugged, unplugged.fit_to_monitors(&[]));
+    }
+
+    #[test]
+    fn larger_than_monitor() {
+        assert_eq!(
+            geometry(1920, 0, 1280, 1024, Some("right")),
+            geometry(1800, -50, 2560, 1440, Some("right")).fit_to_monitors(&monitors())
+        );
+    }
+
+    #[test]
+    fn save_restored_geometry() {
+        let (restored_pos, restored_dims) = (Point2::new(100, 100), DimsBox::new2(800, 600));
+        let (maximized_pos, maximized_dims) = (Point2::new(0, 0), DimsBox::new2(1920, 1080));
+        let mut tracker = GeometryTracker::new();
+
+        tracker.observe(restored_pos, restored_dims);
+        tracker.set_maximized(true);
+        // Moves reported while maximized are the maximized geometry, which shouldn't get saved.
+        tracker.observe(maximized_pos, maximized_dims);
+
+        let saved = tracker.geometry(maximized_pos, maximized_dims, None);
+        assert_eq!(restored_pos, saved.position);
+        assert_eq!(restored_dims, saved.dimensions);
+        assert!(saved.maximized);
+
+        tracker.set_maximized(false);
+        let moved_pos = Point2::new(200, 150);
+        assert_eq!(moved_pos, tracker.geometry(moved_pos, restored_dims, None).position);
+    }
+}
@@ -0,0 +1,100 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Compares how quickly and how tightly each allocator packs a glyph-sized workload and an image
+//! workload.
+//!
+//! The glyph workload isn't rasterized from a real font. It's a fixed pseudo-random set of
+//! rectangles in roughly the sizes glyphs come in, so results are only a rough guide to how the
+//! allocators handle real text.
+
+extern crate derin_atlas;
+extern crate image;
+extern crate cgmath_geometry;
+
+use std::time::{Duration, Instant};
+use derin_atlas::allocator::{Allocator, GuillotineAllocator, ShelfAllocator, SkylineAllocator};
+use image::GenericImage;
+use cgmath_geometry::{D2, rect::{DimsBox, GeoBox}};
+
+const RUNS: u32 = 32;
+
+fn main() {
+    let mut glyphs = vec![];
+    for &face_size in &[12, 16, 24, 48] {
+        glyphs.extend(glyph_workload(face_size, 512));
+    }
+
+    let mut images = vec![];
+    for _ in 0..8 {
+        for path in &["test_images/doge.png", "test_images/ffx.png", "test_images/rust.png", "test_images/tf.png"] {
+            let (width, height) = image::open(path).unwrap().dimensions();
+            images.push(DimsBox::new2(width, height));
+        }
+    }
+
+    for &(name, workload, dims) in &[
+        ("glyphs", &glyphs, DimsBox::new2(512, 512)),
+        ("images", &images, DimsBox::new2(1024, 1024))
+    ] {
+        println!("{} ({} rects in {}x{}):", name, workload.len(), dims.width(), dims.height());
+        bench::<SkylineAllocator>("skyline", workload, dims);
+        bench::<ShelfAllocator>("shelf", workload, dims);
+        bench::<GuillotineAllocator>("guillotine", workload, dims);
+        println!();
+    }
+}
+
+fn bench<A: Allocator>(name: &str, workload: &[DimsBox<D2, u32>], dims: DimsBox<D2, u32>) {
+    let mut elapsed = Duration::new(0, 0);
+    let mut placed = 0;
+    let mut used_area = 0;
+
+    for _ in 0..RUNS {
+        let mut allocator = A::new(dims);
+        placed = 0;
+        used_area = 0;
+
+        let start = Instant::now();
+        for &rect in workload {
+            if allocator.allocate(rect).is_some() {
+                placed += 1;
+                used_area += rect.width() * rect.height();
+            }
+        }
+        elapsed += start.elapsed();
+    }
+
+    let micros = elapsed / RUNS;
+    println!(
+        "    {:<12}{:>6} placed{:>8.1}% utilization{:>8}us",
+        name,
+        placed,
+        used_area as f32 / (dims.width() * dims.height()) as f32 * 100.0,
+        micros.as_secs() * 1_000_000 + micros.subsec_micros() as u64
+    );
+}
+
+/// `count` pseudo-random rectangles approximating glyphs at `face_size`. Widths range from a
+/// quarter to three quarters of the face size. Heights are half, three quarters, or all of the
+/// face size, plus up to two pixels. The same face size always produces the same rectangles.
+fn glyph_workload(face_size: u32, count: usize) -> Vec<DimsBox<D2, u32>> {
+    let mut seed: u32 = 0x2545_F491 ^ face_size;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+
+    (0..count).map(|_| {
+        let width = face_size / 4 + next() % (face_size / 2 + 1);
+        let height = match next() % 4 {
+            0 | 1 => face_size / 2,
+            2 => face_size * 3 / 4,
+            _ => face_size
+        } + next() % 3;
+        DimsBox::new2(width, height)
+    }).collect()
+}
@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Rectangle packing strategies for the atlas.
+//!
+//! Which allocator works best depends on what's being stored. `SkylineAllocator` packs
+//! similarly-sized rectangles (like glyphs) tightly, `ShelfAllocator` is the fastest when heights
+//! are uniform, and `GuillotineAllocator` copes best with a mix of small and large images. Run the
+//! `allocators` example to compare them on a given workload.
+
+mod guillotine;
+mod shelf;
+mod skyline;
+
+pub use self::guillotine::GuillotineAllocator;
+pub use self::shelf::ShelfAllocator;
+pub use self::skyline::SkylineAllocator;
+
+use cgmath_geometry::{D2, rect::{DimsBox, OffsetBox, GeoBox}};
+
+/// Decides where in the atlas rectangles get placed.
+pub trait Allocator: Clone {
+    /// Create an empty allocator covering the given area.
+    fn new(dims: DimsBox<D2, u32>) -> Self;
+
+    fn dims(&self) -> DimsBox<D2, u32>;
+
+    /// Resize the area rectangles can be placed in.
+    ///
+    /// Panics if the new dimensions don't contain all the rectangles that have already been
+    /// allocated.
+    fn set_dims(&mut self, dims: DimsBox<D2, u32>);
+
+    /// Find space for a rectangle with the given dimensions. Returns `None` if there isn't room
+    /// for it.
+    fn allocate(&mut self, rect_dims: DimsBox<D2, u32>) -> Option<OffsetBox<D2, u32>>;
+
+    /// Find space for a set of rectangles, returning the allocated rectangles in the same order as
+    /// `rects`. Returns `None` if they don't all fit, in which case the allocator is left in an
+    /// unspecified state.
+    ///
+    /// The default implementation allocates rectangles tallest-first, which gives better packing
+    /// than allocating them in arbitrary order.
+    fn allocate_all(&mut self, rects: &[DimsBox<D2, u32>]) -> Option<Vec<OffsetBox<D2, u32>>> {
+        let mut rects_sorted: Vec<usize> = (0..rects.len()).collect();
+        rects_sorted.sort_unstable_by(|&a, &b| (rects[b].height(), rects[b].width()).cmp(&(rects[a].height(), rects[a].width())));
+
+        let mut allocated = vec![None; rects.len()];
+        for rect_index in rects_sorted {
+            allocated[rect_index] = Some(self.allocate(rects[rect_index])?);
+        }
+
+        Some(allocated.into_iter().map(Option::unwrap).collect())
+    }
+
+    /// The largest rectangle that can currently be allocated.
+    fn largest_free_rect(&self) -> OffsetBox<D2, u32>;
+
+    /// Free all allocated space.
+    fn clear(&mut self);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Glyph-sized rectangles, in the proportions a Latin font rasterized at `face_size` produces:
+    /// mostly x-height lowercase glyphs, with some taller capitals, ascenders, and descenders.
+    fn glyph_workload(face_size: u32, count: usize) -> Vec<DimsBox<D2, u32>> {
+        let mut seed: u32 = 0x2545_F491;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+
+        (0..count).map(|_| {
+            let width = face_size / 4 + next() % (face_size / 2 + 1);
+            let height = match next() % 4 {
+                0 | 1 => face_size / 2,
+                2 => face_size * 3 / 4,
+                _ => face_size
+            } + next() % 3;
+            DimsBox::new2(width, height)
+        }).collect()
+    }
+
+    fn check_allocator<A: Allocator>(rects: &[DimsBox<D2, u32>], min_utilization: f32) {
+        let dims = DimsBox::new2(256, 256);
+        let mut allocator = A::new(dims);
+
+        let mut allocated: Vec<OffsetBox<D2, u32>> = Vec::new();
+        for &rect_dims in rects {
+            match allocator.allocate(rect_dims) {
+                Some(rect) => {
+                    assert_eq!(rect_dims, rect.dims());
+                    assert!(rect.max().x <= dims.width() && rect.max().y <= dims.height(), "{:?} out of bounds", rect);
+                    for other in &allocated {
+                        let overlaps =
+                            rect.min().x < other.max().x && other.min().x < rect.max().x &&
+                            rect.min().y < other.max().y && other.min().y < rect.max().y;
+                        assert!(!overlaps, "{:?} overlaps {:?}", rect, other);
+                    }
+                    allocated.push(rect);
+                },
+                // Keep going, since a smaller rectangle may still fit.
+                None => ()
+            }
+        }
+
+        let used_area: u32 = allocated.iter().map(|r| r.width() * r.height()).sum();
+        let utilization = used_area as f32 / (dims.width() * dims.height()) as f32;
+        assert!(utilization >= min_utilization, "utilization {} < {}", utilization, min_utilization);
+
+        allocator.clear();
+        let repacked = allocator.allocate_all(&rects[..allocated.len() / 4]).unwrap();
+        for (rect, &rect_dims) in repacked.iter().zip(rects) {
+            assert_eq!(rect_dims, rect.dims());
+        }
+
+        let largest_free_rect = allocator.largest_free_rect();
+        assert!(allocator.allocate(largest_free_rect.dims()).is_some());
+
+        let mut allocator = A::new(dims);
+        assert_eq!(OffsetBox::from(dims), allocator.largest_free_rect());
+        assert_eq!(Some(OffsetBox::from(dims)), allocator.allocate(dims));
+        assert_eq!(0, allocator.largest_free_rect().width() * allocator.largest_free_rect().height());
+    }
+
+    #[test]
+    fn skyline_glyphs() {
+        check_allocator::<SkylineAllocator>(&glyph_workload(16, 2048), 0.6);
+        check_allocator::<SkylineAllocator>(&glyph_workload(48, 512), 0.6);
+    }
+
+    #[test]
+    fn shelf_glyphs() {
+        check_allocator::<ShelfAllocator>(&glyph_workload(16, 2048), 0.6);
+        check_allocator::<ShelfAllocator>(&glyph_workload(48, 512), 0.6);
+    }
+
+    #[test]
+    fn guillotine_glyphs() {
+        check_allocator::<GuillotineAllocator>(&glyph_workload(16, 2048), 0.6);
+        check_allocator::<GuillotineAllocator>(&glyph_workload(48, 512), 0.6);
+    }
+}
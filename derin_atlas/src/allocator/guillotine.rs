@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cmp;
+
+use cgmath_geometry::{D2, rect::{DimsBox, OffsetBox, GeoBox}};
+
+use super::Allocator;
+
+/// Packs rectangles by tracking a list of free rectangles, and splitting whichever free rectangle
+/// fits best in two whenever a rectangle is placed inside of it.
+///
+/// Handles rectangles of very different sizes better than the other allocators, which makes it a
+/// good fit for large images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuillotineAllocator {
+    dims: DimsBox<D2, u32>,
+    free_rects: Vec<OffsetBox<D2, u32>>,
+    // Exclusive max corner of the space that's been allocated.
+    used_max_x: u32,
+    used_max_y: u32,
+}
+
+impl GuillotineAllocator {
+    fn push_free_rect(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.free_rects.push(OffsetBox::new2(x, y, width, height));
+        }
+    }
+}
+
+impl Allocator for GuillotineAllocator {
+    #[inline]
+    fn new(dims: DimsBox<D2, u32>) -> GuillotineAllocator {
+        let mut allocator = GuillotineAllocator {
+            dims,
+            free_rects: Vec::new(),
+            used_max_x: 0,
+            used_max_y: 0,
+        };
+        allocator.clear();
+        allocator
+    }
+
+    #[inline]
+    fn dims(&self) -> DimsBox<D2, u32> {
+        self.dims
+    }
+
+    fn set_dims(&mut self, dims: DimsBox<D2, u32>) {
+        assert!(self.used_max_y <= dims.height());
+        assert!(self.used_max_x <= dims.width());
+
+        let old_dims = self.dims;
+        self.dims = dims;
+
+        // Clip the free space to the new dimensions...
+        for rect in &mut self.free_rects {
+            let max_x = cmp::min(rect.max().x, dims.width());
+            let max_y = cmp::min(rect.max().y, dims.height());
+            *rect = OffsetBox::new2(
+                rect.min().x,
+                rect.min().y,
+                max_x.saturating_sub(rect.min().x),
+                max_y.saturating_sub(rect.min().y)
+            );
+        }
+        self.free_rects.retain(|r| r.width() > 0 && r.height() > 0);
+
+        // ...and add any space that's been newly opened up.
+        let (old_width, old_height) = (cmp::min(old_dims.width(), dims.width()), cmp::min(old_dims.height(), dims.height()));
+        self.push_free_rect(old_width, 0, dims.width() - old_width, old_height);
+        self.push_free_rect(0, old_height, dims.width(), dims.height() - old_height);
+    }
+
+    fn allocate(&mut self, rect_dims: DimsBox<D2, u32>) -> Option<OffsetBox<D2, u32>> {
+        // Find the free rect that leaves the shortest leftover side once the rect is placed in it.
+        let mut best_free: Option<(usize, u32)> = None;
+        for (index, free_rect) in self.free_rects.iter().enumerate() {
+            if free_rect.width() < rect_dims.width() || free_rect.height() < rect_dims.height() {
+                continue;
+            }
+
+            let short_side_lost = cmp::min(free_rect.width() - rect_dims.width(), free_rect.height() - rect_dims.height());
+            match best_free {
+                Some((_, best_short_side_lost)) if best_short_side_lost <= short_side_lost => (),
+                _ => best_free = Some((index, short_side_lost))
+            }
+        }
+
+        let free_rect = self.free_rects.swap_remove(best_free?.0);
+        let (x, y) = (free_rect.min().x, free_rect.min().y);
+        let width_left = free_rect.width() - rect_dims.width();
+        let height_left = free_rect.height() - rect_dims.height();
+
+        // Split along the shorter leftover axis, which keeps the larger of the two new free rects as
+        // big as possible.
+        if width_left < height_left {
+            self.push_free_rect(x + rect_dims.width(), y, width_left, rect_dims.height());
+            self.push_free_rect(x, y + rect_dims.height(), free_rect.width(), height_left);
+        } else {
+            self.push_free_rect(x + rect_dims.width(), y, width_left, free_rect.height());
+            self.push_free_rect(x, y + rect_dims.height(), rect_dims.width(), height_left);
+        }
+
+        self.used_max_x = cmp::max(self.used_max_x, x + rect_dims.width());
+        self.used_max_y = cmp::max(self.used_max_y, y + rect_dims.height());
+
+        Some(OffsetBox::new2(x, y, rect_dims.width(), rect_dims.height()))
+    }
+
+    fn largest_free_rect(&self) -> OffsetBox<D2, u32> {
+        self.free_rects.iter().cloned()
+            .max_by_key(|r| r.width() * r.height())
+            .unwrap_or(OffsetBox::new2(0, 0, 0, 0))
+    }
+
+    fn clear(&mut self) {
+        let dims = self.dims;
+        self.free_rects.clear();
+        self.push_free_rect(0, 0, dims.width(), dims.height());
+        self.used_max_x = 0;
+        self.used_max_y = 0;
+    }
+}
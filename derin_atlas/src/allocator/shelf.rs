@@ -0,0 +1,120 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use cgmath_geometry::{D2, rect::{DimsBox, OffsetBox, GeoBox}};
+
+use super::Allocator;
+
+/// Packs rectangles left-to-right into horizontal shelves, opening a new shelf once none of the
+/// existing shelves have room.
+///
+/// This is the fastest of the allocators, and packs well when rectangle heights are close
+/// together, but wastes space when heights vary wildly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShelfAllocator {
+    dims: DimsBox<D2, u32>,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    used_width: u32,
+}
+
+impl ShelfAllocator {
+    /// The y coordinate at which the next shelf would be opened.
+    fn shelves_top(&self) -> u32 {
+        self.shelves.last().map(|s| s.y + s.height).unwrap_or(0)
+    }
+
+    fn best_shelf(&self, rect_dims: DimsBox<D2, u32>) -> Option<usize> {
+        let mut best_shelf: Option<(usize, u32)> = None;
+        for (index, shelf) in self.shelves.iter().enumerate() {
+            let fits =
+                shelf.height >= rect_dims.height() &&
+                self.dims.width() - shelf.used_width >= rect_dims.width();
+            if !fits {
+                continue;
+            }
+
+            let height_lost = shelf.height - rect_dims.height();
+            match best_shelf {
+                Some((_, best_height_lost)) if best_height_lost <= height_lost => (),
+                _ => best_shelf = Some((index, height_lost))
+            }
+        }
+
+        best_shelf.map(|(index, _)| index)
+    }
+}
+
+impl Allocator for ShelfAllocator {
+    #[inline]
+    fn new(dims: DimsBox<D2, u32>) -> ShelfAllocator {
+        ShelfAllocator {
+            dims,
+            shelves: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn dims(&self) -> DimsBox<D2, u32> {
+        self.dims
+    }
+
+    fn set_dims(&mut self, dims: DimsBox<D2, u32>) {
+        let used_width = self.shelves.iter().map(|s| s.used_width).max().unwrap_or(0);
+        assert!(self.shelves_top() <= dims.height());
+        assert!(used_width <= dims.width());
+
+        self.dims = dims;
+    }
+
+    fn allocate(&mut self, rect_dims: DimsBox<D2, u32>) -> Option<OffsetBox<D2, u32>> {
+        if rect_dims.width() > self.dims.width() {
+            return None;
+        }
+
+        let shelves_top = self.shelves_top();
+        let can_open_shelf = self.dims.height() - shelves_top >= rect_dims.height();
+
+        // Putting a short rectangle on a tall shelf wastes most of the shelf's height, so we'd rather
+        // open a new shelf if there's room for one.
+        let shelf_index = match self.best_shelf(rect_dims) {
+            Some(index) if !can_open_shelf || self.shelves[index].height / 2 <= rect_dims.height() => index,
+            _ if can_open_shelf => {
+                self.shelves.push(Shelf {
+                    y: shelves_top,
+                    height: rect_dims.height(),
+                    used_width: 0,
+                });
+                self.shelves.len() - 1
+            },
+            _ => return None
+        };
+
+        let shelf = &mut self.shelves[shelf_index];
+        let rect = OffsetBox::new2(shelf.used_width, shelf.y, rect_dims.width(), rect_dims.height());
+        shelf.used_width += rect_dims.width();
+
+        Some(rect)
+    }
+
+    fn largest_free_rect(&self) -> OffsetBox<D2, u32> {
+        let shelves_top = self.shelves_top();
+        let top_rect = OffsetBox::new2(0, shelves_top, self.dims.width(), self.dims.height() - shelves_top);
+
+        self.shelves.iter()
+            .map(|s| OffsetBox::new2(s.used_width, s.y, self.dims.width() - s.used_width, s.height))
+            .chain(Some(top_rect))
+            .max_by_key(|r| r.width() * r.height())
+            .unwrap()
+    }
+
+    fn clear(&mut self) {
+        self.shelves.clear();
+    }
+}
@@ -0,0 +1,253 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cmp;
+use std::ops::Range;
+
+use crate::cgmath::Vector2;
+use cgmath_geometry::{D2, rect::{DimsBox, OffsetBox, GeoBox}};
+
+use super::Allocator;
+
+/// Packs rectangles along a skyline - the top edge of the rectangles that have already been
+/// placed.
+///
+/// Gives tight packing for lots of similarly-sized rectangles, which makes it a good fit for glyph
+/// caches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkylineAllocator {
+    dims: DimsBox<D2, u32>,
+    heights: Vec<HeightRange>,
+    max_used_height: u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HeightRange {
+    bounds_min: u32,
+    // Exclusive max value
+    bounds_max: u32,
+    height: u32
+}
+
+#[derive(Debug, Clone)]
+struct InsertOver {
+    range: Range<usize>,
+    width: u32,
+    height: u32,
+    space_lost: u32
+}
+
+impl SkylineAllocator {
+    pub fn max_used_height(&self) -> u32 {
+        self.max_used_height
+    }
+
+    fn calc_insert_over(&self, image_dims: DimsBox<D2, u32>) -> Option<InsertOver> {
+        let mut best_range = InsertOver {
+            range: 0..self.heights.len(),
+            width: self.dims.width(),
+            height: self.dims.height(),
+            space_lost: self.dims.width() * self.dims.height()
+        };
+        for (i, height) in self.heights.iter().enumerate() {
+            let mut active_range = InsertOver {
+                range: i..i+1,
+                width: height.width(),
+                height: height.height,
+                space_lost: 0
+            };
+
+            for next_height in self.heights[i + 1..].iter() {
+                if active_range.width >= image_dims.width() {
+                    break;
+                }
+
+                active_range.range.end += 1;
+                if next_height.height > active_range.height {
+                    active_range.space_lost += active_range.width * next_height.height - active_range.height;
+                    active_range.height = next_height.height;
+                } else if next_height.height < active_range.height {
+                    let lost_width;
+                    if active_range.width + next_height.width() >= image_dims.width() {
+                        lost_width = next_height.width() - (image_dims.width() - active_range.width);
+                    } else {
+                        lost_width = next_height.width();
+                    }
+
+                    if lost_width > 0 {
+                        active_range.space_lost += lost_width * active_range.height - next_height.height;
+                    }
+                }
+
+                active_range.width += next_height.width();
+            }
+
+            let active_is_better =
+                (
+                    active_range.space_lost < best_range.space_lost ||
+                    active_range.height < best_range.height
+                ) &&
+                active_range.width >= image_dims.width() &&
+                self.dims.height() - active_range.height >= image_dims.height();
+            if active_is_better {
+                best_range = active_range;
+            }
+        }
+
+        if self.dims.height() - best_range.height < image_dims.height() || best_range.width < image_dims.width() {
+            return None;
+        }
+
+        Some(best_range)
+    }
+
+    fn insert_over(&mut self, insert_over: InsertOver, image_dims: DimsBox<D2, u32>) -> OffsetBox<D2, u32>
+    {
+        let insert_offset = Vector2::new(self.heights[insert_over.range.start].bounds_min, insert_over.height);
+
+        let bounds_min = self.heights[insert_over.range.start].bounds_min;
+        let insert_range = HeightRange {
+            bounds_min,
+            bounds_max: bounds_min + image_dims.width(),
+            height: insert_over.height + image_dims.height()
+        };
+        if insert_over.width == image_dims.width() {
+            self.heights.splice(insert_over.range.clone(), Some(insert_range));
+        } else {
+            self.heights[insert_over.range.end - 1].bounds_min = bounds_min + image_dims.width();
+            self.heights.splice(insert_over.range.start..insert_over.range.end - 1, Some(insert_range));
+        }
+        self.max_used_height = cmp::max(self.max_used_height, insert_range.height);
+
+        OffsetBox::from(image_dims) + insert_offset
+    }
+}
+
+impl Allocator for SkylineAllocator {
+    #[inline]
+    fn new(dims: DimsBox<D2, u32>) -> SkylineAllocator {
+        let base_range = HeightRange {
+            bounds_min: 0,
+            bounds_max: dims.width(),
+            height: 0
+        };
+
+        SkylineAllocator {
+            dims,
+            heights: vec![base_range],
+            max_used_height: 0
+        }
+    }
+
+    #[inline]
+    fn dims(&self) -> DimsBox<D2, u32> {
+        self.dims
+    }
+
+    fn set_dims(&mut self, dims: DimsBox<D2, u32>) {
+        let free_width = {
+            let last_height = self.heights.last().unwrap();
+            match last_height.height {
+                0 => last_height.width(),
+                _ => 0
+            }
+        };
+        let free_height = self.dims.height() - self.max_used_height;
+        assert!(self.dims.height() - free_height <= dims.height());
+        assert!(self.dims.width() - free_width <= dims.width());
+
+        let old_dims = self.dims;
+        self.dims = dims;
+
+        if dims.width() < old_dims.width() {
+            self.heights.last_mut().unwrap().bounds_max -= old_dims.width() - dims.width();
+        } else {
+            self.heights.last_mut().unwrap().bounds_max += dims.width() - old_dims.width();
+        }
+        if dims.height() < old_dims.height() {
+            self.max_used_height -= old_dims.height() - dims.height();
+        } else {
+            self.max_used_height += dims.height() - old_dims.height();
+        }
+    }
+
+    fn allocate(&mut self, rect_dims: DimsBox<D2, u32>) -> Option<OffsetBox<D2, u32>> {
+        let range = self.calc_insert_over(rect_dims)?;
+        Some(self.insert_over(range, rect_dims))
+    }
+
+    fn allocate_all(&mut self, rects: &[DimsBox<D2, u32>]) -> Option<Vec<OffsetBox<D2, u32>>> {
+        let mut rects_sorted: Vec<usize> = (0..rects.len()).collect();
+        rects_sorted.sort_unstable_by(|&a, &b| (rects[b].height(), rects[b].width()).cmp(&(rects[a].height(), rects[a].width())));
+
+        // Rather than inserting the rectangles in order, insert whichever remaining rectangle wastes
+        // the least space under the skyline.
+        let mut allocated = vec![None; rects.len()];
+        while rects_sorted.len() > 0 {
+            let mut best_insert: Option<(usize, InsertOver)> = None;
+
+            for (index, &rect_index) in rects_sorted.iter().enumerate() {
+                let insert_over = self.calc_insert_over(rects[rect_index])?;
+                let is_better = match best_insert {
+                    Some((_, ref best_insert_over)) => insert_over.space_lost < best_insert_over.space_lost,
+                    None => true
+                };
+
+                if is_better {
+                    let no_space_lost = insert_over.space_lost == 0;
+                    best_insert = Some((index, insert_over));
+
+                    if no_space_lost {
+                        break;
+                    }
+                }
+            }
+
+            let (index, insert_over) = best_insert.unwrap();
+            let rect_index = rects_sorted.remove(index);
+            allocated[rect_index] = Some(self.insert_over(insert_over, rects[rect_index]));
+        }
+
+        Some(allocated.into_iter().map(Option::unwrap).collect())
+    }
+
+    fn largest_free_rect(&self) -> OffsetBox<D2, u32> {
+        let mut largest_free_rect = OffsetBox::new2(0, 0, 0, 0);
+        for (i, start_height) in self.heights.iter().enumerate() {
+            let mut top = 0;
+            for end_height in &self.heights[i..] {
+                top = cmp::max(top, end_height.height);
+                let free_rect = OffsetBox::new2(
+                    start_height.bounds_min,
+                    top,
+                    end_height.bounds_max - start_height.bounds_min,
+                    self.dims.height() - top
+                );
+
+                if free_rect.width() * free_rect.height() > largest_free_rect.width() * largest_free_rect.height() {
+                    largest_free_rect = free_rect;
+                }
+            }
+        }
+
+        largest_free_rect
+    }
+
+    fn clear(&mut self) {
+        self.heights.clear();
+        self.heights.push(HeightRange {
+            bounds_min: 0,
+            bounds_max: self.dims.width(),
+            height: 0
+        });
+        self.max_used_height = 0;
+    }
+}
+
+impl HeightRange {
+    #[inline]
+    fn width(&self) -> u32 {
+        self.bounds_max - self.bounds_min
+    }
+}
@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use png::HasParameters;
+use cgmath_geometry::{D2, rect::{DimsBox, OffsetBox, GeoBox}};
+
+const BACKGROUND: [u8; 4] = [32, 32, 32, 255];
+const FREE_OUTLINE: [u8; 4] = [255, 255, 255, 255];
+const RECT_OUTLINES: [[u8; 4]; 6] = [
+    [230, 80, 80, 255],
+    [80, 200, 80, 255],
+    [80, 130, 230, 255],
+    [230, 200, 60, 255],
+    [200, 90, 220, 255],
+    [60, 210, 210, 255],
+];
+
+pub fn dump_png(
+    path: &Path,
+    dims: DimsBox<D2, u32>,
+    rects: &[OffsetBox<D2, u32>],
+    largest_free_rect: OffsetBox<D2, u32>
+) -> io::Result<()> {
+    let mut pixels = vec![BACKGROUND; (dims.width() * dims.height()) as usize];
+
+    for (i, rect) in rects.iter().enumerate() {
+        let outline = RECT_OUTLINES[i % RECT_OUTLINES.len()];
+        let fill = [outline[0] / 3, outline[1] / 3, outline[2] / 3, 255];
+        draw_rect(&mut pixels, dims, *rect, fill, outline);
+    }
+    draw_rect(&mut pixels, dims, largest_free_rect, BACKGROUND, FREE_OUTLINE);
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), dims.width(), dims.height());
+    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let pixel_bytes: Vec<u8> = pixels.iter().flat_map(|p| p.iter().cloned()).collect();
+    writer.write_image_data(&pixel_bytes)?;
+
+    Ok(())
+}
+
+fn draw_rect(pixels: &mut [[u8; 4]], dims: DimsBox<D2, u32>, rect: OffsetBox<D2, u32>, fill: [u8; 4], outline: [u8; 4]) {
+    if rect.width() == 0 || rect.height() == 0 {
+        return;
+    }
+
+    for y in rect.min().y..rect.max().y {
+        for x in rect.min().x..rect.max().x {
+            let on_edge =
+                x == rect.min().x || x == rect.max().x - 1 ||
+                y == rect.min().y || y == rect.max().y - 1;
+            pixels[(y * dims.width() + x) as usize] = match on_edge {
+                true => outline,
+                false => fill
+            };
+        }
+    }
+}
@@ -7,51 +7,49 @@
 use cgmath_geometry::cgmath;
 extern crate cgmath_geometry;
 
-use std::{cmp, mem};
-use std::ops::Range;
+pub mod allocator;
+#[cfg(feature = "debug-dump")]
+mod debug_dump;
 
-use crate::cgmath::{EuclideanSpace, Point2, Vector2};
-use cgmath_geometry::{D2, rect::{DimsBox, OffsetBox, GeoBox}};
+use std::mem;
+#[cfg(feature = "debug-dump")]
+use std::{io, path::Path};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct HeightRange {
-    bounds_min: u32,
-    // Exclusive max value
-    bounds_max: u32,
-    height: u32
-}
+use crate::allocator::{Allocator, SkylineAllocator};
+use crate::cgmath::{EuclideanSpace, Vector2};
+use cgmath_geometry::{D2, rect::{DimsBox, OffsetBox, GeoBox}};
 
+/// An image atlas, which uses `A` to decide where images get placed.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SkylineAtlas<P: Copy> {
+pub struct Atlas<P: Copy, A: Allocator = SkylineAllocator> {
     background_color: P,
-    dims: DimsBox<D2, u32>,
     pixels: Vec<P>,
-    heights: Vec<HeightRange>,
-    max_used_height: u32
+    allocator: A,
+    rects: Vec<OffsetBox<D2, u32>>
 }
 
-#[derive(Debug, Clone)]
-struct InsertOver {
-    range: Range<usize>,
-    width: u32,
-    height: u32,
-    space_lost: u32
+pub type SkylineAtlas<P> = Atlas<P, SkylineAllocator>;
+
+/// A snapshot of how much of the atlas is in use, retrieved with `Atlas::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasStats {
+    pub dims: DimsBox<D2, u32>,
+    /// The number of images stored in the atlas.
+    pub rect_count: usize,
+    /// The number of pixels covered by images.
+    pub used_area: u32,
+    /// The largest rectangle that can currently be added to the atlas.
+    pub largest_free_rect: OffsetBox<D2, u32>,
 }
 
-impl<P: Copy> SkylineAtlas<P> {
+impl<P: Copy, A: Allocator> Atlas<P, A> {
     #[inline]
-    pub fn new(background_color: P, dims: DimsBox<D2, u32>) -> SkylineAtlas<P> {
-        let base_range = HeightRange {
-            bounds_min: 0,
-            bounds_max: dims.width(),
-            height: 0
-        };
-
-        SkylineAtlas {
-            background_color, dims,
+    pub fn new(background_color: P, dims: DimsBox<D2, u32>) -> Atlas<P, A> {
+        Atlas {
+            background_color,
             pixels: vec![background_color; (dims.width() * dims.height()) as usize],
-            heights: vec![base_range],
-            max_used_height: 0
+            allocator: A::new(dims),
+            rects: Vec::new()
         }
     }
 
@@ -62,122 +60,46 @@ impl<P: Copy> SkylineAtlas<P> {
 
     #[inline]
     pub fn dims(&self) -> DimsBox<D2, u32> {
-        self.dims
+        self.allocator.dims()
     }
 
-    fn calc_insert_over(&self, image_dims: DimsBox<D2, u32>) -> Option<InsertOver> {
-        let mut best_range = InsertOver {
-            range: 0..self.heights.len(),
-            width: self.dims.width(),
-            height: self.dims.height(),
-            space_lost: self.dims.width() * self.dims.height()
-        };
-        for (i, height) in self.heights.iter().enumerate() {
-            let mut active_range = InsertOver {
-                range: i..i+1,
-                width: height.width(),
-                height: height.height,
-                space_lost: 0
-            };
-
-            for next_height in self.heights[i + 1..].iter() {
-                if active_range.width >= image_dims.width() {
-                    break;
-                }
-
-                active_range.range.end += 1;
-                if next_height.height > active_range.height {
-                    active_range.space_lost += active_range.width * next_height.height - active_range.height;
-                    active_range.height = next_height.height;
-                } else if next_height.height < active_range.height {
-                    let lost_width;
-                    if active_range.width + next_height.width() >= image_dims.width() {
-                        lost_width = next_height.width() - (image_dims.width() - active_range.width);
-                    } else {
-                        lost_width = next_height.width();
-                    }
-
-                    if lost_width > 0 {
-                        active_range.space_lost += lost_width * active_range.height - next_height.height;
-                    }
-                }
-
-                active_range.width += next_height.width();
-            }
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
 
-            let active_is_better =
-                (
-                    active_range.space_lost < best_range.space_lost ||
-                    active_range.height < best_range.height
-                ) &&
-                active_range.width >= image_dims.width() &&
-                self.dims.height() - active_range.height >= image_dims.height();
-            if active_is_better {
-                best_range = active_range;
-            }
-        }
+    /// The rectangles of all images currently stored in the atlas.
+    #[inline]
+    pub fn rects(&self) -> &[OffsetBox<D2, u32>] {
+        &self.rects
+    }
 
-        if self.dims.height() - best_range.height < image_dims.height() || best_range.width < image_dims.width() {
-            return None;
+    pub fn stats(&self) -> AtlasStats {
+        AtlasStats {
+            dims: self.dims(),
+            rect_count: self.rects.len(),
+            used_area: self.rects.iter().map(|r| r.width() * r.height()).sum(),
+            largest_free_rect: self.allocator.largest_free_rect(),
         }
+    }
 
-        Some(best_range)
+    /// Write an image of the atlas' layout to a PNG file at `path`, for debugging.
+    ///
+    /// Each stored image is drawn as a colored outline, and the largest free rectangle is drawn
+    /// in white. Pixel contents aren't drawn.
+    #[cfg(feature = "debug-dump")]
+    pub fn debug_dump_png<Q: AsRef<Path>>(&self, path: Q) -> io::Result<()> {
+        debug_dump::dump_png(path.as_ref(), self.dims(), &self.rects, self.allocator.largest_free_rect())
     }
 
     pub fn set_dims(&mut self, background_color: P, dims: DimsBox<D2, u32>) {
-        let free_width = {
-            let last_height = self.heights.last().unwrap();
-            match last_height.height {
-                0 => last_height.width(),
-                _ => 0
-            }
-        };
-        let free_height = self.dims.height() - self.max_used_height;
-        assert!(self.dims.height() - free_height <= dims.height());
-        assert!(self.dims.width() - free_width <= dims.width());
+        let old_dims = self.dims();
+        self.allocator.set_dims(dims);
 
         let mut pixel_swap = vec![background_color; (dims.width() * dims.height()) as usize];
         mem::swap(&mut pixel_swap, &mut self.pixels);
 
-        let old_dims = self.dims;
-        self.dims = dims;
         self.blit(old_dims, old_dims.into(), Vector2::new(0, 0), &pixel_swap);
-
-        if dims.width() < old_dims.width() {
-            self.heights.last_mut().unwrap().bounds_max -= old_dims.width() - dims.width();
-        } else {
-            self.heights.last_mut().unwrap().bounds_max += dims.width() - old_dims.width();
-        }
-        if dims.height() < old_dims.height() {
-            self.max_used_height -= old_dims.height() - dims.height();
-        } else {
-            self.max_used_height += dims.height() - old_dims.height();
-        }
-    }
-
-    pub fn max_used_height(&self) -> u32 {
-        self.max_used_height
-    }
-
-    fn insert_over(&mut self, insert_over: InsertOver, image_dims: DimsBox<D2, u32>) -> OffsetBox<D2, u32>
-    {
-        let insert_offset = Vector2::new(self.heights[insert_over.range.start].bounds_min, insert_over.height);
-
-        let bounds_min = self.heights[insert_over.range.start].bounds_min;
-        let insert_range = HeightRange {
-            bounds_min,
-            bounds_max: bounds_min + image_dims.width(),
-            height: insert_over.height + image_dims.height()
-        };
-        if insert_over.width == image_dims.width() {
-            self.heights.splice(insert_over.range.clone(), Some(insert_range));
-        } else {
-            self.heights[insert_over.range.end - 1].bounds_min = bounds_min + image_dims.width();
-            self.heights.splice(insert_over.range.start..insert_over.range.end - 1, Some(insert_range));
-        }
-        self.max_used_height = cmp::max(self.max_used_height, insert_range.height);
-
-        OffsetBox::from(image_dims) + insert_offset
     }
 
     pub fn add_image(&mut self, image_dims: DimsBox<D2, u32>, image_view: OffsetBox<D2, u32>, image_data: &[P]) -> Option<OffsetBox<D2, u32>> {
@@ -188,10 +110,10 @@ impl<P: Copy> SkylineAtlas<P> {
         where I: IntoIterator<Item=&'a [P]>,
               P: 'a
     {
-        match self.calc_insert_over(image_dims) {
-            Some(range) => {
-                let insert_rect = self.insert_over(range, image_dims);
+        match self.allocator.allocate(image_dims) {
+            Some(insert_rect) => {
                 self.blit_rows(image_dims, insert_rect.min().to_vec(), image_data);
+                self.rects.push(insert_rect);
                 Ok(insert_rect)
             },
             None => Err(image_data)
@@ -202,10 +124,10 @@ impl<P: Copy> SkylineAtlas<P> {
         where I: IntoIterator<Item=J>,
               J: IntoIterator<Item=P>
     {
-        match self.calc_insert_over(image_dims) {
-            Some(range) =>{
-                let insert_rect = self.insert_over(range, image_dims);
+        match self.allocator.allocate(image_dims) {
+            Some(insert_rect) => {
                 self.blit_pixels(image_dims, insert_rect.min().to_vec(), image_data);
+                self.rects.push(insert_rect);
                 Ok(insert_rect)
             },
             None => Err(image_data)
@@ -213,12 +135,8 @@ impl<P: Copy> SkylineAtlas<P> {
     }
 
     pub fn clear(&mut self, background_color: Option<P>) {
-        self.heights.clear();
-        self.heights.push(HeightRange {
-            bounds_min: 0,
-            bounds_max: self.dims.width(),
-            height: 0
-        });
+        self.allocator.clear();
+        self.rects.clear();
 
         if let Some(bgc) = background_color {
             for pixel in &mut self.pixels {
@@ -227,80 +145,40 @@ impl<P: Copy> SkylineAtlas<P> {
         }
     }
 
+    /// Re-pack the given rectangles into the atlas, updating them with their new positions. Any
+    /// pixels outside of `rects` are discarded.
+    ///
+    /// If the rectangles can't be re-packed, the atlas and the rectangles are left unchanged.
     pub fn compact<'a, I>(&mut self, rects: I)
         where I: IntoIterator<Item=&'a mut OffsetBox<D2, u32>>
     {
-        let mut old_pixels = vec![self.background_color; self.pixels.len()];
-        mem::swap(&mut old_pixels, &mut self.pixels);
-        let old_heights = self.heights.clone();
-
-        let mut rects_sorted = {
-            let mut rects: Vec<(OffsetBox<D2, u32>, &'a mut OffsetBox<D2, u32>)> = rects.into_iter().map(|r| (*r, r)).collect();
-            rects.sort_unstable_by(|&(_, ref a), &(_, ref b)| (b.height(), b.width()).cmp(&(a.height(), a.width())));
-            rects
+        let mut rects: Vec<&'a mut OffsetBox<D2, u32>> = rects.into_iter().collect();
+        let rect_dims: Vec<DimsBox<D2, u32>> = rects.iter().map(|r| r.dims()).collect();
+
+        let mut allocator = self.allocator.clone();
+        allocator.clear();
+        let new_rects = match allocator.allocate_all(&rect_dims) {
+            Some(new_rects) => new_rects,
+            None => return
         };
-        let mut removed_rects = Vec::with_capacity(rects_sorted.len());
-
-        self.max_used_height = 0;
-        self.heights.clear();
-        self.heights.push(HeightRange {
-            bounds_min: 0,
-            bounds_max: self.dims.width(),
-            height: 0
-        });
-
-        let mut reset_atlas = false;
-        let dims = self.dims;
-        'main_rect: while rects_sorted.len() > 0 {
-            let mut best_insert_index = usize::max_value();
-            let mut best_insert_over = InsertOver {
-                range: 0..self.heights.len(),
-                width: self.dims.width(),
-                height: self.dims.height(),
-                space_lost: self.dims.width() * self.dims.height()
-            };
-
-            for (index, &mut (_, ref mut rect)) in rects_sorted.iter_mut().enumerate() {
-                match self.calc_insert_over(rect.dims()) {
-                    Some(insert_over) => {
-                        if insert_over.space_lost < best_insert_over.space_lost || best_insert_index == usize::max_value() {
-                            best_insert_index = index;
-                            best_insert_over = insert_over;
-
-                            if best_insert_over.space_lost == 0 {
-                                break;
-                            }
-                        }
-                    },
-                    None => {
-                        reset_atlas = true;
-                        break 'main_rect;
-                    }
-                }
-            }
+        self.allocator = allocator;
 
-            let remove_rect = rects_sorted.remove(best_insert_index);
-            *remove_rect.1 = self.insert_over(
-                best_insert_over,
-                remove_rect.0.dims()
-            );
-            self.blit(dims, remove_rect.0, remove_rect.1.min().to_vec(), &old_pixels);
-            removed_rects.push(remove_rect);
-        }
+        let mut old_pixels = vec![self.background_color; self.pixels.len()];
+        mem::swap(&mut old_pixels, &mut self.pixels);
 
-        if reset_atlas {
-            self.pixels = old_pixels;
-            self.heights = old_heights;
-            for (old_rect, rect_ref) in rects_sorted.drain(..).chain(removed_rects.drain(..)) {
-                *rect_ref = old_rect;
-            }
+        let dims = self.dims();
+        for (rect, new_rect) in rects.iter_mut().zip(new_rects) {
+            self.blit(dims, **rect, new_rect.min().to_vec(), &old_pixels);
+            **rect = new_rect;
         }
+        self.rects = rects.into_iter().map(|r| *r).collect();
     }
 
     pub fn blit(&mut self, image_dims: DimsBox<D2, u32>, image_view: OffsetBox<D2, u32>, write_offset: Vector2<u32>, image_data: &[P]) {
+        let dims = self.dims();
         blit(
             rows_from_image(image_dims, image_view, image_data), image_view.dims(),
-            &mut self.pixels, self.dims, write_offset
+            &mut self.pixels, dims, write_offset
         );
     }
 
@@ -308,21 +186,42 @@ impl<P: Copy> SkylineAtlas<P> {
         where I: IntoIterator<Item=&'a [P]>,
               P: 'a
     {
-        blit(image_data, image_dims, &mut self.pixels, self.dims, write_offset);
+        let dims = self.dims();
+        blit(image_data, image_dims, &mut self.pixels, dims, write_offset);
     }
 
     pub fn blit_pixels<'a, I, J>(&mut self, image_dims: DimsBox<D2, u32>, write_offset: Vector2<u32>, image_data: I)
         where I: IntoIterator<Item=J>,
               J: IntoIterator<Item=P>
     {
-        blit_pixels(image_data, image_dims, &mut self.pixels, self.dims, write_offset);
+        let dims = self.dims();
+        blit_pixels(image_data, image_dims, &mut self.pixels, dims, write_offset);
     }
 }
 
-impl HeightRange {
-    #[inline]
-    fn width(&self) -> u32 {
-        self.bounds_max - self.bounds_min
+impl AtlasStats {
+    /// The fraction of the atlas' area that's covered by images, from `0.0` to `1.0`.
+    pub fn used_fraction(&self) -> f32 {
+        self.used_area as f32 / (self.dims.width() * self.dims.height()) as f32
+    }
+
+    /// Estimates how badly the free space is split up, from `0.0` to `1.0`.
+    ///
+    /// `0.0` means all the free space can be used by one image. Values close to `1.0` mean the free
+    /// space is scattered in slivers, so inserting even a modestly-sized image is likely to fail
+    /// despite `used_fraction` being low.
+    pub fn fragmentation(&self) -> f32 {
+        let free_area = self.dims.width() * self.dims.height() - self.used_area;
+        match free_area {
+            0 => 0.0,
+            _ => 1.0 - (self.largest_free_rect.width() * self.largest_free_rect.height()) as f32 / free_area as f32
+        }
+    }
+}
+
+impl<P: Copy> Atlas<P, SkylineAllocator> {
+    pub fn max_used_height(&self) -> u32 {
+        self.allocator.max_used_height()
     }
 }
 
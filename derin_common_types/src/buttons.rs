@@ -2,6 +2,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::borrow::Cow;
+
 /// A button on the mouse.
 #[repr(u8)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -66,6 +68,8 @@ pub enum Key {
     Insert,
     Delete,
     Help,
+    /// The context menu key, usually found between the right `Alt` and `Ctrl` keys.
+    Apps,
 
     /// The `0` key above the alphabetic keys.
     Alpha0,
@@ -239,6 +243,58 @@ pub enum Key {
     Menu
 }
 
+/// A key pressed while holding a set of modifier keys, such as `Ctrl+S`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: ModifierKeys,
+    pub key: Key
+}
+
+impl KeyChord {
+    #[inline]
+    pub fn new(modifiers: ModifierKeys, key: Key) -> KeyChord {
+        KeyChord{ modifiers, key }
+    }
+
+    /// Format the chord the way the current platform displays shortcuts: `Ctrl+Shift+S` on Windows
+    /// and Linux, and `⌃⇧S` on macOS.
+    pub fn platform_notation(&self) -> String {
+        let mut notation = String::new();
+        if cfg!(target_os = "macos") {
+            let symbols = [
+                (ModifierKeys::CTRL, "⌃"),
+                (ModifierKeys::LOGO, "⌥"),
+                (ModifierKeys::SHIFT, "⇧"),
+                (ModifierKeys::ALT, "⌘"),
+            ];
+            for &(modifier, symbol) in symbols.iter() {
+                if self.modifiers.contains(modifier) {
+                    notation.push_str(symbol);
+                }
+            }
+        } else {
+            let logo_name = match cfg!(target_os = "windows") {
+                true => "Win",
+                false => "Super"
+            };
+            let names = [
+                (ModifierKeys::CTRL, "Ctrl"),
+                (ModifierKeys::ALT, "Alt"),
+                (ModifierKeys::SHIFT, "Shift"),
+                (ModifierKeys::LOGO, logo_name),
+            ];
+            for &(modifier, name) in names.iter() {
+                if self.modifiers.contains(modifier) {
+                    notation.push_str(name);
+                    notation.push('+');
+                }
+            }
+        }
+        notation.push_str(&self.key.display_name());
+        notation
+    }
+}
+
 impl From<MouseButton> for u8 {
     #[inline]
     fn from(button: MouseButton) -> u8 {
@@ -262,3 +318,68 @@ impl MouseButton {
         }
     }
 }
+
+impl Key {
+    /// Whether the key is a modifier key, which can't be the main key of a `KeyChord`.
+    pub fn is_modifier(self) -> bool {
+        use self::Key::*;
+        match self {
+            LShift | RShift | LCtrl | RCtrl | LAlt | RAlt | Shift | Control | Menu => true,
+            _ => false
+        }
+    }
+
+    /// The name of the key as printed on a keyboard, such as `A`, `5`, or `PgUp`.
+    pub fn display_name(self) -> Cow<'static, str> {
+        use self::Key::*;
+        let name = match self {
+            Back => "Backspace",
+            Escape => "Esc",
+            PageUp => "PgUp",
+            PageDown => "PgDn",
+            Insert => "Ins",
+            Delete => "Del",
+            PrntScr => "PrtSc",
+            Apps => "Menu",
+            Alpha0 | Num0 => "0", Alpha1 | Num1 => "1", Alpha2 | Num2 => "2", Alpha3 | Num3 => "3",
+            Alpha4 | Num4 => "4", Alpha5 | Num5 => "5", Alpha6 | Num6 => "6", Alpha7 | Num7 => "7",
+            Alpha8 | Num8 => "8", Alpha9 | Num9 => "9",
+            Semicolon => ";",
+            Equals => "=",
+            Comma => ",",
+            Minus | NumSub => "-",
+            Period | NumDot => ".",
+            Slash | NumSlash => "/",
+            Accent => "`",
+            LBracket => "[",
+            RBracket => "]",
+            Backslash => "\\",
+            Apostrophe => "'",
+            NumStar => "*",
+            NumPlus => "+",
+            LArrow => "Left",
+            UArrow => "Up",
+            RArrow => "Right",
+            DArrow => "Down",
+            _ => return Cow::Owned(format!("{:?}", self))
+        };
+        Cow::Borrowed(name)
+    }
+
+    /// Retrieves the key that types the given alphanumeric character, ignoring case.
+    ///
+    /// Returns `None` for non-alphanumeric characters.
+    pub fn from_char(c: char) -> Option<Key> {
+        use self::Key::*;
+        let key = match c.to_ascii_uppercase() {
+            '0' => Alpha0, '1' => Alpha1, '2' => Alpha2, '3' => Alpha3, '4' => Alpha4,
+            '5' => Alpha5, '6' => Alpha6, '7' => Alpha7, '8' => Alpha8, '9' => Alpha9,
+            'A' => A, 'B' => B, 'C' => C, 'D' => D, 'E' => E, 'F' => F, 'G' => G,
+            'H' => H, 'I' => I, 'J' => J, 'K' => K, 'L' => L, 'M' => M, 'N' => N,
+            'O' => O, 'P' => P, 'Q' => Q, 'R' => R, 'S' => S, 'T' => T, 'U' => U,
+            'V' => V, 'W' => W, 'X' => X, 'Y' => Y, 'Z' => Z,
+            _ => return None
+        };
+        Some(key)
+    }
+}
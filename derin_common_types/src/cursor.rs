@@ -17,6 +17,7 @@ pub enum CursorIcon {
     SizeNeSw,
     SizeNwSe,
     SizeAll,
+    Help,
     Hide
 }
 
@@ -5,7 +5,7 @@
 use crate::Px;
 use num_traits::Bounded;
 use crate::cgmath::Point2;
-use cgmath_geometry::{D2, rect::{DimsBox, GeoBox}};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
 use std::ops::{Add, Range, RangeFrom, RangeFull, RangeTo};
 
 pub type Tr = u32;
@@ -103,6 +103,44 @@ impl Default for Align {
     }
 }
 
+/// The order tracks are laid out in. Defaults to left-to-right, top-to-bottom.
+///
+/// Reversing an axis also swaps what `Align::Start` and `Align::End` mean along that axis, so
+/// `Start` always refers to the edge that the first track is laid out against.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayoutDirection {
+    /// Lay columns out from right to left, as in Arabic and Hebrew UIs.
+    pub right_to_left: bool,
+    /// Lay rows out from bottom to top.
+    pub bottom_to_top: bool
+}
+
+impl LayoutDirection {
+    #[inline]
+    pub fn new(right_to_left: bool, bottom_to_top: bool) -> LayoutDirection {
+        LayoutDirection{ right_to_left, bottom_to_top }
+    }
+}
+
+/// An axis of a grid. Columns are laid out along the `X` axis, and rows along the `Y` axis.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y
+}
+
+/// The order auto-placed widgets fill a grid's cells in.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutoFlow {
+    /// Fill each row before moving on to the next, adding rows as needed.
+    Row,
+    /// Fill each column before moving on to the next, adding columns as needed.
+    Column
+}
+
 
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -111,7 +149,22 @@ pub struct WidgetPos {
     pub size_bounds: SizeBounds,
     pub widget_span: WidgetSpan,
     pub place_in_cell: Align2,
-    pub margins: Margins<Px>
+    pub margins: Margins<Px>,
+    /// The width/height ratio the widget's rect should keep within its cell, if any.
+    ///
+    /// The rect is shrunk along one axis to match the ratio before it gets aligned in the cell.
+    pub aspect_ratio: Option<f32>,
+    /// Position the widget relative to the parent's rect instead of placing it in the grid.
+    ///
+    /// If `Some`, every other field is ignored, and the widget doesn't affect the size of any of
+    /// the grid's tracks.
+    pub absolute: Option<AbsolutePos>,
+    /// Allow the widget to be collapsed when the engine is too small to fit every widget's
+    /// minimum size, if `Some`.
+    ///
+    /// Widgets get collapsed in order of priority, lowest first, until the remaining widgets fit.
+    /// Widgets with the same priority get collapsed together. `None` widgets are never collapsed.
+    pub shrink_priority: Option<u32>
 }
 
 impl WidgetPos {
@@ -120,7 +173,72 @@ impl WidgetPos {
             size_bounds: size_bounds,
             widget_span: widget_span,
             place_in_cell: place_in_cell,
-            margins: margins
+            margins: margins,
+            aspect_ratio: None,
+            absolute: None,
+            shrink_priority: None
+        }
+    }
+}
+
+/// Positions a widget relative to its parent's rectangle, rather than placing it in the parent's
+/// grid.
+///
+/// Useful for widgets that float over the rest of the layout, like badges or close buttons in a
+/// corner.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AbsolutePos {
+    pub size_bounds: SizeBounds,
+    /// Which edge of the parent the widget is anchored to on each axis. `Stretch` stretches the
+    /// widget between both edges, and `Center` centers it between them.
+    pub anchor: Align2,
+    /// The distance between the widget and the parent's edges. Only the margins on the anchored
+    /// edges are used, except for `Center`, where the widget is shifted by the difference between
+    /// the two margins.
+    ///
+    /// Layout engines clamp the widget's rect to the parent's rect, so negative margins can be used
+    /// to crop the widget at the parent's edges.
+    pub margins: Margins<Px>,
+}
+
+impl AbsolutePos {
+    pub fn new(size_bounds: SizeBounds, anchor: Align2, margins: Margins<Px>) -> AbsolutePos {
+        AbsolutePos{ size_bounds, anchor, margins }
+    }
+
+    /// Calculate the widget's rectangle, relative to the parent's origin. Non-stretched axes use
+    /// the minimum size from `size_bounds`.
+    ///
+    /// The returned rect isn't clamped to the parent, and may extend past its edges. Layout engines
+    /// do the clamping when they place the widget.
+    pub fn rect(&self, parent_dims: DimsBox<D2, Px>) -> BoundBox<D2, Px> {
+        let (min_x, max_x) = place_on_axis(
+            self.anchor.x, parent_dims.width(),
+            self.size_bounds.min.width(), self.size_bounds.max.width(),
+            self.margins.left, self.margins.right
+        );
+        let (min_y, max_y) = place_on_axis(
+            self.anchor.y, parent_dims.height(),
+            self.size_bounds.min.height(), self.size_bounds.max.height(),
+            self.margins.top, self.margins.bottom
+        );
+
+        BoundBox::new2(min_x, min_y, max_x, max_y)
+    }
+}
+
+fn place_on_axis(anchor: Align, parent_size: Px, min_size: Px, max_size: Px, front_margin: Px, back_margin: Px) -> (Px, Px) {
+    match anchor {
+        Align::Start => (front_margin, front_margin + min_size),
+        Align::End => (parent_size - back_margin - min_size, parent_size - back_margin),
+        Align::Center => {
+            let start = (parent_size - min_size) / 2 + front_margin - back_margin;
+            (start, start + min_size)
+        },
+        Align::Stretch => {
+            let size = (parent_size - front_margin - back_margin).min(max_size).max(min_size);
+            (front_margin, front_margin + size)
         }
     }
 }
@@ -136,7 +254,21 @@ pub struct TrackHints {
     /// The proportion of free space this track takes up. This value represents a portion of the total
     /// "fractional space" available in the column or row - the layout engine attempts to set the pixel
     /// value to `total_free_space * fr_size / total_fr_size`.
-    pub fr_size: Fr
+    pub fr_size: Fr,
+    /// Size the track to fit the widgets inside of it, rather than distributing free space to it.
+    /// If `Some`, `fr_size` is ignored.
+    pub content_size: Option<ContentSize>
+}
+
+/// How a track's size is derived from the widgets it contains.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentSize {
+    /// The track is as small as the widgets inside of it allow.
+    MinContent,
+    /// The track is as large as the widgets inside of it allow. Widgets without a maximum size
+    /// contribute their minimum size.
+    MaxContent
 }
 
 impl Default for TrackHints {
@@ -144,7 +276,8 @@ impl Default for TrackHints {
         TrackHints {
             min_size: 0,
             max_size: Px::max_value(),
-            fr_size: 1.0
+            fr_size: 1.0,
+            content_size: None
         }
     }
 }
@@ -188,6 +321,21 @@ impl SizeBounds {
 
         desired_size
     }
+
+    /// The smallest size bounds that can fit both `self` and `other`, taking the larger of the two
+    /// minimum sizes and the larger of the two maximum sizes.
+    pub fn union(self, other: SizeBounds) -> SizeBounds {
+        SizeBounds {
+            min: DimsBox::new2(
+                self.min.width().max(other.min.width()),
+                self.min.height().max(other.min.height())
+            ),
+            max: DimsBox::new2(
+                self.max.width().max(other.max.width()),
+                self.max.height().max(other.max.height())
+            )
+        }
+    }
 }
 
 impl Default for SizeBounds {
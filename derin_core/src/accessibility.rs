@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! User accessibility preferences and screen reader announcements.
+//!
+//! The preferences are set on the `Root` with `set_accessibility_prefs`, generally from the
+//! operating system's accessibility settings. Widgets retrieve them with
+//! `WidgetTag::accessibility_prefs`, and should respect them when drawing and animating.
+
+use crate::widget::WidgetId;
+use std::time::Duration;
+
+/// The accessibility settings the user has requested.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccessibilityPrefs {
+    /// Draw widgets with maximized contrast between foreground and background.
+    pub high_contrast: bool,
+    /// Avoid non-essential motion, such as animations and blinking text cursors.
+    ///
+    /// Animations should either be skipped entirely, or be shortened to their end state.
+    pub reduced_motion: bool,
+    /// The factor by which text should be scaled from its theme-specified size.
+    pub text_scale: f32,
+    /// Don't play interface sounds, generally because the user has turned off system sounds.
+    pub mute_sounds: bool,
+}
+
+/// Transient text to be read out by screen readers, independently of keyboard focus.
+///
+/// Sent with `WidgetTag::announce`, and returned in `EventLoopResult::announcements` for the
+/// windowing backend to forward to the platform's accessibility API as a live region update.
+/// Backends without access to such an API, including `derin`'s glutin backend, drop them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// The text to be read out.
+    pub text: String,
+    /// How urgently the text should be read out.
+    pub politeness: Politeness,
+    /// The widget that made the announcement.
+    pub widget_id: WidgetId,
+}
+
+/// How urgently an `Announcement` should interrupt the screen reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Politeness {
+    /// Read the announcement once the screen reader has finished what it's currently reading.
+    Polite,
+    /// Interrupt the screen reader to read the announcement immediately.
+    ///
+    /// This should only be used for time-sensitive information, such as errors.
+    Assertive,
+}
+
+impl AccessibilityPrefs {
+    /// Scale an animation's duration according to the user's motion preferences.
+    ///
+    /// Returns `None` if the animation should be skipped.
+    #[inline]
+    pub fn animation_duration(&self, duration: Duration) -> Option<Duration> {
+        match self.reduced_motion {
+            false => Some(duration),
+            true => None,
+        }
+    }
+}
+
+impl Default for AccessibilityPrefs {
+    #[inline]
+    fn default() -> AccessibilityPrefs {
+        AccessibilityPrefs {
+            high_contrast: false,
+            reduced_motion: false,
+            text_scale: 1.0,
+            mute_sounds: false,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! User activity detection.
+//!
+//! Once the user hasn't given any input for the duration set with [`Root::set_idle_timeout`],
+//! an `AppEvent::Idle` message gets broadcast to every widget that's registered for it. The next
+//! input broadcasts `AppEvent::Active`. Applications can use this to dim the screen, pause
+//! animations, or lock the session.
+//!
+//! [`Root::set_idle_timeout`]: ../struct.Root.html#method.set_idle_timeout
+
+use std::time::{Duration, Instant};
+
+/// Message broadcast when the user stops or resumes interacting with the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppEvent {
+    /// The user hasn't given any input for the idle timeout.
+    Idle,
+    /// The user gave input after being idle.
+    Active,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ActivityTracker {
+    /// How long the user has to go without giving input to be considered idle. `None` disables
+    /// idle detection.
+    pub timeout: Option<Duration>,
+    last_input: Instant,
+    idle: bool,
+}
+
+impl ActivityTracker {
+    pub fn new() -> ActivityTracker {
+        ActivityTracker {
+            timeout: None,
+            last_input: Instant::now(),
+            idle: false,
+        }
+    }
+
+    pub fn idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Records user input, returning `AppEvent::Active` if the user was idle.
+    pub fn input(&mut self, now: Instant) -> Option<AppEvent> {
+        self.last_input = now;
+        match self.idle {
+            true => {
+                self.idle = false;
+                Some(AppEvent::Active)
+            },
+            false => None
+        }
+    }
+
+    /// Returns `AppEvent::Idle` if the user's gone idle since the last time this was called.
+    pub fn update(&mut self, now: Instant) -> Option<AppEvent> {
+        match (self.idle, self.timeout) {
+            (false, Some(timeout)) if now >= self.last_input + timeout => {
+                self.idle = true;
+                Some(AppEvent::Idle)
+            },
+            _ => None
+        }
+    }
+
+    /// The time the user goes idle if they don't give any more input.
+    pub fn next_idle(&self) -> Option<Instant> {
+        match (self.idle, self.timeout) {
+            (false, Some(timeout)) => Some(self.last_input + timeout),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_and_active() {
+        let mut tracker = ActivityTracker::new();
+        let start = Instant::now();
+        tracker.input(start);
+        assert_eq!(None, tracker.update(start + Duration::from_secs(60)));
+        assert_eq!(None, tracker.next_idle());
+
+        tracker.timeout = Some(Duration::from_secs(10));
+        assert_eq!(Some(start + Duration::from_secs(10)), tracker.next_idle());
+        assert_eq!(None, tracker.update(start + Duration::from_secs(9)));
+        assert_eq!(Some(AppEvent::Idle), tracker.update(start + Duration::from_secs(10)));
+        assert_eq!(None, tracker.update(start + Duration::from_secs(11)));
+        assert_eq!(None, tracker.next_idle());
+
+        assert_eq!(Some(AppEvent::Active), tracker.input(start + Duration::from_secs(12)));
+        assert_eq!(None, tracker.input(start + Duration::from_secs(13)));
+        assert_eq!(Some(start + Duration::from_secs(23)), tracker.next_idle());
+    }
+}
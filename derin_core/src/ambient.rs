@@ -0,0 +1,211 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Values that flow down the widget tree, such as the color scheme and locale.
+//!
+//! An ambient value set on a widget with `WidgetTag::set_ambient` applies to that widget and all
+//! of its descendants, unless a descendant overrides it with its own value. Widgets read the value
+//! that applies at their position with `WidgetTag::ambient`. Any type that implements `PartialEq`
+//! and `Debug` can be used as an ambient value; this module provides the common ones.
+//!
+//! When the value that applies to a widget changes, the widget is queued for relayout and redraw,
+//! so widgets that read ambient values while laying themselves out or rendering pick up the change
+//! without any extra work. Only widgets whose effective value actually changed get invalidated.
+//!
+//! Values propagate down the tree as widgets are visited, so a newly inserted widget sees its
+//! ancestors' values once it has been laid out for the first time.
+
+use fnv::FnvHashMap;
+use std::{
+    any::{Any, TypeId},
+    fmt,
+    rc::Rc,
+    sync::Arc,
+};
+
+/// The light or dark variant of the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// The locale text should be displayed in, as a BCP 47 language tag (e.g. `"en-US"`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Locale(pub Arc<str>);
+
+/// The factor widgets should scale their contents by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleFactor(pub f32);
+
+/// Whether widgets accept user input. Disabling a panel disables everything inside of it.
+///
+/// Usually set with `WidgetTag::set_enabled`. Disabled widgets don't receive input; see
+/// `WidgetTag::set_enabled` for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Enabled(pub bool);
+
+/// How opaque widgets are drawn, from `0.0` (invisible) to `1.0` (fully opaque).
+///
+/// Renderers that support it multiply the alpha of everything a widget draws by this. Nested
+/// values don't multiply together; the innermost value applies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Opacity(pub f32);
+
+/// A value that can be stored as an ambient value. Implemented for every `'static` type that
+/// implements `PartialEq` and `Debug`.
+pub trait AmbientValue: 'static + fmt::Debug {
+    #[doc(hidden)]
+    fn as_any(&self) -> &Any;
+    #[doc(hidden)]
+    fn eq_ambient(&self, other: &AmbientValue) -> bool;
+}
+
+impl<T: 'static + PartialEq + fmt::Debug> AmbientValue for T {
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    fn eq_ambient(&self, other: &AmbientValue) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+type ValueMap = FnvHashMap<TypeId, Rc<AmbientValue>>;
+
+/// A set of ambient values. `None` is the empty set, which saves allocating for the common case of
+/// widgets that don't have any ambient values.
+#[derive(Debug, Clone, Default)]
+struct AmbientMap(Option<Rc<ValueMap>>);
+
+impl AmbientMap {
+    fn get(&self, type_id: TypeId) -> Option<&AmbientValue> {
+        self.0.as_ref().and_then(|map| map.get(&type_id)).map(|value| &**value)
+    }
+
+    /// Whether the maps are the same map, which is much cheaper to check than `values_eq`.
+    fn ptr_eq(&self, other: &AmbientMap) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false
+        }
+    }
+
+    fn values_eq(&self, other: &AmbientMap) -> bool {
+        let empty = ValueMap::default();
+        let (a, b) = (self.0.as_ref().map(|m| &**m).unwrap_or(&empty), other.0.as_ref().map(|m| &**m).unwrap_or(&empty));
+        a.len() == b.len() &&
+        a.iter().all(|(type_id, value)| b.get(type_id).map(|other| value.eq_ambient(&**other)).unwrap_or(false))
+    }
+}
+
+/// The ambient values stored in a `WidgetTag`.
+#[derive(Debug, Default)]
+pub(crate) struct Ambient {
+    /// The values set on this widget.
+    overrides: ValueMap,
+    /// The values that apply to this widget's parent.
+    inherited: AmbientMap,
+    /// The values that apply to this widget: `inherited`, with `overrides` applied on top.
+    effective: AmbientMap,
+}
+
+impl Ambient {
+    pub fn new() -> Ambient {
+        Ambient::default()
+    }
+
+    pub fn get<T: AmbientValue>(&self) -> Option<&T> {
+        self.effective.get(TypeId::of::<T>()).and_then(|value| value.as_any().downcast_ref())
+    }
+
+    /// Retrieves the widget's own value of type `T`, ignoring the values it inherits.
+    pub fn get_override<T: AmbientValue>(&self) -> Option<&T> {
+        self.overrides.get(&TypeId::of::<T>()).and_then(|value| value.as_any().downcast_ref())
+    }
+
+    /// Set or clear the widget's own value of type `T`. Returns whether the value that applies to
+    /// the widget changed.
+    pub fn set<T: AmbientValue>(&mut self, value: Option<T>) -> bool {
+        match value {
+            Some(value) => {self.overrides.insert(TypeId::of::<T>(), Rc::new(value));},
+            None => {self.overrides.remove(&TypeId::of::<T>());}
+        }
+        self.update_effective()
+    }
+
+    /// Take on the values that apply to the widget's parent. Returns whether the values that apply
+    /// to this widget changed.
+    pub fn inherit(&mut self, parent: &Ambient) -> bool {
+        if self.inherited.ptr_eq(&parent.effective) {
+            return false;
+        }
+        self.inherited = parent.effective.clone();
+        self.update_effective()
+    }
+
+    fn update_effective(&mut self) -> bool {
+        let effective = match self.overrides.len() {
+            // Share the parent's map, so that our children's `inherit` can take the fast path.
+            0 => self.inherited.clone(),
+            _ => {
+                let mut values = self.inherited.0.as_ref().map(|m| (**m).clone()).unwrap_or_default();
+                values.extend(self.overrides.iter().map(|(&type_id, value)| (type_id, value.clone())));
+                AmbientMap(Some(Rc::new(values)))
+            }
+        };
+
+        // Keep the old map if nothing changed, so our children's `inherit` can take the fast path.
+        let changed = !effective.values_eq(&self.effective);
+        if changed {
+            self.effective = effective;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inherit_and_override() {
+        let (mut root, mut panel, mut button) = (Ambient::new(), Ambient::new(), Ambient::new());
+
+        assert!(root.set(Some(ColorScheme::Dark)));
+        assert!(root.set(Some(Enabled(true))));
+        assert!(panel.set(Some(Enabled(false))));
+        assert!(panel.inherit(&root));
+        assert!(button.inherit(&panel));
+
+        assert_eq!(Some(&ColorScheme::Dark), button.get::<ColorScheme>());
+        assert_eq!(Some(&Enabled(false)), button.get::<Enabled>());
+        assert_eq!(Some(&Enabled(true)), root.get::<Enabled>());
+        assert_eq!(None, button.get::<ScaleFactor>());
+        assert_eq!(Some(&Enabled(false)), panel.get_override::<Enabled>());
+        assert_eq!(None, button.get_override::<Enabled>());
+
+        // Re-inheriting unchanged values isn't a change.
+        assert!(!panel.inherit(&root));
+        assert!(!button.inherit(&panel));
+
+        // Changing a value the panel overrides doesn't change anything for the button...
+        assert!(root.set(Some(Enabled(false))));
+        assert!(!panel.inherit(&root));
+        assert!(!button.inherit(&panel));
+
+        // ...but changing one it doesn't does.
+        assert!(root.set(Some(ColorScheme::Light)));
+        assert!(panel.inherit(&root));
+        assert!(button.inherit(&panel));
+        assert_eq!(Some(&ColorScheme::Light), button.get::<ColorScheme>());
+
+        // Setting the same value again isn't a change either.
+        assert!(!root.set(Some(ColorScheme::Light)));
+        assert!(!panel.set(None::<Enabled>));
+        assert!(!button.inherit(&panel));
+        assert_eq!(Some(&Enabled(false)), button.get::<Enabled>());
+    }
+}
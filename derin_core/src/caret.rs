@@ -0,0 +1,132 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Text caret blinking.
+//!
+//! A single blink timer is shared by every widget in the tree, so all visible carets blink in
+//! sync. Widgets register themselves with `WidgetTag::set_caret_blinking` while they're displaying
+//! a caret, and check `WidgetTag::caret_visible` when laying out or drawing it.
+
+use crate::widget::WidgetId;
+use fnv::FnvHashSet;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub(crate) struct CaretBlink {
+    /// The time between the caret showing and hiding. `None` if the caret shouldn't blink.
+    pub interval: Option<Duration>,
+    /// When the caret was last made visible. Reset whenever the user types or moves the caret.
+    epoch: Instant,
+    /// The number of times the caret had toggled since `epoch` as of the last frame.
+    phase: u32,
+    /// Widgets displaying a caret, which need to be updated whenever it toggles.
+    pub widgets: FnvHashSet<WidgetId>,
+}
+
+fn duration_nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
+impl CaretBlink {
+    pub fn new() -> CaretBlink {
+        CaretBlink {
+            // Windows' default blink rate. Windowing backends should replace this with the OS
+            // setting if they can retrieve it.
+            interval: Some(Duration::from_millis(530)),
+            epoch: Instant::now(),
+            phase: 0,
+            widgets: FnvHashSet::default(),
+        }
+    }
+
+    /// Makes the caret visible, and restarts the blink timer.
+    pub fn reset(&mut self, now: Instant) {
+        self.epoch = now;
+        self.phase = 0;
+    }
+
+    fn phase_at(&self, now: Instant, reduced_motion: bool) -> u32 {
+        match (self.interval, reduced_motion) {
+            (Some(interval), false) if interval != Duration::new(0, 0) && now > self.epoch =>
+                (duration_nanos(now - self.epoch) / duration_nanos(interval)) as u32,
+            _ => 0
+        }
+    }
+
+    pub fn visible(&self, now: Instant, reduced_motion: bool) -> bool {
+        self.phase_at(now, reduced_motion) % 2 == 0
+    }
+
+    /// Advances the stored phase to `now`, returning whether or not the caret toggled since the
+    /// last time this was called.
+    pub fn update(&mut self, now: Instant, reduced_motion: bool) -> bool {
+        let phase = self.phase_at(now, reduced_motion);
+        let toggled = phase % 2 != self.phase % 2;
+        self.phase = phase;
+        toggled
+    }
+
+    /// The next time the caret toggles, if any widgets are displaying it.
+    pub fn next_toggle(&self, now: Instant, reduced_motion: bool) -> Option<Instant> {
+        match (self.interval, reduced_motion, self.widgets.is_empty()) {
+            (Some(interval), false, false) if interval != Duration::new(0, 0) =>
+                Some(self.epoch + interval * (self.phase_at(now, reduced_motion) + 1)),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blink(interval: Option<Duration>) -> (CaretBlink, Instant) {
+        let mut blink = CaretBlink::new();
+        let epoch = Instant::now();
+        blink.interval = interval;
+        blink.reset(epoch);
+        blink.widgets.insert(WidgetId::new());
+        (blink, epoch)
+    }
+
+    #[test]
+    fn caret_toggles_on_interval() {
+        let interval = Duration::from_millis(500);
+        let (mut blink, epoch) = blink(Some(interval));
+
+        assert!(blink.visible(epoch, false));
+        assert!(blink.visible(epoch + Duration::from_millis(499), false));
+        assert!(!blink.visible(epoch + Duration::from_millis(500), false));
+        assert!(blink.visible(epoch + Duration::from_millis(1000), false));
+        assert_eq!(Some(epoch + interval * 2), blink.next_toggle(epoch + Duration::from_millis(750), false));
+
+        assert!(!blink.update(epoch + Duration::from_millis(250), false));
+        assert!(blink.update(epoch + Duration::from_millis(600), false));
+        assert!(!blink.update(epoch + Duration::from_millis(700), false));
+    }
+
+    #[test]
+    fn caret_reset_shows_caret() {
+        let (mut blink, epoch) = blink(Some(Duration::from_millis(500)));
+        let typed_at = epoch + Duration::from_millis(600);
+
+        assert!(!blink.visible(typed_at, false));
+        blink.reset(typed_at);
+        assert!(blink.visible(typed_at, false));
+        assert!(blink.visible(typed_at + Duration::from_millis(499), false));
+    }
+
+    #[test]
+    fn caret_solid_without_blinking() {
+        let (mut blink, epoch) = blink(None);
+        let later = epoch + Duration::from_millis(750);
+        assert!(blink.visible(later, false));
+        assert_eq!(None, blink.next_toggle(later, false));
+
+        blink.interval = Some(Duration::from_millis(500));
+        assert!(blink.visible(later, true));
+        assert_eq!(None, blink.next_toggle(later, true));
+        assert!(!blink.update(later, true));
+    }
+}
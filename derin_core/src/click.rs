@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Detection of double and triple clicks.
+//!
+//! Every `WidgetEvent::MouseDown` carries a `click_count`, which counts how many times the button
+//! has been pressed in quick succession. A press continues the previous click sequence if it uses
+//! the same button on the same widget, happens within the double-click interval of the previous
+//! press, and doesn't move more than `DOUBLE_CLICK_SLOP` pixels away from it. The interval can be
+//! changed with `Root::set_double_click_interval`.
+
+use crate::{
+    cgmath::Point2,
+    widget::WidgetId,
+};
+use derin_common_types::buttons::MouseButton;
+use std::time::{Duration, Instant};
+
+/// The default longest time between two presses of a double click.
+pub const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// How far, in pixels, the mouse can move between two presses of a double click.
+pub const DOUBLE_CLICK_SLOP: i32 = 4;
+
+/// Tracks the last press, to count the presses in a click sequence.
+#[derive(Debug, Clone)]
+pub(crate) struct ClickTracker {
+    interval: Duration,
+    last_press: Option<Press>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    widget_id: WidgetId,
+    button: MouseButton,
+    pos: Point2<i32>,
+    time: Instant,
+    click_count: u32,
+}
+
+impl ClickTracker {
+    pub fn new() -> ClickTracker {
+        ClickTracker {
+            interval: DOUBLE_CLICK_INTERVAL,
+            last_press: None,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Record a button press, returning the number of clicks in the sequence it belongs to.
+    pub fn press(&mut self, widget_id: WidgetId, button: MouseButton, pos: Point2<i32>, now: Instant) -> u32 {
+        let click_count = match self.last_press {
+            Some(last) if
+                last.widget_id == widget_id &&
+                last.button == button &&
+                now.duration_since(last.time) <= self.interval &&
+                (pos.x - last.pos.x).abs() <= DOUBLE_CLICK_SLOP &&
+                (pos.y - last.pos.y).abs() <= DOUBLE_CLICK_SLOP
+                => last.click_count + 1,
+            _ => 1
+        };
+
+        self.last_press = Some(Press{ widget_id, button, pos, time: now, click_count });
+        click_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_clicks() {
+        let mut tracker = ClickTracker::new();
+        let (a, b) = (WidgetId::new(), WidgetId::new());
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+
+        assert_eq!(1, tracker.press(a, MouseButton::Left, Point2::new(10, 10), ms(0)));
+        assert_eq!(2, tracker.press(a, MouseButton::Left, Point2::new(12, 10), ms(200)));
+        assert_eq!(3, tracker.press(a, MouseButton::Left, Point2::new(12, 13), ms(400)));
+
+        // Waiting too long, moving too far, or switching buttons or widgets starts a new sequence.
+        assert_eq!(1, tracker.press(a, MouseButton::Left, Point2::new(12, 13), ms(1000)));
+        assert_eq!(1, tracker.press(a, MouseButton::Left, Point2::new(30, 13), ms(1100)));
+        assert_eq!(1, tracker.press(a, MouseButton::Right, Point2::new(30, 13), ms(1200)));
+        assert_eq!(1, tracker.press(b, MouseButton::Right, Point2::new(30, 13), ms(1300)));
+
+        tracker.set_interval(Duration::from_millis(1000));
+        assert_eq!(2, tracker.press(b, MouseButton::Right, Point2::new(30, 13), ms(2000)));
+    }
+}
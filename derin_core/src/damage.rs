@@ -0,0 +1,127 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::widget::WidgetId;
+use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
+use fnv::FnvHashMap;
+use std::mem;
+
+/// The region of the window that needs to be redrawn, tracked as the bounding box of every
+/// damaged rect.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Damage {
+    rect: Option<BoundBox<D2, i32>>,
+}
+
+impl Damage {
+    pub fn new() -> Damage {
+        Damage::default()
+    }
+
+    pub fn add(&mut self, rect: BoundBox<D2, i32>) {
+        self.rect = Some(match self.rect {
+            Some(damage) => BoundBox::new2(
+                damage.min().x.min(rect.min().x),
+                damage.min().y.min(rect.min().y),
+                damage.max().x.max(rect.max().x),
+                damage.max().y.max(rect.max().y),
+            ),
+            None => rect
+        });
+    }
+
+    /// The damaged region. `None` if nothing's been damaged.
+    pub fn rect(&self) -> Option<BoundBox<D2, i32>> {
+        self.rect
+    }
+
+    pub fn intersects(&self, rect: BoundBox<D2, i32>) -> bool {
+        self.rect.and_then(|damage| damage.intersect_rect(rect)).is_some()
+    }
+}
+
+/// The rect each widget was drawn in on the last frame, used to damage the regions of widgets
+/// that have moved, appeared, or disappeared since then.
+#[derive(Debug, Default)]
+pub(crate) struct DrawnRects {
+    current: FnvHashMap<WidgetId, Option<BoundBox<D2, i32>>>,
+    previous: FnvHashMap<WidgetId, Option<BoundBox<D2, i32>>>,
+}
+
+impl DrawnRects {
+    pub fn start_frame(&mut self) {
+        mem::swap(&mut self.current, &mut self.previous);
+        self.current.clear();
+    }
+
+    /// Record the widget's visible rect for this frame. If the widget asked to be redrawn, or its
+    /// rect is different from the last frame's, both the old and new rects get damaged.
+    pub fn update(&mut self, widget_id: WidgetId, rect: Option<BoundBox<D2, i32>>, redraw: bool, damage: &mut Damage) {
+        let old_rect = self.previous.remove(&widget_id);
+        self.current.insert(widget_id, rect);
+
+        if redraw || old_rect != Some(rect) {
+            for rect in old_rect.and_then(|r| r).into_iter().chain(rect) {
+                damage.add(rect);
+            }
+        }
+    }
+
+    /// Damage the rects of the widgets that were drawn last frame but haven't been seen this frame.
+    pub fn finish_frame(&mut self, damage: &mut Damage) {
+        for (_, rect) in self.previous.drain() {
+            if let Some(rect) = rect {
+                damage.add(rect);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drawn_rects() {
+        let mut drawn_rects = DrawnRects::default();
+        let (a, b, c) = (WidgetId::new(), WidgetId::new(), WidgetId::new());
+        let rect = |x, y| Some(BoundBox::new2(x, y, x + 10, y + 10));
+
+        // Every widget is new on the first frame.
+        let mut damage = Damage::new();
+        drawn_rects.start_frame();
+        drawn_rects.update(a, rect(0, 0), false, &mut damage);
+        drawn_rects.update(b, rect(20, 0), false, &mut damage);
+        drawn_rects.update(c, None, false, &mut damage);
+        drawn_rects.finish_frame(&mut damage);
+        assert_eq!(Some(BoundBox::new2(0, 0, 30, 10)), damage.rect());
+
+        // Nothing changed.
+        let mut damage = Damage::new();
+        drawn_rects.start_frame();
+        drawn_rects.update(a, rect(0, 0), false, &mut damage);
+        drawn_rects.update(b, rect(20, 0), false, &mut damage);
+        drawn_rects.update(c, None, false, &mut damage);
+        drawn_rects.finish_frame(&mut damage);
+        assert_eq!(None, damage.rect());
+
+        // `a` moved down and `b` got removed, so both of `a`'s rects and `b`'s old rect get damaged.
+        let mut damage = Damage::new();
+        drawn_rects.start_frame();
+        drawn_rects.update(a, rect(0, 20), false, &mut damage);
+        drawn_rects.update(c, None, false, &mut damage);
+        drawn_rects.finish_frame(&mut damage);
+        assert_eq!(Some(BoundBox::new2(0, 0, 30, 30)), damage.rect());
+        assert!(damage.intersects(BoundBox::new2(25, 25, 35, 35)));
+        assert!(!damage.intersects(BoundBox::new2(40, 0, 50, 10)));
+
+        // Widgets that ask to be redrawn get damaged even if they didn't move.
+        let mut damage = Damage::new();
+        drawn_rects.start_frame();
+        drawn_rects.update(a, rect(0, 20), true, &mut damage);
+        drawn_rects.update(c, None, true, &mut damage);
+        drawn_rects.finish_frame(&mut damage);
+        assert_eq!(rect(0, 20), damage.rect());
+    }
+}
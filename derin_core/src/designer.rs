@@ -0,0 +1,201 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Design mode, for building visual UI designers on top of derin.
+//!
+//! While design mode is enabled with [`Root::set_design_mode`], clicks aren't delivered to widgets.
+//! Instead, clicking a widget selects it and broadcasts a [`DesignSelection`] message, and the
+//! renderer draws the selection's outline and resize handles over the rest of the UI. Dragging the
+//! selection or one of its handles broadcasts a [`GeometryEdit`] message once the mouse button is
+//! released. The widget itself isn't moved, so it's up to the designer to apply the edit. `Escape`
+//! clears the selection.
+//!
+//! [`Root::set_design_mode`]: ../struct.Root.html#method.set_design_mode
+//! [`DesignSelection`]: ./struct.DesignSelection.html
+//! [`GeometryEdit`]: ./struct.GeometryEdit.html
+
+use crate::{
+    cgmath::{Point2, Vector2},
+    widget::WidgetId,
+};
+use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
+use derin_common_types::cursor::CursorIcon;
+
+/// The width and height of the resize handles, in pixels.
+pub const HANDLE_SIZE: i32 = 6;
+
+/// The part of the selection being dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DesignHandle {
+    /// The body of the selection, which moves the whole rect.
+    Move,
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+}
+
+/// Message broadcast when the selected widget changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DesignSelection {
+    /// The newly selected widget, or `None` if the selection was cleared.
+    pub widget_id: Option<WidgetId>,
+}
+
+/// Message broadcast when the user finishes dragging the selection or one of its handles.
+///
+/// Rects are given relative to the root widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeometryEdit {
+    pub widget_id: WidgetId,
+    pub handle: DesignHandle,
+    /// The widget's rect before the edit.
+    pub old_rect: BoundBox<D2, i32>,
+    /// The rect the user dragged the widget to.
+    pub new_rect: BoundBox<D2, i32>,
+}
+
+/// What the renderer should draw over the UI while a widget is selected in design mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DesignOverlay {
+    /// The outline of the selection, relative to the root widget. Follows the mouse while the
+    /// selection's being dragged.
+    pub selection_rect: BoundBox<D2, i32>,
+    /// The rects of the resize handles.
+    pub handles: [(DesignHandle, BoundBox<D2, i32>); 8],
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DesignState {
+    pub selection: Option<WidgetId>,
+    pub drag: Option<DesignDrag>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DesignDrag {
+    pub handle: DesignHandle,
+    pub down_pos: Point2<i32>,
+    pub old_rect: BoundBox<D2, i32>,
+    pub new_rect: BoundBox<D2, i32>,
+}
+
+impl DesignHandle {
+    /// Every handle except `Move`, clockwise from the top-left corner.
+    pub const RESIZE_HANDLES: [DesignHandle; 8] = [
+        DesignHandle::TopLeft,
+        DesignHandle::Top,
+        DesignHandle::TopRight,
+        DesignHandle::Right,
+        DesignHandle::BottomRight,
+        DesignHandle::Bottom,
+        DesignHandle::BottomLeft,
+        DesignHandle::Left,
+    ];
+
+    /// The rect of the handle on the given selection. `None` for `Move`.
+    pub fn handle_rect(self, rect: BoundBox<D2, i32>) -> Option<BoundBox<D2, i32>> {
+        use self::DesignHandle::*;
+        let (min, max, center) = (rect.min(), rect.max(), rect.center());
+        let pos = match self {
+            Move => return None,
+            TopLeft => Point2::new(min.x, min.y),
+            Top => Point2::new(center.x, min.y),
+            TopRight => Point2::new(max.x, min.y),
+            Right => Point2::new(max.x, center.y),
+            BottomRight => Point2::new(max.x, max.y),
+            Bottom => Point2::new(center.x, max.y),
+            BottomLeft => Point2::new(min.x, max.y),
+            Left => Point2::new(min.x, center.y),
+        };
+        let half = HANDLE_SIZE / 2;
+        Some(BoundBox::new2(pos.x - half, pos.y - half, pos.x - half + HANDLE_SIZE, pos.y - half + HANDLE_SIZE))
+    }
+
+    /// Find the handle of the selection under `point`. The resize handles take priority over
+    /// `Move`.
+    pub fn hit_test(rect: BoundBox<D2, i32>, point: Point2<i32>) -> Option<DesignHandle> {
+        DesignHandle::RESIZE_HANDLES.iter().cloned()
+            .find(|handle| handle.handle_rect(rect).map(|r| r.contains(point)).unwrap_or(false))
+            .or(match rect.contains(point) {
+                true => Some(DesignHandle::Move),
+                false => None
+            })
+    }
+
+    /// Drag the handle by `delta`, returning the new selection rect. Edges can't be dragged past
+    /// the opposite edge.
+    pub fn apply(self, rect: BoundBox<D2, i32>, delta: Vector2<i32>) -> BoundBox<D2, i32> {
+        use self::DesignHandle::*;
+        if self == Move {
+            return rect + delta;
+        }
+
+        let mut new_rect = rect;
+        match self {
+            TopLeft | Left | BottomLeft => new_rect.min.x = (rect.min.x + delta.x).min(rect.max.x),
+            TopRight | Right | BottomRight => new_rect.max.x = (rect.max.x + delta.x).max(rect.min.x),
+            _ => ()
+        }
+        match self {
+            TopLeft | Top | TopRight => new_rect.min.y = (rect.min.y + delta.y).min(rect.max.y),
+            BottomLeft | Bottom | BottomRight => new_rect.max.y = (rect.max.y + delta.y).max(rect.min.y),
+            _ => ()
+        }
+        new_rect
+    }
+
+    /// The cursor shown while hovering over or dragging the handle.
+    pub fn cursor_icon(self) -> CursorIcon {
+        use self::DesignHandle::*;
+        match self {
+            Move => CursorIcon::SizeAll,
+            Top | Bottom => CursorIcon::SizeNS,
+            Left | Right => CursorIcon::SizeWE,
+            TopLeft | BottomRight => CursorIcon::SizeNwSe,
+            TopRight | BottomLeft => CursorIcon::SizeNeSw,
+        }
+    }
+}
+
+impl DesignOverlay {
+    pub fn new(selection_rect: BoundBox<D2, i32>) -> DesignOverlay {
+        let handle = |handle: DesignHandle| (handle, handle.handle_rect(selection_rect).unwrap());
+        let h = DesignHandle::RESIZE_HANDLES;
+        DesignOverlay {
+            selection_rect,
+            handles: [
+                handle(h[0]), handle(h[1]), handle(h[2]), handle(h[3]),
+                handle(h[4]), handle(h[5]), handle(h[6]), handle(h[7]),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_test() {
+        let rect = BoundBox::new2(10, 10, 50, 30);
+        assert_eq!(Some(DesignHandle::TopLeft), DesignHandle::hit_test(rect, Point2::new(9, 11)));
+        assert_eq!(Some(DesignHandle::Right), DesignHandle::hit_test(rect, Point2::new(51, 20)));
+        assert_eq!(Some(DesignHandle::Move), DesignHandle::hit_test(rect, Point2::new(20, 20)));
+        assert_eq!(None, DesignHandle::hit_test(rect, Point2::new(60, 20)));
+    }
+
+    #[test]
+    fn apply_handle() {
+        let rect = BoundBox::new2(10, 10, 50, 30);
+        assert_eq!(BoundBox::new2(15, 5, 55, 25), DesignHandle::Move.apply(rect, Vector2::new(5, -5)));
+        assert_eq!(BoundBox::new2(5, 5, 50, 30), DesignHandle::TopLeft.apply(rect, Vector2::new(-5, -5)));
+        assert_eq!(BoundBox::new2(10, 10, 50, 40), DesignHandle::Bottom.apply(rect, Vector2::new(100, 10)));
+        // Edges stop at the opposite edge.
+        assert_eq!(BoundBox::new2(10, 10, 10, 30), DesignHandle::Right.apply(rect, Vector2::new(-100, 0)));
+    }
+}
@@ -0,0 +1,199 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Per-widget vertex caching, for renderers that tessellate widgets into vertices.
+//!
+//! A widget's draw commands are everything that affects the vertices it generates: its rect and
+//! clip rect, its theme, and its laid-out content. [`DisplayListCache`] keys each widget's
+//! vertices by a hash of those commands, so widgets whose commands haven't changed since the last
+//! frame don't get re-tessellated. A caret blinking in one text box only re-tessellates that text
+//! box instead of every string in the window.
+//!
+//! [`DisplayListCache`]: ./struct.DisplayListCache.html
+
+use crate::widget::WidgetId;
+use fnv::FnvHashMap;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// The renderer state a `DisplayListCache` draws into.
+pub trait DisplayListTarget {
+    type Vertex: Clone;
+
+    /// The vertices queued up to be drawn. Cached vertices get appended to this.
+    fn vertices(&mut self) -> &mut Vec<Self::Vertex>;
+    /// The number of times the queued vertices have been drawn and cleared.
+    fn flushes(&self) -> u64;
+    /// Incremented whenever cached vertices stop being valid, such as when the texture
+    /// coordinates they point to get moved by re-packing an atlas.
+    fn generation(&self) -> u64;
+}
+
+/// The vertices a widget emitted the last time it was drawn.
+#[derive(Debug, Clone)]
+struct DisplayList<V> {
+    /// Hash of the draw commands that produced `vertices`.
+    key: u64,
+    vertices: Vec<V>,
+}
+
+/// Caches the vertices each widget generates. See the module documentation for details.
+#[derive(Debug, Clone)]
+pub struct DisplayListCache<V> {
+    lists: FnvHashMap<WidgetId, DisplayList<V>>,
+    /// The target generation the cached vertices were generated in.
+    generation: u64,
+}
+
+/// Hash a widget's draw commands into a key for `DisplayListCache::draw_widget`.
+pub fn hash_commands<H: Hash>(commands: &H) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    commands.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<V: Clone> DisplayListCache<V> {
+    pub fn new() -> DisplayListCache<V> {
+        DisplayListCache {
+            lists: FnvHashMap::default(),
+            generation: 0,
+        }
+    }
+
+    /// Append the widget's vertices to the target's vertices. If `key` matches the key the widget
+    /// was drawn with last time, the cached vertices are re-used. Otherwise, `generate` is called
+    /// to emit new vertices into the target, and those get cached.
+    ///
+    /// Returns `true` if the vertices were regenerated.
+    pub fn draw_widget<T>(
+        &mut self,
+        widget_id: WidgetId,
+        key: u64,
+        target: &mut T,
+        generate: impl FnOnce(&mut T)
+    ) -> bool
+        where T: DisplayListTarget<Vertex=V>
+    {
+        if target.generation() != self.generation {
+            self.lists.clear();
+            self.generation = target.generation();
+        }
+
+        if let Some(list) = self.lists.get(&widget_id).filter(|list| list.key == key) {
+            target.vertices().extend_from_slice(&list.vertices);
+            return false;
+        }
+
+        let (start, flushes) = (target.vertices().len(), target.flushes());
+        generate(target);
+
+        // `generate` can flush the vertices mid-widget, in which case there's no complete set of
+        // vertices to cache.
+        match target.flushes() == flushes {
+            true => {
+                let list = self.lists.entry(widget_id).or_insert_with(|| DisplayList{ key, vertices: Vec::new() });
+                list.key = key;
+                list.vertices.clear();
+                list.vertices.extend_from_slice(&target.vertices()[start..]);
+            },
+            false => {self.lists.remove(&widget_id);}
+        }
+        true
+    }
+
+    /// Drop the cached vertices of a widget that's been removed from the tree.
+    pub fn remove(&mut self, widget_id: WidgetId) {
+        self.lists.remove(&widget_id);
+    }
+}
+
+impl<V: Clone> Default for DisplayListCache<V> {
+    fn default() -> DisplayListCache<V> {
+        DisplayListCache::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestTarget {
+        vertices: Vec<u32>,
+        flushes: u64,
+        generation: u64,
+    }
+
+    impl DisplayListTarget for TestTarget {
+        type Vertex = u32;
+
+        fn vertices(&mut self) -> &mut Vec<u32> {
+            &mut self.vertices
+        }
+
+        fn flushes(&self) -> u64 {
+            self.flushes
+        }
+
+        fn generation(&self) -> u64 {
+            self.generation
+        }
+    }
+
+    #[test]
+    fn redraw_hits_cache() {
+        let widget = WidgetId::new();
+        let mut cache = DisplayListCache::new();
+        let mut target = TestTarget::default();
+        let key = hash_commands(&("rect", 10));
+
+        assert!(cache.draw_widget(widget, key, &mut target, |t| t.vertices.extend(&[1, 2, 3])));
+        target.vertices.clear();
+
+        assert!(!cache.draw_widget(widget, key, &mut target, |_| panic!("regenerated cached vertices")));
+        assert_eq!(vec![1, 2, 3], target.vertices);
+    }
+
+    #[test]
+    fn changed_commands_regenerate() {
+        let widget = WidgetId::new();
+        let mut cache = DisplayListCache::new();
+        let mut target = TestTarget::default();
+
+        cache.draw_widget(widget, hash_commands(&1), &mut target, |t| t.vertices.push(1));
+        target.vertices.clear();
+
+        assert!(cache.draw_widget(widget, hash_commands(&2), &mut target, |t| t.vertices.push(2)));
+        assert_eq!(vec![2], target.vertices);
+    }
+
+    #[test]
+    fn invalidated_lists() {
+        let widget = WidgetId::new();
+        let mut cache = DisplayListCache::new();
+        let mut target = TestTarget::default();
+        let key = hash_commands(&());
+
+        // Bumping the generation throws away every list.
+        cache.draw_widget(widget, key, &mut target, |t| t.vertices.push(1));
+        target.generation += 1;
+        assert!(cache.draw_widget(widget, key, &mut target, |t| t.vertices.push(1)));
+
+        // Lists that got flushed partway through don't get cached.
+        cache.draw_widget(widget, hash_commands(&1), &mut target, |t| {
+            t.vertices.push(1);
+            t.vertices.clear();
+            t.flushes += 1;
+            t.vertices.push(2);
+        });
+        assert!(cache.draw_widget(widget, hash_commands(&1), &mut target, |_| ()));
+
+        // And neither do removed widgets.
+        cache.draw_widget(widget, key, &mut target, |t| t.vertices.push(1));
+        cache.remove(widget);
+        assert!(cache.draw_widget(widget, key, &mut target, |_| ()));
+    }
+}
@@ -0,0 +1,128 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Drag-and-drop between widgets.
+//!
+//! When the left mouse button is pressed in a widget and the mouse moves more than
+//! [`DRAG_THRESHOLD`] pixels, the widget recieves a `DragStart` event. A widget that wants to be
+//! dragged calls [`WidgetTag::start_drag`] with the dragged value while handling the event;
+//! otherwise, the press continues as a normal click.
+//!
+//! Widgets declare the payload types they can accept with [`WidgetTag::accept_drops`]. While a
+//! drag is in progress, the widget under the mouse - or, if it can't accept the payload, its
+//! nearest ancestor that can - recieves `DragOver` events. Releasing the button over that widget
+//! sends it a `DragDrop`. Releasing the button anywhere else, or pressing `Escape`, cancels the
+//! drag, and the source and the widget being dragged over recieve `DragCancel`.
+//!
+//! Mouse events are still delivered normally during a drag, so every `MouseDown` still gets a
+//! matching `MouseUp`.
+//!
+//! [`DRAG_THRESHOLD`]: ./constant.DRAG_THRESHOLD.html
+//! [`WidgetTag::start_drag`]: ../widget/struct.WidgetTag.html#method.start_drag
+//! [`WidgetTag::accept_drops`]: ../widget/struct.WidgetTag.html#method.accept_drops
+
+use crate::widget::WidgetId;
+use std::{
+    any::{Any, TypeId},
+    fmt,
+    rc::Rc,
+};
+
+/// How far the mouse has to move, in pixels, after being pressed before a drag starts.
+pub const DRAG_THRESHOLD: i32 = 4;
+
+/// The value being dragged.
+///
+/// Cloning the payload is cheap, since every clone refers to the same value.
+#[derive(Clone)]
+pub struct DragPayload {
+    type_id: TypeId,
+    value: Rc<Any>,
+}
+
+/// The drag-and-drop state tracked by the event translator.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DragState {
+    /// Whether the widget the left mouse button was pressed in has been sent `DragStart` for the
+    /// current press.
+    pub offered: bool,
+    pub active: Option<ActiveDrag>,
+    /// Whether the drag target needs to be recomputed and sent `DragOver`.
+    pub moved: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ActiveDrag {
+    pub source: WidgetId,
+    pub payload: DragPayload,
+    /// The widget being dragged over, which recieves the payload if the drag is dropped.
+    pub target: Option<WidgetId>,
+}
+
+impl DragPayload {
+    pub fn new<T: 'static>(value: T) -> DragPayload {
+        DragPayload {
+            type_id: TypeId::of::<T>(),
+            value: Rc::new(value),
+        }
+    }
+
+    /// The type of the dragged value.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    #[inline]
+    pub fn is<T: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Retrieves the dragged value, if it's of type `T`.
+    #[inline]
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+}
+
+impl PartialEq for DragPayload {
+    /// Payloads are equal if they're clones of the same payload.
+    fn eq(&self, other: &DragPayload) -> bool {
+        Rc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+impl fmt::Debug for DragPayload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DragPayload")
+            .field("type_id", &self.type_id)
+            .finish()
+    }
+}
+
+impl DragState {
+    /// Reset the state after the left mouse button is released.
+    pub fn release(&mut self) -> Option<ActiveDrag> {
+        self.offered = false;
+        self.moved = false;
+        self.active.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_downcast() {
+        let payload = DragPayload::new(String::from("file.txt"));
+        assert!(payload.is::<String>());
+        assert!(!payload.is::<&str>());
+        assert_eq!(Some("file.txt"), payload.downcast_ref::<String>().map(|s| &**s));
+        assert_eq!(None, payload.downcast_ref::<u32>());
+
+        assert_eq!(payload, payload.clone());
+        assert_ne!(payload, DragPayload::new(String::from("file.txt")));
+    }
+}
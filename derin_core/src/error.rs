@@ -0,0 +1,49 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Errors that occur while the `Root` lays out and renders the widget tree.
+//!
+//! These errors don't stop the frame: the widget that caused them gets skipped, and the error gets
+//! stored in the `Root` until it's retrieved with [`Root::take_errors`].
+//!
+//! [`Root::take_errors`]: ../struct.Root.html#method.take_errors
+
+use crate::{
+    widget::{WidgetId, dynamic::RenderError},
+};
+use std::{
+    error::Error,
+    fmt,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreError {
+    /// A widget couldn't be rendered, and was skipped.
+    Render {
+        widget_id: WidgetId,
+        error: RenderError,
+    },
+    /// Relaying out the tree didn't settle after the maximum number of iterations, so the layout
+    /// may be inconsistent. Usually caused by widgets whose size bounds depend on each other in a
+    /// cycle.
+    LayoutDiverged,
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CoreError::Render{widget_id, ref error} => write!(f, "couldn't render widget {}: {}", widget_id.to_u32(), error),
+            CoreError::LayoutDiverged => write!(f, "layout iterations happened an unreasonable number of times"),
+        }
+    }
+}
+
+impl Error for CoreError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            CoreError::Render{ref error, ..} => Some(error),
+            CoreError::LayoutDiverged => None,
+        }
+    }
+}
@@ -3,14 +3,20 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use derin_common_types::buttons::{MouseButton, Key, ModifierKeys};
-use crate::cgmath::{Point2, Vector2};
+use crate::cgmath::{EuclideanSpace, Point2, Vector2};
+use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
 use crate::{
+    drag::DragPayload,
+    gesture::Gesture,
     timer::TimerId,
-    widget::{WidgetIdent},
+    widget::{ScopeButton, WidgetIdent},
 };
 
 use std::time::{Instant, Duration};
 
+/// The default distance, in pixels, scrolled by one line of mouse wheel movement.
+pub const SCROLL_LINE_HEIGHT: i32 = 24;
+
 /// The set of operations to be performed after an event is processed by a widget.
 #[derive(Default)]
 #[must_use]
@@ -22,6 +28,13 @@ pub struct EventOps {
     /// the currently focused widget, in which case no events are delivered.
     pub focus: Option<FocusChange>,
     /// Bubble the event to the parent widget.
+    ///
+    /// The parent recieves the event as `WidgetEventSourced::Bubble`, and can bubble it further up
+    /// the tree in turn. `MouseMove` events are never bubbled, since the parent widget recieves its
+    /// own `MouseMove` events.
+    ///
+    /// An ancestor that handles a bubbled event stops it from propagating further by leaving this
+    /// `false`.
     pub bubble: bool,
 }
 
@@ -44,6 +57,23 @@ pub enum FocusChange {
     Remove,
 }
 
+/// How keyboard focus follows the mouse. Set for the whole window with `Root::set_focus_policy`.
+///
+/// Regardless of the policy, widgets can still take focus whenever they want - including when
+/// they're clicked. Policies other than `ClickToFocus` give focus to the hovered widget or its
+/// nearest ancestor that's been marked with `WidgetTag::set_focusable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPolicy {
+    /// Widgets only gain focus when they take it, typically when they're clicked.
+    ClickToFocus,
+    /// Focus moves to the widget under the mouse once the mouse has rested for `delay`.
+    FollowsHover {
+        delay: Duration,
+    },
+    /// Focus moves to the widget under the mouse when the mouse wheel is scrolled.
+    OnScroll,
+}
+
 /// Information regarding a pressed mouse button.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MouseDown {
@@ -65,7 +95,21 @@ pub struct InputState<'a> {
     /// The modifier keys that have been pressed down.
     pub modifiers: ModifierKeys,
     /// The keys that have been pressed inside of the window.
-    pub keys_down: &'a [Key]
+    pub keys_down: &'a [Key],
+    /// The widget's position in the window.
+    pub coords: WidgetCoords,
+}
+
+/// A widget's position and clipping in the window, used for converting between window and
+/// widget-local coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WidgetCoords {
+    /// The widget's rectangle, in window coordinates.
+    pub window_rect: BoundBox<D2, i32>,
+    /// The rectangle the widget gets clipped to, in window coordinates.
+    ///
+    /// `None` if the widget is entirely clipped away.
+    pub clip: Option<BoundBox<D2, i32>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -82,6 +126,61 @@ pub enum FocusSource {
     },
 }
 
+/// Where a pixel scroll delta falls in a trackpad gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrollPhase {
+    /// The scroll isn't part of a gesture, either because it came from a mouse wheel or because
+    /// the platform doesn't report gesture phases.
+    None,
+    /// The user has put their fingers on the trackpad and started scrolling.
+    Started,
+    /// The user is scrolling with their fingers on the trackpad.
+    Moved,
+    /// The user has lifted their fingers off the trackpad.
+    Ended,
+    /// The platform is continuing the scroll after the user lifted their fingers, to give the
+    /// scroll momentum.
+    Momentum,
+    /// The momentum scroll has come to a stop.
+    MomentumEnded,
+}
+
+impl Default for ScrollPhase {
+    #[inline]
+    fn default() -> ScrollPhase {
+        ScrollPhase::None
+    }
+}
+
+/// The end of the pen in contact with the tablet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PenTool {
+    Tip,
+    Eraser,
+}
+
+/// A single reading of the pen's position and orientation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenSample {
+    /// The position of the pen. Pens report their position with sub-pixel precision.
+    pub pos: Point2<f32>,
+    /// How hard the pen is being pressed against the tablet, from `0.0` to `1.0`. Always `0.0`
+    /// while the pen is hovering.
+    pub pressure: f32,
+    /// How far the pen is tilted away from perpendicular on each axis, in degrees from `-90.0` to
+    /// `90.0`. Positive `x` tilts the pen to the right, and positive `y` tilts it towards the user.
+    pub tilt: Vector2<f32>,
+}
+
+/// The state of a pen, as reported by the platform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenInput {
+    pub sample: PenSample,
+    pub tool: PenTool,
+    /// Whether the button on the side of the pen's barrel is pressed.
+    pub barrel_button: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MouseHoverChange {
     /// The mouse cursor has entered the widget.
@@ -94,11 +193,14 @@ pub enum MouseHoverChange {
     ExitChild(WidgetIdent),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WidgetEventSourced<'a> {
     /// The event was dispatched directly to this widget.
     This(WidgetEvent),
     /// The event was dispatched to the specified child widget, and got bubbled up to this widget.
+    ///
+    /// The path is relative to this widget, going from the child the event was bubbled through to
+    /// the widget the event was originally dispatched to.
     Bubble(WidgetEvent, &'a [WidgetIdent])
 }
 
@@ -112,9 +214,18 @@ pub enum WidgetEventSourced<'a> {
 /// * When the widget has recieved keyboard focus, all user input events are delivered.
 /// * When the given amount of time has passed from a timer registered in `register_timers`, a
 ///  `Timer` event is delivered.
+/// * When the widget has requested idle work and the frame has time to spare, an `IdleWork` event
+///   is delivered.
+///
+/// Pen events are delivered to the widget under the cursor, and to the widget the pen touched down
+/// in until it's lifted.
+///
+/// Drag events are delivered as described in the [`drag`] module.
 ///
 /// All point coordinates are given relative to the widget's origin.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// [`drag`]: ../drag/index.html
+#[derive(Debug, Clone, PartialEq)]
 pub enum WidgetEvent {
     /// The mouse cursor has been moved to a new position.
     MouseMove {
@@ -137,7 +248,11 @@ pub enum WidgetEvent {
         /// If the widget doesn't have keyboard focus, this will always be `true`.
         in_widget: bool,
         /// The button that was pressed.
-        button: MouseButton
+        button: MouseButton,
+        /// How many times the button has been pressed in quick succession, including this press:
+        /// `1` for a single click, `2` for a double click, and so on. See the
+        /// [`click`](../click/index.html) module for details.
+        click_count: u32,
     },
     /// A mouse button has been released.
     ///
@@ -156,12 +271,78 @@ pub enum WidgetEvent {
     },
     MouseScrollLines {
         dir: Vector2<i32>,
+        /// `dir` converted to pixels, using the line height set with `Root::set_scroll_line_height`.
+        dir_px: Vector2<i32>,
         in_widget: bool,
     },
     MouseScrollPx {
         dir: Vector2<i32>,
         in_widget: bool,
+        /// Where the scroll falls in a trackpad gesture.
+        phase: ScrollPhase,
+    },
+    /// The pen has touched the tablet.
+    PenDown {
+        input: PenInput,
+        /// Whether or not the pen touched down inside of the widget.
+        in_widget: bool,
+    },
+    /// The pen has moved, while either touching or hovering over the tablet.
+    PenMove {
+        input: PenInput,
+        /// The samples the pen reported since the last `PenMove`, oldest first, not including
+        /// `input.sample`.
+        ///
+        /// Pens report their state far more often than the UI gets redrawn, so the samples get
+        /// coalesced into a single event. Drawing widgets should draw through every sample in the
+        /// history to produce smooth strokes.
+        history: Vec<PenSample>,
+        /// Whether or not the pen was moved to a position within the widget.
+        in_widget: bool,
+    },
+    /// The pen has been lifted from the tablet.
+    PenUp {
+        input: PenInput,
+        /// Whether or not the pen was lifted inside of the widget.
+        in_widget: bool,
+    },
+    /// The left mouse button was pressed in the widget, and the mouse has moved far enough to start
+    /// dragging.
+    ///
+    /// Call `WidgetTag::start_drag` while handling this event to drag a value out of the widget.
+    DragStart {
+        /// The position of the cursor.
+        pos: Point2<i32>,
+        /// The position of the cursor when the button was pressed.
+        down_pos: Point2<i32>,
+    },
+    /// A value is being dragged over the widget, and the widget accepts values of its type.
+    DragOver {
+        /// The position of the cursor.
+        pos: Point2<i32>,
+        payload: DragPayload,
+        /// `Enter` when the drag first moves over the widget, and `Exit` when it moves off of it.
+        hover_change: Option<MouseHoverChange>,
+    },
+    /// A dragged value has been dropped.
+    ///
+    /// Delivered to the widget the value was dropped on, and to the widget it was dragged from.
+    DragDrop {
+        /// The position of the cursor.
+        pos: Point2<i32>,
+        payload: DragPayload,
+        /// Whether or not the value was dropped on this widget, as opposed to being dragged from it.
+        in_widget: bool,
     },
+    /// The drag was cancelled, either by pressing `Escape` or by releasing the mouse somewhere that
+    /// doesn't accept the dragged value.
+    ///
+    /// Delivered to the widget the value was dragged from, and to the widget it was being dragged
+    /// over.
+    DragCancel,
+    /// The widget recognized a gesture. Only delivered to widgets that opted into the gesture
+    /// with `WidgetTag::recognize_gestures`.
+    Gesture(Gesture),
     /// The widget has gained keyboard focus.
     ///
     /// `FocusSource`: The widget that this gained focus from.
@@ -174,10 +355,40 @@ pub enum WidgetEvent {
     /// This includes the effects of any modifier keys on the character - for example, if the `A` key
     /// is pressed while `Shift` is being held down, this will give the `'A'` character.
     Char(char),
+    /// The input method's in-progress composition changed. Widgets that display the composition
+    /// inline should draw `text` at the caret, without inserting it.
+    ///
+    /// `text` is empty once the composition is committed or cancelled. `cursor` is the byte offset
+    /// of the input method's cursor within `text`, if it should be drawn.
+    ImePreedit {
+        text: String,
+        cursor: Option<usize>,
+    },
+    /// The input method committed the given text, which should be inserted as if it had been
+    /// typed.
+    ImeCommit(String),
     /// The given key has been pressed on the keyboard.
     KeyDown(Key, ModifierKeys),
     /// The given key has been released on the keyboard.
     KeyUp(Key, ModifierKeys),
+    /// The user asked for a context menu, by right-clicking the widget or by pressing the menu key
+    /// or `Shift+F10` while it's focused.
+    ///
+    /// `pos` is where the mouse was released, or `None` if the menu was requested with the keyboard,
+    /// in which case the menu should be placed over the widget. Bubbles, so containers can provide
+    /// menus for their children.
+    ContextMenuRequested {
+        pos: Option<Point2<i32>>,
+    },
+    /// The widget's mnemonic key has been pressed alongside `Alt`.
+    ///
+    /// Widgets should respond to this the same way they'd respond to being clicked.
+    MnemonicActivated,
+    /// The widget is its focus scope's default or cancel button, and `Enter` or `Escape` was
+    /// pressed within the scope without being handled.
+    ///
+    /// Widgets should respond to this the same way they'd respond to being clicked.
+    ScopeButtonActivated(ScopeButton),
     /// Enough time has elapsed for a registered timer to be triggered.
     Timer {
         /// The timer's ID.
@@ -194,6 +405,88 @@ pub enum WidgetEvent {
         /// The number of times this timer has been triggered, not including this trigger.
         times_triggered: u32
     },
+    /// The widget requested idle work with `WidgetTag::request_idle_work`, and there's time left in
+    /// the frame to do some of it.
+    IdleWork {
+        /// The time by which the widget should return control to the event loop.
+        ///
+        /// Widgets should check this regularly while working, and request more idle work if they
+        /// haven't finished by the time it passes.
+        deadline: Instant
+    },
+}
+
+impl WidgetCoords {
+    #[inline]
+    fn offset(&self) -> Vector2<i32> {
+        self.window_rect.min().to_vec()
+    }
+
+    /// Convert a point from window coordinates to widget-local coordinates.
+    #[inline]
+    pub fn window_to_local_point(&self, point: Point2<i32>) -> Point2<i32> {
+        point - self.offset()
+    }
+
+    /// Convert a point from widget-local coordinates to window coordinates.
+    #[inline]
+    pub fn local_to_window_point(&self, point: Point2<i32>) -> Point2<i32> {
+        point + self.offset()
+    }
+
+    /// Convert a rectangle from window coordinates to widget-local coordinates.
+    #[inline]
+    pub fn window_to_local_rect(&self, rect: BoundBox<D2, i32>) -> BoundBox<D2, i32> {
+        rect - self.offset()
+    }
+
+    /// Convert a rectangle from widget-local coordinates to window coordinates.
+    #[inline]
+    pub fn local_to_window_rect(&self, rect: BoundBox<D2, i32>) -> BoundBox<D2, i32> {
+        rect + self.offset()
+    }
+
+    /// The part of the widget that's visible after clipping, in widget-local coordinates.
+    ///
+    /// Returns `None` if none of the widget is visible.
+    pub fn visible_rect(&self) -> Option<BoundBox<D2, i32>> {
+        self.clip
+            .and_then(|clip| clip.intersect_rect(self.window_rect))
+            .map(|rect| self.window_to_local_rect(rect))
+    }
+
+    /// The fraction of the widget's area that's visible after clipping, from `0.0` to `1.0`.
+    pub fn visible_fraction(&self) -> f32 {
+        let area = |rect: BoundBox<D2, i32>| rect.width() as f32 * rect.height() as f32;
+        let widget_area = area(self.window_rect);
+
+        match (self.visible_rect(), widget_area > 0.0) {
+            (Some(visible_rect), true) => area(visible_rect) / widget_area,
+            _ => 0.0
+        }
+    }
+}
+
+impl PenSample {
+    /// Shift the sample's position by the specified vector.
+    #[inline]
+    pub fn translate(self, dir: Vector2<i32>) -> PenSample {
+        PenSample {
+            pos: self.pos + dir.map(|i| i as f32),
+            ..self
+        }
+    }
+}
+
+impl PenInput {
+    /// Shift the sample's position by the specified vector.
+    #[inline]
+    pub fn translate(self, dir: Vector2<i32>) -> PenInput {
+        PenInput {
+            sample: self.sample.translate(dir),
+            ..self
+        }
+    }
 }
 
 impl WidgetEventSourced<'_> {
@@ -226,21 +519,54 @@ impl WidgetEventSourced<'_> {
     }
 }
 
+impl Default for FocusPolicy {
+    #[inline]
+    fn default() -> FocusPolicy {
+        FocusPolicy::ClickToFocus
+    }
+}
+
 impl WidgetEvent {
     pub fn default_bubble(&self) -> bool {
         match *self {
-            WidgetEvent::MouseScrollLines{..} |
-            WidgetEvent::MouseScrollPx{..} |
+            // Widgets that receive every mouse event get copies of scroll events that happened
+            // elsewhere, which their parents have already seen.
+            WidgetEvent::MouseScrollLines{in_widget, ..} |
+            WidgetEvent::MouseScrollPx{in_widget, ..} => in_widget,
+
             WidgetEvent::Char(..) |
+            WidgetEvent::ImePreedit{..} |
+            WidgetEvent::ImeCommit(..) |
             WidgetEvent::KeyDown(..) |
-            WidgetEvent::KeyUp(..) => true,
+            WidgetEvent::KeyUp(..) |
+            WidgetEvent::ContextMenuRequested{..} => true,
 
             WidgetEvent::GainFocus(..) |
             WidgetEvent::LoseFocus |
             WidgetEvent::MouseMove{..} |
             WidgetEvent::MouseDown{..} |
             WidgetEvent::MouseUp{..} |
-            WidgetEvent::Timer{..} => false
+            WidgetEvent::PenDown{..} |
+            WidgetEvent::PenMove{..} |
+            WidgetEvent::PenUp{..} |
+            WidgetEvent::DragStart{..} |
+            WidgetEvent::DragOver{..} |
+            WidgetEvent::DragDrop{..} |
+            WidgetEvent::DragCancel |
+            WidgetEvent::Gesture(..) |
+            WidgetEvent::MnemonicActivated |
+            WidgetEvent::ScopeButtonActivated(..) |
+            WidgetEvent::Timer{..} |
+            WidgetEvent::IdleWork{..} => false
+        }
+    }
+
+    /// The distance scrolled by a scroll event, in pixels. Returns `None` for other events.
+    pub fn scroll_px(&self) -> Option<Vector2<i32>> {
+        match *self {
+            WidgetEvent::MouseScrollLines{dir_px, ..} => Some(dir_px),
+            WidgetEvent::MouseScrollPx{dir, ..} => Some(dir),
+            _ => None
         }
     }
 
@@ -252,10 +578,10 @@ impl WidgetEvent {
                     old_pos: old_pos + dir, new_pos: new_pos + dir,
                     in_widget, hover_change,
                 },
-            WidgetEvent::MouseDown{ pos, in_widget, button } =>
+            WidgetEvent::MouseDown{ pos, in_widget, button, click_count } =>
                 WidgetEvent::MouseDown {
                     pos: pos + dir,
-                    in_widget, button,
+                    in_widget, button, click_count,
                 },
             WidgetEvent::MouseUp{ pos, in_widget, pressed_in_widget, down_pos, button } =>
                 WidgetEvent::MouseUp {
@@ -263,12 +589,55 @@ impl WidgetEvent {
                     down_pos: down_pos + dir,
                     in_widget, pressed_in_widget, button,
                 },
+            WidgetEvent::PenDown{ input, in_widget } =>
+                WidgetEvent::PenDown {
+                    input: input.translate(dir),
+                    in_widget,
+                },
+            WidgetEvent::PenMove{ input, history, in_widget } =>
+                WidgetEvent::PenMove {
+                    input: input.translate(dir),
+                    history: history.into_iter().map(|s| s.translate(dir)).collect(),
+                    in_widget,
+                },
+            WidgetEvent::PenUp{ input, in_widget } =>
+                WidgetEvent::PenUp {
+                    input: input.translate(dir),
+                    in_widget,
+                },
+            WidgetEvent::DragStart{ pos, down_pos } =>
+                WidgetEvent::DragStart {
+                    pos: pos + dir,
+                    down_pos: down_pos + dir,
+                },
+            WidgetEvent::DragOver{ pos, payload, hover_change } =>
+                WidgetEvent::DragOver {
+                    pos: pos + dir,
+                    payload, hover_change,
+                },
+            WidgetEvent::DragDrop{ pos, payload, in_widget } =>
+                WidgetEvent::DragDrop {
+                    pos: pos + dir,
+                    payload, in_widget,
+                },
+            WidgetEvent::Gesture(gesture) =>
+                WidgetEvent::Gesture(gesture.translate(dir)),
+            WidgetEvent::ContextMenuRequested{ pos } =>
+                WidgetEvent::ContextMenuRequested {
+                    pos: pos.map(|pos| pos + dir),
+                },
             WidgetEvent::Char(..)              |
+            WidgetEvent::ImePreedit{..}        |
+            WidgetEvent::ImeCommit(..)         |
+            WidgetEvent::DragCancel            |
             WidgetEvent::LoseFocus             |
             WidgetEvent::GainFocus(..)         |
             WidgetEvent::Timer{..}             |
+            WidgetEvent::IdleWork{..}          |
             WidgetEvent::KeyUp(..)             |
             WidgetEvent::KeyDown(..)           |
+            WidgetEvent::MnemonicActivated     |
+            WidgetEvent::ScopeButtonActivated(..) |
             WidgetEvent::MouseScrollPx{..}     |
             WidgetEvent::MouseScrollLines{..} =>
                 self
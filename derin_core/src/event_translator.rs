@@ -7,15 +7,32 @@ mod dispatcher;
 use crate::{
     WindowEvent, InputState, LoopFlow,
     cgmath::{Vector2},
-    event::{EventOps, FocusChange, FocusSource, MouseHoverChange, WidgetEvent, WidgetEventSourced},
+    designer::{DesignDrag, DesignHandle, DesignSelection, GeometryEdit},
+    drag::{ActiveDrag, DragPayload, DRAG_THRESHOLD},
+    event::{EventOps, FocusChange, FocusPolicy, FocusSource, MouseHoverChange, ScrollPhase, WidgetEvent, WidgetEventSourced},
+    gesture::GestureSet,
+    help::{HelpRequest, HelpSource},
+    message_bus::{Message, MessageTargeted},
+    modal,
     render::Renderer,
+    widget::{ScopeButton, WidgetId, WidgetIdent},
     widget_traverser::{Relation, WidgetTraverser, OffsetWidgetScanPath},
     update_state::{UpdateStateCell},
     offset_widget::OffsetWidget,
 };
 use self::dispatcher::{EventDispatcher, EventDestination, DispatchableEvent};
 use cgmath_geometry::rect::{GeoBox, BoundBox};
-use std::rc::Rc;
+use derin_common_types::{
+    buttons::{Key, KeyChord, ModifierKeys, MouseButton},
+    cursor::CursorIcon,
+};
+use std::{
+    any::Any,
+    mem,
+    rc::Rc,
+    sync::Arc,
+    time::Instant,
+};
 
 pub(crate) struct EventTranslator
 {
@@ -64,8 +81,43 @@ impl<R> TranslatorActive<'_, '_, R>
     where R: Renderer + 'static
 {
     pub fn translate_window_event(&mut self, window_event: WindowEvent) {
+        // Pen movements get buffered until something else happens, so they can be coalesced.
+        match window_event {
+            WindowEvent::PenMove(input) => {
+                self.input_state.pen_moves.push(input);
+                return;
+            },
+            _ => self.flush_pen_moves()
+        }
+
+        self.dispatch_window_event(window_event);
+    }
+
+    /// Dispatch the buffered pen movements as a single `PenMove` event.
+    pub fn flush_pen_moves(&mut self) {
+        let input = match self.input_state.pen_moves.pop() {
+            Some(input) => input,
+            None => return
+        };
+        self.input_state.pen_history = self.input_state.pen_moves.drain(..).map(|i| i.sample).collect();
+        self.dispatch_window_event(WindowEvent::PenMove(input));
+    }
+
+    fn dispatch_window_event(&mut self, window_event: WindowEvent) {
         use self::WindowEvent::*;
 
+        // Focus has to be moved into a newly pushed modal widget before the event gets sent to the
+        // focused widget.
+        let modal_focus_changed = update_modal_focus(
+            self.widget_traverser,
+            &mut self.inner.event_dispatcher,
+            self.input_state,
+            &self.update_state,
+        );
+        if modal_focus_changed {
+            self.dispatch_queued_events();
+        }
+
         let TranslatorActive {
             ref mut widget_traverser,
             ref mut inner,
@@ -115,6 +167,15 @@ impl<R> TranslatorActive<'_, '_, R>
                 let old_pos = input_state.mouse_pos
                     .unwrap_or_else(|| project_to_outside_root(new_pos));
                 input_state.mouse_pos = Some(new_pos);
+                input_state.gestures.mouse_move(new_pos);
+                if let FocusPolicy::FollowsHover{ delay } = input_state.focus_policy {
+                    input_state.hover_focus_deadline = Some(Instant::now() + delay);
+                }
+
+                if let Some(drag) = input_state.design.as_mut().and_then(|d| d.drag.as_mut()) {
+                    drag.new_rect = drag.handle.apply(drag.old_rect, new_pos - drag.down_pos);
+                    update_state.borrow_mut().queue_global_update();
+                }
 
                 let hover_widget_id = input_state.mouse_hover_widget
                     .unwrap_or(widget_traverser.root_id());
@@ -137,6 +198,24 @@ impl<R> TranslatorActive<'_, '_, R>
                         },
                     );
                 }
+
+                // The drag target can only be found once the `MouseMove` has updated the hovered
+                // widget, so that's handled after dispatching.
+                if input_state.drag.active.is_some() {
+                    input_state.drag.moved = true;
+                } else if !input_state.drag.offered {
+                    if let Some(down) = input_state.mouse_buttons_down.contains(MouseButton::Left) {
+                        let down_pos = down.mouse_down.down_pos;
+                        let delta = new_pos - down_pos;
+                        if delta.x.abs() > DRAG_THRESHOLD || delta.y.abs() > DRAG_THRESHOLD {
+                            input_state.drag.offered = true;
+                            event_dispatcher.queue_direct_event(
+                                down.widget_id,
+                                WidgetEvent::DragStart{ pos: new_pos, down_pos },
+                            );
+                        }
+                    }
+                }
             },
             MouseEnter => None,
             // We convert `MouseExit` events to `MouseMove` events so that we don't have to duplicate
@@ -155,27 +234,102 @@ impl<R> TranslatorActive<'_, '_, R>
 
                 None
             }
+            MouseDown(_) if input_state.help_mode => try {
+                // Clicking anywhere leaves help mode, even if nothing is under the cursor.
+                input_state.help_mode = false;
+                update_state.borrow_mut().requests.set_cursor_icon = Some(CursorIcon::default());
+                let hover_widget_id = input_state.mouse_hover_widget?;
+
+                // The button isn't tracked in `mouse_buttons_down`, so the matching `MouseUp` gets
+                // dropped as well.
+                let topic = resolve_help_topic(widget_traverser, hover_widget_id)?;
+                send_help_request(update_state, HelpRequest {
+                    topic,
+                    widget_id: hover_widget_id,
+                    source: HelpSource::Click,
+                });
+            },
+            // In design mode, clicks select widgets and drag the selection around instead of
+            // being delivered.
+            MouseDown(MouseButton::Left) if input_state.design.is_some() => try {
+                let mouse_pos = input_state.mouse_pos?;
+                let hover_widget_id = input_state.mouse_hover_widget;
+                let design = input_state.design.as_mut().unwrap();
+
+                let selected = design.selection
+                    .and_then(|id| widget_traverser.get_widget(id).map(|path| (id, path.widget.rect())));
+                let handle = selected.and_then(|(id, rect)| match DesignHandle::hit_test(rect, mouse_pos) {
+                    // Clicking inside of the selection selects the clicked child, rather than
+                    // moving the selection.
+                    Some(DesignHandle::Move) if hover_widget_id != Some(id) => None,
+                    handle => handle
+                });
+
+                match (selected, handle) {
+                    (Some((_, rect)), Some(handle)) => {
+                        design.drag = Some(DesignDrag {
+                            handle,
+                            down_pos: mouse_pos,
+                            old_rect: rect,
+                            new_rect: rect,
+                        });
+                        update_state.borrow_mut().requests.set_cursor_icon = Some(handle.cursor_icon());
+                    },
+                    _ => if design.selection != hover_widget_id {
+                        design.selection = hover_widget_id;
+                        broadcast_message(update_state, DesignSelection{ widget_id: hover_widget_id });
+                    }
+                }
+                update_state.borrow_mut().queue_global_update();
+            },
+            MouseDown(_) if input_state.design.is_some() => None,
+            MouseUp(_) if input_state.design.is_some() => try {
+                let design = input_state.design.as_mut().unwrap();
+                let drag = design.drag.take()?;
+                let widget_id = design.selection?;
+                update_state.borrow_mut().requests.set_cursor_icon = Some(CursorIcon::default());
+
+                if drag.new_rect != drag.old_rect {
+                    broadcast_message(update_state, GeometryEdit {
+                        widget_id,
+                        handle: drag.handle,
+                        old_rect: drag.old_rect,
+                        new_rect: drag.new_rect,
+                    });
+                }
+                update_state.borrow_mut().queue_global_update();
+            },
             MouseDown(mouse_button) => try {
                 let mouse_pos = input_state.mouse_pos?;
                 let hover_widget_id = input_state.mouse_hover_widget?;
+                let click_count = input_state.clicks.press(hover_widget_id, mouse_button, mouse_pos, Instant::now());
 
                 event_dispatcher.queue_direct_event(
                     hover_widget_id,
                     WidgetEvent::MouseDown {
                         pos: mouse_pos,
                         in_widget: true,
-                        button: mouse_button
+                        button: mouse_button,
+                        click_count,
                     },
                 );
                 input_state.mouse_buttons_down.push_button(mouse_button, mouse_pos, hover_widget_id);
 
+                if mouse_button == MouseButton::Left {
+                    match find_gesture_widget(widget_traverser, hover_widget_id) {
+                        Some((widget_id, gestures)) => input_state.gestures.press(widget_id, gestures, mouse_pos, Instant::now()),
+                        None => input_state.gestures.cancel()
+                    }
+                }
+
                 for widget_id in mouse_event_widget_iter.filter(|id| *id != hover_widget_id) {
                     event_dispatcher.queue_direct_event(
                         widget_id,
                         WidgetEvent::MouseDown {
                             pos: mouse_pos,
                             in_widget: false,
-                            button: mouse_button
+                            button: mouse_button,
+                            click_count,
                         },
                     );
                 }
@@ -186,6 +340,39 @@ impl<R> TranslatorActive<'_, '_, R>
                 let hover_widget_id = input_state.mouse_hover_widget
                     .unwrap_or(widget_traverser.root_id());
 
+                if mouse_button == MouseButton::Left {
+                    if let Some(drag) = input_state.drag.release() {
+                        match drag.target {
+                            Some(target) => {
+                                event_dispatcher.queue_direct_event(
+                                    target,
+                                    WidgetEvent::DragDrop {
+                                        pos: mouse_pos,
+                                        payload: drag.payload.clone(),
+                                        in_widget: true,
+                                    },
+                                );
+                                if drag.source != target {
+                                    event_dispatcher.queue_direct_event(
+                                        drag.source,
+                                        WidgetEvent::DragDrop {
+                                            pos: mouse_pos,
+                                            payload: drag.payload.clone(),
+                                            in_widget: false,
+                                        },
+                                    );
+                                }
+                            },
+                            None => cancel_drag(event_dispatcher, &drag)
+                        }
+                        update_state.borrow_mut().requests.set_cursor_icon = Some(CursorIcon::default());
+                    }
+
+                    if let Some((widget_id, gesture)) = input_state.gestures.release(mouse_pos, Instant::now()) {
+                        event_dispatcher.queue_direct_event(widget_id, WidgetEvent::Gesture(gesture));
+                    }
+                }
+
                 event_dispatcher.queue_direct_event(
                     hover_widget_id,
                     WidgetEvent::MouseUp {
@@ -210,32 +397,62 @@ impl<R> TranslatorActive<'_, '_, R>
                         },
                     );
                 }
+
+                // Right-clicking a widget requests its context menu once the button's released.
+                if mouse_button == MouseButton::Right && mouse_down.widget_id == hover_widget_id {
+                    event_dispatcher.queue_direct_event(
+                        hover_widget_id,
+                        WidgetEvent::ContextMenuRequested{ pos: Some(mouse_pos) },
+                    );
+                }
             },
             MouseScrollLines(dir) => try {
                 let hover_widget_id = input_state.mouse_hover_widget?;
+                if input_state.focus_policy == FocusPolicy::OnScroll {
+                    focus_hovered_widget(widget_traverser, event_dispatcher, input_state);
+                }
+                let dir_px = dir * input_state.scroll_line_height;
                 event_dispatcher.queue_direct_event(
                     hover_widget_id,
-                    WidgetEvent::MouseScrollLines{dir, in_widget: true},
+                    WidgetEvent::MouseScrollLines{dir, dir_px, in_widget: true},
                 );
 
                 for widget_id in mouse_event_widget_iter.filter(|id| *id != hover_widget_id) {
                     event_dispatcher.queue_direct_event(
                         widget_id,
-                        WidgetEvent::MouseScrollLines {dir, in_widget: false},
+                        WidgetEvent::MouseScrollLines {dir, dir_px, in_widget: false},
                     );
                 }
             },
-            MouseScrollPx(dir) => try {
+            MouseScrollPx(_) |
+            MouseScrollPxPrecise{..} => try {
+                let (dir, phase) = match window_event {
+                    MouseScrollPx(dir) => (dir, ScrollPhase::None),
+                    MouseScrollPxPrecise{delta, phase} => {
+                        if phase == ScrollPhase::Started {
+                            input_state.scroll_px_remainder = Vector2::new(0.0, 0.0);
+                        }
+                        let delta = delta + input_state.scroll_px_remainder;
+                        let dir = delta.map(|f| f.trunc() as i32);
+                        input_state.scroll_px_remainder = delta - dir.map(|i| i as f32);
+                        (dir, phase)
+                    },
+                    _ => unreachable!()
+                };
+
                 let hover_widget_id = input_state.mouse_hover_widget?;
+                if input_state.focus_policy == FocusPolicy::OnScroll {
+                    focus_hovered_widget(widget_traverser, event_dispatcher, input_state);
+                }
                 event_dispatcher.queue_direct_event(
                     hover_widget_id,
-                    WidgetEvent::MouseScrollPx{dir, in_widget: true},
+                    WidgetEvent::MouseScrollPx{dir, in_widget: true, phase},
                 );
 
                 for widget_id in mouse_event_widget_iter.filter(|id| *id != hover_widget_id) {
                     event_dispatcher.queue_direct_event(
                         widget_id,
-                        WidgetEvent::MouseScrollPx {dir, in_widget: false},
+                        WidgetEvent::MouseScrollPx {dir, in_widget: false, phase},
                     );
                 }
             },
@@ -243,20 +460,99 @@ impl<R> TranslatorActive<'_, '_, R>
                 widget_traverser.get_widget(root_id).unwrap().widget.set_rect(BoundBox::new2(0, 0, size.dims.x as i32, size.dims.y as i32));
                 update_state.borrow_mut().queue_global_update();
             },
+            KeyDown(Key::Escape) if input_state.drag.active.is_some() => try {
+                let drag = input_state.drag.active.take()?;
+                cancel_drag(event_dispatcher, &drag);
+                update_state.borrow_mut().requests.set_cursor_icon = Some(CursorIcon::default());
+            },
+            KeyDown(Key::Escape) if input_state.help_mode => try {
+                input_state.help_mode = false;
+                update_state.borrow_mut().requests.set_cursor_icon = Some(CursorIcon::default());
+            },
+            KeyDown(Key::Escape) if input_state.design.as_ref().map(|d| d.selection.is_some()).unwrap_or(false) => try {
+                let design = input_state.design.as_mut().unwrap();
+                design.selection = None;
+                design.drag = None;
+                broadcast_message(update_state, DesignSelection{ widget_id: None });
+                update_state.borrow_mut().queue_global_update();
+            },
+            // `F1` stays out of `keys_down`, so the focused widget doesn't get a `KeyUp` for a press
+            // that help mode consumed.
+            KeyDown(Key::F1) if input_state.modifiers.contains(ModifierKeys::SHIFT) => try {
+                if !input_state.help_mode {
+                    input_state.help_mode = true;
+                    update_state.borrow_mut().requests.set_cursor_icon = Some(CursorIcon::Help);
+                }
+            },
             KeyDown(key) => try {
                 if !input_state.keys_down.contains(&key) {
                     input_state.keys_down.push(key);
-                    match input_state.focused_widget {
-                        Some(widget) => event_dispatcher.queue_direct_event(
+
+                    let is_alt = key == Key::LAlt || key == Key::RAlt;
+                    if is_alt {
+                        set_mnemonics_visible(update_state, true);
+                    }
+
+                    // If the focused or hovered widget has a help topic, `F1` gets consumed by the
+                    // help request. Otherwise, it gets delivered like any other key.
+                    let help_request = match key {
+                        Key::F1 => input_state.focused_widget.map(|id| (id, HelpSource::Focus))
+                            .or(input_state.mouse_hover_widget.map(|id| (id, HelpSource::Hover)))
+                            .and_then(|(widget_id, source)|
+                                resolve_help_topic(widget_traverser, widget_id)
+                                    .map(|topic| HelpRequest{ topic, widget_id, source })
+                            ),
+                        _ => None
+                    };
+                    // The menu key and `Shift+F10` request the focused widget's context menu
+                    // instead of being delivered.
+                    let context_menu = key == Key::Apps ||
+                        (key == Key::F10 && input_state.modifiers.contains(ModifierKeys::SHIFT));
+                    // Likewise, `Alt` and a widget's mnemonic gets consumed by that widget.
+                    let mnemonic_widget = match input_state.modifiers.contains(ModifierKeys::ALT) && !is_alt {
+                        true => find_mnemonic_widget(widget_traverser, update_state, key),
+                        false => None
+                    };
+                    // Keyboard shortcuts get checked before the key is delivered to the focused
+                    // widget.
+                    let shortcut_message = match (&help_request, mnemonic_widget, context_menu) {
+                        (None, None, false) if !key.is_modifier() => trigger_shortcut(
+                            widget_traverser,
+                            update_state,
+                            KeyChord::new(input_state.modifiers, key),
+                            input_state.focused_widget
+                        ),
+                        _ => None
+                    };
+
+                    match (help_request, mnemonic_widget, shortcut_message, input_state.focused_widget) {
+                        (Some(help_request), _, _, _) => send_help_request(update_state, help_request),
+                        (None, Some(widget), _, _) => event_dispatcher.queue_direct_event(
+                            widget,
+                            WidgetEvent::MnemonicActivated,
+                        ),
+                        (None, None, Some(message), _) => {
+                            update_state.borrow().message_sender.send(MessageTargeted{ message, target: None }).ok();
+                        },
+                        (None, None, None, Some(widget)) if context_menu => event_dispatcher.queue_direct_event(
+                            widget,
+                            WidgetEvent::ContextMenuRequested{ pos: None },
+                        ),
+                        (None, None, None, Some(widget)) => event_dispatcher.queue_direct_event(
                             widget,
                             WidgetEvent::KeyDown(key, input_state.modifiers),
                         ),
-                        None => println!("dispatch to universal fallthrough")
+                        (None, None, None, None) => println!("dispatch to universal fallthrough")
                     }
                 }
             },
             KeyUp(key) => try {
                 if crate::vec_remove_element(&mut input_state.keys_down, &key).is_some() {
+                    let alt_down = input_state.keys_down.iter().any(|k| *k == Key::LAlt || *k == Key::RAlt);
+                    if !alt_down {
+                        set_mnemonics_visible(update_state, false);
+                    }
+
                     match input_state.focused_widget {
                         Some(widget) => event_dispatcher.queue_direct_event(
                             widget,
@@ -275,267 +571,767 @@ impl<R> TranslatorActive<'_, '_, R>
                     None => println!("dispatch to universal fallthrough")
                 }
             },
-            Timer => None, // The timers will be handled in FrameEventProcessor::finish
+            ImePreedit{ text, cursor } => try {
+                event_dispatcher.queue_direct_event(
+                    input_state.focused_widget?,
+                    WidgetEvent::ImePreedit{ text, cursor },
+                );
+            },
+            ImeCommit(text) => try {
+                event_dispatcher.queue_direct_event(
+                    input_state.focused_widget?,
+                    WidgetEvent::ImeCommit(text),
+                );
+            },
+            PenDown(input) => try {
+                let hover_widget_id = input_state.mouse_hover_widget?;
+                input_state.pen_down_widget = Some(hover_widget_id);
+                event_dispatcher.queue_direct_event(
+                    hover_widget_id,
+                    WidgetEvent::PenDown{input, in_widget: true},
+                );
+            },
+            PenMove(input) => try {
+                let history = mem::replace(&mut input_state.pen_history, Vec::new());
+                let hover_widget_id = input_state.mouse_hover_widget;
+
+                if let Some(widget_id) = input_state.pen_down_widget.filter(|id| Some(*id) != hover_widget_id) {
+                    event_dispatcher.queue_direct_event(
+                        widget_id,
+                        WidgetEvent::PenMove{input, history: history.clone(), in_widget: false},
+                    );
+                }
+                event_dispatcher.queue_direct_event(
+                    hover_widget_id?,
+                    WidgetEvent::PenMove{input, history, in_widget: true},
+                );
+            },
+            PenUp(input) => try {
+                let hover_widget_id = input_state.mouse_hover_widget;
+
+                if let Some(widget_id) = input_state.pen_down_widget.take().filter(|id| Some(*id) != hover_widget_id) {
+                    event_dispatcher.queue_direct_event(
+                        widget_id,
+                        WidgetEvent::PenUp{input, in_widget: false},
+                    );
+                }
+                event_dispatcher.queue_direct_event(
+                    hover_widget_id?,
+                    WidgetEvent::PenUp{input, in_widget: true},
+                );
+            },
+            // Widget timers will be handled in FrameEventProcessor::finish
+            Timer => try {
+                let deadline = input_state.hover_focus_deadline?;
+                if Instant::now() >= deadline {
+                    input_state.hover_focus_deadline = None;
+                    focus_hovered_widget(widget_traverser, event_dispatcher, input_state);
+                }
+            },
             Redraw => try {
                 update_state.borrow_mut().queue_global_update();
             },
         };
 
-        event_dispatcher.dispatch_events(
-            widget_traverser,
-            |event_dispatcher, OffsetWidgetScanPath{mut widget, path, widget_id, index}, event| {
-                let widget_ident = path.last().unwrap();
-
-                // Helper function that takes the `EventOps` generated by `on_widget_event`, updates
-                // the input state, and queues more events as necessary.
-                let mut perform_event_ops = |ops| {
-                    let EventOps {
-                        focus,
-                        bubble,
-                    } = ops;
-                    if let Some(focus) = focus {
-                        let of = widget_id;
-                        let ident = widget_ident.clone();
-                        let destination_source_opt = {
-                            match focus.clone() {
-                                FocusChange::Next => Some((
-                                    EventDestination::Relation(of, Relation::Sibling(1)),
-                                    FocusSource::Sibling{ident, delta: -1}
-                                )),
-                                FocusChange::Prev => Some((
-                                    EventDestination::Relation(of, Relation::Sibling(-1)),
-                                    FocusSource::Sibling{ident, delta: 1}
-                                )),
-                                FocusChange::Parent => Some((
-                                    EventDestination::Relation(of, Relation::Parent),
-                                    FocusSource::Child{ident, index}
-                                )),
-                                FocusChange::ChildIdent(ident) => Some((
-                                    EventDestination::Relation(of, Relation::ChildIdent(ident)),
-                                    FocusSource::Parent
-                                )),
-                                FocusChange::ChildIndex(index) => Some((
-                                    EventDestination::Relation(of, Relation::ChildIndex(index)),
-                                    FocusSource::Parent
-                                )),
-                                FocusChange::Take => Some((
-                                    EventDestination::Widget(widget_id),
-                                    FocusSource::This
-                                )),
-                                FocusChange::Remove => None
-                            }
-                        };
+        self.dispatch_queued_events();
+    }
 
-                        if let Some((destination, source)) = destination_source_opt {
-                            event_dispatcher.queue_event(
-                                destination,
-                                DispatchableEvent::GainFocus{source, change: focus}
-                            );
-                        } else if focus == FocusChange::Remove {
-                            event_dispatcher.queue_direct_event(
-                                widget_id,
-                                WidgetEvent::LoseFocus
-                            );
-                        }
-                    }
-                };
+    fn dispatch_queued_events(&mut self) {
+        let TranslatorActive {
+            ref mut widget_traverser,
+            ref mut inner,
+            input_state,
+            ref update_state,
+        } = self;
+        let TranslatorInner {
+            ref mut event_dispatcher,
+        } = inner;
 
-                match event {
-                    // We handle `MouseMove` events differently than all other events because
-                    // `MouseMove` can trigger other `MouseMove`s if the mouse moves into a child
-                    // or parent widget.
-                    DispatchableEvent::MouseMove{old_pos, new_pos, exiting_from_child} => {
-                        let widget_rect = match widget.rect_clipped() {
-                            Some(rect) => rect,
-                            None => return
-                        };
-                        let (contains_new, contains_old) = (widget_rect.contains(new_pos), widget_rect.contains(old_pos));
-
-                        let mut send_exiting_from_child = |widget: &mut OffsetWidget<'_, R>, in_widget| {
-                            if let Some(child_ident) = exiting_from_child.clone() {
-                                perform_event_ops(widget.on_widget_event(
-                                    WidgetEventSourced::This(WidgetEvent::MouseMove {
-                                        old_pos, new_pos,
-                                        in_widget,
-                                        hover_change: Some(MouseHoverChange::ExitChild(child_ident)),
-                                    }),
-                                    input_state,
-                                ));
-                            }
-                        };
-
-                        match contains_new {
-                            true => {
-                                let mut enter_child_opt = None;
-                                widget.children_mut(|child_summary| {
-                                    if child_summary.widget.rect_clipped().map(|r| r.contains(new_pos)).unwrap_or(false) {
-                                        enter_child_opt = Some((child_summary.widget.widget_id(), child_summary.ident));
-                                        LoopFlow::Break
-                                    } else {
-                                        LoopFlow::Continue
-                                    }
-                                });
+        // Focus scopes whose default or cancel button needs to be activated, because `Enter` or
+        // `Escape` bubbled out of them unhandled. Those buttons can only be found once dispatching
+        // is finished, since finding them needs the widget traverser.
+        let mut scope_activations = Vec::new();
+        loop {
+            let modal_id = update_state.borrow().modals.top();
+            event_dispatcher.modal_path = modal_id
+                .and_then(|modal_id| widget_traverser.get_widget(modal_id))
+                .map(|wpath| wpath.path.to_vec());
+            event_dispatcher.dispatch_events(
+                widget_traverser,
+                |event_dispatcher, OffsetWidgetScanPath{mut widget, path, widget_id, index}, event| {
+                    let widget_ident = path.last().unwrap();
+
+                    // Helper function that takes the `EventOps` generated by `on_widget_event`, updates
+                    // the input state, and queues more events as necessary.
+                    let mut perform_event_ops = |ops| {
+                        // Bubbling only applies to direct events, so it's handled in the `Direct` branch.
+                        let EventOps {
+                            focus,
+                            bubble: _,
+                        } = ops;
+                        if let Some(focus) = focus {
+                            let of = widget_id;
+                            let ident = widget_ident.clone();
+                            let destination_source_opt = {
+                                match focus.clone() {
+                                    FocusChange::Next => Some((
+                                        EventDestination::Relation(of, Relation::Sibling(1)),
+                                        FocusSource::Sibling{ident, delta: -1}
+                                    )),
+                                    FocusChange::Prev => Some((
+                                        EventDestination::Relation(of, Relation::Sibling(-1)),
+                                        FocusSource::Sibling{ident, delta: 1}
+                                    )),
+                                    FocusChange::Parent => Some((
+                                        EventDestination::Relation(of, Relation::Parent),
+                                        FocusSource::Child{ident, index}
+                                    )),
+                                    FocusChange::ChildIdent(ident) => Some((
+                                        EventDestination::Relation(of, Relation::ChildIdent(ident)),
+                                        FocusSource::Parent
+                                    )),
+                                    FocusChange::ChildIndex(index) => Some((
+                                        EventDestination::Relation(of, Relation::ChildIndex(index)),
+                                        FocusSource::Parent
+                                    )),
+                                    FocusChange::Take => Some((
+                                        EventDestination::Widget(widget_id),
+                                        FocusSource::This
+                                    )),
+                                    FocusChange::Remove => None
+                                }
+                            };
 
-                                send_exiting_from_child(&mut widget, contains_new && enter_child_opt.is_none());
+                            if let Some((destination, source)) = destination_source_opt {
+                                event_dispatcher.queue_event(
+                                    destination,
+                                    DispatchableEvent::GainFocus{source, change: focus}
+                                );
+                            } else if focus == FocusChange::Remove {
+                                event_dispatcher.queue_direct_event(
+                                    widget_id,
+                                    WidgetEvent::LoseFocus
+                                );
+                            }
+                        }
+                    };
+
+                    match event {
+                        // We handle `MouseMove` events differently than all other events because
+                        // `MouseMove` can trigger other `MouseMove`s if the mouse moves into a child
+                        // or parent widget.
+                        DispatchableEvent::MouseMove{old_pos, new_pos, exiting_from_child} => {
+                            let widget_rect = match widget.rect_clipped() {
+                                Some(rect) => rect,
+                                None => return
+                            };
+                            // Input-transparent widgets never contain the mouse, so if one becomes
+                            // transparent while hovered the mouse moves out into whatever's underneath.
+                            // Disabled widgets and widgets blocked by a modal widget act the same way.
+                            let modal_path = event_dispatcher.modal_path.clone();
+                            let modal_path = modal_path.as_ref().map(|p| &p[..]);
+                            let enabled = widget.widget_tag().is_enabled();
+                            let transparent = widget.widget_tag().input_transparent() ||
+                                !enabled ||
+                                !modal::can_hover(modal_path, path);
+                            let (contains_new, contains_old) = (
+                                !transparent && widget_rect.contains(new_pos),
+                                !transparent && widget_rect.contains(old_pos)
+                            );
 
-                                if !contains_old {
+                            let mut send_exiting_from_child = |widget: &mut OffsetWidget<'_, R>, in_widget| {
+                                if let Some(child_ident) = exiting_from_child.clone() {
                                     perform_event_ops(widget.on_widget_event(
                                         WidgetEventSourced::This(WidgetEvent::MouseMove {
                                             old_pos, new_pos,
-                                            in_widget: enter_child_opt.is_none(),
-                                            hover_change: Some(MouseHoverChange::Enter)
+                                            in_widget,
+                                            hover_change: Some(MouseHoverChange::ExitChild(child_ident)),
                                         }),
                                         input_state,
                                     ));
                                 }
+                            };
+
+                            match contains_new {
+                                true => {
+                                    // Later children get drawn on top of earlier ones, so the last child
+                                    // under the mouse is the one that gets entered.
+                                    // Children only inherit ambient values when they're visited, so
+                                    // check them against this widget in case it was enabled or
+                                    // disabled since.
+                                    let mut enter_child_opt = None;
+                                    widget.children_mut(|child_summary| {
+                                        let hit = !child_summary.widget.widget_tag().input_transparent() &&
+                                            child_summary.widget.widget_tag().is_enabled_under(enabled) &&
+                                            modal::can_hover_child(modal_path, path, &child_summary.ident) &&
+                                            child_summary.widget.rect_clipped().map(|r| r.contains(new_pos)).unwrap_or(false);
+                                        if hit {
+                                            enter_child_opt = Some((child_summary.widget.widget_id(), child_summary.ident));
+                                        }
+                                        LoopFlow::Continue
+                                    });
+
+                                    send_exiting_from_child(&mut widget, contains_new && enter_child_opt.is_none());
 
-                                match enter_child_opt {
-                                    Some((enter_child_id, enter_child_ident)) => {
+                                    if !contains_old {
                                         perform_event_ops(widget.on_widget_event(
                                             WidgetEventSourced::This(WidgetEvent::MouseMove {
                                                 old_pos, new_pos,
-                                                in_widget: false,
-                                                hover_change: Some(MouseHoverChange::EnterChild(enter_child_ident))
+                                                in_widget: enter_child_opt.is_none(),
+                                                hover_change: Some(MouseHoverChange::Enter)
                                             }),
                                             input_state,
                                         ));
-                                        event_dispatcher.queue_event(
-                                            EventDestination::Widget(enter_child_id),
-                                            DispatchableEvent::MouseMove {
-                                                old_pos, new_pos,
-                                                exiting_from_child: None,
-                                            }
-                                        );
-                                    },
-                                    None => {
-                                        if contains_old && exiting_from_child.is_none() {
+                                    }
+
+                                    match enter_child_opt {
+                                        Some((enter_child_id, enter_child_ident)) => {
                                             perform_event_ops(widget.on_widget_event(
                                                 WidgetEventSourced::This(WidgetEvent::MouseMove {
                                                     old_pos, new_pos,
-                                                    in_widget: enter_child_opt.is_none(),
-                                                    hover_change: None
+                                                    in_widget: false,
+                                                    hover_change: Some(MouseHoverChange::EnterChild(enter_child_ident))
                                                 }),
                                                 input_state,
                                             ));
+                                            event_dispatcher.queue_event(
+                                                EventDestination::Widget(enter_child_id),
+                                                DispatchableEvent::MouseMove {
+                                                    old_pos, new_pos,
+                                                    exiting_from_child: None,
+                                                }
+                                            );
+                                        },
+                                        None => {
+                                            if contains_old && exiting_from_child.is_none() {
+                                                perform_event_ops(widget.on_widget_event(
+                                                    WidgetEventSourced::This(WidgetEvent::MouseMove {
+                                                        old_pos, new_pos,
+                                                        in_widget: enter_child_opt.is_none(),
+                                                        hover_change: None
+                                                    }),
+                                                    input_state,
+                                                ));
+                                            }
+                                            input_state.mouse_hover_widget = Some(widget_id);
                                         }
-                                        input_state.mouse_hover_widget = Some(widget_id);
                                     }
+                                },
+                                false => {
+                                    send_exiting_from_child(&mut widget, contains_new);
+
+                                    perform_event_ops(widget.on_widget_event(
+                                        WidgetEventSourced::This(WidgetEvent::MouseMove {
+                                            old_pos, new_pos,
+                                            in_widget: false,
+                                            hover_change: Some(MouseHoverChange::Exit),
+                                        }),
+                                        input_state,
+                                    ));
+                                    event_dispatcher.queue_event(
+                                        EventDestination::Relation(widget_id, Relation::Parent),
+                                        DispatchableEvent::MouseMove {
+                                            old_pos, new_pos,
+                                            exiting_from_child: Some(path.last().cloned().unwrap()),
+                                        }
+                                    );
                                 }
-                            },
-                            false => {
-                                send_exiting_from_child(&mut widget, contains_new);
-
-                                perform_event_ops(widget.on_widget_event(
-                                    WidgetEventSourced::This(WidgetEvent::MouseMove {
-                                        old_pos, new_pos,
-                                        in_widget: false,
-                                        hover_change: Some(MouseHoverChange::Exit),
-                                    }),
-                                    input_state,
-                                ));
-                                event_dispatcher.queue_event(
-                                    EventDestination::Relation(widget_id, Relation::Parent),
-                                    DispatchableEvent::MouseMove {
-                                        old_pos, new_pos,
-                                        exiting_from_child: Some(path.last().cloned().unwrap()),
-                                    }
+                            }
+                        },
+                        DispatchableEvent::GainFocus{source, change} => if input_state.focused_widget != Some(widget_id) {
+                            if let Some(focused_widget_id) = input_state.focused_widget {
+                                event_dispatcher.queue_direct_event(
+                                    focused_widget_id,
+                                    WidgetEvent::LoseFocus
                                 );
                             }
-                        }
-                    },
-                    DispatchableEvent::GainFocus{source, change} => if input_state.focused_widget != Some(widget_id) {
-                        if let Some(focused_widget_id) = input_state.focused_widget {
                             event_dispatcher.queue_direct_event(
-                                focused_widget_id,
-                                WidgetEvent::LoseFocus
+                                widget_id,
+                                WidgetEvent::GainFocus(source, change)
                             );
-                        }
-                        event_dispatcher.queue_direct_event(
-                            widget_id,
-                            WidgetEvent::GainFocus(source, change)
-                        );
-                    },
-                    DispatchableEvent::Direct{bubble_source, event} => {
-                        if bubble_source.is_some() {
-                            unimplemented!()
-                        } else {
-                            match event {
-                                WidgetEvent::LoseFocus =>
-                                    input_state.focused_widget = None,
-                                WidgetEvent::GainFocus(..) =>
-                                    input_state.focused_widget= Some(widget_id),
+                        },
+                        DispatchableEvent::Intercept{child_path, event} => {
+                            if widget.intercept_child_event(&event, &child_path, input_state) {
+                                event_dispatcher.intercepted = true;
+                            }
+                        },
+                        DispatchableEvent::Direct{bubble_source, event} => {
+                            let ops = match bubble_source {
+                                Some(ref source_path) => widget.on_widget_event(
+                                    WidgetEventSourced::Bubble(event.clone(), &source_path[path.len()..]),
+                                    input_state,
+                                ),
+                                None => {
+                                    match event {
+                                        WidgetEvent::LoseFocus =>
+                                            input_state.focused_widget = None,
+                                        WidgetEvent::GainFocus(..) =>
+                                            input_state.focused_widget= Some(widget_id),
+                                        _ => ()
+                                    }
+                                    widget.on_widget_event(
+                                        WidgetEventSourced::This(event.clone()),
+                                        input_state,
+                                    )
+                                }
+                            };
+                            let bubble = ops.bubble;
+                            perform_event_ops(ops);
+
+                            let scope_button = match event {
+                                WidgetEvent::KeyDown(key, _) => ScopeButton::from_key(key),
+                                _ => None
+                            };
+                            // The root widget acts as a focus scope, and doesn't have a parent to
+                            // bubble to.
+                            let is_root = path.len() == 1;
+                            let is_scope = is_root || widget.widget_tag().focus_scope;
+
+                            let bubble_source = bubble_source.unwrap_or_else(|| path.to_vec());
+                            match (bubble, scope_button) {
+                                (true, Some(scope_button)) if is_scope => scope_activations.push(ScopeActivation {
+                                    scope_id: widget_id,
+                                    scope_button,
+                                    is_root,
+                                    bubble_source,
+                                    event,
+                                }),
+                                (true, _) if !is_root => event_dispatcher.queue_event(
+                                    EventDestination::Relation(widget_id, Relation::Parent),
+                                    DispatchableEvent::Direct {
+                                        bubble_source: Some(bubble_source),
+                                        event,
+                                    }
+                                ),
                                 _ => ()
                             }
                         }
-                        perform_event_ops(widget.on_widget_event(
-                            WidgetEventSourced::This(event),
-                            input_state,
-                        ));
                     }
                 }
+            );
+
+            let drag_updated = update_drag(widget_traverser, event_dispatcher, input_state, update_state);
+            if scope_activations.len() == 0 && !drag_updated {
+                break;
             }
-        );
+            for activation in scope_activations.drain(..) {
+                let ScopeActivation{ scope_id, scope_button, is_root, bubble_source, event } = activation;
+                match find_scope_button(widget_traverser, scope_id, scope_button) {
+                    Some(button_id) => event_dispatcher.queue_direct_event(
+                        button_id,
+                        WidgetEvent::ScopeButtonActivated(scope_button),
+                    ),
+                    // If the scope doesn't have the button, keep bubbling the key through the
+                    // enclosing scopes.
+                    None if !is_root => event_dispatcher.queue_event(
+                        EventDestination::Relation(scope_id, Relation::Parent),
+                        DispatchableEvent::Direct {
+                            bubble_source: Some(bubble_source),
+                            event,
+                        }
+                    ),
+                    None => ()
+                }
+            }
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        message_bus::MessageBus,
-        cgmath::Point2,
-        test_helpers::{TestEvent, TestRenderFrame},
-        update_state::UpdateState,
-        widget::WidgetIdent,
-        widget_traverser::WidgetTraverserBase,
-    };
-    use derin_common_types::buttons::{Key, ModifierKeys, MouseButton};
-
-    macro_rules! create_translator {
-        ($translator:pat, $tree:expr, $root_id:expr) => {
-            let message_bus = MessageBus::new();
-            let mut traverser_base: WidgetTraverserBase<TestRenderFrame> = WidgetTraverserBase::new($root_id);
-            let update_state = UpdateState::new(&message_bus);
-            let mut traverser = traverser_base.with_root_ref($tree, update_state.clone());
-            let mut input_state = InputState::new();
+/// Move focus into the modal widget on top of the modal stack if it was just pushed, or give focus
+/// back to the widget that had it before the top modal widget was removed.
+fn update_modal_focus<R>(
+    widget_traverser: &mut WidgetTraverser<'_, R>,
+    event_dispatcher: &mut EventDispatcher,
+    input_state: &InputState,
+    update_state: &UpdateStateCell,
+) -> bool
+    where R: Renderer
+{
+    let mut focus_changed = false;
+    let restore_focus = update_state.borrow_mut().modals_mut().take_restore_focus();
+    match restore_focus {
+        Some(Some(widget_id)) => {
+            event_dispatcher.queue_event(
+                EventDestination::Widget(widget_id),
+                DispatchableEvent::GainFocus {
+                    source: FocusSource::This,
+                    change: FocusChange::Take,
+                }
+            );
+            focus_changed = true;
+        },
+        Some(None) => if let Some(focused_widget) = input_state.focused_widget {
+            event_dispatcher.queue_direct_event(focused_widget, WidgetEvent::LoseFocus);
+            focus_changed = true;
+        },
+        None => ()
+    }
 
-            let mut translator = EventTranslator::new();
-            let $translator = translator.with_data(
-                &mut traverser,
-                &mut input_state,
-                update_state
+    let entered_modal = update_state.borrow_mut().modals_mut().enter_top();
+    if let Some(modal_id) = entered_modal {
+        let modal_path = match widget_traverser.get_widget(modal_id) {
+            Some(wpath) => wpath.path.to_vec(),
+            None => return focus_changed
+        };
+        let focus_inside = input_state.focused_widget
+            .and_then(|id| widget_traverser.get_widget(id))
+            .map(|wpath| wpath.path.starts_with(&modal_path))
+            .unwrap_or(false);
+
+        if !focus_inside {
+            update_state.borrow_mut().modals_mut().set_restore_focus(input_state.focused_widget);
+            event_dispatcher.queue_event(
+                EventDestination::Widget(modal_id),
+                DispatchableEvent::GainFocus {
+                    source: FocusSource::This,
+                    change: FocusChange::Take,
+                }
             );
+            focus_changed = true;
         }
     }
+    focus_changed
+}
 
-    #[test]
-    fn mouse_move_enter_child() {
-        test_widget_tree!{
-            let event_list = crate::test_helpers::EventList::new();
-            let mut tree = a {
-                rect: (0, 0, 40, 40);
-                b {
-                    rect: (10, 10, 30, 30);
-                    c {
-                        rect: (10, 10, 20, 20)
-                    }
-                }
-            };
+/// An `Enter` or `Escape` key press that bubbled out of a focus scope unhandled.
+struct ScopeActivation {
+    scope_id: WidgetId,
+    scope_button: ScopeButton,
+    is_root: bool,
+    bubble_source: Vec<WidgetIdent>,
+    event: WidgetEvent,
+}
+
+/// Find the help topic for the given widget, falling back to the topic of the nearest ancestor if
+/// the widget doesn't have one.
+fn resolve_help_topic<R>(widget_traverser: &mut WidgetTraverser<'_, R>, mut widget_id: WidgetId) -> Option<Arc<str>>
+    where R: Renderer
+{
+    loop {
+        let help_topic = widget_traverser.get_widget(widget_id)?.widget.widget_tag().help_topic.clone();
+        if help_topic.is_some() {
+            return help_topic;
         }
-        // Rough diagram:
-        // a---------------------+
-        // |                     |
-        // |                     |
-        // |    b----------------+
-        // |    |                |
-        // |    |                |
-        // |    |    c-----------+
-        // |    |    |           |
-        // |    |    |           |
-        // |    |    |           |
-        // |    |    |           |
-        // |    |    |           |
-        // +----+----+-----------+
+        widget_id = widget_traverser.get_widget_relation(widget_id, Relation::Parent)?.widget_id;
+    }
+}
+
+/// Give focus to the hovered widget, or its nearest focusable ancestor.
+fn focus_hovered_widget<R>(
+    widget_traverser: &mut WidgetTraverser<'_, R>,
+    event_dispatcher: &mut EventDispatcher,
+    input_state: &InputState,
+)
+    where R: Renderer
+{
+    let mut widget_id = match input_state.mouse_hover_widget {
+        Some(widget_id) => widget_id,
+        None => return
+    };
+    loop {
+        let focusable = match widget_traverser.get_widget(widget_id) {
+            Some(wpath) => wpath.widget.widget_tag().focusable,
+            None => return
+        };
+        if focusable {
+            break;
+        }
+        widget_id = match widget_traverser.get_widget_relation(widget_id, Relation::Parent) {
+            Some(wpath) => wpath.widget_id,
+            None => return
+        };
+    }
+
+    event_dispatcher.queue_event(
+        EventDestination::Widget(widget_id),
+        DispatchableEvent::GainFocus {
+            source: FocusSource::This,
+            change: FocusChange::Take,
+        },
+    );
+}
+
+/// Find the widget that recognizes gestures for presses in the given widget, which is the widget
+/// itself or its nearest ancestor that recognizes any gestures.
+fn find_gesture_widget<R>(widget_traverser: &mut WidgetTraverser<'_, R>, mut widget_id: WidgetId) -> Option<(WidgetId, GestureSet)>
+    where R: Renderer
+{
+    loop {
+        let gestures = widget_traverser.get_widget(widget_id)?.widget.widget_tag().gestures;
+        if !gestures.is_empty() {
+            return Some((widget_id, gestures));
+        }
+        widget_id = widget_traverser.get_widget_relation(widget_id, Relation::Parent)?.widget_id;
+    }
+}
+
+/// Start a drag if a widget called `WidgetTag::start_drag`, and send `DragOver` events if the drag
+/// has moved. Returns whether or not any events were queued.
+fn update_drag<R>(
+    widget_traverser: &mut WidgetTraverser<'_, R>,
+    event_dispatcher: &mut EventDispatcher,
+    input_state: &mut InputState,
+    update_state: &UpdateStateCell,
+) -> bool
+    where R: Renderer
+{
+    let start_drag = update_state.borrow_mut().requests.start_drag.take();
+    if let Some((source, payload)) = start_drag {
+        // Requests made outside of `DragStart` are ignored.
+        let pressed_widget = input_state.mouse_buttons_down.contains(MouseButton::Left).map(|d| d.widget_id);
+        if input_state.drag.offered && input_state.drag.active.is_none() && pressed_widget == Some(source) {
+            input_state.drag.active = Some(ActiveDrag{ source, payload, target: None });
+            input_state.drag.moved = true;
+            // Dragging a value out of a widget isn't a swipe.
+            input_state.gestures.cancel();
+            update_state.borrow_mut().requests.set_cursor_icon = Some(CursorIcon::NotAllowed);
+        }
+    }
+
+    if !input_state.drag.moved {
+        return false;
+    }
+    input_state.drag.moved = false;
+
+    let (drag, pos) = match (&mut input_state.drag.active, input_state.mouse_pos) {
+        (Some(drag), Some(pos)) => (drag, pos),
+        _ => return false
+    };
+    let target = input_state.mouse_hover_widget
+        .and_then(|id| find_drop_target(widget_traverser, id, &drag.payload));
+
+    let old_target = mem::replace(&mut drag.target, target);
+    if target != old_target {
+        if let Some(old_target) = old_target {
+            event_dispatcher.queue_direct_event(
+                old_target,
+                WidgetEvent::DragOver {
+                    pos,
+                    payload: drag.payload.clone(),
+                    hover_change: Some(MouseHoverChange::Exit),
+                },
+            );
+        }
+        update_state.borrow_mut().requests.set_cursor_icon = Some(match target {
+            Some(_) => CursorIcon::default(),
+            None => CursorIcon::NotAllowed
+        });
+    }
+    if let Some(target) = target {
+        event_dispatcher.queue_direct_event(
+            target,
+            WidgetEvent::DragOver {
+                pos,
+                payload: drag.payload.clone(),
+                hover_change: match Some(target) != old_target {
+                    true => Some(MouseHoverChange::Enter),
+                    false => None
+                },
+            },
+        );
+    }
+
+    target.is_some() || old_target.is_some()
+}
+
+/// Find the widget that should recieve the dragged value when it's over the given widget, which is
+/// the widget itself or its nearest ancestor that accepts the value.
+fn find_drop_target<R>(widget_traverser: &mut WidgetTraverser<'_, R>, mut widget_id: WidgetId, payload: &DragPayload) -> Option<WidgetId>
+    where R: Renderer
+{
+    loop {
+        if widget_traverser.get_widget(widget_id)?.widget.widget_tag().accepts_drop(payload) {
+            return Some(widget_id);
+        }
+        widget_id = widget_traverser.get_widget_relation(widget_id, Relation::Parent)?.widget_id;
+    }
+}
+
+/// Notify the drag's source and target that it's been cancelled.
+fn cancel_drag(event_dispatcher: &mut EventDispatcher, drag: &ActiveDrag) {
+    event_dispatcher.queue_direct_event(drag.source, WidgetEvent::DragCancel);
+    if let Some(target) = drag.target.filter(|t| *t != drag.source) {
+        event_dispatcher.queue_direct_event(target, WidgetEvent::DragCancel);
+    }
+}
+
+/// Find the first widget in the tree with the given mnemonic that can take input. Widgets that are
+/// disabled, input-transparent, or blocked by a modal widget get skipped.
+fn find_mnemonic_widget<R>(widget_traverser: &mut WidgetTraverser<'_, R>, update_state: &UpdateStateCell, key: Key) -> Option<WidgetId>
+    where R: Renderer
+{
+    let modal_id = update_state.borrow().modals.top();
+    let modal_path = modal_id
+        .and_then(|modal_id| widget_traverser.get_widget(modal_id))
+        .map(|wpath| wpath.path.to_vec());
+
+    let mut mnemonic_widget = None;
+    // Whether each widget on the path to the current widget is enabled. Widgets only inherit their
+    // parent's `Enabled` value when they get laid out, so that can't be relied on here.
+    let mut enabled_stack: Vec<bool> = Vec::new();
+    widget_traverser.crawl_widgets(|path| {
+        let widget_tag = path.widget.widget_tag();
+        enabled_stack.truncate(path.path.len() - 1);
+        let enabled = widget_tag.is_enabled_under(enabled_stack.last().cloned().unwrap_or(true));
+        enabled_stack.push(enabled);
+
+        if mnemonic_widget.is_none() &&
+            widget_tag.mnemonic == Some(key) &&
+            enabled &&
+            !widget_tag.input_transparent() &&
+            modal::in_modal(modal_path.as_ref().map(|p| &p[..]), path.path)
+        {
+            mnemonic_widget = Some(path.widget_id);
+        }
+    });
+    mnemonic_widget
+}
+
+/// Find the widget in the given focus scope with the given scope button. Widgets in focus scopes
+/// nested within `scope_id` are ignored.
+fn find_scope_button<R>(widget_traverser: &mut WidgetTraverser<'_, R>, scope_id: WidgetId, scope_button: ScopeButton) -> Option<WidgetId>
+    where R: Renderer
+{
+    let mut candidates = Vec::new();
+    widget_traverser.crawl_widget_children(scope_id, |path| {
+        if path.widget.widget_tag().scope_button == Some(scope_button) {
+            candidates.push(path.widget_id);
+        }
+    });
+
+    candidates.into_iter().find(|&candidate_id| {
+        let mut widget_id = candidate_id;
+        loop {
+            let parent = match widget_traverser.get_widget_relation(widget_id, Relation::Parent) {
+                Some(parent) => parent,
+                None => return false
+            };
+            if parent.widget_id == scope_id {
+                return true;
+            }
+            if parent.widget.widget_tag().focus_scope {
+                return false;
+            }
+            widget_id = parent.widget_id;
+        }
+    })
+}
+
+fn set_mnemonics_visible(update_state: &UpdateStateCell, visible: bool) {
+    let mut update_state = update_state.borrow_mut();
+    if update_state.mnemonics_visible != visible {
+        update_state.mnemonics_visible = visible;
+        // Mnemonic underlines are handled in text layout, so everything needs to get re-laid out.
+        update_state.queue_global_update();
+    }
+}
+
+fn send_help_request(update_state: &UpdateStateCell, request: HelpRequest) {
+    broadcast_message(update_state, request);
+}
+
+/// Create the message for the shortcut bound to `chord`, taking the shortcuts scoped to the focused
+/// widget and its ancestors into account.
+fn trigger_shortcut<R>(
+    widget_traverser: &mut WidgetTraverser<'_, R>,
+    update_state: &UpdateStateCell,
+    chord: KeyChord,
+    focused_widget: Option<WidgetId>,
+) -> Option<Message>
+    where R: Renderer
+{
+    if update_state.borrow().shortcuts.is_empty() {
+        return None;
+    }
+
+    // Shortcuts scoped to widgets outside of the modal widget are disabled.
+    let modal = update_state.borrow().modals.top();
+    let mut focus_path = Vec::new();
+    let mut widget_id = focused_widget;
+    while let Some(id) = widget_id {
+        focus_path.push(id);
+        widget_id = match Some(id) == modal {
+            true => None,
+            false => widget_traverser.get_widget_relation(id, Relation::Parent).map(|path| path.widget_id)
+        };
+    }
+
+    update_state.borrow().shortcuts.trigger(chord, &focus_path)
+}
+
+fn broadcast_message(update_state: &UpdateStateCell, message: impl Any) {
+    update_state.borrow().message_sender.send(MessageTargeted {
+        message: Box::new(message),
+        target: None,
+    }).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        message_bus::MessageBus,
+        cgmath::Point2,
+        event::{PenInput, PenSample, PenTool},
+        help::{HelpRequest, HelpSource},
+        message_bus::MessageTarget,
+        shortcut::ShortcutScope,
+        test_helpers::{TestEvent, TestHarness, TestRenderFrame},
+        update_state::UpdateState,
+        widget::WidgetIdent,
+        widget_traverser::WidgetTraverserBase,
+    };
+    use derin_common_types::buttons::{Key, KeyChord, ModifierKeys, MouseButton};
+    use std::any::TypeId;
+
+    macro_rules! create_translator {
+        ($translator:pat, $tree:expr, $root_id:expr) => {
+            create_translator!($translator, $tree, $root_id, message_bus);
+        };
+        ($translator:pat, $tree:expr, $root_id:expr, $message_bus:ident) => {
+            let $message_bus = MessageBus::new();
+            let mut traverser_base: WidgetTraverserBase<TestRenderFrame> = WidgetTraverserBase::new($root_id);
+            let update_state = UpdateState::new(&$message_bus);
+            let mut traverser = traverser_base.with_root_ref($tree, update_state.clone());
+            let mut input_state = InputState::new();
+
+            let mut translator = EventTranslator::new();
+            let $translator = translator.with_data(
+                &mut traverser,
+                &mut input_state,
+                update_state
+            );
+        }
+    }
+
+    #[test]
+    fn mouse_move_enter_child() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = a {
+                rect: (0, 0, 40, 40);
+                b {
+                    rect: (10, 10, 30, 30);
+                    c {
+                        rect: (10, 10, 20, 20)
+                    }
+                }
+            };
+        }
+        // Rough diagram:
+        // a---------------------+
+        // |                     |
+        // |                     |
+        // |    b----------------+
+        // |    |                |
+        // |    |                |
+        // |    |    c-----------+
+        // |    |    |           |
+        // |    |    |           |
+        // |    |    |           |
+        // |    |    |           |
+        // |    |    |           |
+        // +----+----+-----------+
 
 
         event_list.set_events(vec![
@@ -673,6 +1469,119 @@ mod tests {
         translator.translate_window_event(WindowEvent::MouseExit);
     }
 
+    #[test]
+    fn input_transparent() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = a {
+                rect: (0, 0, 40, 40);
+                overlay { rect: (0, 0, 40, 40) },
+                b { rect: (10, 10, 30, 30) }
+            };
+        }
+        tree.children.as_mut().unwrap()[&WidgetIdent::new_str("overlay")].widget_tag.set_input_transparent(true);
+
+        // `overlay` covers everything, but the mouse should pass straight through it.
+        event_list.set_events(vec![
+            // WindowEvent::MouseMove(Point2::new(1, 5))
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-1, 5),
+                    new_pos: Point2::new(1, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+
+            // WindowEvent::MouseMove(Point2::new(15, 15))
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(1, 5),
+                    new_pos: Point2::new(15, 15),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(WidgetIdent::new_str("b"))),
+                }
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-9, -5),
+                    new_pos: Point2::new(5, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, a);
+
+        translator.translate_window_event(WindowEvent::MouseEnter);
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(1, 5)));
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(15, 15)));
+        assert_eq!(Some(b), translator.input_state.mouse_hover_widget);
+    }
+
+    #[test]
+    fn topmost_child_hit() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = a {
+                rect: (0, 0, 40, 40);
+                b { rect: (5, 5, 30, 30) },
+                c { rect: (10, 10, 40, 40) }
+            };
+        }
+
+        // `b` and `c` overlap, and `c` gets drawn on top so it should be the one the mouse enters.
+        event_list.set_events(vec![
+            // WindowEvent::MouseMove(Point2::new(1, 5))
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-1, 5),
+                    new_pos: Point2::new(1, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+
+            // WindowEvent::MouseMove(Point2::new(15, 15))
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(1, 5),
+                    new_pos: Point2::new(15, 15),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(WidgetIdent::new_str("c"))),
+                }
+            },
+            TestEvent {
+                widget: c,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-9, -5),
+                    new_pos: Point2::new(5, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, a);
+
+        translator.translate_window_event(WindowEvent::MouseEnter);
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(1, 5)));
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(15, 15)));
+        assert_eq!(Some(c), translator.input_state.mouse_hover_widget);
+    }
+
     #[test]
     fn mouse_move_though_multiple() {
         test_widget_tree!{
@@ -914,6 +1823,7 @@ mod tests {
                     pos: Point2::new(5, 5),
                     in_widget: true,
                     button: MouseButton::Left,
+                    click_count: 1,
                 },
             },
 
@@ -968,6 +1878,7 @@ mod tests {
                 source_child: vec![],
                 event: WidgetEvent::MouseScrollLines {
                     dir: Vector2::new(0, 1),
+                    dir_px: Vector2::new(0, 24),
                     in_widget: true,
                 },
             },
@@ -976,6 +1887,7 @@ mod tests {
                 source_child: vec![],
                 event: WidgetEvent::MouseScrollLines {
                     dir: Vector2::new(0, 1),
+                    dir_px: Vector2::new(0, 24),
                     in_widget: false,
                 },
             },
@@ -987,6 +1899,7 @@ mod tests {
                 event: WidgetEvent::MouseScrollPx {
                     dir: Vector2::new(0, 1),
                     in_widget: true,
+                    phase: ScrollPhase::None,
                 },
             },
             TestEvent {
@@ -995,6 +1908,7 @@ mod tests {
                 event: WidgetEvent::MouseScrollPx {
                     dir: Vector2::new(0, 1),
                     in_widget: false,
+                    phase: ScrollPhase::None,
                 },
             },
 
@@ -1006,6 +1920,7 @@ mod tests {
                     pos: Point2::new(26, 5),
                     in_widget: true,
                     button: MouseButton::Middle,
+                    click_count: 1,
                 },
             },
             TestEvent {
@@ -1015,6 +1930,7 @@ mod tests {
                     pos: Point2::new(16, 5),
                     in_widget: false,
                     button: MouseButton::Middle,
+                    click_count: 1,
                 },
             },
 
@@ -1058,6 +1974,7 @@ mod tests {
                     pos: Point2::new(5, 5),
                     in_widget: true,
                     button: MouseButton::Right,
+                    click_count: 1,
                 },
             },
             TestEvent {
@@ -1067,6 +1984,7 @@ mod tests {
                     pos: Point2::new(25, 5),
                     in_widget: false,
                     button: MouseButton::Right,
+                    click_count: 1,
                 },
             },
             TestEvent {
@@ -1076,6 +1994,7 @@ mod tests {
                     pos: Point2::new(35, 5),
                     in_widget: false,
                     button: MouseButton::Right,
+                    click_count: 1,
                 },
             },
 
@@ -1204,6 +2123,13 @@ mod tests {
                     button: MouseButton::Right,
                 },
             },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::ContextMenuRequested {
+                    pos: Some(Point2::new(5, 5)),
+                },
+            },
 
             // WindowEvent::MouseMove(Point2::new(36, 5))
             TestEvent {
@@ -1223,6 +2149,7 @@ mod tests {
                 source_child: vec![],
                 event: WidgetEvent::MouseScrollLines {
                     dir: Vector2::new(0, 1),
+                    dir_px: Vector2::new(0, 24),
                     in_widget: true,
                 },
             },
@@ -1234,6 +2161,7 @@ mod tests {
                 event: WidgetEvent::MouseScrollPx {
                     dir: Vector2::new(0, 1),
                     in_widget: true,
+                    phase: ScrollPhase::None,
                 },
             },
         ]);
@@ -1346,6 +2274,7 @@ mod tests {
                     pos: Point2::new(5, 5),
                     in_widget: true,
                     button: MouseButton::Left,
+                    click_count: 1,
                 },
             },
             TestEvent {
@@ -1464,6 +2393,7 @@ mod tests {
                     pos: Point2::new(5, 5),
                     in_widget: true,
                     button: MouseButton::Left,
+                    click_count: 1,
                 },
             },
             TestEvent {
@@ -1473,6 +2403,7 @@ mod tests {
                     pos: Point2::new(25, 5),
                     in_widget: false,
                     button: MouseButton::Left,
+                    click_count: 1,
                 },
             },
             TestEvent {
@@ -1562,6 +2493,7 @@ mod tests {
                     pos: Point2::new(5, 5),
                     in_widget: true,
                     button: MouseButton::Left,
+                    click_count: 1,
                 },
             },
             TestEvent {
@@ -1571,6 +2503,7 @@ mod tests {
                     pos: Point2::new(25, 5),
                     in_widget: false,
                     button: MouseButton::Left,
+                    click_count: 1,
                 },
             },
 
@@ -1700,4 +2633,1086 @@ mod tests {
         translator.translate_window_event(WindowEvent::KeyDown(Key::Escape));
         translator.translate_window_event(WindowEvent::KeyUp(Key::Escape));
     }
+
+    #[test]
+    fn help_request() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 50, 10);
+                a { rect: (10, 0, 20, 10) },
+                b { rect: (30, 0, 40, 10) }
+            };
+        }
+        // rough diagram:
+        // root----a--------+-------b--------+-------+
+        // |       |        |       |        |       |
+        // |       |        |       |        |       |
+        // | root  |   a    | root  |   b    | root  |
+        // |       |        |       |        |       |
+        // |       |        |       |        |       |
+        // +-------+--------+-------+--------+-------+
+        tree.widget_tag.set_help_topic(Some("root_topic"));
+        tree.children.as_mut().unwrap()[&WidgetIdent::new_str("b")].widget_tag.set_help_topic(Some("b_topic"));
+
+        let a_ident = WidgetIdent::new_str("a");
+
+        event_list.set_events(vec![
+            // WindowEvent::MouseEnter
+            // WindowEvent::MouseMove(Point2::new(0, 5))
+            TestEvent {
+                widget: root,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-1, 5),
+                    new_pos: Point2::new(0, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                },
+            },
+
+            // WindowEvent::MouseMove(Point2::new(15, 5))
+            TestEvent {
+                widget: root,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(0, 5),
+                    new_pos: Point2::new(15, 5),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(a_ident.clone())),
+                },
+            },
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-10, 5),
+                    new_pos: Point2::new(5, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                },
+            },
+
+            // WindowEvent::KeyDown(Key::F1)
+            // WindowEvent::KeyUp(Key::F1)
+            // WindowEvent::KeyDown(Key::F1) + Shift
+
+            // WindowEvent::MouseMove(Point2::new(35, 5))
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(5, 5),
+                    new_pos: Point2::new(25, 5),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::Exit),
+                },
+            },
+            TestEvent {
+                widget: root,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(15, 5),
+                    new_pos: Point2::new(35, 5),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::ExitChild(a_ident.clone())),
+                },
+            },
+            TestEvent {
+                widget: root,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(15, 5),
+                    new_pos: Point2::new(35, 5),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(WidgetIdent::new_str("b"))),
+                },
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-15, 5),
+                    new_pos: Point2::new(5, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                },
+            },
+
+            // WindowEvent::MouseDown(MouseButton::Left)
+            // WindowEvent::MouseUp(MouseButton::Left)
+            //
+            // Neither gets delivered, since the click was consumed by help mode.
+        ]);
+
+        create_translator!(mut translator, &mut tree, root, message_bus);
+        let mut message_bus = message_bus;
+        message_bus.register_widget_message_type(TypeId::of::<HelpRequest>(), root);
+
+        let mut next_help_request = || {
+            let (message, mut targets) = message_bus.next_message()?;
+            assert_eq!(Some(MessageTarget::Widget(root)), targets.next());
+            Some(message.downcast::<HelpRequest>().map(|r| *r).unwrap())
+        };
+
+        translator.translate_window_event(WindowEvent::MouseEnter);
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(0, 5)));
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(15, 5)));
+
+        // `a` doesn't have a help topic, so the root's topic should be used.
+        translator.translate_window_event(WindowEvent::KeyDown(Key::F1));
+        translator.translate_window_event(WindowEvent::KeyUp(Key::F1));
+        assert_eq!(
+            Some(HelpRequest {
+                topic: Arc::from("root_topic"),
+                widget_id: a,
+                source: HelpSource::Hover,
+            }),
+            next_help_request()
+        );
+
+        // Enter help mode, and click on `b`.
+        translator.input_state.modifiers = ModifierKeys::SHIFT;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::F1));
+        translator.input_state.modifiers = ModifierKeys::empty();
+        assert_eq!(None, next_help_request());
+
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(35, 5)));
+        translator.translate_window_event(WindowEvent::MouseDown(MouseButton::Left));
+        translator.translate_window_event(WindowEvent::MouseUp(MouseButton::Left));
+        assert_eq!(
+            Some(HelpRequest {
+                topic: Arc::from("b_topic"),
+                widget_id: b,
+                source: HelpSource::Click,
+            }),
+            next_help_request()
+        );
+    }
+
+    #[test]
+    fn help_mode_key_up() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 20, 20);
+                a { rect: (0, 0, 10, 10) }
+            };
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::KeyDown(Key::F1) + Shift
+            // WindowEvent::KeyUp(Key::F1)
+            //
+            // Help mode consumed the press, so `a` doesn't get the release either.
+
+            // WindowEvent::KeyDown(Key::A)
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::KeyDown(Key::A, ModifierKeys::empty()),
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+        translator.input_state.focused_widget = Some(a);
+
+        translator.input_state.modifiers = ModifierKeys::SHIFT;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::F1));
+        translator.input_state.modifiers = ModifierKeys::empty();
+        translator.translate_window_event(WindowEvent::KeyUp(Key::F1));
+        assert!(translator.input_state.help_mode);
+        assert!(translator.input_state.keys_down.is_empty());
+
+        translator.translate_window_event(WindowEvent::KeyDown(Key::A));
+    }
+
+    #[test]
+    fn help_mode_click_nothing_hovered() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 20, 20);
+                a { rect: (0, 0, 10, 10) }
+            };
+        }
+
+        // WindowEvent::KeyDown(Key::F1) + Shift
+        // WindowEvent::MouseDown(MouseButton::Left)
+        //
+        // The mouse isn't over the window, so the click doesn't request help for anything.
+        event_list.set_events(vec![]);
+
+        create_translator!(mut translator, &mut tree, root, message_bus);
+        let mut message_bus = message_bus;
+        message_bus.register_widget_message_type(TypeId::of::<HelpRequest>(), root);
+
+        translator.input_state.modifiers = ModifierKeys::SHIFT;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::F1));
+        translator.input_state.modifiers = ModifierKeys::empty();
+        assert!(translator.input_state.help_mode);
+
+        translator.translate_window_event(WindowEvent::MouseDown(MouseButton::Left));
+        assert!(!translator.input_state.help_mode);
+        assert_eq!(
+            Some(CursorIcon::default()),
+            translator.update_state.borrow().requests.set_cursor_icon
+        );
+        assert!(message_bus.next_message().is_none());
+    }
+
+    #[test]
+    fn mnemonic() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 50, 10);
+                a { rect: (10, 0, 20, 10) },
+                b { rect: (30, 0, 40, 10) }
+            };
+        }
+        tree.children.as_mut().unwrap()[&WidgetIdent::new_str("b")].widget_tag.set_mnemonic(Some(Key::B));
+
+        event_list.set_events(vec![
+            // WindowEvent::KeyDown(Key::B) + Alt
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MnemonicActivated,
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.modifiers = ModifierKeys::ALT;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::LAlt));
+        assert!(translator.update_state.borrow().mnemonics_visible);
+
+        // `a` doesn't have a mnemonic, so nothing should happen.
+        translator.translate_window_event(WindowEvent::KeyDown(Key::A));
+        translator.translate_window_event(WindowEvent::KeyUp(Key::A));
+        translator.translate_window_event(WindowEvent::KeyDown(Key::B));
+        translator.translate_window_event(WindowEvent::KeyUp(Key::B));
+        assert!(translator.update_state.borrow().mnemonics_visible);
+
+        translator.input_state.modifiers = ModifierKeys::empty();
+        translator.translate_window_event(WindowEvent::KeyUp(Key::LAlt));
+        assert!(!translator.update_state.borrow().mnemonics_visible);
+    }
+
+    #[test]
+    fn mnemonic_skips_ineligible_widgets() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 70, 10);
+                a { rect: (10, 0, 20, 10) },
+                b { rect: (30, 0, 40, 10) },
+                c { rect: (50, 0, 60, 10) }
+            };
+        }
+        {
+            let children = tree.children.as_mut().unwrap();
+            for ident in &["a", "b", "c"] {
+                children[&WidgetIdent::new_str(ident)].widget_tag.set_mnemonic(Some(Key::B));
+            }
+            children[&WidgetIdent::new_str("a")].widget_tag.set_enabled(false);
+            children[&WidgetIdent::new_str("b")].widget_tag.set_input_transparent(true);
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::KeyDown(Key::B) + Alt
+            //
+            // `a` is disabled and `b` is input-transparent, so the mnemonic goes to `c`.
+            TestEvent {
+                widget: c,
+                source_child: vec![],
+                event: WidgetEvent::MnemonicActivated,
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.modifiers = ModifierKeys::ALT;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::B));
+    }
+
+    #[test]
+    fn mnemonic_modal() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 50, 10);
+                a { rect: (10, 0, 20, 10) },
+                dialog {
+                    rect: (30, 0, 50, 10);
+                    b { rect: (0, 0, 10, 10) }
+                }
+            };
+        }
+        {
+            let children = tree.children.as_mut().unwrap();
+            children[&WidgetIdent::new_str("a")].widget_tag.set_mnemonic(Some(Key::B));
+            children[&WidgetIdent::new_str("dialog")].children.as_mut().unwrap()[&WidgetIdent::new_str("b")]
+                .widget_tag.set_mnemonic(Some(Key::B));
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::KeyDown(Key::B) + Alt
+            //
+            // `a` comes first, but it's outside of the modal widget.
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MnemonicActivated,
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+        // Focus is already inside of the modal widget, so pushing it doesn't move focus.
+        translator.input_state.focused_widget = Some(b);
+        translator.update_state.borrow_mut().modals_mut().push(dialog);
+
+        translator.input_state.modifiers = ModifierKeys::ALT;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::B));
+    }
+
+    #[test]
+    fn context_menu_key() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 20, 10);
+                a { rect: (0, 0, 10, 10) }
+            };
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::KeyDown(Key::Apps)
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::ContextMenuRequested{ pos: None },
+            },
+            // WindowEvent::KeyUp(Key::Apps)
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::KeyUp(Key::Apps, ModifierKeys::empty()),
+            },
+            // WindowEvent::KeyDown(Key::F10) + Shift
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::ContextMenuRequested{ pos: None },
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+        translator.input_state.focused_widget = Some(a);
+
+        translator.translate_window_event(WindowEvent::KeyDown(Key::Apps));
+        translator.translate_window_event(WindowEvent::KeyUp(Key::Apps));
+        translator.input_state.modifiers = ModifierKeys::SHIFT;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::F10));
+    }
+
+    #[test]
+    fn shortcut_keys() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 20, 10);
+                a { rect: (0, 0, 10, 10) }
+            };
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::KeyUp(Key::S) + Ctrl
+            //
+            // The key press was consumed by the shortcut, but the release still gets delivered.
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::KeyUp(Key::S, ModifierKeys::CTRL),
+            },
+            // WindowEvent::KeyDown(Key::O) + Ctrl
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::KeyDown(Key::O, ModifierKeys::CTRL),
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root, message_bus);
+        let mut message_bus = message_bus;
+        message_bus.register_widget_message_type(TypeId::of::<&'static str>(), root);
+        let mut next_message = || {
+            let (message, _) = message_bus.next_message()?;
+            Some(*message.downcast::<&'static str>().unwrap())
+        };
+
+        let ctrl_s = KeyChord::new(ModifierKeys::CTRL, Key::S);
+        {
+            let mut update_state = translator.update_state.borrow_mut();
+            update_state.shortcuts_mut().add(ctrl_s, ShortcutScope::Global, Box::new(|| Box::new("global"))).unwrap();
+            update_state.shortcuts_mut().add(ctrl_s, ShortcutScope::Subtree(a), Box::new(|| Box::new("a"))).unwrap();
+        }
+        translator.input_state.modifiers = ModifierKeys::CTRL;
+
+        // The shortcut scoped to the focused widget takes priority over the global one.
+        translator.input_state.focused_widget = Some(a);
+        translator.translate_window_event(WindowEvent::KeyDown(Key::S));
+        translator.translate_window_event(WindowEvent::KeyUp(Key::S));
+        assert_eq!(Some("a"), next_message());
+
+        translator.input_state.focused_widget = None;
+        translator.translate_window_event(WindowEvent::KeyDown(Key::S));
+        translator.translate_window_event(WindowEvent::KeyUp(Key::S));
+        assert_eq!(Some("global"), next_message());
+
+        // Unbound chords get delivered as usual.
+        translator.input_state.focused_widget = Some(a);
+        translator.translate_window_event(WindowEvent::KeyDown(Key::O));
+        assert_eq!(None, next_message());
+    }
+
+    #[test]
+    fn precise_scroll() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 20, 10);
+                a { rect: (0, 0, 10, 10) }
+            };
+        }
+
+        let scroll_px = |y, phase| TestEvent {
+            widget: a,
+            source_child: vec![],
+            event: WidgetEvent::MouseScrollPx {
+                dir: Vector2::new(0, y),
+                in_widget: true,
+                phase,
+            },
+        };
+        event_list.set_events(vec![
+            scroll_px(0, ScrollPhase::Started),
+            scroll_px(1, ScrollPhase::Moved),
+            scroll_px(0, ScrollPhase::Ended),
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.mouse_hover_widget = Some(a);
+        // The half pixel from the first event should get carried into the second.
+        translator.translate_window_event(WindowEvent::MouseScrollPxPrecise {
+            delta: Vector2::new(0.0, 0.5),
+            phase: ScrollPhase::Started,
+        });
+        translator.translate_window_event(WindowEvent::MouseScrollPxPrecise {
+            delta: Vector2::new(0.0, 0.75),
+            phase: ScrollPhase::Moved,
+        });
+        translator.translate_window_event(WindowEvent::MouseScrollPxPrecise {
+            delta: Vector2::new(0.0, 0.25),
+            phase: ScrollPhase::Ended,
+        });
+    }
+
+    #[test]
+    fn pen_coalescing() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 20, 10);
+                a { rect: (10, 0, 20, 10) }
+            };
+        }
+
+        let pen_input = |x, y| PenInput {
+            sample: PenSample {
+                pos: Point2::new(x, y),
+                pressure: 0.5,
+                tilt: Vector2::new(0.0, 0.0),
+            },
+            tool: PenTool::Tip,
+            barrel_button: false,
+        };
+        event_list.set_events(vec![
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::PenDown{input: pen_input(2.0, 5.0), in_widget: true},
+            },
+            // The movements get coalesced into a single event when they get flushed.
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::PenMove {
+                    input: pen_input(5.0, 5.0),
+                    history: vec![pen_input(3.0, 5.0).sample, pen_input(4.0, 5.5).sample],
+                    in_widget: true,
+                },
+            },
+            // `a` keeps receiving pen events until the pen's lifted, even after the pen leaves it.
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::PenUp{input: pen_input(-5.0, 5.0), in_widget: false},
+            },
+            TestEvent {
+                widget: root,
+                source_child: vec![],
+                event: WidgetEvent::PenUp{input: pen_input(5.0, 5.0), in_widget: true},
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.mouse_hover_widget = Some(a);
+        translator.translate_window_event(WindowEvent::PenDown(pen_input(12.0, 5.0)));
+        translator.translate_window_event(WindowEvent::PenMove(pen_input(13.0, 5.0)));
+        translator.translate_window_event(WindowEvent::PenMove(pen_input(14.0, 5.5)));
+        translator.translate_window_event(WindowEvent::PenMove(pen_input(15.0, 5.0)));
+        translator.flush_pen_moves();
+
+        translator.input_state.mouse_hover_widget = Some(root);
+        translator.translate_window_event(WindowEvent::PenUp(pen_input(5.0, 5.0)));
+    }
+
+    #[test]
+    fn event_bubbling() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40);
+                a {
+                    rect: (10, 10, 30, 30), bubble_events: true;
+                    b { rect: (10, 10, 20, 20), bubble_events: true }
+                },
+                c { rect: (0, 0, 10, 10) }
+            };
+        }
+
+        let a_ident = WidgetIdent::new_str("a");
+        let b_ident = WidgetIdent::new_str("b");
+
+        event_list.set_events(vec![
+            // WindowEvent::Char('x')
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::Char('x'),
+            },
+            TestEvent {
+                widget: a,
+                source_child: vec![b_ident.clone()],
+                event: WidgetEvent::Char('x'),
+            },
+            TestEvent {
+                widget: root,
+                source_child: vec![a_ident.clone(), b_ident.clone()],
+                event: WidgetEvent::Char('x'),
+            },
+
+            // WindowEvent::Char('y')
+            TestEvent {
+                widget: c,
+                source_child: vec![],
+                event: WidgetEvent::Char('y'),
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.focused_widget = Some(b);
+        translator.translate_window_event(WindowEvent::Char('x'));
+
+        // `c` doesn't bubble its events, so `root` shouldn't see this one.
+        translator.input_state.focused_widget = Some(c);
+        translator.translate_window_event(WindowEvent::Char('y'));
+    }
+
+    #[test]
+    fn stop_bubbling() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40), bubble_events: true;
+                a {
+                    rect: (10, 10, 30, 30);
+                    b { rect: (10, 10, 20, 20), bubble_events: true }
+                }
+            };
+        }
+
+        let b_ident = WidgetIdent::new_str("b");
+
+        // `a` handles the event without bubbling it, so it never reaches `root`.
+        event_list.set_events(vec![
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::Char('x'),
+            },
+            TestEvent {
+                widget: a,
+                source_child: vec![b_ident.clone()],
+                event: WidgetEvent::Char('x'),
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.focused_widget = Some(b);
+        translator.translate_window_event(WindowEvent::Char('x'));
+    }
+
+    #[test]
+    fn intercept_child_events() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40);
+                a {
+                    rect: (10, 10, 30, 30), intercept_events: true;
+                    b { rect: (10, 10, 20, 20) }
+                }
+            };
+        }
+
+        create_translator!(mut translator, &mut tree, root);
+
+        // `a` consumes the event before `b` sees it.
+        event_list.set_events(vec![]);
+        translator.input_state.focused_widget = Some(b);
+        translator.translate_window_event(WindowEvent::Char('x'));
+
+        // Events sent to `a` itself aren't intercepted.
+        event_list.set_events(vec![
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::Char('y'),
+            },
+        ]);
+        translator.input_state.focused_widget = Some(a);
+        translator.translate_window_event(WindowEvent::Char('y'));
+    }
+
+    #[test]
+    fn modal_blocks_input() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40);
+                a { rect: (0, 0, 20, 40) },
+                b { rect: (20, 0, 20, 40) }
+            };
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::Char('x')
+            //
+            // Focus moves into the modal widget before the character gets delivered.
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::LoseFocus,
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::GainFocus(FocusSource::This, FocusChange::Take),
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::Char('x'),
+            },
+            // WindowEvent::MouseDown(MouseButton::Left)
+            //
+            // `a` is under the mouse, but only the focused widget inside of the modal sees the click.
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseDown {
+                    pos: Point2::new(-10, 10),
+                    in_widget: false,
+                    button: MouseButton::Left,
+                    click_count: 1,
+                },
+            },
+            // WindowEvent::Char('y')
+            //
+            // Removing the modal gives focus back.
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::LoseFocus,
+            },
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::GainFocus(FocusSource::This, FocusChange::Take),
+            },
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::Char('y'),
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+        translator.input_state.focused_widget = Some(a);
+        translator.update_state.borrow_mut().modals_mut().push(b);
+
+        translator.translate_window_event(WindowEvent::Char('x'));
+
+        translator.input_state.mouse_pos = Some(Point2::new(10, 10));
+        translator.input_state.mouse_hover_widget = Some(a);
+        translator.translate_window_event(WindowEvent::MouseDown(MouseButton::Left));
+
+        translator.update_state.borrow_mut().modals_mut().remove(b);
+        translator.translate_window_event(WindowEvent::Char('y'));
+    }
+
+    #[test]
+    fn disabled_widgets() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40);
+                a { rect: (0, 20, 20, 40) },
+                b { rect: (20, 0, 40, 20) }
+            };
+        }
+        tree.children.as_mut().unwrap()[&WidgetIdent::new_str("b")].widget_tag.set_enabled(false);
+
+        event_list.set_events(vec![
+            // WindowEvent::Char('x')
+            //
+            // `b` has focus, but it's disabled so the character gets dropped.
+
+            // WindowEvent::MouseMove(Point2::new(1, 5))
+            TestEvent {
+                widget: root,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-1, 5),
+                    new_pos: Point2::new(1, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+
+            // WindowEvent::MouseMove(Point2::new(25, 5))
+            //
+            // The mouse passes through `b`, so `root` stays hovered.
+            TestEvent {
+                widget: root,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(1, 5),
+                    new_pos: Point2::new(25, 5),
+                    in_widget: true,
+                    hover_change: None,
+                }
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+        translator.input_state.focused_widget = Some(b);
+        translator.translate_window_event(WindowEvent::Char('x'));
+
+        translator.translate_window_event(WindowEvent::MouseEnter);
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(1, 5)));
+        translator.translate_window_event(WindowEvent::MouseMove(Point2::new(25, 5)));
+        assert_eq!(Some(root), translator.input_state.mouse_hover_widget);
+    }
+
+    #[test]
+    fn disable_without_relayout() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let tree = a {
+                rect: (0, 0, 40, 40);
+                b {
+                    rect: (10, 10, 30, 30);
+                    c { rect: (0, 0, 20, 20) }
+                }
+            };
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::MouseMove(Point2::new(1, 5))
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-1, 5),
+                    new_pos: Point2::new(1, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+
+            // WindowEvent::MouseMove(Point2::new(15, 15))
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(1, 5),
+                    new_pos: Point2::new(15, 15),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(WidgetIdent::new_str("b"))),
+                }
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-9, -5),
+                    new_pos: Point2::new(5, 5),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-9, -5),
+                    new_pos: Point2::new(5, 5),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(WidgetIdent::new_str("c"))),
+                }
+            },
+            TestEvent {
+                widget: c,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(-9, -5),
+                    new_pos: Point2::new(5, 5),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::Enter),
+                }
+            },
+
+            // WindowEvent::MouseMove(Point2::new(16, 16))
+            //
+            // `b` just got disabled, so the mouse passes through both it and `c` even though
+            // neither has been laid out since.
+            TestEvent {
+                widget: c,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(5, 5),
+                    new_pos: Point2::new(6, 6),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::Exit),
+                }
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(5, 5),
+                    new_pos: Point2::new(6, 6),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::ExitChild(WidgetIdent::new_str("c"))),
+                }
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(5, 5),
+                    new_pos: Point2::new(6, 6),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::Exit),
+                }
+            },
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(15, 15),
+                    new_pos: Point2::new(16, 16),
+                    in_widget: true,
+                    hover_change: Some(MouseHoverChange::ExitChild(WidgetIdent::new_str("b"))),
+                }
+            },
+
+            // WindowEvent::MouseMove(Point2::new(17, 17))
+            //
+            // `c` inherited `b`'s disabled state on the last move, but re-enabling `b` lets the
+            // mouse back into both right away.
+            TestEvent {
+                widget: a,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(16, 16),
+                    new_pos: Point2::new(17, 17),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(WidgetIdent::new_str("b"))),
+                }
+            },
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(6, 6),
+                    new_pos: Point2::new(7, 7),
+                    in_widget: false,
+                    hover_change: Some(MouseHoverChange::EnterChild(WidgetIdent::new_str("c"))),
+                }
+            },
+            TestEvent {
+                widget: c,
+                source_child: vec![],
+                event: WidgetEvent::MouseMove {
+                    old_pos: Point2::new(6, 6),
+                    new_pos: Point2::new(7, 7),
+                    in_widget: true,
+                    hover_change: None,
+                }
+            },
+        ]);
+
+        let mut harness = TestHarness::new(tree);
+        harness.process_events(vec![
+            WindowEvent::MouseEnter,
+            WindowEvent::MouseMove(Point2::new(1, 5)),
+            WindowEvent::MouseMove(Point2::new(15, 15)),
+        ]);
+
+        harness.root_widget.children.as_mut().unwrap()[&WidgetIdent::new_str("b")].widget_tag.set_enabled(false);
+        harness.process_event(WindowEvent::MouseMove(Point2::new(16, 16)));
+
+        harness.root_widget.children.as_mut().unwrap()[&WidgetIdent::new_str("b")].widget_tag.set_enabled(true);
+        harness.process_event(WindowEvent::MouseMove(Point2::new(17, 17)));
+    }
+
+    #[test]
+    fn scroll_bubbling() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40);
+                a {
+                    rect: (10, 10, 30, 30), bubble_events: true;
+                    b { rect: (10, 10, 20, 20), bubble_events: true }
+                }
+            };
+        }
+
+        let a_ident = WidgetIdent::new_str("a");
+        let b_ident = WidgetIdent::new_str("b");
+        let scroll_event = WidgetEvent::MouseScrollLines {
+            dir: Vector2::new(0, -2),
+            dir_px: Vector2::new(0, -20),
+            in_widget: true,
+        };
+
+        event_list.set_events(vec![
+            TestEvent {
+                widget: b,
+                source_child: vec![],
+                event: scroll_event.clone(),
+            },
+            TestEvent {
+                widget: a,
+                source_child: vec![b_ident.clone()],
+                event: scroll_event.clone(),
+            },
+            TestEvent {
+                widget: root,
+                source_child: vec![a_ident.clone(), b_ident.clone()],
+                event: scroll_event.clone(),
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.scroll_line_height = 10;
+        translator.input_state.mouse_hover_widget = Some(b);
+        translator.translate_window_event(WindowEvent::MouseScrollLines(Vector2::new(0, -2)));
+    }
+
+    #[test]
+    fn focus_scope_buttons() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40), bubble_events: true;
+                dialog {
+                    rect: (0, 0, 30, 30), bubble_events: true;
+                    field { rect: (0, 0, 10, 10), bubble_events: true },
+                    ok { rect: (10, 0, 20, 10) }
+                },
+                cancel { rect: (30, 30, 40, 40) }
+            };
+        }
+
+        let dialog_ident = WidgetIdent::new_str("dialog");
+        let field_ident = WidgetIdent::new_str("field");
+        {
+            let tree_children = tree.children.as_mut().unwrap();
+            tree_children[&WidgetIdent::new_str("cancel")].widget_tag.set_scope_button(Some(ScopeButton::Cancel));
+
+            let dialog_widget = &mut tree_children[&dialog_ident];
+            dialog_widget.widget_tag.set_focus_scope(true);
+            dialog_widget.children.as_mut().unwrap()[&WidgetIdent::new_str("ok")].widget_tag.set_scope_button(Some(ScopeButton::Default));
+        }
+
+        event_list.set_events(vec![
+            // WindowEvent::KeyDown(Key::Enter)
+            TestEvent {
+                widget: field,
+                source_child: vec![],
+                event: WidgetEvent::KeyDown(Key::Enter, ModifierKeys::empty()),
+            },
+            TestEvent {
+                widget: dialog,
+                source_child: vec![field_ident.clone()],
+                event: WidgetEvent::KeyDown(Key::Enter, ModifierKeys::empty()),
+            },
+            TestEvent {
+                widget: ok,
+                source_child: vec![],
+                event: WidgetEvent::ScopeButtonActivated(ScopeButton::Default),
+            },
+
+            // WindowEvent::KeyDown(Key::Escape)
+            TestEvent {
+                widget: field,
+                source_child: vec![],
+                event: WidgetEvent::KeyDown(Key::Escape, ModifierKeys::empty()),
+            },
+            TestEvent {
+                widget: dialog,
+                source_child: vec![field_ident.clone()],
+                event: WidgetEvent::KeyDown(Key::Escape, ModifierKeys::empty()),
+            },
+            // `dialog` doesn't have a cancel button, so the key gets passed to the root's scope.
+            TestEvent {
+                widget: root,
+                source_child: vec![dialog_ident.clone(), field_ident.clone()],
+                event: WidgetEvent::KeyDown(Key::Escape, ModifierKeys::empty()),
+            },
+            TestEvent {
+                widget: cancel,
+                source_child: vec![],
+                event: WidgetEvent::ScopeButtonActivated(ScopeButton::Cancel),
+            },
+        ]);
+
+        create_translator!(mut translator, &mut tree, root);
+
+        translator.input_state.focused_widget = Some(field);
+        translator.translate_window_event(WindowEvent::KeyDown(Key::Enter));
+        translator.translate_window_event(WindowEvent::KeyDown(Key::Escape));
+    }
 }
@@ -5,6 +5,7 @@
 use crate::{
     cgmath::Point2,
     event::{FocusChange, FocusSource, WidgetEvent},
+    modal,
     render::Renderer,
     widget::{WidgetId, WidgetIdent},
     widget_traverser::{Relation, WidgetTraverser, OffsetWidgetScanPath},
@@ -12,7 +13,11 @@ use crate::{
 use std::collections::VecDeque;
 
 pub(crate) struct EventDispatcher {
-    events: VecDeque<(EventDestination, DispatchableEvent)>
+    events: VecDeque<(EventDestination, DispatchableEvent)>,
+    /// Set while handling an `Intercept` event to keep the event from reaching its destination.
+    pub intercepted: bool,
+    /// The path to the modal widget on top of the modal stack. Events outside of it get dropped.
+    pub modal_path: Option<Vec<WidgetIdent>>,
 }
 
 #[derive(Debug, Clone)]
@@ -33,7 +38,15 @@ pub(crate) enum DispatchableEvent {
         change: FocusChange,
     },
     Direct {
-        bubble_source: Option<WidgetId>,
+        /// If the event is being bubbled, the full path to the widget the event was originally
+        /// dispatched to.
+        bubble_source: Option<Vec<WidgetIdent>>,
+        event: WidgetEvent,
+    },
+    /// Give an ancestor of the event's destination the chance to consume it.
+    Intercept {
+        /// The path from the ancestor to the destination.
+        child_path: Vec<WidgetIdent>,
         event: WidgetEvent,
     },
 }
@@ -41,7 +54,9 @@ pub(crate) enum DispatchableEvent {
 impl EventDispatcher {
     pub fn new() -> EventDispatcher {
         EventDispatcher {
-            events: VecDeque::new()
+            events: VecDeque::new(),
+            intercepted: false,
+            modal_path: None,
         }
     }
 
@@ -75,11 +90,81 @@ impl EventDispatcher {
                 }
             };
 
-            let widget = match widget_opt {
-                Some(w) => w,
+            let (widget_id, path, enabled) = match widget_opt {
+                Some(w) => (w.widget_id, w.path.to_vec(), w.widget.widget_tag().is_enabled()),
                 None => continue //TODO: LOG WARNING
             };
-            f(self, widget, event);
+
+            if !enabled || !modal::in_modal(self.modal_path.as_ref().map(|p| &p[..]), &path) {
+                let blocked = match event {
+                    // The mouse can't hover over disabled widgets or widgets outside of the modal,
+                    // which gets handled while dispatching the `MouseMove`.
+                    DispatchableEvent::MouseMove{..} => false,
+                    DispatchableEvent::GainFocus{..} => true,
+                    DispatchableEvent::Direct{ref event, ..} => !modal::finishes_interaction(event),
+                    DispatchableEvent::Intercept{..} => false,
+                };
+                if blocked {
+                    continue;
+                }
+            }
+
+            if let DispatchableEvent::Direct{bubble_source: None, ref event} = event {
+                if can_intercept(event) {
+                    self.intercept(widget_traverser, widget_id, &path, event, &mut f);
+                    if self.intercepted {
+                        self.intercepted = false;
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(widget) = widget_traverser.get_widget(widget_id) {
+                f(self, widget, event);
+            }
         }
     }
+
+    /// Offer `event` to each of the destination's ancestors, starting at the root, until one of
+    /// them consumes it.
+    fn intercept<R>(
+        &mut self,
+        widget_traverser: &mut WidgetTraverser<R>,
+        widget_id: WidgetId,
+        path: &[WidgetIdent],
+        event: &WidgetEvent,
+        f: &mut impl FnMut(&mut Self, OffsetWidgetScanPath<R>, DispatchableEvent)
+    )
+        where R: Renderer
+    {
+        let mut ancestors = Vec::new();
+        let mut id = widget_id;
+        while let Some(parent) = widget_traverser.get_widget_relation(id, Relation::Parent) {
+            id = parent.widget_id;
+            ancestors.push(id);
+        }
+
+        for ancestor_id in ancestors.into_iter().rev() {
+            let ancestor = match widget_traverser.get_widget(ancestor_id) {
+                Some(w) => w,
+                None => continue
+            };
+            let child_path = path[ancestor.path.len()..].to_vec();
+            f(self, ancestor, DispatchableEvent::Intercept{ child_path, event: event.clone() });
+            if self.intercepted {
+                break;
+            }
+        }
+    }
+}
+
+fn can_intercept(event: &WidgetEvent) -> bool {
+    match *event {
+        WidgetEvent::MouseMove{..} |
+        WidgetEvent::GainFocus(..) |
+        WidgetEvent::LoseFocus |
+        WidgetEvent::Timer{..} |
+        WidgetEvent::IdleWork{..} => false,
+        _ => true
+    }
 }
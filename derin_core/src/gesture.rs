@@ -0,0 +1,246 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Recognition of high-level gestures from raw mouse input.
+//!
+//! Widgets opt into gestures with [`WidgetTag::recognize_gestures`]. When the left mouse button is
+//! pressed in a widget - or in a descendant of it that doesn't recognize gestures itself - the press
+//! gets tracked, and the widget recieves a `WidgetEvent::Gesture` once a gesture has been
+//! recognized. The raw mouse events are still delivered as usual.
+//!
+//! Only gestures that can be performed with a single pointer are recognized. Pinching needs
+//! multi-touch input, which the window backends don't report.
+//!
+//! [`WidgetTag::recognize_gestures`]: ../widget/struct.WidgetTag.html#method.recognize_gestures
+
+use crate::{
+    cgmath::{Point2, Vector2},
+    timer::{TimerId, TimerTrigger},
+    widget::WidgetId,
+};
+use std::time::{Duration, Instant};
+
+/// How long the mouse has to be held still before a long press is recognized.
+pub const LONG_PRESS_DELAY: Duration = Duration::from_millis(500);
+/// How far, in pixels, the mouse can move during a tap or long press.
+pub const TAP_SLOP: i32 = 8;
+/// How far, in pixels, the mouse has to move during a swipe.
+pub const SWIPE_MIN_DISTANCE: i32 = 48;
+/// The longest a swipe can take, from pressing the button to releasing it.
+pub const SWIPE_MAX_DURATION: Duration = Duration::from_millis(300);
+
+bitflags!{
+    /// The set of gestures a widget recognizes.
+    pub struct GestureSet: u8 {
+        const TAP        = 1 << 0;
+        const LONG_PRESS = 1 << 1;
+        const SWIPE      = 1 << 2;
+    }
+}
+
+/// A recognized gesture. All positions are given relative to the widget's origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// The mouse was pressed and released without moving, before a long press was recognized.
+    Tap {
+        pos: Point2<i32>,
+    },
+    /// The mouse was pressed and held without moving for `LONG_PRESS_DELAY`. Delivered while the
+    /// button is still held down.
+    LongPress {
+        pos: Point2<i32>,
+    },
+    /// The mouse was pressed, quickly moved at least `SWIPE_MIN_DISTANCE` pixels, and released.
+    Swipe {
+        start_pos: Point2<i32>,
+        end_pos: Point2<i32>,
+        direction: SwipeDirection,
+    },
+}
+
+/// The direction of a swipe, along whichever axis the mouse moved farthest on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Tracks the mouse press gestures are being recognized from.
+#[derive(Debug, Clone)]
+pub(crate) struct GestureTracker {
+    press: Option<GesturePress>,
+    long_press_timer: TimerId,
+    /// The long press timer trigger that needs to be queued with the timer tracker.
+    queue_trigger: Option<TimerTrigger>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GesturePress {
+    widget_id: WidgetId,
+    gestures: GestureSet,
+    down_pos: Point2<i32>,
+    down_time: Instant,
+    /// Whether the mouse has moved farther than `TAP_SLOP` since being pressed.
+    moved: bool,
+    long_pressed: bool,
+}
+
+impl Gesture {
+    /// Shift the gesture's positions by the specified vector.
+    pub fn translate(self, dir: Vector2<i32>) -> Gesture {
+        match self {
+            Gesture::Tap{ pos } => Gesture::Tap{ pos: pos + dir },
+            Gesture::LongPress{ pos } => Gesture::LongPress{ pos: pos + dir },
+            Gesture::Swipe{ start_pos, end_pos, direction } => Gesture::Swipe {
+                start_pos: start_pos + dir,
+                end_pos: end_pos + dir,
+                direction,
+            },
+        }
+    }
+}
+
+impl GestureTracker {
+    pub fn new() -> GestureTracker {
+        GestureTracker {
+            press: None,
+            long_press_timer: TimerId::new(),
+            queue_trigger: None,
+        }
+    }
+
+    /// The ID of the timer that fires long presses.
+    pub fn long_press_timer(&self) -> TimerId {
+        self.long_press_timer
+    }
+
+    /// Start tracking a press for the given widget.
+    pub fn press(&mut self, widget_id: WidgetId, gestures: GestureSet, pos: Point2<i32>, now: Instant) {
+        self.press = Some(GesturePress {
+            widget_id, gestures,
+            down_pos: pos,
+            down_time: now,
+            moved: false,
+            long_pressed: false,
+        });
+        if gestures.contains(GestureSet::LONG_PRESS) {
+            self.queue_trigger = Some(TimerTrigger::new(now + LONG_PRESS_DELAY, self.long_press_timer, widget_id));
+        }
+    }
+
+    pub fn mouse_move(&mut self, pos: Point2<i32>) {
+        if let Some(ref mut press) = self.press {
+            let delta = pos - press.down_pos;
+            press.moved |= delta.x.abs() > TAP_SLOP || delta.y.abs() > TAP_SLOP;
+        }
+    }
+
+    /// Stop tracking the press, without recognizing any gesture.
+    pub fn cancel(&mut self) {
+        self.press = None;
+    }
+
+    /// Finish the press, returning the gesture it made if the widget recognizes it.
+    pub fn release(&mut self, pos: Point2<i32>, now: Instant) -> Option<(WidgetId, Gesture)> {
+        let press = self.press.take()?;
+        let delta = pos - press.down_pos;
+
+        let gesture = match (press.moved, press.long_pressed) {
+            (false, false) => Gesture::Tap{ pos },
+            (true, _) => {
+                let direction = match delta.x.abs() >= delta.y.abs() {
+                    true if delta.x < 0 => SwipeDirection::Left,
+                    true => SwipeDirection::Right,
+                    false if delta.y < 0 => SwipeDirection::Up,
+                    false => SwipeDirection::Down,
+                };
+                let distance = delta.x.abs().max(delta.y.abs());
+                if distance < SWIPE_MIN_DISTANCE || now.duration_since(press.down_time) > SWIPE_MAX_DURATION {
+                    return None;
+                }
+                Gesture::Swipe{ start_pos: press.down_pos, end_pos: pos, direction }
+            },
+            (false, true) => return None
+        };
+
+        let kind = match gesture {
+            Gesture::Tap{..} => GestureSet::TAP,
+            Gesture::LongPress{..} => GestureSet::LONG_PRESS,
+            Gesture::Swipe{..} => GestureSet::SWIPE,
+        };
+        match press.gestures.contains(kind) {
+            true => Some((press.widget_id, gesture)),
+            false => None
+        }
+    }
+
+    /// Called when the long press timer fires. Returns the long press, if the mouse has been held
+    /// still for long enough.
+    pub fn long_press(&mut self, now: Instant) -> Option<(WidgetId, Gesture)> {
+        let press = self.press.as_mut()?;
+        let held = now.duration_since(press.down_time) >= LONG_PRESS_DELAY;
+        if press.moved || press.long_pressed || !held || !press.gestures.contains(GestureSet::LONG_PRESS) {
+            return None;
+        }
+
+        press.long_pressed = true;
+        Some((press.widget_id, Gesture::LongPress{ pos: press.down_pos }))
+    }
+
+    /// Take the long press timer trigger that needs to be queued, if any.
+    pub fn take_trigger(&mut self) -> Option<TimerTrigger> {
+        self.queue_trigger.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognize_gestures() {
+        let mut tracker = GestureTracker::new();
+        let widget_id = WidgetId::new();
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+
+        // Releasing without moving is a tap.
+        tracker.press(widget_id, GestureSet::all(), Point2::new(10, 10), start);
+        assert!(tracker.take_trigger().is_some());
+        tracker.mouse_move(Point2::new(12, 10));
+        assert_eq!(Some((widget_id, Gesture::Tap{ pos: Point2::new(12, 10) })), tracker.release(Point2::new(12, 10), ms(100)));
+
+        // Holding still gives a long press, which swallows the release.
+        tracker.press(widget_id, GestureSet::all(), Point2::new(10, 10), start);
+        assert_eq!(None, tracker.long_press(ms(100)));
+        assert_eq!(Some((widget_id, Gesture::LongPress{ pos: Point2::new(10, 10) })), tracker.long_press(ms(500)));
+        assert_eq!(None, tracker.long_press(ms(600)));
+        assert_eq!(None, tracker.release(Point2::new(10, 10), ms(700)));
+
+        // Moving quickly is a swipe, and moving cancels the long press.
+        tracker.press(widget_id, GestureSet::all(), Point2::new(100, 10), start);
+        tracker.mouse_move(Point2::new(40, 20));
+        assert_eq!(None, tracker.long_press(ms(500)));
+        assert_eq!(
+            Some((widget_id, Gesture::Swipe {
+                start_pos: Point2::new(100, 10),
+                end_pos: Point2::new(40, 20),
+                direction: SwipeDirection::Left,
+            })),
+            tracker.release(Point2::new(40, 20), ms(200))
+        );
+
+        // Moving slowly isn't anything.
+        tracker.press(widget_id, GestureSet::all(), Point2::new(10, 10), start);
+        tracker.mouse_move(Point2::new(10, 100));
+        assert_eq!(None, tracker.release(Point2::new(10, 100), ms(1000)));
+
+        // Gestures the widget doesn't recognize aren't reported.
+        tracker.press(widget_id, GestureSet::SWIPE, Point2::new(10, 10), start);
+        assert!(tracker.take_trigger().is_none());
+        assert_eq!(None, tracker.release(Point2::new(10, 10), ms(100)));
+    }
+}
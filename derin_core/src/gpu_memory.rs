@@ -0,0 +1,268 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! GPU memory accounting and eviction, for renderers that cache data on the GPU.
+//!
+//! Renderers record each GPU allocation in a [`GpuMemoryTracker`], along with the widget that owns
+//! it, and mark allocations as used whenever they're drawn. Once the total size of the allocations
+//! exceeds the tracker's budget, the least recently used allocations get evicted. The renderer
+//! returns the evicted allocations' owners from `Renderer::take_evicted_owners`, and each owner
+//! gets sent a [`TexturesEvicted`] message.
+//!
+//! [`GpuMemoryTracker`]: ./struct.GpuMemoryTracker.html
+//! [`TexturesEvicted`]: ./struct.TexturesEvicted.html
+
+use crate::widget::WidgetId;
+use fnv::FnvHashMap;
+use std::hash::Hash;
+
+/// The kind of data stored in a GPU allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GpuResourceKind {
+    /// A page of a glyph or image atlas.
+    AtlasPage,
+    /// A widget subtree that's been rendered to a texture, so it doesn't need to be redrawn.
+    CachedLayer,
+    /// A standalone image texture.
+    ImageTexture,
+}
+
+/// Message sent to a widget after GPU data it owned was evicted.
+///
+/// The renderer re-creates the data the next time the widget is drawn, so widgets generally only
+/// need to handle this if they're tracking their own resource usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TexturesEvicted;
+
+/// A GPU allocation that's been evicted from a `GpuMemoryTracker`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evicted<K> {
+    pub key: K,
+    pub kind: GpuResourceKind,
+    pub bytes: u64,
+    pub owner: Option<WidgetId>,
+}
+
+/// The amount of GPU memory used, broken down by resource kind.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GpuMemoryStats {
+    pub atlas_page_bytes: u64,
+    pub cached_layer_bytes: u64,
+    pub image_texture_bytes: u64,
+    pub budget_bytes: Option<u64>,
+}
+
+/// Tracks GPU allocations, evicting the least recently used allocations when over budget.
+#[derive(Debug, Clone)]
+pub struct GpuMemoryTracker<K: Hash + Eq> {
+    budget_bytes: Option<u64>,
+    used_bytes: u64,
+    frame: u64,
+    resources: FnvHashMap<K, GpuResource>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GpuResource {
+    kind: GpuResourceKind,
+    bytes: u64,
+    owner: Option<WidgetId>,
+    last_used_frame: u64,
+}
+
+impl GpuMemoryStats {
+    pub fn used_bytes(&self) -> u64 {
+        self.atlas_page_bytes + self.cached_layer_bytes + self.image_texture_bytes
+    }
+}
+
+impl<K: Hash + Eq + Clone> GpuMemoryTracker<K> {
+    /// Create a new tracker with the given budget. If `None`, nothing is ever evicted.
+    pub fn new(budget_bytes: Option<u64>) -> GpuMemoryTracker<K> {
+        GpuMemoryTracker {
+            budget_bytes,
+            used_bytes: 0,
+            frame: 0,
+            resources: FnvHashMap::default(),
+        }
+    }
+
+    #[inline]
+    pub fn budget_bytes(&self) -> Option<u64> {
+        self.budget_bytes
+    }
+
+    /// Set the budget. The budget is enforced the next time `evict` is called.
+    #[inline]
+    pub fn set_budget_bytes(&mut self, budget_bytes: Option<u64>) {
+        self.budget_bytes = budget_bytes;
+    }
+
+    #[inline]
+    pub fn used_bytes(&self) -> u64 {
+        self.used_bytes
+    }
+
+    pub fn stats(&self) -> GpuMemoryStats {
+        let mut stats = GpuMemoryStats {
+            budget_bytes: self.budget_bytes,
+            ..GpuMemoryStats::default()
+        };
+        for resource in self.resources.values() {
+            match resource.kind {
+                GpuResourceKind::AtlasPage => stats.atlas_page_bytes += resource.bytes,
+                GpuResourceKind::CachedLayer => stats.cached_layer_bytes += resource.bytes,
+                GpuResourceKind::ImageTexture => stats.image_texture_bytes += resource.bytes,
+            }
+        }
+        stats
+    }
+
+    /// Record an allocation, replacing the previous allocation with the same key. The allocation
+    /// counts as used in the current frame.
+    pub fn insert(&mut self, key: K, kind: GpuResourceKind, bytes: u64, owner: Option<WidgetId>) {
+        let resource = GpuResource {
+            kind, bytes, owner,
+            last_used_frame: self.frame,
+        };
+        if let Some(old_resource) = self.resources.insert(key, resource) {
+            self.used_bytes -= old_resource.bytes;
+        }
+        self.used_bytes += bytes;
+    }
+
+    /// Mark an allocation as used in the current frame. Returns `false` if the allocation isn't
+    /// tracked, which means it was evicted and needs to be re-created.
+    pub fn touch(&mut self, key: &K) -> bool {
+        match self.resources.get_mut(key) {
+            Some(resource) => {
+                resource.last_used_frame = self.frame;
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Stop tracking an allocation, because the renderer freed it.
+    pub fn remove(&mut self, key: &K) {
+        if let Some(resource) = self.resources.remove(key) {
+            self.used_bytes -= resource.bytes;
+        }
+    }
+
+    /// Stop tracking all allocations owned by the given widget, returning their keys so they can
+    /// be freed.
+    pub fn remove_owner(&mut self, owner: WidgetId) -> Vec<K> {
+        let keys: Vec<K> = self.resources.iter()
+            .filter(|(_, r)| r.owner == Some(owner))
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in &keys {
+            self.remove(key);
+        }
+        keys
+    }
+
+    /// Start a new frame. Should be called once the renderer's finished drawing.
+    #[inline]
+    pub fn bump_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Evict the least recently used allocations until the used memory is within budget.
+    ///
+    /// Allocations used in the current frame are never evicted, so this may leave the tracker over
+    /// budget if the current frame needs more memory than the budget allows.
+    pub fn evict(&mut self) -> Vec<Evicted<K>> {
+        let budget_bytes = match self.budget_bytes {
+            Some(budget_bytes) if self.used_bytes > budget_bytes => budget_bytes,
+            _ => return Vec::new()
+        };
+
+        let mut candidates: Vec<(K, u64)> = self.resources.iter()
+            .filter(|(_, r)| r.last_used_frame < self.frame)
+            .map(|(k, r)| (k.clone(), r.last_used_frame))
+            .collect();
+        candidates.sort_by_key(|&(_, last_used_frame)| last_used_frame);
+
+        let mut evicted = Vec::new();
+        for (key, _) in candidates {
+            if self.used_bytes <= budget_bytes {
+                break;
+            }
+
+            let resource = self.resources.remove(&key).unwrap();
+            self.used_bytes -= resource.bytes;
+            evicted.push(Evicted {
+                key,
+                kind: resource.kind,
+                bytes: resource.bytes,
+                owner: resource.owner,
+            });
+        }
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evict_lru() {
+        let (a, b) = (WidgetId::new(), WidgetId::new());
+        let mut tracker = GpuMemoryTracker::new(Some(100));
+
+        tracker.insert(0, GpuResourceKind::ImageTexture, 40, Some(a));
+        tracker.insert(1, GpuResourceKind::ImageTexture, 40, Some(b));
+        tracker.bump_frame();
+        tracker.insert(2, GpuResourceKind::CachedLayer, 40, Some(a));
+        assert_eq!(120, tracker.used_bytes());
+
+        // `0` hasn't been used as recently as `1`, so it gets evicted first.
+        tracker.touch(&1);
+        tracker.bump_frame();
+        let evicted = tracker.evict();
+        assert_eq!(vec![Evicted{ key: 0, kind: GpuResourceKind::ImageTexture, bytes: 40, owner: Some(a) }], evicted);
+        assert_eq!(80, tracker.used_bytes());
+        assert!(!tracker.touch(&0));
+        assert!(tracker.touch(&1));
+    }
+
+    #[test]
+    fn keep_current_frame() {
+        let mut tracker = GpuMemoryTracker::new(Some(10));
+        tracker.insert("a", GpuResourceKind::AtlasPage, 20, None);
+        assert_eq!(Vec::<Evicted<&str>>::new(), tracker.evict());
+
+        tracker.bump_frame();
+        assert_eq!(1, tracker.evict().len());
+        assert_eq!(0, tracker.used_bytes());
+    }
+
+    #[test]
+    fn stats() {
+        let owner = WidgetId::new();
+        let mut tracker = GpuMemoryTracker::new(None);
+        tracker.insert(0, GpuResourceKind::AtlasPage, 1, None);
+        tracker.insert(1, GpuResourceKind::CachedLayer, 2, Some(owner));
+        tracker.insert(2, GpuResourceKind::ImageTexture, 4, Some(owner));
+        tracker.insert(2, GpuResourceKind::ImageTexture, 8, Some(owner));
+
+        assert_eq!(
+            GpuMemoryStats {
+                atlas_page_bytes: 1,
+                cached_layer_bytes: 2,
+                image_texture_bytes: 8,
+                budget_bytes: None,
+            },
+            tracker.stats()
+        );
+        assert_eq!(11, tracker.used_bytes());
+
+        let mut removed = tracker.remove_owner(owner);
+        removed.sort();
+        assert_eq!(vec![1, 2], removed);
+        assert_eq!(1, tracker.used_bytes());
+    }
+}
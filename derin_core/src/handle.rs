@@ -0,0 +1,142 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Typed handles to other widgets.
+//!
+//! Widgets can't hold references to each other, since the widget tree owns every widget. Instead, a
+//! widget that frequently coordinates with another one - such as a label that describes a text box,
+//! or a display showing a slider's value - can hold a [`WidgetHandle`] to it, and access it with
+//! [`WidgetTag::access_widget`].
+//!
+//! Handles are weak: holding one doesn't keep the widget alive. Accesses get performed once the
+//! current event has been processed, by looking the widget up in the widget tree. If the widget
+//! has been removed by then, the access never happens and the returned `Reply` gets abandoned.
+//!
+//! [`WidgetHandle`]: ./struct.WidgetHandle.html
+//! [`WidgetTag::access_widget`]: ../widget/struct.WidgetTag.html#method.access_widget
+
+use crate::{
+    request::{Reply, Request},
+    widget::{Widget, WidgetId},
+};
+use std::{
+    any::Any,
+    cell::RefCell,
+    fmt,
+    marker::PhantomData,
+};
+
+/// A weak, typed reference to a widget.
+pub struct WidgetHandle<W> {
+    widget_id: WidgetId,
+    _marker: PhantomData<fn(&mut W)>,
+}
+
+/// Message that runs a function on the widget it's delivered to.
+pub(crate) struct HandleAccess {
+    access: RefCell<Option<Box<FnMut(&mut Any)>>>,
+}
+
+impl<W: Widget> WidgetHandle<W> {
+    /// Create a handle to the given widget.
+    pub fn new(widget: &W) -> WidgetHandle<W> {
+        WidgetHandle {
+            widget_id: widget.widget_tag().widget_id(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<W> WidgetHandle<W> {
+    /// The ID of the widget the handle points to.
+    pub fn widget_id(&self) -> WidgetId {
+        self.widget_id
+    }
+}
+
+impl<W> Clone for WidgetHandle<W> {
+    fn clone(&self) -> WidgetHandle<W> {
+        *self
+    }
+}
+
+impl<W> Copy for WidgetHandle<W> {}
+
+impl<W> PartialEq for WidgetHandle<W> {
+    fn eq(&self, other: &WidgetHandle<W>) -> bool {
+        self.widget_id == other.widget_id
+    }
+}
+
+impl<W> Eq for WidgetHandle<W> {}
+
+impl<W> fmt::Debug for WidgetHandle<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_tuple("WidgetHandle")
+            .field(&self.widget_id)
+            .finish()
+    }
+}
+
+impl HandleAccess {
+    /// Create an access that calls `f` on a widget of type `W`, along with the reply that `f`'s
+    /// return value gets sent to.
+    pub fn new<W, T>(f: impl 'static + FnOnce(&mut W) -> T) -> (HandleAccess, Reply<T>)
+        where W: 'static,
+              T: 'static
+    {
+        let (request, reply) = Request::<(), T>::new(());
+        let mut f = Some(f);
+        let access = HandleAccess {
+            access: RefCell::new(Some(Box::new(move |widget_any: &mut Any| {
+                // The widget's type won't match if the handle was made for a `BoxedWidget`, since
+                // messages get dispatched to the boxed widget.
+                if let (Some(widget), Some(f)) = (widget_any.downcast_mut::<W>(), f.take()) {
+                    request.respond(f(widget));
+                }
+            })))
+        };
+        (access, reply)
+    }
+
+    /// Run the access on the widget. Does nothing if the access has already been run.
+    pub fn run(&self, widget: &mut Any) {
+        if let Some(mut access) = self.access.borrow_mut().take() {
+            access(widget);
+        }
+    }
+}
+
+impl fmt::Debug for HandleAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("HandleAccess")
+            .field("run", &self.access.borrow().is_none())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_access() {
+        let (access, mut reply) = HandleAccess::new(|value: &mut u32| {
+            *value += 1;
+            *value * 2
+        });
+        let mut value = 1u32;
+        access.run(&mut value);
+        access.run(&mut value);
+        drop(access);
+        assert_eq!(2, value);
+        assert_eq!(Some(4), reply.take());
+
+        // Accesses run on the wrong type get abandoned.
+        let (access, reply) = HandleAccess::new(|value: &mut u32| *value);
+        access.run(&mut String::new());
+        drop(access);
+        assert!(reply.abandoned());
+    }
+}
@@ -0,0 +1,42 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Context-sensitive help.
+//!
+//! Widgets declare a help topic with [`WidgetTag::set_help_topic`]. When the user presses `F1`,
+//! a [`HelpRequest`] is broadcast for the topic of the focused widget (or, if no widget has
+//! focus, the hovered widget). Pressing `Shift+F1` enters help mode instead, in which the next
+//! click broadcasts a request for the clicked widget rather than being delivered to it. `Escape`
+//! leaves help mode without sending a request.
+//!
+//! If a widget doesn't have a help topic, the topic of its nearest ancestor is used. Any widget
+//! can handle help requests by registering a `HelpRequest` message handler.
+//!
+//! [`WidgetTag::set_help_topic`]: ../widget/struct.WidgetTag.html#method.set_help_topic
+//! [`HelpRequest`]: ./struct.HelpRequest.html
+
+use crate::widget::WidgetId;
+use std::sync::Arc;
+
+/// Message broadcast when the user asks for help on a widget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpRequest {
+    /// The help topic of the widget, or of its nearest ancestor with a help topic.
+    pub topic: Arc<str>,
+    /// The widget help was requested for.
+    pub widget_id: WidgetId,
+    /// How the widget was selected.
+    pub source: HelpSource,
+}
+
+/// How the widget a `HelpRequest` refers to was selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HelpSource {
+    /// `F1` was pressed while the widget had keyboard focus.
+    Focus,
+    /// `F1` was pressed while the mouse was over the widget, and no widget had keyboard focus.
+    Hover,
+    /// The widget was clicked while in help mode.
+    Click,
+}
@@ -0,0 +1,76 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::widget::WidgetId;
+use std::collections::VecDeque;
+
+/// Round-robin queue of widgets that have incremental work to do during idle time.
+///
+/// Each widget in the queue gets a single `IdleWork` event per turn. Widgets that still have work
+/// left after their slice queue themselves again, which puts them at the back of the line so one
+/// long task can't starve the others.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdleScheduler {
+    queue: VecDeque<WidgetId>,
+}
+
+impl IdleScheduler {
+    pub fn new() -> IdleScheduler {
+        IdleScheduler {
+            queue: VecDeque::new()
+        }
+    }
+
+    pub fn queue_work(&mut self, widget_id: WidgetId) {
+        if !self.queue.contains(&widget_id) {
+            self.queue.push_back(widget_id);
+        }
+    }
+
+    /// Remove the widget from the queue, returning whether it had work queued.
+    pub fn remove(&mut self, widget_id: WidgetId) -> bool {
+        let old_len = self.queue.len();
+        self.queue.retain(|&id| id != widget_id);
+        self.queue.len() != old_len
+    }
+
+    /// Take the widget whose turn it is to do work.
+    pub fn next_slice(&mut self) -> Option<WidgetId> {
+        self.queue.pop_front()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_robin() {
+        let (a, b) = (WidgetId::new(), WidgetId::new());
+        let mut scheduler = IdleScheduler::new();
+
+        scheduler.queue_work(a);
+        scheduler.queue_work(b);
+        scheduler.queue_work(a);
+
+        assert_eq!(Some(a), scheduler.next_slice());
+        // `a` still has work left after its slice.
+        scheduler.queue_work(a);
+        assert_eq!(Some(b), scheduler.next_slice());
+        assert_eq!(Some(a), scheduler.next_slice());
+        assert_eq!(None, scheduler.next_slice());
+        assert!(scheduler.is_empty());
+
+        scheduler.queue_work(a);
+        scheduler.queue_work(b);
+        assert!(scheduler.remove(a));
+        assert!(!scheduler.remove(a));
+        assert_eq!(Some(b), scheduler.next_slice());
+        assert!(scheduler.is_empty());
+    }
+}
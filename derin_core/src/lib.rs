@@ -6,23 +6,51 @@
 
 use cgmath_geometry::cgmath;
 extern crate derin_common_types;
+#[macro_use]
+extern crate bitflags;
 
 #[macro_use]
 mod macros;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-helpers"))]
 #[macro_use]
 pub mod test_helpers;
 
+pub mod accessibility;
+pub mod activity;
+pub mod ambient;
+pub mod click;
+pub mod designer;
+pub mod display_list;
+pub mod drag;
+pub mod error;
+pub mod gesture;
 pub mod timer;
 #[macro_use]
 pub mod event;
+pub mod gpu_memory;
+pub mod handle;
+pub mod help;
+pub mod listener;
+pub mod modal;
+pub mod rate_limit;
+pub mod recovery;
 pub mod render;
+pub mod request;
+pub mod shortcut;
+pub mod sound;
+pub mod stats;
+pub mod task;
 pub mod widget;
 
+mod caret;
+mod damage;
+mod idle;
 mod mbseq;
 mod offset_widget;
 mod message_bus;
+mod occlusion;
+mod scratch;
 mod event_translator;
 mod update_state;
 mod widget_traverser;
@@ -31,13 +59,29 @@ use crate::cgmath::{Point2, Vector2, Bounded, EuclideanSpace};
 use cgmath_geometry::{D2, rect::{DimsBox, BoundBox, GeoBox}};
 
 use crate::{
-    message_bus::{MessageBus, MessageTarget},
-    event::{WidgetEvent, WidgetEventSourced},
+    accessibility::{AccessibilityPrefs, Announcement},
+    ambient::Opacity,
+    damage::Damage,
+    error::CoreError,
+    designer::{DesignOverlay, DesignSelection, DesignState},
+    drag::DragState,
+    click::ClickTracker,
+    gesture::GestureTracker,
+    gpu_memory::TexturesEvicted,
+    listener::ListenerId,
+    shortcut::{ShortcutError, ShortcutId, ShortcutScope},
+    message_bus::{MessageBus, MessageTarget, MessageTargeted},
+    occlusion::RenderedRect,
+    recovery::{Checkpoint, FrameError, catch_frame_panic},
+    scratch::FrameScratch,
+    sound::UiSound,
+    stats::FrameStats,
+    event::{FocusPolicy, PenInput, PenSample, ScrollPhase, WidgetEvent, WidgetEventSourced, SCROLL_LINE_HEIGHT},
     event_translator::EventTranslator,
     timer::{TimerTrigger, TimerTriggerTracker},
     widget::{
         *,
-        dynamic::{RenderError, RenderParameters},
+        dynamic::RenderParameters,
     },
     render::{Renderer},
     mbseq::MouseButtonSequenceTrackPos,
@@ -45,16 +89,19 @@ use crate::{
     widget_traverser::{Relation, WidgetPath, WidgetTraverser, WidgetTraverserBase},
 };
 use derin_common_types::{
-    buttons::{MouseButton, Key, ModifierKeys},
+    buttons::{MouseButton, Key, KeyChord, ModifierKeys},
     cursor::CursorIcon,
     layout::SizeBounds,
 };
 use std::{
+    any::TypeId,
+    mem,
     rc::Rc,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 const MAX_FRAME_UPDATE_ITERATIONS: usize = 256;
+const DEFAULT_FRAME_BUDGET: Duration = Duration::from_millis(16);
 
 fn find_index<T: PartialEq>(s: &[T], element: &T) -> Option<usize> {
     s.iter().enumerate().find(|&(_, e)| e == element).map(|(i, _)| i)
@@ -79,6 +126,12 @@ pub struct Root<N, R>
     timer_tracker: TimerTriggerTracker,
     message_bus: MessageBus,
     update_state: Rc<UpdateStateCell>,
+    frame_budget: Duration,
+    scratch: FrameScratch,
+    frame_error: Option<FrameError>,
+    errors: Vec<CoreError>,
+    /// The statistics of the frame currently being produced.
+    frame_stats: FrameStats,
 
     // User data
     pub root_widget: N,
@@ -86,16 +139,41 @@ pub struct Root<N, R>
     pub renderer: R,
 }
 
+#[derive(Clone)]
 struct InputState {
     mouse_pos: Option<Point2<i32>>,
     mouse_buttons_down: MouseButtonSequenceTrackPos,
     modifiers: ModifierKeys,
     keys_down: Vec<Key>,
+    /// The fractional pixels left over from the last `MouseScrollPxPrecise` event.
+    scroll_px_remainder: Vector2<f32>,
+    /// The number of pixels `MouseScrollLines` events scroll per line.
+    scroll_line_height: i32,
     mouse_hover_widget: Option<WidgetId>,
-    focused_widget: Option<WidgetId>
+    focused_widget: Option<WidgetId>,
+    focus_policy: FocusPolicy,
+    /// When the hovered widget should be given focus, under `FocusPolicy::FollowsHover`.
+    hover_focus_deadline: Option<Instant>,
+    /// Whether the next click requests help for the clicked widget instead of being dispatched.
+    help_mode: bool,
+    /// The selection and in-progress drag while in design mode. `None` outside of design mode.
+    design: Option<DesignState>,
+    /// The drag-and-drop operation in progress, if any.
+    drag: DragState,
+    /// The mouse press gestures are being recognized from.
+    gestures: GestureTracker,
+    /// The last mouse press, for counting double and triple clicks.
+    clicks: ClickTracker,
+    /// The widget the pen touched down in, which receives pen events until the pen is lifted.
+    pen_down_widget: Option<WidgetId>,
+    /// Pen movements that haven't been dispatched yet, which get coalesced into a single
+    /// `PenMove` event.
+    pen_moves: Vec<PenInput>,
+    /// The coalesced samples of the `PenMove` event currently being dispatched.
+    pen_history: Vec<PenSample>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WindowEvent {
     MouseMove(Point2<i32>),
     MouseEnter,
@@ -104,10 +182,38 @@ pub enum WindowEvent {
     MouseUp(MouseButton),
     MouseScrollLines(Vector2<i32>),
     MouseScrollPx(Vector2<i32>),
+    /// A pixel scroll delta from a trackpad or high-resolution wheel.
+    ///
+    /// Fractional pixels are carried over to the next scroll event, so slow scrolls aren't rounded
+    /// away.
+    MouseScrollPxPrecise {
+        delta: Vector2<f32>,
+        phase: ScrollPhase,
+    },
+    /// A pen touched the tablet.
+    PenDown(PenInput),
+    /// A pen moved while touching or hovering over the tablet, with its position in window
+    /// coordinates.
+    ///
+    /// Consecutive movements get coalesced into a single `WidgetEvent::PenMove`, which is
+    /// dispatched before the next non-`PenMove` event or at the end of the frame.
+    PenMove(PenInput),
+    /// A pen was lifted from the tablet.
+    PenUp(PenInput),
     WindowResize(DimsBox<D2, u32>),
     KeyDown(Key),
     KeyUp(Key),
     Char(char),
+    /// The input method's in-progress composition changed.
+    ///
+    /// `text` is the uncommitted text, which is empty once the composition is cancelled. `cursor`
+    /// is the byte offset of the input method's cursor within `text`, if it should be drawn.
+    ImePreedit {
+        text: String,
+        cursor: Option<usize>,
+    },
+    /// The input method committed the given text.
+    ImeCommit(String),
     Timer,
     Redraw
 }
@@ -132,14 +238,34 @@ pub struct FrameEventProcessor<'a, R>
     message_bus: &'a mut MessageBus,
     update_state: Rc<UpdateStateCell>,
     widget_traverser: WidgetTraverser<'a, R>,
+    scratch: &'a mut FrameScratch,
+    /// The time by which idle work has to be finished for the frame to stay within its budget.
+    idle_deadline: Instant,
+    frame_error: &'a mut Option<FrameError>,
+    /// Whether a widget panicked this frame, in which case the rest of the frame's events get
+    /// dropped.
+    aborted: bool,
 }
 
 #[must_use]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EventLoopResult {
+    /// The next time the event loop should wake up, even if no events arrive.
+    ///
+    /// If widgets still have idle work to do, this is the current time.
     pub next_timer: Option<Instant>,
     pub set_cursor_pos: Option<Point2<i32>>,
     pub set_cursor_icon: Option<CursorIcon>,
+    /// Where the text caret of the focused widget is, in window coordinates. The window should
+    /// place the input method's composition window next to it.
+    pub set_ime_caret_rect: Option<BoundBox<D2, i32>>,
+    /// Text to be read out by screen readers, in the order it was announced. See `Announcement`.
+    pub announcements: Vec<Announcement>,
+    /// Interface sounds to play, in the order they were requested.
+    pub sounds: Vec<UiSound>,
+    /// Whether a widget panicked and the frame was aborted. The panic can be retrieved with
+    /// `Root::frame_error`.
+    pub frame_aborted: bool,
 }
 
 impl InputState {
@@ -149,8 +275,20 @@ impl InputState {
             mouse_buttons_down: MouseButtonSequenceTrackPos::new(),
             modifiers: ModifierKeys::empty(),
             keys_down: Vec::new(),
+            scroll_px_remainder: Vector2::new(0.0, 0.0),
+            scroll_line_height: SCROLL_LINE_HEIGHT,
             mouse_hover_widget: None,
-            focused_widget: None
+            focused_widget: None,
+            focus_policy: FocusPolicy::default(),
+            hover_focus_deadline: None,
+            help_mode: false,
+            design: None,
+            drag: DragState::default(),
+            gestures: GestureTracker::new(),
+            clicks: ClickTracker::new(),
+            pen_down_widget: None,
+            pen_moves: Vec::new(),
+            pen_history: Vec::new(),
         }
     }
 }
@@ -174,41 +312,261 @@ impl<N, R> Root<N, R>
             timer_tracker: TimerTriggerTracker::new(),
             update_state: UpdateState::new(&message_bus),
             message_bus,
+            frame_budget: DEFAULT_FRAME_BUDGET,
+            scratch: FrameScratch::new(),
+            frame_error: None,
+            errors: Vec::new(),
+            frame_stats: FrameStats::default(),
 
             root_widget, theme, renderer,
         }
     }
 
+    /// Retrieves the user's accessibility preferences.
+    pub fn accessibility_prefs(&self) -> AccessibilityPrefs {
+        self.update_state.borrow().accessibility_prefs
+    }
+
+    /// Sets the user's accessibility preferences, redrawing and relaying out all widgets.
+    pub fn set_accessibility_prefs(&mut self, prefs: AccessibilityPrefs) {
+        let mut update_state = self.update_state.borrow_mut();
+        if update_state.accessibility_prefs != prefs {
+            update_state.accessibility_prefs = prefs;
+            update_state.queue_global_update();
+        }
+    }
+
+    /// Retrieves how keyboard focus follows the mouse.
+    pub fn focus_policy(&self) -> FocusPolicy {
+        self.input_state.focus_policy
+    }
+
+    /// Sets how keyboard focus follows the mouse. Defaults to `FocusPolicy::ClickToFocus`.
+    pub fn set_focus_policy(&mut self, policy: FocusPolicy) {
+        self.input_state.focus_policy = policy;
+        self.input_state.hover_focus_deadline = None;
+    }
+
+    /// Retrieves the longest time between two presses of a double click.
+    pub fn double_click_interval(&self) -> Duration {
+        self.input_state.clicks.interval()
+    }
+
+    /// Sets the longest time between two presses of a double click, as configured in the
+    /// operating system. Defaults to `click::DOUBLE_CLICK_INTERVAL`.
+    pub fn set_double_click_interval(&mut self, interval: Duration) {
+        self.input_state.clicks.set_interval(interval);
+    }
+
+    /// Retrieves the number of pixels scrolled by one line of mouse wheel movement.
+    pub fn scroll_line_height(&self) -> i32 {
+        self.input_state.scroll_line_height
+    }
+
+    /// Sets the number of pixels scrolled by one line of mouse wheel movement, which gets used to
+    /// fill in the `dir_px` field of `MouseScrollLines` events. Themes usually set this to match the
+    /// height of a line of text. Defaults to `event::SCROLL_LINE_HEIGHT`.
+    pub fn set_scroll_line_height(&mut self, line_height: i32) {
+        self.input_state.scroll_line_height = line_height;
+    }
+
+    /// Sets the time between the text caret showing and hiding, as configured in the operating
+    /// system. `None` disables blinking.
+    pub fn set_caret_blink_interval(&mut self, interval: Option<Duration>) {
+        let mut update_state = self.update_state.borrow_mut();
+        let caret_blink = update_state.caret_blink_mut();
+        caret_blink.interval = interval;
+        caret_blink.reset(Instant::now());
+    }
+
+    /// Sets how long the user has to go without giving input before `AppEvent::Idle` gets
+    /// broadcast. `None`, the default, disables idle detection. See the [`activity`] module for
+    /// details.
+    ///
+    /// [`activity`]: ./activity/index.html
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.update_state.borrow_mut().activity.timeout = timeout;
+    }
+
+    /// Retrieves whether the user's currently idle.
+    pub fn idle(&self) -> bool {
+        self.update_state.borrow().activity.idle()
+    }
+
+    /// Sets the function background tasks call to wake up the event loop when they finish, so
+    /// their results get delivered without waiting for another event.
+    pub fn set_task_wakeup(&mut self, wakeup: impl 'static + Fn() + Send) {
+        self.update_state.borrow_mut().tasks.set_wakeup(Box::new(wakeup));
+    }
+
+    /// Adds a listener that gets called with every event dispatched to a widget, along with the ID
+    /// of the widget recieving it.
+    ///
+    /// Listeners see events as the widget does, in widget-local coordinates. Events that bubble up
+    /// to a widget's parents are only reported to the widget they were originally dispatched to.
+    pub fn add_global_listener(&mut self, listener: impl 'static + Fn(&WidgetEvent, WidgetId)) -> ListenerId {
+        self.update_state.borrow_mut().global_listeners.add(Box::new(listener))
+    }
+
+    /// Removes a listener added with `add_global_listener`, returning whether it was found.
+    pub fn remove_global_listener(&mut self, listener_id: ListenerId) -> bool {
+        self.update_state.borrow_mut().global_listeners.remove(listener_id)
+    }
+
+    /// Binds `chord` to broadcast a clone of `message` whenever it's pressed within `scope`.
+    ///
+    /// See the [`shortcut`](./shortcut/index.html) module for more information.
+    pub fn register_shortcut<M>(&mut self, chord: KeyChord, scope: ShortcutScope, message: M) -> Result<ShortcutId, ShortcutError>
+        where M: 'static + Clone
+    {
+        self.update_state.borrow_mut().shortcuts_mut().add(chord, scope, Box::new(move || Box::new(message.clone())))
+    }
+
+    /// Removes a shortcut registered with `register_shortcut`, returning whether it was found.
+    pub fn remove_shortcut(&mut self, shortcut_id: ShortcutId) -> bool {
+        self.update_state.borrow_mut().shortcuts_mut().remove(shortcut_id)
+    }
+
+    /// Makes the given widget modal, restricting input to it and its descendants until it's
+    /// removed with `remove_modal`. If another widget is already modal, the new widget gets stacked
+    /// on top of it.
+    ///
+    /// See the [`modal`](./modal/index.html) module for more information.
+    pub fn push_modal(&mut self, widget_id: WidgetId) {
+        self.update_state.borrow_mut().modals_mut().push(widget_id);
+    }
+
+    /// Removes a widget from the modal stack, returning whether it was found.
+    pub fn remove_modal(&mut self, widget_id: WidgetId) -> bool {
+        self.update_state.borrow_mut().modals_mut().remove(widget_id)
+    }
+
+    /// Retrieves the widget currently receiving input, if any widget is modal.
+    pub fn modal(&self) -> Option<WidgetId> {
+        self.update_state.borrow().modals.top()
+    }
+
+    /// Sets how long a frame may take before idle work gets put off to the next frame. Defaults
+    /// to 16 milliseconds.
+    pub fn set_frame_budget(&mut self, budget: Duration) {
+        self.frame_budget = budget;
+    }
+
+    /// Retrieves whether design mode is enabled. See the [`designer`] module for details.
+    ///
+    /// [`designer`]: ./designer/index.html
+    pub fn design_mode(&self) -> bool {
+        self.input_state.design.is_some()
+    }
+
+    /// Enables or disables design mode, in which clicks select widgets instead of being delivered
+    /// to them. Disabling design mode clears the selection.
+    pub fn set_design_mode(&mut self, enabled: bool) {
+        if enabled != self.design_mode() {
+            self.input_state.design = match enabled {
+                true => Some(DesignState::default()),
+                false => None
+            };
+            self.update_state.borrow_mut().queue_global_update();
+        }
+    }
+
+    /// Retrieves the widget selected in design mode.
+    pub fn design_selection(&self) -> Option<WidgetId> {
+        self.input_state.design.as_ref().and_then(|d| d.selection)
+    }
+
+    /// Finds the widget with the given automation ID. See `WidgetTag::set_automation_id`.
+    pub fn find_automation_id(&mut self, automation_id: &str) -> Option<WidgetId> {
+        self.widget_traverser_base.with_root_ref(&mut self.root_widget, self.update_state.clone())
+            .find_automation_id(automation_id)
+    }
+
+    /// Retrieves a widget's rect, relative to the root widget's origin.
+    pub fn widget_rect(&mut self, widget_id: WidgetId) -> Option<BoundBox<D2, i32>> {
+        self.widget_traverser_base.with_root_ref(&mut self.root_widget, self.update_state.clone())
+            .get_widget(widget_id)
+            .map(|path| path.widget.rect())
+    }
+
+    /// Retrieves the panic that aborted the most recent bad frame, if it hasn't been cleared. See
+    /// the [`recovery`] module for details.
+    ///
+    /// [`recovery`]: ./recovery/index.html
+    pub fn frame_error(&self) -> Option<&FrameError> {
+        self.frame_error.as_ref()
+    }
+
+    /// Clears the stored frame error, returning it.
+    pub fn clear_frame_error(&mut self) -> Option<FrameError> {
+        self.frame_error.take()
+    }
+
+    /// Takes the errors that have occurred while laying out and rendering widgets since this was
+    /// last called. See the [`error`] module for details.
+    ///
+    /// [`error`]: ./error/index.html
+    pub fn take_errors(&mut self) -> Vec<CoreError> {
+        mem::replace(&mut self.errors, Vec::new())
+    }
+
     pub fn start_frame(&mut self) -> FrameEventProcessor<'_, R> {
         FrameEventProcessor {
+            idle_deadline: Instant::now() + self.frame_budget,
             input_state: &mut self.input_state,
             event_translator: &mut self.event_translator,
             timer_tracker: &mut self.timer_tracker,
             message_bus: &mut self.message_bus,
             update_state: self.update_state.clone(),
-            widget_traverser: self.widget_traverser_base.with_root_ref(&mut self.root_widget, self.update_state.clone())
+            widget_traverser: self.widget_traverser_base.with_root_ref(&mut self.root_widget, self.update_state.clone()),
+            scratch: &mut self.scratch,
+            frame_error: &mut self.frame_error,
+            aborted: false,
         }
     }
 
     pub fn relayout(&mut self) -> SizeBounds {
+        let layout_start = Instant::now();
         let mut widget_traverser = self.widget_traverser_base.with_root_ref(&mut self.root_widget, self.update_state.clone());
 
-        let mut relayout_widgets = Vec::new();
+        let FrameScratch {
+            ref mut relayout_widgets,
+            ref mut relayout_parent,
+            ref mut relayout_subtree,
+            ..
+        } = self.scratch;
+        relayout_widgets.clear();
+        relayout_parent.clear();
+        relayout_subtree.clear();
 
         let mut iter_num = 0;
         let global_update = self.update_state.borrow().global_update;
 
-        while global_update || self.update_state.borrow().relayout.len() > 0 {
-            match global_update {
-                false => relayout_widgets.extend(self.update_state.borrow_mut().relayout.drain()),
-                true => {
-                    self.update_state.borrow_mut().relayout.clear();
-                    relayout_widgets.extend(widget_traverser.all_widgets());
+        while global_update || self.update_state.borrow().requests.relayout.len() > 0 || relayout_widgets.len() > 0 {
+            {
+                let mut update_state = self.update_state.borrow_mut();
+                relayout_parent.extend(update_state.requests.relayout_parent.drain());
+                relayout_subtree.extend(update_state.requests.relayout_subtree.drain());
+                match global_update {
+                    false => relayout_widgets.extend(update_state.requests.relayout.drain()),
+                    true => {
+                        update_state.requests.relayout.clear();
+                        relayout_widgets.extend(widget_traverser.all_widgets());
+                    }
+                }
+            }
+            if !global_update {
+                for subtree_root in relayout_subtree.drain(..) {
+                    widget_traverser.crawl_widget_children(subtree_root, |path| relayout_widgets.push(path.widget_id));
                 }
             }
 
-            let valid_len = widget_traverser.sort_widgets_by_depth(&mut relayout_widgets).len();
+            let valid_len = widget_traverser.sort_widgets_by_depth(relayout_widgets).len();
             relayout_widgets.truncate(valid_len);
+            relayout_widgets.dedup();
+            let valid_len = relayout_widgets.len();
+            self.frame_stats.layout_iterations += 1;
+            self.frame_stats.widgets_laid_out += valid_len as u32;
 
             for i in 0..valid_len {
                 let widget_id = relayout_widgets[i];
@@ -220,6 +578,9 @@ impl<N, R> Root<N, R>
 
                 let old_widget_rect = widget.rect();
                 self.renderer.layout(widget.widget_id(), |layout| widget.update_layout(layout));
+                // Laying out a widget can move its children around, so make sure its region gets
+                // redrawn.
+                self.update_state.borrow_mut().requests.redraw.insert(widget_id);
                 let size_bounds = widget.size_bounds();
                 let new_widget_rect = widget.rect();
                 let widget_dims = new_widget_rect.dims();
@@ -229,9 +590,10 @@ impl<N, R> Root<N, R>
 
                 // If we're doing a global update, all widgets are in the relayout list so we don't
                 // need to queue the part for relayout. Otherwise, queue the parent for relayout if
-                // the widget's rect has changed or the widget's dimensions no longer fall in its size
-                // bounds.
+                // the widget's rect has changed, the widget's dimensions no longer fall in its size
+                // bounds, or the widget explicitly asked for its parent to be re-laid out.
                 let parent_needs_relayout =
+                    relayout_parent.remove(&widget_id) ||
                     dims_bounded != widget_dims ||
                     old_widget_rect != new_widget_rect;
 
@@ -261,21 +623,43 @@ impl<N, R> Root<N, R>
 
             iter_num += 1;
             if iter_num > MAX_FRAME_UPDATE_ITERATIONS {
-                // TODO: CHANGE TO LOG WARN
-                println!("WARNING: layout iterations happened unreasonable number of times");
+                self.errors.push(CoreError::LayoutDiverged);
                 break;
             }
         }
 
+        // Layout can move offscreen widgets back into view, so restart any of their paused timers.
+        let now = Instant::now();
+        for (widget_id, timer_id) in self.timer_tracker.take_paused() {
+            let widget = match widget_traverser.get_widget(widget_id) {
+                Some(wpath) => wpath.widget,
+                None => continue
+            };
+            let timer = match widget.widget_tag().timers.get(&timer_id) {
+                Some(timer) => timer,
+                None => continue
+            };
+            match timer.pause_offscreen && widget.rect_clipped().is_none() {
+                true => self.timer_tracker.pause(widget_id, timer_id),
+                false => {
+                    timer.resume(now);
+                    self.timer_tracker.queue_trigger(TimerTrigger::new(timer.next_trigger(), timer_id, widget_id));
+                }
+            }
+        }
+
         let root_id = widget_traverser.root_id();
         let root_widget = widget_traverser.get_widget(root_id).unwrap().widget;
+        self.frame_stats.layout_time += layout_start.elapsed();
         root_widget.size_bounds()
     }
 
     pub fn redraw(&mut self) {
+        let render_start = Instant::now();
         let root_rect = self.root_widget.rect();
         let new_dims = root_rect.dims().cast::<u32>().unwrap_or(DimsBox::new2(0, 0));
-        if new_dims != self.renderer.dims() {
+        let resized = new_dims != self.renderer.dims();
+        if resized {
             self.renderer.resized(new_dims);
         }
 
@@ -285,23 +669,102 @@ impl<N, R> Root<N, R>
             ref mut root_widget,
             ref theme,
             ref mut renderer,
+            ref input_state,
+            ref mut scratch,
+            ref mut errors,
+            ref message_bus,
+            ref mut frame_stats,
             ..
         } = *self;
 
         let mut update_state_ref = update_state.borrow_mut();
-        if update_state_ref.global_update || update_state_ref.redraw.len() > 0 {
-            // We should probably support incremental redraw at some point but not doing that is
-            // soooo much easier.
-            update_state_ref.redraw.clear();
+        if update_state_ref.global_update || update_state_ref.requests.redraw.len() > 0 || update_state_ref.requests.redraw_rects.len() > 0 {
+            // The design overlay isn't tracked as a widget, so moving it around needs a full redraw.
+            let full_redraw = update_state_ref.global_update || resized || input_state.design.is_some();
+            // Swap the queues into the scratch buffers, which leaves the old buffers' allocations in
+            // the update state for the next frame.
+            scratch.redraw_rects.clear();
+            mem::swap(&mut update_state_ref.requests.redraw_rects, &mut scratch.redraw_rects);
+            scratch.redraw_widgets.clear();
+            mem::swap(&mut update_state_ref.requests.redraw, &mut scratch.redraw_widgets);
             update_state_ref.reset_global_update();
             drop(update_state_ref);
 
+            let mut widget_traverser = widget_traverser_base.with_root_ref(root_widget, update_state.clone());
+
+            let mut damage = Damage::new();
+            for &(widget_id, rect) in &scratch.redraw_rects {
+                let widget = match widget_traverser.get_widget(widget_id) {
+                    Some(path) => path.widget,
+                    None => continue
+                };
+                let rect = rect + widget.rect().min().to_vec();
+                if let Some(rect) = widget.rect_clipped().and_then(|clip| clip.intersect_rect(rect)) {
+                    damage.add(rect);
+                }
+            }
+
+            // Gather every widget's rect. Widgets that asked to be redrawn, and widgets that have
+            // moved since the last frame, damage both their old and new rects. The rects are also
+            // used to find the widgets hidden behind opaque widgets, so we don't waste time drawing
+            // them.
+            scratch.rendered_rects.clear();
+            scratch.drawn_rects.start_frame();
+            {
+                let FrameScratch {
+                    ref mut rendered_rects,
+                    ref mut drawn_rects,
+                    ref redraw_widgets,
+                    ..
+                } = *scratch;
+                widget_traverser.crawl_widgets(|path| {
+                    let rect = path.widget.rect_clipped();
+                    let widget_tag = path.widget.widget_tag();
+                    rendered_rects.push(RenderedRect {
+                        rect,
+                        opaque: widget_tag.opaque() && widget_tag.ambient::<Opacity>().map(|o| o.0 >= 1.0).unwrap_or(true),
+                    });
+                    let widget_id = path.widget.widget_id();
+                    drawn_rects.update(widget_id, rect, redraw_widgets.contains(&widget_id), &mut damage);
+                });
+                drawn_rects.finish_frame(&mut damage);
+            }
+            occlusion::find_occluded(&scratch.rendered_rects, &mut scratch.opaque_rects, &mut scratch.occluded);
+
+            let damage_rect = match full_redraw {
+                true => None,
+                false => match damage.rect() {
+                    Some(damage_rect) => Some(damage_rect),
+                    // None of the damaged regions are visible, so there's nothing to redraw.
+                    None => return
+                }
+            };
+            // Renderers that don't keep the last frame's pixels need every widget drawn, even if
+            // only part of the window is damaged.
+            let render_all = damage_rect.is_none() || !renderer.preserves_undamaged();
+
+            renderer.set_damage(damage_rect);
             renderer.start_frame(theme);
             let window_rect = renderer.dims();
             let window_rect = BoundBox::new2(0, 0, window_rect.width() as i32, window_rect.height() as i32);
 
-            let mut widget_traverser = widget_traverser_base.with_root_ref(root_widget, update_state.clone());
+            let mut render_index = 0;
+            let occluded = &scratch.occluded;
             widget_traverser.crawl_widgets(|mut path| {
+                let is_occluded = occluded.get(render_index).cloned().unwrap_or(false);
+                render_index += 1;
+
+                // Widgets that are entirely clipped or outside the window don't need to be drawn.
+                // They'll get drawn again once they're scrolled back into view, since scrolling
+                // queues a redraw.
+                let rect_clipped = match path.widget.rect_clipped() {
+                    Some(rect) => rect,
+                    None => return
+                };
+                if is_occluded || !(render_all || damage.intersects(rect_clipped)) {
+                    return;
+                }
+
                 let render_parameters = RenderParameters {
                     renderer,
                     widget_id: path.widget.widget_id(),
@@ -310,14 +773,48 @@ impl<N, R> Root<N, R>
                     clip: path.widget.clip().unwrap_or(window_rect),
                 };
 
-                let result = path.widget.render(render_parameters);
-                match result {
-                    Ok(()) => (),
-                    Err(RenderError::ThemeNotSupported) => println!("WARNING: Attempted to render widget but renderer didn't support theme"),
-                    Err(RenderError::RendererNotSupported) => println!("WARNING: Attempted to render widget but widget didn't support renderer"),
+                let widget_id = path.widget.widget_id();
+                frame_stats.widgets_rendered += 1;
+                renderer.set_opacity(path.widget.widget_tag().ambient::<Opacity>().map(|o| o.0).unwrap_or(1.0));
+                renderer.set_enabled(path.widget.widget_tag().is_enabled());
+                if let Err(error) = path.widget.render(render_parameters) {
+                    errors.push(CoreError::Render{ widget_id, error });
                 }
             });
+
+            if let Some(ref design) = input_state.design {
+                let selection_rect = match design.drag {
+                    Some(drag) => Some(drag.new_rect),
+                    None => design.selection
+                        .and_then(|id| widget_traverser.get_widget(id))
+                        .map(|path| path.widget.rect())
+                };
+                if let Some(selection_rect) = selection_rect {
+                    renderer.render_design_overlay(&DesignOverlay::new(selection_rect));
+                }
+            }
             renderer.finish_frame(theme);
+
+            let evicted_owners = renderer.take_evicted_owners();
+            if evicted_owners.len() > 0 {
+                let update_state = update_state.borrow();
+                for owner in evicted_owners {
+                    update_state.message_sender.send(MessageTargeted {
+                        message: Box::new(TexturesEvicted),
+                        target: Some(MessageTarget::Widget(owner)),
+                    }).ok();
+                }
+            }
+
+            frame_stats.render_time = render_start.elapsed();
+            let mut frame_stats = mem::replace(frame_stats, FrameStats::default());
+            if message_bus.has_receivers(TypeId::of::<FrameStats>()) {
+                frame_stats.render = renderer.render_stats();
+                update_state.borrow().message_sender.send(MessageTargeted {
+                    message: Box::new(frame_stats),
+                    target: None,
+                }).ok();
+            }
         }
     }
 }
@@ -329,22 +826,57 @@ impl<R> FrameEventProcessor<'_, R>
         &mut self,
         event: WindowEvent,
     ) {
+        if self.aborted {
+            return;
+        }
+
         let FrameEventProcessor {
             ref mut input_state,
             ref mut event_translator,
             ref update_state,
             ref mut widget_traverser,
+            ref mut message_bus,
+            ref mut frame_error,
+            ref mut aborted,
             timer_tracker: _,
-            message_bus: _,
+            scratch: _,
+            idle_deadline: _,
         } = *self;
 
-        event_translator
-            .with_data(
-                widget_traverser,
-                input_state,
-                update_state.clone(),
-            )
-            .translate_window_event(event);
+        match event {
+            WindowEvent::WindowResize(_) |
+            WindowEvent::Timer |
+            WindowEvent::Redraw => (),
+            _ => {
+                let mut update_state = update_state.borrow_mut();
+                if let Some(app_event) = update_state.activity.input(Instant::now()) {
+                    update_state.message_sender.send(MessageTargeted {
+                        message: Box::new(app_event),
+                        target: None,
+                    }).ok();
+                }
+            }
+        }
+
+        let checkpoint = Checkpoint::new(input_state, &mut update_state.borrow_mut(), message_bus);
+        let result = catch_frame_panic(Some(event.clone()), || {
+            event_translator
+                .with_data(
+                    widget_traverser,
+                    input_state,
+                    update_state.clone(),
+                )
+                .translate_window_event(event)
+        });
+
+        match result {
+            Ok(()) => checkpoint.commit(&mut update_state.borrow_mut()),
+            Err(error) => {
+                checkpoint.restore(input_state, &mut update_state.borrow_mut(), message_bus);
+                **frame_error = Some(error);
+                *aborted = true;
+            }
+        }
     }
 
     pub fn set_modifiers(&mut self, modifiers: ModifierKeys) {
@@ -352,112 +884,342 @@ impl<R> FrameEventProcessor<'_, R>
     }
 
     pub fn finish(mut self) -> EventLoopResult {
-        {
-            let mut update_state = self.update_state.borrow_mut();
+        let mut checkpoint = Checkpoint::new(self.input_state, &mut self.update_state.borrow_mut(), self.message_bus);
+        let result = catch_frame_panic(None, || {
+            self.event_translator
+                .with_data(
+                    &mut self.widget_traverser,
+                    &mut *self.input_state,
+                    self.update_state.clone(),
+                )
+                .flush_pen_moves();
+
+            {
+                let mut update_state = self.update_state.borrow_mut();
+                // The requests made by the frame's events got moved into the checkpoint, and any
+                // made while flushing pen moves are in the update state.
+                let queued = checkpoint.requests_mut();
+
+                let UpdateState{ ref mut requests, ref mut idle_work, ref mut tasks, ref mut shortcuts, ref mut modals, ref message_sender, .. } = *update_state;
+                for remove_id in queued.remove_from_tree.drain().chain(requests.remove_from_tree.drain()) {
+                    self.widget_traverser.remove_widget(remove_id);
+                    self.message_bus.remove_widget(remove_id);
+                    Rc::make_mut(idle_work).remove(remove_id);
+                    tasks.cancel_widget(remove_id);
+                    Rc::make_mut(shortcuts).remove_scope(remove_id);
+                    Rc::make_mut(modals).remove(remove_id);
+                    if self.input_state.pen_down_widget == Some(remove_id) {
+                        self.input_state.pen_down_widget = None;
+                    }
 
-            for remove_id in update_state.remove_from_tree.drain() {
-                self.widget_traverser.remove_widget(remove_id);
-                self.message_bus.remove_widget(remove_id);
-            }
+                    if let Some(ref mut design) = self.input_state.design {
+                        if design.selection == Some(remove_id) {
+                            design.selection = None;
+                            design.drag = None;
+                            message_sender.send(MessageTargeted {
+                                message: Box::new(DesignSelection{ widget_id: None }),
+                                target: None,
+                            }).ok();
+                        }
+                    }
+                }
 
-            for widget_id in update_state.update_timers.drain() {
-                let widget = match self.widget_traverser.get_widget(widget_id) {
-                    Some(wpath) => wpath.widget,
-                    None => continue
-                };
+                for widget_id in queued.update_timers.drain().chain(update_state.requests.update_timers.drain()) {
+                    let widget = match self.widget_traverser.get_widget(widget_id) {
+                        Some(wpath) => wpath.widget,
+                        None => continue
+                    };
+
+                    for (&timer_id, timer) in &widget.widget_tag().timers {
+                        let trigger_time = timer.next_trigger();
+                        let trigger = TimerTrigger::new(trigger_time, timer_id, widget_id);
+                        self.timer_tracker.queue_trigger(trigger);
+                    }
+                }
+
+                for widget_id in queued.update_messages.drain().chain(update_state.requests.update_messages.drain()) {
+                    let widget = match self.widget_traverser.get_widget(widget_id) {
+                        Some(wpath) => wpath.widget,
+                        None => continue
+                    };
+
+                    let widget_tag = widget.widget_tag();
+                    for message_type in widget_tag.message_types() {
+                        self.message_bus.register_widget_message_type(message_type, widget_tag.widget_id);
+                    }
+                }
 
-                for (&timer_id, timer) in &widget.widget_tag().timers {
-                    let trigger_time = timer.next_trigger();
-                    let trigger = TimerTrigger::new(trigger_time, timer_id, widget_id);
+                if let Some(trigger) = self.input_state.gestures.take_trigger() {
                     self.timer_tracker.queue_trigger(trigger);
                 }
-            }
 
-            for widget_id in update_state.update_messages.drain() {
-                let widget = match self.widget_traverser.get_widget(widget_id) {
-                    Some(wpath) => wpath.widget,
-                    None => continue
-                };
+                if let Some(app_event) = update_state.activity.update(Instant::now()) {
+                    update_state.message_sender.send(MessageTargeted {
+                        message: Box::new(app_event),
+                        target: None,
+                    }).ok();
+                }
 
-                let widget_tag = widget.widget_tag();
-                for message_type in widget_tag.message_types() {
-                    self.message_bus.register_widget_message_type(message_type, widget_tag.widget_id);
+                // Deliver the results of any background tasks that have finished.
+                for (widget_id, message) in update_state.tasks.take_finished() {
+                    update_state.message_sender.send(MessageTargeted {
+                        message,
+                        target: Some(MessageTarget::Widget(widget_id)),
+                    }).ok();
                 }
             }
-        }
 
-        while let Some((message, widgets)) = self.message_bus.next_message() {
-            for message_target in widgets {
-                match message_target {
-                    MessageTarget::Widget(widget_id) => {
-                        match self.widget_traverser.get_widget(widget_id) {
-                            Some(mut wpath) => wpath.widget.inner_mut().dispatch_message(&*message),
-                            None => continue
-                        }
-                    },
-                    MessageTarget::ParentOf(widget_id) => {
-                        match self.widget_traverser.get_widget_relation(widget_id, Relation::Parent) {
-                            Some(mut wpath) => wpath.widget.inner_mut().dispatch_message(&*message),
-                            None => continue
+            while let Some((message, widgets)) = self.message_bus.next_message() {
+                for message_target in widgets {
+                    match message_target {
+                        MessageTarget::Widget(widget_id) => {
+                            match self.widget_traverser.get_widget(widget_id) {
+                                Some(mut wpath) => wpath.widget.inner_mut().dispatch_message(&*message),
+                                None => continue
+                            }
+                        },
+                        MessageTarget::ParentOf(widget_id) => {
+                            match self.widget_traverser.get_widget_relation(widget_id, Relation::Parent) {
+                                Some(mut wpath) => wpath.widget.inner_mut().dispatch_message(&*message),
+                                None => continue
+                            }
+                        },
+                        MessageTarget::ChildrenOf(widget_id) => {
+                            self.widget_traverser.crawl_widget_children(widget_id, |mut wpath| {
+                                wpath.widget.inner_mut().dispatch_message(&*message)
+                            });
                         }
-                    },
-                    MessageTarget::ChildrenOf(widget_id) => {
-                        self.widget_traverser.crawl_widget_children(widget_id, |mut wpath| {
-                            wpath.widget.inner_mut().dispatch_message(&*message)
-                        });
                     }
                 }
             }
-        }
 
-        // Send timer events
-        let timers_triggered = self.timer_tracker.timers_triggered().collect::<Vec<_>>();
-        for timer_trigger in timers_triggered {let _: Option<_> = try {
-            let mut widget = self.widget_traverser.get_widget(timer_trigger.widget_id)?.widget;
-
-            // Dispatch the widget event.
-            let timer = widget.widget_tag().timers.get(&timer_trigger.timer_id)?;
-            let event = WidgetEvent::Timer {
-                timer_id: timer_trigger.timer_id,
-                start_time: timer.start_time(),
-                last_triggered: timer.last_triggered(),
-                frequency: timer.frequency,
-                times_triggered: timer.times_triggered(),
-            };
-            let trigger_time = Instant::now();
-            // TODO: HANDLE OPS
-            widget.on_widget_event(WidgetEventSourced::This(event), self.input_state);
+            // Send timer events
+            self.scratch.timers_triggered.clear();
+            self.scratch.timers_triggered.extend(self.timer_tracker.timers_triggered());
+            for &timer_trigger in &self.scratch.timers_triggered {
+                // The long press timer belongs to the gesture tracker, not to a widget.
+                if timer_trigger.timer_id == self.input_state.gestures.long_press_timer() {
+                    let long_press = self.input_state.gestures.long_press(Instant::now());
+                    if let Some((widget_id, gesture)) = long_press {
+                        if let Some(mut wpath) = self.widget_traverser.get_widget(widget_id) {
+                            // TODO: HANDLE OPS
+                            wpath.widget.on_widget_event(WidgetEventSourced::This(WidgetEvent::Gesture(gesture)), self.input_state);
+                        }
+                    }
+                    continue;
+                }
 
+                let _: Option<_> = try {
+                    let mut widget = self.widget_traverser.get_widget(timer_trigger.widget_id)?.widget;
+
+                    // Timers that asked to be paused while their widget can't be seen don't get
+                    // re-queued until `relayout` finds the widget back in view.
+                    let timer = widget.widget_tag().timers.get(&timer_trigger.timer_id)?;
+                    if timer.pause_offscreen && widget.rect_clipped().is_none() {
+                        timer.pause(timer_trigger.instant);
+                        self.timer_tracker.pause(timer_trigger.widget_id, timer_trigger.timer_id);
+                    } else {
+                        let event = WidgetEvent::Timer {
+                            timer_id: timer_trigger.timer_id,
+                            start_time: timer.start_time(),
+                            last_triggered: timer.last_triggered(),
+                            frequency: timer.frequency,
+                            times_triggered: timer.times_triggered(),
+                        };
+                        let trigger_time = Instant::now();
+                        // TODO: HANDLE OPS
+                        widget.on_widget_event(WidgetEventSourced::This(event), self.input_state);
+
+                        // Update the timer's internal info values.
+                        let timer = widget.widget_tag().timers.get(&timer_trigger.timer_id)?;
+                        timer.times_triggered.set(timer.times_triggered.get() + 1);
+                        timer.last_triggered.set(Some(trigger_time));
+
+                        // Queue the next timer trigger.
+                        self.timer_tracker.queue_trigger(TimerTrigger {
+                            instant: timer.next_trigger(),
+                            ..timer_trigger
+                        });
+                    }
+                };
+            }
 
-            // Update the timer's internal info values.
-            let timer = widget.widget_tag().timers.get(&timer_trigger.timer_id)?;
-            timer.times_triggered.set(timer.times_triggered.get() + 1);
-            timer.last_triggered.set(Some(trigger_time));
+            // Spend whatever's left of the frame budget on idle work. Widgets that don't finish their
+            // work in one slice re-queue themselves, which hands the next slice to the next widget in
+            // line.
+            loop {
+                // Once the deadline's passed, any remaining work gets put off until the next frame.
+                if Instant::now() >= self.idle_deadline {
+                    break;
+                }
+                let widget_id = match self.update_state.borrow_mut().idle_work_mut().next_slice() {
+                    Some(widget_id) => widget_id,
+                    None => break
+                };
 
-            // Queue the next timer trigger.
-            self.timer_tracker.queue_trigger(TimerTrigger {
-                instant: timer.next_trigger(),
-                ..timer_trigger
-            });
-        };}
+                let mut widget = match self.widget_traverser.get_widget(widget_id) {
+                    Some(wpath) => wpath.widget,
+                    None => continue
+                };
+                let event = WidgetEvent::IdleWork {
+                    deadline: self.idle_deadline,
+                };
+                // TODO: HANDLE OPS
+                widget.on_widget_event(WidgetEventSourced::This(event), self.input_state);
+            }
+        });
+        match result {
+            Ok(()) => checkpoint.commit(&mut self.update_state.borrow_mut()),
+            Err(error) => {
+                checkpoint.restore(self.input_state, &mut self.update_state.borrow_mut(), self.message_bus);
+                *self.frame_error = Some(error);
+                self.aborted = true;
+            }
+        }
 
         let mut update_state = self.update_state.borrow_mut();
         let widget_traverser = &mut self.widget_traverser;
-        let set_cursor_icon = update_state.set_cursor_icon.take();
+        let set_cursor_icon = update_state.requests.set_cursor_icon.take();
+        let announcements = update_state.requests.announcements.drain(..).collect();
+        let sounds = update_state.requests.sounds.drain(..).collect();
+
+        // Update the widgets displaying a caret if the caret's toggled since the last frame.
+        let now = Instant::now();
+        let reduced_motion = update_state.accessibility_prefs.reduced_motion;
+        if update_state.caret_blink_mut().update(now, reduced_motion) {
+            let UpdateState{ ref mut requests, ref caret_blink, .. } = *update_state;
+            requests.redraw.extend(caret_blink.widgets.iter().cloned());
+            requests.relayout.extend(caret_blink.widgets.iter().cloned());
+        }
+        let next_caret_toggle = update_state.caret_blink.next_toggle(now, reduced_motion);
 
-        // The cursor position stored in `UpdateState.set_cursor_pos` is relative to the requesting
+        // The cursor position stored in `UpdateRequests.set_cursor_pos` is relative to the requesting
         // widget's origin. This translates it into window-space.
-        let set_cursor_pos = update_state.set_cursor_pos.take()
+        let set_cursor_pos = update_state.requests.set_cursor_pos.take()
             .and_then(|(widget_id, offset_pos)|
                 widget_traverser.get_widget(widget_id)
                     .map(|wpath| wpath.widget.rect().min + offset_pos.to_vec())
             );
+        // Same goes for `UpdateRequests.set_ime_caret_rect`.
+        let set_ime_caret_rect = update_state.requests.set_ime_caret_rect.take()
+            .and_then(|(widget_id, rect)|
+                widget_traverser.get_widget(widget_id)
+                    .map(|wpath| rect + wpath.widget.rect().min.to_vec())
+            );
 
 
+        let next_timer = [
+            self.timer_tracker.next_trigger(),
+            next_caret_toggle,
+            update_state.activity.next_idle(),
+            self.input_state.hover_focus_deadline,
+        ]
+            .iter().cloned()
+            .filter_map(|t| t)
+            .min();
+
         EventLoopResult {
-            next_timer: self.timer_tracker.next_trigger(),
+            next_timer: match update_state.idle_work.is_empty() {
+                true => next_timer,
+                false => Some(now)
+            },
             set_cursor_pos,
             set_cursor_icon,
+            set_ime_caret_rect,
+            announcements,
+            sounds,
+            frame_aborted: self.aborted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records whether each widget was enabled when it got rendered, in rendering order.
+    struct EnabledRenderer {
+        dims: DimsBox<D2, u32>,
+        enabled: Vec<bool>,
+    }
+
+    impl Renderer for EnabledRenderer {
+        type SubFrame = !;
+        type Theme = ();
+        type Layout = !;
+
+        fn resized(&mut self, new_size: DimsBox<D2, u32>) {
+            self.dims = new_size;
+        }
+        fn dims(&self) -> DimsBox<D2, u32> {
+            self.dims
+        }
+        fn widget_removed(&mut self, _: WidgetId) {}
+        fn layout(&mut self, _: WidgetId, _: impl FnOnce(&mut !)) {}
+        fn start_frame(&mut self, _: &()) {
+            self.enabled.clear();
+        }
+        fn set_enabled(&mut self, enabled: bool) {
+            self.enabled.push(enabled);
         }
+        fn finish_frame(&mut self, _: &()) {}
+    }
+
+    #[test]
+    fn render_disabled_subtree() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 40, 40);
+                a { rect: (0, 0, 10, 10) },
+                b {
+                    rect: (10, 10, 30, 30);
+                    c { rect: (0, 0, 10, 10) }
+                }
+            };
+        }
+        tree.children.as_mut().unwrap()[&WidgetIdent::new_str("b")].widget_tag.set_enabled(false);
+
+        let renderer = EnabledRenderer {
+            dims: DimsBox::new2(0, 0),
+            enabled: Vec::new(),
+        };
+        let mut root = Root::new(tree, (), renderer, DimsBox::new2(40, 40));
+        root.relayout();
+        root.redraw();
+
+        // `c` gets drawn disabled along with `b`, since it inherits `b`'s `Enabled` value.
+        assert_eq!(vec![true, true, false, false], root.renderer.enabled);
+    }
+
+    #[test]
+    fn announcements() {
+        use crate::accessibility::Politeness;
+
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let tree = root {
+                rect: (0, 0, 20, 20);
+                a { rect: (0, 0, 10, 10) }
+            };
+        }
+
+        let renderer = EnabledRenderer {
+            dims: DimsBox::new2(0, 0),
+            enabled: Vec::new(),
+        };
+        let mut root = Root::new(tree, (), renderer, DimsBox::new2(20, 20));
+        root.relayout();
+
+        root.root_widget.children.as_mut().unwrap()[&WidgetIdent::new_str("a")].widget_tag
+            .announce("3 results", Politeness::Polite).unwrap();
+        assert_eq!(
+            vec![Announcement {
+                text: "3 results".to_string(),
+                politeness: Politeness::Polite,
+                widget_id: a,
+            }],
+            root.start_frame().finish().announcements
+        );
     }
 }
@@ -0,0 +1,91 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Application-level listeners that observe every event dispatched to widgets.
+//!
+//! Listeners are added with `Root::add_global_listener`. Since they don't require any changes to
+//! the widgets being observed, they're useful for analytics, interactive tutorials, and debugging.
+//! Listeners only get read-only access to events, and can't stop them from being delivered.
+
+use crate::{
+    event::WidgetEvent,
+    widget::WidgetId,
+};
+use std::fmt;
+
+id!(pub ListenerId);
+
+pub(crate) struct GlobalListeners {
+    listeners: Vec<(ListenerId, Box<Fn(&WidgetEvent, WidgetId)>)>,
+}
+
+impl GlobalListeners {
+    pub fn new() -> GlobalListeners {
+        GlobalListeners {
+            listeners: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, listener: Box<Fn(&WidgetEvent, WidgetId)>) -> ListenerId {
+        let listener_id = ListenerId::new();
+        self.listeners.push((listener_id, listener));
+        listener_id
+    }
+
+    /// Remove the listener with the given ID, returning whether it was found.
+    pub fn remove(&mut self, listener_id: ListenerId) -> bool {
+        let len = self.listeners.len();
+        self.listeners.retain(|(id, _)| *id != listener_id);
+        self.listeners.len() != len
+    }
+
+    /// Call every listener with the given event, in the order they were added.
+    pub fn notify(&self, event: &WidgetEvent, widget_id: WidgetId) {
+        for (_, listener) in &self.listeners {
+            listener(event, widget_id);
+        }
+    }
+}
+
+impl fmt::Debug for GlobalListeners {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.listeners.iter().map(|(id, _)| id))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn add_and_remove() {
+        let mut listeners = GlobalListeners::new();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let widget_id = WidgetId::new();
+
+        let first = {
+            let seen = seen.clone();
+            listeners.add(Box::new(move |event, id| seen.borrow_mut().push((1, event.clone(), id))))
+        };
+        {
+            let seen = seen.clone();
+            listeners.add(Box::new(move |event, id| seen.borrow_mut().push((2, event.clone(), id))));
+        }
+
+        listeners.notify(&WidgetEvent::LoseFocus, widget_id);
+        assert_eq!(
+            vec![(1, WidgetEvent::LoseFocus, widget_id), (2, WidgetEvent::LoseFocus, widget_id)],
+            *seen.borrow()
+        );
+
+        seen.borrow_mut().clear();
+        assert!(listeners.remove(first));
+        assert!(!listeners.remove(first));
+        listeners.notify(&WidgetEvent::LoseFocus, widget_id);
+        assert_eq!(vec![(2, WidgetEvent::LoseFocus, widget_id)], *seen.borrow());
+    }
+}
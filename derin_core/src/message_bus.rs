@@ -6,6 +6,7 @@ use crate::widget::WidgetId;
 use fnv::{FnvHashMap, FnvHashSet};
 use std::{
     any::{Any, TypeId},
+    collections::VecDeque,
     sync::mpsc::{self, Sender, Receiver},
 };
 
@@ -48,6 +49,9 @@ pub struct MessageBus {
     type_map: FnvHashMap<TypeId, FnvHashSet<WidgetId>>,
     messages_recv: Receiver<MessageTargeted>,
     messages_send: Sender<MessageTargeted>,
+    /// Messages pulled out of the channel by `commit`, which get delivered before any messages
+    /// still in the channel.
+    committed: VecDeque<MessageTargeted>,
 }
 
 #[derive(Debug)]
@@ -69,6 +73,7 @@ impl MessageBus {
         MessageBus {
             type_map: FnvHashMap::default(),
             messages_recv, messages_send,
+            committed: VecDeque::new(),
         }
     }
 
@@ -77,7 +82,9 @@ impl MessageBus {
     }
 
     pub fn next_message(&mut self) -> Option<(Message, impl '_ + Iterator<Item=MessageTarget>)> {
-        while let Ok(MessageTargeted{message, target}) = self.messages_recv.try_recv() {
+        let messages_recv = &self.messages_recv;
+        let next = self.committed.pop_front().or_else(|| messages_recv.try_recv().ok());
+        if let Some(MessageTargeted{message, target}) = next {
             // We have to dereference `message` here because otherwise it would get the TypeId of
             // `Box<Any>`, not the inner `Any`.
             let type_id = (*message).type_id();
@@ -101,10 +108,25 @@ impl MessageBus {
         None
     }
 
+    /// Mark every message sent so far as committed, so they survive `discard_uncommitted`.
+    pub fn commit(&mut self) {
+        self.committed.extend(self.messages_recv.try_iter());
+    }
+
+    /// Drop every message sent since the last call to `commit`.
+    pub fn discard_uncommitted(&mut self) {
+        self.messages_recv.try_iter().for_each(drop);
+    }
+
     pub fn register_widget_message_type(&mut self, message_type: TypeId, widget_id: WidgetId) {
         self.type_map.entry(message_type).or_default().insert(widget_id);
     }
 
+    /// Whether any widget has registered a handler for messages of the given type.
+    pub fn has_receivers(&self, message_type: TypeId) -> bool {
+        self.type_map.get(&message_type).map(|wids| wids.len() > 0).unwrap_or(false)
+    }
+
     pub fn remove_widget(&mut self, widget_id: WidgetId) {
         for wid_vec in self.type_map.values_mut() {
             wid_vec.retain(|id| *id != widget_id);
@@ -186,4 +208,18 @@ mod tests {
             MessageTarget::ChildrenOf(a),
         );
     }
+
+    #[test]
+    fn has_receivers() {
+        let a = WidgetId::new();
+        let mut message_bus = MessageBus::new();
+
+        assert!(!message_bus.has_receivers(TypeId::of::<MessageA>()));
+        message_bus.register_widget_message_type(TypeId::of::<MessageA>(), a);
+        assert!(message_bus.has_receivers(TypeId::of::<MessageA>()));
+        assert!(!message_bus.has_receivers(TypeId::of::<MessageB>()));
+
+        message_bus.remove_widget(a);
+        assert!(!message_bus.has_receivers(TypeId::of::<MessageA>()));
+    }
 }
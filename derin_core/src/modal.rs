@@ -0,0 +1,194 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Modal widgets, which block input to the rest of the widget tree.
+//!
+//! While a widget is modal, input only gets delivered to it and its descendants. Clicks and key
+//! presses aimed at other widgets get dropped, the mouse can't hover over them, keyboard focus
+//! can't be moved to them, and shortcuts scoped to them are disabled. Widgets outside of the modal
+//! still receive the events they need to clean up after interactions that were in progress when
+//! the modal was pushed, such as `MouseUp` and `LoseFocus`, along with their timers.
+//!
+//! Modal widgets form a stack, so a dialog can open another dialog; only the widget on top of the
+//! stack receives input. Pushing a widget onto the stack moves focus into it, and removing it from
+//! the stack gives focus back to the widget that had it before. Widgets get pushed with
+//! `Root::push_modal` or `WidgetTag::push_modal`, and are removed from the stack automatically
+//! when they're removed from the widget tree.
+//!
+//! Modality only affects input. Widgets that want to dim the rest of the window, like `derin`'s
+//! `Dialog`, have to draw that themselves.
+
+use crate::{
+    event::WidgetEvent,
+    widget::{WidgetId, WidgetIdent},
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct ModalStack {
+    modals: Vec<Modal>,
+    /// Set when the modal on top of the stack gets removed, to the widget that should get focus
+    /// back.
+    restore_focus: Option<Option<WidgetId>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Modal {
+    widget_id: WidgetId,
+    /// Whether focus has been moved into the modal.
+    entered: bool,
+    /// The widget that had focus before the modal was entered.
+    restore_focus: Option<WidgetId>,
+}
+
+impl ModalStack {
+    pub fn new() -> ModalStack {
+        ModalStack {
+            modals: Vec::new(),
+            restore_focus: None,
+        }
+    }
+
+    /// The widget currently receiving input, if any widget is modal.
+    pub fn top(&self) -> Option<WidgetId> {
+        self.modals.last().map(|m| m.widget_id)
+    }
+
+    pub fn contains(&self, widget_id: WidgetId) -> bool {
+        self.modals.iter().any(|m| m.widget_id == widget_id)
+    }
+
+    /// Push `widget_id` onto the stack. A widget that's already on the stack gets moved to the top.
+    pub fn push(&mut self, widget_id: WidgetId) {
+        self.modals.retain(|m| m.widget_id != widget_id);
+        self.modals.push(Modal {
+            widget_id,
+            entered: false,
+            restore_focus: None,
+        });
+    }
+
+    /// Remove `widget_id` from the stack, returning whether it was found.
+    pub fn remove(&mut self, widget_id: WidgetId) -> bool {
+        let index = match self.modals.iter().position(|m| m.widget_id == widget_id) {
+            Some(index) => index,
+            None => return false
+        };
+        let modal = self.modals.remove(index);
+        if index == self.modals.len() && modal.entered {
+            self.restore_focus = Some(modal.restore_focus);
+        }
+        true
+    }
+
+    /// Take the widget that should get focus back after the top modal was removed.
+    pub fn take_restore_focus(&mut self) -> Option<Option<WidgetId>> {
+        self.restore_focus.take()
+    }
+
+    /// Retrieve the modal widget on top of the stack if focus hasn't been moved into it yet,
+    /// marking it as entered.
+    pub fn enter_top(&mut self) -> Option<WidgetId> {
+        match self.modals.last_mut() {
+            Some(modal) if !modal.entered => {
+                modal.entered = true;
+                Some(modal.widget_id)
+            },
+            _ => None
+        }
+    }
+
+    /// Set the widget that gets focus back once the top modal widget is removed.
+    pub fn set_restore_focus(&mut self, widget_id: Option<WidgetId>) {
+        if let Some(modal) = self.modals.last_mut() {
+            modal.restore_focus = widget_id;
+        }
+    }
+}
+
+/// Whether `event` still gets delivered to widgets outside of the modal widget, since they need it
+/// to clean up after an interaction that was in progress when the modal was pushed. Disabled
+/// widgets get the same events, for the same reason.
+pub(crate) fn finishes_interaction(event: &WidgetEvent) -> bool {
+    match *event {
+        WidgetEvent::MouseUp{..} |
+        WidgetEvent::PenUp{..} |
+        WidgetEvent::DragCancel |
+        WidgetEvent::LoseFocus |
+        WidgetEvent::Timer{..} |
+        WidgetEvent::IdleWork{..} => true,
+        _ => false
+    }
+}
+
+/// Whether the widget at `path` is inside of the modal widget at `modal_path`.
+pub(crate) fn in_modal(modal_path: Option<&[WidgetIdent]>, path: &[WidgetIdent]) -> bool {
+    match modal_path {
+        Some(modal_path) => path.starts_with(modal_path),
+        None => true
+    }
+}
+
+/// Whether the mouse can hover over the widget at `path`. The mouse can enter the modal widget's
+/// ancestors, so that it can reach the modal widget.
+pub(crate) fn can_hover(modal_path: Option<&[WidgetIdent]>, path: &[WidgetIdent]) -> bool {
+    match modal_path {
+        Some(modal_path) if path.len() < modal_path.len() => modal_path.starts_with(path),
+        _ => in_modal(modal_path, path)
+    }
+}
+
+/// Whether the mouse can hover over the child `child_ident` of the widget at `path`.
+pub(crate) fn can_hover_child(modal_path: Option<&[WidgetIdent]>, path: &[WidgetIdent], child_ident: &WidgetIdent) -> bool {
+    match modal_path {
+        Some(modal_path) if path.len() < modal_path.len() =>
+            modal_path.starts_with(path) && modal_path[path.len()] == *child_ident,
+        _ => in_modal(modal_path, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_focus() {
+        let (a, b, focused) = (WidgetId::new(), WidgetId::new(), WidgetId::new());
+        let mut stack = ModalStack::new();
+
+        stack.push(a);
+        assert_eq!(Some(a), stack.enter_top());
+        stack.set_restore_focus(Some(focused));
+        assert_eq!(None, stack.enter_top());
+        stack.push(b);
+        assert_eq!(Some(b), stack.top());
+
+        // Removing a modal that was never entered doesn't move focus.
+        assert!(stack.remove(b));
+        assert_eq!(None, stack.take_restore_focus());
+        assert!(!stack.remove(b));
+
+        assert!(stack.remove(a));
+        assert_eq!(Some(Some(focused)), stack.take_restore_focus());
+        assert_eq!(None, stack.top());
+    }
+
+    #[test]
+    fn hover_paths() {
+        let ident = |s| WidgetIdent::new_str(s);
+        let modal_path = [ident("root"), ident("a"), ident("b")];
+        let modal_path = Some(&modal_path[..]);
+
+        assert!(can_hover_child(modal_path, &[ident("root")], &ident("a")));
+        assert!(!can_hover_child(modal_path, &[ident("root")], &ident("c")));
+        assert!(can_hover_child(modal_path, &[ident("root"), ident("a")], &ident("b")));
+        assert!(can_hover_child(modal_path, &[ident("root"), ident("a"), ident("b")], &ident("c")));
+        assert!(!can_hover_child(modal_path, &[ident("root"), ident("c")], &ident("b")));
+        assert!(can_hover_child(None, &[ident("root")], &ident("c")));
+        assert!(can_hover(modal_path, &[ident("root"), ident("a")]));
+        assert!(!can_hover(modal_path, &[ident("root"), ident("c")]));
+
+        assert!(in_modal(modal_path, &[ident("root"), ident("a"), ident("b"), ident("c")]));
+        assert!(!in_modal(modal_path, &[ident("root"), ident("a")]));
+    }
+}
@@ -0,0 +1,87 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use cgmath_geometry::{D2, rect::{BoundBox, GeoBox}};
+
+/// A widget's visible rect, as gathered before rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RenderedRect {
+    /// The widget's rect after clipping. `None` if the widget can't be seen.
+    pub rect: Option<BoundBox<D2, i32>>,
+    /// Whether the widget fully covers its rect with opaque pixels.
+    pub opaque: bool,
+}
+
+fn contains_rect(outer: BoundBox<D2, i32>, inner: BoundBox<D2, i32>) -> bool {
+    outer.min().x <= inner.min().x &&
+    outer.min().y <= inner.min().y &&
+    inner.max().x <= outer.max().x &&
+    inner.max().y <= outer.max().y
+}
+
+/// Determine which widgets are hidden behind opaque widgets rendered after them.
+///
+/// `rects` is in rendering order. After this returns, `occluded[i]` is `true` if `rects[i]` lies
+/// entirely inside of a single opaque rect later in the list, in which case drawing it would be
+/// wasted work.
+pub(crate) fn find_occluded(
+    rects: &[RenderedRect],
+    opaque_rects: &mut Vec<BoundBox<D2, i32>>,
+    occluded: &mut Vec<bool>
+) {
+    opaque_rects.clear();
+    occluded.clear();
+    occluded.resize(rects.len(), false);
+
+    // Walk backwards through the render order, so that `opaque_rects` holds every opaque rect
+    // drawn on top of the current widget.
+    for (index, rendered) in rects.iter().enumerate().rev() {
+        let rect = match rendered.rect {
+            Some(rect) => rect,
+            None => continue
+        };
+
+        if opaque_rects.iter().any(|&opaque| contains_rect(opaque, rect)) {
+            occluded[index] = true;
+        // An occluded opaque rect is already covered by the rect occluding it, so there's no
+        // point in tracking it.
+        } else if rendered.opaque {
+            opaque_rects.push(rect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(rect: (i32, i32, i32, i32), opaque: bool) -> RenderedRect {
+        RenderedRect {
+            rect: Some(BoundBox::new2(rect.0, rect.1, rect.2, rect.3)),
+            opaque,
+        }
+    }
+
+    #[test]
+    fn occlusion() {
+        let rects = [
+            // Background, covered by the maximized panel.
+            rendered((0, 0, 100, 100), true),
+            // Background decoration, also covered.
+            rendered((10, 10, 20, 20), false),
+            // Only partially covered by the panel.
+            rendered((-10, 0, 10, 10), false),
+            // Maximized panel.
+            rendered((0, 0, 100, 100), true),
+            // Translucent overlay over the panel. Doesn't hide anything.
+            rendered((0, 0, 100, 100), false),
+            // Not visible at all.
+            RenderedRect{ rect: None, opaque: true },
+        ];
+
+        let (mut opaque_rects, mut occluded) = (Vec::new(), Vec::new());
+        find_occluded(&rects, &mut opaque_rects, &mut occluded);
+        assert_eq!(vec![true, true, false, false, false, false], occluded);
+    }
+}
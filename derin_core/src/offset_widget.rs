@@ -8,7 +8,7 @@ use crate::{
         WidgetDyn, WidgetId, WidgetIdent, WidgetTag,
         dynamic::{RenderParameters, RenderError},
     },
-    event::{InputState as EventInputState, WidgetEventSourced, EventOps},
+    event::{InputState as EventInputState, WidgetCoords, WidgetEvent, WidgetEventSourced, EventOps},
     render::{Renderer},
 };
 
@@ -67,6 +67,14 @@ impl<'a, R: Renderer> OffsetWidget<'a, R> {
     pub fn rect_clipped(&self) -> Option<BoundBox<D2, i32>> {
         self.clip.and_then(|clip_rect| clip_rect.intersect_rect(self.rect()))
     }
+    /// The widget's window-space position, for converting between window and widget-local
+    /// coordinates.
+    pub fn coords(&self) -> WidgetCoords {
+        WidgetCoords {
+            window_rect: self.rect(),
+            clip: self.clip,
+        }
+    }
     pub fn set_rect(&mut self, rect: BoundBox<D2, i32>) {
         *self.widget.rect_mut() = rect - self.offset;
     }
@@ -78,6 +86,35 @@ impl<'a, R: Renderer> OffsetWidget<'a, R> {
         event: WidgetEventSourced,
         input_state: &InputState,
     ) -> EventOps
+    {
+        self.with_input_state(input_state, |widget, input_state| {
+            let event = event.map(|e| e.translate(-input_state.coords.window_rect.min().to_vec()));
+            if let WidgetEventSourced::This(ref event) = event {
+                widget.widget_tag().notify_global_listeners(event);
+            }
+            widget.on_widget_event(event, input_state)
+        })
+    }
+
+    pub fn intercept_child_event(
+        &mut self,
+        event: &WidgetEvent,
+        child_path: &[WidgetIdent],
+        input_state: &InputState,
+    ) -> bool
+    {
+        self.with_input_state(input_state, |widget, input_state| {
+            let event = event.clone().translate(-input_state.coords.window_rect.min().to_vec());
+            widget.intercept_child_event(&event, child_path, input_state)
+        })
+    }
+
+    /// Call `f` with the widget and the input state, translated into the widget's coordinate space.
+    fn with_input_state<T>(
+        &mut self,
+        input_state: &InputState,
+        f: impl FnOnce(&mut WidgetDyn<R>, EventInputState) -> T
+    ) -> T
     {
         let InputState {
             mouse_pos,
@@ -86,27 +123,24 @@ impl<'a, R: Renderer> OffsetWidget<'a, R> {
             modifiers,
             ..
         } = input_state;
-        let offset = self.rect().min().to_vec();
+        let coords = self.coords();
         let mbd_array: ArrayVec<[_; 5]> = mouse_buttons_down.clone().into_iter()
             .map(|down| down.mouse_down)
             .map(|mut down| {
-                down.down_pos -= offset;
+                down.down_pos = coords.window_to_local_point(down.down_pos);
                 down
             }).collect();
         let mbdin_array: ArrayVec<[(); 5]> = ArrayVec::new(); //TODO: GET ACTUAL VALUES
 
         let input_state = EventInputState {
-            mouse_pos: mouse_pos.map(|p| p - offset),
+            mouse_pos: mouse_pos.map(|p| coords.window_to_local_point(p)),
             modifiers: *modifiers,
             mouse_buttons_down: &mbd_array[..],
             mouse_buttons_down_in_widget: &mbd_array[..],
-            keys_down
+            keys_down,
+            coords,
         };
-        let ops = self.widget.on_widget_event(
-            event.map(|e| e.translate(-offset)),
-            input_state,
-        );
-        ops
+        f(self.widget, input_state)
     }
     // pub fn subtrait(&self) -> WidgetSubtrait<R>;
     // pub fn subtrait_mut(&mut self) -> WidgetSubtraitMut<R>;
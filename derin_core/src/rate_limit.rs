@@ -0,0 +1,295 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Helpers for limiting how often a widget broadcasts messages.
+//!
+//! Some widgets produce messages far faster than their consumers need them: a search box emits a
+//! message on every keystroke, and a slider on every pixel the mouse moves. A widget can route its
+//! messages through one of these helpers instead of broadcasting them directly:
+//!
+//! * [`Debounce`] waits until no messages have been pushed for a while, then broadcasts the last one.
+//! * [`Throttle`] broadcasts at most one message per interval, always including the last one.
+//! * [`DistinctUntilChanged`] drops messages that are equal to the last one broadcast.
+//!
+//! `Debounce` and `Throttle` are built on the widget's timers. They register a timer in the
+//! widget's `WidgetTag` when they have a message waiting, so the widget must pass its
+//! `WidgetEvent::Timer` events to their `handle_timer` functions.
+//!
+//! [`Debounce`]: ./struct.Debounce.html
+//! [`Throttle`]: ./struct.Throttle.html
+//! [`DistinctUntilChanged`]: ./struct.DistinctUntilChanged.html
+
+use crate::{
+    timer::{Timer, TimerId},
+    widget::WidgetTag,
+};
+use std::time::{Duration, Instant};
+
+/// Broadcasts a message once no new messages have been pushed for the given delay.
+#[derive(Debug, Clone)]
+pub struct Debounce<M> {
+    timer_id: TimerId,
+    delay: Duration,
+    pending: Option<M>,
+    deadline: Option<Instant>,
+}
+
+/// Broadcasts at most one message per interval.
+///
+/// The first message pushed after a quiet period gets broadcast immediately. Messages pushed
+/// during the following interval are held back, and the last of them gets broadcast once the
+/// interval ends.
+#[derive(Debug, Clone)]
+pub struct Throttle<M> {
+    timer_id: TimerId,
+    interval: Duration,
+    pending: Option<M>,
+    window_end: Option<Instant>,
+}
+
+/// Drops messages equal to the last message broadcast.
+#[derive(Debug, Clone)]
+pub struct DistinctUntilChanged<M> {
+    last: Option<M>,
+}
+
+impl<M: 'static> Debounce<M> {
+    pub fn new(delay: Duration) -> Debounce<M> {
+        Debounce {
+            timer_id: TimerId::new(),
+            delay,
+            pending: None,
+            deadline: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Whether there's a message waiting to be broadcast.
+    #[inline(always)]
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Queue `message`, replacing any message that's already waiting and restarting the delay.
+    pub fn push(&mut self, message: M, widget_tag: &mut WidgetTag) {
+        let deadline = self.push_at(message, Instant::now());
+        widget_tag.timers_mut().insert(self.timer_id, Timer::new_delayed(self.delay, deadline));
+    }
+
+    /// Handle a `WidgetEvent::Timer` event, broadcasting the waiting message if the delay has
+    /// passed. Returns `false` if the timer doesn't belong to this helper.
+    pub fn handle_timer(&mut self, timer_id: TimerId, widget_tag: &mut WidgetTag) -> bool {
+        if timer_id != self.timer_id {
+            return false;
+        }
+
+        // Restarting the delay leaves the old trigger queued, so the timer may fire early.
+        if let Some(message) = self.poll(Instant::now()) {
+            widget_tag.timers_mut().remove(&self.timer_id);
+            widget_tag.broadcast_message(message);
+        }
+        true
+    }
+
+    /// Broadcast the waiting message immediately, if there is one. Useful for committing a search
+    /// when the user presses enter.
+    pub fn flush(&mut self, widget_tag: &mut WidgetTag) {
+        self.deadline = None;
+        if let Some(message) = self.pending.take() {
+            widget_tag.timers_mut().remove(&self.timer_id);
+            widget_tag.broadcast_message(message);
+        }
+    }
+
+    /// Drop the waiting message without broadcasting it.
+    pub fn cancel(&mut self, widget_tag: &mut WidgetTag) {
+        self.deadline = None;
+        if self.pending.take().is_some() {
+            widget_tag.timers_mut().remove(&self.timer_id);
+        }
+    }
+
+    fn push_at(&mut self, message: M, now: Instant) -> Instant {
+        let deadline = now + self.delay;
+        self.pending = Some(message);
+        self.deadline = Some(deadline);
+        deadline
+    }
+
+    fn poll(&mut self, now: Instant) -> Option<M> {
+        match self.deadline {
+            Some(deadline) if deadline <= now => {
+                self.deadline = None;
+                self.pending.take()
+            },
+            _ => None
+        }
+    }
+}
+
+impl<M: 'static> Throttle<M> {
+    pub fn new(interval: Duration) -> Throttle<M> {
+        Throttle {
+            timer_id: TimerId::new(),
+            interval,
+            pending: None,
+            window_end: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Whether there's a message waiting for the current interval to end.
+    #[inline(always)]
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Broadcast `message` if the last interval has ended, or hold it until the interval ends
+    /// otherwise.
+    pub fn push(&mut self, message: M, widget_tag: &mut WidgetTag) {
+        let now = Instant::now();
+        let was_pending = self.pending.is_some();
+        match self.push_at(message, now) {
+            Some(message) => widget_tag.broadcast_message(message),
+            None if !was_pending => {
+                let window_end = self.window_end.unwrap();
+                widget_tag.timers_mut().insert(self.timer_id, Timer::new_delayed(self.interval, window_end));
+            },
+            None => ()
+        }
+    }
+
+    /// Handle a `WidgetEvent::Timer` event, broadcasting the held message if the interval has
+    /// ended. Returns `false` if the timer doesn't belong to this helper.
+    pub fn handle_timer(&mut self, timer_id: TimerId, widget_tag: &mut WidgetTag) -> bool {
+        if timer_id != self.timer_id {
+            return false;
+        }
+
+        let now = Instant::now();
+        if let Some(message) = self.poll(now) {
+            widget_tag.broadcast_message(message);
+        }
+        if self.pending.is_none() {
+            widget_tag.timers_mut().remove(&self.timer_id);
+        }
+        true
+    }
+
+    /// Returns the message to broadcast immediately, if any.
+    fn push_at(&mut self, message: M, now: Instant) -> Option<M> {
+        match self.window_end {
+            Some(window_end) if now < window_end => {
+                self.pending = Some(message);
+                None
+            },
+            _ => {
+                self.window_end = Some(now + self.interval);
+                Some(message)
+            }
+        }
+    }
+
+    fn poll(&mut self, now: Instant) -> Option<M> {
+        match self.window_end {
+            Some(window_end) if window_end <= now => {
+                // Broadcasting the held message starts a new interval, so messages pushed right
+                // after it still get throttled.
+                let message = self.pending.take();
+                self.window_end = message.as_ref().map(|_| now + self.interval);
+                message
+            },
+            _ => None
+        }
+    }
+}
+
+impl<M: 'static + PartialEq + Clone> DistinctUntilChanged<M> {
+    pub fn new() -> DistinctUntilChanged<M> {
+        DistinctUntilChanged {
+            last: None,
+        }
+    }
+
+    /// Broadcast `message` if it differs from the last message broadcast.
+    pub fn push(&mut self, message: M, widget_tag: &mut WidgetTag) {
+        if let Some(message) = self.filter(message) {
+            widget_tag.broadcast_message(message);
+        }
+    }
+
+    /// Returns `message` if it differs from the last message passed through the filter. Can be used
+    /// to drop duplicates before passing messages to a `Debounce` or `Throttle`.
+    pub fn filter(&mut self, message: M) -> Option<M> {
+        match self.last {
+            Some(ref last) if *last == message => None,
+            _ => {
+                self.last = Some(message.clone());
+                Some(message)
+            }
+        }
+    }
+
+    /// Forget the last message, so the next message always passes through.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debounce() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut debounce = Debounce::new(Duration::from_millis(100));
+
+        debounce.push_at(1, ms(0));
+        debounce.push_at(2, ms(50));
+        assert_eq!(None, debounce.poll(ms(100)));
+        assert_eq!(Some(2), debounce.poll(ms(150)));
+        assert_eq!(None, debounce.poll(ms(200)));
+        assert!(!debounce.is_pending());
+    }
+
+    #[test]
+    fn throttle() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut throttle = Throttle::new(Duration::from_millis(100));
+
+        assert_eq!(Some(1), throttle.push_at(1, ms(0)));
+        assert_eq!(None, throttle.push_at(2, ms(20)));
+        assert_eq!(None, throttle.push_at(3, ms(40)));
+        assert_eq!(None, throttle.poll(ms(60)));
+        assert_eq!(Some(3), throttle.poll(ms(100)));
+
+        // The trailing message started a new interval.
+        assert_eq!(None, throttle.push_at(4, ms(150)));
+        assert_eq!(Some(4), throttle.poll(ms(200)));
+        assert_eq!(None, throttle.poll(ms(300)));
+        assert_eq!(Some(5), throttle.push_at(5, ms(400)));
+    }
+
+    #[test]
+    fn distinct_until_changed() {
+        let mut distinct = DistinctUntilChanged::new();
+        assert_eq!(Some(1), distinct.filter(1));
+        assert_eq!(None, distinct.filter(1));
+        assert_eq!(Some(2), distinct.filter(2));
+        assert_eq!(Some(1), distinct.filter(1));
+        distinct.reset();
+        assert_eq!(Some(1), distinct.filter(1));
+    }
+}
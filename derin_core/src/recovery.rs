@@ -0,0 +1,280 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Recovering from panics in widget code.
+//!
+//! If a widget panics while the `FrameEventProcessor` is dispatching an event, message, or timer,
+//! the panic gets caught and the frame is aborted: any input and update state changes made by the
+//! event that panicked get rolled back, the rest of the frame's events are dropped, and the whole
+//! tree gets re-laid out and redrawn on the next pass. The panic is recorded as a [`FrameError`],
+//! which can be retrieved with [`Root::frame_error`] and cleared with [`Root::clear_frame_error`].
+//!
+//! State owned by the widgets themselves can't be rolled back, so a widget that panicked may be
+//! left half-updated. The tree's structure stays valid, though, so the application can keep
+//! running: widgets that were dropped by the panicking event still get removed from the tree, and
+//! any background tasks it spawned get cancelled.
+//!
+//! [`FrameError`]: ./struct.FrameError.html
+//! [`Root::frame_error`]: ../struct.Root.html#method.frame_error
+//! [`Root::clear_frame_error`]: ../struct.Root.html#method.clear_frame_error
+
+use crate::{
+    InputState, WindowEvent,
+    activity::ActivityTracker,
+    caret::CaretBlink,
+    idle::IdleScheduler,
+    message_bus::MessageBus,
+    modal::ModalStack,
+    shortcut::ShortcutTable,
+    update_state::{UpdateRequests, UpdateState},
+};
+use std::{
+    any::Any,
+    error::Error,
+    fmt,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+};
+
+/// A panic that aborted a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameError {
+    /// The window event being processed when the panic happened. `None` if the panic happened
+    /// while the frame was being finished.
+    pub event: Option<WindowEvent>,
+    /// The panic's message, if it had one.
+    pub message: Option<String>,
+}
+
+impl FrameError {
+    fn from_panic(event: Option<WindowEvent>, payload: Box<Any + Send>) -> FrameError {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => Some(*message),
+            Err(payload) => payload.downcast_ref::<&'static str>().map(|s| s.to_string())
+        };
+        FrameError{ event, message }
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "widget panicked")?;
+        if let Some(event) = self.event {
+            write!(f, " while processing {:?}", event)?;
+        }
+        if let Some(ref message) = self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for FrameError {}
+
+/// A snapshot of the state a panicking event could leave inconsistent.
+///
+/// Taking a checkpoint doesn't copy anything up-front. The requests queued before the checkpoint
+/// get moved into it, leaving the update state with an empty request buffer, and the rest of the
+/// update state gets shared with the checkpoint and only copied if it's modified before the
+/// checkpoint is committed.
+pub(crate) struct Checkpoint {
+    input_state: InputState,
+    requests: UpdateRequests,
+    caret_blink: Rc<CaretBlink>,
+    idle_work: Rc<IdleScheduler>,
+    shortcuts: Rc<ShortcutTable>,
+    modals: Rc<ModalStack>,
+    activity: ActivityTracker,
+    mnemonics_visible: bool,
+}
+
+impl Checkpoint {
+    pub fn new(input_state: &InputState, update_state: &mut UpdateState, message_bus: &mut MessageBus) -> Checkpoint {
+        message_bus.commit();
+
+        // Every field gets named here, so new update state can't be added without deciding how it
+        // gets rolled back.
+        let UpdateState {
+            ref mut requests,
+            ref caret_blink,
+            ref idle_work,
+            ref shortcuts,
+            ref modals,
+            ref activity,
+            ref mnemonics_visible,
+            // Tasks spawned after the checkpoint get recorded in `requests`, and cancelled when
+            // rolling back.
+            tasks: _,
+            // Messages get rolled back by the message bus.
+            message_sender: _,
+            // These can only be changed through `Root`, which can't be used mid-frame.
+            accessibility_prefs: _,
+            global_listeners: _,
+            // Rolling back always queues a global update.
+            global_update: _,
+        } = *update_state;
+
+        Checkpoint {
+            input_state: input_state.clone(),
+            requests: mem::replace(requests, UpdateRequests::default()),
+            caret_blink: caret_blink.clone(),
+            idle_work: idle_work.clone(),
+            shortcuts: shortcuts.clone(),
+            modals: modals.clone(),
+            activity: activity.clone(),
+            mnemonics_visible: *mnemonics_visible,
+        }
+    }
+
+    /// The requests that were queued before the checkpoint was taken.
+    pub fn requests_mut(&mut self) -> &mut UpdateRequests {
+        &mut self.requests
+    }
+
+    /// Keep the changes made since the checkpoint was taken.
+    pub fn commit(self, update_state: &mut UpdateState) {
+        let newer = mem::replace(&mut update_state.requests, self.requests);
+        update_state.requests.merge(newer);
+    }
+
+    /// Roll back to the checkpoint, and queue the whole tree for relayout and redraw so that any
+    /// widgets left half-updated get drawn in a consistent state.
+    pub fn restore(self, input_state: &mut InputState, update_state: &mut UpdateState, message_bus: &mut MessageBus) {
+        message_bus.discard_uncommitted();
+        *input_state = self.input_state;
+
+        let rolled_back = mem::replace(&mut update_state.requests, self.requests);
+        for (widget_id, task_id) in rolled_back.spawned_tasks {
+            update_state.tasks.cancel(widget_id, task_id);
+        }
+        // Widgets that got dropped or had their children changed stay that way, since widgets
+        // can't be rolled back, so the virtual tree still needs to catch up with them.
+        update_state.requests.merge(UpdateRequests {
+            remove_from_tree: rolled_back.remove_from_tree,
+            rescan: rolled_back.rescan,
+            ..UpdateRequests::default()
+        });
+
+        update_state.caret_blink = self.caret_blink;
+        update_state.idle_work = self.idle_work;
+        update_state.shortcuts = self.shortcuts;
+        update_state.modals = self.modals;
+        update_state.activity = self.activity;
+        update_state.mnemonics_visible = self.mnemonics_visible;
+        update_state.queue_global_update();
+    }
+}
+
+/// Run `f`, catching any panic that happens inside of it.
+pub(crate) fn catch_frame_panic<T>(event: Option<WindowEvent>, f: impl FnOnce() -> T) -> Result<T, FrameError> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .map_err(|payload| FrameError::from_panic(event, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accessibility::{Announcement, Politeness},
+        message_bus::MessageTargeted,
+        sound::UiSound,
+        widget::WidgetId,
+    };
+
+    fn announcement(text: &str, widget_id: WidgetId) -> Announcement {
+        Announcement {
+            text: text.to_string(),
+            politeness: Politeness::Polite,
+            widget_id,
+        }
+    }
+
+    #[test]
+    fn restore_checkpoint() {
+        let mut message_bus = MessageBus::new();
+        let update_state = UpdateState::new(&message_bus);
+        let mut update_state = update_state.borrow_mut();
+        let mut input_state = InputState::new();
+        let (a, b, c) = (WidgetId::new(), WidgetId::new(), WidgetId::new());
+
+        update_state.reset_global_update();
+        update_state.requests.redraw.insert(a);
+        update_state.requests.redraw.insert(c);
+        update_state.requests.announcements.push(announcement("kept", a));
+        update_state.message_sender.send(MessageTargeted{ message: Box::new(1u32), target: None }).unwrap();
+
+        let checkpoint = Checkpoint::new(&input_state, &mut update_state, &mut message_bus);
+        input_state.focused_widget = Some(b);
+        update_state.requests.redraw.insert(b);
+        update_state.requests.remove_from_tree.insert(c);
+        update_state.requests.announcements.push(announcement("dropped", b));
+        update_state.requests.sounds.push(UiSound::Click);
+        update_state.modals_mut().push(b);
+        update_state.idle_work_mut().queue_work(b);
+        update_state.mnemonics_visible = true;
+        update_state.message_sender.send(MessageTargeted{ message: Box::new(2u32), target: None }).unwrap();
+        checkpoint.restore(&mut input_state, &mut update_state, &mut message_bus);
+
+        assert_eq!(None, input_state.focused_widget);
+        // `c` was dropped, so it stays removed, and its queued redraw doesn't matter anymore.
+        assert_eq!(Some(&a), update_state.requests.redraw.iter().next());
+        assert_eq!(1, update_state.requests.redraw.len());
+        assert_eq!(Some(&c), update_state.requests.remove_from_tree.iter().next());
+        assert_eq!(1, update_state.requests.announcements.len());
+        assert!(update_state.requests.sounds.is_empty());
+        assert_eq!(None, update_state.modals.top());
+        assert!(update_state.idle_work.is_empty());
+        assert!(!update_state.mnemonics_visible);
+        assert!(update_state.global_update);
+
+        let (message, _) = message_bus.next_message().unwrap();
+        assert_eq!(Some(&1), message.downcast_ref::<u32>());
+        assert!(message_bus.next_message().is_none());
+    }
+
+    #[test]
+    fn commit_checkpoint() {
+        let mut message_bus = MessageBus::new();
+        let update_state = UpdateState::new(&message_bus);
+        let mut update_state = update_state.borrow_mut();
+        let input_state = InputState::new();
+        let (a, b) = (WidgetId::new(), WidgetId::new());
+
+        update_state.reset_global_update();
+        update_state.requests.redraw.insert(a);
+        update_state.requests.announcements.push(announcement("first", a));
+
+        let checkpoint = Checkpoint::new(&input_state, &mut update_state, &mut message_bus);
+        assert!(update_state.requests.redraw.is_empty());
+        // Nothing gets copied until it's modified.
+        assert_eq!(2, Rc::strong_count(&update_state.modals));
+
+        update_state.requests.redraw.insert(b);
+        update_state.requests.remove_from_tree.insert(a);
+        update_state.requests.announcements.push(announcement("second", b));
+        update_state.modals_mut().push(b);
+        assert_eq!(1, Rc::strong_count(&update_state.modals));
+        checkpoint.commit(&mut update_state);
+
+        assert_eq!(Some(&b), update_state.requests.redraw.iter().next());
+        assert_eq!(1, update_state.requests.redraw.len());
+        assert_eq!(Some(&a), update_state.requests.remove_from_tree.iter().next());
+        let announcements: Vec<_> = update_state.requests.announcements.iter().map(|n| &n.text[..]).collect();
+        assert_eq!(vec!["first", "second"], announcements);
+        assert_eq!(Some(b), update_state.modals.top());
+        assert!(!update_state.global_update);
+    }
+
+    #[test]
+    fn panic_message() {
+        let error = catch_frame_panic(Some(WindowEvent::Redraw), || panic!("bad {}", "widget")).unwrap_err();
+        assert_eq!(Some(WindowEvent::Redraw), error.event);
+        assert_eq!(Some("bad widget".to_string()), error.message);
+
+        let error = catch_frame_panic(None, || panic!("static")).unwrap_err();
+        assert_eq!(Some("static".to_string()), error.message);
+        assert_eq!(Ok(1), catch_frame_panic(None, || 1));
+    }
+}
@@ -2,7 +2,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::widget::WidgetId;
+use crate::{
+    designer::DesignOverlay,
+    stats::RenderStats,
+    widget::WidgetId,
+};
 use cgmath_geometry::{
     D2,
     line::Segment,
@@ -24,8 +28,50 @@ pub trait Renderer: 'static {
         widget_id: WidgetId,
         layout: impl FnOnce(&mut Self::Layout)
     );
+    /// Called before `start_frame` with the region of the window that needs to be redrawn, or
+    /// `None` if the whole window needs to be redrawn.
+    ///
+    /// Unless `preserves_undamaged` returns `true`, all widgets are rendered regardless, so
+    /// renderers that don't support partial redraws can leave this unimplemented.
+    fn set_damage(&mut self, _damage: Option<BoundBox<D2, i32>>) {}
+    /// Whether the pixels outside of the region passed to `set_damage` keep the contents of the
+    /// last frame. If they do, only the widgets overlapping the damaged region get rendered, and
+    /// the renderer should restrict clearing and drawing to that region.
+    fn preserves_undamaged(&self) -> bool {
+        false
+    }
     fn start_frame(&mut self, theme: &Self::Theme);
+    /// Called before each widget is rendered with the widget's `Opacity` ambient value, or `1.0`
+    /// if it doesn't have one.
+    ///
+    /// Renderers that don't support translucent widgets can leave this unimplemented.
+    fn set_opacity(&mut self, _opacity: f32) {}
+    /// Called before each widget is rendered with whether the widget is enabled, so that disabled
+    /// widgets can be drawn greyed out.
+    ///
+    /// Renderers that draw disabled widgets the same as enabled ones can leave this unimplemented.
+    fn set_enabled(&mut self, _enabled: bool) {}
+    /// Draw the design mode selection over the rest of the frame. Called after every widget has
+    /// been rendered, and before `finish_frame`.
+    ///
+    /// Renderers that aren't used for UI designers can leave this unimplemented.
+    fn render_design_overlay(&mut self, _overlay: &DesignOverlay) {}
     fn finish_frame(&mut self, theme: &Self::Theme);
+    /// Retrieve the widgets whose GPU data has been evicted since this was last called. Each
+    /// widget gets sent a `TexturesEvicted` message.
+    ///
+    /// Renderers that don't cache data on the GPU can leave this unimplemented.
+    fn take_evicted_owners(&mut self) -> Vec<WidgetId> {
+        Vec::new()
+    }
+    /// Retrieve the statistics of the frame that was just finished, which get sent to widgets as
+    /// part of the `FrameStats` message. Called after `finish_frame`, and only while a widget is
+    /// listening for `FrameStats`.
+    ///
+    /// Renderers that don't track statistics can leave this unimplemented.
+    fn render_stats(&mut self) -> Option<RenderStats> {
+        None
+    }
 }
 
 pub trait WidgetRenderer<T: WidgetTheme>: Renderer {
@@ -47,10 +93,26 @@ pub trait SubFrame {
 #[derive(Debug, Clone)]
 pub struct CursorData {
     pub draw_cursor: bool,
+    /// The position of the primary caret.
     pub cursor_pos: usize,
+    /// The text selected by the primary caret.
     pub highlight_range: Range<usize>,
+    /// Carets besides the primary one, for multi-caret editing. Carets never overlap each other.
+    pub extra_carets: Vec<Caret>,
 }
 
+/// A caret and the text it has selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caret {
+    pub cursor_pos: usize,
+    pub highlight_range: Range<usize>,
+}
+
+/// An operation on an edit string's carets and text.
+///
+/// Movements and edits get applied at every caret, which renderers can do with
+/// `CursorData::for_each_caret`. `SelectOnSegment`, `SelectAll` and `UnselectAll` replace all the
+/// carets with a single caret.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CursorOp {
     MoveVertical {
@@ -72,6 +134,74 @@ pub enum CursorOp {
         jump_to_word_boundaries: bool,
     },
     DeleteSelection,
+    /// Select the text under the segment with a new primary caret, keeping the other carets.
+    AddCaretOnSegment(Segment<D2, i32>),
+    /// Select the next occurrence of the primary caret's selection with a new primary caret. If
+    /// nothing is selected, the word under the primary caret gets selected instead.
+    SelectNextOccurrence,
+    /// Remove every caret but the primary one.
+    RemoveExtraCarets,
+    /// Expand every caret's selection outwards to the boundaries of the units it touches. Used to
+    /// select the word or paragraph under a double- or triple-click, and to extend that selection
+    /// while dragging.
+    ExpandSelection(TextUnit),
+    /// Move every caret to the start or end of the unit it's in.
+    MoveToBoundary {
+        unit: TextUnit,
+        forward: bool,
+        expand_selection: bool,
+    },
+}
+
+/// A unit of text that carets can select or move by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextUnit {
+    /// A run of alphanumeric characters and underscores.
+    Word,
+    /// The text between two line breaks. Wrapped lines count as a single paragraph.
+    Paragraph,
+    /// The whole string.
+    Document,
+}
+
+/// A range of text drawn with a style class, such as `"keyword"` or `"comment"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HighlightSpan {
+    /// The byte range of the text in the span.
+    pub range: Range<usize>,
+    pub class: &'static str,
+}
+
+/// Extra content drawn along with an edit string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditDecorations<'a> {
+    /// Text to draw with the style the theme gives each span's class.
+    pub spans: &'a [HighlightSpan],
+    /// The gutter to draw to the left of the text, if any.
+    pub gutter: Option<GutterLayout<'a>>,
+}
+
+/// A column to the left of an edit string's text, showing line numbers, markers, and fold controls.
+///
+/// Lines are referred to by their zero-based index in the string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GutterLayout<'a> {
+    /// The width of the gutter, in pixels. The text gets shifted right to make room for it.
+    pub width: i32,
+    pub line_numbers: bool,
+    /// The lines that have markers, such as breakpoints, along with the style class of each marker.
+    pub markers: &'a [(usize, &'static str)],
+    /// The regions of lines that can be folded.
+    pub folds: &'a [FoldRegion],
+}
+
+/// A range of lines that can be collapsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FoldRegion {
+    /// The lines in the region. The first line stays visible when the region gets folded, with the
+    /// rest of the lines hidden behind a placeholder.
+    pub lines: Range<usize>,
+    pub folded: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -83,6 +213,14 @@ pub struct LayoutResult {
 
 pub trait RendererLayout {
     fn prepare_string(&mut self, string: &str);
+    /// Layout the render string, underlining the characters in the given byte range. Used to draw
+    /// mnemonics.
+    ///
+    /// Renderers that don't support underlining can leave this unimplemented, in which case the
+    /// string is laid out without the underline.
+    fn prepare_string_underlined(&mut self, string: &str, _underline: Range<usize>) {
+        self.prepare_string(string);
+    }
     /// Layout the render string and perform any queued cursor operations.
     fn prepare_edit_string(
         &mut self,
@@ -90,6 +228,21 @@ pub trait RendererLayout {
         cursor_data: &mut CursorData,
         cursor_ops: impl Iterator<Item=CursorOp>,
     );
+    /// Layout the render string and perform any queued cursor operations, along with the
+    /// decorations used by code editors: syntax highlighting, a gutter, and folded lines.
+    ///
+    /// The decorations describe the string as it was before the cursor operations got applied.
+    /// Renderers that don't support decorations can leave this unimplemented, in which case the
+    /// string is laid out without them.
+    fn prepare_edit_string_decorated(
+        &mut self,
+        string: &mut String,
+        cursor_data: &mut CursorData,
+        cursor_ops: impl Iterator<Item=CursorOp>,
+        _decorations: &EditDecorations,
+    ) {
+        self.prepare_edit_string(string, cursor_data, cursor_ops);
+    }
     fn prepare_icon(&mut self, icon_name: &str);
     /// Finish laying stuff out and retrieve widget-level layout parameters. Calling this more than
     /// once should panic.
@@ -149,6 +302,323 @@ impl Default for CursorData {
             draw_cursor: false,
             cursor_pos: 0,
             highlight_range: 0..0,
+            extra_carets: Vec::new(),
+        }
+    }
+}
+
+impl Caret {
+    /// The range the caret covers: its selection, or its position if nothing is selected.
+    pub fn span(&self) -> Range<usize> {
+        match self.highlight_range.len() {
+            0 => self.cursor_pos..self.cursor_pos,
+            _ => self.highlight_range.clone()
+        }
+    }
+
+    fn shift(&mut self, delta: isize) {
+        let shift = |i: usize| (i as isize + delta) as usize;
+        self.cursor_pos = shift(self.cursor_pos);
+        self.highlight_range = shift(self.highlight_range.start)..shift(self.highlight_range.end);
+    }
+}
+
+impl CursorData {
+    pub fn primary_caret(&self) -> Caret {
+        Caret {
+            cursor_pos: self.cursor_pos,
+            highlight_range: self.highlight_range.clone(),
+        }
+    }
+
+    pub fn set_primary_caret(&mut self, caret: Caret) {
+        self.cursor_pos = caret.cursor_pos;
+        self.highlight_range = caret.highlight_range;
+    }
+
+    /// Make `caret` the primary caret, keeping the old primary caret as an extra caret. Carets it
+    /// overlaps get merged into it.
+    pub fn push_caret(&mut self, caret: Caret) {
+        let old_primary = self.primary_caret();
+        self.extra_carets.push(old_primary);
+        self.set_primary_caret(caret);
+        self.merge_carets();
+    }
+
+    pub fn remove_extra_carets(&mut self) {
+        self.extra_carets.clear();
+    }
+
+    /// Iterate over every caret, starting with the primary caret.
+    pub fn carets(&self) -> impl '_ + Iterator<Item=Caret> {
+        Some(self.primary_caret()).into_iter().chain(self.extra_carets.iter().cloned())
+    }
+
+    /// Apply `f` at every caret.
+    ///
+    /// Carets get visited from the end of the string to the start, and any change in the string's
+    /// length gets applied to the carets after the one being visited, so `f` can edit the string
+    /// around the caret without worrying about the other carets. Carets that overlap afterwards get
+    /// merged.
+    pub fn for_each_caret(&mut self, string: &mut String, mut f: impl FnMut(&mut String, &mut Caret)) {
+        let mut carets = self.take_carets();
+        for i in (0..carets.len()).rev() {
+            let old_len = string.len();
+            f(string, &mut carets[i].1);
+            let delta = string.len() as isize - old_len as isize;
+            for (_, caret) in &mut carets[i + 1..] {
+                caret.shift(delta);
+            }
+        }
+        self.put_carets(carets);
+    }
+
+    /// Perform `CursorOp::SelectNextOccurrence`.
+    pub fn select_next_occurrence(&mut self, string: &str) {
+        let primary = self.primary_caret();
+        if primary.highlight_range.len() == 0 {
+            let word = word_at(string, primary.cursor_pos);
+            self.cursor_pos = word.end;
+            self.highlight_range = word;
+            self.merge_carets();
+            return;
+        }
+
+        // Search after the furthest caret first, so repeated searches walk through the string in
+        // order, then wrap around to the start.
+        let needle = &string[primary.highlight_range.clone()];
+        let search_start = self.carets().map(|c| c.span().end).max().unwrap_or(0);
+        let matches = string.match_indices(needle).map(|(i, _)| i..i + needle.len());
+        let next_match = matches.clone().filter(|m| search_start <= m.start)
+            .chain(matches.filter(|m| m.start < search_start))
+            .find(|m| self.carets().all(|c| c.span().end <= m.start || m.end <= c.span().start));
+
+        if let Some(range) = next_match {
+            self.push_caret(Caret {
+                cursor_pos: range.end,
+                highlight_range: range,
+            });
+        }
+    }
+
+    /// Perform `CursorOp::ExpandSelection`.
+    pub fn expand_selection(&mut self, string: &str, unit: TextUnit) {
+        self.map_carets(|caret| {
+            let span = caret.span();
+            let range = unit_at(string, span.start, unit).start..unit_at(string, span.end, unit).end;
+            caret.cursor_pos = match caret.cursor_pos == span.start && span.len() != 0 {
+                true => range.start,
+                false => range.end
+            };
+            caret.highlight_range = range;
+        });
+    }
+
+    /// Perform `CursorOp::MoveToBoundary`.
+    pub fn move_to_boundary(&mut self, string: &str, unit: TextUnit, forward: bool, expand_selection: bool) {
+        self.map_carets(|caret| {
+            let unit_range = unit_at(string, caret.cursor_pos, unit);
+            let target = match forward {
+                true => unit_range.end,
+                false => unit_range.start
+            };
+
+            // The end of the selection opposite the cursor stays put while expanding.
+            let anchor = match caret.highlight_range.len() {
+                0 => caret.cursor_pos,
+                _ if caret.cursor_pos == caret.highlight_range.start => caret.highlight_range.end,
+                _ => caret.highlight_range.start
+            };
+            caret.highlight_range = match expand_selection {
+                true => usize::min(anchor, target)..usize::max(anchor, target),
+                false => target..target
+            };
+            caret.cursor_pos = target;
+        });
+    }
+
+    /// Apply `f` to every caret, merging the carets that overlap afterwards.
+    fn map_carets(&mut self, mut f: impl FnMut(&mut Caret)) {
+        let mut carets = self.take_carets();
+        for (_, caret) in &mut carets {
+            f(caret);
+        }
+        carets.sort_by_key(|(_, c)| (c.span().start, c.span().end));
+        self.put_carets(carets);
+    }
+
+    /// Remove every caret and return them sorted by position, with the primary caret marked.
+    fn take_carets(&mut self) -> Vec<(bool, Caret)> {
+        let primary = self.primary_caret();
+        let mut carets: Vec<_> = self.extra_carets.drain(..).map(|c| (false, c))
+            .chain(Some((true, primary)))
+            .collect();
+        carets.sort_by_key(|(_, c)| (c.span().start, c.span().end));
+        carets
+    }
+
+    /// Store sorted carets taken by `take_carets`, merging the ones that overlap.
+    fn put_carets(&mut self, carets: Vec<(bool, Caret)>) {
+        let mut merged: Vec<(bool, Caret)> = Vec::with_capacity(carets.len());
+        for (is_primary, caret) in carets {
+            match merged.last_mut() {
+                Some((last_primary, last)) if caret.span().start < last.span().end || caret.span() == last.span() => {
+                    let span = last.span().start..usize::max(last.span().end, caret.span().end);
+                    if is_primary {
+                        last.cursor_pos = caret.cursor_pos;
+                    }
+                    if last.highlight_range.len() != 0 || caret.highlight_range.len() != 0 {
+                        last.highlight_range = span;
+                    }
+                    *last_primary |= is_primary;
+                },
+                _ => merged.push((is_primary, caret))
+            }
+        }
+
+        for (is_primary, caret) in merged {
+            match is_primary {
+                true => self.set_primary_caret(caret),
+                false => self.extra_carets.push(caret)
+            }
+        }
+    }
+
+    fn merge_carets(&mut self) {
+        let carets = self.take_carets();
+        self.put_carets(carets);
+    }
+}
+
+/// The range of the word around `pos`, where words are runs of alphanumeric characters and
+/// underscores.
+fn word_at(string: &str, pos: usize) -> Range<usize> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let start = string[..pos].char_indices().rev()
+        .take_while(|&(_, c)| is_word_char(c))
+        .last().map(|(i, _)| i).unwrap_or(pos);
+    let end = string[pos..].char_indices()
+        .find(|&(_, c)| !is_word_char(c))
+        .map(|(i, _)| pos + i).unwrap_or(string.len());
+    start..end
+}
+
+/// The range of the unit around `pos`.
+fn unit_at(string: &str, pos: usize, unit: TextUnit) -> Range<usize> {
+    match unit {
+        TextUnit::Word => word_at(string, pos),
+        TextUnit::Paragraph => {
+            let start = string[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let end = string[pos..].find('\n').map(|i| pos + i).unwrap_or(string.len());
+            start..end
+        },
+        TextUnit::Document => 0..string.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caret(cursor_pos: usize) -> Caret {
+        Caret {
+            cursor_pos,
+            highlight_range: 0..0,
         }
     }
+
+    #[test]
+    fn edit_at_each_caret() {
+        let mut string = "ab\ncd\nef".to_string();
+        let mut cursor_data = CursorData::default();
+        cursor_data.push_caret(caret(3));
+        cursor_data.push_caret(caret(6));
+        assert_eq!(6, cursor_data.cursor_pos);
+
+        cursor_data.for_each_caret(&mut string, |string, caret| {
+            string.insert_str(caret.cursor_pos, "> ");
+            caret.cursor_pos += 2;
+        });
+        assert_eq!("> ab\n> cd\n> ef", string);
+        assert_eq!(vec![2, 7, 12], {
+            let mut positions: Vec<_> = cursor_data.carets().map(|c| c.cursor_pos).collect();
+            positions.sort();
+            positions
+        });
+        assert_eq!(12, cursor_data.cursor_pos);
+
+        let backspace = |dist: usize| move |string: &mut String, caret: &mut Caret| {
+            let start = caret.cursor_pos.saturating_sub(dist);
+            string.drain(start..caret.cursor_pos);
+            caret.cursor_pos = start;
+        };
+        cursor_data.for_each_caret(&mut string, backspace(2));
+        assert_eq!("ab\ncd\nef", string);
+        assert_eq!(6, cursor_data.cursor_pos);
+
+        // Carets that end up in the same place get merged.
+        cursor_data.for_each_caret(&mut string, backspace(3));
+        assert_eq!("ef", string);
+        assert_eq!(0, cursor_data.cursor_pos);
+        assert!(cursor_data.extra_carets.is_empty());
+    }
+
+    #[test]
+    fn select_next_occurrence() {
+        let string = "foo bar foo_bar foo";
+        let mut cursor_data = CursorData::default();
+        cursor_data.cursor_pos = 1;
+
+        cursor_data.select_next_occurrence(string);
+        assert_eq!(0..3, cursor_data.highlight_range);
+        assert!(cursor_data.extra_carets.is_empty());
+
+        cursor_data.select_next_occurrence(string);
+        assert_eq!(8..11, cursor_data.highlight_range);
+        cursor_data.select_next_occurrence(string);
+        assert_eq!(16..19, cursor_data.highlight_range);
+        assert_eq!(2, cursor_data.extra_carets.len());
+
+        // Every occurrence is selected, so there's nothing left to add.
+        cursor_data.select_next_occurrence(string);
+        assert_eq!(16..19, cursor_data.highlight_range);
+        assert_eq!(2, cursor_data.extra_carets.len());
+
+        cursor_data.remove_extra_carets();
+        assert_eq!(vec![cursor_data.primary_caret()], cursor_data.carets().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn select_by_unit() {
+        let string = "one two\nthree four\nfive";
+        let mut cursor_data = CursorData::default();
+
+        // A double-click on "two", dragged backwards into "one".
+        cursor_data.cursor_pos = 2;
+        cursor_data.highlight_range = 2..5;
+        cursor_data.expand_selection(string, TextUnit::Word);
+        assert_eq!(0..7, cursor_data.highlight_range);
+        assert_eq!(0, cursor_data.cursor_pos);
+
+        cursor_data.push_caret(caret(10));
+        cursor_data.expand_selection(string, TextUnit::Paragraph);
+        assert_eq!(8..18, cursor_data.highlight_range);
+        assert_eq!(18, cursor_data.cursor_pos);
+        assert_eq!(1, cursor_data.extra_carets.len());
+
+        cursor_data.remove_extra_carets();
+        cursor_data.move_to_boundary(string, TextUnit::Paragraph, false, false);
+        assert_eq!(8, cursor_data.cursor_pos);
+        assert_eq!(0, cursor_data.highlight_range.len());
+
+        cursor_data.move_to_boundary(string, TextUnit::Document, true, true);
+        assert_eq!(8..string.len(), cursor_data.highlight_range);
+        assert_eq!(string.len(), cursor_data.cursor_pos);
+
+        // Carets that end up in the same place get merged.
+        cursor_data.push_caret(caret(3));
+        cursor_data.move_to_boundary(string, TextUnit::Document, false, false);
+        assert_eq!(0, cursor_data.cursor_pos);
+        assert!(cursor_data.extra_carets.is_empty());
+    }
 }
@@ -0,0 +1,232 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Request-response messages.
+//!
+//! A widget sends a request with [`WidgetTag::send_request`], which delivers a [`Request`] message
+//! through the message bus and returns a [`Reply`] handle. Widgets answer requests by registering a
+//! handler with [`WidgetTag::register_request`]; the value the handler returns resolves the
+//! `Reply`. This lets widgets query each other's state without holding direct references to one
+//! another.
+//!
+//! Only the first widget to handle a request answers it. If no widget handles the request, the
+//! `Reply` gets marked as abandoned once the request has been delivered.
+//!
+//! [`WidgetTag::send_request`]: ../widget/struct.WidgetTag.html#method.send_request
+//! [`WidgetTag::register_request`]: ../widget/struct.WidgetTag.html#method.register_request
+//! [`Request`]: ./struct.Request.html
+//! [`Reply`]: ./struct.Reply.html
+
+use std::{
+    cell::RefCell,
+    fmt,
+    rc::Rc,
+};
+
+/// A message asking the widget it's delivered to for a `Resp`.
+pub struct Request<Req, Resp> {
+    /// The value sent alongside the request.
+    pub request: Req,
+    reply: Rc<RefCell<ReplyState<Resp>>>,
+}
+
+/// Handle to the response to a `Request`, which gets resolved when a widget answers it.
+pub struct Reply<Resp> {
+    state: Rc<RefCell<ReplyState<Resp>>>,
+}
+
+struct ReplyState<Resp> {
+    response: Option<Resp>,
+    answered: bool,
+    abandoned: bool,
+    on_reply: Option<Box<FnMut(Resp)>>,
+}
+
+impl<Req, Resp> Request<Req, Resp> {
+    pub(crate) fn new(request: Req) -> (Request<Req, Resp>, Reply<Resp>) {
+        let state = Rc::new(RefCell::new(ReplyState {
+            response: None,
+            answered: false,
+            abandoned: false,
+            on_reply: None,
+        }));
+        (
+            Request{ request, reply: state.clone() },
+            Reply{ state },
+        )
+    }
+
+    /// Whether or not a widget has already answered this request.
+    pub fn answered(&self) -> bool {
+        self.reply.borrow().answered
+    }
+
+    /// Answer the request, resolving the `Reply`.
+    ///
+    /// Returns `false` and discards the response if the request has already been answered.
+    pub fn respond(&self, response: Resp) -> bool {
+        let on_reply = {
+            let mut state = self.reply.borrow_mut();
+            if state.answered {
+                return false;
+            }
+            state.answered = true;
+            match state.on_reply.take() {
+                Some(on_reply) => Some(on_reply),
+                None => {
+                    state.response = Some(response);
+                    return true;
+                }
+            }
+        };
+
+        // The borrow has to be released before calling `on_reply`, in case it inspects the reply.
+        if let Some(mut on_reply) = on_reply {
+            on_reply(response);
+        }
+        true
+    }
+}
+
+impl<Req, Resp> Drop for Request<Req, Resp> {
+    fn drop(&mut self) {
+        let mut state = self.reply.borrow_mut();
+        state.abandoned = !state.answered;
+    }
+}
+
+impl<Resp> Reply<Resp> {
+    /// Whether or not the request has been answered.
+    pub fn resolved(&self) -> bool {
+        self.state.borrow().answered
+    }
+
+    /// Whether or not the request was delivered without any widget answering it.
+    pub fn abandoned(&self) -> bool {
+        self.state.borrow().abandoned
+    }
+
+    /// Take the response, if the request has been answered and the response hasn't already been
+    /// taken.
+    pub fn take(&mut self) -> Option<Resp> {
+        self.state.borrow_mut().response.take()
+    }
+
+    /// Call the given function with the response once the request has been answered.
+    ///
+    /// If the request has already been answered, `on_reply` gets called immediately with the
+    /// response, unless it's already been taken.
+    pub fn then(self, on_reply: impl 'static + FnOnce(Resp)) {
+        let response = {
+            let mut state = self.state.borrow_mut();
+            match state.answered {
+                true => state.response.take(),
+                false => {
+                    let mut on_reply = Some(on_reply);
+                    state.on_reply = Some(Box::new(move |response| (on_reply.take().unwrap())(response)));
+                    return;
+                }
+            }
+        };
+
+        if let Some(response) = response {
+            on_reply(response);
+        }
+    }
+}
+
+impl<Req: fmt::Debug, Resp> fmt::Debug for Request<Req, Resp> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("Request")
+            .field("request", &self.request)
+            .field("answered", &self.answered())
+            .finish()
+    }
+}
+
+impl<Resp> fmt::Debug for Reply<Resp> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("Reply")
+            .field("resolved", &self.resolved())
+            .field("abandoned", &self.abandoned())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{EventList, TestWidget},
+        widget::Widget,
+    };
+    use std::cell::Cell;
+
+    #[test]
+    fn respond() {
+        let (request, mut reply) = Request::<&str, u32>::new("len");
+        assert!(!reply.resolved());
+        assert_eq!(None, reply.take());
+
+        assert!(request.respond(3));
+        assert!(!request.respond(4));
+        drop(request);
+
+        assert!(reply.resolved());
+        assert!(!reply.abandoned());
+        assert_eq!(Some(3), reply.take());
+        assert_eq!(None, reply.take());
+    }
+
+    #[test]
+    fn then() {
+        let recieved = Rc::new(Cell::new(None));
+
+        let (request, reply) = Request::<(), u32>::new(());
+        let recieved_clone = recieved.clone();
+        reply.then(move |r| recieved_clone.set(Some(r)));
+        assert_eq!(None, recieved.get());
+        request.respond(1);
+        assert_eq!(Some(1), recieved.get());
+
+        let (request, reply) = Request::<(), u32>::new(());
+        request.respond(2);
+        let recieved_clone = recieved.clone();
+        reply.then(move |r| recieved_clone.set(Some(r)));
+        assert_eq!(Some(2), recieved.get());
+    }
+
+    #[test]
+    fn abandoned() {
+        let (request, reply) = Request::<(), u32>::new(());
+        assert!(!reply.abandoned());
+        drop(request);
+        assert!(reply.abandoned());
+        assert!(!reply.resolved());
+    }
+
+    #[test]
+    fn register_request() {
+        test_widget_tree!{
+            let event_list = EventList::new();
+            let mut tree = root {rect: (0, 0, 10, 10)};
+        }
+        let _ = (event_list, root);
+
+        let calls = Rc::new(Cell::new(0));
+        for response in 1..=2 {
+            let calls = calls.clone();
+            tree.widget_tag.register_request(move |_: &mut TestWidget, _: &()| {
+                calls.set(calls.get() + 1);
+                response
+            });
+        }
+
+        // Only the first handler gets to answer the request.
+        let (request, mut reply) = Request::<(), u32>::new(());
+        tree.dispatch_message(&request);
+        assert_eq!(1, calls.get());
+        assert_eq!(Some(1), reply.take());
+    }
+}
@@ -0,0 +1,41 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    damage::DrawnRects,
+    occlusion::RenderedRect,
+    timer::TimerTrigger,
+    widget::WidgetId,
+};
+use cgmath_geometry::{D2, rect::BoundBox};
+use fnv::FnvHashSet;
+
+/// Buffers for transient per-frame data, owned by the `Root` and reused from frame to frame.
+///
+/// Each pass clears the buffers it uses before filling them, so their allocations stick around
+/// and the layout and render passes don't have to hit the allocator every frame once the buffers
+/// have grown to fit the widget tree.
+#[derive(Debug, Default)]
+pub(crate) struct FrameScratch {
+    /// Widgets waiting to be re-laid out, sorted by depth.
+    pub relayout_widgets: Vec<WidgetId>,
+    pub relayout_parent: FnvHashSet<WidgetId>,
+    pub relayout_subtree: Vec<WidgetId>,
+    /// The damaged regions being redrawn.
+    pub redraw_rects: Vec<(WidgetId, BoundBox<D2, i32>)>,
+    /// The widgets being redrawn.
+    pub redraw_widgets: FnvHashSet<WidgetId>,
+    pub drawn_rects: DrawnRects,
+    /// Every widget's visible rect, in rendering order, for finding occluded widgets.
+    pub rendered_rects: Vec<RenderedRect>,
+    pub opaque_rects: Vec<BoundBox<D2, i32>>,
+    pub occluded: Vec<bool>,
+    pub timers_triggered: Vec<TimerTrigger>,
+}
+
+impl FrameScratch {
+    pub fn new() -> FrameScratch {
+        FrameScratch::default()
+    }
+}
@@ -0,0 +1,171 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Keyboard shortcuts that broadcast messages when their key chord is pressed.
+//!
+//! Shortcuts are registered with `Root::register_shortcut` or `WidgetTag::register_shortcut`. When
+//! a key is pressed, the shortcut table gets checked before the `KeyDown` event is delivered to the
+//! focused widget; if a shortcut matches, its message gets broadcast and the focused widget doesn't
+//! see the key press.
+//!
+//! Shortcuts registered on a widget are scoped to that widget's subtree: they're only active while
+//! the widget or one of its descendants has focus, and they're removed along with the widget. If
+//! several shortcuts match, the one scoped to the widget closest to the focused widget wins, with
+//! global shortcuts checked last. Binding a chord that's already bound in the same scope is an
+//! error.
+
+use crate::{
+    message_bus::Message,
+    widget::WidgetId,
+};
+use derin_common_types::buttons::KeyChord;
+use std::{
+    error::Error,
+    fmt,
+    rc::Rc,
+};
+
+id!(pub ShortcutId);
+
+/// Where a shortcut is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShortcutScope {
+    /// Active regardless of which widget has focus, or if no widget has focus.
+    Global,
+    /// Active while the given widget or one of its descendants has focus.
+    Subtree(WidgetId),
+}
+
+/// Error returned when registering a shortcut that would conflict with an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutError {
+    /// The chord is already bound to the given shortcut in the same scope.
+    Conflict {
+        existing: ShortcutId,
+        chord: KeyChord,
+    },
+    /// The widget registering the shortcut hasn't been added to a `Root` yet.
+    NoRootWidget,
+}
+
+#[derive(Clone)]
+struct Shortcut {
+    id: ShortcutId,
+    chord: KeyChord,
+    scope: ShortcutScope,
+    message: Rc<Fn() -> Message>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ShortcutTable {
+    shortcuts: Vec<Shortcut>,
+}
+
+impl ShortcutTable {
+    pub fn new() -> ShortcutTable {
+        ShortcutTable {
+            shortcuts: Vec::new(),
+        }
+    }
+
+    /// Bind `chord` to the message created by `message` within the given scope.
+    pub fn add(&mut self, chord: KeyChord, scope: ShortcutScope, message: Box<Fn() -> Message>) -> Result<ShortcutId, ShortcutError> {
+        if let Some(existing) = self.shortcuts.iter().find(|s| s.chord == chord && s.scope == scope) {
+            return Err(ShortcutError::Conflict{ existing: existing.id, chord });
+        }
+
+        let id = ShortcutId::new();
+        self.shortcuts.push(Shortcut{ id, chord, scope, message: Rc::from(message) });
+        Ok(id)
+    }
+
+    /// Remove the shortcut with the given ID, returning whether it was found.
+    pub fn remove(&mut self, shortcut_id: ShortcutId) -> bool {
+        let len = self.shortcuts.len();
+        self.shortcuts.retain(|s| s.id != shortcut_id);
+        self.shortcuts.len() != len
+    }
+
+    /// Remove all the shortcuts scoped to the given widget.
+    pub fn remove_scope(&mut self, widget_id: WidgetId) {
+        self.shortcuts.retain(|s| s.scope != ShortcutScope::Subtree(widget_id));
+    }
+
+    /// Create the message for the shortcut bound to `chord`. `focus_path` contains the focused
+    /// widget followed by its ancestors, innermost first.
+    pub fn trigger(&self, chord: KeyChord, focus_path: &[WidgetId]) -> Option<Message> {
+        let scopes = focus_path.iter().map(|id| ShortcutScope::Subtree(*id))
+            .chain(Some(ShortcutScope::Global));
+        for scope in scopes {
+            if let Some(shortcut) = self.shortcuts.iter().find(|s| s.chord == chord && s.scope == scope) {
+                return Some((shortcut.message)());
+            }
+        }
+        None
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shortcuts.is_empty()
+    }
+}
+
+impl fmt::Debug for ShortcutTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.shortcuts.iter().map(|s| (s.id, s.chord, s.scope)))
+            .finish()
+    }
+}
+
+impl fmt::Display for ShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShortcutError::Conflict{ chord, .. } => write!(f, "{} is already bound in this scope", chord.platform_notation()),
+            ShortcutError::NoRootWidget => write!(f, "widget isn't in a root"),
+        }
+    }
+}
+
+impl Error for ShortcutError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use derin_common_types::buttons::{Key, ModifierKeys};
+
+    fn message(value: u32) -> Box<Fn() -> Message> {
+        Box::new(move || Box::new(value))
+    }
+
+    fn triggered(table: &ShortcutTable, chord: KeyChord, focus_path: &[WidgetId]) -> Option<u32> {
+        table.trigger(chord, focus_path).map(|m| *m.downcast::<u32>().unwrap())
+    }
+
+    #[test]
+    fn scoped_shortcuts() {
+        let mut table = ShortcutTable::new();
+        let (inner, outer) = (WidgetId::new(), WidgetId::new());
+        let ctrl_s = KeyChord::new(ModifierKeys::CTRL, Key::S);
+        let ctrl_o = KeyChord::new(ModifierKeys::CTRL, Key::O);
+
+        let global = table.add(ctrl_s, ShortcutScope::Global, message(0)).unwrap();
+        table.add(ctrl_s, ShortcutScope::Subtree(outer), message(1)).unwrap();
+        table.add(ctrl_o, ShortcutScope::Subtree(inner), message(2)).unwrap();
+        assert_eq!(
+            Err(ShortcutError::Conflict{ existing: global, chord: ctrl_s }),
+            table.add(ctrl_s, ShortcutScope::Global, message(3)).map(|_| ())
+        );
+
+        assert_eq!(Some(1), triggered(&table, ctrl_s, &[inner, outer]));
+        assert_eq!(Some(2), triggered(&table, ctrl_o, &[inner, outer]));
+        assert_eq!(Some(0), triggered(&table, ctrl_s, &[]));
+        assert_eq!(None, triggered(&table, ctrl_o, &[outer]));
+
+        table.remove_scope(outer);
+        assert_eq!(Some(0), triggered(&table, ctrl_s, &[inner, outer]));
+        assert!(table.remove(global));
+        assert!(!table.remove(global));
+        assert_eq!(None, triggered(&table, ctrl_s, &[inner, outer]));
+    }
+}
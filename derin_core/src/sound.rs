@@ -0,0 +1,21 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Audio feedback for interface events.
+//!
+//! Widgets request sounds with `WidgetTag::play_sound`, naming the kind of event that happened
+//! rather than a specific sound. The requested sounds are returned in `EventLoopResult::sounds`,
+//! and it's up to the windowing backend to play whichever sound the theme declares for each event.
+//! No sounds are requested while `AccessibilityPrefs::mute_sounds` is set.
+
+/// A standard interface event that can have a sound associated with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiSound {
+    /// A button or other control was activated.
+    Click,
+    /// The user tried to do something that isn't allowed, such as entering invalid input.
+    Error,
+    /// Something happened that the user should be made aware of, such as a message arriving.
+    Notification,
+}
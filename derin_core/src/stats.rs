@@ -0,0 +1,49 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Per-frame performance statistics.
+//!
+//! After every frame that gets drawn, `Root` broadcasts a [`FrameStats`] message describing how
+//! long the frame took to lay out and render, along with whatever the renderer reports through
+//! `Renderer::render_stats`. The message only gets sent while some widget has registered a
+//! handler for it, so applications that don't display statistics don't pay for them.
+//!
+//! [`FrameStats`]: ./struct.FrameStats.html
+
+use std::time::Duration;
+
+/// Statistics reported by the renderer for a single frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub triangles: u32,
+    /// The fraction of the glyph and image atlas that's in use, from `0.0` to `1.0`.
+    pub atlas_occupancy: Option<f32>,
+    /// How long the GPU spent drawing. GPU timings take a while to become available, so this is
+    /// generally the time of a frame or two before this one.
+    pub gpu_time: Option<Duration>,
+}
+
+/// Message broadcast after each frame, describing the work done to produce it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    /// The time spent laying out widgets since the last frame was drawn.
+    pub layout_time: Duration,
+    /// The number of passes over the widgets that needed re-laying out. Anything above one means
+    /// widgets resizing themselves made their parents get laid out again.
+    pub layout_iterations: u32,
+    pub widgets_laid_out: u32,
+    /// The time spent drawing the frame, on the CPU.
+    pub render_time: Duration,
+    pub widgets_rendered: u32,
+    /// The renderer's statistics, if it reports any.
+    pub render: Option<RenderStats>,
+}
+
+impl FrameStats {
+    /// The total CPU time spent on the frame.
+    pub fn frame_time(&self) -> Duration {
+        self.layout_time + self.render_time
+    }
+}
@@ -0,0 +1,210 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Background tasks owned by widgets.
+//!
+//! [`WidgetTag::spawn_task`] runs a future on a background thread. Once the future completes, a
+//! [`TaskFinished`] message holding its result gets delivered to the widget that spawned it, on the
+//! UI thread. If the widget is removed from the tree first, the task is cancelled: the future gets
+//! dropped without being polled again, and no message is sent.
+//!
+//! [`WidgetTag::spawn_task`]: ../widget/struct.WidgetTag.html#method.spawn_task
+//! [`TaskFinished`]: ./struct.TaskFinished.html
+
+use crate::widget::WidgetId;
+use fnv::FnvHashMap;
+use futures::{
+    Future,
+    future::Either,
+    sync::oneshot,
+};
+use std::{
+    any::Any,
+    fmt,
+    sync::{Arc, Mutex, mpsc::{self, Receiver, Sender}},
+    thread,
+};
+
+id!(pub TaskId);
+
+/// Message delivered to a widget when a task it spawned finishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskFinished<T, E> {
+    /// The ID `spawn_task` returned for the task.
+    pub task_id: TaskId,
+    /// The value the task's future resolved to.
+    pub result: Result<T, E>,
+}
+
+/// A task's future, with its result boxed up into a `TaskFinished` message.
+pub(crate) type BoxedTask = Box<Future<Item=Box<Any + Send>, Error=()> + Send>;
+
+/// A task spawned by a widget that hasn't been inserted into a tree yet.
+pub(crate) struct PendingTask {
+    pub task_id: TaskId,
+    pub task: BoxedTask,
+}
+
+struct TaskResult {
+    widget_id: WidgetId,
+    task_id: TaskId,
+    message: Box<Any + Send>,
+}
+
+pub(crate) struct TaskTracker {
+    result_sender: Sender<TaskResult>,
+    result_receiver: Receiver<TaskResult>,
+    /// Dropping a task's cancel handle cancels the task.
+    cancel_handles: FnvHashMap<WidgetId, Vec<(TaskId, oneshot::Sender<()>)>>,
+    wakeup: Arc<Mutex<Option<Box<Fn() + Send>>>>,
+}
+
+pub(crate) fn box_task<F>(task_id: TaskId, future: F) -> BoxedTask
+    where F: 'static + Future + Send,
+          F::Item: Send,
+          F::Error: Send
+{
+    Box::new(future.then(move |result| {
+        let message = Box::new(TaskFinished{ task_id, result }) as Box<Any + Send>;
+        Ok(message)
+    }))
+}
+
+impl TaskTracker {
+    pub fn new() -> TaskTracker {
+        let (result_sender, result_receiver) = mpsc::channel();
+        TaskTracker {
+            result_sender,
+            result_receiver,
+            cancel_handles: FnvHashMap::default(),
+            wakeup: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Set the function that wakes up the event loop when a task finishes.
+    pub fn set_wakeup(&mut self, wakeup: Box<Fn() + Send>) {
+        *self.wakeup.lock().unwrap() = Some(wakeup);
+    }
+
+    pub fn spawn(&mut self, widget_id: WidgetId, task_id: TaskId, task: BoxedTask) {
+        let (cancel_sender, cancel_receiver) = oneshot::channel::<()>();
+        self.cancel_handles.entry(widget_id).or_insert(Vec::new()).push((task_id, cancel_sender));
+
+        let result_sender = self.result_sender.clone();
+        let wakeup = self.wakeup.clone();
+        thread::spawn(move || {
+            // `cancel_receiver` resolves as soon as its sender gets dropped, which stops the task
+            // from being polled any further.
+            if let Ok(Either::A((message, _))) = task.select2(cancel_receiver).wait() {
+                if result_sender.send(TaskResult{ widget_id, task_id, message }).is_ok() {
+                    if let Some(ref wakeup) = *wakeup.lock().unwrap() {
+                        wakeup();
+                    }
+                }
+            }
+        });
+    }
+
+    /// Cancel all of the widget's tasks.
+    pub fn cancel_widget(&mut self, widget_id: WidgetId) {
+        self.cancel_handles.remove(&widget_id);
+    }
+
+    /// Cancel a single one of the widget's tasks.
+    pub fn cancel(&mut self, widget_id: WidgetId, task_id: TaskId) {
+        if let Some(handles) = self.cancel_handles.get_mut(&widget_id) {
+            handles.retain(|&(id, _)| id != task_id);
+            if handles.is_empty() {
+                self.cancel_handles.remove(&widget_id);
+            }
+        }
+    }
+
+    /// Retrieve the messages for every task that's finished since the last call, alongside the
+    /// widgets they should be delivered to.
+    pub fn take_finished(&mut self) -> Vec<(WidgetId, Box<Any + Send>)> {
+        let mut finished = Vec::new();
+        for TaskResult{ widget_id, task_id, message } in self.result_receiver.try_iter() {
+            // The task may have been cancelled after it finished, but before we got the result.
+            let handles = match self.cancel_handles.get_mut(&widget_id) {
+                Some(handles) => handles,
+                None => continue
+            };
+            let num_handles = handles.len();
+            handles.retain(|&(id, _)| id != task_id);
+            if handles.len() != num_handles {
+                finished.push((widget_id, message));
+            }
+            if handles.is_empty() {
+                self.cancel_handles.remove(&widget_id);
+            }
+        }
+        finished
+    }
+}
+
+impl fmt::Debug for TaskTracker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaskTracker")
+            .field("cancel_handles", &self.cancel_handles)
+            .finish()
+    }
+}
+
+impl fmt::Debug for PendingTask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PendingTask")
+            .field("task_id", &self.task_id)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use std::time::{Duration, Instant};
+
+    fn wait_for_finished(tracker: &mut TaskTracker) -> Vec<(WidgetId, Box<Any + Send>)> {
+        let start = Instant::now();
+        loop {
+            let finished = tracker.take_finished();
+            if finished.len() > 0 || start.elapsed() > Duration::from_secs(5) {
+                return finished;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn task_result() {
+        let mut tracker = TaskTracker::new();
+        let (widget_id, task_id) = (WidgetId::new(), TaskId::new());
+
+        tracker.spawn(widget_id, task_id, box_task(task_id, future::ok::<u32, ()>(42)));
+        let mut finished = wait_for_finished(&mut tracker);
+
+        assert_eq!(1, finished.len());
+        let (id, message) = finished.pop().unwrap();
+        assert_eq!(widget_id, id);
+        assert_eq!(
+            Some(&TaskFinished{ task_id, result: Ok(42) }),
+            message.downcast_ref::<TaskFinished<u32, ()>>()
+        );
+    }
+
+    #[test]
+    fn task_cancel() {
+        let mut tracker = TaskTracker::new();
+        let (widget_id, task_id) = (WidgetId::new(), TaskId::new());
+
+        let (sender, receiver) = oneshot::channel::<u32>();
+        tracker.spawn(widget_id, task_id, box_task(task_id, receiver));
+        tracker.cancel_widget(widget_id);
+        sender.send(42).ok();
+
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(0, tracker.take_finished().len());
+    }
+}
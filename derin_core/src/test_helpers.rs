@@ -2,28 +2,90 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+//! Helpers for unit testing widgets without a window or a renderer.
+//!
+//! Only available when the `test-helpers` feature is enabled. The general flow of a test is:
+//!
+//! 1. Declare a widget tree with the [`test_widget_tree!`] macro. Each widget in the tree is a
+//!    [`TestWidget`], and the macro binds a `WidgetId` for each widget to a local variable with the
+//!    widget's name.
+//! 2. Tell the tree's [`EventList`] which events each widget should receive, in order.
+//! 3. Wrap the tree in a [`TestHarness`], and feed it `WindowEvent`s.
+//! 4. Check the messages the widgets sent and the widgets' final rects.
+//!
+//! ```
+//! use cgmath_geometry::{cgmath::Point2, rect::BoundBox};
+//! use derin_core::{
+//!     test_widget_tree, WindowEvent,
+//!     event::{MouseHoverChange, WidgetEvent},
+//!     test_helpers::{EventList, TestEvent, TestHarness},
+//! };
+//!
+//! test_widget_tree!{
+//!     let event_list = EventList::new();
+//!     let tree = root {
+//!         rect: (0, 0, 40, 40);
+//!         child {rect: (10, 10, 30, 30)}
+//!     };
+//! }
+//!
+//! event_list.set_events(vec![
+//!     TestEvent {
+//!         widget: root,
+//!         event: WidgetEvent::MouseMove {
+//!             old_pos: Point2::new(-1, 5),
+//!             new_pos: Point2::new(1, 5),
+//!             in_widget: true,
+//!             hover_change: Some(MouseHoverChange::Enter),
+//!         },
+//!         source_child: vec![],
+//!     },
+//! ]);
+//!
+//! let mut harness = TestHarness::new(tree);
+//! harness.process_events(vec![
+//!     WindowEvent::MouseEnter,
+//!     WindowEvent::MouseMove(Point2::new(1, 5)),
+//! ]);
+//! assert_eq!(Some(BoundBox::new2(10, 10, 30, 30)), harness.widget_rect(child));
+//! ```
+//!
+//! If a widget receives an event that doesn't match the next event in the list, or the list isn't
+//! empty when the tree gets dropped, the test panics.
+//!
+//! [`test_widget_tree!`]: ../macro.test_widget_tree.html
+//! [`TestWidget`]: ./struct.TestWidget.html
+//! [`EventList`]: ./struct.EventList.html
+//! [`TestHarness`]: ./struct.TestHarness.html
+
 use crate::{
-    LoopFlow,
+    LoopFlow, WindowEvent,
     event::{EventOps, FocusChange, InputState, WidgetEvent, WidgetEventSourced},
+    event_translator::EventTranslator,
+    message_bus::MessageBus,
     render::{RenderFrameClipped, RenderFrame, Theme},
+    update_state::{UpdateState, UpdateStateCell},
     widget::*,
+    widget_traverser::WidgetTraverserBase,
 };
-use cgmath_geometry::{
-    D2,
-    rect::BoundBox,
-};
-use derin_common_types::{
-    buttons::Key,
-    layout::SizeBounds,
-};
-use indexmap::IndexMap;
+use derin_common_types::buttons::Key;
 use std::{
+    any::Any,
     cell::RefCell,
     ops::Drop,
     rc::Rc,
 };
 
-pub(crate) struct TestWidget {
+// Re-exported so that the test macros can be used from crates that don't depend on these.
+#[doc(hidden)]
+pub use cgmath_geometry::{D2, rect::BoundBox};
+#[doc(hidden)]
+pub use derin_common_types::layout::SizeBounds;
+#[doc(hidden)]
+pub use indexmap::IndexMap;
+
+/// A widget that checks the events it receives against an `EventList`.
+pub struct TestWidget {
     pub widget_tag: WidgetTag,
     pub rect: BoundBox<D2, i32>,
     pub size_bounds: SizeBounds,
@@ -35,25 +97,45 @@ pub(crate) struct TestWidget {
     /// - Right Arrow Key: Focus Next
     /// - Left Arrow Key: Focus Previous
     pub focus_controls: bool,
+    /// Bubbles all events to the parent widget.
+    pub bubble_events: bool,
+    /// Consumes every event headed for a descendant.
+    pub intercept_events: bool,
     pub children: Option<IndexMap<WidgetIdent, TestWidget>>,
 }
 
+/// The events a widget tree is expected to receive, in the order they're expected.
 #[derive(Clone)]
-pub(crate) struct EventList {
+pub struct EventList {
     events: Rc<RefCell<std::vec::IntoIter<TestEvent>>>,
 }
 
+/// An event a `TestWidget` is expected to receive.
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) struct TestEvent {
+pub struct TestEvent {
     pub widget: WidgetId,
     pub event: WidgetEvent,
     pub source_child: Vec<WidgetIdent>,
 }
 
 #[derive(Default)]
-pub(crate) struct TestRenderFrame {}
+pub struct TestRenderFrame {}
 #[derive(Default)]
-pub(crate) struct TestTheme {}
+pub struct TestTheme {}
+
+/// Drives a widget tree with `WindowEvent`s.
+///
+/// Messages sent by the widgets get recorded instead of delivered, so they can be checked with
+/// `take_messages`.
+pub struct TestHarness<W: Widget> {
+    pub root_widget: W,
+    event_translator: EventTranslator,
+    input_state: crate::InputState,
+    widget_traverser_base: WidgetTraverserBase<TestRenderFrame>,
+    message_bus: MessageBus,
+    update_state: Rc<UpdateStateCell>,
+    messages: Vec<(Box<Any>, Vec<MessageTarget>)>,
+}
 
 impl Theme for TestTheme {
     type Key = ();
@@ -86,6 +168,99 @@ impl Drop for EventList {
     }
 }
 
+impl<W: Widget> TestHarness<W> {
+    pub fn new(root_widget: W) -> TestHarness<W> {
+        let message_bus = MessageBus::new();
+        TestHarness {
+            event_translator: EventTranslator::new(),
+            input_state: crate::InputState::new(),
+            widget_traverser_base: WidgetTraverserBase::new(root_widget.widget_id()),
+            update_state: UpdateState::new(&message_bus),
+            message_bus,
+            messages: Vec::new(),
+            root_widget,
+        }
+    }
+
+    /// Translate a window event and dispatch the resulting events to the widget tree.
+    pub fn process_event(&mut self, event: WindowEvent) {
+        let TestHarness {
+            ref mut root_widget,
+            ref mut event_translator,
+            ref mut input_state,
+            ref mut widget_traverser_base,
+            ref mut message_bus,
+            ref update_state,
+            ref mut messages,
+        } = *self;
+
+        let mut widget_traverser = widget_traverser_base.with_root_ref(root_widget, update_state.clone());
+        event_translator
+            .with_data(
+                &mut widget_traverser,
+                input_state,
+                update_state.clone(),
+            )
+            .translate_window_event(event);
+
+        while let Some((message, targets)) = message_bus.next_message() {
+            let targets = targets.collect();
+            messages.push((message, targets));
+        }
+    }
+
+    pub fn process_events(&mut self, events: impl IntoIterator<Item=WindowEvent>) {
+        for event in events {
+            self.process_event(event);
+        }
+    }
+
+    /// Remove all recorded messages of type `M`, returning them in the order they were sent along
+    /// with their targets.
+    ///
+    /// Messages sent without a target have no targets, since the harness doesn't track which
+    /// widgets listen for which message types.
+    pub fn take_messages<M: 'static>(&mut self) -> Vec<(M, Vec<MessageTarget>)> {
+        let mut taken = Vec::new();
+        let mut i = 0;
+        while i < self.messages.len() {
+            match self.messages[i].0.is::<M>() {
+                true => {
+                    let (message, targets) = self.messages.remove(i);
+                    taken.push((*message.downcast::<M>().unwrap(), targets));
+                },
+                false => i += 1
+            }
+        }
+        taken
+    }
+
+    /// Retrieve a widget's rect, relative to the root widget's origin.
+    pub fn widget_rect(&mut self, widget_id: WidgetId) -> Option<BoundBox<D2, i32>> {
+        let mut widget_traverser = self.widget_traverser_base.with_root_ref(&mut self.root_widget, self.update_state.clone());
+        widget_traverser.get_widget(widget_id).map(|path| path.widget.rect())
+    }
+
+    /// Find the widget with the given automation ID.
+    pub fn find_automation_id(&mut self, automation_id: &str) -> Option<WidgetId> {
+        let mut widget_traverser = self.widget_traverser_base.with_root_ref(&mut self.root_widget, self.update_state.clone());
+        widget_traverser.find_automation_id(automation_id)
+    }
+
+    #[inline]
+    pub fn focused_widget(&self) -> Option<WidgetId> {
+        self.input_state.focused_widget
+    }
+}
+
+/// Create a widget tag with a specific ID. Used by `test_widget_tree!`.
+#[doc(hidden)]
+pub fn widget_tag_with_id(widget_id: WidgetId) -> WidgetTag {
+    let mut widget_tag = WidgetTag::new();
+    widget_tag.widget_id = widget_id;
+    widget_tag
+}
+
 impl RenderFrame for TestRenderFrame {
     type Theme = TestTheme;
     type Primitive = ();
@@ -146,10 +321,19 @@ impl Widget for TestWidget {
 
         EventOps {
             focus,
-            ..EventOps::default()
+            bubble: self.bubble_events,
         }
     }
 
+    fn intercept_child_event(
+        &mut self,
+        _event: &WidgetEvent,
+        _child_path: &[WidgetIdent],
+        _input_state: InputState,
+    ) -> bool {
+        self.intercept_events
+    }
+
     fn size_bounds(&self) -> SizeBounds {
         self.size_bounds
     }
@@ -210,56 +394,67 @@ impl Parent for TestWidget {
     }
 }
 
+#[doc(hidden)]
+#[macro_export]
 macro_rules! extract_widget_tree_idents {
     ($($widget_ident:ident {
         rect: ($x:expr, $y:expr, $w:expr, $h:expr)
         $(, focus_controls: $focus_controls:expr)?
+        $(, bubble_events: $bubble_events:expr)?
+        $(, intercept_events: $intercept_events:expr)?
         $(;$($children:tt)*)?
     }),*) => {$(
-        let $widget_ident = crate::widget::WidgetId::new();
+        let $widget_ident = $crate::widget::WidgetId::new();
         println!("widget {} = {:?}", stringify!($widget_ident), $widget_ident);
 
-        extract_widget_tree_idents!{$($($children)*)*}
+        $crate::extract_widget_tree_idents!{$($($children)*)*}
     )*};
 }
 
+/// Declare a tree of `TestWidget`s, binding each widget's ID to a variable with the widget's name.
+///
+/// See the [`test_helpers`](./test_helpers/index.html) module for an example.
+#[macro_export]
 macro_rules! test_widget_tree {
     (
         let $event_list:ident = $event_list_expr:expr;
         let $root_pat:pat = $root:ident {
             rect: ($x:expr, $y:expr, $w:expr, $h:expr)
             $(, focus_controls: $focus_controls:expr)?
+            $(, bubble_events: $bubble_events:expr)?
+            $(, intercept_events: $intercept_events:expr)?
             $(;$($rest:tt)*)?
         };
     ) => {
-        extract_widget_tree_idents!{
+        $crate::extract_widget_tree_idents!{
             $root {
                 rect: ($x, $y, $w, $h)
                 $(;$($rest)*)?
             }
         }
-        let $event_list: crate::test_helpers::EventList = $event_list_expr;
+        let $event_list: $crate::test_helpers::EventList = $event_list_expr;
         let $root_pat = {
             #[allow(unused_mut)]
             {
                 use std::sync::Arc;
-                let mut children = indexmap::IndexMap::new();
-                test_widget_tree!(
+                let mut children = $crate::test_helpers::IndexMap::new();
+                $crate::test_widget_tree!(
                     @insert
                     $event_list,
                     children,
                     $($($rest)*)*
                 );
 
-                let mut widget_tag = crate::widget::WidgetTag::new();
-                widget_tag.widget_id = $root;
+                let widget_tag = $crate::test_helpers::widget_tag_with_id($root);
 
-                let root = crate::test_helpers::TestWidget {
+                let root = $crate::test_helpers::TestWidget {
                     widget_tag,
-                    rect: cgmath_geometry::rect::BoundBox::new2($x, $y, $w, $h),
-                    size_bounds: derin_common_types::layout::SizeBounds::default(),
+                    rect: $crate::test_helpers::BoundBox::new2($x, $y, $w, $h),
+                    size_bounds: $crate::test_helpers::SizeBounds::default(),
                     event_list: $event_list.clone(),
                     focus_controls: $($focus_controls ||)? false,
+                    bubble_events: $($bubble_events ||)? false,
+                    intercept_events: $($intercept_events ||)? false,
                     children: match children.len() {
                         0 => None,
                         _ => Some(children)
@@ -274,33 +469,36 @@ macro_rules! test_widget_tree {
         $($child:ident {
             rect: ($x:expr, $y:expr, $w:expr, $h:expr)
             $(, focus_controls: $focus_controls:expr)?
+            $(, bubble_events: $bubble_events:expr)?
+            $(, intercept_events: $intercept_events:expr)?
             $(;$($children:tt)*)?
         }),*
     ) => {$({
-        let mut children = indexmap::IndexMap::new();
-        test_widget_tree!(
+        let mut children = $crate::test_helpers::IndexMap::new();
+        $crate::test_widget_tree!(
             @insert
             $event_list,
             children,
             $($($children)*)*
         );
 
-        let mut widget_tag = crate::widget::WidgetTag::new();
-        widget_tag.widget_id = $child;
+        let widget_tag = $crate::test_helpers::widget_tag_with_id($child);
 
-        let widget = crate::test_helpers::TestWidget {
+        let widget = $crate::test_helpers::TestWidget {
             widget_tag,
-            rect: cgmath_geometry::rect::BoundBox::new2($x, $y, $w, $h),
-            size_bounds: derin_common_types::layout::SizeBounds::default(),
+            rect: $crate::test_helpers::BoundBox::new2($x, $y, $w, $h),
+            size_bounds: $crate::test_helpers::SizeBounds::default(),
             event_list: $event_list.clone(),
             focus_controls: $($focus_controls ||)? false,
+            bubble_events: $($bubble_events ||)? false,
+            intercept_events: $($intercept_events ||)? false,
             children: match children.len() {
                 0 => None,
                 _ => Some(children)
             }
         };
 
-        $widget_map.insert(crate::widget::WidgetIdent::Str(Arc::from(stringify!($child))), widget);
+        $widget_map.insert($crate::widget::WidgetIdent::Str(Arc::from(stringify!($child))), widget);
     })*};
 }
 
@@ -369,4 +567,29 @@ mod tests {
             source_child: vec![],
         }])
     }
+
+    #[test]
+    fn harness_widget_rect() {
+        test_widget_tree!{
+            let event_list = EventList::new();
+            let tree = root {
+                rect: (0, 0, 100, 100);
+                outer {
+                    rect: (10, 10, 90, 90);
+                    inner {rect: (10, 10, 20, 20)}
+                }
+            };
+        }
+        let _ = event_list;
+
+        let mut harness = TestHarness::new(tree);
+        assert_eq!(Some(BoundBox::new2(0, 0, 100, 100)), harness.widget_rect(root));
+        assert_eq!(Some(BoundBox::new2(10, 10, 90, 90)), harness.widget_rect(outer));
+        assert_eq!(Some(BoundBox::new2(20, 20, 30, 30)), harness.widget_rect(inner));
+        assert_eq!(None, harness.widget_rect(WidgetId::new()));
+
+        harness.process_event(WindowEvent::KeyDown(Key::Enter));
+        assert_eq!(None, harness.focused_widget());
+        assert_eq!(0, harness.take_messages::<()>().len());
+    }
 }
@@ -13,7 +13,16 @@ id!(pub TimerId);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Timer {
     pub frequency: Duration,
-    start_time: Instant,
+    /// Stop the timer while the widget is entirely clipped or outside the window.
+    ///
+    /// A paused timer doesn't wake up the event loop, and `times_triggered` only counts the
+    /// triggers that got delivered. Once the widget comes back into view, the timer triggers
+    /// immediately and carries on from there, with its start time pushed back by however long it
+    /// was paused. Useful for animations, which don't need to advance while they can't be seen.
+    pub pause_offscreen: bool,
+    start_time: Cell<Instant>,
+    /// When the timer would have triggered if it hadn't been paused.
+    paused_at: Cell<Option<Instant>>,
     pub(crate) last_triggered: Cell<Option<Instant>>,
     pub(crate) times_triggered: Cell<u32>
 }
@@ -27,13 +36,18 @@ pub(crate) struct TimerTrigger {
 
 pub(crate) struct TimerTriggerTracker {
     timers_by_next_trigger: Vec<TimerTrigger>,
+    /// Timers that are paused because their widgets are offscreen, which don't get queued until
+    /// the widgets come back into view.
+    paused: Vec<(WidgetId, TimerId)>,
 }
 
 impl Timer {
     pub fn new(frequency: Duration) -> Timer {
         Timer {
             frequency,
-            start_time: Instant::now(),
+            pause_offscreen: false,
+            start_time: Cell::new(Instant::now()),
+            paused_at: Cell::new(None),
             last_triggered: Cell::new(None),
             times_triggered: Cell::new(0),
         }
@@ -41,7 +55,10 @@ impl Timer {
 
     pub fn new_delayed(frequency: Duration, start_time: Instant) -> Timer {
         Timer {
-            frequency, start_time,
+            frequency,
+            start_time: Cell::new(start_time),
+            pause_offscreen: false,
+            paused_at: Cell::new(None),
             last_triggered: Cell::new(None),
             times_triggered: Cell::new(0),
         }
@@ -49,7 +66,7 @@ impl Timer {
 
     #[inline(always)]
     pub fn start_time(&self) -> Instant {
-        self.start_time
+        self.start_time.get()
     }
     #[inline(always)]
     pub fn last_triggered(&self) -> Option<Instant> {
@@ -61,7 +78,23 @@ impl Timer {
     }
 
     pub fn next_trigger(&self) -> Instant {
-        self.start_time + self.frequency * self.times_triggered()
+        self.start_time.get() + self.frequency * self.times_triggered()
+    }
+
+    /// Pause the timer, which was due to trigger at `trigger_time`.
+    pub(crate) fn pause(&self, trigger_time: Instant) {
+        if self.paused_at.get().is_none() {
+            self.paused_at.set(Some(trigger_time));
+        }
+    }
+
+    /// Resume the timer, pushing its schedule back so that it next triggers at `now`.
+    pub(crate) fn resume(&self, now: Instant) {
+        if let Some(paused_at) = self.paused_at.take() {
+            if now > paused_at {
+                self.start_time.set(self.start_time.get() + (now - paused_at));
+            }
+        }
     }
 }
 
@@ -75,6 +108,7 @@ impl TimerTriggerTracker {
     pub fn new() -> TimerTriggerTracker {
         TimerTriggerTracker {
             timers_by_next_trigger: Vec::new(),
+            paused: Vec::new(),
         }
     }
 
@@ -106,4 +140,41 @@ impl TimerTriggerTracker {
 
         self.timers_by_next_trigger.insert(insert_location, timer_trigger);
     }
+
+    pub fn pause(&mut self, widget_id: WidgetId, timer_id: TimerId) {
+        if !self.paused.contains(&(widget_id, timer_id)) {
+            self.paused.push((widget_id, timer_id));
+        }
+    }
+
+    pub fn take_paused(&mut self) -> Vec<(WidgetId, TimerId)> {
+        std::mem::replace(&mut self.paused, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_resume() {
+        let start = Instant::now();
+        let frequency = Duration::from_millis(10);
+        let timer = Timer::new_delayed(frequency, start);
+        timer.times_triggered.set(2);
+        assert_eq!(start + frequency * 2, timer.next_trigger());
+
+        // The timer stays paused from the first time it was due, however many times it gets
+        // paused.
+        timer.pause(start + frequency * 2);
+        timer.pause(start + frequency * 5);
+        let now = start + frequency * 20;
+        timer.resume(now);
+        assert_eq!(now, timer.next_trigger());
+        assert_eq!(2, timer.times_triggered());
+
+        // Resuming a timer that isn't paused doesn't move it.
+        timer.resume(now + frequency);
+        assert_eq!(now, timer.next_trigger());
+    }
 }
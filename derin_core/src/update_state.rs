@@ -3,17 +3,33 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::{
+    accessibility::{AccessibilityPrefs, Announcement, Politeness},
+    activity::ActivityTracker,
+    caret::CaretBlink,
+    drag::DragPayload,
+    event::WidgetEvent,
+    idle::IdleScheduler,
+    listener::GlobalListeners,
+    modal::ModalStack,
     message_bus::{Message, MessageTarget, MessageTargeted, MessageBus},
+    shortcut::{ShortcutError, ShortcutId, ShortcutScope, ShortcutTable},
+    sound::UiSound,
+    task::{BoxedTask, PendingTask, TaskId, TaskTracker},
     cgmath::Point2,
-    widget::WidgetId,
+    widget::{RelayoutScope, WidgetId},
+};
+use cgmath_geometry::{D2, rect::BoundBox};
+use derin_common_types::{
+    buttons::KeyChord,
+    cursor::CursorIcon,
 };
-use derin_common_types::cursor::CursorIcon;
 use fnv::FnvHashSet;
 use std::{
     mem,
     rc::{Rc, Weak},
     sync::mpsc::Sender,
     cell::RefCell,
+    time::Instant,
 };
 
 /// `UpdateState` handle that gets stored in a `WidgetTag`. Used to access and modify global update
@@ -27,20 +43,61 @@ pub(crate) enum UpdateStateShared<R = Weak<UpdateStateCell>> {
 #[derive(Debug, Default)]
 pub(crate) struct UpdateStateVacant {
     buffered_messages: Vec<MessageTargeted>,
+    idle_work_requested: bool,
+    modal_requested: bool,
+    pending_tasks: Vec<PendingTask>,
 }
 
 pub(crate) type UpdateStateCell = RefCell<UpdateState>;
 
-#[derive(Debug)]
-pub(crate) struct UpdateState {
+/// Requests made by widgets and the event translator that get acted on later in the frame.
+///
+/// These are kept apart from the rest of the update state so that everything requested while
+/// processing a single event can be set aside, and thrown out if the event panics. See the
+/// [`recovery`](../recovery/index.html) module.
+#[derive(Debug, Default)]
+pub(crate) struct UpdateRequests {
     pub redraw: FnvHashSet<WidgetId>,
+    /// Widget-local regions that need to be redrawn. Only used if `redraw` is empty.
+    pub redraw_rects: Vec<(WidgetId, BoundBox<D2, i32>)>,
     pub relayout: FnvHashSet<WidgetId>,
+    /// Widgets that need their parent re-laid out, even if their size doesn't change.
+    pub relayout_parent: FnvHashSet<WidgetId>,
+    /// Widgets that need to be re-laid out alongside all their descendants.
+    pub relayout_subtree: FnvHashSet<WidgetId>,
     pub update_timers: FnvHashSet<WidgetId>,
     pub update_messages: FnvHashSet<WidgetId>,
     pub remove_from_tree: FnvHashSet<WidgetId>,
+    /// Widgets whose entire subtree needs to be rescanned into the virtual widget tree.
+    pub rescan: FnvHashSet<WidgetId>,
     pub set_cursor_icon: Option<CursorIcon>,
     pub set_cursor_pos: Option<(WidgetId, Point2<i32>)>,
+    pub set_ime_caret_rect: Option<(WidgetId, BoundBox<D2, i32>)>,
+    /// The widget that called `WidgetTag::start_drag`, and the value it's dragging.
+    pub start_drag: Option<(WidgetId, DragPayload)>,
+    pub announcements: Vec<Announcement>,
+    pub sounds: Vec<UiSound>,
+    /// Tasks that were spawned since the requests were last merged. Tasks start running as soon
+    /// as they're spawned, so these are only kept around to cancel them if they get rolled back.
+    pub spawned_tasks: Vec<(WidgetId, TaskId)>,
+}
+
+#[derive(Debug)]
+pub(crate) struct UpdateState {
+    pub requests: UpdateRequests,
     pub message_sender: Sender<MessageTargeted>,
+    pub accessibility_prefs: AccessibilityPrefs,
+    pub mnemonics_visible: bool,
+    pub activity: ActivityTracker,
+    pub tasks: TaskTracker,
+    pub global_listeners: GlobalListeners,
+    // These are reference-counted so that a recovery checkpoint can hold onto them without copying
+    // them. They only get copied if they're modified while the checkpoint is alive, so they should
+    // be modified through the `_mut` methods below.
+    pub caret_blink: Rc<CaretBlink>,
+    pub idle_work: Rc<IdleScheduler>,
+    pub shortcuts: Rc<ShortcutTable>,
+    pub modals: Rc<ModalStack>,
     pub global_update: bool,
 }
 
@@ -53,24 +110,50 @@ impl UpdateState {
     pub fn new(message_bus: &MessageBus) -> Rc<UpdateStateCell> {
         Rc::new(
             RefCell::new(UpdateState {
-                redraw: FnvHashSet::default(),
-                relayout: FnvHashSet::default(),
-                update_timers: FnvHashSet::default(),
-                update_messages: FnvHashSet::default(),
-                remove_from_tree: FnvHashSet::default(),
-                set_cursor_icon: None,
-                set_cursor_pos: None,
+                requests: UpdateRequests::default(),
                 message_sender: message_bus.sender(),
+                accessibility_prefs: AccessibilityPrefs::default(),
+                mnemonics_visible: false,
+                activity: ActivityTracker::new(),
+                tasks: TaskTracker::new(),
+                global_listeners: GlobalListeners::new(),
+                caret_blink: Rc::new(CaretBlink::new()),
+                idle_work: Rc::new(IdleScheduler::new()),
+                shortcuts: Rc::new(ShortcutTable::new()),
+                modals: Rc::new(ModalStack::new()),
                 global_update: true,
             })
         )
     }
 
+    pub fn caret_blink_mut(&mut self) -> &mut CaretBlink {
+        Rc::make_mut(&mut self.caret_blink)
+    }
+
+    pub fn idle_work_mut(&mut self) -> &mut IdleScheduler {
+        Rc::make_mut(&mut self.idle_work)
+    }
+
+    pub fn shortcuts_mut(&mut self) -> &mut ShortcutTable {
+        Rc::make_mut(&mut self.shortcuts)
+    }
+
+    pub fn modals_mut(&mut self) -> &mut ModalStack {
+        Rc::make_mut(&mut self.modals)
+    }
+
+    /// Spawn a task, recording it in `requests` so that it can be cancelled if the request gets
+    /// rolled back.
+    pub fn spawn_task(&mut self, widget_id: WidgetId, task_id: TaskId, task: BoxedTask) {
+        self.tasks.spawn(widget_id, task_id, task);
+        self.requests.spawned_tasks.push((widget_id, task_id));
+    }
+
     fn queue_insert_id(&mut self, id: WidgetId) {
-        self.redraw.insert(id);
-        self.relayout.insert(id);
-        self.update_timers.insert(id);
-        self.update_messages.insert(id);
+        self.requests.redraw.insert(id);
+        self.requests.relayout.insert(id);
+        self.requests.update_timers.insert(id);
+        self.requests.update_messages.insert(id);
     }
 
     pub fn queue_global_update(&mut self) {
@@ -82,10 +165,80 @@ impl UpdateState {
     }
 }
 
+impl UpdateRequests {
+    /// Add the requests in `newer`, which were made after the ones in `self`.
+    pub fn merge(&mut self, newer: UpdateRequests) {
+        // Every field gets named here, so new requests can't be added without deciding how they
+        // get merged.
+        let UpdateRequests {
+            redraw,
+            redraw_rects,
+            relayout,
+            relayout_parent,
+            relayout_subtree,
+            update_timers,
+            update_messages,
+            remove_from_tree,
+            rescan,
+            set_cursor_icon,
+            set_cursor_pos,
+            set_ime_caret_rect,
+            start_drag,
+            announcements,
+            sounds,
+            spawned_tasks: _,
+        } = newer;
+
+        // Widgets that were removed since the older requests were made don't need any of the
+        // updates that were queued for them. They're re-queued in `newer` if they got re-inserted.
+        for id in &remove_from_tree {
+            self.forget_widget(*id);
+        }
+
+        self.redraw.extend(redraw);
+        self.redraw_rects.extend(redraw_rects);
+        self.relayout.extend(relayout);
+        self.relayout_parent.extend(relayout_parent);
+        self.relayout_subtree.extend(relayout_subtree);
+        self.update_timers.extend(update_timers);
+        self.update_messages.extend(update_messages);
+        self.remove_from_tree.extend(remove_from_tree);
+        self.rescan.extend(rescan);
+        if set_cursor_icon.is_some() {
+            self.set_cursor_icon = set_cursor_icon;
+        }
+        if set_cursor_pos.is_some() {
+            self.set_cursor_pos = set_cursor_pos;
+        }
+        if set_ime_caret_rect.is_some() {
+            self.set_ime_caret_rect = set_ime_caret_rect;
+        }
+        if start_drag.is_some() {
+            self.start_drag = start_drag;
+        }
+        self.announcements.extend(announcements);
+        self.sounds.extend(sounds);
+        // Once merged, the tasks can't be rolled back on their own anymore.
+        self.spawned_tasks.clear();
+    }
+
+    fn forget_widget(&mut self, id: WidgetId) {
+        self.redraw.remove(&id);
+        self.relayout.remove(&id);
+        self.relayout_parent.remove(&id);
+        self.relayout_subtree.remove(&id);
+        self.update_timers.remove(&id);
+        self.update_messages.remove(&id);
+    }
+}
+
 impl UpdateStateShared {
     pub fn new() -> UpdateStateShared {
         UpdateStateShared::Vacant(UpdateStateVacant {
             buffered_messages: Vec::new(),
+            idle_work_requested: false,
+            modal_requested: false,
+            pending_tasks: Vec::new(),
         })
     }
 
@@ -128,20 +281,41 @@ impl UpdateStateShared {
                     for message in vacant.buffered_messages.drain(..) {
                         parent_state.message_sender.send(message).ok();
                     }
+                    if vacant.idle_work_requested {
+                        parent_state.idle_work_mut().queue_work(id);
+                    }
+                    if vacant.modal_requested {
+                        parent_state.modals_mut().push(id);
+                    }
+                    for PendingTask{ task_id, task } in vacant.pending_tasks.drain(..) {
+                        parent_state.spawn_task(id, task_id, task);
+                    }
                 }
 
                 *this = UpdateStateShared::Occupied(parent_state.clone())
             },
             UpdateStateShared::Occupied(old_state) => {
                 if !Rc::ptr_eq(&old_state, &parent_state) {
+                    let had_idle_work;
                     {
                         let mut old_state = old_state.borrow_mut();
-                        old_state.redraw.remove(&id);
-                        old_state.relayout.remove(&id);
-                        old_state.remove_from_tree.insert(id);
+                        old_state.requests.redraw.remove(&id);
+                        old_state.requests.relayout.remove(&id);
+                        old_state.requests.relayout_parent.remove(&id);
+                        old_state.requests.relayout_subtree.remove(&id);
+                        old_state.requests.rescan.remove(&id);
+                        old_state.caret_blink_mut().widgets.remove(&id);
+                        had_idle_work = old_state.idle_work_mut().remove(id);
+                        old_state.requests.remove_from_tree.insert(id);
                     }
 
-                    parent_state.borrow_mut().queue_insert_id(id);
+                    {
+                        let mut parent_state = parent_state.borrow_mut();
+                        parent_state.queue_insert_id(id);
+                        if had_idle_work {
+                            parent_state.idle_work_mut().queue_work(id);
+                        }
+                    }
                     *this = UpdateStateShared::Occupied(parent_state.clone());
                 }
             }
@@ -152,7 +326,7 @@ impl UpdateStateShared {
         self.upgrade(|this| match this {
             UpdateStateShared::Occupied(update_state) => {
                 let mut update_state = update_state.borrow_mut();
-                update_state.redraw.insert(id);
+                update_state.requests.redraw.insert(id);
             },
             // All updates are automatically performed on a fresh insert so we don't need to log that
             // an update was requested.
@@ -164,7 +338,45 @@ impl UpdateStateShared {
         self.upgrade(|this| match this {
             UpdateStateShared::Occupied(update_state) => {
                 let mut update_state = update_state.borrow_mut();
-                update_state.relayout.insert(id);
+                update_state.requests.relayout.insert(id);
+            },
+            // Ditto.
+            UpdateStateShared::Vacant(_) => ()
+        });
+    }
+
+    pub fn request_redraw_rect(&mut self, id: WidgetId, rect: BoundBox<D2, i32>) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.requests.redraw_rects.push((id, rect));
+            },
+            // Ditto.
+            UpdateStateShared::Vacant(_) => ()
+        });
+    }
+
+    pub fn request_relayout_scope(&mut self, id: WidgetId, scope: RelayoutScope) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.requests.relayout.insert(id);
+                match scope {
+                    RelayoutScope::This => (),
+                    RelayoutScope::Parent => {update_state.requests.relayout_parent.insert(id);},
+                    RelayoutScope::Subtree => {update_state.requests.relayout_subtree.insert(id);},
+                }
+            },
+            // Ditto.
+            UpdateStateShared::Vacant(_) => ()
+        });
+    }
+
+    pub fn request_rescan(&mut self, id: WidgetId) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.requests.rescan.insert(id);
             },
             // Ditto.
             UpdateStateShared::Vacant(_) => ()
@@ -175,18 +387,71 @@ impl UpdateStateShared {
         self.upgrade(|this| match this {
             UpdateStateShared::Occupied(update_state) => {
                 let mut update_state = update_state.borrow_mut();
-                update_state.update_timers.insert(id);
+                update_state.requests.update_timers.insert(id);
             },
             // Ditto.
             UpdateStateShared::Vacant(_) => ()
         });
     }
 
+    pub fn request_idle_work(&mut self, id: WidgetId) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.idle_work_mut().queue_work(id);
+            },
+            // Unlike the other updates, idle work isn't automatically performed on insert, so
+            // remember that it was requested.
+            UpdateStateShared::Vacant(vacant) => vacant.idle_work_requested = true
+        });
+    }
+
+    pub fn push_modal(&mut self, id: WidgetId) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => update_state.borrow_mut().modals_mut().push(id),
+            // Like idle work, remember the request until the widget gets inserted.
+            UpdateStateShared::Vacant(vacant) => vacant.modal_requested = true
+        });
+    }
+
+    pub fn remove_modal(&mut self, id: WidgetId) -> bool {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => update_state.borrow_mut().modals_mut().remove(id),
+            UpdateStateShared::Vacant(vacant) => mem::replace(&mut vacant.modal_requested, false)
+        })
+    }
+
+    pub fn is_modal(&mut self, id: WidgetId) -> bool {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => update_state.borrow().modals.contains(id),
+            UpdateStateShared::Vacant(vacant) => vacant.modal_requested
+        })
+    }
+
+    pub fn is_top_modal(&mut self, id: WidgetId) -> bool {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => update_state.borrow().modals.top() == Some(id),
+            UpdateStateShared::Vacant(_) => false
+        })
+    }
+
+    pub fn spawn_task(&mut self, id: WidgetId, task_id: TaskId, task: BoxedTask) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.spawn_task(id, task_id, task);
+            },
+            // The task gets tied to the widget's lifetime once the widget's inserted into a tree,
+            // so hold off on starting it until then.
+            UpdateStateShared::Vacant(vacant) => vacant.pending_tasks.push(PendingTask{ task_id, task })
+        });
+    }
+
     pub fn request_update_messages(&mut self, id: WidgetId) {
         self.upgrade(|this| match this {
             UpdateStateShared::Occupied(update_state) => {
                 let mut update_state = update_state.borrow_mut();
-                update_state.update_messages.insert(id);
+                update_state.requests.update_messages.insert(id);
             },
             // Ditto.
             UpdateStateShared::Vacant(_) => ()
@@ -213,7 +478,7 @@ impl UpdateStateShared {
         self.upgrade(|this| match this {
             UpdateStateShared::Occupied(update_state) => {
                 let mut update_state = update_state.borrow_mut();
-                update_state.set_cursor_pos = Some((id, pos));
+                update_state.requests.set_cursor_pos = Some((id, pos));
                 Ok(())
             },
             UpdateStateShared::Vacant(_) => Err(UpdateError::NoRootWidget)
@@ -224,22 +489,138 @@ impl UpdateStateShared {
         self.upgrade(|this| match this {
             UpdateStateShared::Occupied(update_state) => {
                 let mut update_state = update_state.borrow_mut();
-                update_state.set_cursor_icon = Some(icon);
+                update_state.requests.set_cursor_icon = Some(icon);
+                Ok(())
+            },
+            UpdateStateShared::Vacant(_) => Err(UpdateError::NoRootWidget)
+        })
+    }
+
+    pub fn request_set_ime_caret_rect(&mut self, id: WidgetId, rect: BoundBox<D2, i32>) -> Result<(), UpdateError> {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.requests.set_ime_caret_rect = Some((id, rect));
+                Ok(())
+            },
+            UpdateStateShared::Vacant(_) => Err(UpdateError::NoRootWidget)
+        })
+    }
+
+    pub fn request_start_drag(&mut self, id: WidgetId, payload: DragPayload) -> Result<(), UpdateError> {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.requests.start_drag = Some((id, payload));
+                Ok(())
+            },
+            UpdateStateShared::Vacant(_) => Err(UpdateError::NoRootWidget)
+        })
+    }
+
+    pub fn request_announce(&mut self, id: WidgetId, text: String, politeness: Politeness) -> Result<(), UpdateError> {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.requests.announcements.push(Announcement{ text, politeness, widget_id: id });
+                Ok(())
+            },
+            UpdateStateShared::Vacant(_) => Err(UpdateError::NoRootWidget)
+        })
+    }
+
+    pub fn request_play_sound(&mut self, sound: UiSound) -> Result<(), UpdateError> {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                if !update_state.accessibility_prefs.mute_sounds {
+                    update_state.requests.sounds.push(sound);
+                }
                 Ok(())
             },
             UpdateStateShared::Vacant(_) => Err(UpdateError::NoRootWidget)
         })
     }
 
+    pub fn accessibility_prefs(&mut self) -> AccessibilityPrefs {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => update_state.borrow().accessibility_prefs,
+            UpdateStateShared::Vacant(_) => AccessibilityPrefs::default()
+        })
+    }
+
+    pub fn notify_global_listeners(&mut self, event: &WidgetEvent, widget_id: WidgetId) {
+        self.upgrade(|this| if let UpdateStateShared::Occupied(update_state) = this {
+            update_state.borrow().global_listeners.notify(event, widget_id);
+        })
+    }
+
+    pub fn register_shortcut(&mut self, id: WidgetId, chord: KeyChord, message: Box<Fn() -> Message>) -> Result<ShortcutId, ShortcutError> {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) =>
+                update_state.borrow_mut().shortcuts_mut().add(chord, ShortcutScope::Subtree(id), message),
+            UpdateStateShared::Vacant(_) => Err(ShortcutError::NoRootWidget)
+        })
+    }
+
+    pub fn remove_shortcut(&mut self, shortcut_id: ShortcutId) -> bool {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => update_state.borrow_mut().shortcuts_mut().remove(shortcut_id),
+            UpdateStateShared::Vacant(_) => false
+        })
+    }
+
+    pub fn mnemonics_visible(&mut self) -> bool {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => update_state.borrow().mnemonics_visible,
+            UpdateStateShared::Vacant(_) => false
+        })
+    }
+
+    pub fn caret_visible(&mut self) -> bool {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let update_state = update_state.borrow();
+                update_state.caret_blink.visible(Instant::now(), update_state.accessibility_prefs.reduced_motion)
+            },
+            UpdateStateShared::Vacant(_) => true
+        })
+    }
+
+    pub fn set_caret_blinking(&mut self, id: WidgetId, blinking: bool) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                match blinking {
+                    true => update_state.caret_blink_mut().widgets.insert(id),
+                    false => update_state.caret_blink_mut().widgets.remove(&id),
+                };
+            },
+            UpdateStateShared::Vacant(_) => ()
+        });
+    }
+
+    pub fn reset_caret_blink(&mut self) {
+        self.upgrade(|this| match this {
+            UpdateStateShared::Occupied(update_state) => {
+                let mut update_state = update_state.borrow_mut();
+                update_state.caret_blink_mut().reset(Instant::now());
+                // Every caret in the tree becomes visible, not just the one that was reset.
+                let UpdateState{ ref mut requests, ref caret_blink, .. } = *update_state;
+                requests.redraw.extend(caret_blink.widgets.iter().cloned());
+                requests.relayout.extend(caret_blink.widgets.iter().cloned());
+            },
+            UpdateStateShared::Vacant(_) => ()
+        });
+    }
+
     pub fn remove_from_tree(&mut self, id: WidgetId) {
         self.upgrade(|this| match this {
             UpdateStateShared::Occupied(update_state) => {
                 let mut update_state = update_state.borrow_mut();
-                update_state.redraw.remove(&id);
-                update_state.relayout.remove(&id);
-                update_state.update_timers.remove(&id);
-                update_state.update_messages.remove(&id);
-                update_state.remove_from_tree.insert(id);
+                update_state.requests.forget_widget(id);
+                update_state.caret_blink_mut().widgets.remove(&id);
+                update_state.requests.remove_from_tree.insert(id);
             },
             UpdateStateShared::Vacant(_) => ()
         });
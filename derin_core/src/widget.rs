@@ -11,13 +11,23 @@ pub use crate::{
 
 use crate::{
     LoopFlow,
-    event::{WidgetEventSourced, EventOps, InputState},
+    accessibility::{AccessibilityPrefs, Politeness},
+    ambient::{Ambient, AmbientValue, Enabled},
+    drag::DragPayload,
+    event::{WidgetEvent, WidgetEventSourced, EventOps, InputState},
+    gesture::GestureSet,
+    handle::{HandleAccess, WidgetHandle},
     message_bus::{WidgetMessageKey, WidgetMessageFn},
     render::{Renderer, WidgetTheme},
+    request::{Reply, Request},
+    shortcut::{ShortcutError, ShortcutId},
+    sound::UiSound,
+    task::{self, TaskId},
     timer::{TimerId, Timer},
     update_state::{UpdateStateShared, UpdateStateCell},
 };
 use derin_common_types::{
+    buttons::{Key, KeyChord},
     cursor::CursorIcon,
     layout::SizeBounds,
 };
@@ -36,6 +46,7 @@ use cgmath_geometry::{
     cgmath::Point2,
 };
 use fnv::FnvHashMap;
+use futures::Future;
 
 
 pub(crate) const ROOT_IDENT: WidgetIdent = WidgetIdent::Num(0);
@@ -44,7 +55,10 @@ pub enum WidgetIdent {
     Str(Arc<str>),
     Num(u32),
     StrCollection(Arc<str>, u32),
-    NumCollection(u32, u32)
+    NumCollection(u32, u32),
+    /// An item in a keyed collection, identified by its key instead of its position.
+    StrKeyed(Arc<str>, u64),
+    NumKeyed(u32, u64)
 }
 
 pub struct WidgetTag {
@@ -52,6 +66,17 @@ pub struct WidgetTag {
     registered_messages: FnvHashMap<WidgetMessageKey, Cell<SmallVec<[WidgetMessageFn; 1]>>>,
     pub(crate) widget_id: WidgetId,
     pub(crate) timers: FnvHashMap<TimerId, Timer>,
+    pub(crate) help_topic: Option<Arc<str>>,
+    pub(crate) automation_id: Option<Arc<str>>,
+    pub(crate) mnemonic: Option<Key>,
+    pub(crate) focus_scope: bool,
+    pub(crate) focusable: bool,
+    pub(crate) scope_button: Option<ScopeButton>,
+    pub(crate) input_transparent: bool,
+    pub(crate) opaque: bool,
+    pub(crate) drop_types: Vec<TypeId>,
+    pub(crate) gestures: GestureSet,
+    pub(crate) ambient: RefCell<Ambient>,
 }
 
 impl fmt::Debug for WidgetTag {
@@ -72,6 +97,39 @@ impl Clone for WidgetTag {
 
 id!(pub WidgetId);
 
+/// Which widgets get re-laid out when calling `WidgetTag::request_relayout_scope`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelayoutScope {
+    /// Only re-layout this widget. The parent is still re-laid out if this widget's size bounds no
+    /// longer contain its rect. Equivalent to `WidgetTag::request_relayout`.
+    This,
+    /// Re-layout this widget and all of its descendants.
+    Subtree,
+    /// Re-layout this widget and its parent, even if this widget's size bounds haven't changed.
+    Parent,
+}
+
+/// A button that gets activated by a key pressed anywhere in its focus scope, as set by
+/// `WidgetTag::set_scope_button`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScopeButton {
+    /// Activated by `Enter`. Generally used for a dialog's "OK" button.
+    Default,
+    /// Activated by `Escape`. Generally used for a dialog's "Cancel" button.
+    Cancel,
+}
+
+impl ScopeButton {
+    /// Retrieves the scope button activated by the given key, if any.
+    pub fn from_key(key: Key) -> Option<ScopeButton> {
+        match key {
+            Key::Enter => Some(ScopeButton::Default),
+            Key::Escape => Some(ScopeButton::Cancel),
+            _ => None
+        }
+    }
+}
+
 
 /// The base widget trait.
 ///
@@ -91,12 +149,36 @@ pub trait Widget: 'static {
         input_state: InputState,
     ) -> EventOps;
 
+    /// Called before an event reaches one of this widget's descendants, so the widget can observe
+    /// the event or keep the descendant from seeing it. Returns `true` to consume the event.
+    ///
+    /// `child_path` is the path from this widget to the descendant the event was dispatched to.
+    /// Ancestors get to intercept the event in order, starting with the root. Events get
+    /// intercepted on their way down the tree, not when they bubble back up. Mouse movement,
+    /// focus, timer and idle work events can't be intercepted.
+    ///
+    /// Useful for containers that block input to their contents, such as disabled panels or the
+    /// parts of a window covered by a modal dialog. Doesn't intercept anything by default.
+    fn intercept_child_event(
+        &mut self,
+        _event: &WidgetEvent,
+        _child_path: &[WidgetIdent],
+        _input_state: InputState,
+    ) -> bool {
+        false
+    }
+
     fn size_bounds(&self) -> SizeBounds {
         SizeBounds::default()
     }
 
     #[doc(hidden)]
     fn dispatch_message(&mut self, message: &Any) {
+        if let Some(access) = message.downcast_ref::<HandleAccess>() {
+            dynamic::to_any(self, |w| access.run(w));
+            return;
+        }
+
         let message_key = WidgetMessageKey::from_dyn_message::<Self>(message);
 
         // We have to pull the `message_fns` list out of the widget tag so that we can pass self
@@ -155,6 +237,14 @@ impl<W> Widget for Box<W>
     ) -> EventOps {
         W::on_widget_event(self, event, input_state)
     }
+    fn intercept_child_event(
+        &mut self,
+        event: &WidgetEvent,
+        child_path: &[WidgetIdent],
+        input_state: InputState,
+    ) -> bool {
+        W::intercept_child_event(self, event, child_path, input_state)
+    }
 
     fn size_bounds(&self) -> SizeBounds {
         W::size_bounds(self)
@@ -165,6 +255,69 @@ impl<W> Widget for Box<W>
     }
 }
 
+/// A widget whose type is only known at runtime.
+///
+/// Unlike `Box<dyn Widget>`, this remembers how to render the widget and how to traverse its
+/// children with the renderer `R`, so it can be used for containers whose children are assembled
+/// at runtime.
+pub struct BoxedWidget<R: Renderer> {
+    pub(crate) widget: Box<dyn WidgetDyn<R>>,
+}
+
+impl<R: Renderer> BoxedWidget<R> {
+    pub fn new<W: Widget>(widget: W) -> BoxedWidget<R> {
+        BoxedWidget {
+            widget: Box::new(widget)
+        }
+    }
+
+    #[inline]
+    pub fn widget(&self) -> &Widget {
+        self.widget.to_widget()
+    }
+
+    #[inline]
+    pub fn widget_mut(&mut self) -> &mut Widget {
+        self.widget.to_widget_mut()
+    }
+}
+
+impl<R: Renderer> Widget for BoxedWidget<R> {
+    #[inline]
+    fn widget_tag(&self) -> &WidgetTag {
+        self.widget.widget_tag()
+    }
+    fn rect(&self) -> BoundBox<D2, i32> {
+        self.widget.rect()
+    }
+    fn rect_mut(&mut self) -> &mut BoundBox<D2, i32> {
+        self.widget.rect_mut()
+    }
+    fn on_widget_event(
+        &mut self,
+        event: WidgetEventSourced<'_>,
+        input_state: InputState,
+    ) -> EventOps {
+        self.widget.on_widget_event(event, input_state)
+    }
+    fn intercept_child_event(
+        &mut self,
+        event: &WidgetEvent,
+        child_path: &[WidgetIdent],
+        input_state: InputState,
+    ) -> bool {
+        self.widget.intercept_child_event(event, child_path, input_state)
+    }
+
+    fn size_bounds(&self) -> SizeBounds {
+        self.widget.size_bounds()
+    }
+
+    fn dispatch_message(&mut self, message: &Any) {
+        self.widget.dispatch_message(message)
+    }
+}
+
 pub struct WidgetInfo<'a, R: Renderer, S: ?Sized=Widget> {
     pub ident: WidgetIdent,
     pub index: usize,
@@ -450,6 +603,10 @@ impl WidgetIdent {
     pub fn new_str_collection(s: &str, i: u32) -> WidgetIdent {
         WidgetIdent::StrCollection(Arc::from(s), i)
     }
+
+    pub fn new_str_keyed(s: &str, key: u64) -> WidgetIdent {
+        WidgetIdent::StrKeyed(Arc::from(s), key)
+    }
 }
 
 impl WidgetTag {
@@ -460,6 +617,17 @@ impl WidgetTag {
             widget_id: WidgetId::new(),
             registered_messages: FnvHashMap::default(),
             timers: FnvHashMap::default(),
+            help_topic: None,
+            automation_id: None,
+            mnemonic: None,
+            focus_scope: false,
+            focusable: false,
+            scope_button: None,
+            input_transparent: false,
+            opaque: false,
+            drop_types: Vec::new(),
+            gestures: GestureSet::empty(),
+            ambient: RefCell::new(Ambient::new()),
         }
     }
 
@@ -474,12 +642,81 @@ impl WidgetTag {
         self
     }
 
+    /// Redraw the given region of the widget, in widget-local coordinates.
+    ///
+    /// Renderers that support partial redraws only redraw the requested regions, as long as no
+    /// widget requests a full redraw in the same frame.
+    #[inline]
+    pub fn request_redraw_rect(&mut self, rect: BoundBox<D2, i32>) -> &mut WidgetTag {
+        self.update_state.get_mut().request_redraw_rect(self.widget_id, rect);
+        self
+    }
+
     #[inline]
     pub fn request_relayout(&mut self) -> &mut WidgetTag {
         self.update_state.get_mut().request_relayout(self.widget_id);
         self
     }
 
+    #[inline]
+    pub fn request_relayout_scope(&mut self, scope: RelayoutScope) -> &mut WidgetTag {
+        self.update_state.get_mut().request_relayout_scope(self.widget_id, scope);
+        self
+    }
+
+    /// Rescan the widget's entire subtree once the widget has been updated.
+    ///
+    /// Derin only rescans the children of widgets that have been accessed mutably, and only
+    /// descends into children that were added, moved, or renamed. Widgets that directly change the
+    /// children of their descendants (instead of letting the descendants change their own
+    /// children) need to call this so the changes get picked up.
+    #[inline]
+    pub fn request_rescan(&mut self) -> &mut WidgetTag {
+        self.update_state.get_mut().request_rescan(self.widget_id);
+        self
+    }
+
+    /// Queue an `IdleWork` event, which gets delivered once the frame's other events have been
+    /// processed and there's time left in the frame budget.
+    ///
+    /// Long-running work should be split into slices: do work until the event's deadline, then
+    /// call this again if there's work left over. Widgets with pending work take turns, so one
+    /// widget's task can't hold up the others.
+    #[inline]
+    pub fn request_idle_work(&mut self) -> &mut WidgetTag {
+        self.update_state.get_mut().request_idle_work(self.widget_id);
+        self
+    }
+
+    /// Make this widget modal, restricting input to it and its descendants until `remove_modal`
+    /// gets called or the widget is removed from the tree. Can be called before the widget has
+    /// been added to a `Root`.
+    ///
+    /// See the [`modal`](../modal/index.html) module for more information.
+    #[inline]
+    pub fn push_modal(&mut self) -> &mut WidgetTag {
+        self.update_state.get_mut().push_modal(self.widget_id);
+        self
+    }
+
+    /// Stop this widget from being modal, returning whether it was modal.
+    #[inline]
+    pub fn remove_modal(&mut self) -> bool {
+        self.update_state.get_mut().remove_modal(self.widget_id)
+    }
+
+    /// Whether this widget is on the modal stack.
+    #[inline]
+    pub fn is_modal(&self) -> bool {
+        self.update_state.borrow_mut().is_modal(self.widget_id)
+    }
+
+    /// Whether this widget is on top of the modal stack, and so is the widget receiving input.
+    #[inline]
+    pub fn is_top_modal(&self) -> bool {
+        self.update_state.borrow_mut().is_top_modal(self.widget_id)
+    }
+
     pub fn timers(&self) -> &FnvHashMap<TimerId, Timer> {
         &self.timers
     }
@@ -507,6 +744,23 @@ impl WidgetTag {
             .push(f);
     }
 
+    /// Like `register_message`, but `f` only gets called for messages that `filter` returns `true`
+    /// for.
+    pub fn register_message_filtered<W, A>(
+        &mut self,
+        filter: impl 'static + Fn(&A) -> bool,
+        mut f: impl 'static + FnMut(&mut W, &A)
+    )
+        where W: 'static,
+              A: 'static
+    {
+        self.register_message(move |widget: &mut W, message: &A| {
+            if filter(message) {
+                f(widget, message);
+            }
+        });
+    }
+
     pub fn message_types(&self) -> impl '_ + Iterator<Item=TypeId> {
         self.registered_messages.keys().map(|k| k.message_type())
     }
@@ -519,14 +773,416 @@ impl WidgetTag {
         self.update_state.get_mut().send_message(message, Some(target));
     }
 
+    /// Send a request to the given target, returning a handle that gets resolved when a widget
+    /// answers it.
+    ///
+    /// See the [`request`](../request/index.html) module for more information.
+    pub fn send_request<Req, Resp>(&mut self, target: MessageTarget, request: Req) -> Reply<Resp>
+        where Req: 'static,
+              Resp: 'static
+    {
+        let (request, reply) = Request::<Req, Resp>::new(request);
+        self.update_state.get_mut().send_message(request, Some(target));
+        reply
+    }
+
+    /// Bind `chord` to broadcast a clone of `message` whenever it's pressed while this widget or
+    /// one of its descendants has focus. The shortcut gets removed when the widget is removed from
+    /// the tree.
+    ///
+    /// See the [`shortcut`](../shortcut/index.html) module for more information.
+    pub fn register_shortcut<M>(&mut self, chord: KeyChord, message: M) -> Result<ShortcutId, ShortcutError>
+        where M: 'static + Clone
+    {
+        self.update_state.get_mut().register_shortcut(self.widget_id, chord, Box::new(move || Box::new(message.clone())))
+    }
+
+    /// Remove a shortcut registered with `register_shortcut`, returning whether it was found.
+    pub fn remove_shortcut(&mut self, shortcut_id: ShortcutId) -> bool {
+        self.update_state.get_mut().remove_shortcut(shortcut_id)
+    }
+
+    /// Call `f` on the widget `handle` points to, returning a handle that gets resolved with `f`'s
+    /// return value.
+    ///
+    /// `f` gets called once the current event has been processed. If the widget has been removed
+    /// from the tree by then, `f` doesn't get called and the `Reply` gets abandoned.
+    ///
+    /// See the [`handle`](../handle/index.html) module for more information.
+    pub fn access_widget<W, T>(&mut self, handle: WidgetHandle<W>, f: impl 'static + FnOnce(&mut W) -> T) -> Reply<T>
+        where W: 'static,
+              T: 'static
+    {
+        let (access, reply) = HandleAccess::new(f);
+        self.update_state.get_mut().send_message(access, Some(MessageTarget::Widget(handle.widget_id())));
+        reply
+    }
+
+    /// Answer `Req` requests sent to this widget with the value returned by `f`.
+    pub fn register_request<W, Req, Resp>(&mut self, mut f: impl 'static + FnMut(&mut W, &Req) -> Resp)
+        where W: 'static,
+              Req: 'static,
+              Resp: 'static
+    {
+        self.register_message_filtered(
+            |request: &Request<Req, Resp>| !request.answered(),
+            move |widget: &mut W, request: &Request<Req, Resp>| {
+                request.respond(f(widget, &request.request));
+            }
+        );
+    }
+
+    /// Run `future` on a background thread, returning an ID that identifies the task.
+    ///
+    /// Once the future completes, its result is delivered to this widget as a
+    /// `TaskFinished<F::Item, F::Error>` message, which can be handled with `register_message`. If
+    /// the widget is removed from the tree before then, the task is cancelled and no message is
+    /// sent.
+    ///
+    /// See the [`task`](../task/index.html) module for more information.
+    pub fn spawn_task<F>(&mut self, future: F) -> TaskId
+        where F: 'static + Future + Send,
+              F::Item: Send,
+              F::Error: Send
+    {
+        let task_id = TaskId::new();
+        self.update_state.get_mut().spawn_task(self.widget_id, task_id, task::box_task(task_id, future));
+        task_id
+    }
+
     pub fn set_cursor_pos(&mut self, cursor_pos: Point2<i32>) -> Result<(), UpdateError> {
         self.update_state.get_mut().request_set_cursor_pos(self.widget_id, cursor_pos)
     }
 
+    /// Tell the window where this widget's text caret is, relative to the widget's origin, so
+    /// that the input method's composition window appears next to it. Should be called by text
+    /// inputs while they have focus.
+    pub fn set_ime_caret_rect(&mut self, rect: BoundBox<D2, i32>) -> Result<(), UpdateError> {
+        self.update_state.get_mut().request_set_ime_caret_rect(self.widget_id, rect)
+    }
+
     pub fn set_cursor_icon(&mut self, cursor_icon: CursorIcon) -> Result<(), UpdateError> {
         self.update_state.get_mut().request_set_cursor_icon(cursor_icon)
     }
 
+    /// Have screen readers read out the given text, regardless of which widget has keyboard focus.
+    ///
+    /// Useful for transient information, such as search result counts or validation errors. See
+    /// `Announcement` for which backends read it out.
+    pub fn announce(&mut self, text: impl Into<String>, politeness: Politeness) -> Result<(), UpdateError> {
+        self.update_state.get_mut().request_announce(self.widget_id, text.into(), politeness)
+    }
+
+    /// Play the sound the theme declares for the given interface event.
+    ///
+    /// Does nothing if the user has muted interface sounds. See the [`sound`] module for details.
+    ///
+    /// [`sound`]: ../sound/index.html
+    pub fn play_sound(&mut self, sound: UiSound) -> Result<(), UpdateError> {
+        self.update_state.get_mut().request_play_sound(sound)
+    }
+
+    /// Retrieves the user's accessibility preferences.
+    ///
+    /// If the widget hasn't been inserted into a widget tree, this returns the default preferences.
+    pub fn accessibility_prefs(&self) -> AccessibilityPrefs {
+        self.update_state.borrow_mut().accessibility_prefs()
+    }
+
+    pub(crate) fn notify_global_listeners(&self, event: &WidgetEvent) {
+        self.update_state.borrow_mut().notify_global_listeners(event, self.widget_id);
+    }
+
+    /// Retrieves the help topic sent when the user requests help on this widget.
+    #[inline]
+    pub fn help_topic(&self) -> Option<&str> {
+        self.help_topic.as_ref().map(|t| &**t)
+    }
+
+    /// Sets the help topic sent when the user requests help on this widget.
+    ///
+    /// If `None`, help requests use the topic of the nearest ancestor with a help topic.
+    #[inline]
+    pub fn set_help_topic(&mut self, topic: Option<&str>) -> &mut WidgetTag {
+        self.help_topic = topic.map(Arc::from);
+        self
+    }
+
+    /// Retrieves the ID automated tests use to find this widget.
+    #[inline]
+    pub fn automation_id(&self) -> Option<&str> {
+        self.automation_id.as_ref().map(|id| &**id)
+    }
+
+    /// Sets the ID automated tests use to find this widget, with `Root::find_automation_id` or
+    /// `TestHarness::find_automation_id`.
+    ///
+    /// Unlike the widget's `WidgetId`, the automation ID is chosen by the application, so it stays
+    /// the same from run to run and doesn't depend on where the widget is in the tree. IDs should be
+    /// unique within a window; if they aren't, lookups find the first matching widget in drawing
+    /// order.
+    #[inline]
+    pub fn set_automation_id(&mut self, automation_id: Option<&str>) -> &mut WidgetTag {
+        self.automation_id = automation_id.map(Arc::from);
+        self
+    }
+
+    /// Retrieves the key that activates this widget when pressed alongside `Alt`.
+    #[inline]
+    pub fn mnemonic(&self) -> Option<Key> {
+        self.mnemonic
+    }
+
+    /// Sets the key that activates this widget when pressed alongside `Alt`.
+    ///
+    /// When the mnemonic is pressed, the widget recieves a `MnemonicActivated` event.
+    #[inline]
+    pub fn set_mnemonic(&mut self, mnemonic: Option<Key>) -> &mut WidgetTag {
+        self.mnemonic = mnemonic;
+        self
+    }
+
+    /// Whether or not this widget is a focus scope.
+    #[inline]
+    pub fn focus_scope(&self) -> bool {
+        self.focus_scope
+    }
+
+    /// Sets whether or not this widget is a focus scope, such as a dialog or a pane.
+    ///
+    /// If `Enter` or `Escape` is pressed within a focus scope and isn't handled by the focused
+    /// widget or the widgets it bubbles through, the scope's default or cancel button is activated.
+    /// The root widget always acts as a focus scope.
+    #[inline]
+    pub fn set_focus_scope(&mut self, focus_scope: bool) -> &mut WidgetTag {
+        self.focus_scope = focus_scope;
+        self
+    }
+
+    /// Whether or not this widget can be given focus by the window's focus policy.
+    #[inline]
+    pub fn focusable(&self) -> bool {
+        self.focusable
+    }
+
+    /// Sets whether or not this widget can be given focus by the window's `FocusPolicy`, such as
+    /// when the mouse hovers over it. Widgets that take focus when clicked should set this.
+    #[inline]
+    pub fn set_focusable(&mut self, focusable: bool) -> &mut WidgetTag {
+        self.focusable = focusable;
+        self
+    }
+
+    /// Retrieves the button role this widget plays in its focus scope.
+    #[inline]
+    pub fn scope_button(&self) -> Option<ScopeButton> {
+        self.scope_button
+    }
+
+    /// Sets the button role this widget plays in the nearest focus scope containing it.
+    ///
+    /// When the scope button is activated, the widget recieves a `ScopeButtonActivated` event.
+    #[inline]
+    pub fn set_scope_button(&mut self, scope_button: Option<ScopeButton>) -> &mut WidgetTag {
+        self.scope_button = scope_button;
+        self
+    }
+
+    /// Whether or not the mouse passes through this widget.
+    #[inline]
+    pub fn input_transparent(&self) -> bool {
+        self.input_transparent
+    }
+
+    /// Sets whether or not the mouse passes through this widget, for purely decorative widgets
+    /// like watermarks and highlights drawn over the rest of the UI.
+    ///
+    /// Input-transparent widgets and their children are skipped when looking for the widget under
+    /// the mouse, so mouse events go to whatever's underneath instead.
+    #[inline]
+    pub fn set_input_transparent(&mut self, input_transparent: bool) -> &mut WidgetTag {
+        self.input_transparent = input_transparent;
+        self
+    }
+
+    /// Whether or not this widget covers its entire rect with opaque pixels.
+    #[inline]
+    pub fn opaque(&self) -> bool {
+        self.opaque
+    }
+
+    /// Marks this widget as covering its entire rect with opaque pixels.
+    ///
+    /// Widgets drawn entirely behind an opaque widget are skipped when rendering, so setting this
+    /// on large panels drawn over complex backgrounds can save a lot of overdraw. Setting it on a
+    /// widget with any transparent pixels leaves whatever's behind those pixels undrawn.
+    #[inline]
+    pub fn set_opaque(&mut self, opaque: bool) -> &mut WidgetTag {
+        if self.opaque != opaque {
+            self.opaque = opaque;
+            self.request_redraw();
+        }
+        self
+    }
+
+    /// Start dragging `payload` out of this widget. Should be called while handling a `DragStart`
+    /// event; calls at any other time are ignored.
+    ///
+    /// See the [`drag`] module for details.
+    ///
+    /// [`drag`]: ../drag/index.html
+    pub fn start_drag(&mut self, payload: DragPayload) -> Result<(), UpdateError> {
+        self.update_state.get_mut().request_start_drag(self.widget_id, payload)
+    }
+
+    /// Accept dragged values of type `T`, so that the widget recieves `DragOver` and `DragDrop`
+    /// events when they're dragged over it.
+    #[inline]
+    pub fn accept_drops<T: 'static>(&mut self) -> &mut WidgetTag {
+        let type_id = TypeId::of::<T>();
+        if !self.drop_types.contains(&type_id) {
+            self.drop_types.push(type_id);
+        }
+        self
+    }
+
+    /// Stop accepting dragged values of type `T`.
+    #[inline]
+    pub fn reject_drops<T: 'static>(&mut self) -> &mut WidgetTag {
+        let type_id = TypeId::of::<T>();
+        self.drop_types.retain(|t| *t != type_id);
+        self
+    }
+
+    /// Whether or not the widget accepts the given dragged value.
+    #[inline]
+    pub fn accepts_drop(&self, payload: &DragPayload) -> bool {
+        self.drop_types.contains(&payload.type_id())
+    }
+
+    /// Retrieves the set of gestures the widget recognizes.
+    #[inline]
+    pub fn gestures(&self) -> GestureSet {
+        self.gestures
+    }
+
+    /// Sets the gestures the widget recieves `Gesture` events for. See the [`gesture`] module for
+    /// details.
+    ///
+    /// [`gesture`]: ../gesture/index.html
+    #[inline]
+    pub fn recognize_gestures(&mut self, gestures: GestureSet) -> &mut WidgetTag {
+        self.gestures = gestures;
+        self
+    }
+
+    /// Retrieves the ambient value of type `T` that applies to this widget, either set on this
+    /// widget or inherited from the nearest ancestor it's set on. See the [`ambient`] module for
+    /// details.
+    ///
+    /// [`ambient`]: ../ambient/index.html
+    pub fn ambient<T: AmbientValue + Clone>(&self) -> Option<T> {
+        self.ambient.borrow().get::<T>().cloned()
+    }
+
+    /// Sets an ambient value for this widget and all of its descendants that don't set their own.
+    ///
+    /// Every widget whose effective value changes gets re-laid out and redrawn.
+    pub fn set_ambient<T: AmbientValue>(&mut self, value: T) -> &mut WidgetTag {
+        if self.ambient.get_mut().set(Some(value)) {
+            self.request_ambient_update();
+        }
+        self
+    }
+
+    /// Clears the ambient value of type `T` set on this widget, so that it inherits its parent's
+    /// value again.
+    pub fn clear_ambient<T: AmbientValue>(&mut self) -> &mut WidgetTag {
+        if self.ambient.get_mut().set(None::<T>) {
+            self.request_ambient_update();
+        }
+        self
+    }
+
+    /// Whether this widget accepts input, which is the case unless it or one of its ancestors has
+    /// been disabled.
+    pub fn is_enabled(&self) -> bool {
+        self.ambient.borrow().get::<Enabled>().map(|e| e.0).unwrap_or(true)
+    }
+
+    /// Enables or disables this widget and its descendants.
+    ///
+    /// Disabled widgets don't receive mouse, keyboard or focus events: the mouse passes through
+    /// them as if they were input-transparent, and events sent to them directly get dropped, aside
+    /// from the ones needed to clean up after an interaction that was in progress when the widget
+    /// got disabled, such as `MouseUp` and `LoseFocus`. Renderers get told whether each widget is
+    /// enabled, so that disabled widgets can be drawn greyed out.
+    ///
+    /// This sets the `Enabled` ambient value, so re-enabling a widget doesn't enable it if one of
+    /// its ancestors is disabled. The change applies to input sent to descendants right away, even
+    /// if they haven't been laid out since.
+    pub fn set_enabled(&mut self, enabled: bool) -> &mut WidgetTag {
+        match enabled {
+            true => self.clear_ambient::<Enabled>(),
+            false => self.set_ambient(Enabled(false))
+        }
+    }
+
+    /// Whether this widget accepts input, given whether its parent does. Unlike `is_enabled`, this
+    /// doesn't rely on the widget having inherited its parent's current ambient values.
+    pub(crate) fn is_enabled_under(&self, parent_enabled: bool) -> bool {
+        self.ambient.borrow().get_override::<Enabled>().map(|e| e.0).unwrap_or(parent_enabled)
+    }
+
+    /// Invalidate this widget after its ambient values change, and re-layout its descendants so
+    /// they pick up the new values.
+    fn request_ambient_update(&mut self) {
+        self.request_redraw();
+        self.request_relayout_scope(RelayoutScope::Subtree);
+    }
+
+    /// Take on the ambient values that apply to the parent, invalidating this widget if that
+    /// changes the values that apply to it.
+    pub(crate) fn inherit_ambient(&self, parent: &WidgetTag) {
+        let changed = self.ambient.borrow_mut().inherit(&parent.ambient.borrow());
+        if changed {
+            let mut update_state = self.update_state.borrow_mut();
+            update_state.request_redraw(self.widget_id);
+            update_state.request_relayout(self.widget_id);
+        }
+    }
+
+    /// Whether or not mnemonics should be drawn, which happens while `Alt` is held down.
+    pub fn mnemonics_visible(&self) -> bool {
+        self.update_state.borrow_mut().mnemonics_visible()
+    }
+
+    /// Whether or not a text caret drawn by this widget should currently be visible.
+    ///
+    /// All carets in the widget tree blink in sync. If the user has asked for reduced motion or
+    /// the operating system has blinking disabled, this always returns `true`.
+    pub fn caret_visible(&self) -> bool {
+        self.update_state.borrow_mut().caret_visible()
+    }
+
+    /// Sets whether or not this widget is displaying a blinking text caret.
+    ///
+    /// While set, the widget is re-laid out and redrawn whenever the caret toggles, so it can
+    /// check `caret_visible` in either `update_layout` or `render`.
+    pub fn set_caret_blinking(&mut self, blinking: bool) -> &mut WidgetTag {
+        self.update_state.get_mut().set_caret_blinking(self.widget_id, blinking);
+        self
+    }
+
+    /// Makes the caret visible and restarts the blink timer.
+    ///
+    /// Should be called whenever the user types or moves the caret, which keeps the caret solid
+    /// while the user is typing.
+    pub fn reset_caret_blink(&mut self) -> &mut WidgetTag {
+        self.update_state.get_mut().reset_caret_blink();
+        self
+    }
+
     #[inline]
     pub fn has_keyboard_focus(&self) -> bool {
         unimplemented!()
@@ -6,14 +6,15 @@
 
 use crate::{
     LoopFlow,
-    event::{EventOps, InputState, WidgetEventSourced},
+    event::{EventOps, InputState, WidgetEvent, WidgetEventSourced},
     render::{Renderer, WidgetRenderer, WidgetTheme},
-    widget::{Parent, WidgetIdent, Widget, WidgetRenderable, WidgetId, WidgetTag, WidgetInfo, WidgetInfoMut},
+    widget::{BoxedWidget, Parent, WidgetIdent, Widget, WidgetRenderable, WidgetId, WidgetTag, WidgetInfo, WidgetInfoMut},
 };
 use arrayvec::ArrayVec;
 use std::{
-    mem,
+    fmt, mem,
     any::{Any, TypeId},
+    error::Error,
 };
 use cgmath_geometry::{
     D2, rect::BoundBox,
@@ -36,6 +37,12 @@ pub(crate) trait WidgetDyn<R: Renderer>: 'static {
         event: WidgetEventSourced<'_>,
         input_state: InputState,
     ) -> EventOps;
+    fn intercept_child_event(
+        &mut self,
+        event: &WidgetEvent,
+        child_path: &[WidgetIdent],
+        input_state: InputState,
+    ) -> bool;
 
     fn size_bounds(&self) -> SizeBounds;
     fn dispatch_message(&mut self, message: &Any);
@@ -124,6 +131,9 @@ impl<W, R> WidgetDyn<R> for W
     fn on_widget_event(&mut self, event: WidgetEventSourced<'_>, input_state: InputState) -> EventOps {
         <Self as Widget>::on_widget_event(self, event, input_state)
     }
+    fn intercept_child_event(&mut self, event: &WidgetEvent, child_path: &[WidgetIdent], input_state: InputState) -> bool {
+        <Self as Widget>::intercept_child_event(self, event, child_path, input_state)
+    }
 
     fn size_bounds(&self) -> SizeBounds {
         <Self as Widget>::size_bounds(self)
@@ -243,10 +253,19 @@ impl<R> dyn WidgetDyn<R>
                   W: WidgetDyn<R>
         {
             #[inline(always)]
-            fn as_widget_dyn(self) -> &'a WidgetDyn<R> {
+            default fn as_widget_dyn(self) -> &'a WidgetDyn<R> {
                 self
             }
         }
+        // Boxed widgets get unwrapped, so that the inner widget gets rendered and traversed.
+        impl<'a, R> AsWidget<'a, R> for &'a BoxedWidget<R>
+            where R: Renderer
+        {
+            #[inline(always)]
+            fn as_widget_dyn(self) -> &'a WidgetDyn<R> {
+                &*self.widget
+            }
+        }
         impl<'a, R> AsWidget<'a, R> for &'a WidgetDyn<R>
             where R: Renderer
         {
@@ -270,10 +289,19 @@ impl<R> dyn WidgetDyn<R>
                   W: WidgetDyn<R>
         {
             #[inline(always)]
-            fn as_widget_dyn(self) -> &'a mut WidgetDyn<R> {
+            default fn as_widget_dyn(self) -> &'a mut WidgetDyn<R> {
                 self
             }
         }
+        // Boxed widgets get unwrapped, so that the inner widget gets rendered and traversed.
+        impl<'a, R> AsWidget<'a, R> for &'a mut BoxedWidget<R>
+            where R: Renderer
+        {
+            #[inline(always)]
+            fn as_widget_dyn(self) -> &'a mut WidgetDyn<R> {
+                &mut *self.widget
+            }
+        }
         impl<'a, R> AsWidget<'a, R> for &'a mut WidgetDyn<R>
             where R: Renderer
         {
@@ -320,12 +348,23 @@ pub struct RenderParameters<'a, R: Renderer> {
     pub clip: BoundBox<D2, i32>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RenderError {
     ThemeNotSupported,
     RendererNotSupported,
 }
 
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenderError::ThemeNotSupported => write!(f, "renderer didn't support the widget's theme"),
+            RenderError::RendererNotSupported => write!(f, "widget didn't support the renderer"),
+        }
+    }
+}
+
+impl Error for RenderError {}
+
 /// Given a widget and a renderer, iterate over the widget's primary and fallback themes, and render
 /// the widget with the first theme the renderer supports.
 fn render_with_theme_or_fallback<W, R>(widget: &mut W, render_parameters: RenderParameters<R>) -> Result<(), RenderError>
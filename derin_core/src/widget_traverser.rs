@@ -176,6 +176,17 @@ impl<R> WidgetTraverser<'_, R>
         }
     }
 
+    /// Find the first widget in drawing order with the given automation ID.
+    pub fn find_automation_id(&mut self, automation_id: &str) -> Option<WidgetId> {
+        let mut found = None;
+        self.crawl_widgets(|path| {
+            if found.is_none() && path.widget.widget_tag().automation_id() == Some(automation_id) {
+                found = Some(path.widget_id);
+            }
+        });
+        found
+    }
+
     pub fn crawl_widget_children(&mut self, parent: WidgetId, mut for_each: impl FnMut(OffsetWidgetPath<'_, R>)) {
         if let None = self.get_widget_with_tree(parent) {
             return;
@@ -192,6 +203,13 @@ impl<R> WidgetTraverser<'_, R>
             match child_opt {
                 Some(child) => {
                     for_each(child);
+                    // `for_each` may have changed the child's children, so bring the tree's record
+                    // of them up-to-date before descending into them.
+                    offset_widget_scan::update_children(
+                        stack.top_mut().widget.inner_mut(),
+                        &mut self.virtual_widget_tree,
+                        &self.update_state
+                    );
                     child_index = 0;
                 },
                 None => {
@@ -206,11 +224,6 @@ impl<R> WidgetTraverser<'_, R>
         }
 
         assert_eq!(self.stack.top_id(), parent);
-        offset_widget_scan::update_recursive(
-            self.stack.top_mut().widget.inner_mut(),
-            &mut self.virtual_widget_tree,
-            &self.update_state
-        );
     }
 
     pub fn root_id(&self) -> WidgetId {
@@ -290,6 +303,7 @@ mod tests {
     use super::*;
     use crate::{
         message_bus::MessageBus,
+        offset_widget::OffsetWidget,
         test_helpers::TestRenderFrame,
         update_state::UpdateState,
     };
@@ -331,6 +345,35 @@ mod tests {
         assert_eq!(None, expected_id_iter.next());
     }
 
+    #[test]
+    fn find_automation_id() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 0, 0);
+                a {
+                    rect: (0, 0, 0, 0);
+                    aa { rect: (0, 0, 0, 0) }
+                },
+                b { rect: (0, 0, 0, 0) }
+            };
+        }
+        {
+            let children = tree.children.as_mut().unwrap();
+            children[&WidgetIdent::new_str("a")].children.as_mut().unwrap()[&WidgetIdent::new_str("aa")]
+                .widget_tag.set_automation_id(Some("save"));
+            children[&WidgetIdent::new_str("b")].widget_tag.set_automation_id(Some("save"));
+        }
+
+        let mut traverser_base: WidgetTraverserBase<TestRenderFrame> = WidgetTraverserBase::new(root);
+        let message_bus = MessageBus::new();
+        let update_state = UpdateState::new(&message_bus);
+        let mut traverser = traverser_base.with_root_ref(&mut tree, update_state.clone());
+
+        assert_eq!(Some(aa), traverser.find_automation_id("save"));
+        assert_eq!(None, traverser.find_automation_id("open"));
+    }
+
     #[test]
     fn get_widget() {
         test_widget_tree!{
@@ -434,4 +477,60 @@ mod tests {
         test_crawl_children(b, &[ba]);
         test_crawl_children(ba, &[]);
     }
+
+    #[test]
+    fn incremental_scan() {
+        test_widget_tree!{
+            let event_list = crate::test_helpers::EventList::new();
+            let mut tree = root {
+                rect: (0, 0, 100, 100);
+                a {
+                    rect: (10, 10, 30, 30);
+                    aa {
+                        rect: (0, 0, 10, 10);
+                        aaa {rect: (0, 0, 10, 10)}
+                    },
+                    ab {rect: (10, 10, 20, 20)}
+                },
+                b {
+                    rect: (20, 20, 40, 40);
+                    ba {rect: (0, 0, 10, 10)}
+                }
+            };
+        }
+
+        let mut traverser_base: WidgetTraverserBase<TestRenderFrame> = WidgetTraverserBase::new(root);
+        let message_bus = MessageBus::new();
+        let update_state = UpdateState::new(&message_bus);
+        let mut traverser = traverser_base.with_root_ref(&mut tree, update_state.clone());
+
+        fn scan_widget(traverser: &mut WidgetTraverser<TestRenderFrame>, id: WidgetId) {
+            // Mutably accessing the widget queues it to be scanned when the path gets dropped.
+            let mut path = traverser.get_widget(id).unwrap();
+            let _: &mut OffsetWidget<_> = &mut path.widget;
+        }
+
+        // The first scan picks up the entire tree.
+        scan_widget(&mut traverser, root);
+        for &id in &[a, aa, aaa, ab, b, ba] {
+            assert!(traverser.virtual_widget_tree.get_widget(id).is_some());
+        }
+
+        // `aa` is already in the tree, so rescanning `a` doesn't descend into it...
+        traverser.virtual_widget_tree.remove(aaa);
+        scan_widget(&mut traverser, a);
+        assert!(traverser.virtual_widget_tree.get_widget(aaa).is_none());
+
+        // ...but children that are missing from the tree get scanned.
+        traverser.virtual_widget_tree.remove(ab);
+        scan_widget(&mut traverser, a);
+        assert!(traverser.virtual_widget_tree.get_widget(ab).is_some());
+        assert!(traverser.virtual_widget_tree.get_widget(aaa).is_none());
+
+        // Requesting a rescan picks up the whole subtree.
+        update_state.borrow_mut().requests.rescan.insert(a);
+        scan_widget(&mut traverser, a);
+        assert!(traverser.virtual_widget_tree.get_widget(aaa).is_some());
+        assert!(update_state.borrow().requests.rescan.is_empty());
+    }
 }
@@ -42,7 +42,7 @@ impl<'a, R: Renderer> DerefMut for OffsetWidgetScan<'a, R> {
 impl<R: Renderer> Drop for OffsetWidgetScan<'_, R> {
     fn drop(&mut self) {
         if self.scan {
-            update_recursive(self.offset_widget.inner(), self.virtual_widget_tree, &self.update_state);
+            update_children(self.offset_widget.inner(), self.virtual_widget_tree, &self.update_state);
         }
     }
 }
@@ -62,6 +62,40 @@ impl<R: Renderer> OffsetWidgetScan<'_, R> {
     }
 }
 
+/// Bring the virtual tree's record of the widget's children up-to-date.
+///
+/// Only children that are new to the widget, or that have moved or been renamed, get their
+/// subtrees scanned. Children the tree already knows about are assumed to have unchanged
+/// subtrees, unless the widget requested a subtree rescan with `WidgetTag::request_rescan`.
+/// Children that were removed don't need to be handled here, since dropping their `WidgetTag`
+/// removes them from the tree.
+pub(crate) fn update_children<R: Renderer>(widget: &dyn WidgetDyn<R>, tree: &mut VirtualWidgetTree, update_state: &Rc<UpdateStateCell>) {
+    let widget_tag = widget.widget_tag();
+    let widget_id = widget_tag.widget_id;
+    widget_tag.set_owning_update_state(update_state);
+
+    if update_state.borrow_mut().requests.rescan.remove(&widget_id) {
+        update_recursive(widget, tree, update_state);
+        return;
+    }
+
+    widget.children(&mut |children| {
+        for child in children {
+            let child_id = child.widget.widget_id();
+            let unchanged =
+                tree.child_index(widget_id, child.index) == Ok(child_id) &&
+                tree.get_widget(child_id).map(|data| data.ident == child.ident).unwrap_or(false);
+
+            if !unchanged {
+                tree.insert(widget_id, child_id, child.index, child.ident).expect("Widget insert error");
+                update_recursive(child.widget, tree, update_state);
+            }
+        }
+        LoopFlow::Continue
+    });
+}
+
+/// Scan the widget's entire subtree into the virtual tree.
 pub(crate) fn update_recursive<R: Renderer>(widget: &dyn WidgetDyn<R>, tree: &mut VirtualWidgetTree, update_state: &Rc<UpdateStateCell>) {
     let widget_tag = widget.widget_tag();
     let widget_id = widget_tag.widget_id;
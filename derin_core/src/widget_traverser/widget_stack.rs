@@ -218,6 +218,7 @@ impl<'a, R: Renderer> WidgetStack<'a, R> {
                 index: new_top_index
             });
             self.ident_vec.push(new_top_ident);
+            self.inherit_top_ambient();
             Some(self.top_mut())
         } else {
             None
@@ -244,6 +245,7 @@ impl<'a, R: Renderer> WidgetStack<'a, R> {
         self.vec.truncate(diverge_index);
         self.vec.last_mut().unwrap().rectangles = None;
         self.truncate_offset_and_clip(diverge_index);
+        self.inherit_stack_ambient();
 
         let new_widget = try {
             while self.vec.len() < self.ident_vec.len() {
@@ -271,6 +273,7 @@ impl<'a, R: Renderer> WidgetStack<'a, R> {
                     rectangles: None,
                     index: new_top.index,
                 });
+                self.inherit_top_ambient();
             }
             assert_eq!(self.vec.len(), self.ident_vec.len());
 
@@ -290,6 +293,24 @@ impl<'a, R: Renderer> WidgetStack<'a, R> {
         }
     }
 
+    /// Pass the ambient values down from the second-to-top widget to the top widget.
+    fn inherit_top_ambient(&self) {
+        let len = self.vec.len();
+        if len >= 2 {
+            let (parent, child) = unsafe{ (&*self.vec[len - 2].widget, &*self.vec[len - 1].widget) };
+            child.widget_tag().inherit_ambient(parent.widget_tag());
+        }
+    }
+
+    /// Pass the ambient values down the whole stack, so that widgets kept on the stack from an
+    /// earlier move pick up changes made to their ancestors since then.
+    fn inherit_stack_ambient(&self) {
+        for i in 1..self.vec.len() {
+            let (parent, child) = unsafe{ (&*self.vec[i - 1].widget, &*self.vec[i].widget) };
+            child.widget_tag().inherit_ambient(parent.widget_tag());
+        }
+    }
+
     #[inline]
     pub fn pop(&mut self) -> Option<&mut WidgetDyn<R>> {
         // Ensure the base is never popped
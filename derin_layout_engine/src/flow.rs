@@ -0,0 +1,242 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_common_types::Px;
+use cgmath_geometry::{D2, rect::{DimsBox, BoundBox, GeoBox}};
+use derin_common_types::layout::{Align, Axis, LayoutDirection, WidgetPos, SizeBounds, Margins};
+use crate::{CellHinter, HintError, SolveError, Unsolvable, axis_min_size, place_absolute};
+
+/// Places widgets left-to-right, wrapping onto a new line when the engine's width runs out.
+///
+/// Useful for tag clouds and toolbars. Each widget takes up its minimum size, and is placed
+/// vertically within its line with its own `place_in_cell` alignment; `widget_span` is ignored.
+///
+/// The engine's minimum height depends on its width, since a narrower engine needs more lines to
+/// fit every widget. The minimum height reported in `actual_size_bounds` is the height needed at
+/// the engine's current width, so a container that resizes the engine has to re-run the layout
+/// until the height stops changing. `Root::relayout` does this automatically, since the
+/// container's size bounds change whenever the height does.
+#[derive(Debug, Clone)]
+pub struct FlowEngine {
+    /// The pixel size of the layout engine, as requested by the programmer.
+    pub desired_size: DimsBox<D2, Px>,
+    /// The pixel size of the layout engine, accounting for the size bounds of the widgets and the size
+    /// bounds of the engine.
+    actual_size: DimsBox<D2, Px>,
+    /// The size bounds of the engine, as requested by the programmer.
+    pub desired_size_bounds: SizeBounds,
+    /// The size bounds of the engine, accounting for the size bounds of the widgets.
+    actual_size_bounds: SizeBounds,
+    /// The margins that appear around the outside of the lines.
+    pub flow_margins: Margins<Px>,
+    /// The horizontal space between widgets on the same line.
+    pub spacing: Px,
+    /// The vertical space between lines.
+    pub line_spacing: Px,
+    /// How each line's widgets are aligned horizontally within the line. `Align::Stretch`
+    /// distributes the leftover space between the widgets.
+    pub line_align: Align,
+}
+
+/// A line of widgets, as computed by `FlowEngine::break_lines`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Line {
+    /// The index of the first widget in the line, in the list of non-absolute widgets.
+    start: usize,
+    /// The number of widgets in the line.
+    len: usize,
+    /// The width of the widgets in the line, including the spacing between them.
+    width: Px,
+    height: Px,
+}
+
+impl FlowEngine {
+    pub fn new() -> FlowEngine {
+        FlowEngine {
+            desired_size: DimsBox::new2(0, 0),
+            actual_size: DimsBox::new2(0, 0),
+            desired_size_bounds: SizeBounds::default(),
+            actual_size_bounds: SizeBounds::default(),
+            flow_margins: Margins::default(),
+            spacing: 0,
+            line_spacing: 0,
+            line_align: Align::Start,
+        }
+    }
+
+    pub fn actual_size(&self) -> DimsBox<D2, Px> {
+        self.actual_size
+    }
+
+    pub fn actual_size_bounds(&self) -> SizeBounds {
+        self.actual_size_bounds
+    }
+
+    /// Break the widgets into lines no wider than `line_width`. Widgets wider than `line_width`
+    /// get a line to themselves.
+    fn break_lines(&self, hints: &[&WidgetPos], line_width: Px) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let mut line: Option<Line> = None;
+
+        for (index, hint) in hints.iter().enumerate() {
+            let (width, height) = (axis_min_size(hint, Axis::X), axis_min_size(hint, Axis::Y));
+            line = Some(match line {
+                Some(line) if line.width + self.spacing + width <= line_width => Line {
+                    len: line.len + 1,
+                    width: line.width + self.spacing + width,
+                    height: line.height.max(height),
+                    ..line
+                },
+                line_opt => {
+                    lines.extend(line_opt);
+                    Line{ start: index, len: 1, width, height }
+                }
+            });
+        }
+        lines.extend(line);
+
+        lines
+    }
+
+    /// Place the widgets in lines, storing the results in `rects`.
+    pub fn update_engine(
+        &mut self,
+        hints: &[WidgetPos],
+        rects: &mut [Result<BoundBox<D2, Px>, SolveError>]
+    ) {
+        assert_eq!(hints.len(), rects.len());
+
+        let flow_hints: Vec<&WidgetPos> = hints.iter().filter(|hint| hint.absolute.is_none()).collect();
+        let (margins_x, margins_y) = (self.flow_margins.width(), self.flow_margins.height());
+
+        // The engine has to be at least as wide as its widest widget, but the width doesn't
+        // otherwise depend on the widgets.
+        let widest = flow_hints.iter().map(|hint| axis_min_size(hint, Axis::X)).max().unwrap_or(0);
+        let min_width = self.desired_size_bounds.min.width().max(widest + margins_x);
+        let max_width = self.desired_size_bounds.max.width().max(min_width);
+        let width = self.desired_size.width().max(min_width).min(max_width);
+
+        let lines = self.break_lines(&flow_hints, width - margins_x);
+        let lines_height = lines.iter().map(|line| line.height).sum::<Px>()
+            + self.line_spacing * (lines.len() as Px - 1).max(0);
+
+        let min_height = self.desired_size_bounds.min.height().max(lines_height + margins_y);
+        self.actual_size_bounds = SizeBounds::new(
+            DimsBox::new2(min_width, min_height),
+            DimsBox::new2(max_width, self.desired_size_bounds.max.height().max(min_height))
+        );
+        self.actual_size = DimsBox::new2(width, self.actual_size_bounds.bound_rect(self.desired_size).height());
+
+        let line_width = width - margins_x;
+        let mut flow_rects = Vec::with_capacity(flow_hints.len());
+        let mut y = self.flow_margins.top;
+        for line in &lines {
+            let free_space = line_width - line.width;
+            let (mut x, extra_spacing, mut remainder) = match self.line_align {
+                Align::Start => (0, 0, 0),
+                Align::Center => (free_space / 2, 0, 0),
+                Align::End => (free_space, 0, 0),
+                Align::Stretch => match line.len {
+                    1 => (0, 0, 0),
+                    _ => (0, free_space / (line.len as Px - 1), free_space % (line.len as Px - 1))
+                }
+            };
+            x += self.flow_margins.left;
+
+            for hint in &flow_hints[line.start..line.start + line.len] {
+                let width = axis_min_size(hint, Axis::X);
+                let cell = BoundBox::new2(x, y, x + width, y + line.height);
+                let cell_hinter = CellHinter::new(cell, hint.place_in_cell, hint.aspect_ratio, LayoutDirection::default());
+                flow_rects.push(
+                    cell_hinter.hint(hint.size_bounds, hint.margins)
+                        .map_err(|HintError::ORTooSmall(axis)| SolveError::WidgetUnsolvable(Unsolvable {
+                            axis,
+                            widget_min: axis_min_size(hint, axis),
+                            cell_size: match axis {
+                                Axis::X => cell.width(),
+                                Axis::Y => cell.height()
+                            },
+                            first_track: 0,
+                            num_tracks: 0,
+                            tracks_saturated: false
+                        }))
+                );
+
+                x += width + self.spacing + extra_spacing;
+                if remainder > 0 {
+                    x += 1;
+                    remainder -= 1;
+                }
+            }
+
+            y += line.height + self.line_spacing;
+        }
+
+        let mut flow_rects = flow_rects.into_iter();
+        for (hint, rect) in hints.iter().zip(rects.iter_mut()) {
+            *rect = match hint.absolute {
+                Some(ref absolute) => place_absolute(absolute, self.actual_size),
+                None => flow_rects.next().unwrap()
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use derin_common_types::layout::Align2;
+
+    fn tag(width: Px, height: Px) -> WidgetPos {
+        WidgetPos {
+            size_bounds: SizeBounds::new_min(DimsBox::new2(width, height)),
+            place_in_cell: Align2::new(Align::Start, Align::Center),
+            ..WidgetPos::default()
+        }
+    }
+
+    #[test]
+    fn flow_engine() {
+        let mut engine = FlowEngine::new();
+        engine.desired_size = DimsBox::new2(100, 0);
+        engine.spacing = 10;
+        engine.line_spacing = 5;
+
+        let hints = [tag(40, 10), tag(40, 20), tag(60, 10), tag(30, 10)];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 4];
+        engine.update_engine(&hints, &mut rects);
+
+        assert_eq!(DimsBox::new2(100, 35), engine.actual_size());
+        assert_eq!(DimsBox::new2(60, 35), engine.actual_size_bounds().min);
+        assert_eq!(Ok(BoundBox::new2(0, 5, 40, 15)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(50, 0, 90, 20)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(0, 25, 60, 35)), rects[2]);
+        assert_eq!(Ok(BoundBox::new2(70, 25, 100, 35)), rects[3]);
+
+        // Narrowing the engine adds lines, which raises the minimum height.
+        engine.desired_size = DimsBox::new2(60, 0);
+        engine.line_align = Align::End;
+        engine.update_engine(&hints, &mut rects);
+        assert_eq!(DimsBox::new2(60, 65), engine.actual_size_bounds().min);
+        assert_eq!(Ok(BoundBox::new2(20, 0, 60, 10)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(20, 15, 60, 35)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(0, 40, 60, 50)), rects[2]);
+        assert_eq!(Ok(BoundBox::new2(30, 55, 60, 65)), rects[3]);
+    }
+
+    #[test]
+    fn stretch_lines() {
+        let mut engine = FlowEngine::new();
+        engine.desired_size = DimsBox::new2(101, 10);
+        engine.line_align = Align::Stretch;
+
+        let hints = [tag(20, 10), tag(20, 10), tag(20, 10)];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 3];
+        engine.update_engine(&hints, &mut rects);
+
+        assert_eq!(Ok(BoundBox::new2(0, 0, 20, 10)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(41, 0, 61, 10)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(81, 0, 101, 10)), rects[2]);
+    }
+}
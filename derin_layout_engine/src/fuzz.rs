@@ -0,0 +1,230 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Property-testing tools for the layout engine. Requires the `fuzz` feature.
+//!
+//! [`check_engine`] solves an engine and verifies that the result upholds the solver's global
+//! invariants, so containers can validate their own configurations. [`EngineCase`] generates
+//! random grids and widgets for use with `quickcheck`:
+//!
+//! ```ignore
+//! quickcheck! {
+//!     fn engine_invariants(case: EngineCase) -> bool {
+//!         check_engine(&case.engine, &case.hints).is_ok()
+//!     }
+//! }
+//! ```
+//!
+//! [`check_engine`]: ./fn.check_engine.html
+//! [`EngineCase`]: ./struct.EngineCase.html
+
+use crate::{GridEngine, UpdateHeapCache, SolveError};
+use crate::grid::GridTrack;
+use derin_common_types::Px;
+use derin_common_types::layout::{Axis, GridSize, SizeBounds, TrackHints, Tr, WidgetPos, WidgetSpan};
+use cgmath_geometry::{D2, rect::{BoundBox, DimsBox, GeoBox}};
+use quickcheck::{Arbitrary, Gen};
+
+use std::fmt;
+
+/// A solver invariant that didn't hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// A widget's rect extends past the engine's rect.
+    WidgetOutsideEngine {
+        widget_index: usize,
+        rect: BoundBox<D2, Px>,
+        engine_size: DimsBox<D2, Px>,
+    },
+    /// A rigid track is smaller than its minimum size.
+    RigidMinViolated {
+        axis: Axis,
+        track: Tr,
+        size: Px,
+        min_size: Px,
+    },
+    /// The fractional tracks don't add up to the space left over by the rigid tracks.
+    FracSpaceMismatch {
+        axis: Axis,
+        free_space: Px,
+        frac_size: Px,
+    },
+}
+
+/// Solve a copy of `engine` with `hints`, and check that the result upholds the solver's
+/// invariants:
+///
+/// * No widget extends past the engine's rect.
+/// * Every rigid track is at least as large as its minimum size.
+/// * The fractional tracks take up exactly the space not taken by the rigid tracks, margins and
+///   gaps. If any fractional track is at its maximum size, they may take up less.
+pub fn check_engine(engine: &GridEngine, hints: &[WidgetPos]) -> Result<(), Violation> {
+    let mut engine = engine.clone();
+    let mut rects = vec![Err(SolveError::CellOutOfBounds); hints.len()];
+    engine.update_engine(hints, &mut rects, &mut UpdateHeapCache::new());
+
+    let engine_size = engine.actual_size();
+    for (widget_index, rect) in rects.iter().enumerate() {
+        if let Ok(rect) = *rect {
+            let inside =
+                0 <= rect.min.x && rect.max.x <= engine_size.width() &&
+                0 <= rect.min.y && rect.max.y <= engine_size.height();
+            if !inside {
+                return Err(Violation::WidgetOutsideEngine{ widget_index, rect, engine_size });
+            }
+        }
+    }
+
+    check_axis(&engine, Axis::X)?;
+    check_axis(&engine, Axis::Y)?;
+    Ok(())
+}
+
+fn check_axis(engine: &GridEngine, axis: Axis) -> Result<(), Violation> {
+    let (tracks, engine_size, margins, gap): (&[GridTrack], _, _, _) = match axis {
+        Axis::X => (engine.grid.col_range(..).unwrap(), engine.actual_size.width(), engine.grid_margins.width(), engine.gap.x),
+        Axis::Y => (engine.grid.row_range(..).unwrap(), engine.actual_size.height(), engine.grid_margins.height(), engine.gap.y)
+    };
+    if tracks.len() == 0 {
+        return Ok(());
+    }
+
+    let mut free_space = engine_size - margins - gap * (tracks.len() as Px - 1);
+    let (mut frac_size, mut any_frac, mut frac_at_max) = (0, false, false);
+    for (index, track) in tracks.iter().enumerate() {
+        match track.fr_size() <= 0.0 {
+            true => {
+                if track.size() < track.min_size() {
+                    return Err(Violation::RigidMinViolated {
+                        axis,
+                        track: index as Tr,
+                        size: track.size(),
+                        min_size: track.min_size(),
+                    });
+                }
+                free_space -= track.size();
+            },
+            false => {
+                any_frac = true;
+                frac_at_max |= track.size() >= track.max_size();
+                frac_size += track.size();
+            }
+        }
+    }
+
+    let fits = match frac_at_max {
+        true => frac_size <= free_space,
+        false => frac_size == free_space
+    };
+    match !any_frac || fits {
+        true => Ok(()),
+        false => Err(Violation::FracSpaceMismatch{ axis, free_space, frac_size })
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::WidgetOutsideEngine{ widget_index, rect, engine_size } => write!(
+                f, "widget {} at ({}, {})-({}, {}) extends past the {}x{} engine",
+                widget_index, rect.min.x, rect.min.y, rect.max.x, rect.max.y, engine_size.width(), engine_size.height()
+            ),
+            Violation::RigidMinViolated{ axis, track, size, min_size } => write!(
+                f, "rigid {:?} track {} is {}px, but its minimum size is {}px",
+                axis, track, size, min_size
+            ),
+            Violation::FracSpaceMismatch{ axis, free_space, frac_size } => write!(
+                f, "fractional {:?} tracks take up {}px, but {}px are free",
+                axis, frac_size, free_space
+            ),
+        }
+    }
+}
+
+/// A randomly generated engine and set of widgets.
+#[derive(Debug, Clone)]
+pub struct EngineCase {
+    pub engine: GridEngine,
+    pub hints: Vec<WidgetPos>,
+}
+
+fn gen_range<G: Gen>(g: &mut G, start: u32, end: u32) -> u32 {
+    start + g.next_u32() % (end - start)
+}
+
+fn gen_track_hints<G: Gen>(g: &mut G) -> TrackHints {
+    match g.next_u32() % 3 {
+        0 => {
+            let size = gen_range(g, 0, 100) as Px;
+            TrackHints {
+                min_size: size,
+                max_size: size,
+                fr_size: 0.0,
+                ..TrackHints::default()
+            }
+        },
+        _ => TrackHints {
+            min_size: gen_range(g, 0, 50) as Px,
+            fr_size: gen_range(g, 1, 8) as f32 / 2.0,
+            ..TrackHints::default()
+        }
+    }
+}
+
+impl Arbitrary for EngineCase {
+    fn arbitrary<G: Gen>(g: &mut G) -> EngineCase {
+        let grid_size = GridSize::new(gen_range(g, 1, 6), gen_range(g, 1, 6));
+
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(grid_size);
+        for col in 0..grid_size.x {
+            engine.set_col_hints(col, gen_track_hints(g));
+        }
+        for row in 0..grid_size.y {
+            engine.set_row_hints(row, gen_track_hints(g));
+        }
+        engine.desired_size = DimsBox::new2(gen_range(g, 0, 600) as Px, gen_range(g, 0, 600) as Px);
+
+        let num_widgets = gen_range(g, 0, 12);
+        let hints = (0..num_widgets).map(|_| {
+            let (col, row) = (gen_range(g, 0, grid_size.x), gen_range(g, 0, grid_size.y));
+            let (cols, rows) = (gen_range(g, 1, grid_size.x - col + 1), gen_range(g, 1, grid_size.y - row + 1));
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(gen_range(g, 0, 80) as Px, gen_range(g, 0, 80) as Px)),
+                widget_span: WidgetSpan::new(col..col + cols, row..row + rows),
+                ..WidgetPos::default()
+            }
+        }).collect();
+
+        EngineCase{ engine, hints }
+    }
+
+    fn shrink(&self) -> Box<Iterator<Item=EngineCase>> {
+        // Shrink by dropping widgets, which is usually enough to find the widget that breaks the
+        // solver.
+        let case = self.clone();
+        Box::new((0..self.hints.len()).rev().map(move |remove| {
+            let mut case = case.clone();
+            case.hints.remove(remove);
+            case
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck!{
+        fn engine_invariants(case: EngineCase) -> bool {
+            match check_engine(&case.engine, &case.hints) {
+                Ok(()) => true,
+                Err(violation) => {
+                    println!("{}", violation);
+                    false
+                }
+            }
+        }
+    }
+}
@@ -5,7 +5,7 @@
 use crate::Tr;
 use derin_common_types::Px;
 use crate::cgmath::Point2;
-use derin_common_types::layout::{GridSize, TrRange, TrackHints};
+use derin_common_types::layout::{Fr, GridSize, LayoutDirection, TrRange, TrackHints};
 
 use std::cmp;
 use std::fmt::{Debug, Formatter, Error};
@@ -27,7 +27,11 @@ pub struct GridTrack {
     /// This must be greater than `min_size` and less than `max_size`.
     size: Px,
     widget_min_size: Px,
-    hints: TrackHints
+    /// The size derived from the track's widgets, if the track is content-sized.
+    content_size: Px,
+    hints: TrackHints,
+    /// Whether the splitter after this track can be dragged.
+    resizable: bool
 }
 
 impl GridTrack {
@@ -38,7 +42,7 @@ impl GridTrack {
 
     /// Get the minimum size of this grid track in pixels.
     pub fn min_size(&self) -> Px {
-        cmp::max(self.hints.min_size, self.widget_min_size)
+        cmp::max(self.hints.min_size, cmp::max(self.widget_min_size, self.content_size))
     }
 
     /// Get the maximum size of this grid track in pixels.
@@ -48,10 +52,27 @@ impl GridTrack {
         cmp::max(self.hints.max_size, self.hints.min_size)
     }
 
+    /// The fractional size of the track. Content-sized tracks don't take up free space, so this is
+    /// zero for them.
+    pub fn fr_size(&self) -> Fr {
+        match self.hints.content_size {
+            Some(_) => 0.0,
+            None => self.hints.fr_size
+        }
+    }
+
+    /// Set the size derived from the track's widgets. Ignored if the track isn't content-sized.
+    pub fn set_content_size(&mut self, content_size: Px) {
+        self.content_size = match self.hints.content_size {
+            Some(_) => cmp::min(content_size, self.max_size()),
+            None => 0
+        };
+    }
+
     /// Reset the widget minimum size and track size to the track minimum size.
     pub fn reset_shrink(&mut self) {
-        self.size = self.hints.min_size;
         self.widget_min_size = self.hints.min_size;
+        self.size = self.min_size();
     }
 
     pub fn reset_expand(&mut self) {
@@ -98,6 +119,16 @@ impl GridTrack {
         }
     }
 
+    #[inline]
+    pub fn resizable(&self) -> bool {
+        self.resizable
+    }
+
+    #[inline]
+    pub fn set_resizable(&mut self, resizable: bool) {
+        self.resizable = resizable;
+    }
+
     #[inline]
     pub fn hints(&self) -> TrackHints {
         self.hints
@@ -329,15 +360,23 @@ impl<T> TrackVec<T> {
 
 impl TrackVec<GridTrack> {
     /// Get the given cell's offset from the origin point of the layout.
-    pub fn get_cell_offset(&self, column_num: Tr, row_num: Tr) -> Option<Point2<Px>> {
+    pub fn get_cell_offset(&self, column_num: Tr, row_num: Tr, direction: LayoutDirection) -> Option<Point2<Px>> {
         if column_num < self.num_cols &&
            row_num < self.num_rows
         {
-            // Sum up the sizes of every column and row up to `column_num` and `row_num` variables. That sum
-            // is the offset of the given column and row.
+            // Sum up the sizes of every column and row that gets laid out before `column_num` and
+            // `row_num`. That sum is the offset of the given column and row.
+            let cols = match direction.right_to_left {
+                false => 0..column_num,
+                true => column_num + 1..self.num_cols
+            };
+            let rows = match direction.bottom_to_top {
+                false => 0..row_num,
+                true => row_num + 1..self.num_rows
+            };
             Some(Point2::new(
-                (0..column_num).map(|c| self.get_col(c).unwrap().size()).sum(),
-                (0..row_num).map(|r| self.get_row(r).unwrap().size()).sum()
+                cols.map(|c| self.get_col(c).unwrap().size()).sum(),
+                rows.map(|r| self.get_row(r).unwrap().size()).sum()
             ))
         } else {
             None
@@ -420,7 +459,8 @@ mod tests {
             track.set_hints(TrackHints {
                 min_size: g.next_u32() as i32 & !i32::min_value(),
                 max_size: g.next_u32() as i32 & !i32::min_value(),
-                fr_size: g.next_f32()
+                fr_size: g.next_f32(),
+                content_size: None
             }).ok();
             track
         }
@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-#[cfg(test)]
+#[cfg(any(test, feature = "fuzz"))]
 #[cfg_attr(test, macro_use)]
 extern crate quickcheck;
 extern crate num_traits;
@@ -10,15 +10,27 @@ use cgmath_geometry::cgmath;
 extern crate cgmath_geometry;
 extern crate derin_common_types;
 
+mod flow;
 mod grid;
+mod stack;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+
+pub use crate::flow::FlowEngine;
+pub use crate::stack::StackEngine;
 
 use derin_common_types::Px;
 use crate::cgmath::{Point2, Vector2, EuclideanSpace};
 use cgmath_geometry::{D2, rect::{DimsBox, BoundBox, GeoBox}};
-use derin_common_types::layout::{Fr, Tr, Align2, Align, GridSize, WidgetPos, TrackHints, SizeBounds, Margins};
-use crate::grid::{TrackVec, SizeResult};
+use derin_common_types::layout::{AbsolutePos, AutoFlow, Axis, Fr, Tr, Align2, Align, ContentSize, GridSize, LayoutDirection, WidgetPos, WidgetSpan, TrRange, TrackHints, SizeBounds, Margins};
+use crate::grid::{GridTrack, TrackVec, SizeResult};
 
-use std::cmp;
+use std::{
+    cmp, fmt, mem,
+    collections::hash_map::DefaultHasher,
+    error::Error,
+    hash::{Hash, Hasher},
+};
 
 #[derive(Default)]
 pub struct UpdateHeapCache {
@@ -26,23 +38,81 @@ pub struct UpdateHeapCache {
     potential_frac_tracks: TrackVec<Tr>,
     rigid_tracks_widget: Vec<Tr>,
     frac_tracks_widget: Vec<Tr>,
-    solvable_widgets: Vec<Solvable>
+    solvable_widgets: Vec<Solvable>,
+    track_sizes: Vec<Px>,
+    placed_hints: Vec<WidgetPos>,
+    collapsed_hints: Vec<WidgetPos>,
+    measured_hints: Vec<WidgetPos>
 }
 
+/// The maximum number of times `GridEngine::update_engine_measured` solves the grid.
+const MAX_MEASURE_PASSES: usize = 4;
+
 #[derive(Debug, Clone)]
 pub struct GridEngine {
     grid: TrackVec,
+    /// The grid size set with `set_grid_size`. The grid may be bigger than this if auto-flow added
+    /// tracks to fit widgets.
+    explicit_grid_size: GridSize,
     /// The pixel size of the layout engine, as requested by the programmer.
     pub desired_size: DimsBox<D2, Px>,
     /// The pixel size of the layout engine, accounting for the size bounds of the widgets and the size
     /// bounds of the engine.
     actual_size: DimsBox<D2, Px>,
+    /// How far `actual_size` extended past `desired_size` in the last solve.
+    overflow: Vector2<Px>,
     /// The size bounds of the engine, as requested by the programmer.
     pub desired_size_bounds: SizeBounds,
     /// The size bounds of the engine, accounting for the size bounds of the widgets.
     actual_size_bounds: SizeBounds,
     /// The margins that appear around the outside of the widget grid
-    pub grid_margins: Margins<Px>
+    pub grid_margins: Margins<Px>,
+    /// The space between adjacent columns (`x`) and adjacent rows (`y`).
+    pub gap: Vector2<Px>,
+    /// The order columns and rows are laid out in.
+    pub direction: LayoutDirection,
+    /// Place widgets that don't have a span into the next free cell, if `Some`.
+    ///
+    /// Widgets whose spans are empty on both axes, like `WidgetSpan::default()`, are placed in
+    /// the first free cell after the last auto-placed widget. The grid gains tracks along the flow
+    /// axis if the widgets don't fit, and loses them again once they're no longer needed.
+    pub auto_flow: Option<AutoFlow>,
+    /// Information from the last full solve, used by `update_widget` to skip re-solving the grid.
+    /// `None` if the grid's changed since.
+    solved: Option<SolvedState>,
+    /// The results of the last full solve, used by `update_engine` to skip solving the grid when
+    /// none of the inputs have changed.
+    cache: Option<LayoutCache>
+}
+
+#[derive(Debug, Clone)]
+struct LayoutCache {
+    /// Hash of every input that affects the solve.
+    input_hash: u64,
+    hints: Vec<WidgetPos>,
+    rects: Vec<Result<BoundBox<D2, Px>, SolveError>>
+}
+
+#[derive(Debug, Clone)]
+struct SolvedState {
+    desired_size: DimsBox<D2, Px>,
+    desired_size_bounds: SizeBounds,
+    grid_margins: Margins<Px>,
+    gap: Vector2<Px>,
+    frac_min_size: DimsBox<D2, Px>,
+    widgets: Vec<Option<SolvedWidget>>,
+    /// Whether any widgets were collapsed to make the rest fit.
+    collapsed: bool
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SolvedWidget {
+    widget_span: WidgetSpan,
+    /// The widget's minimum size, including its margins.
+    min_size: DimsBox<D2, Px>,
+    /// The ratio between the widget's minimum size and the minimum size it requires of the
+    /// fractional tracks.
+    frac_scale: Vector2<Fr>
 }
 
 impl UpdateHeapCache {
@@ -56,12 +126,57 @@ impl GridEngine {
     pub fn new() -> GridEngine {
         GridEngine {
             grid: TrackVec::new(),
+            explicit_grid_size: GridSize::new(0, 0),
             desired_size: DimsBox::new2(0, 0),
             actual_size: DimsBox::new2(0, 0),
+            overflow: Vector2::new(0, 0),
             desired_size_bounds: SizeBounds::default(),
             actual_size_bounds: SizeBounds::default(),
-            grid_margins: Margins::default()
+            grid_margins: Margins::default(),
+            gap: Vector2::new(0, 0),
+            direction: LayoutDirection::default(),
+            auto_flow: None,
+            solved: None,
+            cache: None
+        }
+    }
+
+    /// Throw away the results of the last solve, forcing the next call to `update_engine` to
+    /// re-solve the grid.
+    ///
+    /// `update_engine` skips solving if none of its inputs have changed since the last call. This
+    /// only needs to be called if the grid's been changed in a way that the engine can't detect.
+    pub fn invalidate(&mut self) {
+        self.solved = None;
+        self.cache = None;
+    }
+
+    /// Hash the widget hints, alongside every setting of the engine that affects the solve.
+    fn hash_inputs(&self, hints: &[WidgetPos]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.desired_size.width(), self.desired_size.height()).hash(&mut hasher);
+        self.desired_size_bounds.hash(&mut hasher);
+        self.grid_margins.hash(&mut hasher);
+        (self.gap.x, self.gap.y).hash(&mut hasher);
+        self.direction.hash(&mut hasher);
+        self.grid.grid_size().hash(&mut hasher);
+        for track in self.grid.col_range(..).unwrap().iter().chain(self.grid.row_range(..).unwrap()) {
+            let track_hints = track.hints();
+            track_hints.min_size.hash(&mut hasher);
+            track_hints.max_size.hash(&mut hasher);
+            track_hints.fr_size.to_bits().hash(&mut hasher);
+            track_hints.content_size.hash(&mut hasher);
+        }
+        for hint in hints {
+            hint.size_bounds.hash(&mut hasher);
+            hint.widget_span.hash(&mut hasher);
+            hint.place_in_cell.hash(&mut hasher);
+            hint.margins.hash(&mut hasher);
+            hint.aspect_ratio.map(f32::to_bits).hash(&mut hasher);
+            hint.absolute.hash(&mut hasher);
+            hint.shrink_priority.hash(&mut hasher);
         }
+        hasher.finish()
     }
 
     pub fn grid_size(&self) -> GridSize {
@@ -69,23 +184,194 @@ impl GridEngine {
     }
 
     pub fn set_grid_size(&mut self, size: GridSize) {
-        self.grid.set_grid_size(size)
+        // Leave any tracks added by auto-flow alone, unless the grid size actually changed.
+        if size != self.explicit_grid_size || (self.auto_flow.is_none() && size != self.grid.grid_size()) {
+            self.explicit_grid_size = size;
+            self.solved = None;
+            self.grid.set_grid_size(size)
+        }
+    }
+
+    /// Fill the spans of widgets that don't have one, growing or shrinking the grid along the flow
+    /// axis to fit them.
+    fn auto_place(&mut self, auto_flow: AutoFlow, hints: &[WidgetPos], placed_hints: &mut Vec<WidgetPos>) {
+        let grid_size = place_auto_flow(self.explicit_grid_size, auto_flow, hints, placed_hints);
+        if grid_size != self.grid.grid_size() {
+            self.solved = None;
+            self.grid.set_grid_size(grid_size);
+        }
+    }
+
+    /// Get a track, or an error if it's out of range.
+    fn track(&self, axis: Axis, index: Tr) -> Result<&GridTrack, TrackOutOfRange> {
+        let track = match axis {
+            Axis::X => self.grid.get_col(index),
+            Axis::Y => self.grid.get_row(index)
+        };
+        track.ok_or(TrackOutOfRange {
+            axis,
+            index,
+            num_tracks: match axis {
+                Axis::X => self.grid.num_cols(),
+                Axis::Y => self.grid.num_rows()
+            }
+        })
+    }
+
+    fn track_mut(&mut self, axis: Axis, index: Tr) -> Result<&mut GridTrack, TrackOutOfRange> {
+        let num_tracks = match axis {
+            Axis::X => self.grid.num_cols(),
+            Axis::Y => self.grid.num_rows()
+        };
+        let track = match axis {
+            Axis::X => self.grid.get_col_mut(index),
+            Axis::Y => self.grid.get_row_mut(index)
+        };
+        track.ok_or(TrackOutOfRange{ axis, index, num_tracks })
     }
 
+    /// Get the hints of a row.
+    ///
+    /// ## Panics
+    /// Panics if the row is out of range. See `try_row_hints` for a non-panicking version.
     pub fn row_hints(&self, row: Tr) -> TrackHints {
-        self.grid.get_row(row).expect(&format!("Row {} out of range", row)).hints()
+        self.try_row_hints(row).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_row_hints(&self, row: Tr) -> Result<TrackHints, TrackOutOfRange> {
+        self.track(Axis::Y, row).map(|track| track.hints())
     }
 
+    /// Set the hints of a row.
+    ///
+    /// ## Panics
+    /// Panics if the row is out of range. See `try_set_row_hints` for a non-panicking version.
     pub fn set_row_hints(&mut self, row: Tr, hints: TrackHints) {
-        self.grid.get_row_mut(row).expect(&format!("Row {} out of range", row)).set_hints(hints).ok();
+        self.try_set_row_hints(row, hints).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set_row_hints(&mut self, row: Tr, hints: TrackHints) -> Result<(), TrackOutOfRange> {
+        self.track_mut(Axis::Y, row)?.set_hints(hints).ok();
+        self.solved = None;
+        Ok(())
     }
 
+    /// Get the hints of a column.
+    ///
+    /// ## Panics
+    /// Panics if the column is out of range. See `try_col_hints` for a non-panicking version.
     pub fn col_hints(&self, col: Tr) -> TrackHints {
-        self.grid.get_col(col).expect(&format!("Col {} out of range", col)).hints()
+        self.try_col_hints(col).unwrap_or_else(|e| panic!("{}", e))
     }
 
+    pub fn try_col_hints(&self, col: Tr) -> Result<TrackHints, TrackOutOfRange> {
+        self.track(Axis::X, col).map(|track| track.hints())
+    }
+
+    /// Set the hints of a column.
+    ///
+    /// ## Panics
+    /// Panics if the column is out of range. See `try_set_col_hints` for a non-panicking version.
     pub fn set_col_hints(&mut self, col: Tr, hints: TrackHints) {
-        self.grid.get_col_mut(col).expect(&format!("Col {} out of range", col)).set_hints(hints).ok();
+        self.try_set_col_hints(col, hints).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set_col_hints(&mut self, col: Tr, hints: TrackHints) -> Result<(), TrackOutOfRange> {
+        self.track_mut(Axis::X, col)?.set_hints(hints).ok();
+        self.solved = None;
+        Ok(())
+    }
+
+    /// ## Panics
+    /// Panics if the track is out of range. See `try_track_resizable` for a non-panicking version.
+    pub fn track_resizable(&self, axis: Axis, index: Tr) -> bool {
+        self.try_track_resizable(axis, index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_track_resizable(&self, axis: Axis, index: Tr) -> Result<bool, TrackOutOfRange> {
+        self.track(axis, index).map(|track| track.resizable())
+    }
+
+    /// Set whether the splitter between track `index` and track `index + 1` can be dragged with
+    /// `resize_track_by`.
+    ///
+    /// ## Panics
+    /// Panics if the track is out of range. See `try_set_track_resizable` for a non-panicking
+    /// version.
+    pub fn set_track_resizable(&mut self, axis: Axis, index: Tr, resizable: bool) {
+        self.try_set_track_resizable(axis, index, resizable).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set_track_resizable(&mut self, axis: Axis, index: Tr, resizable: bool) -> Result<(), TrackOutOfRange> {
+        self.track_mut(axis, index).map(|track| track.set_resizable(resizable))
+    }
+
+    /// Drag the splitter between track `index` and track `index + 1` by `delta_px`, growing one
+    /// track and shrinking the other by the same amount. Returns the number of pixels the splitter
+    /// actually moved, which gets clamped so that neither track leaves its size bounds.
+    ///
+    /// The drag is applied to the sizes from the last call to `update_engine` and stored by
+    /// redistributing the fractional space between the two tracks, so the split keeps its
+    /// proportions when the engine gets resized. As such, both tracks must be fractional; this does
+    /// nothing if either track isn't, if `index` isn't resizable, or if `index` is the last track.
+    pub fn resize_track_by(&mut self, axis: Axis, index: Tr, delta_px: Px) -> Px {
+        let tracks = match axis {
+            Axis::X => self.grid.col_range(index..index + 2),
+            Axis::Y => self.grid.row_range(index..index + 2)
+        };
+        let (a, b) = match tracks {
+            Some(&[a, b]) if a.resizable() && a.fr_size() > 0.0 && b.fr_size() > 0.0 => (a, b),
+            _ => return 0
+        };
+
+        let total = a.size() + b.size();
+        let min_a = cmp::max(a.min_size(), total.saturating_sub(b.max_size()));
+        let max_a = cmp::min(a.max_size(), total - b.min_size());
+        if min_a > max_a || total <= 0 {
+            return 0;
+        }
+        let new_a = cmp::max(min_a, cmp::min(max_a, a.size().saturating_add(delta_px)));
+        if new_a == a.size() {
+            return 0;
+        }
+
+        let (mut hints_a, mut hints_b) = (a.hints(), b.hints());
+        let fr_total = a.fr_size() + b.fr_size();
+        hints_a.fr_size = fr_total * new_a as Fr / total as Fr;
+        hints_b.fr_size = fr_total - hints_a.fr_size;
+
+        match axis {
+            Axis::X => {
+                self.set_col_hints(index, hints_a);
+                self.set_col_hints(index + 1, hints_b);
+            },
+            Axis::Y => {
+                self.set_row_hints(index, hints_a);
+                self.set_row_hints(index + 1, hints_b);
+            }
+        }
+        new_a - a.size()
+    }
+
+    /// The width the last call to `update_engine` gave the column.
+    pub fn col_size(&self, col: Tr) -> Px {
+        self.grid.get_col(col).expect(&format!("Col {} out of range", col)).size()
+    }
+
+    /// The height the last call to `update_engine` gave the row.
+    pub fn row_size(&self, row: Tr) -> Px {
+        self.grid.get_row(row).expect(&format!("Row {} out of range", row)).size()
+    }
+
+    /// The offset of the given cell's top-left corner from the engine's origin, as of the last call
+    /// to `update_engine`. Accounts for the grid margins, gaps, and layout direction.
+    pub fn track_offset(&self, col: Tr, row: Tr) -> Point2<Px> {
+        let offset = self.grid.get_cell_offset(col, row, self.direction)
+            .expect(&format!("Cell ({}, {}) out of range", col, row));
+
+        offset +
+            gaps_offset(&self.grid, self.gap, self.direction, col, row) +
+            Vector2::new(self.grid_margins.left, self.grid_margins.top)
     }
 
     pub fn actual_size(&self) -> DimsBox<D2, Px> {
@@ -96,6 +382,118 @@ impl GridEngine {
         self.actual_size_bounds
     }
 
+    /// The number of pixels the solved grid extended past `desired_size` along each axis, as of the
+    /// last call to `update_engine`. Zero on axes where the content fit.
+    ///
+    /// Widget rects are laid out across the full overflowing size, so scroll containers can size
+    /// their scrollbars from this and translate the children to scroll them.
+    pub fn overflow(&self) -> Vector2<Px> {
+        self.overflow
+    }
+
+    /// Computes the natural size of the grid with the given widgets: the sum of the tracks' minimum
+    /// sizes, plus the grid margins and gaps.
+    ///
+    /// Unlike `update_engine`, this doesn't modify the engine, so parents can find out how big the
+    /// grid wants to be before committing to a layout. Widgets spanning several tracks spread the
+    /// space their tracks don't already cover evenly across those tracks, which may not match the
+    /// distribution a full solve ends up with.
+    pub fn measure(&self, hints: &[WidgetPos]) -> DimsBox<D2, Px> {
+        let mut placed_hints = Vec::new();
+        let (hints, grid_size) = match self.auto_flow {
+            Some(auto_flow) => {
+                let grid_size = place_auto_flow(self.explicit_grid_size, auto_flow, hints, &mut placed_hints);
+                (&placed_hints[..], grid_size)
+            },
+            None => (hints, self.grid.grid_size())
+        };
+
+        let measure_axis = |axis: Axis, num_tracks: Tr, gap: Px| -> Px {
+            // Tracks added by auto-flow since the last solve don't exist yet, so they use the
+            // default hints.
+            let mut track_mins = (0..num_tracks)
+                .map(|index| match axis {
+                    Axis::X => self.grid.get_col(index).map(|t| t.hints()),
+                    Axis::Y => self.grid.get_row(index).map(|t| t.hints())
+                }.unwrap_or_default().min_size)
+                .collect::<Vec<_>>();
+
+            let mut spans = hints.iter()
+                .filter(|hint| hint.absolute.is_none() && !is_auto_placed(hint))
+                .map(|hint| {
+                    let range = match axis {
+                        Axis::X => hint.widget_span.x,
+                        Axis::Y => hint.widget_span.y
+                    };
+                    let end = cmp::min(range.end.unwrap_or(num_tracks), num_tracks);
+                    (range.start.unwrap_or(0), end, axis_min_size(hint, axis))
+                })
+                .filter(|&(start, end, _)| start < end)
+                .collect::<Vec<_>>();
+
+            // Size the tracks for widgets spanning a single track first, so widgets spanning
+            // multiple tracks only add whatever those tracks are missing.
+            spans.sort_by_key(|&(start, end, _)| end - start);
+            for (start, end, widget_min) in spans {
+                let tracks = &mut track_mins[start as usize..end as usize];
+                let num_spanned = tracks.len() as Px;
+                let covered = tracks.iter().sum::<Px>() + gap * (num_spanned - 1);
+                let missing = widget_min - covered;
+                if missing > 0 {
+                    for (i, track_min) in tracks.iter_mut().enumerate() {
+                        *track_min += missing / num_spanned + ((i as Px) < missing % num_spanned) as Px;
+                    }
+                }
+            }
+
+            track_mins.iter().sum::<Px>() + gap.saturating_mul(num_tracks.saturating_sub(1) as Px)
+        };
+
+        DimsBox::new2(
+            measure_axis(Axis::X, grid_size.x, self.gap.x) + self.grid_margins.width(),
+            measure_axis(Axis::Y, grid_size.y, self.gap.y) + self.grid_margins.height()
+        )
+    }
+
+    /// Derive the sizes of content-sized tracks from the size bounds of the widgets that fit in a
+    /// single track. Widgets that span multiple tracks still expand content-sized tracks, the same
+    /// way they expand rigid tracks.
+    fn update_content_sizes(&mut self, hints: &[WidgetPos]) {
+        macro_rules! content_pass {
+            ($axis:ident, $size:ident, $track_range_mut:ident, $margin_front:ident, $margin_back:ident) => {
+                for (index, track) in self.grid.$track_range_mut(..).unwrap().iter_mut().enumerate() {
+                    let content_size = match track.hints().content_size {
+                        Some(content_size) => content_size,
+                        None => continue
+                    };
+
+                    let mut track_content_size = 0;
+                    for hint in hints.iter().filter(|hint| hint.absolute.is_none()) {
+                        let span = hint.widget_span.$axis;
+                        if span.start.unwrap_or(0) != index as Tr || span.size(0, 1) != 1 {
+                            continue;
+                        }
+
+                        let margins = hint.margins.$margin_front + hint.margins.$margin_back;
+                        let widget_min = hint.size_bounds.min.$size();
+                        let widget_max = hint.size_bounds.max.$size();
+                        let widget_content_size = match content_size {
+                            ContentSize::MaxContent if widget_max != Px::max_value() => cmp::max(widget_min, widget_max),
+                            ContentSize::MaxContent |
+                            ContentSize::MinContent => widget_min
+                        };
+                        track_content_size = cmp::max(track_content_size, widget_content_size.saturating_add(margins));
+                    }
+
+                    track.set_content_size(track_content_size);
+                }
+            }
+        }
+
+        content_pass!(x, width, col_range_mut, left, right);
+        content_pass!(y, height, row_range_mut, top, bottom);
+    }
+
     /// This method is the core of the derin layout engine, and is easily the most complex
     /// method it has. This takes a layout engine, iterates over all of the updates performed on that
     /// engine, and performs constraint solving to ensure that all* of the constraints within the engine
@@ -112,10 +510,191 @@ impl GridEngine {
     ) {
         assert_eq!(hints.len(), rects.len());
 
+        let mut placed_hints = mem::replace(&mut heap_cache.placed_hints, Vec::new());
+        let hints = match self.auto_flow {
+            Some(auto_flow) => {
+                self.auto_place(auto_flow, hints, &mut placed_hints);
+                &placed_hints[..]
+            },
+            None => hints
+        };
+
+        let input_hash = self.hash_inputs(hints);
+        match self.cache {
+            Some(ref cache) if cache.input_hash == input_hash && cache.rects.len() == rects.len() => {
+                rects.copy_from_slice(&cache.rects);
+                heap_cache.placed_hints = placed_hints;
+                return;
+            },
+            _ => ()
+        }
+
+        self.solve_collapsing(hints, rects, heap_cache);
+
+        let (mut cached_hints, mut cached_rects) = self.cache.take()
+            .map(|c| (c.hints, c.rects))
+            .unwrap_or((Vec::new(), Vec::new()));
+        cached_hints.clear();
+        cached_hints.extend_from_slice(hints);
+        cached_rects.clear();
+        cached_rects.extend_from_slice(rects);
+        self.cache = Some(LayoutCache {
+            input_hash,
+            hints: cached_hints,
+            rects: cached_rects
+        });
+        heap_cache.placed_hints = placed_hints;
+    }
+
+    /// Solve the grid for widgets whose size bounds depend on their width, such as wrapped text.
+    ///
+    /// After the grid is solved, `measure` gets called with the index and rect width of every
+    /// widget placed in the grid. If it returns new size bounds for any widget, the grid is
+    /// re-solved with those bounds, so a wrapped label can report the height it needs at the width
+    /// it actually got within a single relayout. This repeats until the size bounds settle, up to
+    /// a fixed number of passes.
+    pub fn update_engine_measured<M>(
+        &mut self,
+        hints: &[WidgetPos],
+        rects: &mut [Result<BoundBox<D2, Px>, SolveError>],
+        heap_cache: &mut UpdateHeapCache,
+        mut measure: M
+    )
+        where M: FnMut(usize, Px) -> Option<SizeBounds>
+    {
+        let mut measured_hints = mem::replace(&mut heap_cache.measured_hints, Vec::new());
+        measured_hints.clear();
+        measured_hints.extend_from_slice(hints);
+
+        let mut passes = 0;
+        loop {
+            self.update_engine(&measured_hints, rects, heap_cache);
+            passes += 1;
+            if passes == MAX_MEASURE_PASSES {
+                break;
+            }
+
+            let mut changed = false;
+            for (index, (hint, rect)) in measured_hints.iter_mut().zip(rects.iter()).enumerate() {
+                let rect = match (hint.absolute, rect) {
+                    (None, Ok(rect)) => rect,
+                    _ => continue
+                };
+                if let Some(size_bounds) = measure(index, rect.width()) {
+                    changed |= size_bounds != hint.size_bounds;
+                    hint.size_bounds = size_bounds;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        heap_cache.measured_hints = measured_hints;
+    }
+
+    /// Solve the grid. If the widgets don't fit in the engine, collapse the widgets that have a
+    /// `shrink_priority` until the rest do, starting with the lowest priority. Collapsed widgets
+    /// take up no space, and get their rects set to `Err(SolveError::Collapsed)`.
+    fn solve_collapsing(
+        &mut self,
+        hints: &[WidgetPos],
+        rects: &mut [Result<BoundBox<D2, Px>, SolveError>],
+        heap_cache: &mut UpdateHeapCache
+    ) {
+        let collapsible = |hint: &WidgetPos| match hint.absolute {
+            None => hint.shrink_priority,
+            Some(_) => None
+        };
+        if !hints.iter().any(|hint| collapsible(hint).is_some()) {
+            self.solve(hints, rects, heap_cache);
+            return;
+        }
+
+        let fits = |engine: &GridEngine, rects: &[Result<BoundBox<D2, Px>, SolveError>]|
+            engine.overflow == Vector2::new(0, 0) &&
+            !rects.iter().any(|rect| match rect {
+                Err(SolveError::WidgetUnsolvable(_)) => true,
+                _ => false
+            });
+        let collapse = |hint: &mut WidgetPos| {
+            hint.size_bounds.min = DimsBox::new2(0, 0);
+            hint.margins = Margins::default();
+        };
+
+        let mut collapsed_hints = mem::replace(&mut heap_cache.collapsed_hints, Vec::new());
+
+        // The engine's minimum size is its size with every collapsible widget collapsed, since
+        // otherwise the engine's parent would never shrink it enough for widgets to collapse.
+        collapsed_hints.clear();
+        collapsed_hints.extend_from_slice(hints);
+        for hint in collapsed_hints.iter_mut().filter(|hint| collapsible(hint).is_some()) {
+            collapse(hint);
+        }
+        self.solve(&collapsed_hints, rects, heap_cache);
+        let min_size = self.actual_size_bounds.min;
+
+        collapsed_hints.clear();
+        collapsed_hints.extend_from_slice(hints);
+        // The highest priority that's been collapsed so far.
+        let mut collapsed_priority = None;
+        loop {
+            self.solve(&collapsed_hints, rects, heap_cache);
+            if fits(self, rects) {
+                break;
+            }
+
+            let priority = match hints.iter()
+                .filter_map(collapsible)
+                .filter(|&priority| Some(priority) > collapsed_priority)
+                .min()
+            {
+                Some(priority) => priority,
+                None => break
+            };
+            for hint in collapsed_hints.iter_mut().filter(|hint| collapsible(hint) == Some(priority)) {
+                collapse(hint);
+            }
+            collapsed_priority = Some(priority);
+        }
+        self.actual_size_bounds.min = min_size;
+
+        if let Some(collapsed_priority) = collapsed_priority {
+            for (hint, rect) in hints.iter().zip(rects.iter_mut()) {
+                if collapsible(hint).map(|p| p <= collapsed_priority).unwrap_or(false) {
+                    *rect = Err(SolveError::Collapsed);
+                }
+            }
+            if let Some(ref mut solved) = self.solved {
+                solved.collapsed = true;
+            }
+        }
+        heap_cache.collapsed_hints = collapsed_hints;
+    }
+
+    /// Solve the grid, storing the results in `rects`.
+    fn solve(
+        &mut self,
+        hints: &[WidgetPos],
+        rects: &mut [Result<BoundBox<D2, Px>, SolveError>],
+        heap_cache: &mut UpdateHeapCache
+    ) {
+        // Gaps only appear between tracks, so there's one less gap than there are tracks.
+        let gaps_size = DimsBox::new2(
+            self.gap.x.saturating_mul(self.grid.num_cols().saturating_sub(1) as Px),
+            self.gap.y.saturating_mul(self.grid.num_rows().saturating_sub(1) as Px)
+        );
+
         // We start out by setting the free space to its maximum possible value.
-        let mut free_width = sub_px_bound_zero(self.desired_size.width(), self.grid_margins.width());
+        let mut free_width = sub_px_bound_zero(
+            sub_px_bound_zero(self.desired_size.width(), self.grid_margins.width()),
+            gaps_size.width()
+        );
         let mut fr_total_width = 0.0;
-        let mut free_height = sub_px_bound_zero(self.desired_size.height(), self.grid_margins.height());
+        let mut free_height = sub_px_bound_zero(
+            sub_px_bound_zero(self.desired_size.height(), self.grid_margins.height()),
+            gaps_size.height()
+        );
         let mut fr_total_height = 0.0;
 
         // Reset the actual size bounds to zero.
@@ -124,6 +703,9 @@ impl GridEngine {
             max: DimsBox::new2(0, 0)
         };
 
+        self.update_content_sizes(hints);
+        let mut solved_widgets = vec![None; hints.len()];
+
         let mut frac_min_size = DimsBox::new2(0, 0);
 
         let mut rigid_min_size = DimsBox::new2(0, 0);
@@ -136,7 +718,7 @@ impl GridEngine {
                     self.actual_size_bounds.max.dims.$axis = Px::max_value();
                 } else {
                     for (index, track) in self.grid.$track_range_mut(..).unwrap().iter_mut().enumerate() {
-                        let track_fr_size = track.hints().fr_size;
+                        let track_fr_size = track.fr_size();
                         if track_fr_size <= 0.0 {
                             track.reset_shrink();
                             rigid_min_size.dims.$axis += track.min_size();
@@ -163,13 +745,15 @@ impl GridEngine {
         first_track_pass!(x, push_col, col_range_mut, free_width, fr_total_width);
         first_track_pass!(y, push_row, row_range_mut, free_height, fr_total_height);
 
+        self.actual_size_bounds.max.dims.x = self.actual_size_bounds.max.width().saturating_add(gaps_size.width());
+        self.actual_size_bounds.max.dims.y = self.actual_size_bounds.max.height().saturating_add(gaps_size.height());
 
         self.actual_size_bounds.max =
             self.desired_size_bounds.bound_rect(self.actual_size_bounds.max);
 
         self.actual_size_bounds.min = DimsBox::new2(
-            frac_min_size.width() + rigid_min_size.width() + self.grid_margins.width(),
-            frac_min_size.height() + rigid_min_size.height() + self.grid_margins.height()
+            frac_min_size.width() + rigid_min_size.width() + self.grid_margins.width() + gaps_size.width(),
+            frac_min_size.height() + rigid_min_size.height() + self.grid_margins.height() + gaps_size.height()
         );
         self.actual_size_bounds.min =
             self.desired_size_bounds.bound_rect(self.actual_size_bounds.min);
@@ -190,7 +774,7 @@ impl GridEngine {
                     let mut pft_index = 0;
                     while let Some(track_index) = heap_cache.potential_frac_tracks.$get_track(pft_index).cloned() {
                         let track = self.grid.$get_track(track_index).unwrap();
-                        let track_fr_size = track.hints().fr_size;
+                        let track_fr_size = track.fr_size();
                         let mut track_copy = track.clone();
 
                         // While this isn't an *exact* calculation of the new size of the track (due to remainders and whatnot
@@ -234,7 +818,7 @@ impl GridEngine {
                         let mut fr_divider = FrDivider::new(heap_cache.frac_tracks.$num_tracks_method(), $free_size, $fr_total);
                         while let Some(track_index) = heap_cache.frac_tracks.$get_track(frac_index).map(|t| *t as Tr) {
                             let track = self.grid.$get_track_mut(track_index).unwrap();
-                            let track_fr_size = track.hints().fr_size;
+                            let track_fr_size = track.fr_size();
 
                             let new_size = fr_divider.divvy(track_fr_size);
 
@@ -269,7 +853,13 @@ impl GridEngine {
             track_constraints!(get_row, get_row_mut, push_row, num_rows, remove_row, free_height, fr_total_height);
 
             let mut solvable_index = 0;
-            for (hint, widget_rect) in hints.iter().zip(rects.iter_mut()) {
+            for (widget_index, (hint, widget_rect)) in hints.iter().zip(rects.iter_mut()).enumerate() {
+                // Absolutely positioned widgets don't affect the tracks, and get placed once the
+                // engine's size is known.
+                if hint.absolute.is_some() {
+                    continue;
+                }
+
                 if 0 < hint.widget_span.x.size(0, 1) &&
                    0 < hint.widget_span.y.size(0, 1)
                 {
@@ -319,8 +909,13 @@ impl GridEngine {
                             let mut min_size_debt = widget_size_bounds.min.$size();
 
                             if let Some(track_slice) = grid.$track_range(hint.widget_span.$axis) {
+                                // The gaps between the tracks the widget spans are part of the widget's cell.
+                                let spanned_gaps = self.gap.$axis.saturating_mul(track_slice.len().saturating_sub(1) as Px);
+                                px_widget += spanned_gaps;
+                                min_size_debt = sub_px_bound_zero(min_size_debt, spanned_gaps);
+
                                 for (index, track) in track_slice.iter().enumerate() {
-                                    let track_fr_size = track.hints().fr_size;
+                                    let track_fr_size = track.fr_size();
                                     px_widget += track.size();
                                     min_size_debt = sub_px_bound_zero(min_size_debt, track.min_size());
 
@@ -334,6 +929,11 @@ impl GridEngine {
                                 }
                             }
 
+                            let frac_scale = match fr_widget > 0.0 {
+                                true => $fr_axis / fr_widget,
+                                false => 0.0
+                            };
+
                             if solvable.$axis == SolveAxis::Solvable {
                                 let mut grid_changed = false;
 
@@ -388,11 +988,7 @@ impl GridEngine {
                                 }
 
                                 frac_min_size.dims.$axis = cmp::max(
-                                    match fr_widget > 0.0 {
-                                        true =>
-                                            (widget_size_bounds.min.$size() as Fr * $fr_axis / fr_widget).ceil() as Px,
-                                        false => 0
-                                    },
+                                    (widget_size_bounds.min.$size() as Fr * frac_scale).ceil() as Px,
                                     frac_min_size.$size()
                                 );
 
@@ -402,7 +998,7 @@ impl GridEngine {
                                     solvable.$axis = SolveAxis::Unsolvable;
                                 }
 
-                                actual_size_bounds.min.dims.$axis = frac_min_size.$size() + rigid_min_size.$size() + self.grid_margins.$size();
+                                actual_size_bounds.min.dims.$axis = frac_min_size.$size() + rigid_min_size.$size() + self.grid_margins.$size() + gaps_size.$size();
                                 if actual_size.$size() < actual_size_bounds.min.$size() {
                                     grid_changed = true;
                                     actual_size.dims.$axis = actual_size_bounds.min.$size();
@@ -416,55 +1012,378 @@ impl GridEngine {
                                 }
                             }
 
-                            px_widget
+                            (px_widget, frac_scale)
                         }}
                     }
 
                     // The widget_scale macro isn't guaranteed to return, but if it does it returns the axis size
                     // if it does. If it doesn't, the rest of this body is skipped and we go back to the beginning
                     // of the `update` loop.
-                    let size_x = widget_scale!(x, width, col_range, col_range_mut, free_width, fr_total_width);
-                    let size_y = widget_scale!(y, height, row_range, row_range_mut, free_height, fr_total_height);
+                    let (size_x, frac_scale_x) = widget_scale!(x, width, col_range, col_range_mut, free_width, fr_total_width);
+                    let (size_y, frac_scale_y) = widget_scale!(y, height, row_range, row_range_mut, free_height, fr_total_height);
+
+                    if *solvable == Solvable::default() {
+                        solved_widgets[widget_index] = Some(SolvedWidget {
+                            widget_span: hint.widget_span,
+                            min_size: widget_size_bounds.min,
+                            frac_scale: Vector2::new(frac_scale_x, frac_scale_y)
+                        });
+                    }
 
                     // Perform cell hinting and set
-                    let widget_origin_rect = DimsBox::new2(size_x, size_y);
-
-                    if let Some(offset) = grid.get_cell_offset(
-                        hint.widget_span.x.start.unwrap_or(0),
-                        hint.widget_span.y.start.unwrap_or(0)
-                    ) {
-                        let outer_rect = BoundBox::from(widget_origin_rect) + offset.to_vec();
-                        let cell_hinter = CellHinter::new(outer_rect, hint.place_in_cell);
-
+                    *widget_rect = hint_cell(grid, self.gap, self.grid_margins, self.direction, hint, DimsBox::new2(size_x, size_y));
+                    if *widget_rect != Err(SolveError::CellOutOfBounds) {
                         solvable_index += 1;
-                        let grid_margin_offset = Vector2::new(self.grid_margins.left, self.grid_margins.top);
-                        *widget_rect = cell_hinter.hint(hint.size_bounds, hint.margins)
-                            .map(|rect| BoundBox::from(rect) + grid_margin_offset)
-                            .map_err(|_| SolveError::WidgetUnsolvable)
-                    } else {
-                        *widget_rect = Err(SolveError::CellOutOfBounds)
                     }
                 } else {
-                    *widget_rect = Err(SolveError::WidgetUnsolvable)
+                    let axis = match hint.widget_span.x.size(0, 1) {
+                        0 => Axis::X,
+                        _ => Axis::Y
+                    };
+                    *widget_rect = Err(SolveError::WidgetUnsolvable(Unsolvable {
+                        axis,
+                        widget_min: axis_min_size(hint, axis),
+                        cell_size: 0,
+                        first_track: match axis {
+                            Axis::X => hint.widget_span.x.start.unwrap_or(0),
+                            Axis::Y => hint.widget_span.y.start.unwrap_or(0)
+                        },
+                        num_tracks: 0,
+                        tracks_saturated: false
+                    }))
                 }
             }
 
             break 'update;
         }
 
+        self.overflow = Vector2::new(
+            sub_px_bound_zero(self.actual_size.width(), self.desired_size.width()),
+            sub_px_bound_zero(self.actual_size.height(), self.desired_size.height())
+        );
+
+        for (hint, widget_rect) in hints.iter().zip(rects.iter_mut()) {
+            if let Some(ref absolute) = hint.absolute {
+                *widget_rect = place_absolute(absolute, self.actual_size);
+            }
+        }
+
+        self.solved = Some(SolvedState {
+            desired_size: self.desired_size,
+            desired_size_bounds: self.desired_size_bounds,
+            grid_margins: self.grid_margins,
+            gap: self.gap,
+            frac_min_size,
+            widgets: solved_widgets,
+            collapsed: false
+        });
+
         heap_cache.frac_tracks.clear();
         heap_cache.potential_frac_tracks.clear();
         heap_cache.rigid_tracks_widget.clear();
         heap_cache.frac_tracks_widget.clear();
         heap_cache.solvable_widgets.clear();
     }
+
+    /// Re-solve the layout after the hint of a single widget changed.
+    ///
+    /// `hints` and `rects` should be the slices last passed to `update_engine`, with the hint at
+    /// `widget_index` changed. If the new hint fits in the widget's cell without resizing any of
+    /// the grid's tracks, only that widget's rect gets updated. Otherwise, the whole grid gets
+    /// re-solved with `update_engine`.
+    ///
+    /// Returns `true` if the rects of any other widgets may have changed.
+    pub fn update_widget(
+        &mut self,
+        widget_index: usize,
+        hints: &[WidgetPos],
+        rects: &mut [Result<BoundBox<D2, Px>, SolveError>],
+        heap_cache: &mut UpdateHeapCache
+    ) -> bool {
+        assert_eq!(hints.len(), rects.len());
+
+        // Auto-placed widgets keep the cell they were placed in by the last solve.
+        let mut hint = hints[widget_index];
+        if let (true, Some(cache)) = (self.auto_flow.is_some() && is_auto_placed(&hint), self.cache.as_ref()) {
+            if let Some(placed_hint) = cache.hints.get(widget_index) {
+                hint.widget_span = placed_hint.widget_span;
+            }
+        }
+
+        if let Some(rect) = self.update_widget_in_place(widget_index, &hint) {
+            rects[widget_index] = rect;
+
+            // Keep the cache in sync, so `explain` reports the new rect.
+            if let Some(mut cache) = self.cache.take() {
+                if cache.rects.len() == rects.len() {
+                    cache.hints[widget_index] = hint;
+                    cache.rects[widget_index] = rect;
+                    cache.input_hash = self.hash_inputs(&cache.hints);
+                    self.cache = Some(cache);
+                }
+            }
+            return false;
+        }
+
+        let mut track_sizes = mem::replace(&mut heap_cache.track_sizes, Vec::new());
+        track_sizes.clear();
+        track_sizes.extend(self.grid.col_range(..).unwrap().iter().chain(self.grid.row_range(..).unwrap()).map(|t| t.size()));
+
+        let old_actual_size = self.actual_size;
+        self.update_engine(hints, rects, heap_cache);
+
+        let tracks_moved =
+            old_actual_size != self.actual_size ||
+            !track_sizes.iter().cloned().eq(self.grid.col_range(..).unwrap().iter().chain(self.grid.row_range(..).unwrap()).map(|t| t.size()));
+        heap_cache.track_sizes = track_sizes;
+        tracks_moved
+    }
+
+    /// Solve the widget's rect without touching the grid's tracks. Returns `None` if the widget's
+    /// new hint could change the size of any track, in which case the whole grid must be re-solved.
+    fn update_widget_in_place(&mut self, widget_index: usize, hint: &WidgetPos) -> Option<Result<BoundBox<D2, Px>, SolveError>> {
+        let solved = self.solved.as_mut()?;
+        let unchanged_engine =
+            solved.desired_size == self.desired_size &&
+            solved.desired_size_bounds == self.desired_size_bounds &&
+            solved.grid_margins == self.grid_margins &&
+            solved.gap == self.gap;
+        // Resizing a widget could change which widgets need to be collapsed.
+        if !unchanged_engine || solved.collapsed {
+            return None;
+        }
+
+        // Absolutely positioned widgets never touch the tracks, so they can always be placed
+        // in-place unless they were previously laid out in the grid.
+        if let Some(ref absolute) = hint.absolute {
+            return match solved.widgets.get(widget_index)? {
+                None => Some(place_absolute(absolute, self.actual_size)),
+                Some(_) => None
+            };
+        }
+
+        let old_widget = (*solved.widgets.get(widget_index)?)?;
+        if old_widget.widget_span != hint.widget_span {
+            return None;
+        }
+
+        let new_min_size = DimsBox::new2(
+            hint.size_bounds.min.width() + hint.margins.left + hint.margins.right,
+            hint.size_bounds.min.height() + hint.margins.top + hint.margins.bottom
+        );
+        let mut cell_dims = DimsBox::new2(0, 0);
+
+        macro_rules! check_axis {
+            ($axis:ident, $size:ident, $track_range:ident) => {{
+                let tracks = self.grid.$track_range(hint.widget_span.$axis)?;
+                if tracks.len() == 0 || tracks.iter().any(|t| t.hints().content_size.is_some()) {
+                    return None;
+                }
+
+                let gaps = self.gap.$axis.saturating_mul(tracks.len() as Px - 1);
+                let tracks_min: Px = tracks.iter().map(|t| t.min_size()).sum::<Px>() + gaps;
+                let (old_min, new_min) = (old_widget.min_size.$size(), new_min_size.$size());
+
+                // If the widget was holding the rigid tracks open, shrinking it shrinks the tracks.
+                // If it's bigger than the tracks' minimum size, the rigid tracks have to grow.
+                let has_rigid = tracks.iter().any(|t| t.fr_size() == 0.0);
+                if has_rigid && (new_min > tracks_min || (old_min >= tracks_min && new_min < old_min)) {
+                    return None;
+                }
+
+                // Likewise for the minimum size of the fractional tracks.
+                let frac_scale = old_widget.frac_scale.$axis;
+                let frac_min = solved.frac_min_size.$size();
+                let (old_frac_min, new_frac_min) = (
+                    (old_min as Fr * frac_scale).ceil() as Px,
+                    (new_min as Fr * frac_scale).ceil() as Px
+                );
+                if new_frac_min > frac_min || (old_frac_min >= frac_min && new_frac_min < old_frac_min) {
+                    return None;
+                }
+
+                cell_dims.dims.$axis = tracks.iter().map(|t| t.size()).sum::<Px>() + gaps;
+            }}
+        }
+
+        check_axis!(x, width, col_range);
+        check_axis!(y, height, row_range);
+
+        solved.widgets[widget_index] = Some(SolvedWidget {
+            min_size: new_min_size,
+            ..old_widget
+        });
+        Some(hint_cell(&self.grid, self.gap, self.grid_margins, self.direction, hint, cell_dims))
+    }
+
+    /// Describe how the widget at `widget_index` was placed by the last solve, for debugging
+    /// layouts. The returned value's `Display` implementation prints a human-readable summary,
+    /// including why the widget couldn't be solved if it failed.
+    ///
+    /// Returns `None` if the engine hasn't been solved since it was last invalidated, or if
+    /// `widget_index` is out of range.
+    pub fn explain(&self, widget_index: usize) -> Option<Explanation> {
+        let cache = self.cache.as_ref()?;
+        let hint = *cache.hints.get(widget_index)?;
+
+        let track_infos = |tracks: Option<&[GridTrack]>, first_track: Tr| -> Vec<TrackInfo> {
+            tracks.unwrap_or(&[]).iter().enumerate()
+                .map(|(i, track)| TrackInfo {
+                    index: first_track + i as Tr,
+                    size: track.size(),
+                    min_size: track.min_size(),
+                    max_size: track.max_size(),
+                    fr_size: track.fr_size()
+                })
+                .collect()
+        };
+        let (cols, rows) = match hint.absolute {
+            Some(_) => (Vec::new(), Vec::new()),
+            None => (
+                track_infos(self.grid.col_range(hint.widget_span.x), hint.widget_span.x.start.unwrap_or(0)),
+                track_infos(self.grid.row_range(hint.widget_span.y), hint.widget_span.y.start.unwrap_or(0))
+            )
+        };
+
+        Some(Explanation {
+            widget_index,
+            hint,
+            result: cache.rects[widget_index],
+            cols,
+            rows
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SolveError {
     /// The widget's constraints lead to it being unsolvable.
-    WidgetUnsolvable,
-    CellOutOfBounds
+    WidgetUnsolvable(Unsolvable),
+    CellOutOfBounds,
+    /// The widget was collapsed because the engine was too small to fit every widget, as allowed
+    /// by its `shrink_priority`. The widget should be hidden.
+    Collapsed
+}
+
+/// An error returned when accessing a column or row that isn't in the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackOutOfRange {
+    /// `X` for columns, `Y` for rows.
+    pub axis: Axis,
+    pub index: Tr,
+    /// The number of tracks in the grid along `axis`.
+    pub num_tracks: Tr
+}
+
+impl fmt::Display for TrackOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match (self.axis, self.num_tracks) {
+            (Axis::X, 1) => "column",
+            (Axis::X, _) => "columns",
+            (Axis::Y, 1) => "row",
+            (Axis::Y, _) => "rows"
+        };
+        let track_name = match self.axis {
+            Axis::X => "Col",
+            Axis::Y => "Row"
+        };
+        write!(f, "{} {} out of range; the grid has {} {}", track_name, self.index, self.num_tracks, name)
+    }
+}
+
+impl Error for TrackOutOfRange {}
+
+/// Details on why a widget couldn't be solved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsolvable {
+    /// The axis the widget didn't fit on.
+    pub axis: Axis,
+    /// The widget's minimum size along the axis, including its margins.
+    pub widget_min: Px,
+    /// The size of the cell the widget was placed in along the axis.
+    pub cell_size: Px,
+    /// The first track the widget spans along the axis.
+    pub first_track: Tr,
+    /// The number of tracks the widget spans along the axis. `0` if the widget's span is empty.
+    pub num_tracks: Tr,
+    /// Whether every track the widget spans has hit its maximum size, so the cell can't grow to
+    /// fit the widget.
+    pub tracks_saturated: bool
+}
+
+/// How a widget was placed by the last solve, as returned by `GridEngine::explain`.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub widget_index: usize,
+    /// The hint the widget was solved with.
+    pub hint: WidgetPos,
+    pub result: Result<BoundBox<D2, Px>, SolveError>,
+    /// The columns the widget spans.
+    pub cols: Vec<TrackInfo>,
+    /// The rows the widget spans.
+    pub rows: Vec<TrackInfo>
+}
+
+/// The solved state of a single track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackInfo {
+    pub index: Tr,
+    pub size: Px,
+    pub min_size: Px,
+    pub max_size: Px,
+    pub fr_size: Fr
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let axis_name = |axis| match axis {
+            Axis::X => "x",
+            Axis::Y => "y"
+        };
+        let px = |size: Px| match size {
+            size if size == Px::max_value() => "inf".to_string(),
+            size => size.to_string()
+        };
+
+        write!(f, "widget {}: ", self.widget_index)?;
+        match self.result {
+            Ok(rect) => writeln!(f, "placed at ({}, {})-({}, {})", rect.min.x, rect.min.y, rect.max.x, rect.max.y)?,
+            Err(SolveError::CellOutOfBounds) => writeln!(f, "its cell is outside of the grid")?,
+            Err(SolveError::Collapsed) => writeln!(f, "collapsed to make room for higher-priority widgets")?,
+            Err(SolveError::WidgetUnsolvable(unsolvable)) if unsolvable.num_tracks == 0 =>
+                writeln!(f, "unsolvable, since its span covers no tracks along the {} axis", axis_name(unsolvable.axis))?,
+            Err(SolveError::WidgetUnsolvable(unsolvable)) => {
+                write!(
+                    f, "unsolvable along the {} axis: it needs {}px including margins, but its cell is {}px",
+                    axis_name(unsolvable.axis), unsolvable.widget_min, unsolvable.cell_size
+                )?;
+                match unsolvable.tracks_saturated {
+                    true => writeln!(f, ", and every track it spans is at its maximum size")?,
+                    false => writeln!(f)?
+                }
+            }
+        }
+
+        let bounds = self.hint.size_bounds;
+        let margins = self.hint.margins;
+        writeln!(
+            f, "  size bounds: min {}x{}, max {}x{}",
+            bounds.min.width(), bounds.min.height(), px(bounds.max.width()), px(bounds.max.height())
+        )?;
+        writeln!(f, "  margins: left {}, top {}, right {}, bottom {}", margins.left, margins.top, margins.right, margins.bottom)?;
+        if self.hint.absolute.is_some() {
+            writeln!(f, "  absolutely positioned")?;
+        }
+        for (name, tracks) in [("column", &self.cols), ("row", &self.rows)].iter() {
+            for track in tracks.iter() {
+                writeln!(
+                    f, "  {} {}: {}px (min {}, max {}, {}fr)",
+                    name, track.index, track.size, track.min_size, px(track.max_size), track.fr_size
+                )?;
+            }
+        }
+        Ok(())
+    }
 }
 
 struct FrDivider {
@@ -532,14 +1451,28 @@ impl Default for SolveAxis {
 #[derive(Debug, Clone, Copy)]
 struct CellHinter {
     outer_rect: BoundBox<D2, Px>,
-    place_in_or: Align2
+    place_in_or: Align2,
+    aspect_ratio: Option<f32>
 }
 
 impl CellHinter {
-    pub fn new(outer_rect: BoundBox<D2, Px>, place_in_or: Align2) -> CellHinter {
+    pub fn new(outer_rect: BoundBox<D2, Px>, place_in_or: Align2, aspect_ratio: Option<f32>, direction: LayoutDirection) -> CellHinter {
+        // `Start` and `End` are relative to the direction the tracks are laid out in, so they swap
+        // along reversed axes.
+        let reverse = |align, reversed| match (align, reversed) {
+            (Align::Start, true) => Align::End,
+            (Align::End, true) => Align::Start,
+            (align, _) => align
+        };
+        let place_in_or = Align2::new(
+            reverse(place_in_or.x, direction.right_to_left),
+            reverse(place_in_or.y, direction.bottom_to_top)
+        );
+
         CellHinter {
             outer_rect: outer_rect,
             place_in_or: place_in_or,
+            aspect_ratio: aspect_ratio
         }
     }
 
@@ -547,12 +1480,36 @@ impl CellHinter {
         let margins_x = margins.left + margins.right;
         let margins_y = margins.top + margins.bottom;
 
-        if bounds.min.width() + margins_x > self.outer_rect.width() ||
-           bounds.min.height() + margins_y > self.outer_rect.height()
-        {
-            return Err(HintError::ORTooSmall)
+        if bounds.min.width() + margins_x > self.outer_rect.width() {
+            return Err(HintError::ORTooSmall(Axis::X))
+        }
+        if bounds.min.height() + margins_y > self.outer_rect.height() {
+            return Err(HintError::ORTooSmall(Axis::Y))
         }
 
+        let bounds = match self.aspect_ratio {
+            Some(ratio) if ratio > 0.0 => {
+                // Shrink whichever axis has more room than the ratio allows, then pin the bounds to
+                // the fitted size so every alignment lays out the same rect.
+                let avail_width = cmp::min(self.outer_rect.width() - margins_x, bounds.max.width());
+                let avail_height = cmp::min(self.outer_rect.height() - margins_y, bounds.max.height());
+
+                let (mut width, mut height) = (avail_width, avail_height);
+                if avail_width as f32 > avail_height as f32 * ratio {
+                    width = (avail_height as f32 * ratio) as Px;
+                } else {
+                    height = (avail_width as f32 / ratio) as Px;
+                }
+
+                let fitted = DimsBox::new2(
+                    cmp::max(width, bounds.min.width()),
+                    cmp::max(height, bounds.min.height())
+                );
+                SizeBounds::new(fitted, fitted)
+            },
+            _ => bounds
+        };
+
         let mut inner_rect = BoundBox::new2(0, 0, 0, 0);
 
         macro_rules! place_on_axis {
@@ -570,7 +1527,7 @@ impl CellHinter {
                         }
                     },
                     Align::Start => {
-                        inner_rect.min.$axis = self.outer_rect.min.$axis + $front_margin + $front_margin;
+                        inner_rect.min.$axis = self.outer_rect.min.$axis + $front_margin;
                         inner_rect.max.$axis = self.outer_rect.min.$axis + bounds.min.$size() + $front_margin;
                     },
                     Align::End => {
@@ -607,6 +1564,142 @@ impl CellHinter {
     }
 }
 
+/// Place a widget in the cell that starts at the widget's span, given the size of the tracks the
+/// widget spans.
+fn hint_cell(
+    grid: &TrackVec,
+    gap: Vector2<Px>,
+    grid_margins: Margins<Px>,
+    direction: LayoutDirection,
+    hint: &WidgetPos,
+    cell_dims: DimsBox<D2, Px>
+) -> Result<BoundBox<D2, Px>, SolveError> {
+    let (cell_x, cell_y) = (hint.widget_span.x.start.unwrap_or(0), hint.widget_span.y.start.unwrap_or(0));
+    let offset = grid.get_cell_offset(cell_x, cell_y, direction).ok_or(SolveError::CellOutOfBounds)?;
+
+    let gaps = gaps_offset(grid, gap, direction, cell_x, cell_y);
+
+    // Along reversed axes, the rest of the span gets laid out before the cell instead of after it.
+    let first_col = grid.get_col(cell_x).unwrap().size();
+    let first_row = grid.get_row(cell_y).unwrap().size();
+    let span_offset = Vector2::new(
+        if direction.right_to_left {first_col - cell_dims.width()} else {0},
+        if direction.bottom_to_top {first_row - cell_dims.height()} else {0}
+    );
+
+    let outer_rect = BoundBox::from(cell_dims) + offset.to_vec() + gaps + span_offset;
+    let cell_hinter = CellHinter::new(outer_rect, hint.place_in_cell, hint.aspect_ratio, direction);
+
+    let grid_margin_offset = Vector2::new(grid_margins.left, grid_margins.top);
+    cell_hinter.hint(hint.size_bounds, hint.margins)
+        .map(|rect| BoundBox::from(rect) + grid_margin_offset)
+        .map_err(|HintError::ORTooSmall(axis)| {
+            let (span, tracks, cell_size) = match axis {
+                Axis::X => (hint.widget_span.x, grid.col_range(hint.widget_span.x), cell_dims.width()),
+                Axis::Y => (hint.widget_span.y, grid.row_range(hint.widget_span.y), cell_dims.height())
+            };
+            let tracks = tracks.unwrap_or(&[]);
+            SolveError::WidgetUnsolvable(Unsolvable {
+                axis,
+                widget_min: axis_min_size(hint, axis),
+                cell_size,
+                first_track: span.start.unwrap_or(0),
+                num_tracks: tracks.len() as Tr,
+                tracks_saturated: tracks.iter().all(|t| t.size() >= t.max_size())
+            })
+        })
+}
+
+/// Fill the spans of widgets that don't have one, returning the size of the grid needed to fit
+/// them.
+fn place_auto_flow(explicit: GridSize, auto_flow: AutoFlow, hints: &[WidgetPos], placed_hints: &mut Vec<WidgetPos>) -> GridSize {
+    placed_hints.clear();
+    placed_hints.extend_from_slice(hints);
+
+    // The grid always keeps its size on the fixed axis, and grows along the flow axis.
+    let (fixed_tracks, flow_tracks) = match auto_flow {
+        AutoFlow::Row => (cmp::max(explicit.x, 1), explicit.y),
+        AutoFlow::Column => (cmp::max(explicit.y, 1), explicit.x)
+    };
+
+    // Whether each cell is taken, indexed by `flow_track * fixed_tracks + fixed_track`.
+    let mut occupied = vec![false; (fixed_tracks * flow_tracks) as usize];
+    let occupy = |occupied: &mut Vec<bool>, cell: usize| {
+        while occupied.len() <= cell {
+            occupied.extend((0..fixed_tracks).map(|_| false));
+        }
+        occupied[cell] = true;
+    };
+
+    for hint in hints.iter().filter(|h| h.absolute.is_none() && !is_auto_placed(h)) {
+        let (fixed_range, flow_range) = match auto_flow {
+            AutoFlow::Row => (hint.widget_span.x, hint.widget_span.y),
+            AutoFlow::Column => (hint.widget_span.y, hint.widget_span.x)
+        };
+        let fixed_end = cmp::min(fixed_range.end.unwrap_or(fixed_tracks), fixed_tracks);
+        for flow in flow_range.start.unwrap_or(0)..flow_range.end.unwrap_or(flow_tracks) {
+            for fixed in fixed_range.start.unwrap_or(0)..fixed_end {
+                occupy(&mut occupied, (flow * fixed_tracks + fixed) as usize);
+            }
+        }
+    }
+
+    let mut cursor = 0;
+    for hint in placed_hints.iter_mut().filter(|h| is_auto_placed(h)) {
+        while occupied.get(cursor) == Some(&true) {
+            cursor += 1;
+        }
+        occupy(&mut occupied, cursor);
+
+        let (fixed, flow) = ((cursor % fixed_tracks as usize) as Tr, (cursor / fixed_tracks as usize) as Tr);
+        hint.widget_span = match auto_flow {
+            AutoFlow::Row => WidgetSpan::new(fixed, flow),
+            AutoFlow::Column => WidgetSpan::new(flow, fixed)
+        };
+        cursor += 1;
+    }
+
+    let flow_tracks = cmp::max(flow_tracks, occupied.len() as Tr / fixed_tracks);
+    match auto_flow {
+        AutoFlow::Row => GridSize::new(fixed_tracks, flow_tracks),
+        AutoFlow::Column => GridSize::new(flow_tracks, fixed_tracks)
+    }
+}
+
+/// Whether auto-flow should pick the widget's cell, because its span is empty on both axes.
+fn is_auto_placed(hint: &WidgetPos) -> bool {
+    let is_empty = |range: TrRange| range.start.is_some() && range.start == range.end;
+    hint.absolute.is_none() && is_empty(hint.widget_span.x) && is_empty(hint.widget_span.y)
+}
+
+/// The widget's minimum size along the axis, including its margins.
+fn axis_min_size(hint: &WidgetPos, axis: Axis) -> Px {
+    match axis {
+        Axis::X => hint.size_bounds.min.width() + hint.margins.left + hint.margins.right,
+        Axis::Y => hint.size_bounds.min.height() + hint.margins.top + hint.margins.bottom
+    }
+}
+
+/// Place an absolutely positioned widget in a parent of the given size. The widget's rect is
+/// clamped to the parent's rect.
+fn place_absolute(absolute: &AbsolutePos, parent_dims: DimsBox<D2, Px>) -> Result<BoundBox<D2, Px>, SolveError> {
+    BoundBox::from(parent_dims).intersect_rect(absolute.rect(parent_dims))
+        .ok_or(SolveError::CellOutOfBounds)
+}
+
+/// The total size of the gaps between the given cell and the layout's origin.
+fn gaps_offset(grid: &TrackVec, gap: Vector2<Px>, direction: LayoutDirection, col: Tr, row: Tr) -> Vector2<Px> {
+    let gaps_before = |cell: Tr, num_tracks: Tr, reversed: bool| match reversed {
+        false => cell,
+        true => num_tracks - 1 - cell
+    } as Px;
+
+    Vector2::new(
+        gap.x * gaps_before(col, grid.num_cols(), direction.right_to_left),
+        gap.y * gaps_before(row, grid.num_rows(), direction.bottom_to_top)
+    )
+}
+
 #[inline]
 fn sub_px_bound_zero(lhs: Px, rhs: Px) -> Px {
     let result = lhs.saturating_sub(rhs);
@@ -618,8 +1711,9 @@ fn sub_px_bound_zero(lhs: Px, rhs: Px) -> Px {
 }
 
 enum HintError {
-    /// The outer rect is smaller than the minimum size bound, making constraint unsolvable
-    ORTooSmall
+    /// The outer rect is smaller than the minimum size bound along the axis, making constraint
+    /// unsolvable
+    ORTooSmall(Axis)
 }
 
 
@@ -629,7 +1723,6 @@ mod tests {
     use quickcheck::{Arbitrary, Gen};
     use cgmath_geometry::*;
     use crate::cgmath::Point2;
-    use std::mem;
 
     quickcheck!{
         fn test_px_divvy(desired_size: Px, frac_sizes: Vec<Fr>) -> bool {
@@ -659,6 +1752,508 @@ mod tests {
         }
     }
 
+    #[test]
+    fn grid_gaps() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(3, 2));
+        engine.desired_size = DimsBox::new2(320, 110);
+        engine.gap = Vector2::new(10, 10);
+
+        let hints = [
+            WidgetPos {
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(1..3, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(2, 1),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 3];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        assert_eq!(Ok(BoundBox::new2(0, 0, 100, 50)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(110, 0, 320, 50)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(220, 60, 320, 110)), rects[2]);
+        assert_eq!(DimsBox::new2(20, 10), engine.actual_size_bounds().min);
+
+        assert_eq!(100, engine.col_size(2));
+        assert_eq!(50, engine.row_size(1));
+        assert_eq!(Point2::new(0, 0), engine.track_offset(0, 0));
+        assert_eq!(Point2::new(220, 60), engine.track_offset(2, 1));
+    }
+
+    #[test]
+    fn absolute_position() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.desired_size = DimsBox::new2(100, 50);
+
+        let hints = [
+            WidgetPos {
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                absolute: Some(AbsolutePos::new(
+                    SizeBounds::new_min(DimsBox::new2(500, 10)),
+                    Align2::new(Align::Start, Align::Start),
+                    Margins::default()
+                )),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                absolute: Some(AbsolutePos::new(
+                    SizeBounds::new_min(DimsBox::new2(10, 10)),
+                    Align2::new(Align::End, Align::End),
+                    Margins::new(0, 0, -5, -5)
+                )),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(1, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 4];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        // The absolute widgets don't expand the grid, and get clamped to the engine's rect.
+        assert_eq!(DimsBox::new2(0, 0), engine.actual_size_bounds().min);
+        assert_eq!(Ok(BoundBox::new2(0, 0, 50, 50)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(0, 0, 100, 10)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(95, 45, 100, 50)), rects[2]);
+        assert_eq!(Ok(BoundBox::new2(50, 0, 100, 50)), rects[3]);
+    }
+
+    #[test]
+    fn auto_flow() {
+        let mut engine = GridEngine::new();
+        engine.auto_flow = Some(AutoFlow::Row);
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.desired_size = DimsBox::new2(100, 100);
+
+        let hints = [
+            WidgetPos {
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos::default(),
+            WidgetPos::default(),
+            WidgetPos::default(),
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 4];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        // The explicitly placed widget's cell gets skipped, and a row gets added for the overflow.
+        assert_eq!(GridSize::new(2, 2), engine.grid_size());
+        assert_eq!(Ok(BoundBox::new2(0, 0, 50, 50)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(50, 0, 100, 50)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(0, 50, 50, 100)), rects[2]);
+        assert_eq!(Ok(BoundBox::new2(50, 50, 100, 100)), rects[3]);
+    }
+
+    #[test]
+    fn measure() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.gap = Vector2::new(10, 0);
+        engine.grid_margins = Margins::new(5, 5, 5, 5);
+
+        let hints = [
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(30, 20)),
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(50, 10)),
+                widget_span: WidgetSpan::new(1, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(200, 10)),
+                widget_span: WidgetSpan::new(0..2, 0),
+                ..WidgetPos::default()
+            },
+        ];
+
+        // The spanning widget needs 110 more pixels than the other two widgets provide, which get
+        // split between both columns.
+        assert_eq!(DimsBox::new2(210, 30), engine.measure(&hints));
+        // Measuring doesn't lay anything out.
+        assert_eq!(DimsBox::new2(0, 0), engine.actual_size());
+        assert_eq!(DimsBox::new2(0, 0), engine.actual_size_bounds().min);
+    }
+
+    #[test]
+    fn overflow() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(1, 1));
+        engine.desired_size = DimsBox::new2(50, 50);
+
+        let hints = [WidgetPos {
+            size_bounds: SizeBounds::new_min(DimsBox::new2(80, 20)),
+            widget_span: WidgetSpan::new(0, 0),
+            ..WidgetPos::default()
+        }];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 1];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(Vector2::new(30, 0), engine.overflow());
+
+        engine.desired_size = DimsBox::new2(100, 50);
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(Vector2::new(0, 0), engine.overflow());
+    }
+
+    #[test]
+    fn explain_unsolvable() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(1, 1));
+        engine.set_col_hints(0, TrackHints {
+            max_size: 30,
+            ..TrackHints::default()
+        });
+        engine.desired_size = DimsBox::new2(100, 50);
+
+        let hints = [
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(30, 10)),
+                margins: Margins::new(5, 0, 5, 0),
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 1];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        let unsolvable = Unsolvable {
+            axis: Axis::X,
+            widget_min: 40,
+            cell_size: 30,
+            first_track: 0,
+            num_tracks: 1,
+            tracks_saturated: true
+        };
+        assert_eq!(Err(SolveError::WidgetUnsolvable(unsolvable)), rects[0]);
+
+        let explanation = engine.explain(0).unwrap();
+        assert_eq!(Err(SolveError::WidgetUnsolvable(unsolvable)), explanation.result);
+        assert_eq!(1, explanation.cols.len());
+        assert_eq!(30, explanation.cols[0].size);
+        assert!(explanation.to_string().contains("needs 40px including margins, but its cell is 30px"));
+        assert!(engine.explain(1).is_none());
+    }
+
+    #[test]
+    fn layout_cache() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.desired_size = DimsBox::new2(100, 50);
+
+        let hints = [
+            WidgetPos {
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(1, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        let solved_rects = rects;
+
+        // Nothing's changed, so the cached rects get returned.
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(solved_rects, rects);
+
+        engine.set_col_hints(0, TrackHints {
+            fr_size: 3.0,
+            ..TrackHints::default()
+        });
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(Ok(BoundBox::new2(0, 0, 75, 50)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(75, 0, 100, 50)), rects[1]);
+
+        engine.invalidate();
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(Ok(BoundBox::new2(0, 0, 75, 50)), rects[0]);
+    }
+
+    #[test]
+    fn resize_track() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(3, 1));
+        engine.desired_size = DimsBox::new2(300, 50);
+        engine.set_col_hints(2, TrackHints {
+            fr_size: 0.0,
+            min_size: 100,
+            max_size: 100,
+            ..TrackHints::default()
+        });
+        engine.set_track_resizable(Axis::X, 0, true);
+        engine.set_track_resizable(Axis::X, 1, true);
+
+        let hints = [
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(40, 0)),
+                widget_span: WidgetSpan::new(1, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 1];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(100, engine.col_size(0));
+
+        // Column 1 can't shrink below its widget's minimum size.
+        assert_eq!(60, engine.resize_track_by(Axis::X, 0, 80));
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(160, engine.col_size(0));
+        assert_eq!(40, engine.col_size(1));
+
+        // Both columns are fractional, so the split keeps its proportions when resized.
+        engine.desired_size = DimsBox::new2(500, 50);
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(320, engine.col_size(0));
+        assert_eq!(80, engine.col_size(1));
+
+        // Column 2 isn't fractional, and column 2 is the last column.
+        assert_eq!(0, engine.resize_track_by(Axis::X, 1, -30));
+        assert_eq!(0, engine.resize_track_by(Axis::X, 2, 10));
+        assert_eq!(0, engine.resize_track_by(Axis::Y, 0, 10));
+    }
+
+    #[test]
+    fn content_sized_tracks() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.desired_size = DimsBox::new2(200, 50);
+
+        let hints = [
+            WidgetPos {
+                size_bounds: SizeBounds::new(DimsBox::new2(30, 0), DimsBox::new2(80, Px::max_value())),
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(1, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+
+        for &(content_size, col_width) in &[(ContentSize::MinContent, 30), (ContentSize::MaxContent, 80)] {
+            engine.set_col_hints(0, TrackHints {
+                content_size: Some(content_size),
+                ..TrackHints::default()
+            });
+            engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+            assert_eq!(Ok(BoundBox::new2(0, 0, col_width, 50)), rects[0]);
+            assert_eq!(Ok(BoundBox::new2(col_width, 0, 200, 50)), rects[1]);
+        }
+    }
+
+    #[test]
+    fn update_widget() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.desired_size = DimsBox::new2(200, 50);
+
+        let mut hints = [
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(10, 10)),
+                widget_span: WidgetSpan::new(0, 0),
+                place_in_cell: Align2::new(Align::Start, Align::Start),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(30, 10)),
+                widget_span: WidgetSpan::new(1, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+        let mut heap_cache = UpdateHeapCache::new();
+        engine.update_engine(&hints, &mut rects, &mut heap_cache);
+        assert_eq!(Ok(BoundBox::new2(0, 0, 10, 10)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(100, 0, 200, 50)), rects[1]);
+
+        // Still fits in its cell, so only the changed widget gets updated.
+        hints[0].size_bounds.min = DimsBox::new2(20, 20);
+        assert!(!engine.update_widget(0, &hints, &mut rects, &mut heap_cache));
+        assert_eq!(Ok(BoundBox::new2(0, 0, 20, 20)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(100, 0, 200, 50)), rects[1]);
+
+        // Needs more space than the fractional columns have, so the whole grid gets re-solved.
+        hints[0].size_bounds.min = DimsBox::new2(150, 20);
+        assert!(engine.update_widget(0, &hints, &mut rects, &mut heap_cache));
+        assert_eq!(300, engine.actual_size_bounds().min.width());
+    }
+
+    #[test]
+    fn layout_direction() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(3, 2));
+        engine.desired_size = DimsBox::new2(320, 110);
+        engine.gap = Vector2::new(10, 10);
+        engine.direction = LayoutDirection::new(true, false);
+
+        let hints = [
+            WidgetPos {
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(1..3, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(20, 10)),
+                widget_span: WidgetSpan::new(0, 1),
+                place_in_cell: Align2::new(Align::Start, Align::Stretch),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 3];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        assert_eq!(Ok(BoundBox::new2(220, 0, 320, 50)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(0, 0, 210, 50)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(300, 60, 320, 110)), rects[2]);
+
+        engine.direction = LayoutDirection::new(true, true);
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        assert_eq!(Ok(BoundBox::new2(220, 60, 320, 110)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(0, 60, 210, 110)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(300, 0, 320, 50)), rects[2]);
+    }
+
+    #[test]
+    fn aspect_ratio() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.desired_size = DimsBox::new2(400, 100);
+
+        let hints = [
+            WidgetPos {
+                widget_span: WidgetSpan::new(0, 0),
+                place_in_cell: Align2::new(Align::Center, Align::Center),
+                aspect_ratio: Some(1.0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(1, 0),
+                aspect_ratio: Some(4.0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        assert_eq!(Ok(BoundBox::new2(50, 0, 150, 100)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(200, 25, 400, 75)), rects[1]);
+    }
+
+    #[test]
+    fn shrink_priority() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(3, 1));
+        engine.desired_size = DimsBox::new2(100, 20);
+
+        let item = |col: Tr, shrink_priority: Option<u32>| WidgetPos {
+            size_bounds: SizeBounds::new_min(DimsBox::new2(40, 20)),
+            widget_span: WidgetSpan::new(col, 0),
+            shrink_priority,
+            ..WidgetPos::default()
+        };
+        let hints = [item(0, None), item(1, Some(1)), item(2, Some(0))];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 3];
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+
+        // The lowest-priority widget gets collapsed to make room for the others.
+        assert_eq!(Ok(BoundBox::new2(0, 0, 40, 20)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(40, 0, 80, 20)), rects[1]);
+        assert_eq!(Err(SolveError::Collapsed), rects[2]);
+        assert_eq!(Vector2::new(0, 0), engine.overflow());
+        // The engine can shrink down to the size of the widgets that can't be collapsed.
+        assert_eq!(DimsBox::new2(40, 20), engine.actual_size_bounds().min);
+
+        engine.desired_size = DimsBox::new2(50, 20);
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert!(rects[0].is_ok());
+        assert_eq!(Err(SolveError::Collapsed), rects[1]);
+        assert_eq!(Err(SolveError::Collapsed), rects[2]);
+
+        // Once there's room again, the widgets come back.
+        engine.desired_size = DimsBox::new2(120, 20);
+        engine.update_engine(&hints, &mut rects, &mut UpdateHeapCache::new());
+        assert_eq!(Ok(BoundBox::new2(0, 0, 40, 20)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(40, 0, 80, 20)), rects[1]);
+        assert_eq!(Ok(BoundBox::new2(80, 0, 120, 20)), rects[2]);
+    }
+
+    #[test]
+    fn update_engine_measured() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+        engine.desired_size = DimsBox::new2(100, 10);
+
+        let hints = [
+            WidgetPos {
+                widget_span: WidgetSpan::new(0, 0),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                widget_span: WidgetSpan::new(1, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+        let mut widths = Vec::new();
+        // The first widget acts like wrapped text covering 1000 square pixels.
+        engine.update_engine_measured(&hints, &mut rects, &mut UpdateHeapCache::new(), |index, width| {
+            widths.push((index, width));
+            match index {
+                0 => Some(SizeBounds::new_min(DimsBox::new2(0, 1000 / width))),
+                _ => None
+            }
+        });
+
+        assert_eq!(vec![(0, 50), (1, 50), (0, 50), (1, 50)], widths);
+        assert_eq!(20, engine.actual_size_bounds().min.height());
+        assert_eq!(Ok(BoundBox::new2(0, 0, 50, 20)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(50, 0, 100, 20)), rects[1]);
+    }
+
+    #[test]
+    fn track_out_of_range() {
+        let mut engine = GridEngine::new();
+        engine.set_grid_size(GridSize::new(2, 1));
+
+        let error = TrackOutOfRange{ axis: Axis::Y, index: 1, num_tracks: 1 };
+        assert_eq!(Err(error), engine.try_row_hints(1));
+        assert_eq!(Err(error), engine.try_set_row_hints(1, TrackHints::default()));
+        assert_eq!("Row 1 out of range; the grid has 1 row", error.to_string());
+
+        assert!(engine.try_col_hints(1).is_ok());
+        assert_eq!(Ok(()), engine.try_set_track_resizable(Axis::X, 1, true));
+        assert_eq!(Ok(true), engine.try_track_resizable(Axis::X, 1));
+        assert_eq!(
+            Err(TrackOutOfRange{ axis: Axis::X, index: 2, num_tracks: 2 }),
+            engine.try_track_resizable(Axis::X, 2)
+        );
+    }
+
     #[derive(Clone)]
     struct A<T>(T);
 
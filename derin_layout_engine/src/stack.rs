@@ -0,0 +1,150 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use derin_common_types::Px;
+use cgmath_geometry::{D2, rect::{DimsBox, BoundBox, GeoBox}};
+use derin_common_types::layout::{Axis, LayoutDirection, WidgetPos, SizeBounds, Margins};
+use crate::{CellHinter, HintError, SolveError, Unsolvable, axis_min_size, place_absolute};
+
+/// Layers widgets on top of each other, placing every widget in the full area of the engine.
+///
+/// Useful for overlays, badges, and toast notifications. Each widget is placed with its own
+/// `place_in_cell` alignment and margins; `widget_span` is ignored. The engine's size bounds are
+/// the union of the size bounds of its widgets, excluding absolutely positioned widgets.
+#[derive(Debug, Clone)]
+pub struct StackEngine {
+    /// The pixel size of the layout engine, as requested by the programmer.
+    pub desired_size: DimsBox<D2, Px>,
+    /// The pixel size of the layout engine, accounting for the size bounds of the widgets and the size
+    /// bounds of the engine.
+    actual_size: DimsBox<D2, Px>,
+    /// The size bounds of the engine, as requested by the programmer.
+    pub desired_size_bounds: SizeBounds,
+    /// The size bounds of the engine, accounting for the size bounds of the widgets.
+    actual_size_bounds: SizeBounds,
+    /// The margins that appear around the outside of the stacked widgets.
+    pub stack_margins: Margins<Px>
+}
+
+impl StackEngine {
+    pub fn new() -> StackEngine {
+        StackEngine {
+            desired_size: DimsBox::new2(0, 0),
+            actual_size: DimsBox::new2(0, 0),
+            desired_size_bounds: SizeBounds::default(),
+            actual_size_bounds: SizeBounds::default(),
+            stack_margins: Margins::default()
+        }
+    }
+
+    pub fn actual_size(&self) -> DimsBox<D2, Px> {
+        self.actual_size
+    }
+
+    pub fn actual_size_bounds(&self) -> SizeBounds {
+        self.actual_size_bounds
+    }
+
+    /// Place every widget in the engine's area, storing the results in `rects`.
+    pub fn update_engine(
+        &mut self,
+        hints: &[WidgetPos],
+        rects: &mut [Result<BoundBox<D2, Px>, SolveError>]
+    ) {
+        assert_eq!(hints.len(), rects.len());
+
+        let margins_size = DimsBox::new2(self.stack_margins.width(), self.stack_margins.height());
+        let widget_bounds = hints.iter()
+            .filter(|hint| hint.absolute.is_none())
+            .map(|hint| {
+                let margins = DimsBox::new2(hint.margins.width(), hint.margins.height());
+                SizeBounds::new(
+                    add_dims(hint.size_bounds.min, margins),
+                    add_dims(hint.size_bounds.max, margins)
+                )
+            })
+            .fold(None, |bounds: Option<SizeBounds>, widget_bounds| match bounds {
+                Some(bounds) => Some(bounds.union(widget_bounds)),
+                None => Some(widget_bounds)
+            })
+            .unwrap_or_default();
+
+        // The widgets' bounds take priority over the desired bounds, so the widgets always fit.
+        let min = add_dims(widget_bounds.min, margins_size);
+        let max = add_dims(widget_bounds.max, margins_size);
+        self.actual_size_bounds.min = DimsBox::new2(
+            self.desired_size_bounds.min.width().max(min.width()),
+            self.desired_size_bounds.min.height().max(min.height())
+        );
+        self.actual_size_bounds.max = DimsBox::new2(
+            self.desired_size_bounds.max.width().min(max.width()).max(self.actual_size_bounds.min.width()),
+            self.desired_size_bounds.max.height().min(max.height()).max(self.actual_size_bounds.min.height())
+        );
+        self.actual_size = self.actual_size_bounds.bound_rect(self.desired_size);
+
+        let outer_rect = BoundBox::new2(
+            self.stack_margins.left,
+            self.stack_margins.top,
+            self.actual_size.width() - self.stack_margins.right,
+            self.actual_size.height() - self.stack_margins.bottom
+        );
+        for (hint, rect) in hints.iter().zip(rects.iter_mut()) {
+            if let Some(ref absolute) = hint.absolute {
+                *rect = place_absolute(absolute, self.actual_size);
+                continue;
+            }
+
+            let cell_hinter = CellHinter::new(outer_rect, hint.place_in_cell, hint.aspect_ratio, LayoutDirection::default());
+            *rect = cell_hinter.hint(hint.size_bounds, hint.margins)
+                .map_err(|HintError::ORTooSmall(axis)| SolveError::WidgetUnsolvable(Unsolvable {
+                    axis,
+                    widget_min: axis_min_size(hint, axis),
+                    cell_size: match axis {
+                        Axis::X => outer_rect.width(),
+                        Axis::Y => outer_rect.height()
+                    },
+                    first_track: 0,
+                    num_tracks: 0,
+                    tracks_saturated: false
+                }));
+        }
+    }
+}
+
+#[inline]
+fn add_dims(a: DimsBox<D2, Px>, b: DimsBox<D2, Px>) -> DimsBox<D2, Px> {
+    DimsBox::new2(a.width().saturating_add(b.width()), a.height().saturating_add(b.height()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use derin_common_types::layout::{Align, Align2};
+
+    #[test]
+    fn stack_engine() {
+        let mut engine = StackEngine::new();
+        engine.desired_size = DimsBox::new2(50, 50);
+
+        let hints = [
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(100, 20)),
+                ..WidgetPos::default()
+            },
+            WidgetPos {
+                size_bounds: SizeBounds::new_min(DimsBox::new2(10, 10)),
+                place_in_cell: Align2::new(Align::End, Align::Start),
+                margins: Margins::new(0, 5, 5, 0),
+                ..WidgetPos::default()
+            },
+        ];
+        let mut rects = [Err(SolveError::CellOutOfBounds); 2];
+        engine.update_engine(&hints, &mut rects);
+
+        assert_eq!(DimsBox::new2(100, 50), engine.actual_size());
+        assert_eq!(DimsBox::new2(100, 20), engine.actual_size_bounds().min);
+        assert_eq!(Ok(BoundBox::new2(0, 0, 100, 50)), rects[0]);
+        assert_eq!(Ok(BoundBox::new2(85, 5, 95, 15)), rects[1]);
+    }
+}
@@ -36,22 +36,49 @@ fn impl_widget_container(derive_input: &DeriveInput) -> Tokens {
 
     // Process attributes on the fields in the item being derived
     let mut widget_fields = Vec::new();
+    let mut slots = Vec::new();
     match *body {
         Body::Struct(ref variant_data) =>
             for field in variant_data.fields().iter() {
                 let mut widget_field = WidgetField::Widget(field);
+                let mut slot = None;
                 derin_attribute_iter(&field.attrs, |attr| {
                     match *attr {
+                        MetaItem::Word(ref attr_name) if attr_name == "slot" =>
+                            match slot {
+                                None => slot = Some(false),
+                                Some(_) => panic!("Repeated #[derin(slot)] attribute")
+                            },
+                        MetaItem::NameValue(ref attr_name, Lit::Str(ref slot_kind, _)) if attr_name == "slot" =>
+                            match (slot, &**slot_kind) {
+                                (None, "stretch") => slot = Some(true),
+                                (None, _) => panic!("Bad slot kind: {}", slot_kind),
+                                (Some(_), _) => panic!("Repeated #[derin(slot)] attribute")
+                            },
                         MetaItem::NameValue(ref attr_name, Lit::Str(ref collection_inner, _))
                             if attr_name == "collection" =>
                                 match widget_field {
                                     WidgetField::Widget(_) => widget_field = WidgetField::Collection(field, syn::parse_type(collection_inner).expect("Malformed collection type")),
-                                    WidgetField::Collection(_, _) => panic!("Repeated #[derin(collection)] attribute")
+                                    _ => panic!("Repeated #[derin(collection)] attribute")
+                                },
+                        MetaItem::NameValue(ref attr_name, Lit::Str(ref collection_inner, _))
+                            if attr_name == "keyed_collection" =>
+                                match widget_field {
+                                    WidgetField::Widget(_) => widget_field = WidgetField::KeyedCollection(field, syn::parse_type(collection_inner).expect("Malformed collection type")),
+                                    _ => panic!("Repeated #[derin(collection)] attribute")
                                 },
                         _ => panic!("Bad Derin attribute: {}", quote!(#attr).to_string())
                     }
                 });
 
+                if let Some(stretch) = slot {
+                    match (&widget_field, &field.ident) {
+                        (&WidgetField::Widget(_), &Some(ref slot_ident)) => slots.push((slot_ident.clone(), stretch)),
+                        (&WidgetField::Widget(_), &None) => panic!("Slots must be named fields"),
+                        _ => panic!("Collections can't be slots")
+                    }
+                }
+
                 widget_fields.push(widget_field);
             },
         _ => unimplemented!()
@@ -66,6 +93,40 @@ fn impl_widget_container(derive_input: &DeriveInput) -> Tokens {
     let (impl_generics, _, where_clause) = generics_expanded.split_for_impl();
     let (_, ty_generics, _) = generics.split_for_impl();
 
+    if slots.len() != 0 && slots.len() != widget_fields.len() {
+        panic!("Either every field or no fields must be a #[derin(slot)]");
+    }
+    let template_slots_impl = match slots.len() {
+        0 => quote!(),
+        _ => {
+            let slot_generics = expand_slot_generics(generics, &widget_fields);
+            let (slot_impl_generics, _, slot_where_clause) = slot_generics.split_for_impl();
+            let slot_iter = slots.iter().map(|&(ref slot_ident, stretch)| {
+                let stretch = match stretch {
+                    true => quote!(true),
+                    false => quote!(false)
+                };
+                quote!{
+                    Slot {
+                        name: stringify!(#slot_ident),
+                        stretch: #stretch,
+                    }
+                }
+            });
+
+            quote!{
+                #[automatically_derived]
+                impl #slot_impl_generics TemplateSlots for #ident #ty_generics #slot_where_clause {
+                    #[inline]
+                    fn slots() -> &'static [Slot] {
+                        const SLOTS: &[Slot] = &[#(#slot_iter),*];
+                        SLOTS
+                    }
+                }
+            }
+        }
+    };
+
     let widget_trait_ty = quote!(Widget);
     let mut widget_ty = None;
     for ty in field_types(widget_fields.iter()) {
@@ -98,7 +159,8 @@ fn impl_widget_container(derive_input: &DeriveInput) -> Tokens {
         let widget_ident = widget_field.ident().clone().unwrap_or(Ident::new(field_num));
         match widget_field {
             WidgetField::Widget(_) => quote!(+ 1),
-            WidgetField::Collection(_, _) => quote!(+ (&self.#widget_ident).into_iter().count())
+            WidgetField::Collection(_, _) => quote!(+ (&self.#widget_ident).into_iter().count()),
+            WidgetField::KeyedCollection(_, _) => quote!(+ KeyedCollection::len(&self.#widget_ident))
         }
     });
 
@@ -117,7 +179,7 @@ fn impl_widget_container(derive_input: &DeriveInput) -> Tokens {
         const #dummy_const: () = {mod import {
             extern crate derin as _derive_derin;
             use self::_derive_derin::LoopFlow;
-            use self::_derive_derin::container::WidgetContainer;
+            use self::_derive_derin::container::{KeyedCollection, Slot, TemplateSlots, WidgetContainer};
             use self::_derive_derin::widgets::custom::{Widget, WidgetInfo, WidgetInfoMut};
             use self::_derive_derin::gl_render::RenderFrame;
             use std::sync::Arc;
@@ -154,6 +216,8 @@ fn impl_widget_container(derive_input: &DeriveInput) -> Tokens {
                     #(#call_child_mut_iter)*
                 }
             }
+
+            #template_slots_impl
         }};
     }
 }
@@ -223,6 +287,31 @@ impl<'a, W> Iterator for CallChildIter<'a, W>
                             index += 1;
                         }
                     }}
+                },
+                WidgetField::KeyedCollection(field, _) => {
+                    let child_id = match field.ident {
+                        Some(_) => quote!(_derive_derin::widgets::custom::WidgetIdent::StrKeyed(#tl_ident.with(|i| i.clone()), key)),
+                        None => quote!(_derive_derin::widgets::custom::WidgetIdent::NumKeyed(#widget_ident, key))
+                    };
+                    let for_each_keyed = match self.is_mut {
+                        true => quote!(KeyedCollection::for_each_keyed_mut),
+                        false => quote!(KeyedCollection::for_each_keyed)
+                    };
+
+                    // The collection iterates internally, so the flow has to be carried out of the
+                    // closure before we can return.
+                    output = quote!{{
+                        let mut flow = LoopFlow::Continue;
+                        #for_each_keyed(#widget_expr, |key, child| {
+                            flow = for_each_child(#new_summary (#child_id, index, child));
+                            index += 1;
+                            flow
+                        });
+
+                        if let LoopFlow::Break = flow {
+                            return;
+                        }
+                    }}
                 }
             }
 
@@ -237,14 +326,16 @@ impl<'a, W> Iterator for CallChildIter<'a, W>
 #[derive(Debug, Clone)]
 enum WidgetField<'a> {
     Widget(&'a Field),
-    Collection(&'a Field, Ty)
+    Collection(&'a Field, Ty),
+    KeyedCollection(&'a Field, Ty)
 }
 
 impl<'a> WidgetField<'a> {
     fn ident(&self) -> &'a Option<Ident> {
         match *self {
             WidgetField::Widget(field) |
-            WidgetField::Collection(field, _) => &field.ident
+            WidgetField::Collection(field, _) |
+            WidgetField::KeyedCollection(field, _) => &field.ident
         }
     }
 }
@@ -315,11 +406,33 @@ fn expand_generics(generics: &Generics, widget_fields: &[WidgetField]) -> Generi
     generics
 }
 
+/// The generics for a template's `TemplateSlots` impl, which requires every slot to be a widget.
+fn expand_slot_generics(generics: &Generics, widget_fields: &[WidgetField]) -> Generics {
+    let mut generics = generics.clone();
+    for ty in field_types(widget_fields.iter()) {
+        let member_bound = WhereBoundPredicate {
+            bound_lifetimes: Vec::new(),
+            bounded_ty: ty,
+            bounds: vec![TyParamBound::Trait(
+                PolyTraitRef{
+                    bound_lifetimes: Vec::new(),
+                    trait_ref: syn::parse_path(&quote!(_derive_derin::widgets::custom::Widget).to_string()).unwrap(),
+                },
+                TraitBoundModifier::None
+            )]
+        };
+        generics.where_clause.predicates.push(WherePredicate::BoundPredicate(member_bound));
+    }
+
+    generics
+}
+
 fn field_types<'a, I: 'a + Iterator<Item = &'a WidgetField<'a>>>(widget_fields: I) -> impl 'a + Iterator<Item=Ty> {
     widget_fields.map(|widget_field|
         match *widget_field {
             WidgetField::Widget(ref widget_field) => widget_field.ty.clone(),
-            WidgetField::Collection(_, ref collection_ty) => collection_ty.clone()
+            WidgetField::Collection(_, ref collection_ty) |
+            WidgetField::KeyedCollection(_, ref collection_ty) => collection_ty.clone()
         }
     )
 }